@@ -0,0 +1,266 @@
+//! Derive macros for Plonky2.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, GenericParam, Generics,
+    Index, Lit, Member, Meta, NestedMeta, Path, PathArguments, Type, WhereClause,
+};
+
+/// Derives `plonky2::iop::circuit_variable::CircuitVariable` for a struct whose fields are all
+/// `CircuitVariable`s.
+///
+/// The derived implementation is generic over the field `F` and extension degree `D`, unless the
+/// struct already has generic parameters with these names, in which case they are reused. The
+/// `Value` of the variable is a generated `{Name}Value` struct with the same fields, holding their
+/// values. Alternatively, an existing type with the same field names can be used with
+/// `#[circuit_variable(value = "Type")]`. The `Size` of the variable is a generated `{Name}Size`
+/// struct with the same fields, holding their sizes, which implements `FixedSize` if the sizes of
+/// all the fields do.
+#[proc_macro_derive(CircuitVariable, attributes(circuit_variable))]
+pub fn derive_circuit_variable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_circuit_variable(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_circuit_variable(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "CircuitVariable can only be derived for structs",
+            ))
+        }
+    };
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        })
+        .collect::<Vec<_>>();
+    let types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let value_path = value_attribute(&input.attrs)?;
+
+    let trait_path: Path = parse_quote!(::plonky2::iop::circuit_variable::CircuitVariable<F, D>);
+    let mut generics = input.generics.clone();
+    let has_param = |name: &str| {
+        input.generics.params.iter().any(|param| match param {
+            GenericParam::Type(ty) => ty.ident == name,
+            GenericParam::Const(c) => c.ident == name,
+            GenericParam::Lifetime(_) => false,
+        })
+    };
+    if !has_param("F") {
+        generics.params.push(parse_quote!(F));
+    }
+    if !has_param("D") {
+        generics.params.push(parse_quote!(const D: usize));
+    }
+    let where_clause = generics.make_where_clause();
+    where_clause.predicates.push(parse_quote!(
+        F: ::plonky2::hash::hash_types::RichField + ::plonky2::field::extension::Extendable<D>
+    ));
+    // Fields of concrete types are resolved to their implementations directly; bounding them here
+    // would hide the concrete `Value` types behind the bound.
+    for ty in types.iter().filter(|ty| is_type_param(ty, &input.generics)) {
+        where_clause.predicates.push(parse_quote!(#ty: #trait_path));
+    }
+    let mut fixed_size_generics = generics.clone();
+    let fixed_size_where_clause = fixed_size_generics.make_where_clause();
+    for ty in &types {
+        fixed_size_where_clause.predicates.push(parse_quote!(
+            <#ty as #trait_path>::Size: ::plonky2::iop::circuit_variable::FixedSize
+        ));
+    }
+    let (_, _, fixed_size_where_clause) = fixed_size_generics.split_for_impl();
+    let (impl_generics, impl_ty_generics, where_clause) = generics.split_for_impl();
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let (value_type, value_constructor, value_definition) = match value_path {
+        Some(path) => {
+            let constructor = turbofish(path.clone());
+            (quote!(#path), quote!(#constructor), quote!())
+        }
+        None => {
+            let value_name = format_ident!("{}Value", name);
+            let doc = format!("The value of a `{name}` in a witness.");
+            let field_types = types
+                .iter()
+                .map(|ty| quote!(<#ty as #trait_path>::Value))
+                .collect::<Vec<_>>();
+            let definition = quote! {
+                #[doc = #doc]
+                #vis struct #value_name #impl_generics
+            };
+            let definition = struct_body(definition, fields, &members, &field_types, where_clause);
+            (
+                quote!(#value_name #impl_ty_generics),
+                quote!(#value_name),
+                definition,
+            )
+        }
+    };
+
+    let size_name = format_ident!("{}Size", name);
+    let size_doc = format!("The sizes of the fields of a `{name}`.");
+    let size_field_types = types
+        .iter()
+        .map(|ty| quote!(<#ty as #trait_path>::Size))
+        .collect::<Vec<_>>();
+    let size_definition = quote! {
+        #[doc = #size_doc]
+        #vis struct #size_name #impl_generics
+    };
+    let size_definition = struct_body(
+        size_definition,
+        fields,
+        &members,
+        &size_field_types,
+        where_clause,
+    );
+
+    Ok(quote! {
+        #value_definition
+
+        #size_definition
+
+        impl #impl_generics ::plonky2::iop::circuit_variable::FixedSize
+            for #size_name #impl_ty_generics #fixed_size_where_clause
+        {
+            fn fixed_size() -> Self {
+                #size_name {
+                    #(#members: ::plonky2::iop::circuit_variable::FixedSize::fixed_size(),)*
+                }
+            }
+        }
+
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+            type Value = #value_type;
+            type Size = #size_name #impl_ty_generics;
+
+            fn add_virtual_with_size(
+                builder: &mut ::plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+                size: Self::Size,
+            ) -> Self {
+                Self {
+                    #(#members: <#types as #trait_path>::add_virtual_with_size(
+                        builder,
+                        size.#members,
+                    ),)*
+                }
+            }
+
+            fn targets(&self) -> ::std::vec::Vec<::plonky2::iop::target::Target> {
+                let targets: ::std::vec::Vec<::std::vec::Vec<::plonky2::iop::target::Target>> =
+                    ::std::vec![#(<#types as #trait_path>::targets(&self.#members)),*];
+                targets.concat()
+            }
+
+            fn set_witness<W: ::plonky2::iop::witness::Witness<F>>(
+                &self,
+                witness: &mut W,
+                value: &Self::Value,
+            ) {
+                #(<#types as #trait_path>::set_witness(&self.#members, witness, &value.#members);)*
+            }
+
+            fn get_witness<W: ::plonky2::iop::witness::Witness<F>>(
+                &self,
+                witness: &W,
+            ) -> Self::Value {
+                #value_constructor {
+                    #(#members: <#types as #trait_path>::get_witness(&self.#members, witness),)*
+                }
+            }
+
+            fn register_public(
+                &self,
+                builder: &mut ::plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+            ) {
+                #(<#types as #trait_path>::register_public(&self.#members, builder);)*
+            }
+
+            fn connect(
+                &self,
+                builder: &mut ::plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+                other: &Self,
+            ) {
+                #(<#types as #trait_path>::connect(&self.#members, builder, &other.#members);)*
+            }
+        }
+    })
+}
+
+/// Completes the definition `header` of a struct with the same fields as `fields`, but of types
+/// `field_types`.
+fn struct_body(
+    header: TokenStream2,
+    fields: &Fields,
+    members: &[Member],
+    field_types: &[TokenStream2],
+    where_clause: Option<&WhereClause>,
+) -> TokenStream2 {
+    let field_vis = fields.iter().map(|field| &field.vis);
+    match fields {
+        Fields::Named(_) => quote! {
+            #header #where_clause { #(#field_vis #members: #field_types),* }
+        },
+        Fields::Unnamed(_) | Fields::Unit => quote! {
+            #header (#(#field_vis #field_types),*) #where_clause;
+        },
+    }
+}
+
+/// Parses the `#[circuit_variable(value = "Type")]` attribute, if present.
+fn value_attribute(attrs: &[Attribute]) -> syn::Result<Option<Path>> {
+    let mut value = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("circuit_variable"))
+    {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "Expected a list of options")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("value") => {
+                    match nv.lit {
+                        Lit::Str(s) => value = Some(s.parse()?),
+                        lit => return Err(syn::Error::new_spanned(lit, "Expected a string")),
+                    }
+                }
+                nested => return Err(syn::Error::new_spanned(nested, "Unknown option")),
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Whether `ty` is one of the type parameters in `generics`.
+fn is_type_param(ty: &Type, generics: &Generics) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => generics
+            .type_params()
+            .any(|param| path.path.is_ident(&param.ident)),
+        _ => false,
+    }
+}
+
+/// Adds `::` before generic arguments, so that the path can be used in expressions.
+fn turbofish(mut path: Path) -> Path {
+    for segment in &mut path.segments {
+        if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+            args.colon2_token = Some(Default::default());
+        }
+    }
+    path
+}
@@ -6,6 +6,7 @@ use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::iop::witness::{PartitionWitness, Witness};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult};
 use plonky2_field::extension::Extendable;
 use plonky2_field::types::{PrimeField, PrimeField64};
 use plonky2_u32::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
@@ -25,6 +26,16 @@ impl BigUintTarget {
     pub fn get_limb(&self, i: usize) -> U32Target {
         self.limbs[i]
     }
+
+    pub fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        let limbs = self.limbs.iter().map(|limb| limb.0).collect::<Vec<_>>();
+        dst.write_target_vec(&limbs)
+    }
+
+    pub fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let limbs = src.read_target_vec()?.into_iter().map(U32Target).collect();
+        Ok(Self { limbs })
+    }
 }
 
 pub trait CircuitBuilderBiguint<F: RichField + Extendable<D>, const D: usize> {
@@ -312,7 +323,7 @@ impl<F: PrimeField> GeneratedValuesBigUint<F> for GeneratedValues<F> {
 }
 
 #[derive(Debug)]
-struct BigUintDivRemGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub struct BigUintDivRemGenerator<F: RichField + Extendable<D>, const D: usize> {
     a: BigUintTarget,
     b: BigUintTarget,
     div: BigUintTarget,
@@ -340,6 +351,27 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
         out_buffer.set_biguint_target(&self.div, &div);
         out_buffer.set_biguint_target(&self.rem, &rem);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.a.serialize(dst)?;
+        self.b.serialize(dst)?;
+        self.div.serialize(dst)?;
+        self.rem.serialize(dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let a = BigUintTarget::deserialize(src)?;
+        let b = BigUintTarget::deserialize(src)?;
+        let div = BigUintTarget::deserialize(src)?;
+        let rem = BigUintTarget::deserialize(src)?;
+        Ok(Self {
+            a,
+            b,
+            div,
+            rem,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,7 @@
 use plonky2::hash::hash_types::RichField;
-use plonky2::iop::target::BoolTarget;
+use plonky2::iop::circuit_variable::CircuitVariable;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::iop::witness::Witness;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2_field::extension::Extendable;
 use plonky2_field::types::Field;
@@ -21,6 +23,42 @@ impl<C: Curve> AffinePointTarget<C> {
     }
 }
 
+/// As with `AffinePointTarget` itself, the point at infinity is not supported.
+impl<F: RichField + Extendable<D>, const D: usize, C: Curve> CircuitVariable<F, D>
+    for AffinePointTarget<C>
+{
+    type Value = AffinePoint<C>;
+    type Size = ();
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, _size: ()) -> Self {
+        let x = CircuitVariable::add_virtual(builder);
+        let y = CircuitVariable::add_virtual(builder);
+        Self { x, y }
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        [
+            CircuitVariable::<F, D>::targets(&self.x),
+            CircuitVariable::<F, D>::targets(&self.y),
+        ]
+        .concat()
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &AffinePoint<C>) {
+        assert!(!value.zero, "The point at infinity has no affine target");
+        self.x.set_witness(witness, &value.x);
+        self.y.set_witness(witness, &value.y);
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> AffinePoint<C> {
+        AffinePoint::nonzero(self.x.get_witness(witness), self.y.get_witness(witness))
+    }
+
+    fn connect(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) {
+        builder.connect_affine_point(self, other);
+    }
+}
+
 pub trait CircuitBuilderCurve<F: RichField + Extendable<D>, const D: usize> {
     fn constant_affine_point<C: Curve>(&mut self, point: AffinePoint<C>) -> AffinePointTarget<C>;
 
@@ -1,24 +1,29 @@
 use std::marker::PhantomData;
 
 use plonky2::hash::hash_types::RichField;
+use plonky2::iop::circuit_variable::CircuitVariable;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2_field::extension::Extendable;
 use plonky2_field::secp256k1_scalar::Secp256K1Scalar;
 
 use crate::curve::curve_types::Curve;
+use crate::curve::ecdsa::{ECDSAPublicKey, ECDSASecretKey, ECDSASignature};
 use crate::curve::secp256k1::Secp256K1;
 use crate::gadgets::curve::{AffinePointTarget, CircuitBuilderCurve};
 use crate::gadgets::curve_fixed_base::fixed_base_curve_mul_circuit;
 use crate::gadgets::glv::CircuitBuilderGlv;
 use crate::gadgets::nonnative::{CircuitBuilderNonNative, NonNativeTarget};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, CircuitVariable)]
+#[circuit_variable(value = "ECDSASecretKey<C>")]
 pub struct ECDSASecretKeyTarget<C: Curve>(pub NonNativeTarget<C::ScalarField>);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, CircuitVariable)]
+#[circuit_variable(value = "ECDSAPublicKey<C>")]
 pub struct ECDSAPublicKeyTarget<C: Curve>(pub AffinePointTarget<C>);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, CircuitVariable)]
+#[circuit_variable(value = "ECDSASignature<C>")]
 pub struct ECDSASignatureTarget<C: Curve> {
     pub r: NonNativeTarget<C::ScalarField>,
     pub s: NonNativeTarget<C::ScalarField>,
@@ -52,6 +57,7 @@ pub fn verify_message_circuit<F: RichField + Extendable<D>, const D: usize>(
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use plonky2::iop::circuit_variable::CircuitVariable;
     use plonky2::iop::witness::PartialWitness;
     use plonky2::plonk::circuit_builder::CircuitBuilder;
     use plonky2::plonk::circuit_data::CircuitConfig;
@@ -65,7 +71,7 @@ mod tests {
     use crate::curve::secp256k1::Secp256K1;
     use crate::gadgets::curve::CircuitBuilderCurve;
     use crate::gadgets::ecdsa::verify_message_circuit;
-    use crate::gadgets::nonnative::CircuitBuilderNonNative;
+    use crate::gadgets::nonnative::{CircuitBuilderNonNative, NonNativeTarget};
 
     fn test_ecdsa_circuit_with_config(config: CircuitConfig) -> Result<()> {
         const D: usize = 2;
@@ -114,4 +120,44 @@ mod tests {
     fn test_ecdsa_circuit_wide() -> Result<()> {
         test_ecdsa_circuit_with_config(CircuitConfig::wide_ecc_config())
     }
+
+    #[derive(CircuitVariable)]
+    struct SignedMessageTarget {
+        msg: NonNativeTarget<Secp256K1Scalar>,
+        pk: ECDSAPublicKeyTarget<Secp256K1>,
+        sig: ECDSASignatureTarget<Secp256K1>,
+    }
+
+    #[test]
+    fn test_ecdsa_circuit_variables() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_variable::<SignedMessageTarget>();
+        let y = builder.add_virtual_variable::<SignedMessageTarget>();
+        builder.connect_variables(&x, &y);
+        builder.register_public_variable(&y);
+        let data = builder.build::<C>();
+
+        let msg = Secp256K1Scalar::rand();
+        let sk = ECDSASecretKey::<Secp256K1>(Secp256K1Scalar::rand());
+        let value = SignedMessageTargetValue {
+            msg,
+            pk: sk.to_public(),
+            sig: sign_message(msg, sk),
+        };
+        let mut pw = PartialWitness::new();
+        CircuitVariable::<F, D>::set_witness(&x, &mut pw, &value);
+        let SignedMessageTargetValue { msg, pk, sig } =
+            CircuitVariable::<F, D>::get_witness(&x, &pw);
+        assert_eq!(msg, value.msg);
+        assert_eq!(pk, value.pk);
+        assert_eq!(sig, value.sig);
+
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
 }
@@ -5,6 +5,7 @@ use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::iop::witness::PartitionWitness;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult};
 use plonky2_field::extension::Extendable;
 use plonky2_field::secp256k1_base::Secp256K1Base;
 use plonky2_field::secp256k1_scalar::Secp256K1Scalar;
@@ -99,7 +100,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderGlv<F, D>
 }
 
 #[derive(Debug)]
-struct GLVDecompositionGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub struct GLVDecompositionGenerator<F: RichField + Extendable<D>, const D: usize> {
     k: NonNativeTarget<Secp256K1Scalar>,
     k1: NonNativeTarget<Secp256K1Scalar>,
     k2: NonNativeTarget<Secp256K1Scalar>,
@@ -127,6 +128,30 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
         out_buffer.set_bool_target(self.k1_neg, k1_neg);
         out_buffer.set_bool_target(self.k2_neg, k2_neg);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.k.serialize(dst)?;
+        self.k1.serialize(dst)?;
+        self.k2.serialize(dst)?;
+        dst.write_bool_target(self.k1_neg)?;
+        dst.write_bool_target(self.k2_neg)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let k = NonNativeTarget::deserialize(src)?;
+        let k1 = NonNativeTarget::deserialize(src)?;
+        let k2 = NonNativeTarget::deserialize(src)?;
+        let k1_neg = src.read_bool_target()?;
+        let k2_neg = src.read_bool_target()?;
+        Ok(Self {
+            k,
+            k1,
+            k2,
+            k1_neg,
+            k2_neg,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -2,10 +2,12 @@ use std::marker::PhantomData;
 
 use num::{BigUint, Integer, One, Zero};
 use plonky2::hash::hash_types::RichField;
+use plonky2::iop::circuit_variable::CircuitVariable;
 use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::{BoolTarget, Target};
-use plonky2::iop::witness::PartitionWitness;
+use plonky2::iop::witness::{PartitionWitness, Witness};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult};
 use plonky2_field::types::PrimeField;
 use plonky2_field::{extension::Extendable, types::Field};
 use plonky2_u32::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
@@ -23,6 +25,50 @@ pub struct NonNativeTarget<FF: Field> {
     pub(crate) _phantom: PhantomData<FF>,
 }
 
+impl<FF: Field> NonNativeTarget<FF> {
+    pub fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.value.serialize(dst)
+    }
+
+    pub fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let value = BigUintTarget::deserialize(src)?;
+        Ok(Self {
+            value,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// A non-native variable holds a canonical element of `FF`; its limbs are range checked when added.
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> CircuitVariable<F, D>
+    for NonNativeTarget<FF>
+{
+    type Value = FF;
+    type Size = ();
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, _size: ()) -> Self {
+        let x = builder.add_virtual_nonnative_target();
+        range_check_u32_circuit(builder, x.value.limbs.clone());
+        x
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        self.value.limbs.iter().map(|limb| limb.0).collect()
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &FF) {
+        witness.set_biguint_target(&self.value, &value.to_canonical_biguint());
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> FF {
+        FF::from_noncanonical_biguint(witness.get_biguint_target(self.value.clone()))
+    }
+
+    fn connect(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) {
+        builder.connect_nonnative(self, other);
+    }
+}
+
 pub trait CircuitBuilderNonNative<F: RichField + Extendable<D>, const D: usize> {
     fn num_nonnative_limbs<FF: Field>() -> usize {
         ceil_div_usize(FF::BITS, 32)
@@ -444,7 +490,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderNonNative<F, D>
 }
 
 #[derive(Debug)]
-struct NonNativeAdditionGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
+pub struct NonNativeAdditionGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>
+{
     a: NonNativeTarget<FF>,
     b: NonNativeTarget<FF>,
     sum: NonNativeTarget<FF>,
@@ -482,11 +529,35 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.sum.value, &sum_reduced);
         out_buffer.set_bool_target(self.overflow, overflow);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.a.serialize(dst)?;
+        self.b.serialize(dst)?;
+        self.sum.serialize(dst)?;
+        dst.write_bool_target(self.overflow)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let a = NonNativeTarget::deserialize(src)?;
+        let b = NonNativeTarget::deserialize(src)?;
+        let sum = NonNativeTarget::deserialize(src)?;
+        let overflow = src.read_bool_target()?;
+        Ok(Self {
+            a,
+            b,
+            sum,
+            overflow,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeMultipleAddsGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>
-{
+pub struct NonNativeMultipleAddsGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: PrimeField,
+> {
     summands: Vec<NonNativeTarget<FF>>,
     sum: NonNativeTarget<FF>,
     overflow: U32Target,
@@ -527,10 +598,34 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.sum.value, &sum_reduced);
         out_buffer.set_u32_target(self.overflow, overflow);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.summands.len())?;
+        for x in &self.summands {
+            x.serialize(dst)?;
+        }
+        self.sum.serialize(dst)?;
+        dst.write_target(self.overflow.0)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let num_summands = src.read_usize()?;
+        let summands = (0..num_summands)
+            .map(|_| NonNativeTarget::deserialize(src))
+            .collect::<IoResult<Vec<_>>>()?;
+        let sum = NonNativeTarget::deserialize(src)?;
+        let overflow = U32Target(src.read_target()?);
+        Ok(Self {
+            summands,
+            sum,
+            overflow,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeSubtractionGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
+pub struct NonNativeSubtractionGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
     a: NonNativeTarget<FF>,
     b: NonNativeTarget<FF>,
     diff: NonNativeTarget<FF>,
@@ -568,10 +663,32 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.diff.value, &diff_biguint);
         out_buffer.set_bool_target(self.overflow, overflow);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.a.serialize(dst)?;
+        self.b.serialize(dst)?;
+        self.diff.serialize(dst)?;
+        dst.write_bool_target(self.overflow)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let a = NonNativeTarget::deserialize(src)?;
+        let b = NonNativeTarget::deserialize(src)?;
+        let diff = NonNativeTarget::deserialize(src)?;
+        let overflow = src.read_bool_target()?;
+        Ok(Self {
+            a,
+            b,
+            diff,
+            overflow,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeMultiplicationGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
+pub struct NonNativeMultiplicationGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field>
+{
     a: NonNativeTarget<FF>,
     b: NonNativeTarget<FF>,
     prod: NonNativeTarget<FF>,
@@ -607,10 +724,31 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.prod.value, &prod_reduced);
         out_buffer.set_biguint_target(&self.overflow, &overflow_biguint);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.a.serialize(dst)?;
+        self.b.serialize(dst)?;
+        self.prod.serialize(dst)?;
+        self.overflow.serialize(dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let a = NonNativeTarget::deserialize(src)?;
+        let b = NonNativeTarget::deserialize(src)?;
+        let prod = NonNativeTarget::deserialize(src)?;
+        let overflow = BigUintTarget::deserialize(src)?;
+        Ok(Self {
+            a,
+            b,
+            prod,
+            overflow,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Debug)]
-struct NonNativeInverseGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
+pub struct NonNativeInverseGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
     x: NonNativeTarget<FF>,
     inv: BigUintTarget,
     div: BigUintTarget,
@@ -637,6 +775,24 @@ impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerat
         out_buffer.set_biguint_target(&self.div, &div);
         out_buffer.set_biguint_target(&self.inv, &inv_biguint);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.x.serialize(dst)?;
+        self.inv.serialize(dst)?;
+        self.div.serialize(dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let x = NonNativeTarget::deserialize(src)?;
+        let inv = BigUintTarget::deserialize(src)?;
+        let div = BigUintTarget::deserialize(src)?;
+        Ok(Self {
+            x,
+            inv,
+            div,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -83,19 +83,19 @@ pub(crate) fn all_cross_table_lookups<F: Field>() -> Vec<CrossTableLookup<F>> {
 
 fn ctl_keccak<F: Field>() -> CrossTableLookup<F> {
     let cpu_looking = TableWithColumns::new(
-        Table::Cpu,
+        Table::Cpu as usize,
         cpu_stark::ctl_data_keccak(),
         Some(cpu_stark::ctl_filter_keccak()),
     );
     let keccak_memory_looking = TableWithColumns::new(
-        Table::KeccakMemory,
+        Table::KeccakMemory as usize,
         keccak_memory_stark::ctl_looking_keccak(),
         Some(keccak_memory_stark::ctl_filter()),
     );
     CrossTableLookup::new(
         vec![cpu_looking, keccak_memory_looking],
         TableWithColumns::new(
-            Table::Keccak,
+            Table::Keccak as usize,
             keccak_stark::ctl_data(),
             Some(keccak_stark::ctl_filter()),
         ),
@@ -106,12 +106,12 @@ fn ctl_keccak<F: Field>() -> CrossTableLookup<F> {
 fn ctl_keccak_memory<F: Field>() -> CrossTableLookup<F> {
     CrossTableLookup::new(
         vec![TableWithColumns::new(
-            Table::Cpu,
+            Table::Cpu as usize,
             cpu_stark::ctl_data_keccak_memory(),
             Some(cpu_stark::ctl_filter_keccak_memory()),
         )],
         TableWithColumns::new(
-            Table::KeccakMemory,
+            Table::KeccakMemory as usize,
             keccak_memory_stark::ctl_looked_data(),
             Some(keccak_memory_stark::ctl_filter()),
         ),
@@ -122,38 +122,42 @@ fn ctl_keccak_memory<F: Field>() -> CrossTableLookup<F> {
 fn ctl_logic<F: Field>() -> CrossTableLookup<F> {
     CrossTableLookup::new(
         vec![TableWithColumns::new(
-            Table::Cpu,
+            Table::Cpu as usize,
             cpu_stark::ctl_data_logic(),
             Some(cpu_stark::ctl_filter_logic()),
         )],
-        TableWithColumns::new(Table::Logic, logic::ctl_data(), Some(logic::ctl_filter())),
+        TableWithColumns::new(
+            Table::Logic as usize,
+            logic::ctl_data(),
+            Some(logic::ctl_filter()),
+        ),
         None,
     )
 }
 
 fn ctl_memory<F: Field>() -> CrossTableLookup<F> {
     let cpu_memory_code_read = TableWithColumns::new(
-        Table::Cpu,
+        Table::Cpu as usize,
         cpu_stark::ctl_data_code_memory(),
         Some(cpu_stark::ctl_filter_code_memory()),
     );
     let cpu_memory_gp_ops = (0..NUM_GP_CHANNELS).map(|channel| {
         TableWithColumns::new(
-            Table::Cpu,
+            Table::Cpu as usize,
             cpu_stark::ctl_data_gp_memory(channel),
             Some(cpu_stark::ctl_filter_gp_memory(channel)),
         )
     });
     let keccak_memory_reads = (0..KECCAK_WIDTH_BYTES).map(|i| {
         TableWithColumns::new(
-            Table::KeccakMemory,
+            Table::KeccakMemory as usize,
             keccak_memory_stark::ctl_looking_memory(i, true),
             Some(keccak_memory_stark::ctl_filter()),
         )
     });
     let keccak_memory_writes = (0..KECCAK_WIDTH_BYTES).map(|i| {
         TableWithColumns::new(
-            Table::KeccakMemory,
+            Table::KeccakMemory as usize,
             keccak_memory_stark::ctl_looking_memory(i, false),
             Some(keccak_memory_stark::ctl_filter()),
         )
@@ -166,7 +170,7 @@ fn ctl_memory<F: Field>() -> CrossTableLookup<F> {
     CrossTableLookup::new(
         all_lookers,
         TableWithColumns::new(
-            Table::Memory,
+            Table::Memory as usize,
             memory_stark::ctl_data(),
             Some(memory_stark::ctl_filter()),
         ),
@@ -189,12 +193,12 @@ mod tests {
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use plonky2::util::timing::TimingTree;
     use rand::{thread_rng, Rng};
+    use starky::cross_table_lookup::testutils::check_ctls;
 
     use crate::all_stark::{AllStark, NUM_TABLES};
     use crate::config::StarkConfig;
     use crate::cpu::cpu_stark::CpuStark;
     use crate::cpu::kernel::aggregator::KERNEL;
-    use crate::cross_table_lookup::testutils::check_ctls;
     use crate::keccak::keccak_stark::{KeccakStark, NUM_INPUTS, NUM_ROUNDS};
     use crate::keccak_memory::keccak_memory_stark::KeccakMemoryStark;
     use crate::logic::{self, LogicStark, Operation};
@@ -745,6 +749,7 @@ mod tests {
     fn test_all_stark() -> Result<()> {
         let config = StarkConfig::standard_fast_config();
         let (all_stark, proof) = get_proof(&config)?;
+        let proof = AllProof::<F, C, D>::from_bytes(proof.to_bytes(&config)?, &config)?;
         verify_proof(all_stark, proof, &config)
     }
 
@@ -4,13 +4,13 @@ use plonky2::hash::hash_types::RichField;
 use plonky2::iop::challenger::{Challenger, RecursiveChallenger};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use starky::permutation::{
+    get_n_permutation_challenge_sets, get_n_permutation_challenge_sets_target,
+    get_permutation_challenge_set,
+};
 
 use crate::all_stark::{AllStark, NUM_TABLES};
 use crate::config::StarkConfig;
-use crate::permutation::{
-    get_grand_product_challenge_set, get_n_grand_product_challenge_sets,
-    get_n_grand_product_challenge_sets_target,
-};
 use crate::proof::*;
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> AllProof<F, C, D> {
@@ -28,8 +28,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
 
         // TODO: Observe public values.
 
-        let ctl_challenges =
-            get_grand_product_challenge_set(&mut challenger, config.num_challenges);
+        let ctl_challenges = get_permutation_challenge_set(&mut challenger, config.num_challenges);
 
         let num_permutation_zs = all_stark.nums_permutation_zs(config);
         let num_permutation_batch_sizes = all_stark.permutation_batch_sizes();
@@ -62,8 +61,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
 
         // TODO: Observe public values.
 
-        let ctl_challenges =
-            get_grand_product_challenge_set(&mut challenger, config.num_challenges);
+        let ctl_challenges = get_permutation_challenge_set(&mut challenger, config.num_challenges);
 
         let num_permutation_zs = all_stark.nums_permutation_zs(config);
         let num_permutation_batch_sizes = all_stark.permutation_batch_sizes();
@@ -118,7 +116,7 @@ where
         let num_challenges = config.num_challenges;
 
         let permutation_challenge_sets = stark_use_permutation.then(|| {
-            get_n_grand_product_challenge_sets(
+            get_n_permutation_challenge_sets(
                 challenger,
                 num_challenges,
                 stark_permutation_batch_size,
@@ -178,7 +176,7 @@ impl<const D: usize> StarkProofTarget<D> {
         let num_challenges = config.num_challenges;
 
         let permutation_challenge_sets = stark_use_permutation.then(|| {
-            get_n_grand_product_challenge_sets_target(
+            get_n_permutation_challenge_sets_target(
                 builder,
                 challenger,
                 num_challenges,
@@ -549,13 +549,13 @@ mod tests {
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use plonky2::timed;
     use plonky2::util::timing::TimingTree;
+    use starky::permutation::PermutationChallenge;
     use tiny_keccak::keccakf;
 
     use crate::config::StarkConfig;
     use crate::cross_table_lookup::{CtlData, CtlZData};
     use crate::keccak::columns::reg_output_limb;
     use crate::keccak::keccak_stark::{KeccakStark, NUM_INPUTS, NUM_ROUNDS};
-    use crate::permutation::GrandProductChallenge;
     use crate::prover::prove_single_table;
     use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
 
@@ -661,7 +661,7 @@ mod tests {
         // Fake CTL data.
         let ctl_z_data = CtlZData {
             z: PolynomialValues::zero(degree),
-            challenge: GrandProductChallenge {
+            challenge: PermutationChallenge {
                 beta: F::ZERO,
                 gamma: F::ZERO,
             },
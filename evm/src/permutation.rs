@@ -1,7 +1,5 @@
 //! Permutation arguments.
 
-use std::fmt::Debug;
-
 use itertools::Itertools;
 use maybe_rayon::*;
 use plonky2::field::batch_util::batch_multiply_inplace;
@@ -10,15 +8,12 @@ use plonky2::field::packed::PackedField;
 use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
-use plonky2::iop::challenger::{Challenger, RecursiveChallenger};
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
-use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
-use plonky2::plonk::plonk_common::{
-    reduce_with_powers, reduce_with_powers_circuit, reduce_with_powers_ext_circuit,
-};
+use plonky2::plonk::config::GenericConfig;
 use plonky2::util::reducing::{ReducingFactor, ReducingFactorTarget};
+use starky::permutation::{PermutationChallenge, PermutationChallengeSet};
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
@@ -44,59 +39,9 @@ impl PermutationPair {
 }
 
 /// A single instance of a permutation check protocol.
-pub(crate) struct PermutationInstance<'a, T: Copy + Eq + PartialEq + Debug> {
+pub(crate) struct PermutationInstance<'a, T: Copy> {
     pub(crate) pair: &'a PermutationPair,
-    pub(crate) challenge: GrandProductChallenge<T>,
-}
-
-/// Randomness for a single instance of a permutation check protocol.
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub(crate) struct GrandProductChallenge<T: Copy + Eq + PartialEq + Debug> {
-    /// Randomness used to combine multiple columns into one.
-    pub(crate) beta: T,
-    /// Random offset that's added to the beta-reduced column values.
-    pub(crate) gamma: T,
-}
-
-impl<F: Field> GrandProductChallenge<F> {
-    pub(crate) fn combine<'a, FE, P, T: IntoIterator<Item = &'a P>, const D2: usize>(
-        &self,
-        terms: T,
-    ) -> P
-    where
-        FE: FieldExtension<D2, BaseField = F>,
-        P: PackedField<Scalar = FE>,
-        T::IntoIter: DoubleEndedIterator,
-    {
-        reduce_with_powers(terms, FE::from_basefield(self.beta)) + FE::from_basefield(self.gamma)
-    }
-}
-
-impl GrandProductChallenge<Target> {
-    pub(crate) fn combine_circuit<F: RichField + Extendable<D>, const D: usize>(
-        &self,
-        builder: &mut CircuitBuilder<F, D>,
-        terms: &[ExtensionTarget<D>],
-    ) -> ExtensionTarget<D> {
-        let reduced = reduce_with_powers_ext_circuit(builder, terms, self.beta);
-        let gamma = builder.convert_to_ext(self.gamma);
-        builder.add_extension(reduced, gamma)
-    }
-
-    pub(crate) fn combine_base_circuit<F: RichField + Extendable<D>, const D: usize>(
-        &self,
-        builder: &mut CircuitBuilder<F, D>,
-        terms: &[Target],
-    ) -> Target {
-        let reduced = reduce_with_powers_circuit(builder, terms, self.beta);
-        builder.add(reduced, self.gamma)
-    }
-}
-
-/// Like `PermutationChallenge`, but with `num_challenges` copies to boost soundness.
-#[derive(Clone, Eq, PartialEq, Debug)]
-pub(crate) struct GrandProductChallengeSet<T: Copy + Eq + PartialEq + Debug> {
-    pub(crate) challenges: Vec<GrandProductChallenge<T>>,
+    pub(crate) challenge: PermutationChallenge<T>,
 }
 
 /// Compute all Z polynomials (for permutation arguments).
@@ -104,7 +49,7 @@ pub(crate) fn compute_permutation_z_polys<F, C, S, const D: usize>(
     stark: &S,
     config: &StarkConfig,
     trace_poly_values: &[PolynomialValues<F>],
-    permutation_challenge_sets: &[GrandProductChallengeSet<F>],
+    permutation_challenge_sets: &[PermutationChallengeSet<F>],
 ) -> Vec<PolynomialValues<F>>
 where
     F: RichField + Extendable<D>,
@@ -163,7 +108,7 @@ fn permutation_reduced_polys<F: Field>(
 ) -> (PolynomialValues<F>, PolynomialValues<F>) {
     let PermutationInstance {
         pair: PermutationPair { column_pairs },
-        challenge: GrandProductChallenge { beta, gamma },
+        challenge: PermutationChallenge { beta, gamma },
     } = instance;
 
     let mut reduced_lhs = PolynomialValues::constant(*gamma, degree);
@@ -187,85 +132,14 @@ fn poly_product_elementwise<F: Field>(
     product
 }
 
-fn get_grand_product_challenge<F: RichField, H: Hasher<F>>(
-    challenger: &mut Challenger<F, H>,
-) -> GrandProductChallenge<F> {
-    let beta = challenger.get_challenge();
-    let gamma = challenger.get_challenge();
-    GrandProductChallenge { beta, gamma }
-}
-
-pub(crate) fn get_grand_product_challenge_set<F: RichField, H: Hasher<F>>(
-    challenger: &mut Challenger<F, H>,
-    num_challenges: usize,
-) -> GrandProductChallengeSet<F> {
-    let challenges = (0..num_challenges)
-        .map(|_| get_grand_product_challenge(challenger))
-        .collect();
-    GrandProductChallengeSet { challenges }
-}
-
-pub(crate) fn get_n_grand_product_challenge_sets<F: RichField, H: Hasher<F>>(
-    challenger: &mut Challenger<F, H>,
-    num_challenges: usize,
-    num_sets: usize,
-) -> Vec<GrandProductChallengeSet<F>> {
-    (0..num_sets)
-        .map(|_| get_grand_product_challenge_set(challenger, num_challenges))
-        .collect()
-}
-
-fn get_grand_product_challenge_target<
-    F: RichField + Extendable<D>,
-    H: AlgebraicHasher<F>,
-    const D: usize,
->(
-    builder: &mut CircuitBuilder<F, D>,
-    challenger: &mut RecursiveChallenger<F, H, D>,
-) -> GrandProductChallenge<Target> {
-    let beta = challenger.get_challenge(builder);
-    let gamma = challenger.get_challenge(builder);
-    GrandProductChallenge { beta, gamma }
-}
-
-pub(crate) fn get_grand_product_challenge_set_target<
-    F: RichField + Extendable<D>,
-    H: AlgebraicHasher<F>,
-    const D: usize,
->(
-    builder: &mut CircuitBuilder<F, D>,
-    challenger: &mut RecursiveChallenger<F, H, D>,
-    num_challenges: usize,
-) -> GrandProductChallengeSet<Target> {
-    let challenges = (0..num_challenges)
-        .map(|_| get_grand_product_challenge_target(builder, challenger))
-        .collect();
-    GrandProductChallengeSet { challenges }
-}
-
-pub(crate) fn get_n_grand_product_challenge_sets_target<
-    F: RichField + Extendable<D>,
-    H: AlgebraicHasher<F>,
-    const D: usize,
->(
-    builder: &mut CircuitBuilder<F, D>,
-    challenger: &mut RecursiveChallenger<F, H, D>,
-    num_challenges: usize,
-    num_sets: usize,
-) -> Vec<GrandProductChallengeSet<Target>> {
-    (0..num_sets)
-        .map(|_| get_grand_product_challenge_set_target(builder, challenger, num_challenges))
-        .collect()
-}
-
 /// Get a list of instances of our batch-permutation argument. These are permutation arguments
 /// where the same `Z(x)` polynomial is used to check more than one permutation.
 /// Before batching, each permutation pair leads to `num_challenges` permutation arguments, so we
 /// start with the cartesian product of `permutation_pairs` and `0..num_challenges`. Then we
 /// chunk these arguments based on our batch size.
-pub(crate) fn get_permutation_batches<'a, T: Copy + Eq + PartialEq + Debug>(
+pub(crate) fn get_permutation_batches<'a, T: Copy>(
     permutation_pairs: &'a [PermutationPair],
-    permutation_challenge_sets: &[GrandProductChallengeSet<T>],
+    permutation_challenge_sets: &[PermutationChallengeSet<T>],
     num_challenges: usize,
     batch_size: usize,
 ) -> Vec<Vec<PermutationInstance<'a, T>>> {
@@ -294,7 +168,7 @@ where
 {
     pub(crate) local_zs: Vec<P>,
     pub(crate) next_zs: Vec<P>,
-    pub(crate) permutation_challenge_sets: Vec<GrandProductChallengeSet<F>>,
+    pub(crate) permutation_challenge_sets: Vec<PermutationChallengeSet<F>>,
 }
 
 pub(crate) fn eval_permutation_checks<F, FE, P, C, S, const D: usize, const D2: usize>(
@@ -338,7 +212,7 @@ pub(crate) fn eval_permutation_checks<F, FE, P, C, S, const D: usize, const D2:
             .map(|instance| {
                 let PermutationInstance {
                     pair: PermutationPair { column_pairs },
-                    challenge: GrandProductChallenge { beta, gamma },
+                    challenge: PermutationChallenge { beta, gamma },
                 } = instance;
                 let mut factor = ReducingFactor::new(*beta);
                 let (lhs, rhs): (Vec<_>, Vec<_>) = column_pairs
@@ -360,7 +234,7 @@ pub(crate) fn eval_permutation_checks<F, FE, P, C, S, const D: usize, const D2:
 pub struct PermutationCheckDataTarget<const D: usize> {
     pub(crate) local_zs: Vec<ExtensionTarget<D>>,
     pub(crate) next_zs: Vec<ExtensionTarget<D>>,
-    pub(crate) permutation_challenge_sets: Vec<GrandProductChallengeSet<Target>>,
+    pub(crate) permutation_challenge_sets: Vec<PermutationChallengeSet<Target>>,
 }
 
 pub(crate) fn eval_permutation_checks_circuit<F, S, const D: usize>(
@@ -405,7 +279,7 @@ pub(crate) fn eval_permutation_checks_circuit<F, S, const D: usize>(
                 .map(|instance| {
                     let PermutationInstance {
                         pair: PermutationPair { column_pairs },
-                        challenge: GrandProductChallenge { beta, gamma },
+                        challenge: PermutationChallenge { beta, gamma },
                     } = instance;
                     let beta_ext = builder.convert_to_ext(*beta);
                     let gamma_ext = builder.convert_to_ext(*gamma);
@@ -1,3 +1,4 @@
+use anyhow::Result;
 use ethereum_types::{Address, H256, U256};
 use itertools::Itertools;
 use maybe_rayon::*;
@@ -13,11 +14,13 @@ use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::config::GenericConfig;
+use plonky2::util::serialization::Buffer;
 use serde::{Deserialize, Serialize};
+use starky::permutation::PermutationChallengeSet;
 
 use crate::all_stark::NUM_TABLES;
 use crate::config::StarkConfig;
-use crate::permutation::GrandProductChallengeSet;
+use crate::serialization::{read_all_proof, write_all_proof};
 
 #[derive(Debug, Clone)]
 pub struct AllProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
@@ -29,11 +32,23 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
     pub fn degree_bits(&self, config: &StarkConfig) -> [usize; NUM_TABLES] {
         std::array::from_fn(|i| self.stark_proofs[i].recover_degree_bits(config))
     }
+
+    pub fn to_bytes(&self, config: &StarkConfig) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        write_all_proof(&mut buffer, self, config)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, config: &StarkConfig) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let proof = read_all_proof(&mut buffer, config)?;
+        Ok(proof)
+    }
 }
 
 pub(crate) struct AllProofChallenges<F: RichField + Extendable<D>, const D: usize> {
     pub stark_challenges: [StarkProofChallenges<F, D>; NUM_TABLES],
-    pub ctl_challenges: GrandProductChallengeSet<F>,
+    pub ctl_challenges: PermutationChallengeSet<F>,
 }
 
 #[allow(unused)] // TODO: should be used soon
@@ -41,7 +56,7 @@ pub(crate) struct AllChallengerState<F: RichField + Extendable<D>, const D: usiz
     /// Sponge state of the challenger before starting each proof,
     /// along with the final state after all proofs are done. This final state isn't strictly needed.
     pub states: [[F; SPONGE_WIDTH]; NUM_TABLES + 1],
-    pub ctl_challenges: GrandProductChallengeSet<F>,
+    pub ctl_challenges: PermutationChallengeSet<F>,
 }
 
 pub struct AllProofTarget<const D: usize> {
@@ -50,21 +65,21 @@ pub struct AllProofTarget<const D: usize> {
 }
 
 /// Memory values which are public.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub struct PublicValues {
     pub trie_roots_before: TrieRoots,
     pub trie_roots_after: TrieRoots,
     pub block_metadata: BlockMetadata,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub struct TrieRoots {
     pub state_root: H256,
     pub transactions_root: H256,
     pub receipts_root: H256,
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct BlockMetadata {
     pub block_beneficiary: Address,
     pub block_timestamp: U256,
@@ -151,7 +166,7 @@ impl<const D: usize> StarkProofTarget<D> {
 
 pub(crate) struct StarkProofChallenges<F: RichField + Extendable<D>, const D: usize> {
     /// Randomness used in any permutation arguments.
-    pub permutation_challenge_sets: Option<Vec<GrandProductChallengeSet<F>>>,
+    pub permutation_challenge_sets: Option<Vec<PermutationChallengeSet<F>>>,
 
     /// Random values used to combine STARK constraints.
     pub stark_alphas: Vec<F>,
@@ -163,7 +178,7 @@ pub(crate) struct StarkProofChallenges<F: RichField + Extendable<D>, const D: us
 }
 
 pub(crate) struct StarkProofChallengesTarget<const D: usize> {
-    pub permutation_challenge_sets: Option<Vec<GrandProductChallengeSet<Target>>>,
+    pub permutation_challenge_sets: Option<Vec<PermutationChallengeSet<Target>>>,
     pub stark_alphas: Vec<Target>,
     pub stark_zeta: ExtensionTarget<D>,
     pub fri_challenges: FriChallengesTarget<D>,
@@ -197,15 +212,20 @@ impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
         num_permutation_zs: usize,
     ) -> Self {
         let eval_commitment = |z: F::Extension, c: &PolynomialBatch<F, C, D>| {
-            c.polynomials
-                .par_iter()
-                .map(|p| p.to_extension().eval(z))
+            let indices = (0..c.num_polynomials()).collect::<Vec<_>>();
+            c.polynomial_chunks(&indices)
+                .flat_map(|chunk| {
+                    chunk
+                        .par_iter()
+                        .map(|p| p.to_extension().eval(z))
+                        .collect::<Vec<_>>()
+                })
                 .collect::<Vec<_>>()
         };
         let eval_commitment_base = |z: F, c: &PolynomialBatch<F, C, D>| {
-            c.polynomials
-                .par_iter()
-                .map(|p| p.eval(z))
+            let indices = (0..c.num_polynomials()).collect::<Vec<_>>();
+            c.polynomial_chunks(&indices)
+                .flat_map(|chunk| chunk.par_iter().map(|p| p.eval(z)).collect::<Vec<_>>())
                 .collect::<Vec<_>>()
         };
         let zeta_next = zeta.scalar_mul(g);
@@ -16,21 +16,22 @@ use plonky2::timed;
 use plonky2::util::timing::TimingTree;
 use plonky2::util::transpose;
 use plonky2_util::{log2_ceil, log2_strict};
+use starky::cross_table_lookup::cross_table_lookup_data;
+use starky::permutation::{
+    get_n_permutation_challenge_sets, get_permutation_challenge_set, PermutationChallengeSet,
+};
 
 use crate::all_stark::{AllStark, Table, NUM_TABLES};
 use crate::config::StarkConfig;
 use crate::constraint_consumer::ConstraintConsumer;
 use crate::cpu::cpu_stark::CpuStark;
-use crate::cross_table_lookup::{cross_table_lookup_data, CtlCheckVars, CtlData};
+use crate::cross_table_lookup::{CtlCheckVars, CtlData};
 use crate::generation::{generate_traces, GenerationInputs};
 use crate::keccak::keccak_stark::KeccakStark;
 use crate::keccak_memory::keccak_memory_stark::KeccakMemoryStark;
 use crate::logic::LogicStark;
 use crate::memory::memory_stark::MemoryStark;
-use crate::permutation::PermutationCheckVars;
-use crate::permutation::{
-    compute_permutation_z_polys, get_n_grand_product_challenge_sets, GrandProductChallengeSet,
-};
+use crate::permutation::{compute_permutation_z_polys, PermutationCheckVars};
 use crate::proof::{AllProof, PublicValues, StarkOpeningSet, StarkProof};
 use crate::stark::Stark;
 use crate::vanishing_poly::eval_vanishing_poly;
@@ -107,11 +108,11 @@ where
         challenger.observe_cap(cap);
     }
 
-    let ctl_data_per_table = cross_table_lookup_data::<F, C, D>(
-        config,
+    let ctl_challenges = get_permutation_challenge_set(&mut challenger, config.num_challenges);
+    let ctl_data_per_table = cross_table_lookup_data(
         &trace_poly_values,
         &all_stark.cross_table_lookups,
-        &mut challenger,
+        &ctl_challenges,
     );
 
     let cpu_proof = prove_single_table(
@@ -205,7 +206,7 @@ where
 
     // Permutation arguments.
     let permutation_challenges = stark.uses_permutation_args().then(|| {
-        get_n_grand_product_challenge_sets(
+        get_n_permutation_challenge_sets(
             challenger,
             config.num_challenges,
             stark.permutation_batch_size(),
@@ -359,7 +360,7 @@ fn compute_quotient_polys<'a, F, P, C, S, const D: usize>(
     stark: &S,
     trace_commitment: &'a PolynomialBatch<F, C, D>,
     permutation_ctl_zs_commitment: &'a PolynomialBatch<F, C, D>,
-    permutation_challenges: Option<&'a Vec<GrandProductChallengeSet<F>>>,
+    permutation_challenges: Option<&'a Vec<PermutationChallengeSet<F>>>,
     ctl_data: &CtlData<F>,
     alphas: Vec<F>,
     degree_bits: usize,
@@ -496,7 +497,7 @@ fn check_constraints<'a, F, C, S, const D: usize>(
     stark: &S,
     trace_commitment: &'a PolynomialBatch<F, C, D>,
     permutation_ctl_zs_commitment: &'a PolynomialBatch<F, C, D>,
-    permutation_challenges: Option<&'a Vec<GrandProductChallengeSet<F>>>,
+    permutation_challenges: Option<&'a Vec<PermutationChallengeSet<F>>>,
     ctl_data: &CtlData<F>,
     alphas: Vec<F>,
     degree_bits: usize,
@@ -523,10 +524,15 @@ fn check_constraints<'a, F, C, S, const D: usize>(
 
     // Get the evaluations of a batch of polynomials over our subgroup.
     let get_subgroup_evals = |comm: &PolynomialBatch<F, C, D>| -> Vec<Vec<F>> {
+        let indices = (0..comm.num_polynomials()).collect::<Vec<_>>();
         let values = comm
-            .polynomials
-            .par_iter()
-            .map(|coeffs| coeffs.clone().fft().values)
+            .polynomial_chunks(&indices)
+            .flat_map(|chunk| {
+                chunk
+                    .into_par_iter()
+                    .map(|coeffs| coeffs.into_owned().fft().values)
+                    .collect::<Vec<_>>()
+            })
             .collect::<Vec<_>>();
         transpose(&values)
     };
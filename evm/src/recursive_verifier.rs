@@ -18,23 +18,22 @@ use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
 use plonky2::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
 use plonky2::util::reducing::ReducingFactorTarget;
 use plonky2::with_context;
+use starky::cross_table_lookup::{verify_cross_table_lookups, verify_cross_table_lookups_circuit};
+use starky::permutation::{
+    get_permutation_challenge_set, get_permutation_challenge_set_target, PermutationChallenge,
+    PermutationChallengeSet,
+};
 
 use crate::all_stark::NUM_TABLES;
 use crate::config::StarkConfig;
 use crate::constraint_consumer::RecursiveConstraintConsumer;
 use crate::cpu::cpu_stark::CpuStark;
-use crate::cross_table_lookup::{
-    verify_cross_table_lookups, verify_cross_table_lookups_circuit, CrossTableLookup,
-    CtlCheckVarsTarget,
-};
+use crate::cross_table_lookup::{CrossTableLookup, CtlCheckVarsTarget};
 use crate::keccak::keccak_stark::KeccakStark;
 use crate::keccak_memory::keccak_memory_stark::KeccakMemoryStark;
 use crate::logic::LogicStark;
 use crate::memory::memory_stark::MemoryStark;
-use crate::permutation::{
-    get_grand_product_challenge_set, get_grand_product_challenge_set_target, GrandProductChallenge,
-    GrandProductChallengeSet, PermutationCheckDataTarget,
-};
+use crate::permutation::PermutationCheckDataTarget;
 use crate::proof::{
     AllProof, AllProofTarget, BlockMetadata, BlockMetadataTarget, PublicValues, PublicValuesTarget,
     StarkOpeningSetTarget, StarkProof, StarkProofChallengesTarget, StarkProofTarget, TrieRoots,
@@ -66,7 +65,7 @@ pub struct RecursiveAllProofTargetWithData<const D: usize> {
 struct PublicInputs<T: Copy + Eq + PartialEq + Debug> {
     trace_cap: Vec<Vec<T>>,
     ctl_zs_last: Vec<T>,
-    ctl_challenges: GrandProductChallengeSet<T>,
+    ctl_challenges: PermutationChallengeSet<T>,
     challenger_state_before: [T; SPONGE_WIDTH],
     challenger_state_after: [T; SPONGE_WIDTH],
 }
@@ -86,9 +85,9 @@ impl<T: Copy + Eq + PartialEq + Debug> PublicInputs<T> {
         let trace_cap = (0..1 << config.fri_config.cap_height)
             .map(|_| next_chunk::<_, 4>(&mut iter).to_vec())
             .collect();
-        let ctl_challenges = GrandProductChallengeSet {
+        let ctl_challenges = PermutationChallengeSet {
             challenges: (0..config.num_challenges)
-                .map(|_| GrandProductChallenge {
+                .map(|_| PermutationChallenge {
                     beta: iter.next().unwrap(),
                     gamma: iter.next().unwrap(),
                 })
@@ -132,7 +131,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             }
         }
         let ctl_challenges =
-            get_grand_product_challenge_set(&mut challenger, inner_config.num_challenges);
+            get_permutation_challenge_set(&mut challenger, inner_config.num_challenges);
         // Check that the correct CTL challenges are used in every proof.
         for pi in &pis {
             ensure!(ctl_challenges == pi.ctl_challenges);
@@ -146,13 +145,15 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         }
 
         // Verify the CTL checks.
-        let degrees_bits = std::array::from_fn(|i| verifier_data[i].common.degree_bits());
-        verify_cross_table_lookups::<F, C, D>(
-            cross_table_lookups,
-            pis.map(|p| p.ctl_zs_last),
-            degrees_bits,
-            ctl_challenges,
-            inner_config,
+        let degrees_bits = verifier_data
+            .iter()
+            .map(|data| data.common.degree_bits())
+            .collect::<Vec<_>>();
+        verify_cross_table_lookups(
+            &cross_table_lookups,
+            &pis.map(|p| p.ctl_zs_last),
+            &degrees_bits,
+            &ctl_challenges,
         )?;
 
         // Verify the proofs.
@@ -187,7 +188,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
                 challenger.observe_elements(h);
             }
         }
-        let ctl_challenges = get_grand_product_challenge_set_target(
+        let ctl_challenges = get_permutation_challenge_set_target(
             builder,
             &mut challenger,
             inner_config.num_challenges,
@@ -221,14 +222,16 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         }
 
         // Verify the CTL checks.
-        let degrees_bits = std::array::from_fn(|i| verifier_data[i].common.degree_bits());
-        verify_cross_table_lookups_circuit::<F, C, D>(
+        let degrees_bits = verifier_data
+            .iter()
+            .map(|data| data.common.degree_bits())
+            .collect::<Vec<_>>();
+        verify_cross_table_lookups_circuit(
             builder,
-            cross_table_lookups,
-            pis.map(|p| p.ctl_zs_last),
-            degrees_bits,
-            ctl_challenges,
-            inner_config,
+            &cross_table_lookups,
+            &pis.map(|p| p.ctl_zs_last),
+            &degrees_bits,
+            &ctl_challenges,
         );
         for (i, (recursive_proof, verifier_data_target)) in recursive_proofs
             .into_iter()
@@ -267,8 +270,11 @@ where
 
     let num_permutation_zs = stark.num_permutation_batches(inner_config);
     let num_permutation_batch_size = stark.permutation_batch_size();
-    let num_ctl_zs =
-        CrossTableLookup::num_ctl_zs(cross_table_lookups, table, inner_config.num_challenges);
+    let num_ctl_zs = CrossTableLookup::num_ctl_zs(
+        cross_table_lookups,
+        table as usize,
+        inner_config.num_challenges,
+    );
     let proof_target =
         add_virtual_stark_proof(&mut builder, &stark, inner_config, degree_bits, num_ctl_zs);
     builder.register_public_inputs(
@@ -280,9 +286,9 @@ where
             .collect::<Vec<_>>(),
     );
 
-    let ctl_challenges_target = GrandProductChallengeSet {
+    let ctl_challenges_target = PermutationChallengeSet {
         challenges: (0..inner_config.num_challenges)
-            .map(|_| GrandProductChallenge {
+            .map(|_| PermutationChallenge {
                 beta: builder.add_virtual_public_input(),
                 gamma: builder.add_virtual_public_input(),
             })
@@ -853,6 +859,7 @@ pub(crate) mod tests {
     use plonky2::plonk::config::Hasher;
     use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
     use plonky2::plonk::proof::ProofWithPublicInputs;
+    use starky::permutation::{PermutationChallenge, PermutationChallengeSet};
 
     use crate::all_stark::{AllStark, Table};
     use crate::config::StarkConfig;
@@ -862,7 +869,6 @@ pub(crate) mod tests {
     use crate::keccak_memory::keccak_memory_stark::KeccakMemoryStark;
     use crate::logic::LogicStark;
     use crate::memory::memory_stark::MemoryStark;
-    use crate::permutation::{GrandProductChallenge, GrandProductChallengeSet};
     use crate::proof::{AllChallengerState, AllProof, StarkProof};
     use crate::recursive_verifier::{
         add_virtual_stark_proof, set_stark_proof_target,
@@ -882,7 +888,7 @@ pub(crate) mod tests {
         stark: S,
         proof: &StarkProof<F, C, D>,
         cross_table_lookups: &[CrossTableLookup<F>],
-        ctl_challenges: &GrandProductChallengeSet<F>,
+        ctl_challenges: &PermutationChallengeSet<F>,
         challenger_state_before_vals: [F; SPONGE_WIDTH],
         inner_config: &StarkConfig,
         circuit_config: &CircuitConfig,
@@ -914,9 +920,9 @@ pub(crate) mod tests {
                 .collect::<Vec<_>>(),
         );
 
-        let ctl_challenges_target = GrandProductChallengeSet {
+        let ctl_challenges_target = PermutationChallengeSet {
             challenges: (0..inner_config.num_challenges)
-                .map(|_| GrandProductChallenge {
+                .map(|_| PermutationChallenge {
                     beta: builder.add_virtual_public_input(),
                     gamma: builder.add_virtual_public_input(),
                 })
@@ -0,0 +1,265 @@
+//! Binary encoding of `AllProof`s, built on plonky2's `Buffer` and the helpers of
+//! `starky::serialization`, whose version byte it shares.
+//!
+//! The proofs of the tables are written in the order of `Table`, each prefixed with the length of
+//! its trace, followed by the public values. As in starky, the `StarkConfig` is needed to read
+//! Merkle caps and FRI proofs.
+
+use ethereum_types::{H160, H256, U256};
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::util::serialization::{Buffer, IoResult};
+use starky::serialization::{
+    checked_fri_params, read_field_ext_vec, read_field_vec, read_version, write_degree_bits,
+    write_field_ext_vec, write_field_vec, SERIALIZATION_VERSION,
+};
+
+use crate::all_stark::NUM_TABLES;
+use crate::config::StarkConfig;
+use crate::proof::{AllProof, BlockMetadata, PublicValues, StarkOpeningSet, StarkProof, TrieRoots};
+
+pub fn write_all_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    buffer: &mut Buffer,
+    all_proof: &AllProof<F, C, D>,
+    config: &StarkConfig,
+) -> IoResult<()> {
+    buffer.write_u8(SERIALIZATION_VERSION)?;
+    for proof in &all_proof.stark_proofs {
+        write_stark_proof(buffer, proof, config)?;
+    }
+    write_public_values(buffer, &all_proof.public_values)
+}
+
+pub fn read_all_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    buffer: &mut Buffer,
+    config: &StarkConfig,
+) -> IoResult<AllProof<F, C, D>> {
+    read_version(buffer)?;
+    let stark_proofs = (0..NUM_TABLES)
+        .map(|_| read_stark_proof(buffer, config))
+        .collect::<IoResult<Vec<_>>>()?;
+    let public_values = read_public_values(buffer)?;
+    Ok(AllProof {
+        stark_proofs: stark_proofs
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("One proof is read per table.")),
+        public_values,
+    })
+}
+
+fn write_stark_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    buffer: &mut Buffer,
+    proof: &StarkProof<F, C, D>,
+    config: &StarkConfig,
+) -> IoResult<()> {
+    let StarkProof {
+        trace_cap,
+        permutation_ctl_zs_cap,
+        quotient_polys_cap,
+        openings,
+        opening_proof,
+    } = proof;
+
+    // The trace length can't be recovered before reading the FRI proof, which depends on it.
+    write_degree_bits(buffer, proof.recover_degree_bits(config))?;
+    buffer.write_merkle_cap(trace_cap)?;
+    buffer.write_merkle_cap(permutation_ctl_zs_cap)?;
+    buffer.write_merkle_cap(quotient_polys_cap)?;
+    write_opening_set(buffer, openings)?;
+    buffer.write_fri_proof::<F, C, D>(opening_proof)
+}
+
+fn read_stark_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    buffer: &mut Buffer,
+    config: &StarkConfig,
+) -> IoResult<StarkProof<F, C, D>> {
+    let degree_bits = buffer.read_u8()? as usize;
+    let cap_height = config.fri_config.cap_height;
+    let trace_cap = buffer.read_merkle_cap(cap_height)?;
+    let permutation_ctl_zs_cap = buffer.read_merkle_cap(cap_height)?;
+    let quotient_polys_cap = buffer.read_merkle_cap(cap_height)?;
+    let openings = read_opening_set(buffer)?;
+    // The EVM's oracles are never salted, so their leaves are just the opened polynomials.
+    let initial_leaf_sizes = [
+        openings.local_values.len(),
+        openings.permutation_ctl_zs.len(),
+        openings.quotient_polys.len(),
+    ];
+    let fri_params = checked_fri_params(&config.fri_config, degree_bits, false)?;
+    let opening_proof = buffer.read_fri_proof::<F, C, D>(&fri_params, &initial_leaf_sizes)?;
+
+    Ok(StarkProof {
+        trace_cap,
+        permutation_ctl_zs_cap,
+        quotient_polys_cap,
+        openings,
+        opening_proof,
+    })
+}
+
+fn write_opening_set<F: RichField + Extendable<D>, const D: usize>(
+    buffer: &mut Buffer,
+    openings: &StarkOpeningSet<F, D>,
+) -> IoResult<()> {
+    let StarkOpeningSet {
+        local_values,
+        next_values,
+        permutation_ctl_zs,
+        permutation_ctl_zs_next,
+        ctl_zs_last,
+        quotient_polys,
+    } = openings;
+
+    write_field_ext_vec::<F, D>(buffer, local_values)?;
+    write_field_ext_vec::<F, D>(buffer, next_values)?;
+    write_field_ext_vec::<F, D>(buffer, permutation_ctl_zs)?;
+    write_field_ext_vec::<F, D>(buffer, permutation_ctl_zs_next)?;
+    write_field_vec(buffer, ctl_zs_last)?;
+    write_field_ext_vec::<F, D>(buffer, quotient_polys)
+}
+
+fn read_opening_set<F: RichField + Extendable<D>, const D: usize>(
+    buffer: &mut Buffer,
+) -> IoResult<StarkOpeningSet<F, D>> {
+    Ok(StarkOpeningSet {
+        local_values: read_field_ext_vec::<F, D>(buffer)?,
+        next_values: read_field_ext_vec::<F, D>(buffer)?,
+        permutation_ctl_zs: read_field_ext_vec::<F, D>(buffer)?,
+        permutation_ctl_zs_next: read_field_ext_vec::<F, D>(buffer)?,
+        ctl_zs_last: read_field_vec(buffer)?,
+        quotient_polys: read_field_ext_vec::<F, D>(buffer)?,
+    })
+}
+
+fn write_public_values(buffer: &mut Buffer, public_values: &PublicValues) -> IoResult<()> {
+    let PublicValues {
+        trie_roots_before,
+        trie_roots_after,
+        block_metadata,
+    } = public_values;
+    let BlockMetadata {
+        block_beneficiary,
+        block_timestamp,
+        block_number,
+        block_difficulty,
+        block_gaslimit,
+        block_chain_id,
+        block_base_fee,
+    } = block_metadata;
+
+    write_trie_roots(buffer, trie_roots_before)?;
+    write_trie_roots(buffer, trie_roots_after)?;
+    write_bytes(buffer, block_beneficiary.as_bytes())?;
+    for x in [
+        block_timestamp,
+        block_number,
+        block_difficulty,
+        block_gaslimit,
+        block_chain_id,
+        block_base_fee,
+    ] {
+        write_u256(buffer, *x)?;
+    }
+    Ok(())
+}
+
+fn read_public_values(buffer: &mut Buffer) -> IoResult<PublicValues> {
+    Ok(PublicValues {
+        trie_roots_before: read_trie_roots(buffer)?,
+        trie_roots_after: read_trie_roots(buffer)?,
+        block_metadata: BlockMetadata {
+            block_beneficiary: H160(read_bytes(buffer)?),
+            block_timestamp: read_u256(buffer)?,
+            block_number: read_u256(buffer)?,
+            block_difficulty: read_u256(buffer)?,
+            block_gaslimit: read_u256(buffer)?,
+            block_chain_id: read_u256(buffer)?,
+            block_base_fee: read_u256(buffer)?,
+        },
+    })
+}
+
+fn write_trie_roots(buffer: &mut Buffer, trie_roots: &TrieRoots) -> IoResult<()> {
+    write_bytes(buffer, trie_roots.state_root.as_bytes())?;
+    write_bytes(buffer, trie_roots.transactions_root.as_bytes())?;
+    write_bytes(buffer, trie_roots.receipts_root.as_bytes())
+}
+
+fn read_trie_roots(buffer: &mut Buffer) -> IoResult<TrieRoots> {
+    Ok(TrieRoots {
+        state_root: H256(read_bytes(buffer)?),
+        transactions_root: H256(read_bytes(buffer)?),
+        receipts_root: H256(read_bytes(buffer)?),
+    })
+}
+
+/// Writes a `U256` as 32 big-endian bytes.
+fn write_u256(buffer: &mut Buffer, x: U256) -> IoResult<()> {
+    let mut bytes = [0; 32];
+    x.to_big_endian(&mut bytes);
+    write_bytes(buffer, &bytes)
+}
+
+fn read_u256(buffer: &mut Buffer) -> IoResult<U256> {
+    let bytes: [u8; 32] = read_bytes(buffer)?;
+    Ok(U256::from_big_endian(&bytes))
+}
+
+/// Writes bytes whose number is fixed by their type, so without a length prefix.
+fn write_bytes(buffer: &mut Buffer, bytes: &[u8]) -> IoResult<()> {
+    for &byte in bytes {
+        buffer.write_u8(byte)?;
+    }
+    Ok(())
+}
+
+fn read_bytes<const N: usize>(buffer: &mut Buffer) -> IoResult<[u8; N]> {
+    let mut bytes = [0; N];
+    for byte in &mut bytes {
+        *byte = buffer.read_u8()?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use ethereum_types::{Address, H256, U256};
+    use plonky2::util::serialization::Buffer;
+
+    use crate::proof::{BlockMetadata, PublicValues, TrieRoots};
+    use crate::serialization::{read_public_values, write_public_values};
+
+    #[test]
+    fn test_public_values_serialization() -> Result<()> {
+        let public_values = PublicValues {
+            trie_roots_before: TrieRoots {
+                state_root: H256::random(),
+                transactions_root: H256::random(),
+                receipts_root: H256::random(),
+            },
+            trie_roots_after: TrieRoots {
+                state_root: H256::random(),
+                transactions_root: H256::random(),
+                receipts_root: H256::random(),
+            },
+            block_metadata: BlockMetadata {
+                block_beneficiary: Address::random(),
+                block_timestamp: U256::from(1_000_000_000u64),
+                block_number: U256::from(15_537_394u64),
+                block_difficulty: U256::MAX,
+                block_gaslimit: U256::from(30_000_000u64),
+                block_chain_id: U256::one(),
+                block_base_fee: U256::from(7u64) << 128,
+            },
+        };
+
+        let mut buffer = Buffer::new(Vec::new());
+        write_public_values(&mut buffer, &public_values)?;
+        let mut buffer = Buffer::new(buffer.bytes());
+        assert_eq!(read_public_values(&mut buffer)?, public_values);
+        assert_eq!(buffer.remaining(), 0);
+        Ok(())
+    }
+}
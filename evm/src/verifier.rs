@@ -5,12 +5,13 @@ use plonky2::fri::verifier::verify_fri_proof;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::config::{GenericConfig, Hasher};
 use plonky2::plonk::plonk_common::reduce_with_powers;
+use starky::cross_table_lookup::verify_cross_table_lookups;
 
 use crate::all_stark::{AllStark, Table};
 use crate::config::StarkConfig;
 use crate::constraint_consumer::ConstraintConsumer;
 use crate::cpu::cpu_stark::CpuStark;
-use crate::cross_table_lookup::{verify_cross_table_lookups, CtlCheckVars};
+use crate::cross_table_lookup::CtlCheckVars;
 use crate::keccak::keccak_stark::KeccakStark;
 use crate::keccak_memory::keccak_memory_stark::KeccakMemoryStark;
 use crate::logic::LogicStark;
@@ -95,14 +96,16 @@ where
         config,
     )?;
 
-    let degrees_bits =
-        std::array::from_fn(|i| all_proof.stark_proofs[i].recover_degree_bits(config));
-    verify_cross_table_lookups::<F, C, D>(
-        cross_table_lookups,
-        all_proof.stark_proofs.map(|p| p.openings.ctl_zs_last),
-        degrees_bits,
-        ctl_challenges,
-        config,
+    let degrees_bits = all_proof
+        .stark_proofs
+        .iter()
+        .map(|p| p.recover_degree_bits(config))
+        .collect::<Vec<_>>();
+    verify_cross_table_lookups(
+        &cross_table_lookups,
+        &all_proof.stark_proofs.map(|p| p.openings.ctl_zs_last),
+        &degrees_bits,
+        &ctl_challenges,
     )
 }
 
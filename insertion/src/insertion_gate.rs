@@ -13,6 +13,7 @@ use plonky2::iop::wire::Wire;
 use plonky2::iop::witness::{PartitionWitness, Witness};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use plonky2::util::serialization::{Buffer, IoResult};
 
 /// A gate for inserting a value into a list at a non-deterministic location.
 #[derive(Clone, Debug)]
@@ -76,6 +77,18 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for InsertionGate<
         format!("{self:?}<D={D}>")
     }
 
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.vec_size)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let vec_size = src.read_usize()?;
+        Ok(Self {
+            vec_size,
+            _phantom: PhantomData,
+        })
+    }
+
     fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
         let insertion_index = vars.local_wires[self.wires_insertion_index()];
         let list_items = (0..self.vec_size)
@@ -313,6 +326,17 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F> for Insert
             out_buffer.set_wire(insert_here_wire, insert_here_vals[i]);
         }
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        self.gate.serialize(dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let gate = InsertionGate::deserialize(src)?;
+        Ok(Self { row, gate })
+    }
 }
 
 #[cfg(test)]
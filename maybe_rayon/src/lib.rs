@@ -277,3 +277,15 @@ where
 {
     (oper_a(), oper_b())
 }
+
+/// The number of threads available for parallel work.
+#[cfg(feature = "parallel")]
+pub fn current_num_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+/// The number of threads available for parallel work.
+#[cfg(not(feature = "parallel"))]
+pub fn current_num_threads() -> usize {
+    1
+}
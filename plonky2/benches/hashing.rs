@@ -5,10 +5,14 @@ mod allocator;
 
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::packable::Packable;
+use plonky2::field::packed::PackedField;
 use plonky2::hash::hash_types::{BytesHash, RichField};
 use plonky2::hash::hashing::SPONGE_WIDTH;
 use plonky2::hash::keccak::KeccakHash;
 use plonky2::hash::poseidon::Poseidon;
+use plonky2::hash::poseidon2::CustomPoseidon2;
+use plonky2::hash::rescue_prime::CustomRescuePrime;
 use plonky2::plonk::config::Hasher;
 use tynm::type_name;
 
@@ -35,8 +39,54 @@ pub(crate) fn bench_poseidon<F: Poseidon>(c: &mut Criterion) {
     );
 }
 
+pub(crate) fn bench_poseidon_packed<F: Poseidon>(c: &mut Criterion) {
+    c.bench_function(
+        &format!(
+            "poseidon_packed<{}, {SPONGE_WIDTH}, {} lanes>",
+            type_name::<F>(),
+            <F as Packable>::Packing::WIDTH
+        ),
+        |b| {
+            b.iter_batched(
+                || F::rand_arr::<SPONGE_WIDTH>().map(<F as Packable>::Packing::from),
+                |state| F::poseidon_packed(state),
+                BatchSize::SmallInput,
+            )
+        },
+    );
+}
+
+pub(crate) fn bench_poseidon2<F: CustomPoseidon2>(c: &mut Criterion) {
+    c.bench_function(
+        &format!("custom_poseidon2<{}, {SPONGE_WIDTH}>", type_name::<F>()),
+        |b| {
+            b.iter_batched(
+                || F::rand_arr::<SPONGE_WIDTH>(),
+                |state| F::poseidon2(state),
+                BatchSize::SmallInput,
+            )
+        },
+    );
+}
+
+pub(crate) fn bench_rescue_prime<F: CustomRescuePrime>(c: &mut Criterion) {
+    c.bench_function(
+        &format!("custom_rescue_prime<{}, {SPONGE_WIDTH}>", type_name::<F>()),
+        |b| {
+            b.iter_batched(
+                || F::rand_arr::<SPONGE_WIDTH>(),
+                |state| F::rescue_prime(state),
+                BatchSize::SmallInput,
+            )
+        },
+    );
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     bench_poseidon::<GoldilocksField>(c);
+    bench_poseidon_packed::<GoldilocksField>(c);
+    bench_poseidon2::<GoldilocksField>(c);
+    bench_rescue_prime::<GoldilocksField>(c);
     bench_keccak::<GoldilocksField>(c);
 }
 
@@ -0,0 +1,77 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+mod allocator;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use plonky2::field::types::Field;
+use plonky2::iop::generator::generate_partial_witness;
+use plonky2::iop::witness::{PartialWitness, Witness};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// A circuit, along with the inputs of its witness.
+type CircuitWithInputs = (CircuitData<F, C, D>, PartialWitness<F>);
+
+/// A circuit whose generators form a single chain, each one depending on the previous one.
+fn deep_circuit(num_ops: usize) -> CircuitWithInputs {
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let x0 = builder.add_virtual_target();
+    let mut x = x0;
+    for _ in 0..num_ops {
+        x = builder.mul_add(x, x, x0);
+    }
+    builder.register_public_input(x);
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x0, F::rand());
+    (builder.build::<C>(), pw)
+}
+
+/// A circuit whose generators are all independent of each other.
+fn wide_circuit(num_ops: usize) -> CircuitWithInputs {
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let mut pw = PartialWitness::new();
+    for _ in 0..num_ops {
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        builder.mul(x, y);
+        pw.set_target(x, F::rand());
+        pw.set_target(y, F::rand());
+    }
+    (builder.build::<C>(), pw)
+}
+
+fn bench_witness_generation(
+    c: &mut Criterion,
+    name: &str,
+    circuit: fn(usize) -> CircuitWithInputs,
+) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    for num_ops_log in [12, 14, 16] {
+        let num_ops = 1 << num_ops_log;
+        let (data, pw) = circuit(num_ops);
+        group.bench_with_input(BenchmarkId::from_parameter(num_ops), &num_ops, |b, _| {
+            b.iter_batched(
+                || pw.clone(),
+                |pw| generate_partial_witness(pw, &data.prover_only, &data.common),
+                BatchSize::SmallInput,
+            );
+        });
+    }
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_witness_generation(c, "witness-generation-deep", deep_circuit);
+    bench_witness_generation(c, "witness-generation-wide", wide_circuit);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
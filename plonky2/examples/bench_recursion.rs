@@ -18,7 +18,10 @@ use plonky2::{
         circuit_data::{
             CircuitConfig, CommonCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
         },
-        config::{AlgebraicHasher, GenericConfig, Hasher, PoseidonGoldilocksConfig},
+        config::{
+            AlgebraicHasher, CustomPoseidon2GoldilocksConfig, CustomRescuePrimeGoldilocksConfig,
+            GenericConfig, Hasher, PoseidonGoldilocksConfig,
+        },
         proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs},
         prover::prove,
     },
@@ -60,6 +63,17 @@ struct Options {
     /// range.
     #[structopt(long, default_value="14", parse(try_from_str = parse_range_usize))]
     size: RangeInclusive<usize>,
+
+    /// Hash function used by the proofs: `poseidon`, `custom-poseidon2` or `custom-rescue-prime`.
+    #[structopt(long, default_value = "poseidon", parse(try_from_str = parse_hash))]
+    hash: HashKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum HashKind {
+    Poseidon,
+    CustomPoseidon2,
+    CustomRescuePrime,
 }
 
 /// Creates a dummy proof which should have `2 ** log2_size` rows.
@@ -180,13 +194,16 @@ where
     Ok(())
 }
 
-fn benchmark(config: &CircuitConfig, log2_inner_size: usize) -> Result<()> {
-    const D: usize = 2;
-    type C = PoseidonGoldilocksConfig;
-    type F = <C as GenericConfig<D>>::F;
-
+fn benchmark<C: GenericConfig<D>, const D: usize>(
+    config: &CircuitConfig,
+    log2_inner_size: usize,
+) -> Result<()>
+where
+    C::Hasher: AlgebraicHasher<C::F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
     // Start with a dummy proof of specified size
-    let inner = dummy_proof::<F, C, D>(config, log2_inner_size)?;
+    let inner = dummy_proof::<C::F, C, D>(config, log2_inner_size)?;
     let (_, _, cd) = &inner;
     info!(
         "Initial proof degree {} = 2^{}",
@@ -195,7 +212,7 @@ fn benchmark(config: &CircuitConfig, log2_inner_size: usize) -> Result<()> {
     );
 
     // Recursively verify the proof
-    let middle = recursive_proof::<F, C, C, D>(&inner, config, None)?;
+    let middle = recursive_proof::<C::F, C, C, D>(&inner, config, None)?;
     let (_, _, cd) = &middle;
     info!(
         "Single recursion proof degree {} = 2^{}",
@@ -204,7 +221,7 @@ fn benchmark(config: &CircuitConfig, log2_inner_size: usize) -> Result<()> {
     );
 
     // Add a second layer of recursion to shrink the proof size further
-    let outer = recursive_proof::<F, C, C, D>(&middle, config, None)?;
+    let outer = recursive_proof::<C::F, C, C, D>(&middle, config, None)?;
     let (proof, vd, cd) = &outer;
     info!(
         "Double recursion proof degree {} = 2^{}",
@@ -257,7 +274,23 @@ fn main() -> Result<()> {
                         num_cpus
                     );
                     // Run the benchmark
-                    benchmark(&config, log2_inner_size)
+                    match options.hash {
+                        HashKind::Poseidon => {
+                            benchmark::<PoseidonGoldilocksConfig, 2>(&config, log2_inner_size)
+                        }
+                        HashKind::CustomPoseidon2 => {
+                            benchmark::<CustomPoseidon2GoldilocksConfig, 2>(
+                                &config,
+                                log2_inner_size,
+                            )
+                        }
+                        HashKind::CustomRescuePrime => {
+                            benchmark::<CustomRescuePrimeGoldilocksConfig, 2>(
+                                &config,
+                                log2_inner_size,
+                            )
+                        }
+                    }
                 })?;
         }
     }
@@ -270,6 +303,15 @@ fn parse_hex_u64(src: &str) -> Result<u64, ParseIntError> {
     u64::from_str_radix(src, 16)
 }
 
+fn parse_hash(src: &str) -> Result<HashKind> {
+    match src {
+        "poseidon" => Ok(HashKind::Poseidon),
+        "custom-poseidon2" => Ok(HashKind::CustomPoseidon2),
+        "custom-rescue-prime" => Ok(HashKind::CustomRescuePrime),
+        _ => Err(anyhow!("Unknown hash function {src}")),
+    }
+}
+
 fn parse_range_usize(src: &str) -> Result<RangeInclusive<usize>, ParseIntError> {
     if let Some((left, right)) = src.split_once("..=") {
         Ok(RangeInclusive::new(
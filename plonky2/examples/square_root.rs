@@ -9,6 +9,7 @@ use plonky2::iop::witness::{PartialWitness, PartitionWitness, Witness};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::circuit_data::CircuitConfig;
 use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::util::serialization::{Buffer, IoResult};
 use plonky2_field::extension::Extendable;
 
 /// A generator used by the prover to calculate the square root (`x`) of a given value
@@ -35,6 +36,21 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
 
         out_buffer.set_target(self.x, x);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.x)?;
+        dst.write_target(self.x_squared)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let x = src.read_target()?;
+        let x_squared = src.read_target()?;
+        Ok(Self {
+            x,
+            x_squared,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 /// An example of using Plonky2 to prove a statement of the form
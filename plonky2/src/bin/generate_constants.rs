@@ -1,4 +1,7 @@
-//! Generates random constants using ChaCha20, seeded with zero.
+//! Generates random constants using ChaCha8, seeded with zero by default.
+//!
+//! Usage: `generate_constants [count] [seed]`. With no arguments, this prints the 12 * 30 Poseidon
+//! round constants.
 
 #![allow(clippy::needless_range_loop)]
 
@@ -12,9 +15,13 @@ const SAMPLE_RANGE_END: u64 = GoldilocksField::ORDER;
 const N: usize = 12 * 30; // For Poseidon-12
 
 pub(crate) fn main() {
-    let mut rng = ChaCha8Rng::seed_from_u64(0);
-    let mut constants = [0u64; N];
-    for i in 0..N {
+    let mut args = std::env::args().skip(1);
+    let n = args.next().map_or(N, |s| s.parse().expect("Invalid count"));
+    let seed = args.next().map_or(0, |s| s.parse().expect("Invalid seed"));
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut constants = vec![0u64; n];
+    for i in 0..n {
         constants[i] = rng.gen_range(0..SAMPLE_RANGE_END);
     }
 
@@ -6,6 +6,7 @@ pub mod proof;
 pub mod prover;
 pub mod recursive_verifier;
 pub mod reduction_strategies;
+pub mod soundness;
 pub mod structure;
 mod validate_shape;
 pub mod verifier;
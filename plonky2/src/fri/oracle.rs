@@ -1,3 +1,6 @@
+use std::borrow::Cow;
+use std::mem::size_of;
+
 use itertools::Itertools;
 use maybe_rayon::*;
 use plonky2_field::extension::Extendable;
@@ -27,11 +30,18 @@ pub const SALT_SIZE: usize = 4;
 /// Represents a FRI oracle, i.e. a batch of polynomials which have been Merklized.
 pub struct PolynomialBatch<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
 {
-    pub polynomials: Vec<PolynomialCoeffs<F>>,
+    /// The committed polynomials. This is empty for batches committed in low-memory mode, so it is
+    /// only accessed through `polynomial` and `polynomial_chunks`, which recover the polynomials
+    /// from the Merkle leaves in that case.
+    polynomials: Vec<PolynomialCoeffs<F>>,
     pub merkle_tree: MerkleTree<F, C::Hasher>,
     pub degree_log: usize,
     pub rate_bits: usize,
     pub blinding: bool,
+    /// The memory budget of batches committed in low-memory mode, which bounds how many
+    /// polynomials are recovered from the Merkle leaves at once. It is not serialized; without it,
+    /// polynomials are recovered one at a time.
+    memory_budget: Option<usize>,
 }
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
@@ -98,9 +108,217 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             degree_log: log2_strict(degree),
             rate_bits,
             blinding,
+            memory_budget: None,
         }
     }
 
+    /// Like `from_values`, but in low-memory mode; see `from_coeffs_low_memory`.
+    pub fn from_values_low_memory(
+        values: Vec<PolynomialValues<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        memory_budget: usize,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let coeffs = timed!(
+            timing,
+            "IFFT",
+            values.into_par_iter().map(|v| v.ifft()).collect::<Vec<_>>()
+        );
+
+        Self::from_coeffs_low_memory(
+            coeffs,
+            rate_bits,
+            blinding,
+            cap_height,
+            memory_budget,
+            timing,
+            fft_root_table,
+        )
+    }
+
+    /// Like `from_coeffs`, but computes the LDEs in batches of polynomials whose LDEs fit in
+    /// `memory_budget` bytes, writing them directly into the Merkle leaves rather than transposing
+    /// the full LDE matrix. The coefficients are dropped once committed, and recomputed from the
+    /// leaves when needed, again in chunks whose LDEs fit in `memory_budget` bytes.
+    ///
+    /// The budget only bounds these temporary LDEs and recovered coefficients: the Merkle tree
+    /// still holds the full LDE of every polynomial in memory.
+    pub fn from_coeffs_low_memory(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        memory_budget: usize,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let degree = polynomials[0].len();
+        let lde_bytes = (degree << rate_bits) * size_of::<F>();
+        let batch_size = (memory_budget / lde_bytes).max(1);
+        let mut leaves = timed!(
+            timing,
+            &format!("FFT + blinding in batches of {batch_size} polynomials"),
+            Self::lde_leaves_in_batches(
+                polynomials,
+                rate_bits,
+                blinding,
+                batch_size,
+                fft_root_table
+            )
+        );
+
+        reverse_index_bits_in_place(&mut leaves);
+        let merkle_tree = timed!(
+            timing,
+            "build Merkle tree",
+            MerkleTree::new(leaves, cap_height)
+        );
+
+        Self {
+            polynomials: Vec::new(),
+            merkle_tree,
+            degree_log: log2_strict(degree),
+            rate_bits,
+            blinding,
+            memory_budget: Some(memory_budget),
+        }
+    }
+
+    /// Computes the rows of the LDE matrix of `polynomials`, with salts, computing the LDEs of
+    /// `batch_size` polynomials at a time. The polynomials are dropped as soon as they are used.
+    fn lde_leaves_in_batches(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        batch_size: usize,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Vec<Vec<F>> {
+        let lde_size = polynomials[0].len() << rate_bits;
+        let salt_size = if blinding { SALT_SIZE } else { 0 };
+        let leaf_size = polynomials.len() + salt_size;
+        let mut leaves = (0..lde_size)
+            .map(|_| Vec::with_capacity(leaf_size))
+            .collect::<Vec<_>>();
+
+        let mut polynomials = polynomials.into_iter().peekable();
+        while polynomials.peek().is_some() {
+            let batch = polynomials.by_ref().take(batch_size).collect::<Vec<_>>();
+            let ldes = Self::lde_values(&batch, rate_bits, false, fft_root_table);
+            drop(batch);
+            leaves.par_iter_mut().enumerate().for_each(|(i, leaf)| {
+                leaf.extend(ldes.iter().map(|lde| lde[i]));
+            });
+        }
+        if blinding {
+            leaves
+                .par_iter_mut()
+                .for_each(|leaf| leaf.extend(F::rand_vec(salt_size)));
+        }
+        leaves
+    }
+
+    /// A batch read back from its serialized parts. `polynomials` is empty if the batch was
+    /// committed in low-memory mode.
+    pub(crate) fn from_parts(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        merkle_tree: MerkleTree<F, C::Hasher>,
+        degree_log: usize,
+        rate_bits: usize,
+        blinding: bool,
+    ) -> Self {
+        Self {
+            polynomials,
+            merkle_tree,
+            degree_log,
+            rate_bits,
+            blinding,
+            memory_budget: None,
+        }
+    }
+
+    /// The coefficients of the committed polynomials, if they are kept in memory, i.e. if the
+    /// batch was not committed in low-memory mode.
+    pub(crate) fn stored_polynomials(&self) -> Option<&[PolynomialCoeffs<F>]> {
+        (!self.polynomials.is_empty()).then_some(&self.polynomials[..])
+    }
+
+    /// The number of committed polynomials.
+    pub fn num_polynomials(&self) -> usize {
+        let salt_size = if self.blinding { SALT_SIZE } else { 0 };
+        self.merkle_tree.leaves[0].len() - salt_size
+    }
+
+    /// The `index`th committed polynomial, recovered from the Merkle leaves if the batch was
+    /// committed in low-memory mode. Recovering a polynomial takes a pass over the leaves and a
+    /// coset IFFT, so prefer `polynomial_chunks` to access several polynomials.
+    pub fn polynomial(&self, index: usize) -> Cow<'_, PolynomialCoeffs<F>> {
+        if self.polynomials.is_empty() {
+            Cow::Owned(self.recover_polynomials(&[index]).remove(0))
+        } else {
+            Cow::Borrowed(&self.polynomials[index])
+        }
+    }
+
+    /// The committed polynomials with the given indices, in chunks. If the batch was committed in
+    /// low-memory mode, each chunk is recovered from the Merkle leaves when the iterator reaches
+    /// it, and holds as many polynomials as fit in the memory budget. Otherwise, there is a single
+    /// chunk borrowing the polynomials.
+    pub fn polynomial_chunks<'a>(
+        &'a self,
+        indices: &'a [usize],
+    ) -> impl Iterator<Item = Vec<Cow<'a, PolynomialCoeffs<F>>>> + 'a {
+        let chunk_size = if self.polynomials.is_empty() {
+            let lde_bytes = (1 << (self.degree_log + self.rate_bits)) * size_of::<F>();
+            self.memory_budget
+                .map_or(1, |budget| budget / lde_bytes)
+                .max(1)
+        } else {
+            indices.len().max(1)
+        };
+        indices.chunks(chunk_size).map(move |chunk| {
+            if self.polynomials.is_empty() {
+                self.recover_polynomials(chunk)
+                    .into_iter()
+                    .map(Cow::Owned)
+                    .collect()
+            } else {
+                chunk
+                    .iter()
+                    .map(|&i| Cow::Borrowed(&self.polynomials[i]))
+                    .collect()
+            }
+        })
+    }
+
+    /// Recovers the polynomials with the given indices from the Merkle leaves, with a single pass
+    /// over the leaves.
+    fn recover_polynomials(&self, indices: &[usize]) -> Vec<PolynomialCoeffs<F>> {
+        let lde_size = 1 << (self.degree_log + self.rate_bits);
+        let mut ldes = vec![Vec::with_capacity(lde_size); indices.len()];
+        for i in 0..lde_size {
+            let leaf = self.get_lde_values(i, 1);
+            for (lde, &index) in ldes.iter_mut().zip(indices) {
+                lde.push(leaf[index]);
+            }
+        }
+        ldes.into_par_iter()
+            .map(|lde| {
+                let mut coeffs = PolynomialValues::new(lde).coset_ifft(F::coset_shift());
+                coeffs.coeffs.truncate(1 << self.degree_log);
+                coeffs
+            })
+            .collect()
+    }
+
     fn lde_values(
         polynomials: &[PolynomialCoeffs<F>],
         rate_bits: usize,
@@ -189,8 +407,19 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         // The oracles used in Plonky2 are given in `FRI_ORACLES` in `plonky2/src/plonk/plonk_common.rs`.
         for FriBatchInfo { point, polynomials } in &instance.batches {
             // Collect the coefficients of all the polynomials in `polynomials`.
-            let polys_coeff = polynomials.iter().map(|fri_poly| {
-                &oracles[fri_poly.oracle_index].polynomials[fri_poly.polynomial_index]
+            // Group consecutive polynomials of the same oracle, so that low-memory oracles recover
+            // them in chunks rather than one at a time.
+            let mut runs: Vec<(usize, Vec<usize>)> = Vec::new();
+            for fri_poly in polynomials {
+                match runs.last_mut() {
+                    Some((oracle_index, indices)) if *oracle_index == fri_poly.oracle_index => {
+                        indices.push(fri_poly.polynomial_index)
+                    }
+                    _ => runs.push((fri_poly.oracle_index, vec![fri_poly.polynomial_index])),
+                }
+            }
+            let polys_coeff = runs.iter().flat_map(|(oracle_index, indices)| {
+                oracles[*oracle_index].polynomial_chunks(indices).flatten()
             });
             let composition_poly = timed!(
                 timing,
@@ -1,12 +1,16 @@
-use itertools::Itertools;
+use std::ops::Range;
+
 use maybe_rayon::*;
 use plonky2_field::extension::{flatten, unflatten, Extendable};
+use plonky2_field::packable::Packable;
+use plonky2_field::packed::PackedField;
 use plonky2_field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use plonky2_util::reverse_index_bits_in_place;
 
 use crate::fri::proof::{FriInitialTreeProof, FriProof, FriQueryRound, FriQueryStep};
 use crate::fri::{FriConfig, FriParams};
 use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::hashing::{PlonkyPermutation, SPONGE_WIDTH};
 use crate::hash::merkle_tree::MerkleTree;
 use crate::iop::challenger::Challenger;
 use crate::plonk::config::{GenericConfig, Hasher};
@@ -114,27 +118,87 @@ where
     (trees, coeffs)
 }
 
+/// The number of proof-of-work witnesses tried by a thread before checking whether another thread
+/// found a valid one.
+const POW_CHUNK_SIZE: u64 = 1 << 12;
+
+/// Finds the smallest proof-of-work witness, as checked by the verifier.
+///
+/// When `C::InnerHasher` hashes `current_hash || witness` with a single overwrite-mode permutation
+/// of a fixed state in which only the witness changes, as the sponge of `hash_no_pad` does, this
+/// permutation is applied to `F::Packing::WIDTH` witnesses at once with `permute_packed`, which
+/// Poseidon vectorizes over the AVX-512 Goldilocks packing. The witness found this way is
+/// checked with `hash_no_pad`, and if the hasher doesn't match this shortcut, we grind with
+/// `hash_no_pad` itself instead.
 fn fri_proof_of_work<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     current_hash: HashOut<F>,
     config: &FriConfig,
 ) -> F {
-    (0..=F::NEG_ONE.to_canonical_u64())
-        .into_par_iter()
-        .find_any(|&i| {
-            C::InnerHasher::hash_no_pad(
-                &current_hash
-                    .elements
-                    .iter()
-                    .copied()
-                    .chain(Some(F::from_canonical_u64(i)))
-                    .collect_vec(),
-            )
-            .elements[0]
-                .to_canonical_u64()
-                .leading_zeros()
-                >= config.proof_of_work_bits + (64 - F::order().bits()) as u32
+    let min_leading_zeros = config.proof_of_work_bits + (64 - F::order().bits()) as u32;
+    let is_valid_output =
+        |output: F| output.to_canonical_u64().leading_zeros() >= min_leading_zeros;
+    let verifier_accepts = |witness: u64| {
+        let inputs = [
+            current_hash.elements.as_slice(),
+            &[F::from_canonical_u64(witness)],
+        ]
+        .concat();
+        is_valid_output(C::InnerHasher::hash_no_pad(&inputs).elements[0])
+    };
+
+    let mut initial_state = [<F as Packable>::Packing::ZEROS; SPONGE_WIDTH];
+    for (x, &h) in initial_state.iter_mut().zip(&current_hash.elements) {
+        *x = h.into();
+    }
+    let witness = find_smallest_pow_witness::<F>(|chunk| {
+        let width = <F as Packable>::Packing::WIDTH;
+        chunk.clone().step_by(width).find_map(|batch_start| {
+            let mut state = initial_state;
+            for (lane, x) in state[4].as_slice_mut().iter_mut().enumerate() {
+                // Lanes past the end of the chunk are filled with its last witness and ignored.
+                *x = F::from_canonical_u64((batch_start + lane as u64).min(chunk.end - 1));
+            }
+            let state = <C::InnerHasher as Hasher<F>>::Permutation::permute_packed(state);
+            (batch_start..chunk.end)
+                .zip(state[0].as_slice())
+                .find(|&(_, &output)| is_valid_output(output))
+                .map(|(witness, _)| witness)
+        })
+    });
+    if verifier_accepts(witness) {
+        return F::from_canonical_u64(witness);
+    }
+
+    // The hasher doesn't hash with a single overwrite-mode permutation.
+    let witness =
+        find_smallest_pow_witness::<F>(|chunk| chunk.into_iter().find(|&w| verifier_accepts(w)));
+    F::from_canonical_u64(witness)
+}
+
+/// Finds the smallest witness in `[0, F::ORDER)` for which `find_in_chunk` finds a valid witness
+/// in its chunk. Chunks are searched in rounds of one chunk per thread, and the smallest valid
+/// witness of the first successful round is returned, which doesn't depend on scheduling or on
+/// the number of threads.
+fn find_smallest_pow_witness<F: RichField>(
+    find_in_chunk: impl Fn(Range<u64>) -> Option<u64> + Sync,
+) -> u64 {
+    let num_witnesses = F::ORDER;
+    let round_size = POW_CHUNK_SIZE * current_num_threads() as u64;
+    (0..num_witnesses)
+        .step_by(round_size as usize)
+        .find_map(|round_start| {
+            (0..current_num_threads() as u64)
+                .into_par_iter()
+                .filter_map(|chunk| {
+                    let chunk_start = round_start + chunk * POW_CHUNK_SIZE;
+                    let chunk_end = (chunk_start + POW_CHUNK_SIZE).min(num_witnesses);
+                    if chunk_start >= chunk_end {
+                        return None;
+                    }
+                    find_in_chunk(chunk_start..chunk_end)
+                })
+                .min()
         })
-        .map(F::from_canonical_u64)
         .expect("Proof of work failed. This is highly unlikely!")
 }
 
@@ -193,3 +257,90 @@ fn fri_prover_query_round<
         steps: query_steps,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use plonky2_field::types::{Field, PrimeField64};
+
+    use super::*;
+    use crate::hash::poseidon::{PoseidonHash, PoseidonPermutation};
+    use crate::iop::target::{BoolTarget, Target};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{AlgebraicHasher, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+
+    fn check_fri_proof_of_work<C: GenericConfig<D>>() {
+        let config = FriConfig {
+            proof_of_work_bits: 10,
+            ..CircuitConfig::standard_recursion_config().fri_config
+        };
+        let current_hash = HashOut::<C::F>::rand();
+        let leading_zeros = |witness: u64| {
+            let inputs = [
+                current_hash.elements.as_slice(),
+                &[C::F::from_canonical_u64(witness)],
+            ]
+            .concat();
+            C::InnerHasher::hash_no_pad(&inputs).elements[0]
+                .to_canonical_u64()
+                .leading_zeros()
+        };
+
+        // The witness is the smallest valid one, as checked by the verifier.
+        let witness = fri_proof_of_work::<C::F, C, D>(current_hash, &config).to_canonical_u64();
+        assert!(leading_zeros(witness) >= config.proof_of_work_bits);
+        assert!((0..witness).all(|w| leading_zeros(w) < config.proof_of_work_bits));
+    }
+
+    #[test]
+    fn test_fri_proof_of_work() {
+        check_fri_proof_of_work::<PoseidonGoldilocksConfig>();
+    }
+
+    /// Poseidon, except that `hash_no_pad` pads its input, so that it doesn't hash
+    /// `current_hash || witness` with a single permutation.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    struct PaddedPoseidonHash;
+    impl Hasher<GoldilocksField> for PaddedPoseidonHash {
+        const HASH_SIZE: usize = 4 * 8;
+        type Hash = HashOut<GoldilocksField>;
+        type Permutation = PoseidonPermutation;
+
+        fn hash_no_pad(input: &[GoldilocksField]) -> Self::Hash {
+            PoseidonHash::hash_pad(input)
+        }
+
+        fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+            PoseidonHash::two_to_one(left, right)
+        }
+    }
+    impl AlgebraicHasher<GoldilocksField> for PaddedPoseidonHash {
+        fn permute_swapped<const D: usize>(
+            inputs: [Target; SPONGE_WIDTH],
+            swap: BoolTarget,
+            builder: &mut CircuitBuilder<GoldilocksField, D>,
+        ) -> [Target; SPONGE_WIDTH]
+        where
+            GoldilocksField: RichField + Extendable<D>,
+        {
+            PoseidonHash::permute_swapped(inputs, swap, builder)
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    struct PaddedPoseidonGoldilocksConfig;
+    impl GenericConfig<D> for PaddedPoseidonGoldilocksConfig {
+        type F = GoldilocksField;
+        type FE = <PoseidonGoldilocksConfig as GenericConfig<D>>::FE;
+        type Hasher = PoseidonHash;
+        type InnerHasher = PaddedPoseidonHash;
+    }
+
+    #[test]
+    fn test_fri_proof_of_work_without_single_permutation() {
+        check_fri_proof_of_work::<PaddedPoseidonGoldilocksConfig>();
+    }
+}
@@ -0,0 +1,265 @@
+//! Estimates of the soundness of FRI-based proofs, and selection of FRI parameters reaching a
+//! target security level.
+//!
+//! Two estimates are given. The conjectured one follows the ethSTARK conjecture: each query
+//! contributes `rate_bits` bits of security, grinding contributes `proof_of_work_bits` bits, and
+//! the total is capped by the size of the extension field relative to the LDE domain. This cap is
+//! stricter than the one `CircuitBuilder::new` enforces, which only caps the query security by the
+//! size of the extension field, so circuits with large domains may get a lower estimate than their
+//! target security; `CircuitBuilder::build` fails in that case, unless the config sets
+//! `allow_insufficient_fri_soundness`. The provable
+//! one follows the ethSTARK and "Proximity Gaps for Reed-Solomon Codes" analyses of DEEP-ALI and
+//! FRI in the list-decoding regime, up to the Johnson bound. It is much lower, and is mostly
+//! useful to compare configurations.
+
+use plonky2_field::extension::Extendable;
+use plonky2_field::types::Field;
+use plonky2_util::ceil_div_usize;
+
+use crate::fri::reduction_strategies::FriReductionStrategy;
+use crate::fri::{FriConfig, FriParams};
+use crate::hash::hash_types::RichField;
+
+/// The range of the Johnson bound proximity parameter `m` over which the provable soundness is
+/// optimized.
+const MIN_JOHNSON_PARAMETER: usize = 3;
+const MAX_JOHNSON_PARAMETER: usize = 100;
+
+/// The soundness of a FRI-based proof, in bits of security.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FriSoundness {
+    /// Security under the ethSTARK conjecture.
+    pub conjectured_bits: f64,
+    /// Proven security, in the list-decoding regime up to the Johnson bound.
+    pub provable_bits: f64,
+}
+
+impl FriParams {
+    /// The soundness of proofs using these parameters, with challenges drawn from the degree `D`
+    /// extension of `F`.
+    pub fn soundness<F: RichField + Extendable<D>, const D: usize>(&self) -> FriSoundness {
+        self.soundness_with_field_bits(F::Extension::order().bits() as f64)
+    }
+
+    /// The soundness of proofs using these parameters, with challenges drawn from a field of
+    /// `2^field_bits` elements.
+    pub fn soundness_with_field_bits(&self, field_bits: f64) -> FriSoundness {
+        FriSoundness {
+            conjectured_bits: self.conjectured_security_bits(field_bits),
+            provable_bits: (MIN_JOHNSON_PARAMETER..=MAX_JOHNSON_PARAMETER)
+                .map(|m| self.provable_security_bits(field_bits, m as f64))
+                .fold(0.0, f64::max),
+        }
+    }
+
+    fn conjectured_security_bits(&self, field_bits: f64) -> f64 {
+        let query_bits = self.config.query_security_bits() as f64;
+        query_bits.min(field_bits - self.lde_bits() as f64)
+    }
+
+    /// The provable security for the Johnson bound parameter `m`, i.e. when decoding up to a
+    /// distance of `1 - sqrt(rate) (1 + 1/2m)`.
+    fn provable_security_bits(&self, field_bits: f64, m: f64) -> f64 {
+        let log_field_size = field_bits;
+        let rate = self.config.rate();
+        let sqrt_rate = rate.sqrt();
+        let degree = (1u64 << self.degree_bits) as f64;
+        let lde_size = self.lde_size() as f64;
+        // Constraints have a degree of at most `2^rate_bits` times the trace degree.
+        let max_constraint_degree = (1u64 << self.config.rate_bits) as f64;
+        let num_openings = 2.0;
+
+        let list_size = (m + 0.5) / sqrt_rate;
+        let log_errors = [
+            // ALI: combining the constraints with random challenges.
+            list_size.log2() - log_field_size,
+            // DEEP: sampling the out-of-domain point `zeta`.
+            (list_size * (max_constraint_degree * (degree + num_openings - 1.0) + (degree - 1.0)))
+                .log2()
+                - (log_field_size - (lde_size + degree).log2()),
+            // FRI commit phase: batching the polynomials, then each folding step.
+            ((m + 0.5).powi(7) / (3.0 * rate.powf(1.5)) * lde_size * lde_size).log2()
+                - log_field_size,
+            ((2.0 * m + 1.0) * (lde_size + 1.0) / sqrt_rate
+                * self
+                    .reduction_arity_bits
+                    .iter()
+                    .map(|&arity_bits| (1u64 << arity_bits) as f64)
+                    .sum::<f64>())
+            .log2()
+                - log_field_size,
+            // FRI query phase.
+            self.config.num_query_rounds as f64 * (sqrt_rate * (1.0 + 0.5 / m)).log2()
+                - self.config.proof_of_work_bits as f64,
+        ];
+        -log_errors.iter().map(|e| e.exp2()).sum::<f64>().log2()
+    }
+}
+
+impl FriConfig {
+    /// A configuration reaching `security_bits` of conjectured query security, with as few query
+    /// rounds as possible given that at most `max_proof_of_work_bits` bits of grinding are used.
+    /// The grinding is then lowered to what the remaining security requires.
+    ///
+    /// The conjectured security is also capped by the size of the field relative to the LDE
+    /// domain, which this can't compensate for; see `FriParams::soundness`.
+    pub fn for_conjectured_security(
+        security_bits: usize,
+        rate_bits: usize,
+        cap_height: usize,
+        max_proof_of_work_bits: u32,
+        reduction_strategy: FriReductionStrategy,
+    ) -> Self {
+        assert!(rate_bits > 0, "FRI needs a rate below 1 to be sound");
+        let num_query_rounds = ceil_div_usize(
+            security_bits.saturating_sub(max_proof_of_work_bits as usize),
+            rate_bits,
+        );
+        let proof_of_work_bits = security_bits.saturating_sub(num_query_rounds * rate_bits) as u32;
+        Self {
+            rate_bits,
+            cap_height,
+            proof_of_work_bits,
+            reduction_strategy,
+            num_query_rounds,
+        }
+    }
+
+    /// The conjectured security of the query phase, ignoring the size of the field:
+    /// `rate_bits` bits per query round, plus the grinding.
+    pub fn query_security_bits(&self) -> usize {
+        self.num_query_rounds * self.rate_bits + self.proof_of_work_bits as usize
+    }
+
+    /// The same configuration with `proof_of_work_bits` bits of grinding, and as few query rounds
+    /// as keep the conjectured query security at least as high. More grinding makes proofs smaller
+    /// and cheaper to verify, at the cost of proving time.
+    ///
+    /// Panics if the grinding alone would reach the query security, which would leave no query
+    /// rounds.
+    pub fn with_proof_of_work_bits(&self, proof_of_work_bits: u32) -> Self {
+        let num_query_rounds = ceil_div_usize(
+            self.query_security_bits()
+                .saturating_sub(proof_of_work_bits as usize),
+            self.rate_bits,
+        );
+        assert!(
+            num_query_rounds > 0,
+            "{proof_of_work_bits} bits of grinding would leave no FRI query rounds"
+        );
+        Self {
+            proof_of_work_bits,
+            num_query_rounds,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_standard_config_soundness() {
+        let config = CircuitConfig::standard_recursion_config();
+        let params = config.fri_config.fri_params(12, false);
+        let soundness = params.soundness::<F, D>();
+        assert_eq!(soundness.conjectured_bits, 100.0);
+        assert!(soundness.provable_bits > 0.0);
+        assert!(soundness.provable_bits < soundness.conjectured_bits);
+
+        // More queries only ever help.
+        let mut more_queries = params;
+        more_queries.config.num_query_rounds *= 2;
+        let more_soundness = more_queries.soundness::<F, D>();
+        assert!(more_soundness.provable_bits > soundness.provable_bits);
+
+        // A large enough domain makes the field the bottleneck.
+        let large_params = config.fri_config.fri_params(40, false);
+        assert_eq!(large_params.soundness::<F, D>().conjectured_bits, 85.0);
+    }
+
+    #[test]
+    fn test_for_conjectured_security() {
+        let standard_config = CircuitConfig::standard_recursion_config().fri_config;
+        let config = FriConfig::for_conjectured_security(
+            100,
+            standard_config.rate_bits,
+            standard_config.cap_height,
+            16,
+            standard_config.reduction_strategy.clone(),
+        );
+        assert_eq!(config, standard_config);
+
+        // Grinding is lowered when a round number of queries leaves some slack.
+        let config = FriConfig::for_conjectured_security(
+            100,
+            7,
+            4,
+            20,
+            FriReductionStrategy::ConstantArityBits(4, 5),
+        );
+        assert_eq!(config.num_query_rounds, 12);
+        assert_eq!(config.proof_of_work_bits, 16);
+        assert_eq!(
+            config
+                .fri_params(12, false)
+                .soundness::<F, D>()
+                .conjectured_bits,
+            100.0
+        );
+    }
+
+    #[test]
+    fn test_with_proof_of_work_bits() {
+        let config = CircuitConfig::standard_recursion_config().fri_config;
+        let more_grinding = config.with_proof_of_work_bits(22);
+        assert_eq!(more_grinding.num_query_rounds, 26);
+        assert_eq!(more_grinding.with_proof_of_work_bits(16), config);
+
+        // Grinding is never traded for a loss of security.
+        let less_grinding = config.with_proof_of_work_bits(15);
+        assert_eq!(less_grinding.num_query_rounds, 29);
+        let params = less_grinding.fri_params(12, false);
+        assert!(params.soundness::<F, D>().conjectured_bits >= 100.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "would leave no FRI query rounds")]
+    fn test_with_proof_of_work_bits_without_queries() {
+        let config = CircuitConfig::standard_recursion_config().fri_config;
+        config.with_proof_of_work_bits(100);
+    }
+
+    #[test]
+    #[should_panic(expected = "FRI params fall short of target security")]
+    fn test_insecure_config() {
+        let mut config = CircuitConfig::standard_recursion_config();
+        config.fri_config.num_query_rounds = 20;
+        CircuitBuilder::<F, D>::new(config);
+    }
+
+    #[test]
+    #[should_panic(expected = "FRI soundness falls short of target security")]
+    fn test_large_domain_fails_build() {
+        // With an LDE domain of at least 2^29 points, the estimate capped by the domain falls below
+        // the target, although the config passes the check in `CircuitBuilder::new`, which only
+        // caps by the size of the extension field.
+        let mut config = CircuitConfig::standard_recursion_config();
+        config.fri_config.rate_bits = 28;
+        let params = config.fri_config.fri_params(1, false);
+        assert!(params.soundness::<F, D>().conjectured_bits < config.security_bits as f64);
+        assert!(config.fri_config.query_security_bits() >= config.security_bits);
+        let builder = CircuitBuilder::<F, D>::new(config);
+        builder.build::<C>();
+    }
+}
@@ -10,6 +10,7 @@ use crate::iop::generator::{GeneratedValues, SimpleGenerator};
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness};
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::util::serialization::{Buffer, IoResult};
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Computes `-x`.
@@ -364,7 +365,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 }
 
 #[derive(Debug)]
-struct EqualityGenerator {
+pub struct EqualityGenerator {
     x: Target,
     y: Target,
     equal: BoolTarget,
@@ -385,6 +386,21 @@ impl<F: RichField> SimpleGenerator<F> for EqualityGenerator {
         out_buffer.set_bool_target(self.equal, x == y);
         out_buffer.set_target(self.inv, inv);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.x)?;
+        dst.write_target(self.y)?;
+        dst.write_bool_target(self.equal)?;
+        dst.write_target(self.inv)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let x = src.read_target()?;
+        let y = src.read_target()?;
+        let equal = src.read_bool_target()?;
+        let inv = src.read_target()?;
+        Ok(Self { x, y, equal, inv })
+    }
 }
 
 /// Represents a base arithmetic operation in the circuit. Used to memoize results.
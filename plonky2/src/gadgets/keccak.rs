@@ -0,0 +1,227 @@
+use plonky2_field::extension::Extendable;
+
+use crate::hash::hash_types::RichField;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// The Keccak-256 rate, in 64-bit lanes.
+const KECCAK_RATE_LANES: usize = 17;
+
+const KECCAK_ROUNDS: usize = 24;
+
+const KECCAK_ROUND_CONSTANTS: [u64; KECCAK_ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Rotation offsets of the rho step, indexed by `x + 5 * y`.
+const KECCAK_ROTATIONS: [usize; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// A 64-bit lane, as little-endian bits. Each bit `b` is represented by the target `(-1)^b`, so
+/// that xor is a multiplication.
+pub type SignedLane = [Target; 64];
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Computes the Keccak-256 hash of the canonical little-endian encoding of `inputs`, as
+    /// `KeccakHash::hash_no_pad` does. The hash is returned as eight little-endian 32-bit words.
+    ///
+    /// To hash bytes, see `CircuitBuilderKeccak256` in `plonky2_u32`.
+    pub fn keccak256_field_elements(&mut self, inputs: &[Target]) -> [Target; 8] {
+        let one = self.one();
+        let minus_one = self.neg_one();
+        let mut lanes = inputs
+            .iter()
+            .map(|&x| self.canonical_signed_lane(x))
+            .collect::<Vec<_>>();
+
+        // Keccak padding: a one bit after the message, and a one bit at the end of the last block.
+        let num_blocks = inputs.len() / KECCAK_RATE_LANES + 1;
+        lanes.resize(num_blocks * KECCAK_RATE_LANES, [one; 64]);
+        lanes[inputs.len()][0] = minus_one;
+        let last_lane = lanes.last_mut().unwrap();
+        last_lane[63] = self.mul(last_lane[63], minus_one);
+
+        let mut state = [[one; 64]; 25];
+        for block in lanes.chunks(KECCAK_RATE_LANES) {
+            for (state_lane, block_lane) in state.iter_mut().zip(block) {
+                for (s, &b) in state_lane.iter_mut().zip(block_lane) {
+                    *s = self.mul(*s, b);
+                }
+            }
+            self.keccak_f(&mut state);
+        }
+
+        let mut words = Vec::with_capacity(8);
+        for lane in &state[..4] {
+            for half in lane.chunks(32) {
+                words.push(self.signed_bits_to_integer(half));
+            }
+        }
+        words.try_into().unwrap()
+    }
+
+    /// Splits `x` into the signed bits of its canonical 64-bit representation.
+    fn canonical_signed_lane(&mut self, x: Target) -> SignedLane {
+        let bits = self.split_le(x, 64);
+        self.assert_bits_le_constant(&bits, F::ORDER - 1);
+        let one = self.one();
+        bits.into_iter()
+            .map(|b| self.arithmetic(-F::TWO, F::ONE, b.target, one, one))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    }
+
+    /// Asserts that the integer with little-endian bits `bits` is at most `c`.
+    fn assert_bits_le_constant(&mut self, bits: &[BoolTarget], c: u64) {
+        // Scanning from the most significant bit, `prefix_equal` is one while the bits of the
+        // integer match those of `c`. At the first mismatch, the integer's bit must be zero.
+        let mut prefix_equal = self._true();
+        for (i, &b) in bits.iter().enumerate().rev() {
+            if (c >> i) & 1 == 1 {
+                prefix_equal = self.and(prefix_equal, b);
+            } else {
+                let exceeds = self.and(prefix_equal, b);
+                self.assert_zero(exceeds.target);
+            }
+        }
+    }
+
+    /// Recomposes an integer from its little-endian signed bits.
+    pub fn signed_bits_to_integer(&mut self, bits: &[Target]) -> Target {
+        // Each bit is `(1 - s) / 2` for its signed representation `s`.
+        let half = F::TWO.inverse();
+        let max = F::from_canonical_u64((1 << bits.len()) - 1);
+        let mut acc = self.constant(max * half);
+        for (i, &s) in bits.iter().enumerate() {
+            acc = self.mul_const_add(-F::from_canonical_u64(1 << i) * half, s, acc);
+        }
+        acc
+    }
+
+    /// Applies the Keccak-f[1600] permutation to a state of signed lanes, indexed by `x + 5 * y`.
+    pub fn keccak_f(&mut self, state: &mut [SignedLane; 25]) {
+        for &round_constant in &KECCAK_ROUND_CONSTANTS {
+            // Theta.
+            let c: [SignedLane; 5] =
+                std::array::from_fn(|x| self.xor_lanes(&[0, 1, 2, 3, 4].map(|y| state[x + 5 * y])));
+            for x in 0..5 {
+                let d = self.xor_lanes(&[c[(x + 4) % 5], rotate_left(&c[(x + 1) % 5], 1)]);
+                for y in 0..5 {
+                    state[x + 5 * y] = self.xor_lanes(&[state[x + 5 * y], d]);
+                }
+            }
+
+            // Rho and pi.
+            let mut b = *state;
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                        rotate_left(&state[x + 5 * y], KECCAK_ROTATIONS[x + 5 * y]);
+                }
+            }
+
+            // Chi, with iota folded into the first lane.
+            for x in 0..5 {
+                for y in 0..5 {
+                    for i in 0..64 {
+                        let flip = x == 0 && y == 0 && (round_constant >> i) & 1 == 1;
+                        state[x + 5 * y][i] = self.keccak_chi_bit(
+                            b[x + 5 * y][i],
+                            b[(x + 1) % 5 + 5 * y][i],
+                            b[(x + 2) % 5 + 5 * y][i],
+                            flip,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn xor_lanes(&mut self, lanes: &[SignedLane]) -> SignedLane {
+        std::array::from_fn(|i| {
+            lanes[1..]
+                .iter()
+                .fold(lanes[0][i], |acc, lane| self.mul(acc, lane[i]))
+        })
+    }
+
+    /// Computes `a ^ (!b & c)`, negated if `flip` is set, on signed bits.
+    fn keccak_chi_bit(&mut self, a: Target, b: Target, c: Target, flip: bool) -> Target {
+        // The signed bit of `!b & c` is `(1 - s_b + s_c + s_b s_c) / 2`.
+        let half = F::TWO.inverse();
+        let sign = if flip { F::NEG_ONE } else { F::ONE };
+        let diff = self.sub(c, b);
+        let v = self.arithmetic(half, half, b, c, diff);
+        self.arithmetic(sign, sign * half, a, v, a)
+    }
+}
+
+fn rotate_left(lane: &SignedLane, n: usize) -> SignedLane {
+    std::array::from_fn(|i| lane[(i + 64 - n) % 64])
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::types::Field;
+
+    use crate::hash::keccak::KeccakHash;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, Hasher, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_keccak256_field_elements() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        // Cover padding within a single lane, and an input spanning two blocks.
+        for len in [16, 20] {
+            let inputs = F::rand_vec(len);
+            let input_targets = builder.add_virtual_targets(len);
+            for (&t, &x) in input_targets.iter().zip(&inputs) {
+                pw.set_target(t, x);
+            }
+            let hash = builder.keccak256_field_elements(&input_targets);
+
+            let expected = KeccakHash::<32>::hash_no_pad(&inputs);
+            for (&word, bytes) in hash.iter().zip(expected.0.chunks(4)) {
+                let expected_word = u32::from_le_bytes(bytes.try_into().unwrap());
+                let expected_word = builder.constant(F::from_canonical_u32(expected_word));
+                builder.connect(word, expected_word);
+            }
+        }
+
+        let data = builder.build::<C>();
+        assert_eq!(data.mock_prove(pw), vec![]);
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use plonky2_field::extension::Extendable;
+
+use crate::gates::lookup::{LookupGate, LookupGenerator};
+use crate::gates::lookup_table::LookupTable;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Registers a lookup table, and returns its index, to be used in `lookup`. Tables which are
+    /// never looked into don't take any space in the circuit.
+    pub fn add_lookup_table(&mut self, table: LookupTable) -> usize {
+        let outputs = table.iter().copied().collect::<HashMap<_, _>>();
+        assert_eq!(
+            outputs.len(),
+            table.len(),
+            "Lookup table inputs must be distinct."
+        );
+        assert!(!table.is_empty(), "Lookup table must not be empty.");
+
+        self.lookup_tables.push(table);
+        self.lookup_inputs.push(Vec::new());
+        self.lookup_outputs.push(Arc::new(outputs));
+        self.lookup_tables.len() - 1
+    }
+
+    /// Returns the output associated with `input` in the lookup table with index `table_index`,
+    /// and constrains `input` to be one of the table's inputs.
+    pub fn lookup(&mut self, table_index: usize, input: Target) -> Target {
+        let outputs = self
+            .lookup_outputs
+            .get(table_index)
+            .unwrap_or_else(|| panic!("No lookup table with index {table_index}."))
+            .clone();
+
+        let gate = LookupGate::new_from_config(&self.config);
+        let index = F::from_canonical_usize(table_index);
+        let (row, slot) = self.find_slot(gate, &[index], &[index]);
+
+        let looking_input = Target::wire(row, LookupGate::wire_ith_looking_inp(slot));
+        let looking_output = Target::wire(row, LookupGate::wire_ith_looking_out(slot));
+        self.connect(input, looking_input);
+        self.add_simple_generator(LookupGenerator {
+            input: looking_input,
+            output: looking_output,
+            outputs,
+        });
+        self.lookup_inputs[table_index].push(looking_input);
+
+        looking_output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use crate::gates::lookup::LookupGate;
+    use crate::gates::lookup_table::LookupTableGate;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::prover::{prove_with_witness, ProverOptions, ProverPrecomputation};
+    use crate::plonk::verifier::verify;
+    use crate::util::timing::TimingTree;
+
+    #[test]
+    fn test_lookup() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let square_table = Arc::new((0..256).map(|i| (i, i * i)).collect::<Vec<_>>());
+        let square_index = builder.add_lookup_table(square_table);
+        let xor_table = Arc::new(
+            (0..256u16)
+                .map(|i| (i, (i >> 4) ^ (i & 0xf)))
+                .collect::<Vec<_>>(),
+        );
+        let xor_index = builder.add_lookup_table(xor_table);
+
+        let xs = builder.add_virtual_targets(100);
+        for (i, &x) in xs.iter().enumerate() {
+            let square = builder.lookup(square_index, x);
+            let expected = builder.constant(F::from_canonical_usize((i % 256) * (i % 256)));
+            builder.connect(square, expected);
+
+            let xor = builder.lookup(xor_index, x);
+            let expected = builder.constant(F::from_canonical_usize((i >> 4) ^ (i & 0xf)));
+            builder.connect(xor, expected);
+        }
+
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        for (i, &x) in xs.iter().enumerate() {
+            pw.set_target(x, F::from_canonical_usize(i));
+        }
+        let proof = data.prove(pw)?;
+
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_lookup_missing_input() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let table = Arc::new((0..16).map(|i| (i, 15 - i)).collect::<Vec<_>>());
+        let table_index = builder.add_lookup_table(table);
+        let x = builder.add_virtual_target();
+        builder.lookup(table_index, x);
+
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_usize(16));
+        data.prove(pw).unwrap();
+    }
+
+    /// A prover may not use the unused slots of the last `LookupTableGate` to add entries to the
+    /// table.
+    #[test]
+    fn test_lookup_tampered_unused_slot() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let table = Arc::new((0..3).map(|i| (i, 2 * i)).collect::<Vec<_>>());
+        let table_gate = LookupTableGate::new_from_config(&builder.config);
+        assert!(table.len() < table_gate.num_slots);
+        let table_index = builder.add_lookup_table(table);
+        let x = builder.add_virtual_target();
+        let y = builder.lookup(table_index, x);
+
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ZERO);
+        let partition_witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        let public_inputs = partition_witness.get_targets(&data.prover_only.public_inputs);
+        let mut witness = partition_witness.full_witness();
+
+        let num_selectors = data.common.selectors_info.num_selectors();
+        let constants = data.prover_only.constants_values(&data.common);
+        let table_row = (0..data.common.degree())
+            .find(|&row| {
+                let selectors = constants[..num_selectors]
+                    .iter()
+                    .map(|c| c.values[row])
+                    .collect::<Vec<_>>();
+                let gate = &data.common.gates[data.common.gate_index(&selectors)];
+                gate.0.as_any().is::<LookupTableGate>()
+            })
+            .unwrap();
+        let Target::Wire(y_wire) = y else {
+            panic!("Expected a wire")
+        };
+
+        // Look up (100, 200), which is not in the table, and move its multiplicity from the first
+        // entry to the last, unused slot, set to (100, 200). The lookup argument still balances.
+        let unused_slot = table_gate.num_slots - 1;
+        let looking_inp = LookupGate::wire_ith_looking_inp(0);
+        assert_eq!(y_wire.column, LookupGate::wire_ith_looking_out(0));
+        let first_multiplicity = table_gate.wire_ith_multiplicity(0);
+        let wires = &mut witness.wire_values;
+        wires[looking_inp][y_wire.row] = F::from_canonical_u64(100);
+        wires[y_wire.column][y_wire.row] = F::from_canonical_u64(200);
+        wires[first_multiplicity][table_row] -= F::ONE;
+        wires[LookupTableGate::wire_ith_looked_inp(unused_slot)][table_row] =
+            F::from_canonical_u64(100);
+        wires[LookupTableGate::wire_ith_looked_out(unused_slot)][table_row] =
+            F::from_canonical_u64(200);
+        wires[table_gate.wire_ith_multiplicity(unused_slot)][table_row] = F::ONE;
+
+        let precomputation = ProverPrecomputation::new(&data.prover_only, &data.common);
+        let proof = prove_with_witness::<F, C, D>(
+            &data.prover_only,
+            &data.common,
+            &precomputation,
+            public_inputs,
+            witness,
+            &ProverOptions::default(),
+            &mut TimingTree::default(),
+        )?;
+        assert!(verify(proof, &data.verifier_only, &data.common).is_err());
+
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use crate::iop::generator::{GeneratedValues, SimpleGenerator};
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness};
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::util::serialization::{Buffer, IoResult};
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Split the given integer into a list of wires, where each one represents a
@@ -54,7 +55,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 }
 
 #[derive(Debug)]
-struct SplitGenerator {
+pub struct SplitGenerator {
     integer: Target,
     bits: Vec<Target>,
 }
@@ -78,10 +79,21 @@ impl<F: RichField> SimpleGenerator<F> for SplitGenerator {
             "Integer too large to fit in given number of bits"
         );
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.integer)?;
+        dst.write_target_vec(&self.bits)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let integer = src.read_target()?;
+        let bits = src.read_target_vec()?;
+        Ok(Self { integer, bits })
+    }
 }
 
 #[derive(Debug)]
-struct WireSplitGenerator {
+pub struct WireSplitGenerator {
     integer: Target,
     gates: Vec<usize>,
     num_limbs: usize,
@@ -118,4 +130,21 @@ impl<F: RichField> SimpleGenerator<F> for WireSplitGenerator {
             self.gates.len()
         );
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.integer)?;
+        dst.write_usize_vec(&self.gates)?;
+        dst.write_usize(self.num_limbs)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let integer = src.read_target()?;
+        let gates = src.read_usize_vec()?;
+        let num_limbs = src.read_usize()?;
+        Ok(Self {
+            integer,
+            gates,
+            num_limbs,
+        })
+    }
 }
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use plonky2_field::extension::Extendable;
+
+use crate::gates::gate::Gate;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
+use crate::util::serialization::{Buffer, IoResult};
+
+/// A gate holding `num_slots` lookups into a lookup table, as `(input, output)` pairs of routed
+/// wires. The gate has no constraints of its own: the pairs are checked against the table by the
+/// lookup argument in the vanishing polynomial, which is enabled on this gate's rows by a
+/// dedicated lookup selector. The gate's only constant is the index of the table being used.
+#[derive(Copy, Clone, Debug)]
+pub struct LookupGate {
+    /// Number of lookups per gate.
+    pub num_slots: usize,
+}
+
+impl LookupGate {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_slots: Self::num_slots(config),
+        }
+    }
+
+    /// The number of lookup slots per row, shared by `LookupGate` and `LookupTableGate` so that
+    /// the lookup argument sees the same wire layout on both.
+    pub(crate) fn num_slots(config: &CircuitConfig) -> usize {
+        // Each slot needs two routed wires, plus one (non-routed) multiplicity wire in the
+        // `LookupTableGate`.
+        (config.num_routed_wires / 2).min(config.num_wires / 3)
+    }
+
+    pub fn wire_ith_looking_inp(i: usize) -> usize {
+        2 * i
+    }
+
+    pub fn wire_ith_looking_out(i: usize) -> usize {
+        2 * i + 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for LookupGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.num_slots)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let num_slots = src.read_usize()?;
+        Ok(Self { num_slots })
+    }
+
+    fn eval_unfiltered(&self, _vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        // Lookups are checked by the lookup argument, see `vanishing_poly.rs`.
+        Vec::new()
+    }
+
+    fn eval_unfiltered_base_batch(&self, _vars: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        Vec::new()
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        _builder: &mut CircuitBuilder<F, D>,
+        _vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        Vec::new()
+    }
+
+    fn generators(&self, _row: usize, _local_constants: &[F]) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        // The outputs depend on the table, which isn't part of the gate, so the generators are
+        // added by `CircuitBuilder::lookup` instead.
+        Vec::new()
+    }
+
+    fn num_wires(&self) -> usize {
+        2 * self.num_slots
+    }
+
+    fn num_constants(&self) -> usize {
+        1
+    }
+
+    fn degree(&self) -> usize {
+        1
+    }
+
+    fn num_constraints(&self) -> usize {
+        0
+    }
+
+    fn num_ops(&self) -> usize {
+        self.num_slots
+    }
+}
+
+/// Sets the output of a lookup to the table entry matching its input.
+#[derive(Clone, Debug)]
+pub struct LookupGenerator {
+    pub(crate) input: Target,
+    pub(crate) output: Target,
+    /// The output of each input of the table, shared by all the lookups into the table.
+    pub(crate) outputs: Arc<HashMap<u16, u16>>,
+}
+
+impl<F: RichField> SimpleGenerator<F> for LookupGenerator {
+    fn dependencies(&self) -> Vec<Target> {
+        vec![self.input]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let input = witness.get_target(self.input).to_canonical_u64();
+        let output = u16::try_from(input)
+            .ok()
+            .and_then(|input| self.outputs.get(&input))
+            .unwrap_or_else(|| panic!("Input {input} is not in the lookup table."));
+        out_buffer.set_target(self.output, F::from_canonical_u16(*output));
+    }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.input)?;
+        dst.write_target(self.output)?;
+        let mut table = self
+            .outputs
+            .iter()
+            .map(|(&inp, &out)| (inp, out))
+            .collect::<Vec<_>>();
+        table.sort_unstable();
+        dst.write_lookup_table(&table)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let input = src.read_target()?;
+        let output = src.read_target()?;
+        let outputs = Arc::new(src.read_lookup_table()?.into_iter().collect());
+        Ok(Self {
+            input,
+            output,
+            outputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::lookup::LookupGate;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn low_degree() {
+        let gate = LookupGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> anyhow::Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = LookupGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
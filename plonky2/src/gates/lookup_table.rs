@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use plonky2_field::extension::Extendable;
+
+use crate::gates::gate::Gate;
+use crate::gates::lookup::LookupGate;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
+use crate::util::serialization::{Buffer, IoResult};
+
+/// A lookup table, mapping 16-bit inputs to 16-bit outputs. Inputs must be distinct.
+pub type LookupTable = Arc<Vec<(u16, u16)>>;
+
+/// A gate holding `num_slots` entries of a lookup table, as `(input, output)` pairs of routed
+/// wires, along with the number of times each entry is looked up. The entries are fixed by copy
+/// constraints to constant wires, so they are bound by the circuit's preprocessed polynomials.
+/// As for `LookupGate`, the gate has no constraints of its own, and its only constant is the
+/// index of the table.
+#[derive(Copy, Clone, Debug)]
+pub struct LookupTableGate {
+    /// Number of table entries per gate.
+    pub num_slots: usize,
+}
+
+impl LookupTableGate {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_slots: LookupGate::num_slots(config),
+        }
+    }
+
+    pub fn wire_ith_looked_inp(i: usize) -> usize {
+        2 * i
+    }
+
+    pub fn wire_ith_looked_out(i: usize) -> usize {
+        2 * i + 1
+    }
+
+    pub fn wire_ith_multiplicity(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_slots);
+        2 * self.num_slots + i
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for LookupTableGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.num_slots)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let num_slots = src.read_usize()?;
+        Ok(Self { num_slots })
+    }
+
+    fn eval_unfiltered(&self, _vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        // Table entries are checked by the lookup argument, see `vanishing_poly.rs`.
+        Vec::new()
+    }
+
+    fn eval_unfiltered_base_batch(&self, _vars: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        Vec::new()
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        _builder: &mut CircuitBuilder<F, D>,
+        _vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        Vec::new()
+    }
+
+    fn generators(&self, _row: usize, _local_constants: &[F]) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        // Multiplicities depend on every lookup into the table, so they are computed by a single
+        // `LookupTableGenerator` per table, added in `CircuitBuilder::build`.
+        Vec::new()
+    }
+
+    fn num_wires(&self) -> usize {
+        3 * self.num_slots
+    }
+
+    fn num_constants(&self) -> usize {
+        1
+    }
+
+    fn degree(&self) -> usize {
+        1
+    }
+
+    fn num_constraints(&self) -> usize {
+        0
+    }
+}
+
+/// Counts how many times each entry of a lookup table is looked up, and sets the multiplicity
+/// wires of the table's `LookupTableGate`s accordingly.
+#[derive(Clone, Debug)]
+pub struct LookupTableGenerator {
+    pub(crate) table: LookupTable,
+    /// The input wires of every lookup into the table.
+    pub(crate) looking_inputs: Vec<Target>,
+    /// The multiplicity wire of each table entry, in table order.
+    pub(crate) multiplicities: Vec<Target>,
+}
+
+impl<F: RichField> SimpleGenerator<F> for LookupTableGenerator {
+    fn dependencies(&self) -> Vec<Target> {
+        self.looking_inputs.clone()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let entry_indices = self
+            .table
+            .iter()
+            .enumerate()
+            .map(|(i, &(inp, _))| (inp as u64, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut counts = vec![0usize; self.table.len()];
+        for &input in &self.looking_inputs {
+            let input = witness.get_target(input).to_canonical_u64();
+            let i = entry_indices
+                .get(&input)
+                .unwrap_or_else(|| panic!("Input {input} is not in the lookup table."));
+            counts[*i] += 1;
+        }
+
+        for (&m, count) in self.multiplicities.iter().zip(counts) {
+            out_buffer.set_target(m, F::from_canonical_usize(count));
+        }
+    }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_lookup_table(&self.table)?;
+        dst.write_target_vec(&self.looking_inputs)?;
+        dst.write_target_vec(&self.multiplicities)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let table = Arc::new(src.read_lookup_table()?);
+        let looking_inputs = src.read_target_vec()?;
+        let multiplicities = src.read_target_vec()?;
+        Ok(Self {
+            table,
+            looking_inputs,
+            multiplicities,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::lookup_table::LookupTableGate;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn low_degree() {
+        let gate = LookupTableGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> anyhow::Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = LookupTableGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
@@ -20,6 +20,7 @@ use crate::plonk::vars::{
     EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
     EvaluationVarsBasePacked,
 };
+use crate::util::serialization::{Buffer, IoResult};
 
 /// A gate for checking that a particular element of a list matches a given value.
 #[derive(Copy, Clone, Debug)]
@@ -118,6 +119,19 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for RandomAccessGa
         format!("{self:?}<D={D}>")
     }
 
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.bits)?;
+        dst.write_usize(self.num_copies)?;
+        dst.write_usize(self.num_extra_constants)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let bits = src.read_usize()?;
+        let num_copies = src.read_usize()?;
+        let num_extra_constants = src.read_usize()?;
+        Ok(Self::new(num_copies, bits, num_extra_constants))
+    }
+
     fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
         let mut constraints = Vec::with_capacity(self.num_constraints());
 
@@ -322,7 +336,7 @@ impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D>
 }
 
 #[derive(Debug)]
-struct RandomAccessGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub struct RandomAccessGenerator<F: RichField + Extendable<D>, const D: usize> {
     row: usize,
     gate: RandomAccessGate<F, D>,
     copy: usize,
@@ -372,6 +386,27 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
             set_local_wire(self.gate.wire_bit(i, copy), bit);
         }
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.gate.bits)?;
+        dst.write_usize(self.gate.num_copies)?;
+        dst.write_usize(self.gate.num_extra_constants)?;
+        dst.write_usize(self.copy)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let bits = src.read_usize()?;
+        let num_copies = src.read_usize()?;
+        let num_extra_constants = src.read_usize()?;
+        let copy = src.read_usize()?;
+        Ok(Self {
+            row,
+            gate: RandomAccessGate::new(num_copies, bits, num_extra_constants),
+            copy,
+        })
+    }
 }
 
 #[cfg(test)]
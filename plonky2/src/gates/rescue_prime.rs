@@ -0,0 +1,447 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension::Extendable;
+use plonky2_field::types::Field;
+
+use crate::gates::gate::Gate;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::hash::hashing::SPONGE_WIDTH;
+use crate::hash::poseidon::Poseidon;
+use crate::hash::rescue_prime;
+use crate::hash::rescue_prime::CustomRescuePrime;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use crate::util::serialization::{Buffer, IoResult};
+
+/// Evaluates a full permutation of our custom Rescue-Prime instance with 12 state elements.
+///
+/// The inverse S-box `x^(1/7)` has a huge degree, so for each round we store its outputs `y` as
+/// wires and check `y^7` against its input instead. Like `PoseidonGate`, this has a flag which can
+/// be used to swap the first four inputs with the next four, for ordering sibling digests in Merkle
+/// proofs.
+#[derive(Debug)]
+pub struct CustomRescuePrimeGate<F: RichField + Extendable<D> + CustomRescuePrime, const D: usize> {
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D> + CustomRescuePrime, const D: usize> CustomRescuePrimeGate<F, D> {
+    pub fn new() -> Self {
+        CustomRescuePrimeGate {
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The wire index for the `i`th input to the permutation.
+    pub fn wire_input(i: usize) -> usize {
+        i
+    }
+
+    /// The wire index for the `i`th output to the permutation.
+    pub fn wire_output(i: usize) -> usize {
+        SPONGE_WIDTH + i
+    }
+
+    /// If this is set to 1, the first four inputs will be swapped with the next four inputs. This
+    /// is useful for ordering hashes in Merkle proofs. Otherwise, this should be set to 0.
+    pub const WIRE_SWAP: usize = 2 * SPONGE_WIDTH;
+
+    const START_DELTA: usize = 2 * SPONGE_WIDTH + 1;
+
+    /// A wire which stores `swap * (input[i + 4] - input[i])`; used to compute the swapped inputs.
+    fn wire_delta(i: usize) -> usize {
+        assert!(i < 4);
+        Self::START_DELTA + i
+    }
+
+    const START_INV_SBOX: usize = Self::START_DELTA + 4;
+
+    /// A wire which stores the output of the `i`-th inverse S-box of the `round`-th round.
+    fn wire_inv_sbox_output(round: usize, i: usize) -> usize {
+        debug_assert!(round < rescue_prime::N_ROUNDS);
+        debug_assert!(i < SPONGE_WIDTH);
+        Self::START_INV_SBOX + SPONGE_WIDTH * round + i
+    }
+
+    /// End of wire indices, exclusive.
+    fn end() -> usize {
+        Self::START_INV_SBOX + SPONGE_WIDTH * rescue_prime::N_ROUNDS
+    }
+}
+
+impl<F: RichField + Extendable<D> + CustomRescuePrime, const D: usize> Gate<F, D>
+    for CustomRescuePrimeGate<F, D>
+{
+    fn id(&self) -> String {
+        format!("{self:?}<WIDTH={SPONGE_WIDTH}>")
+    }
+
+    fn serialize(&self, _dst: &mut Buffer) -> IoResult<()> {
+        Ok(())
+    }
+
+    fn deserialize(_src: &mut Buffer) -> IoResult<Self> {
+        Ok(Self::new())
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(swap * (swap - F::Extension::ONE));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            constraints.push(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::Extension::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        for r in 0..rescue_prime::N_ROUNDS {
+            <F as Poseidon>::sbox_layer_field(&mut state);
+            state = <F as Poseidon>::mds_layer_field(&state);
+            <F as CustomRescuePrime>::rescue_constant_layer_field(&mut state, 2 * r);
+
+            for i in 0..SPONGE_WIDTH {
+                let inv_sbox_out = vars.local_wires[Self::wire_inv_sbox_output(r, i)];
+                constraints.push(<F as Poseidon>::sbox_monomial(inv_sbox_out) - state[i]);
+                state[i] = inv_sbox_out;
+            }
+            state = <F as Poseidon>::mds_layer_field(&state);
+            <F as CustomRescuePrime>::rescue_constant_layer_field(&mut state, 2 * r + 1);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            constraints.push(state[i] - vars.local_wires[Self::wire_output(i)]);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        vars: EvaluationVarsBase<F>,
+        mut yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        yield_constr.one(swap * swap.sub_one());
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            yield_constr.one(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        for r in 0..rescue_prime::N_ROUNDS {
+            <F as Poseidon>::sbox_layer(&mut state);
+            state = <F as Poseidon>::mds_layer(&state);
+            <F as CustomRescuePrime>::rescue_constant_layer_field(&mut state, 2 * r);
+
+            for i in 0..SPONGE_WIDTH {
+                let inv_sbox_out = vars.local_wires[Self::wire_inv_sbox_output(r, i)];
+                yield_constr.one(<F as Poseidon>::sbox_monomial(inv_sbox_out) - state[i]);
+                state[i] = inv_sbox_out;
+            }
+            state = <F as Poseidon>::mds_layer(&state);
+            <F as CustomRescuePrime>::rescue_constant_layer_field(&mut state, 2 * r + 1);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            yield_constr.one(state[i] - vars.local_wires[Self::wire_output(i)]);
+        }
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(builder.mul_sub_extension(swap, swap, swap));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let diff = builder.sub_extension(input_rhs, input_lhs);
+            constraints.push(builder.mul_sub_extension(swap, diff, delta_i));
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [builder.zero_extension(); SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            state[i] = builder.add_extension(input_lhs, delta_i);
+            state[i + 4] = builder.sub_extension(input_rhs, delta_i);
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        for r in 0..rescue_prime::N_ROUNDS {
+            <F as Poseidon>::sbox_layer_circuit(builder, &mut state);
+            state = <F as Poseidon>::mds_layer_circuit(builder, &state);
+            <F as CustomRescuePrime>::rescue_constant_layer_circuit(builder, &mut state, 2 * r);
+
+            for i in 0..SPONGE_WIDTH {
+                let inv_sbox_out = vars.local_wires[Self::wire_inv_sbox_output(r, i)];
+                let sbox_out = <F as Poseidon>::sbox_monomial_circuit(builder, inv_sbox_out);
+                constraints.push(builder.sub_extension(sbox_out, state[i]));
+                state[i] = inv_sbox_out;
+            }
+            state = <F as Poseidon>::mds_layer_circuit(builder, &state);
+            <F as CustomRescuePrime>::rescue_constant_layer_circuit(builder, &mut state, 2 * r + 1);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            constraints
+                .push(builder.sub_extension(state[i], vars.local_wires[Self::wire_output(i)]));
+        }
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = CustomRescuePrimeGenerator::<F, D> {
+            row,
+            _phantom: PhantomData,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        Self::end()
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        7
+    }
+
+    fn num_constraints(&self) -> usize {
+        SPONGE_WIDTH * rescue_prime::N_ROUNDS + SPONGE_WIDTH + 1 + 4
+    }
+}
+
+#[derive(Debug)]
+pub struct CustomRescuePrimeGenerator<
+    F: RichField + Extendable<D> + CustomRescuePrime,
+    const D: usize,
+> {
+    row: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D> + CustomRescuePrime, const D: usize> SimpleGenerator<F>
+    for CustomRescuePrimeGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        (0..SPONGE_WIDTH)
+            .map(|i| CustomRescuePrimeGate::<F, D>::wire_input(i))
+            .chain(Some(CustomRescuePrimeGate::<F, D>::WIRE_SWAP))
+            .map(|column| Target::wire(self.row, column))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |column| Wire {
+            row: self.row,
+            column,
+        };
+
+        let mut state = (0..SPONGE_WIDTH)
+            .map(|i| witness.get_wire(local_wire(CustomRescuePrimeGate::<F, D>::wire_input(i))))
+            .collect::<Vec<_>>();
+
+        let swap_value = witness.get_wire(local_wire(CustomRescuePrimeGate::<F, D>::WIRE_SWAP));
+        debug_assert!(swap_value == F::ZERO || swap_value == F::ONE);
+
+        for i in 0..4 {
+            let delta_i = swap_value * (state[i + 4] - state[i]);
+            out_buffer.set_wire(
+                local_wire(CustomRescuePrimeGate::<F, D>::wire_delta(i)),
+                delta_i,
+            );
+        }
+
+        if swap_value == F::ONE {
+            for i in 0..4 {
+                state.swap(i, 4 + i);
+            }
+        }
+
+        let mut state: [F; SPONGE_WIDTH] = state.try_into().unwrap();
+
+        for r in 0..rescue_prime::N_ROUNDS {
+            <F as Poseidon>::sbox_layer(&mut state);
+            state = <F as Poseidon>::mds_layer(&state);
+            <F as CustomRescuePrime>::rescue_constant_layer_field(&mut state, 2 * r);
+
+            state = state.map(|x| x.exp_u64(<F as CustomRescuePrime>::ALPHA_INV));
+            for i in 0..SPONGE_WIDTH {
+                out_buffer.set_wire(
+                    local_wire(CustomRescuePrimeGate::<F, D>::wire_inv_sbox_output(r, i)),
+                    state[i],
+                );
+            }
+            state = <F as Poseidon>::mds_layer(&state);
+            <F as CustomRescuePrime>::rescue_constant_layer_field(&mut state, 2 * r + 1);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            out_buffer.set_wire(
+                local_wire(CustomRescuePrimeGate::<F, D>::wire_output(i)),
+                state[i],
+            );
+        }
+    }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.row)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        Ok(Self {
+            row,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use plonky2_field::types::Field;
+
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::rescue_prime::CustomRescuePrimeGate;
+    use crate::hash::hashing::SPONGE_WIDTH;
+    use crate::hash::rescue_prime::CustomRescuePrime;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::wire::Wire;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{CustomRescuePrimeGoldilocksConfig, GenericConfig};
+
+    #[test]
+    fn wire_indices() {
+        type F = GoldilocksField;
+        type Gate = CustomRescuePrimeGate<F, 4>;
+
+        assert_eq!(Gate::wire_input(0), 0);
+        assert_eq!(Gate::wire_output(11), 23);
+        assert_eq!(Gate::WIRE_SWAP, 24);
+        assert_eq!(Gate::wire_delta(3), 28);
+        assert_eq!(Gate::wire_inv_sbox_output(0, 0), 29);
+        assert_eq!(Gate::wire_inv_sbox_output(6, 11), 112);
+        assert_eq!(Gate::end(), 113);
+    }
+
+    #[test]
+    fn generated_output() {
+        const D: usize = 2;
+        type C = CustomRescuePrimeGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::new(config);
+        type Gate = CustomRescuePrimeGate<F, D>;
+        let row = builder.add_gate(Gate::new(), vec![]);
+        let circuit = builder.build_prover::<C>();
+
+        let permutation_inputs = F::rand_arr::<SPONGE_WIDTH>();
+
+        let mut inputs = PartialWitness::new();
+        inputs.set_wire(
+            Wire {
+                row,
+                column: Gate::WIRE_SWAP,
+            },
+            F::ZERO,
+        );
+        for i in 0..SPONGE_WIDTH {
+            inputs.set_wire(
+                Wire {
+                    row,
+                    column: Gate::wire_input(i),
+                },
+                permutation_inputs[i],
+            );
+        }
+
+        let witness = generate_partial_witness(inputs, &circuit.prover_only, &circuit.common);
+
+        let expected_outputs = F::rescue_prime(permutation_inputs);
+        for i in 0..SPONGE_WIDTH {
+            let out = witness.get_wire(Wire {
+                row,
+                column: Gate::wire_output(i),
+            });
+            assert_eq!(out, expected_outputs[i]);
+        }
+    }
+
+    #[test]
+    fn low_degree() {
+        type F = GoldilocksField;
+        let gate = CustomRescuePrimeGate::<F, 4>::new();
+        test_low_degree(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = CustomRescuePrimeGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = CustomRescuePrimeGate::<F, 2>::new();
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
@@ -1,6 +1,7 @@
 //! Concrete instantiation of a hash function.
 
 use plonky2_field::extension::Extendable;
+use plonky2_field::packed::PackedField;
 
 use crate::hash::hash_types::RichField;
 use crate::hash::hash_types::{HashOut, HashOutTarget};
@@ -74,6 +75,30 @@ pub fn compress<F: RichField, P: PlonkyPermutation<F>>(x: HashOut<F>, y: HashOut
 /// Permutation that can be used in the sponge construction for an algebraic hash.
 pub trait PlonkyPermutation<F: RichField> {
     fn permute(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH];
+
+    /// Permutes `P::WIDTH` states at once, one per lane of `P`. By default, the lanes are permuted
+    /// one at a time.
+    fn permute_packed<P: PackedField<Scalar = F>>(inputs: [P; SPONGE_WIDTH]) -> [P; SPONGE_WIDTH] {
+        permute_lanes::<F, Self, P>(inputs)
+    }
+}
+
+/// Permutes each lane of `inputs` separately.
+pub fn permute_lanes<
+    F: RichField,
+    P: PlonkyPermutation<F> + ?Sized,
+    PF: PackedField<Scalar = F>,
+>(
+    inputs: [PF; SPONGE_WIDTH],
+) -> [PF; SPONGE_WIDTH] {
+    let mut outputs = inputs;
+    for lane in 0..PF::WIDTH {
+        let state = P::permute(inputs.map(|x| x.as_slice()[lane]));
+        for (output, x) in outputs.iter_mut().zip(state) {
+            output.as_slice_mut()[lane] = x;
+        }
+    }
+    outputs
 }
 
 /// Hash a message without any padding step. Note that this can enable length-extension attacks.
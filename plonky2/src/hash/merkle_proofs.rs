@@ -1,5 +1,6 @@
 use anyhow::{ensure, Result};
 use plonky2_field::extension::Extendable;
+use plonky2_util::log2_strict;
 use serde::{Deserialize, Serialize};
 
 use crate::hash::hash_types::RichField;
@@ -29,6 +30,22 @@ pub struct MerkleProofTarget {
     pub siblings: Vec<HashOutTarget>,
 }
 
+/// A proof that several leaves are present in a Merkle tree, which holds each sibling digest
+/// needed to recompute the cap only once.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(bound = "")]
+pub struct MerkleMultiProof<F: RichField, H: Hasher<F>> {
+    /// The Merkle digest of each sibling subtree which isn't an ancestor of one of the leaves,
+    /// ordered by layer starting from the bottommost one, then by index within the layer.
+    pub siblings: Vec<H::Hash>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MerkleMultiProofTarget {
+    /// The Merkle digest of each sibling subtree, ordered as in `MerkleMultiProof`.
+    pub siblings: Vec<HashOutTarget>,
+}
+
 /// Verifies that the given leaf data is present at the given index in the Merkle tree with the
 /// given root.
 pub fn verify_merkle_proof<F: RichField, H: Hasher<F>>(
@@ -74,6 +91,98 @@ where
     Ok(())
 }
 
+/// Verifies that replacing the data of the given leaf of the Merkle tree with root `old_root`
+/// results in the tree with root `new_root`.
+pub fn verify_merkle_update<F: RichField, H: Hasher<F>>(
+    old_leaf_data: Vec<F>,
+    new_leaf_data: Vec<F>,
+    leaf_index: usize,
+    old_root: H::Hash,
+    new_root: H::Hash,
+    proof: &MerkleProof<F, H>,
+) -> Result<()>
+where
+    [(); H::HASH_SIZE]:,
+{
+    verify_merkle_proof(old_leaf_data, leaf_index, old_root, proof)?;
+    verify_merkle_proof(new_leaf_data, leaf_index, new_root, proof)
+}
+
+/// Verifies that the given leaves are present at the given indices in the Merkle tree of height
+/// `height` with the given cap. The leaf indices must be distinct, but may be in any order.
+pub fn verify_merkle_multiproof_to_cap<F: RichField, H: Hasher<F>>(
+    leaves_data: &[Vec<F>],
+    leaf_indices: &[usize],
+    height: usize,
+    merkle_cap: &MerkleCap<F, H>,
+    proof: &MerkleMultiProof<F, H>,
+) -> Result<()>
+where
+    [(); H::HASH_SIZE]:,
+{
+    ensure!(
+        leaves_data.len() == leaf_indices.len(),
+        "Expected one index per leaf."
+    );
+    let mut layer = leaf_indices
+        .iter()
+        .zip(leaves_data)
+        .map(|(&i, leaf_data)| (i, H::hash_or_noop(leaf_data)))
+        .collect::<Vec<_>>();
+    layer.sort_unstable_by_key(|&(i, _)| i);
+    ensure!(
+        layer.windows(2).all(|w| w[0].0 < w[1].0),
+        "Leaf indices should be distinct."
+    );
+    ensure!(
+        merkle_cap.height() <= height,
+        "Merkle cap is higher than the tree."
+    );
+    ensure!(
+        layer.last().map_or(true, |&(i, _)| i < 1 << height),
+        "Leaf index out of range."
+    );
+
+    let mut siblings = proof.siblings.iter();
+    for _ in 0..height - merkle_cap.height() {
+        let mut next_layer = Vec::with_capacity(layer.len());
+        let mut k = 0;
+        while k < layer.len() {
+            let (node, digest) = layer[k];
+            let parent_digest = match layer.get(k + 1) {
+                Some(&(sibling_node, sibling_digest))
+                    if node & 1 == 0 && sibling_node == node + 1 =>
+                {
+                    k += 2;
+                    H::two_to_one(digest, sibling_digest)
+                }
+                _ => {
+                    k += 1;
+                    let &sibling_digest = siblings.next().ok_or_else(|| {
+                        anyhow::anyhow!("Merkle multiproof has too few siblings.")
+                    })?;
+                    if node & 1 == 1 {
+                        H::two_to_one(sibling_digest, digest)
+                    } else {
+                        H::two_to_one(digest, sibling_digest)
+                    }
+                }
+            };
+            next_layer.push((node >> 1, parent_digest));
+        }
+        layer = next_layer;
+    }
+    ensure!(
+        siblings.next().is_none(),
+        "Merkle multiproof has too many siblings."
+    );
+    for (node, digest) in layer {
+        ensure!(digest == merkle_cap.0[node], "Invalid Merkle multiproof.");
+    }
+
+    Ok(())
+}
+
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Verifies that the given leaf data is present at the given index in the Merkle tree with the
     /// given root. The index is given by its little-endian bits.
@@ -117,10 +226,29 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         merkle_cap: &MerkleCapTarget,
         proof: &MerkleProofTarget,
     ) {
-        let zero = self.zero();
-        let mut state: HashOutTarget = self.hash_or_noop::<H>(leaf_data);
+        let leaf_digest = self.hash_or_noop::<H>(leaf_data);
+        let state = self.merkle_path_top::<H>(leaf_digest, leaf_index_bits, &proof.siblings);
+
+        for i in 0..4 {
+            let result = self.random_access(
+                cap_index,
+                merkle_cap.0.iter().map(|h| h.elements[i]).collect(),
+            );
+            self.connect(result, state.elements[i]);
+        }
+    }
 
-        for (&bit, &sibling) in leaf_index_bits.iter().zip(&proof.siblings) {
+    /// Computes the digest at the top of a Merkle path, from the digest of a leaf and its
+    /// siblings. The leaf index is given by its little-endian bits.
+    pub(crate) fn merkle_path_top<H: AlgebraicHasher<F>>(
+        &mut self,
+        leaf_digest: HashOutTarget,
+        leaf_index_bits: &[BoolTarget],
+        siblings: &[HashOutTarget],
+    ) -> HashOutTarget {
+        let zero = self.zero();
+        let mut state = leaf_digest;
+        for (&bit, &sibling) in leaf_index_bits.iter().zip(siblings) {
             let mut perm_inputs = [zero; SPONGE_WIDTH];
             perm_inputs[..4].copy_from_slice(&state.elements);
             perm_inputs[4..8].copy_from_slice(&sibling.elements);
@@ -130,13 +258,102 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
                 elements: hash_outs,
             };
         }
+        state
+    }
 
-        for i in 0..4 {
-            let result = self.random_access(
-                cap_index,
-                merkle_cap.0.iter().map(|h| h.elements[i]).collect(),
-            );
-            self.connect(result, state.elements[i]);
+    /// Verifies that replacing the data of the given leaf of the Merkle tree with root `old_root`
+    /// results in the tree with root `new_root`. The index is given by its little-endian bits.
+    pub fn verify_merkle_update<H: AlgebraicHasher<F>>(
+        &mut self,
+        old_leaf_data: Vec<Target>,
+        new_leaf_data: Vec<Target>,
+        leaf_index_bits: &[BoolTarget],
+        old_root: HashOutTarget,
+        new_root: HashOutTarget,
+        proof: &MerkleProofTarget,
+    ) {
+        for (leaf_data, root) in [(old_leaf_data, old_root), (new_leaf_data, new_root)] {
+            let leaf_digest = self.hash_or_noop::<H>(leaf_data);
+            let top = self.merkle_path_top::<H>(leaf_digest, leaf_index_bits, &proof.siblings);
+            self.connect_hashes(top, root);
+        }
+    }
+
+    /// Verifies that the given leaves are present in the Merkle tree of height `height` with the
+    /// given cap. Since the shape of a multiproof depends on the leaf indices, these are fixed when
+    /// building the circuit.
+    pub fn verify_merkle_multiproof_to_cap<H: AlgebraicHasher<F>>(
+        &mut self,
+        leaves_data: Vec<Vec<Target>>,
+        leaf_indices: &[usize],
+        height: usize,
+        merkle_cap: &MerkleCapTarget,
+        proof: &MerkleMultiProofTarget,
+    ) {
+        assert_eq!(leaves_data.len(), leaf_indices.len());
+        let mut layer = leaf_indices
+            .iter()
+            .zip(leaves_data)
+            .map(|(&i, leaf_data)| (i, self.hash_or_noop::<H>(leaf_data)))
+            .collect::<Vec<_>>();
+        layer.sort_unstable_by_key(|&(i, _)| i);
+        assert!(
+            layer.windows(2).all(|w| w[0].0 < w[1].0),
+            "Leaf indices should be distinct."
+        );
+
+        let cap_height = log2_strict(merkle_cap.0.len());
+        let mut siblings = proof.siblings.iter();
+        for _ in 0..height - cap_height {
+            let mut next_layer = Vec::with_capacity(layer.len());
+            let mut k = 0;
+            while k < layer.len() {
+                let (node, digest) = layer[k];
+                let (left, right) = match layer.get(k + 1) {
+                    Some(&(sibling_node, sibling_digest))
+                        if node & 1 == 0 && sibling_node == node + 1 =>
+                    {
+                        k += 2;
+                        (digest, sibling_digest)
+                    }
+                    _ => {
+                        k += 1;
+                        let &sibling_digest = siblings
+                            .next()
+                            .expect("Merkle multiproof has too few siblings.");
+                        if node & 1 == 1 {
+                            (sibling_digest, digest)
+                        } else {
+                            (digest, sibling_digest)
+                        }
+                    }
+                };
+                next_layer.push((node >> 1, self.two_to_one::<H>(left, right)));
+            }
+            layer = next_layer;
+        }
+        assert!(
+            siblings.next().is_none(),
+            "Merkle multiproof has too many siblings."
+        );
+        for (node, digest) in layer {
+            self.connect_hashes(digest, merkle_cap.0[node]);
+        }
+    }
+
+    /// Hashes two digests into their parent's digest, as `Hasher::two_to_one` does.
+    pub fn two_to_one<H: AlgebraicHasher<F>>(
+        &mut self,
+        left: HashOutTarget,
+        right: HashOutTarget,
+    ) -> HashOutTarget {
+        let zero = self.zero();
+        let mut perm_inputs = [zero; SPONGE_WIDTH];
+        perm_inputs[..4].copy_from_slice(&left.elements);
+        perm_inputs[4..8].copy_from_slice(&right.elements);
+        let perm_outs = self.permute::<H>(perm_inputs);
+        HashOutTarget {
+            elements: perm_outs[0..4].try_into().unwrap(),
         }
     }
 
@@ -209,4 +426,79 @@ mod tests {
 
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_recursive_merkle_update_and_multiproof() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::InnerHasher;
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let log_n = 6;
+        let n = 1 << log_n;
+        let cap_height = 1;
+        let leaves = random_data::<F>(n, 7);
+        let mut tree = MerkleTree::<F, H>::new(leaves.clone(), 0);
+
+        // Update a leaf.
+        let i: usize = thread_rng().gen_range(0..n);
+        let proof = tree.prove(i);
+        let old_root = tree.cap.0[0];
+        let new_leaf = F::rand_vec(7);
+        tree.update_leaf(i, new_leaf.clone());
+        let new_root = tree.cap.0[0];
+
+        let proof_t = MerkleProofTarget {
+            siblings: builder.add_virtual_hashes(proof.siblings.len()),
+        };
+        for (&t, &h) in proof_t.siblings.iter().zip(&proof.siblings) {
+            pw.set_hash_target(t, h);
+        }
+        let old_leaf_t = builder.add_virtual_targets(7);
+        let new_leaf_t = builder.add_virtual_targets(7);
+        for j in 0..7 {
+            pw.set_target(old_leaf_t[j], leaves[i][j]);
+            pw.set_target(new_leaf_t[j], new_leaf[j]);
+        }
+        let old_root_t = builder.constant_hash(old_root);
+        let new_root_t = builder.constant_hash(new_root);
+        let i_t = builder.add_virtual_target();
+        pw.set_target(i_t, F::from_canonical_usize(i));
+        let i_bits = builder.split_le(i_t, log_n);
+        builder.verify_merkle_update::<H>(
+            old_leaf_t, new_leaf_t, &i_bits, old_root_t, new_root_t, &proof_t,
+        );
+
+        // Prove several leaves of a tree with a cap at once.
+        let tree = MerkleTree::<F, H>::new(leaves.clone(), cap_height);
+        let indices = [1, 0, 17, 40, 41, 63];
+        let proof = tree.prove_multi(&indices);
+        let proof_t = MerkleMultiProofTarget {
+            siblings: builder.add_virtual_hashes(proof.siblings.len()),
+        };
+        for (&t, &h) in proof_t.siblings.iter().zip(&proof.siblings) {
+            pw.set_hash_target(t, h);
+        }
+        let cap_t = builder.add_virtual_cap(cap_height);
+        pw.set_cap_target(&cap_t, &tree.cap);
+        let leaves_t = indices
+            .iter()
+            .map(|&i| {
+                let leaf_t = builder.add_virtual_targets(7);
+                for j in 0..7 {
+                    pw.set_target(leaf_t[j], leaves[i][j]);
+                }
+                leaf_t
+            })
+            .collect();
+        builder.verify_merkle_multiproof_to_cap::<H>(leaves_t, &indices, log_n, &cap_t, &proof_t);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+
+        verify(proof, &data.verifier_only, &data.common)
+    }
 }
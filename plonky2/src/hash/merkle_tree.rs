@@ -6,7 +6,7 @@ use plonky2_util::log2_strict;
 use serde::{Deserialize, Serialize};
 
 use crate::hash::hash_types::RichField;
-use crate::hash::merkle_proofs::MerkleProof;
+use crate::hash::merkle_proofs::{MerkleMultiProof, MerkleProof};
 use crate::plonk::config::GenericHashOut;
 use crate::plonk::config::Hasher;
 
@@ -207,15 +207,93 @@ impl<F: RichField, H: Hasher<F>> MerkleTree<F, H> {
 
         MerkleProof { siblings }
     }
+
+    /// Replaces the data of a leaf, and recomputes the digests on its path to the cap.
+    pub fn update_leaf(&mut self, leaf_index: usize, leaf_data: Vec<F>)
+    where
+        [(); H::HASH_SIZE]:,
+    {
+        let cap_height = log2_strict(self.cap.len());
+        let num_layers = log2_strict(self.leaves.len()) - cap_height;
+        debug_assert_eq!(leaf_index >> (cap_height + num_layers), 0);
+
+        let tree_index = leaf_index >> num_layers;
+        let digest_tree = {
+            let tree_len = self.digests.len() >> cap_height;
+            &mut self.digests[tree_len * tree_index..tree_len * (tree_index + 1)]
+        };
+
+        // Walk up the path as in `prove`, storing the new digest of each node next to its sibling.
+        let mut digest = H::hash_or_noop(&leaf_data);
+        let mut pair_index = leaf_index & ((1 << num_layers) - 1);
+        for i in 0..num_layers {
+            let parity = pair_index & 1;
+            pair_index >>= 1;
+            let siblings_index = (pair_index << (i + 1)) + (1 << i) - 1;
+            digest_tree[2 * siblings_index + parity] = digest;
+            let sibling = digest_tree[2 * siblings_index + 1 - parity];
+            digest = if parity == 1 {
+                H::two_to_one(sibling, digest)
+            } else {
+                H::two_to_one(digest, sibling)
+            };
+        }
+        self.cap.0[tree_index] = digest;
+        self.leaves[leaf_index] = leaf_data;
+    }
+
+    /// Create a single proof for several leaves, holding each sibling digest needed to recompute
+    /// the cap only once. Siblings are ordered by layer from the bottom, then by index. The leaf
+    /// indices must be distinct.
+    pub fn prove_multi(&self, leaf_indices: &[usize]) -> MerkleMultiProof<F, H> {
+        let num_layers = log2_strict(self.leaves.len()) - log2_strict(self.cap.len());
+        let mut sorted_indices = leaf_indices.to_vec();
+        sorted_indices.sort_unstable();
+        sorted_indices.dedup();
+        assert_eq!(
+            sorted_indices.len(),
+            leaf_indices.len(),
+            "Leaf indices should be distinct."
+        );
+
+        // Each node of the current layer is paired with the index of a leaf below it, whose path
+        // holds the node's sibling.
+        let proofs = sorted_indices
+            .iter()
+            .map(|&i| self.prove(i))
+            .collect::<Vec<_>>();
+        let mut layer = (0..sorted_indices.len())
+            .map(|k| (sorted_indices[k], k))
+            .collect::<Vec<_>>();
+        let mut siblings = Vec::new();
+        for i in 0..num_layers {
+            let mut next_layer = Vec::with_capacity(layer.len());
+            let mut k = 0;
+            while k < layer.len() {
+                let (node, leaf) = layer[k];
+                if node & 1 == 0 && layer.get(k + 1).map(|&(n, _)| n) == Some(node + 1) {
+                    k += 2;
+                } else {
+                    siblings.push(proofs[leaf].siblings[i]);
+                    k += 1;
+                }
+                next_layer.push((node >> 1, leaf));
+            }
+            layer = next_layer;
+        }
+
+        MerkleMultiProof { siblings }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use plonky2_field::extension::Extendable;
+    use plonky2_field::types::Field;
 
     use super::*;
-    use crate::hash::merkle_proofs::verify_merkle_proof_to_cap;
+    use crate::hash::merkle_proofs::{verify_merkle_multiproof_to_cap, verify_merkle_proof_to_cap};
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
 
     fn random_data<F: RichField>(n: usize, k: usize) -> Vec<Vec<F>> {
@@ -280,4 +358,73 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_update_leaf() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::Hasher;
+
+        let log_n = 8;
+        let n = 1 << log_n;
+        let cap_height = 2;
+        let mut leaves = random_data::<F>(n, 7);
+        let mut tree = MerkleTree::<F, H>::new(leaves.clone(), cap_height);
+        for i in [0, 37, n - 1] {
+            let old_root_proof = tree.prove(i);
+            let old_cap = tree.cap.clone();
+            leaves[i] = F::rand_vec(7);
+            tree.update_leaf(i, leaves[i].clone());
+
+            let new_tree = MerkleTree::<F, H>::new(leaves.clone(), cap_height);
+            assert_eq!(tree.digests, new_tree.digests);
+            assert_eq!(tree.cap, new_tree.cap);
+            assert_ne!(tree.cap, old_cap);
+            // The siblings of an updated leaf don't change.
+            assert_eq!(tree.prove(i), old_root_proof);
+            verify_merkle_proof_to_cap(leaves[i].clone(), i, &tree.cap, &tree.prove(i))?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merkle_multiproofs() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::Hasher;
+
+        let log_n = 8;
+        let n = 1 << log_n;
+        let leaves = random_data::<F>(n, 7);
+        for cap_height in [0, 3, log_n] {
+            let tree = MerkleTree::<F, H>::new(leaves.clone(), cap_height);
+            for indices in [vec![5], vec![200, 3, 4, 2, 201, 100], (0..n).collect()] {
+                let proof = tree.prove_multi(&indices);
+                let num_layers = log_n - cap_height;
+                // Shared siblings are only included once.
+                assert!(proof.siblings.len() <= indices.len() * num_layers);
+                if indices.len() == n {
+                    assert!(proof.siblings.is_empty());
+                }
+
+                let data = indices
+                    .iter()
+                    .map(|&i| leaves[i].clone())
+                    .collect::<Vec<_>>();
+                verify_merkle_multiproof_to_cap(&data, &indices, log_n, &tree.cap, &proof)?;
+
+                let mut bad_data = data;
+                bad_data[0][0] += F::ONE;
+                assert!(verify_merkle_multiproof_to_cap(
+                    &bad_data, &indices, log_n, &tree.cap, &proof
+                )
+                .is_err());
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -2,6 +2,7 @@
 //! https://eprint.iacr.org/2019/458.pdf
 
 use plonky2_field::extension::{Extendable, FieldExtension};
+use plonky2_field::packed::PackedField;
 use plonky2_field::types::{Field, PrimeField64};
 use unroll::unroll_for_loops;
 
@@ -9,7 +10,9 @@ use crate::gates::gate::Gate;
 use crate::gates::poseidon::PoseidonGate;
 use crate::gates::poseidon_mds::PoseidonMdsGate;
 use crate::hash::hash_types::{HashOut, RichField};
-use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation, SPONGE_WIDTH};
+use crate::hash::hashing::{
+    compress, hash_n_to_hash_no_pad, permute_lanes, PlonkyPermutation, SPONGE_WIDTH,
+};
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
@@ -624,6 +627,132 @@ pub trait Poseidon: PrimeField64 {
 
         state
     }
+
+    /// Same as `constant_layer` for packed fields, with one state per lane.
+    #[inline(always)]
+    fn constant_layer_packed<P: PackedField<Scalar = Self>>(
+        state: &mut [P; WIDTH],
+        round_ctr: usize,
+    ) {
+        for i in 0..WIDTH {
+            state[i] += Self::from_canonical_u64(ALL_ROUND_CONSTANTS[i + WIDTH * round_ctr]);
+        }
+    }
+
+    /// Same as `sbox_monomial` for packed fields.
+    #[inline(always)]
+    fn sbox_monomial_packed<P: PackedField<Scalar = Self>>(x: P) -> P {
+        // x |--> x^7
+        let x2 = x.square();
+        let x4 = x2.square();
+        let x3 = x * x2;
+        x3 * x4
+    }
+
+    /// Same as `mds_layer` for packed fields, with one state per lane. Since the entries of the MDS
+    /// matrix are small, the products are computed with additions only, one bit of the entries at
+    /// a time, which is cheaper than packed multiplications.
+    #[inline(always)]
+    fn mds_layer_packed<P: PackedField<Scalar = Self>>(state: &[P; WIDTH]) -> [P; WIDTH] {
+        let max_entry = Self::MDS_MATRIX_CIRC
+            .iter()
+            .chain(&Self::MDS_MATRIX_DIAG)
+            .fold(0, |acc, &x| acc | x);
+        let entry_bits = 64 - max_entry.leading_zeros();
+
+        let mut result = [P::ZEROS; WIDTH];
+        for bit in (0..entry_bits).rev() {
+            for r in 0..WIDTH {
+                result[r] = result[r] + result[r];
+                for i in 0..WIDTH {
+                    if (Self::MDS_MATRIX_CIRC[i] >> bit) & 1 == 1 {
+                        result[r] += state[(i + r) % WIDTH];
+                    }
+                }
+                if (Self::MDS_MATRIX_DIAG[r] >> bit) & 1 == 1 {
+                    result[r] += state[r];
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Same as `mds_partial_layer_init` for packed fields, with one state per lane.
+    #[inline(always)]
+    fn mds_partial_layer_init_packed<P: PackedField<Scalar = Self>>(
+        state: &[P; WIDTH],
+    ) -> [P; WIDTH] {
+        let mut result = [P::ZEROS; WIDTH];
+        result[0] = state[0];
+
+        for r in 1..WIDTH {
+            for c in 1..WIDTH {
+                let t =
+                    Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_INITIAL_MATRIX[r - 1][c - 1]);
+                result[c] += state[r] * t;
+            }
+        }
+        result
+    }
+
+    /// Same as `mds_partial_layer_fast` for packed fields, with one state per lane.
+    #[inline(always)]
+    fn mds_partial_layer_fast_packed<P: PackedField<Scalar = Self>>(
+        state: &[P; WIDTH],
+        r: usize,
+    ) -> [P; WIDTH] {
+        let s0 = state[0];
+        let mds0to0 = Self::MDS_MATRIX_CIRC[0] + Self::MDS_MATRIX_DIAG[0];
+        let mut d = s0 * Self::from_canonical_u64(mds0to0);
+        for i in 1..WIDTH {
+            d += state[i] * Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_W_HATS[r][i - 1]);
+        }
+
+        // result = [d] concat [state[0] * v + state[shift up by 1]]
+        let mut result = [P::ZEROS; WIDTH];
+        result[0] = d;
+        for i in 1..WIDTH {
+            let t = Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_VS[r][i - 1]);
+            result[i] = s0 * t + state[i];
+        }
+        result
+    }
+
+    /// Same as `poseidon` for packed fields: permutes `P::WIDTH` states at once, one per lane.
+    #[inline]
+    fn poseidon_packed<P: PackedField<Scalar = Self>>(input: [P; WIDTH]) -> [P; WIDTH] {
+        let mut state = input;
+        let mut round_ctr = 0;
+
+        for _ in 0..HALF_N_FULL_ROUNDS {
+            Self::constant_layer_packed(&mut state, round_ctr);
+            state = state.map(Self::sbox_monomial_packed);
+            state = Self::mds_layer_packed(&state);
+            round_ctr += 1;
+        }
+
+        for i in 0..WIDTH {
+            state[i] += Self::from_canonical_u64(Self::FAST_PARTIAL_FIRST_ROUND_CONSTANT[i]);
+        }
+        state = Self::mds_partial_layer_init_packed(&state);
+        for i in 0..N_PARTIAL_ROUNDS {
+            state[0] = Self::sbox_monomial_packed(state[0]);
+            state[0] += Self::from_canonical_u64(Self::FAST_PARTIAL_ROUND_CONSTANTS[i]);
+            state = Self::mds_partial_layer_fast_packed(&state, i);
+        }
+        round_ctr += N_PARTIAL_ROUNDS;
+
+        for _ in 0..HALF_N_FULL_ROUNDS {
+            Self::constant_layer_packed(&mut state, round_ctr);
+            state = state.map(Self::sbox_monomial_packed);
+            state = Self::mds_layer_packed(&state);
+            round_ctr += 1;
+        }
+        debug_assert_eq!(round_ctr, N_ROUNDS);
+
+        state
+    }
 }
 
 pub struct PoseidonPermutation;
@@ -631,6 +760,16 @@ impl<F: RichField> PlonkyPermutation<F> for PoseidonPermutation {
     fn permute(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH] {
         F::poseidon(input)
     }
+
+    fn permute_packed<P: PackedField<Scalar = F>>(inputs: [P; SPONGE_WIDTH]) -> [P; SPONGE_WIDTH] {
+        // Packed Poseidon only beats permuting the lanes one at a time with 8 lanes or more, i.e.
+        // with AVX-512 rather than AVX2, as measured by the `hashing` benchmark.
+        if P::WIDTH >= 8 {
+            F::poseidon_packed(inputs)
+        } else {
+            permute_lanes::<F, Self, P>(inputs)
+        }
+    }
 }
 
 /// Poseidon hash function.
@@ -684,6 +823,8 @@ impl<F: RichField> AlgebraicHasher<F> for PoseidonHash {
 
 #[cfg(test)]
 pub(crate) mod test_helpers {
+    use plonky2_field::packable::Packable;
+    use plonky2_field::packed::PackedField;
     use plonky2_field::types::Field;
 
     use crate::hash::hashing::SPONGE_WIDTH;
@@ -720,5 +861,20 @@ pub(crate) mod test_helpers {
         for i in 0..SPONGE_WIDTH {
             assert_eq!(output[i], output_naive[i]);
         }
+
+        // Each lane of the packed permutation permutes its own state.
+        let mut packed_input = [<F as Packable>::Packing::ZEROS; SPONGE_WIDTH];
+        for (i, x) in packed_input.iter_mut().enumerate() {
+            for (lane, y) in x.as_slice_mut().iter_mut().enumerate() {
+                *y = F::from_canonical_u64((i + lane * SPONGE_WIDTH) as u64);
+            }
+        }
+        let packed_output = F::poseidon_packed(packed_input);
+        for lane in 0..<F as Packable>::Packing::WIDTH {
+            let output = F::poseidon(packed_input.map(|x| x.as_slice()[lane]));
+            for i in 0..SPONGE_WIDTH {
+                assert_eq!(packed_output[i].as_slice()[lane], output[i]);
+            }
+        }
     }
 }
@@ -0,0 +1,405 @@
+//! Implementation of the Poseidon2 permutation, as described in
+//! https://eprint.iacr.org/2023/323.pdf
+//!
+//! We use a width of 12 with the S-box `x^7`, 8 full rounds and 22 partial rounds, which matches
+//! the round numbers of our Poseidon instance. The external linear layer is built from the 4x4
+//! matrix `M_4` of the paper, and the internal layer is `1 + diag(INTERNAL_MATRIX_DIAG_M_1)`.
+//!
+//! This is a custom instance rather than the reference Poseidon2 instance for Goldilocks: while the
+//! internal diagonal is the reference one, the round constants are sampled with ChaCha8 from a
+//! fixed seed (see `generate_constants`), so digests do not match those of the reference
+//! implementation. The types are named accordingly, and the test vectors below pin this instance.
+
+use plonky2_field::extension::{Extendable, FieldExtension};
+use plonky2_field::goldilocks_field::GoldilocksField;
+use plonky2_field::types::{Field, PrimeField64};
+
+use crate::gates::poseidon2::CustomPoseidon2Gate;
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation, SPONGE_WIDTH};
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+pub const HALF_N_FULL_ROUNDS: usize = 4;
+pub(crate) const N_FULL_ROUNDS_TOTAL: usize = 2 * HALF_N_FULL_ROUNDS;
+pub const N_PARTIAL_ROUNDS: usize = 22;
+const WIDTH: usize = SPONGE_WIDTH;
+
+/// Round constants of the full rounds, `WIDTH` per round. These were generated by running
+/// `generate_constants 118 1`; the first `WIDTH * N_FULL_ROUNDS_TOTAL` outputs are used here and
+/// the remaining ones in `INTERNAL_ROUND_CONSTANTS`.
+#[rustfmt::skip]
+pub const EXTERNAL_ROUND_CONSTANTS: [u64; WIDTH * N_FULL_ROUNDS_TOTAL] = [
+    0x67094cea259ac0c6, 0x149406d8e77a8792, 0x98b82b029d4edb62, 0x3825a7dc2ae26565,
+    0x489ff2538c7bd991, 0xb5ea8d3633f79321, 0x7609dfe008f5aa36, 0x27143f428eb1094b,
+    0xdff1a71a2abfb8f8, 0xb4e550af779b1858, 0x7bb6bdb18b5585bf, 0x93c3fa5e5d4c4f84,
+    0x19762de5bc65603d, 0x43901a0bb659c795, 0x7415efbefe910c23, 0x47bd11da2bd45542,
+    0xfdb44c300bafebac, 0xe99017b1cc911a59, 0xf2764a7bd69fa5fd, 0x221c45e61fe1fdf6,
+    0x57fbae91b7c4e199, 0x7af5168bb97b235a, 0x65b7b5a8c3d40538, 0x0381aea1eb7515b4,
+    0x0c86001bbe8c542e, 0x2f64a601123a6dcc, 0x8688cdf45c5c0259, 0x44a454638c9df67e,
+    0x16a2767621a32db8, 0x19b7645605541bb8, 0xfdb3467d086d2c62, 0xb1eb364484bc5965,
+    0x1bb5f96c259c4477, 0x946562d58224a0c5, 0xbb12468d5c96dad4, 0xb463fa38d2553fc3,
+    0x39321c4b018eb5a8, 0xf400c342764cbbeb, 0x20d3bb36dc2c0a24, 0xa1ed022384c99a4c,
+    0xcdcba8b7bf5fa5ce, 0x94d311974c509d6e, 0xe64cafef1a97462d, 0x611a48bc321ae969,
+    0x7a12006530316ba5, 0xec1493b9d8a2fb5a, 0x56aac955b972df85, 0xa035a47b1a8ba9b1,
+    0x481f5aebe2b712b4, 0x3a11776f0219c24a, 0x9265985c13e45cfc, 0xefba0ed2aad34cae,
+    0x584c32c95f0e0002, 0xe8464dfcb031af99, 0xff06c0e100847565, 0xe3aea7fb0640d17c,
+    0xbb7fe83d41849d2a, 0x83e5f5c8c9e62731, 0xa66185af0e79f72b, 0x91908e4cc5ef6a0f,
+    0x9d44f722371199ce, 0x78d64f7e8560701f, 0xa5fbebb0638f00e7, 0x9b48d14119e29aad,
+    0x10ad6c11bc55081b, 0x3d958de43afe2826, 0x6ba6a69650b977c3, 0x5daa569c778ef40e,
+    0x5cd902ef57f3f62e, 0x4128dbc0ff544f70, 0x96e8bd8ff82ea45e, 0x46793a6ec945692f,
+    0xe3de281720c15935, 0xa773c7f38a5e50fc, 0x3769869fa08b7698, 0x9ee5f191c2dcb222,
+    0x5ce57f8e52d9ffa3, 0x40de025dae9a8770, 0xfbc66d7f233fbd21, 0x89fc8aa21e72e5df,
+    0xf0c17145243fc8d7, 0x414eae6adb7d6448, 0x44cdc127a7fff4d7, 0x233e3f469158e299,
+    0x65ac66927f702ad7, 0xe15c6b3c99554c95, 0xe141c11278d938b6, 0x112abfc1a88ba5b5,
+    0xdcfb4ed776da3e2e, 0xf54f30103d6cb3f9, 0xed68d7e03dad4134, 0x20199a408893b823,
+    0x09ca9d6b63ca1022, 0x0726efe3e75bd9c1, 0x959006efa13725d6, 0xb8040c3db3ed5a3f,
+];
+
+/// Round constants of the partial rounds, which are only added to the first state element.
+#[rustfmt::skip]
+pub const INTERNAL_ROUND_CONSTANTS: [u64; N_PARTIAL_ROUNDS] = [
+    0xc7eccecedced4e79, 0xf6897763088bbf92, 0xbfd0b3376f3adb56, 0x19e6fe1042b80367,
+    0xa9dba6e4d32044e8, 0x6d20bba36bd18ffb, 0x6f6e75ada1e62db4, 0xa679e23608437228,
+    0xcde98166362bf3a6, 0x0921332d42e6d984, 0xcf15098cd3e9ebe7, 0x824348c60de657fb,
+    0x0599ad688e197051, 0x5086b0813b6143b0, 0xc646daa5737fc824, 0xfbf79a6c6c0e39b5,
+    0xd95dfa4265a4d9b5, 0x9db00f2247d8bac5, 0x88f40d8570ff87d0, 0x815a6e6656739dd9,
+    0x66e6eb404261dfdf, 0xdc3f3fca163f6b35,
+];
+
+/// The S-box `x |--> x^7`.
+#[inline(always)]
+pub(crate) fn sbox_monomial<F: Field>(x: F) -> F {
+    let x2 = x.square();
+    let x4 = x2.square();
+    let x3 = x * x2;
+    x3 * x4
+}
+
+pub trait CustomPoseidon2: PrimeField64 {
+    /// The internal matrix is `1 + D`, where `1` is the all-ones matrix and `D` is the diagonal
+    /// matrix whose diagonal is given by `INTERNAL_MATRIX_DIAG_M_1`.
+    const INTERNAL_MATRIX_DIAG_M_1: [u64; WIDTH];
+
+    /// Multiplies a chunk of four elements by the matrix `M_4` of the paper, using its addition
+    /// chain.
+    fn apply_m_4<F: FieldExtension<D, BaseField = Self>, const D: usize>(x: &mut [F]) {
+        let t0 = x[0] + x[1];
+        let t1 = x[2] + x[3];
+        let t2 = x[1].double() + t1;
+        let t3 = x[3].double() + t0;
+        let t4 = t1.double().double() + t3;
+        let t5 = t0.double().double() + t2;
+        let t6 = t3 + t5;
+        let t7 = t2 + t4;
+        x[0] = t6;
+        x[1] = t5;
+        x[2] = t7;
+        x[3] = t4;
+    }
+
+    /// Recursive version of `apply_m_4`.
+    fn apply_m_4_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        x: &mut [ExtensionTarget<D>],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        let two = Self::TWO;
+        let four = Self::from_canonical_u64(4);
+        let t0 = builder.add_extension(x[0], x[1]);
+        let t1 = builder.add_extension(x[2], x[3]);
+        let t2 = builder.mul_const_add_extension(two, x[1], t1);
+        let t3 = builder.mul_const_add_extension(two, x[3], t0);
+        let t4 = builder.mul_const_add_extension(four, t1, t3);
+        let t5 = builder.mul_const_add_extension(four, t0, t2);
+        let t6 = builder.add_extension(t3, t5);
+        let t7 = builder.add_extension(t2, t4);
+        x[0] = t6;
+        x[1] = t5;
+        x[2] = t7;
+        x[3] = t4;
+    }
+
+    /// The external linear layer, i.e. the circulant matrix `circ(2 M_4, M_4, M_4)`.
+    fn external_linear_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+    ) {
+        for chunk in state.chunks_exact_mut(4) {
+            Self::apply_m_4(chunk);
+        }
+        for i in 0..4 {
+            let sum = (0..WIDTH / 4).map(|j| state[4 * j + i]).sum::<F>();
+            for j in 0..WIDTH / 4 {
+                state[4 * j + i] += sum;
+            }
+        }
+    }
+
+    /// Recursive version of `external_linear_layer_field`.
+    fn external_linear_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for chunk in state.chunks_exact_mut(4) {
+            Self::apply_m_4_circuit(builder, chunk);
+        }
+        for i in 0..4 {
+            let sum = builder.add_many_extension((0..WIDTH / 4).map(|j| state[4 * j + i]));
+            for j in 0..WIDTH / 4 {
+                state[4 * j + i] = builder.add_extension(state[4 * j + i], sum);
+            }
+        }
+    }
+
+    /// The internal linear layer, i.e. `x_i |--> x_i * INTERNAL_MATRIX_DIAG_M_1[i] + sum_j x_j`.
+    fn internal_linear_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+    ) {
+        let sum = state.iter().copied().sum::<F>();
+        for i in 0..WIDTH {
+            let d = F::from_canonical_u64(Self::INTERNAL_MATRIX_DIAG_M_1[i]);
+            state[i] = state[i] * d + sum;
+        }
+    }
+
+    /// Recursive version of `internal_linear_layer_field`.
+    fn internal_linear_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        let sum = builder.add_many_extension(state.iter());
+        for i in 0..WIDTH {
+            let d = Self::from_canonical_u64(Self::INTERNAL_MATRIX_DIAG_M_1[i]);
+            state[i] = builder.mul_const_add_extension(d, state[i], sum);
+        }
+    }
+
+    /// Adds the round constants of the `round`-th full round.
+    fn external_constant_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+        round: usize,
+    ) {
+        for i in 0..WIDTH {
+            state[i] += F::from_canonical_u64(EXTERNAL_ROUND_CONSTANTS[WIDTH * round + i]);
+        }
+    }
+
+    /// Recursive version of `external_constant_layer_field`.
+    fn external_constant_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+        round: usize,
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for i in 0..WIDTH {
+            let c = EXTERNAL_ROUND_CONSTANTS[WIDTH * round + i];
+            let c = Self::Extension::from_canonical_u64(c);
+            let c = builder.constant_extension(c);
+            state[i] = builder.add_extension(state[i], c);
+        }
+    }
+
+    fn poseidon2(input: [Self; WIDTH]) -> [Self; WIDTH] {
+        let mut state = input;
+
+        Self::external_linear_layer_field(&mut state);
+        for r in 0..HALF_N_FULL_ROUNDS {
+            Self::external_constant_layer_field(&mut state, r);
+            state = state.map(sbox_monomial);
+            Self::external_linear_layer_field(&mut state);
+        }
+
+        for r in 0..N_PARTIAL_ROUNDS {
+            state[0] += Self::from_canonical_u64(INTERNAL_ROUND_CONSTANTS[r]);
+            state[0] = sbox_monomial(state[0]);
+            Self::internal_linear_layer_field(&mut state);
+        }
+
+        for r in HALF_N_FULL_ROUNDS..N_FULL_ROUNDS_TOTAL {
+            Self::external_constant_layer_field(&mut state, r);
+            state = state.map(sbox_monomial);
+            Self::external_linear_layer_field(&mut state);
+        }
+
+        state
+    }
+}
+
+impl CustomPoseidon2 for GoldilocksField {
+    // The internal diagonal of the reference implementation of Poseidon2 for Goldilocks with
+    // width 12. The round constants above are not the reference ones.
+    const INTERNAL_MATRIX_DIAG_M_1: [u64; WIDTH] = [
+        0xc3b6c08e23ba9300,
+        0xd84b5de94a324fb6,
+        0x0d0c371c5b35b84f,
+        0x7964f570e7188037,
+        0x5daf18bbd996604b,
+        0x6743bc47b9595257,
+        0x5528b9362c59bb70,
+        0xac45e25b7127b68b,
+        0xa2077d7dfbb606b5,
+        0xf3faac6faee378ae,
+        0x0c6388b51545e883,
+        0xd27dbb6944917b60,
+    ];
+}
+
+pub struct CustomPoseidon2Permutation;
+impl<F: RichField + CustomPoseidon2> PlonkyPermutation<F> for CustomPoseidon2Permutation {
+    fn permute(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH] {
+        F::poseidon2(input)
+    }
+}
+
+/// Poseidon2 hash function.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CustomPoseidon2Hash;
+impl<F: RichField + CustomPoseidon2> Hasher<F> for CustomPoseidon2Hash {
+    const HASH_SIZE: usize = 4 * 8;
+    type Hash = HashOut<F>;
+    type Permutation = CustomPoseidon2Permutation;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        hash_n_to_hash_no_pad::<F, Self::Permutation>(input)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        compress::<F, Self::Permutation>(left, right)
+    }
+}
+
+impl<F: RichField + CustomPoseidon2> AlgebraicHasher<F> for CustomPoseidon2Hash {
+    fn permute_swapped<const D: usize>(
+        inputs: [Target; SPONGE_WIDTH],
+        swap: BoolTarget,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> [Target; SPONGE_WIDTH]
+    where
+        F: RichField + Extendable<D>,
+    {
+        let gate_type = CustomPoseidon2Gate::<F, D>::new();
+        let gate = builder.add_gate(gate_type, vec![]);
+
+        let swap_wire = CustomPoseidon2Gate::<F, D>::WIRE_SWAP;
+        let swap_wire = Target::wire(gate, swap_wire);
+        builder.connect(swap.target, swap_wire);
+
+        // Route input wires.
+        for i in 0..SPONGE_WIDTH {
+            let in_wire = CustomPoseidon2Gate::<F, D>::wire_input(i);
+            let in_wire = Target::wire(gate, in_wire);
+            builder.connect(inputs[i], in_wire);
+        }
+
+        // Collect output wires.
+        (0..SPONGE_WIDTH)
+            .map(|i| Target::wire(gate, CustomPoseidon2Gate::<F, D>::wire_output(i)))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::goldilocks_field::GoldilocksField as F;
+    use plonky2_field::types::{Field, PrimeField64};
+
+    use crate::hash::hashing::SPONGE_WIDTH;
+    use crate::hash::poseidon2::CustomPoseidon2;
+
+    /// Applies the linear layer described by `matrix` naively.
+    fn mat_vec(
+        matrix: &[[u64; SPONGE_WIDTH]; SPONGE_WIDTH],
+        v: &[F; SPONGE_WIDTH],
+    ) -> [F; SPONGE_WIDTH] {
+        let mut res = [F::ZERO; SPONGE_WIDTH];
+        for i in 0..SPONGE_WIDTH {
+            for j in 0..SPONGE_WIDTH {
+                res[i] += F::from_canonical_u64(matrix[i][j]) * v[j];
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn external_linear_layer() {
+        const M_4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+        let mut matrix = [[0; SPONGE_WIDTH]; SPONGE_WIDTH];
+        for i in 0..SPONGE_WIDTH {
+            for j in 0..SPONGE_WIDTH {
+                let factor = if i / 4 == j / 4 { 2 } else { 1 };
+                matrix[i][j] = factor * M_4[i % 4][j % 4];
+            }
+        }
+
+        let input = F::rand_arr::<SPONGE_WIDTH>();
+        let mut state = input;
+        F::external_linear_layer_field(&mut state);
+        assert_eq!(state, mat_vec(&matrix, &input));
+    }
+
+    #[test]
+    fn internal_linear_layer() {
+        let mut matrix = [[1; SPONGE_WIDTH]; SPONGE_WIDTH];
+        for i in 0..SPONGE_WIDTH {
+            let d = F::from_canonical_u64(F::INTERNAL_MATRIX_DIAG_M_1[i]);
+            matrix[i][i] = (d + F::ONE).to_canonical_u64();
+        }
+
+        let input = F::rand_arr::<SPONGE_WIDTH>();
+        let mut state = input;
+        F::internal_linear_layer_field(&mut state);
+        assert_eq!(state, mat_vec(&matrix, &input));
+    }
+
+    #[test]
+    fn test_vectors() {
+        // Test inputs are:
+        // 1. all zeros
+        // 2. range 0..WIDTH
+        // 3. all -1's
+        // 4. random elements of GoldilocksField.
+        // These pin our custom Poseidon2 instance; there is no reference implementation to compare
+        // against, so the expected outputs were computed with this implementation.
+
+        let neg_one: u64 = F::NEG_ONE.to_canonical_u64();
+
+        #[rustfmt::skip]
+        let test_vectors12: Vec<([u64; 12], [u64; 12])> = vec![
+            ([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, ],
+             [0xe3a251681ab751ab, 0x3bac09129cc64703, 0x9e56a99ded04a1d5, 0xc8f1f4eafe02e8d1,
+              0xa73bf54bb6943b3e, 0xad92c40981fe8672, 0xb9332b408c3880f9, 0x1016c7e462c68ef6,
+              0x2057837f2f673def, 0x82756f541fc86c89, 0x47fbdce4642619f6, 0x83b2644a0335d880, ]),
+            ([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, ],
+             [0x8e819ee0b310a010, 0xead83786160bb057, 0xf5304b19bf29296e, 0x1d221cddb7f29abb,
+              0x6364e32bfbd86fc1, 0x83fa85d4bd144b75, 0x6aab63cbf3662999, 0xe0c048bbe090c538,
+              0x61443cdfcf4dd94a, 0x65fade40039144f9, 0xa1458e9b51670445, 0xc41c22015f3b9354, ]),
+            ([neg_one, neg_one, neg_one, neg_one,
+              neg_one, neg_one, neg_one, neg_one,
+              neg_one, neg_one, neg_one, neg_one, ],
+             [0x220845a2714cfbfd, 0xbf0df241595e77b7, 0x434d69d430c09109, 0xa7b02cb50e697a57,
+              0xfd0038a575317c7a, 0xacfb14e31fd0973f, 0xe2f576007451e8fa, 0x3e55cbb46d755bdd,
+              0xef0e49308f71f87d, 0x72b9647d27b3c9c5, 0xa5814b7a7fa93dfc, 0x88fcb7016e425865, ]),
+            ([0x8ccbbbea4fe5d2b7, 0xc2af59ee9ec49970, 0x90f7e1a9e658446a, 0xdcc0630a3ab8b1b8,
+              0x7ff8256bca20588c, 0x5d99a7ca0c44ecfb, 0x48452b17a70fbee3, 0xeb09d654690b6c88,
+              0x4a55d3a39c676a88, 0xc0407a38d2285139, 0xa234bac9356386d1, 0xe1633f2bad98a52f, ],
+             [0x861836f38421ab3d, 0x80a0a38ea6ffba86, 0x1d4d2ef9fd7f7647, 0x06f8fbbfdc408485,
+              0x5de64dd1164ad70b, 0xc75681b89726a0c0, 0xfb5622ea0f33f3e7, 0xb48578967d70bf08,
+              0x5138de9786e3a981, 0x40bfe6ff268443a5, 0x83adb1d2e0d34f6b, 0xdf4d7660beb75d59, ]),
+        ];
+
+        for (input, expected_output) in test_vectors12 {
+            let output = F::poseidon2(input.map(F::from_canonical_u64));
+            assert_eq!(output, expected_output.map(F::from_canonical_u64));
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Implementation of the Rescue-Prime permutation, as described in
+//! https://eprint.iacr.org/2020/1143.pdf
+//!
+//! We use a width of 12 with `alpha = 7` and 7 rounds. Each round applies the S-box `x^alpha`, the
+//! linear layer, a constant layer, the inverse S-box `x^(1/alpha)`, the linear layer again and a
+//! second constant layer. For the linear layer we reuse Poseidon's MDS matrix, which lets the
+//! recursive gate use `PoseidonMdsGate`.
+//!
+//! This is a custom instance rather than the reference Rescue-Prime instance: the MDS matrix is
+//! Poseidon's and the round constants are sampled with ChaCha8 from a fixed seed (see
+//! `generate_constants`), so digests do not match those of the reference implementation. The
+//! types are named accordingly, and the test vectors below pin this instance.
+
+use plonky2_field::extension::{Extendable, FieldExtension};
+use plonky2_field::goldilocks_field::GoldilocksField;
+use plonky2_field::types::Field;
+
+use crate::gates::rescue_prime::CustomRescuePrimeGate;
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation, SPONGE_WIDTH};
+use crate::hash::poseidon::Poseidon;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+pub const N_ROUNDS: usize = 7;
+pub const ALPHA: u64 = 7;
+const WIDTH: usize = SPONGE_WIDTH;
+
+/// Round constants, `2 * WIDTH` per round: the first `WIDTH` are added after the S-box and the
+/// next `WIDTH` after the inverse S-box. These were generated by running
+/// `generate_constants 168 2`.
+#[rustfmt::skip]
+pub const ROUND_CONSTANTS: [u64; 2 * WIDTH * N_ROUNDS] = [
+    0xe19f8bc84fea8ffc, 0x8c65f8c8f94e6c67, 0xca0c57423e36f4d6, 0xdb7ab42f5f8fbe92,
+    0x38daa77ff5af83c1, 0xad007776237e70bf, 0xee816b59333c8559, 0x043c69e54fd2503d,
+    0x2c0bbbf52fb838ea, 0x3a9b74acd70d9dd5, 0xc04483d5d4d449d2, 0xf96b0a3977e69509,
+    0x4f4e19c9eb069930, 0xcdde195191ea55e3, 0x02095913e3b43b9e, 0x1a1b42f354dccc39,
+    0xb6f3204e06228558, 0xde44a32c11128d47, 0xaff477254610b873, 0xda51af390455f20e,
+    0x03f5b38726bda8dc, 0x93d234ab2cf0a508, 0xb16906e33a275f5d, 0x2bf81a6e5fb0d1b2,
+    0x9d7a5c62d01a6ca4, 0x6ddc5a9037163d53, 0xbd941ef65cc0754e, 0x032181df2befc6ef,
+    0x9c1ffdabbbb54be8, 0x10562f9780ee1141, 0x1510bdcca0360620, 0x23ef5a595c150785,
+    0x7207c243981b2480, 0x31974821abb7f1e4, 0xff29d3598a6e0313, 0x0ed4637433815b0c,
+    0xaa49fd9399567b5e, 0x3e593d372d74ebd6, 0x9537c4d9769b8c0e, 0xae9e20c694fa422d,
+    0xf92dd8c004b89044, 0xbc8b65346a3620d8, 0x1ce9122ad9f9ba1d, 0x5bb87c93ecfc9645,
+    0x46fea225efff5d47, 0xf9286e200dc77326, 0x331b07c273411efd, 0xbdfb4a2dd6a51a7a,
+    0x1434a0c9f76ac0f5, 0x2e7c314e940d49d9, 0x359f91b611ac049b, 0x5d68fa622fb648f7,
+    0xebbe2089e9aa9c72, 0x21fc7b7a0bb8c4b9, 0xeebf1696bacb8c6b, 0xb77e3b179a292bba,
+    0xafefc9bc54708cd6, 0xb0b18f295c72e4cd, 0xda0e7c25ba45509a, 0x5549c88c5b138f6f,
+    0x026ee2c5cf491f21, 0xa172a08bb38d596c, 0x003cd9165f2dbf05, 0x84bd50eae6bc97fd,
+    0x2c5c3bfec03a35b5, 0x04686c16319384cb, 0x41275a2fe96125a3, 0x716c8b2f0488e926,
+    0x250bde22447deb17, 0x12089591fdc2f584, 0x3b927b6959ff05db, 0xff97018eea147e11,
+    0x78d1f2f7e9d153fc, 0x7bbd81a361118c3e, 0xcbe90a2104fbf473, 0x4975c4163daafbdd,
+    0x873000efe5a72a1c, 0x4dff46a0a641275a, 0x50f6ad4f27b189c2, 0x1f697bf5aa49fec4,
+    0x7800344ee2db2716, 0x2f205b686ad02045, 0x10f9fac35a00ead4, 0x5c2671da30dcf6be,
+    0x74f142b8888bbac2, 0x959d67759d0965d6, 0x91a585c69553338a, 0x4513b84b75cb0050,
+    0x095c7a711c8f9058, 0x2778b8a3e7b0c46c, 0xf10f39ed0f973a72, 0xcc9b9387d4b9d7a3,
+    0x2dc96278c08a203f, 0x9bd6f85ba3803c08, 0x16e5539eb68e9397, 0xa51a5b16498a89e2,
+    0xfa1720c34cd9f552, 0x75897072dd7d5c1b, 0xf01cea4810748c3d, 0x01eeb1e2222d1b13,
+    0xcdaab0a6a62fe915, 0x6133868e72c3e269, 0x236f8379ed8d7e14, 0x7c69a718a73644ff,
+    0xd7f5f6668eb9878f, 0xc421ac537d6fa6f4, 0x784640627ac05754, 0x5885821d35d5f09b,
+    0x671c0a999895b8d5, 0xbbc0f531d0ac91fb, 0x7333f09e8bd87330, 0x086ca09624a781ef,
+    0x9e11dbc3efaaaa50, 0x39da37d74888386a, 0x4fbfce896d3839dd, 0x5e607900a5f067f4,
+    0xa78b0a90bfb13aac, 0x85fbfc835bc8563b, 0xf44698627779dd0e, 0x694d9f8d00e1a8dd,
+    0x9b3f41978d4334e9, 0x5d5826c427fd8688, 0x5b7d924912e3818e, 0xe43323fbda96a1fe,
+    0x87ec65c3862fa192, 0xf1fefae7aa70d6ed, 0x95ad72e9467c7f3e, 0x033d8407ea7554e8,
+    0x3939822cc2eba193, 0xc2505d982bdf673d, 0xa739be672536723e, 0xe547e7e79261242e,
+    0xd68847b10329f91b, 0x6d6997007f8c89f4, 0x38f705b62dd73072, 0x41a9a48f769e40dd,
+    0xd895cbaa7d0543b3, 0xd11a34a5d3f2c77c, 0x77dedc0c946d8ca9, 0x6183d9cee555b398,
+    0xd2bc8cee9aabc4fe, 0xcfd67ca54dc15c90, 0xd6f389fcb10b9cc1, 0xd32df96f606dabae,
+    0x7b04d91815bffc3a, 0x25b823548e2f8ee2, 0x155c83bf7ea81621, 0x56988e727a72a3a9,
+    0x9e7b580ccab2978a, 0x2790732a2586b086, 0xf4d6c7ad67d607c6, 0x8a39d26ffb2b44ce,
+    0xfa7c22e9035ee831, 0x5e3b3c1f090a87de, 0x52a7535083b66bc7, 0xdd7622c6772c22cd,
+    0x3a36efc6f3760bbd, 0x9fec5c8a4f2e777a, 0x8dc8914f2b51cc1a, 0xf86a9e87e3fc4a0d,
+    0xa85a8919ddd1909e, 0x58ad8e80a6067bc3, 0x1bdaec88c891897f, 0x6cbbaec22fea3e85,
+    0xba109f86a5cad408, 0x6cc12a6098049780, 0x433aea7256d64912, 0x8a10ff006f123f76,
+];
+
+pub trait CustomRescuePrime: Poseidon {
+    /// The inverse of `ALPHA` modulo `Self::ORDER - 1`, so that `x |--> x^ALPHA_INV` inverts the
+    /// S-box.
+    const ALPHA_INV: u64;
+
+    /// Adds the `i`-th set of round constants, for `i < 2 * N_ROUNDS`.
+    fn rescue_constant_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; WIDTH],
+        i: usize,
+    ) {
+        for j in 0..WIDTH {
+            state[j] += F::from_canonical_u64(ROUND_CONSTANTS[WIDTH * i + j]);
+        }
+    }
+
+    /// Recursive version of `rescue_constant_layer_field`.
+    fn rescue_constant_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; WIDTH],
+        i: usize,
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for j in 0..WIDTH {
+            let c = ROUND_CONSTANTS[WIDTH * i + j];
+            let c = Self::Extension::from_canonical_u64(c);
+            let c = builder.constant_extension(c);
+            state[j] = builder.add_extension(state[j], c);
+        }
+    }
+
+    fn rescue_prime(input: [Self; WIDTH]) -> [Self; WIDTH] {
+        let mut state = input;
+
+        for r in 0..N_ROUNDS {
+            <Self as Poseidon>::sbox_layer(&mut state);
+            state = <Self as Poseidon>::mds_layer(&state);
+            Self::rescue_constant_layer_field(&mut state, 2 * r);
+
+            state = state.map(|x| x.exp_u64(Self::ALPHA_INV));
+            state = <Self as Poseidon>::mds_layer(&state);
+            Self::rescue_constant_layer_field(&mut state, 2 * r + 1);
+        }
+
+        state
+    }
+}
+
+impl CustomRescuePrime for GoldilocksField {
+    const ALPHA_INV: u64 = 10540996611094048183;
+}
+
+pub struct CustomRescuePrimePermutation;
+impl<F: RichField + CustomRescuePrime> PlonkyPermutation<F> for CustomRescuePrimePermutation {
+    fn permute(input: [F; SPONGE_WIDTH]) -> [F; SPONGE_WIDTH] {
+        F::rescue_prime(input)
+    }
+}
+
+/// Rescue-Prime hash function.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CustomRescuePrimeHash;
+impl<F: RichField + CustomRescuePrime> Hasher<F> for CustomRescuePrimeHash {
+    const HASH_SIZE: usize = 4 * 8;
+    type Hash = HashOut<F>;
+    type Permutation = CustomRescuePrimePermutation;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        hash_n_to_hash_no_pad::<F, Self::Permutation>(input)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        compress::<F, Self::Permutation>(left, right)
+    }
+}
+
+impl<F: RichField + CustomRescuePrime> AlgebraicHasher<F> for CustomRescuePrimeHash {
+    fn permute_swapped<const D: usize>(
+        inputs: [Target; SPONGE_WIDTH],
+        swap: BoolTarget,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> [Target; SPONGE_WIDTH]
+    where
+        F: RichField + Extendable<D>,
+    {
+        let gate_type = CustomRescuePrimeGate::<F, D>::new();
+        let gate = builder.add_gate(gate_type, vec![]);
+
+        let swap_wire = CustomRescuePrimeGate::<F, D>::WIRE_SWAP;
+        let swap_wire = Target::wire(gate, swap_wire);
+        builder.connect(swap.target, swap_wire);
+
+        // Route input wires.
+        for i in 0..SPONGE_WIDTH {
+            let in_wire = CustomRescuePrimeGate::<F, D>::wire_input(i);
+            let in_wire = Target::wire(gate, in_wire);
+            builder.connect(inputs[i], in_wire);
+        }
+
+        // Collect output wires.
+        (0..SPONGE_WIDTH)
+            .map(|i| Target::wire(gate, CustomRescuePrimeGate::<F, D>::wire_output(i)))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::goldilocks_field::GoldilocksField as F;
+    use plonky2_field::types::{Field, Field64, PrimeField64};
+
+    use crate::hash::rescue_prime::{CustomRescuePrime, ALPHA};
+
+    #[test]
+    fn inverse_sbox() {
+        let order_minus_one = (F::ORDER - 1) as u128;
+        assert_eq!((F::ALPHA_INV as u128 * ALPHA as u128) % order_minus_one, 1);
+
+        let x = F::rand();
+        assert_eq!(x.exp_u64(F::ALPHA_INV).exp_u64(ALPHA), x);
+    }
+
+    #[test]
+    fn test_vectors() {
+        // Test inputs are:
+        // 1. all zeros
+        // 2. range 0..WIDTH
+        // 3. all -1's
+        // 4. random elements of GoldilocksField.
+        // These pin our custom Rescue-Prime instance; there is no reference implementation to compare
+        // against, so the expected outputs were computed with this implementation.
+
+        let neg_one: u64 = F::NEG_ONE.to_canonical_u64();
+
+        #[rustfmt::skip]
+        let test_vectors12: Vec<([u64; 12], [u64; 12])> = vec![
+            ([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, ],
+             [0x56870142bc5966af, 0x24ada79c1d02db64, 0x2293522544cb98c8, 0xe21330146cba7bc1,
+              0x67d67985f632013f, 0x1a212576d58792f9, 0x226611d9ddf13bdb, 0xf625156de21c0e88,
+              0xe5bac7809d16c6df, 0xd3d7b1c4cc29b012, 0xb23dc418903926b7, 0x13778ebb8b10569c, ]),
+            ([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, ],
+             [0x16c5153094ee7652, 0x1c6772e5c39d0680, 0x42cf761ae572f341, 0x8e216906f57e4dec,
+              0xc7edfe17109067af, 0x084ea7615dd2fe51, 0x8bce6c4f44ceda8a, 0x9af9ed60ae7b3763,
+              0x1d6f181e97eced00, 0x943d732c6271b22e, 0xf3e55cde27a4dc7d, 0x8ce0280cd653742e, ]),
+            ([neg_one, neg_one, neg_one, neg_one,
+              neg_one, neg_one, neg_one, neg_one,
+              neg_one, neg_one, neg_one, neg_one, ],
+             [0xd74ca905646f7e8b, 0x55283b54292ed637, 0x05bbcce297e1d8c3, 0x0a884229840b6662,
+              0xca39c4247e447d79, 0x02bc85359d5b0c11, 0x509fac6633462181, 0xb2c1b87fdd05cf1b,
+              0x5723547606928cf3, 0x752434810c4e6d7e, 0x482d6fded483077e, 0x4f87c3bb01d660e4, ]),
+            ([0x8ccbbbea4fe5d2b7, 0xc2af59ee9ec49970, 0x90f7e1a9e658446a, 0xdcc0630a3ab8b1b8,
+              0x7ff8256bca20588c, 0x5d99a7ca0c44ecfb, 0x48452b17a70fbee3, 0xeb09d654690b6c88,
+              0x4a55d3a39c676a88, 0xc0407a38d2285139, 0xa234bac9356386d1, 0xe1633f2bad98a52f, ],
+             [0x5d828810f5915cdf, 0x05fc523ce65b1a89, 0x6ff1a62dfd4478fd, 0x2557b5795e7e52a8,
+              0x785a3f5c1e249816, 0xfb9bce734c95f52e, 0x31a6935c39b6253f, 0x9409a95a8a02b7f6,
+              0x19ad1a5d1796b08b, 0x922d47025a8c37a8, 0xae1525bff6627898, 0x9f54dbaf92074162, ]),
+        ];
+
+        for (input, expected_output) in test_vectors12 {
+            let output = F::rescue_prime(input.map(F::from_canonical_u64));
+            assert_eq!(output, expected_output.map(F::from_canonical_u64));
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Sparse Merkle trees, i.e. Merkle trees with a leaf for each 256-bit key, most of which are empty.
+//!
+//! The key, as a little-endian 256-bit integer, is the index of its leaf, so its bit `i` selects
+//! the child at layer `i` from the bottom. Empty leaves have a zero digest, and other leaves the
+//! digest `hash_no_pad(value)`. Since every key has a leaf, the Merkle proof of a key proves either
+//! that it has a given value (inclusion) or that it has none (non-inclusion).
+
+use std::collections::HashMap;
+
+use anyhow::{ensure, Result};
+use plonky2_field::extension::Extendable;
+
+use crate::hash::hash_types::{HashOutTarget, RichField};
+use crate::hash::merkle_proofs::{MerkleProof, MerkleProofTarget};
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, GenericHashOut, Hasher};
+
+/// The number of layers of a sparse Merkle tree, i.e. the number of bits of its keys.
+pub const SPARSE_MERKLE_TREE_DEPTH: usize = 256;
+
+/// A 256-bit key, as little-endian 32-bit words.
+pub type SparseMerkleKey = [u32; 8];
+
+/// A 256-bit key, as little-endian 32-bit words. The `keccak256_field_elements` gadget outputs
+/// hashes in this form, so they can be used as keys directly.
+pub type SparseMerkleKeyTarget = [Target; 8];
+
+fn key_bit(key: &SparseMerkleKey, i: usize) -> bool {
+    (key[i / 32] >> (i % 32)) & 1 == 1
+}
+
+/// The key of the node at layer `layer` above the given key's leaf, i.e. the key with its `layer`
+/// low bits cleared.
+fn node_key(key: &SparseMerkleKey, layer: usize) -> SparseMerkleKey {
+    std::array::from_fn(|w| {
+        let cleared_bits = layer.saturating_sub(32 * w).min(32);
+        if cleared_bits == 32 {
+            0
+        } else {
+            key[w] & (u32::MAX << cleared_bits)
+        }
+    })
+}
+
+fn empty_leaf_digest<F: RichField, H: Hasher<F>>() -> H::Hash {
+    H::Hash::from_bytes(&vec![0; H::HASH_SIZE])
+}
+
+fn leaf_digest<F: RichField, H: Hasher<F>>(value: Option<&[F]>) -> H::Hash {
+    value.map_or_else(empty_leaf_digest::<F, H>, H::hash_no_pad)
+}
+
+/// A sparse Merkle tree, storing only its non-empty leaves and the nodes above them.
+#[derive(Clone, Debug)]
+pub struct SparseMerkleTree<F: RichField, H: Hasher<F>> {
+    leaves: HashMap<SparseMerkleKey, Vec<F>>,
+    /// The digests of non-empty nodes, indexed by their layer and by `node_key`.
+    nodes: HashMap<(usize, SparseMerkleKey), H::Hash>,
+    /// The digest of an empty subtree at each layer.
+    empty_digests: Vec<H::Hash>,
+}
+
+impl<F: RichField, H: Hasher<F>> Default for SparseMerkleTree<F, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: RichField, H: Hasher<F>> SparseMerkleTree<F, H> {
+    /// Creates an empty tree.
+    pub fn new() -> Self {
+        let mut empty_digests = Vec::with_capacity(SPARSE_MERKLE_TREE_DEPTH + 1);
+        empty_digests.push(empty_leaf_digest::<F, H>());
+        for i in 0..SPARSE_MERKLE_TREE_DEPTH {
+            empty_digests.push(H::two_to_one(empty_digests[i], empty_digests[i]));
+        }
+        Self {
+            leaves: HashMap::new(),
+            nodes: HashMap::new(),
+            empty_digests,
+        }
+    }
+
+    fn node_digest(&self, layer: usize, key: &SparseMerkleKey) -> H::Hash {
+        self.nodes
+            .get(&(layer, *key))
+            .copied()
+            .unwrap_or(self.empty_digests[layer])
+    }
+
+    pub fn root(&self) -> H::Hash {
+        self.node_digest(SPARSE_MERKLE_TREE_DEPTH, &[0; 8])
+    }
+
+    /// The number of non-empty leaves.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn get(&self, key: &SparseMerkleKey) -> Option<&[F]> {
+        self.leaves.get(key).map(|v| v.as_slice())
+    }
+
+    /// Sets the value of a key, returning its previous value if any.
+    pub fn insert(&mut self, key: SparseMerkleKey, value: Vec<F>) -> Option<Vec<F>> {
+        self.update_path(&key, leaf_digest::<F, H>(Some(&value)));
+        self.leaves.insert(key, value)
+    }
+
+    /// Empties the leaf of a key, returning its previous value if any.
+    pub fn remove(&mut self, key: &SparseMerkleKey) -> Option<Vec<F>> {
+        self.update_path(key, empty_leaf_digest::<F, H>());
+        self.leaves.remove(key)
+    }
+
+    /// Sets the digest of a leaf and recomputes the nodes above it.
+    fn update_path(&mut self, key: &SparseMerkleKey, leaf_digest: H::Hash) {
+        let mut digest = leaf_digest;
+        for layer in 0..=SPARSE_MERKLE_TREE_DEPTH {
+            let node = node_key(key, layer);
+            if digest == self.empty_digests[layer] {
+                self.nodes.remove(&(layer, node));
+            } else {
+                self.nodes.insert((layer, node), digest);
+            }
+            if layer == SPARSE_MERKLE_TREE_DEPTH {
+                break;
+            }
+
+            let mut sibling = node;
+            sibling[layer / 32] ^= 1 << (layer % 32);
+            let sibling_digest = self.node_digest(layer, &sibling);
+            digest = if key_bit(key, layer) {
+                H::two_to_one(sibling_digest, digest)
+            } else {
+                H::two_to_one(digest, sibling_digest)
+            };
+        }
+    }
+
+    /// Creates a Merkle proof for the leaf of a key, which proves its value if it has one, and its
+    /// absence otherwise.
+    pub fn prove(&self, key: &SparseMerkleKey) -> MerkleProof<F, H> {
+        let siblings = (0..SPARSE_MERKLE_TREE_DEPTH)
+            .map(|layer| {
+                let mut sibling = node_key(key, layer);
+                sibling[layer / 32] ^= 1 << (layer % 32);
+                self.node_digest(layer, &sibling)
+            })
+            .collect();
+        MerkleProof { siblings }
+    }
+}
+
+/// Computes the root of a sparse Merkle tree from the digest of a key's leaf and its siblings.
+fn sparse_merkle_root<F: RichField, H: Hasher<F>>(
+    key: &SparseMerkleKey,
+    leaf_digest: H::Hash,
+    proof: &MerkleProof<F, H>,
+) -> Result<H::Hash> {
+    ensure!(
+        proof.len() == SPARSE_MERKLE_TREE_DEPTH,
+        "Sparse Merkle proofs should have one sibling per layer."
+    );
+    let mut digest = leaf_digest;
+    for (layer, &sibling_digest) in proof.siblings.iter().enumerate() {
+        digest = if key_bit(key, layer) {
+            H::two_to_one(sibling_digest, digest)
+        } else {
+            H::two_to_one(digest, sibling_digest)
+        };
+    }
+    Ok(digest)
+}
+
+/// Verifies that the given key has the given value in the sparse Merkle tree with the given root.
+pub fn verify_sparse_merkle_inclusion<F: RichField, H: Hasher<F>>(
+    key: &SparseMerkleKey,
+    value: &[F],
+    root: H::Hash,
+    proof: &MerkleProof<F, H>,
+) -> Result<()> {
+    ensure!(
+        sparse_merkle_root(key, leaf_digest::<F, H>(Some(value)), proof)? == root,
+        "Invalid sparse Merkle inclusion proof."
+    );
+    Ok(())
+}
+
+/// Verifies that the given key has no value in the sparse Merkle tree with the given root.
+pub fn verify_sparse_merkle_non_inclusion<F: RichField, H: Hasher<F>>(
+    key: &SparseMerkleKey,
+    root: H::Hash,
+    proof: &MerkleProof<F, H>,
+) -> Result<()> {
+    ensure!(
+        sparse_merkle_root(key, empty_leaf_digest::<F, H>(), proof)? == root,
+        "Invalid sparse Merkle non-inclusion proof."
+    );
+    Ok(())
+}
+
+/// Verifies that changing the value of the given key from `old_value` to `new_value` turns the
+/// sparse Merkle tree with root `old_root` into the one with root `new_root`. A value of `None`
+/// stands for an empty leaf, so this also covers insertions and removals.
+pub fn verify_sparse_merkle_update<F: RichField, H: Hasher<F>>(
+    key: &SparseMerkleKey,
+    old_value: Option<&[F]>,
+    new_value: Option<&[F]>,
+    old_root: H::Hash,
+    new_root: H::Hash,
+    proof: &MerkleProof<F, H>,
+) -> Result<()> {
+    ensure!(
+        sparse_merkle_root(key, leaf_digest::<F, H>(old_value), proof)? == old_root
+            && sparse_merkle_root(key, leaf_digest::<F, H>(new_value), proof)? == new_root,
+        "Invalid sparse Merkle update proof."
+    );
+    Ok(())
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Adds a virtual Merkle proof for a sparse Merkle tree.
+    pub fn add_virtual_sparse_merkle_proof(&mut self) -> MerkleProofTarget {
+        MerkleProofTarget {
+            siblings: self.add_virtual_hashes(SPARSE_MERKLE_TREE_DEPTH),
+        }
+    }
+
+    /// The little-endian bits of a key, which are range checked.
+    fn sparse_merkle_key_bits(&mut self, key: SparseMerkleKeyTarget) -> Vec<BoolTarget> {
+        key.into_iter()
+            .flat_map(|word| self.split_le(word, 32))
+            .collect()
+    }
+
+    fn sparse_merkle_leaf_digest<H: AlgebraicHasher<F>>(
+        &mut self,
+        value: Option<Vec<Target>>,
+    ) -> HashOutTarget {
+        match value {
+            Some(value) => self.hash_n_to_hash_no_pad::<H>(value),
+            None => {
+                let zero = self.zero();
+                HashOutTarget::from_partial(&[], zero)
+            }
+        }
+    }
+
+    fn verify_sparse_merkle_leaf<H: AlgebraicHasher<F>>(
+        &mut self,
+        key_bits: &[BoolTarget],
+        value: Option<Vec<Target>>,
+        root: HashOutTarget,
+        proof: &MerkleProofTarget,
+    ) {
+        assert_eq!(proof.siblings.len(), SPARSE_MERKLE_TREE_DEPTH);
+        let leaf_digest = self.sparse_merkle_leaf_digest::<H>(value);
+        let computed_root = self.merkle_path_top::<H>(leaf_digest, key_bits, &proof.siblings);
+        self.connect_hashes(computed_root, root);
+    }
+
+    /// Verifies that the given key has the given value in the sparse Merkle tree with the given
+    /// root.
+    pub fn verify_sparse_merkle_inclusion<H: AlgebraicHasher<F>>(
+        &mut self,
+        key: SparseMerkleKeyTarget,
+        value: Vec<Target>,
+        root: HashOutTarget,
+        proof: &MerkleProofTarget,
+    ) {
+        let key_bits = self.sparse_merkle_key_bits(key);
+        self.verify_sparse_merkle_leaf::<H>(&key_bits, Some(value), root, proof);
+    }
+
+    /// Verifies that the given key has no value in the sparse Merkle tree with the given root.
+    pub fn verify_sparse_merkle_non_inclusion<H: AlgebraicHasher<F>>(
+        &mut self,
+        key: SparseMerkleKeyTarget,
+        root: HashOutTarget,
+        proof: &MerkleProofTarget,
+    ) {
+        let key_bits = self.sparse_merkle_key_bits(key);
+        self.verify_sparse_merkle_leaf::<H>(&key_bits, None, root, proof);
+    }
+
+    /// Verifies that changing the value of the given key from `old_value` to `new_value` turns the
+    /// sparse Merkle tree with root `old_root` into the one with root `new_root`. A value of `None`
+    /// stands for an empty leaf, so this also covers insertions and removals.
+    pub fn verify_sparse_merkle_update<H: AlgebraicHasher<F>>(
+        &mut self,
+        key: SparseMerkleKeyTarget,
+        old_value: Option<Vec<Target>>,
+        new_value: Option<Vec<Target>>,
+        old_root: HashOutTarget,
+        new_root: HashOutTarget,
+        proof: &MerkleProofTarget,
+    ) {
+        let key_bits = self.sparse_merkle_key_bits(key);
+        self.verify_sparse_merkle_leaf::<H>(&key_bits, old_value, old_root, proof);
+        self.verify_sparse_merkle_leaf::<H>(&key_bits, new_value, new_root, proof);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type H = <C as GenericConfig<D>>::InnerHasher;
+
+    fn random_key() -> SparseMerkleKey {
+        thread_rng().gen()
+    }
+
+    #[test]
+    fn test_sparse_merkle_tree() -> Result<()> {
+        let mut tree = SparseMerkleTree::<F, H>::new();
+        let empty_root = tree.root();
+        let keys = [random_key(), random_key(), random_key()];
+        let values = [F::rand_vec(3), F::rand_vec(1), F::rand_vec(6)];
+
+        let mut roots = vec![empty_root];
+        for (key, value) in keys.iter().zip(&values) {
+            let proof = tree.prove(key);
+            verify_sparse_merkle_non_inclusion(key, tree.root(), &proof)?;
+            tree.insert(*key, value.clone());
+            verify_sparse_merkle_update(
+                key,
+                None,
+                Some(value),
+                *roots.last().unwrap(),
+                tree.root(),
+                &proof,
+            )?;
+            roots.push(tree.root());
+        }
+        assert_eq!(tree.len(), keys.len());
+
+        for (key, value) in keys.iter().zip(&values) {
+            let proof = tree.prove(key);
+            verify_sparse_merkle_inclusion(key, value, tree.root(), &proof)?;
+            assert!(verify_sparse_merkle_non_inclusion(key, tree.root(), &proof).is_err());
+            assert!(verify_sparse_merkle_inclusion(key, &[F::ONE], tree.root(), &proof).is_err());
+        }
+
+        // Removing the keys in reverse order goes back through the same roots.
+        for key in keys.iter().rev() {
+            roots.pop();
+            tree.remove(key);
+            assert_eq!(tree.root(), *roots.last().unwrap());
+        }
+        assert!(tree.is_empty());
+        assert!(tree.nodes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_merkle_tree_circuit() -> Result<()> {
+        let mut tree = SparseMerkleTree::<F, H>::new();
+        for _ in 0..10 {
+            tree.insert(random_key(), F::rand_vec(2));
+        }
+        let present_key = random_key();
+        let present_value = F::rand_vec(2);
+        tree.insert(present_key, present_value.clone());
+        let absent_key = random_key();
+        let new_value = F::rand_vec(2);
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        let key_targets = |builder: &mut CircuitBuilder<F, D>,
+                           pw: &mut PartialWitness<F>,
+                           key: &SparseMerkleKey| {
+            let targets: SparseMerkleKeyTarget = builder.add_virtual_target_arr();
+            for (&t, &word) in targets.iter().zip(key) {
+                pw.set_target(t, F::from_canonical_u32(word));
+            }
+            targets
+        };
+        let set_proof =
+            |pw: &mut PartialWitness<F>, target: &MerkleProofTarget, proof: &MerkleProof<F, H>| {
+                for (&t, &h) in target.siblings.iter().zip(&proof.siblings) {
+                    pw.set_hash_target(t, h);
+                }
+            };
+
+        let old_root = builder.add_virtual_hash();
+        pw.set_hash_target(old_root, tree.root());
+
+        // Inclusion.
+        let key = key_targets(&mut builder, &mut pw, &present_key);
+        let value = builder.add_virtual_target_arr::<2>();
+        pw.set_target_arr(value, present_value.try_into().unwrap());
+        let proof = builder.add_virtual_sparse_merkle_proof();
+        set_proof(&mut pw, &proof, &tree.prove(&present_key));
+        builder.verify_sparse_merkle_inclusion::<H>(key, value.to_vec(), old_root, &proof);
+
+        // Non-inclusion, then insertion.
+        let key = key_targets(&mut builder, &mut pw, &absent_key);
+        let proof = builder.add_virtual_sparse_merkle_proof();
+        set_proof(&mut pw, &proof, &tree.prove(&absent_key));
+        builder.verify_sparse_merkle_non_inclusion::<H>(key, old_root, &proof);
+        let value = builder.add_virtual_target_arr::<2>();
+        pw.set_target_arr(value, new_value.clone().try_into().unwrap());
+        tree.insert(absent_key, new_value);
+        let new_root = builder.add_virtual_hash();
+        pw.set_hash_target(new_root, tree.root());
+        builder.verify_sparse_merkle_update::<H>(
+            key,
+            None,
+            Some(value.to_vec()),
+            old_root,
+            new_root,
+            &proof,
+        );
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
@@ -0,0 +1,508 @@
+use std::marker::PhantomData;
+
+pub use plonky2_derive::CircuitVariable;
+use plonky2_field::extension::Extendable;
+
+use crate::hash::hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField};
+use crate::hash::merkle_proofs::MerkleProofTarget;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::witness::Witness;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// A typed group of targets, which can be allocated, assigned, read, made public and connected as
+/// a unit.
+///
+/// It is implemented for the basic target types, Merkle caps and proofs, arrays and tuples, and can
+/// be derived for structs whose fields are all `CircuitVariable`s. By default, the derived `Value`
+/// is a generated `{Name}Value` struct with the same fields; `#[circuit_variable(value = "Type")]`
+/// uses an existing type with the same fields instead. The derived `Size` is a generated
+/// `{Name}Size` struct with the same fields, holding their sizes.
+pub trait CircuitVariable<F: RichField + Extendable<D>, const D: usize>: Sized {
+    /// The value assigned to the variable in a witness.
+    type Value;
+
+    /// What fixes the number of targets of the variable, such as the height of a Merkle cap. It is
+    /// `()` for variables whose number of targets is fixed by their type.
+    type Size;
+
+    /// Adds a new virtual variable of the given size to the circuit. Variables with restricted
+    /// values, such as `BoolTarget`, are range checked.
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, size: Self::Size) -> Self;
+
+    /// Adds a new virtual variable to the circuit, if its size is fixed by its type.
+    fn add_virtual(builder: &mut CircuitBuilder<F, D>) -> Self
+    where
+        Self::Size: FixedSize,
+    {
+        Self::add_virtual_with_size(builder, FixedSize::fixed_size())
+    }
+
+    /// The targets of the variable, in a fixed order.
+    fn targets(&self) -> Vec<Target>;
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &Self::Value);
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> Self::Value;
+
+    fn register_public(&self, builder: &mut CircuitBuilder<F, D>) {
+        builder.register_public_inputs(&self.targets());
+    }
+
+    /// Connects the targets of two variables, which must have the same size.
+    fn connect(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) {
+        let (targets, other_targets) = (self.targets(), other.targets());
+        assert_eq!(
+            targets.len(),
+            other_targets.len(),
+            "Cannot connect variables of different sizes"
+        );
+        for (x, y) in targets.into_iter().zip(other_targets) {
+            builder.connect(x, y);
+        }
+    }
+}
+
+/// The `Size` of variables whose number of targets is fixed by their type, which can be added
+/// without giving their size.
+pub trait FixedSize {
+    fn fixed_size() -> Self;
+}
+
+impl FixedSize for () {
+    fn fixed_size() -> Self {}
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitVariable<F, D> for Target {
+    type Value = F;
+    type Size = ();
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, _size: ()) -> Self {
+        builder.add_virtual_target()
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        vec![*self]
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &F) {
+        witness.set_target(*self, *value);
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> F {
+        witness.get_target(*self)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitVariable<F, D> for BoolTarget {
+    type Value = bool;
+    type Size = ();
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, _size: ()) -> Self {
+        builder.add_virtual_bool_target_safe()
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        vec![self.target]
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &bool) {
+        witness.set_bool_target(*self, *value);
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> bool {
+        witness.get_bool_target(*self)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitVariable<F, D> for HashOutTarget {
+    type Value = HashOut<F>;
+    type Size = ();
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, _size: ()) -> Self {
+        builder.add_virtual_hash()
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        self.elements.to_vec()
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &HashOut<F>) {
+        witness.set_hash_target(*self, *value);
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> HashOut<F> {
+        witness.get_hash_target(*self)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitVariable<F, D> for ExtensionTarget<D> {
+    type Value = F::Extension;
+    type Size = ();
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, _size: ()) -> Self {
+        builder.add_virtual_extension_target()
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        self.to_target_array().to_vec()
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &F::Extension) {
+        witness.set_extension_target(*self, *value);
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> F::Extension {
+        witness.get_extension_target(*self)
+    }
+}
+
+/// The value of a Merkle cap target is the list of digests of the cap, and its size is the height
+/// of the cap.
+impl<F: RichField + Extendable<D>, const D: usize> CircuitVariable<F, D> for MerkleCapTarget {
+    type Value = Vec<HashOut<F>>;
+    type Size = usize;
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, cap_height: usize) -> Self {
+        builder.add_virtual_cap(cap_height)
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        self.0.iter().flat_map(|h| h.elements).collect()
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &Vec<HashOut<F>>) {
+        assert_eq!(self.0.len(), value.len(), "Merkle cap has the wrong size");
+        for (&ht, &h) in self.0.iter().zip(value) {
+            witness.set_hash_target(ht, h);
+        }
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> Vec<HashOut<F>> {
+        self.0
+            .iter()
+            .map(|&ht| witness.get_hash_target(ht))
+            .collect()
+    }
+}
+
+/// The value of a Merkle proof target is the list of sibling digests of the proof, and its size is
+/// the length of the proof.
+impl<F: RichField + Extendable<D>, const D: usize> CircuitVariable<F, D> for MerkleProofTarget {
+    type Value = Vec<HashOut<F>>;
+    type Size = usize;
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, len: usize) -> Self {
+        builder.add_virtual_merkle_proof(len)
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        self.siblings.iter().flat_map(|h| h.elements).collect()
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &Vec<HashOut<F>>) {
+        assert_eq!(
+            self.siblings.len(),
+            value.len(),
+            "Merkle proof has the wrong length"
+        );
+        for (&ht, &h) in self.siblings.iter().zip(value) {
+            witness.set_hash_target(ht, h);
+        }
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> Vec<HashOut<F>> {
+        self.siblings
+            .iter()
+            .map(|&ht| witness.get_hash_target(ht))
+            .collect()
+    }
+}
+
+/// Allows deriving `CircuitVariable` for structs holding a marker.
+impl<F: RichField + Extendable<D>, const D: usize, T> CircuitVariable<F, D> for PhantomData<T> {
+    type Value = PhantomData<T>;
+    type Size = ();
+
+    fn add_virtual_with_size(_builder: &mut CircuitBuilder<F, D>, _size: ()) -> Self {
+        PhantomData
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        vec![]
+    }
+
+    fn set_witness<W: Witness<F>>(&self, _witness: &mut W, _value: &PhantomData<T>) {}
+
+    fn get_witness<W: Witness<F>>(&self, _witness: &W) -> PhantomData<T> {
+        PhantomData
+    }
+}
+
+/// All the elements of an array have the same size.
+impl<F: RichField + Extendable<D>, const D: usize, V: CircuitVariable<F, D>, const N: usize>
+    CircuitVariable<F, D> for [V; N]
+where
+    V::Size: Clone,
+{
+    type Value = [V::Value; N];
+    type Size = V::Size;
+
+    fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, size: V::Size) -> Self {
+        std::array::from_fn(|_| V::add_virtual_with_size(builder, size.clone()))
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        self.iter().flat_map(|v| v.targets()).collect()
+    }
+
+    fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &Self::Value) {
+        for (v, x) in self.iter().zip(value) {
+            v.set_witness(witness, x);
+        }
+    }
+
+    fn get_witness<W: Witness<F>>(&self, witness: &W) -> Self::Value {
+        std::array::from_fn(|i| self[i].get_witness(witness))
+    }
+
+    fn register_public(&self, builder: &mut CircuitBuilder<F, D>) {
+        for v in self {
+            v.register_public(builder);
+        }
+    }
+
+    fn connect(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) {
+        assert_eq!(
+            self.targets().len(),
+            other.targets().len(),
+            "Cannot connect variables of different sizes"
+        );
+        for (v, w) in self.iter().zip(other) {
+            v.connect(builder, w);
+        }
+    }
+}
+
+macro_rules! impl_circuit_variable_for_tuple {
+    ($($v:ident: $i:tt),+) => {
+        impl<F: RichField + Extendable<D>, const D: usize, $($v: CircuitVariable<F, D>),+>
+            CircuitVariable<F, D> for ($($v,)+)
+        {
+            type Value = ($($v::Value,)+);
+            type Size = ($($v::Size,)+);
+
+            fn add_virtual_with_size(builder: &mut CircuitBuilder<F, D>, size: Self::Size) -> Self {
+                ($($v::add_virtual_with_size(builder, size.$i),)+)
+            }
+
+            fn targets(&self) -> Vec<Target> {
+                [$(self.$i.targets()),+].concat()
+            }
+
+            fn set_witness<W: Witness<F>>(&self, witness: &mut W, value: &Self::Value) {
+                $(self.$i.set_witness(witness, &value.$i);)+
+            }
+
+            fn get_witness<W: Witness<F>>(&self, witness: &W) -> Self::Value {
+                ($(self.$i.get_witness(witness),)+)
+            }
+
+            fn register_public(&self, builder: &mut CircuitBuilder<F, D>) {
+                $(self.$i.register_public(builder);)+
+            }
+
+            fn connect(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) {
+                $(self.$i.connect(builder, &other.$i);)+
+            }
+        }
+
+        impl<$($v: FixedSize),+> FixedSize for ($($v,)+) {
+            fn fixed_size() -> Self {
+                ($($v::fixed_size(),)+)
+            }
+        }
+    };
+}
+
+impl_circuit_variable_for_tuple!(A: 0);
+impl_circuit_variable_for_tuple!(A: 0, B: 1);
+impl_circuit_variable_for_tuple!(A: 0, B: 1, C: 2);
+impl_circuit_variable_for_tuple!(A: 0, B: 1, C: 2, E: 3);
+impl_circuit_variable_for_tuple!(A: 0, B: 1, C: 2, E: 3, G: 4);
+impl_circuit_variable_for_tuple!(A: 0, B: 1, C: 2, E: 3, G: 4, H: 5);
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Adds a new virtual `CircuitVariable`.
+    pub fn add_virtual_variable<V: CircuitVariable<F, D>>(&mut self) -> V
+    where
+        V::Size: FixedSize,
+    {
+        V::add_virtual(self)
+    }
+
+    /// Adds a new virtual `CircuitVariable` of the given size.
+    pub fn add_virtual_variable_with_size<V: CircuitVariable<F, D>>(&mut self, size: V::Size) -> V {
+        V::add_virtual_with_size(self, size)
+    }
+
+    /// Connects two `CircuitVariable`s.
+    pub fn connect_variables<V: CircuitVariable<F, D>>(&mut self, x: &V, y: &V) {
+        x.connect(self, y);
+    }
+
+    /// Registers a `CircuitVariable` as public inputs.
+    pub fn register_public_variable<V: CircuitVariable<F, D>>(&mut self, v: &V) {
+        v.register_public(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use super::*;
+    use crate::hash::merkle_tree::MerkleTree;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FE = <C as GenericConfig<D>>::FE;
+
+    #[test]
+    fn test_circuit_variables() -> Result<()> {
+        type V = (
+            Target,
+            BoolTarget,
+            HashOutTarget,
+            [ExtensionTarget<D>; 2],
+            PhantomData<u8>,
+        );
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_variable::<V>();
+        let y = builder.add_virtual_variable::<V>();
+        builder.connect_variables(&x, &y);
+        builder.register_public_variable(&y);
+        let data = builder.build::<C>();
+
+        let value = (
+            F::rand(),
+            true,
+            HashOut::rand(),
+            [FE::rand(), FE::rand()],
+            PhantomData,
+        );
+        let mut pw = PartialWitness::new();
+        x.set_witness(&mut pw, &value);
+        assert_eq!(x.get_witness(&pw), value);
+
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, {
+            let mut pw = PartialWitness::<F>::new();
+            y.set_witness(&mut pw, &value);
+            pw.get_targets(&CircuitVariable::<F, D>::targets(&y))
+        });
+        data.verify(proof)
+    }
+
+    /// A leaf of a Merkle tree, with its index.
+    #[derive(CircuitVariable)]
+    struct LeafTarget([Target; 7], [BoolTarget; 8]);
+
+    #[derive(CircuitVariable)]
+    struct MerkleOpeningTarget {
+        leaf: LeafTarget,
+        proof: MerkleProofTarget,
+        cap: MerkleCapTarget,
+    }
+
+    #[test]
+    fn test_derived_circuit_variables() -> Result<()> {
+        const LOG_N: usize = 8;
+        const CAP_HEIGHT: usize = 2;
+        let leaves = (0..1 << LOG_N).map(|_| F::rand_vec(7)).collect();
+        let tree = MerkleTree::<F, <C as GenericConfig<D>>::Hasher>::new(leaves, CAP_HEIGHT);
+        let index = 123;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let opening = builder.add_virtual_variable_with_size::<MerkleOpeningTarget>(
+            MerkleOpeningTargetSize {
+                leaf: FixedSize::fixed_size(),
+                proof: LOG_N - CAP_HEIGHT,
+                cap: CAP_HEIGHT,
+            },
+        );
+        let LeafTarget(leaf_data, leaf_index_bits) = &opening.leaf;
+        builder.verify_merkle_proof_to_cap::<<C as GenericConfig<D>>::InnerHasher>(
+            leaf_data.to_vec(),
+            leaf_index_bits,
+            &opening.cap,
+            &opening.proof,
+        );
+        builder.register_public_variable(&opening.cap);
+        let data = builder.build::<C>();
+
+        let value = MerkleOpeningTargetValue::<F, D> {
+            leaf: LeafTargetValue(
+                tree.leaves[index].clone().try_into().unwrap(),
+                std::array::from_fn(|i| (index >> i) & 1 == 1),
+            ),
+            proof: tree.prove(index).siblings,
+            cap: tree.cap.0.clone(),
+        };
+        let mut pw = PartialWitness::new();
+        opening.set_witness(&mut pw, &value);
+        let MerkleOpeningTargetValue { proof, cap, .. } =
+            CircuitVariable::<F, D>::get_witness(&opening, &pw);
+        assert_eq!(proof, value.proof);
+        assert_eq!(cap, value.cap);
+
+        let proof = data.prove(pw)?;
+        assert_eq!(
+            proof.public_inputs,
+            tree.cap
+                .0
+                .iter()
+                .flat_map(|h| h.elements)
+                .collect::<Vec<_>>()
+        );
+        data.verify(proof)
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot connect variables of different sizes")]
+    fn test_connect_caps_of_different_heights() {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_variable_with_size::<MerkleCapTarget>(2);
+        let y = builder.add_virtual_variable_with_size::<MerkleCapTarget>(4);
+        builder.connect_variables(&x, &y);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot connect variables of different sizes")]
+    fn test_connect_arrays_of_proofs_of_different_lengths() {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_variable_with_size::<[MerkleProofTarget; 2]>(3);
+        let y = builder.add_virtual_variable_with_size::<[MerkleProofTarget; 2]>(5);
+        builder.connect_variables(&x, &y);
+    }
+
+    #[test]
+    #[should_panic(expected = "Merkle cap has the wrong size")]
+    fn test_set_cap_of_wrong_height() {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let cap = builder.add_virtual_variable_with_size::<MerkleCapTarget>(2);
+        let mut pw = PartialWitness::<F>::new();
+        CircuitVariable::<F, D>::set_witness(&cap, &mut pw, &vec![HashOut::rand(); 2]);
+    }
+}
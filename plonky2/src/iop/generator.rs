@@ -1,6 +1,9 @@
+use std::any::Any;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
+use maybe_rayon::*;
 use plonky2_field::extension::{Extendable, FieldExtension};
 use plonky2_field::types::Field;
 
@@ -8,13 +11,28 @@ use crate::hash::hash_types::{HashOut, HashOutTarget, RichField};
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
-use crate::iop::witness::{PartialWitness, PartitionWitness, Witness};
+use crate::iop::witness::{PartialWitness, PartitionWitness, Witness, WitnessConflict};
 use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
 use crate::plonk::config::GenericConfig;
+use crate::util::serialization::{Buffer, IoResult};
+
+/// Rounds with fewer pending generators than this aren't worth running in parallel.
+const MIN_PARALLEL_ROUND_SIZE: usize = 64;
 
 /// Given a `PartitionWitness` that has only inputs set, populates the rest of the witness using the
 /// given set of generators.
-pub(crate) fn generate_partial_witness<
+///
+/// Generators are run in rounds. The first round runs the generators which don't watch any target,
+/// along with the watchers of the inputs, and each subsequent round runs the watchers of the targets
+/// populated by the previous round. The generators of a wide round run in parallel on the witness as
+/// it was at the start of the round, and their outputs are then merged in order. The generators of a
+/// narrow round run eagerly instead, one after the other, and the watchers of the targets they
+/// populate join the same round, so that deep chains of dependent generators don't pay for a
+/// parallel round each. Either way, the witness doesn't depend on how generators were scheduled.
+///
+/// Returns an error if a generated value, or an input, conflicts with a value already set in the
+/// same partition.
+pub(crate) fn try_generate_partial_witness<
     'a,
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -23,64 +41,104 @@ pub(crate) fn generate_partial_witness<
     inputs: PartialWitness<F>,
     prover_data: &'a ProverOnlyCircuitData<F, C, D>,
     common_data: &'a CommonCircuitData<F, D>,
-) -> PartitionWitness<'a, F> {
+) -> Result<PartitionWitness<'a, F>, WitnessConflict<F>> {
+    try_generate_partial_witness_with_buffer(inputs, prover_data, common_data, Vec::new())
+}
+
+/// Like `try_generate_partial_witness`, but reuses the allocation of `values` for the witness.
+pub(crate) fn try_generate_partial_witness_with_buffer<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inputs: PartialWitness<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+    values: Vec<Option<F>>,
+) -> Result<PartitionWitness<'a, F>, WitnessConflict<F>> {
     let config = &common_data.config;
     let generators = &prover_data.generators;
     let generator_indices_by_watches = &prover_data.generator_indices_by_watches;
 
-    let mut witness = PartitionWitness::new(
+    let mut witness = PartitionWitness::new_with_buffer(
         config.num_wires,
         common_data.degree(),
         &prover_data.representative_map,
+        values,
     );
 
-    for (t, v) in inputs.target_values.into_iter() {
-        witness.set_target(t, v);
-    }
-
-    // Build a list of "pending" generators which are queued to be run. Initially, all generators
-    // are queued.
-    let mut pending_generator_indices: Vec<_> = (0..generators.len()).collect();
-
-    // We also track a list of "expired" generators which have already returned false.
+    // We track a list of "expired" generators which have already returned true.
     let mut generator_is_expired = vec![false; generators.len()];
     let mut remaining_generators = generators.len();
 
-    let mut buffer = GeneratedValues::empty();
+    let mut pending_generator_indices = prover_data.generator_indices_without_watches.clone();
+    merge_generated_values(
+        &mut witness,
+        generator_indices_by_watches,
+        &generator_is_expired,
+        inputs.target_values,
+        &mut pending_generator_indices,
+    )?;
 
     // Keep running generators until we fail to make progress.
     while !pending_generator_indices.is_empty() {
-        let mut next_pending_generator_indices = Vec::new();
+        pending_generator_indices.sort_unstable();
+        pending_generator_indices.dedup();
+        pending_generator_indices.retain(|&generator_idx| !generator_is_expired[generator_idx]);
+
+        if pending_generator_indices.len() < MIN_PARALLEL_ROUND_SIZE {
+            // Run the round eagerly, until the generators left to run are enough for a parallel
+            // round.
+            let mut num_run = 0;
+            while num_run < pending_generator_indices.len()
+                && pending_generator_indices.len() - num_run < MIN_PARALLEL_ROUND_SIZE
+            {
+                let generator_idx = pending_generator_indices[num_run];
+                num_run += 1;
+                if generator_is_expired[generator_idx] {
+                    continue;
+                }
 
-        for &generator_idx in &pending_generator_indices {
-            if generator_is_expired[generator_idx] {
-                continue;
+                let mut buffer = GeneratedValues::empty();
+                if generators[generator_idx].run(&witness, &mut buffer) {
+                    generator_is_expired[generator_idx] = true;
+                    remaining_generators -= 1;
+                }
+                merge_generated_values(
+                    &mut witness,
+                    generator_indices_by_watches,
+                    &generator_is_expired,
+                    buffer.target_values,
+                    &mut pending_generator_indices,
+                )?;
             }
+            pending_generator_indices.drain(..num_run);
+            continue;
+        }
 
-            let finished = generators[generator_idx].run(&witness, &mut buffer);
+        let outputs = pending_generator_indices
+            .par_iter()
+            .map(|&generator_idx| {
+                let mut buffer = GeneratedValues::empty();
+                let finished = generators[generator_idx].run(&witness, &mut buffer);
+                (finished, buffer)
+            })
+            .collect::<Vec<_>>();
+
+        let mut next_pending_generator_indices = Vec::new();
+        for (&generator_idx, (finished, buffer)) in pending_generator_indices.iter().zip(outputs) {
             if finished {
                 generator_is_expired[generator_idx] = true;
                 remaining_generators -= 1;
             }
-
-            // Merge any generated values into our witness, and get a list of newly-populated
-            // targets' representatives.
-            let new_target_reps = buffer
-                .target_values
-                .drain(..)
-                .flat_map(|(t, v)| witness.set_target_returning_rep(t, v));
-
-            // Enqueue unfinished generators that were watching one of the newly populated targets.
-            for watch in new_target_reps {
-                let opt_watchers = generator_indices_by_watches.get(&watch);
-                if let Some(watchers) = opt_watchers {
-                    for &watching_generator_idx in watchers {
-                        if !generator_is_expired[watching_generator_idx] {
-                            next_pending_generator_indices.push(watching_generator_idx);
-                        }
-                    }
-                }
-            }
+            merge_generated_values(
+                &mut witness,
+                generator_indices_by_watches,
+                &generator_is_expired,
+                buffer.target_values,
+                &mut next_pending_generator_indices,
+            )?;
         }
 
         pending_generator_indices = next_pending_generator_indices;
@@ -92,7 +150,46 @@ pub(crate) fn generate_partial_witness<
         remaining_generators,
     );
 
-    witness
+    Ok(witness)
+}
+
+/// Merges generated values into the witness, and enqueues the unfinished generators watching
+/// newly populated targets.
+fn merge_generated_values<F: Field>(
+    witness: &mut PartitionWitness<F>,
+    generator_indices_by_watches: &BTreeMap<usize, Vec<usize>>,
+    generator_is_expired: &[bool],
+    target_values: impl IntoIterator<Item = (Target, F)>,
+    pending_generator_indices: &mut Vec<usize>,
+) -> Result<(), WitnessConflict<F>> {
+    for (t, v) in target_values {
+        if let Some(watch) = witness.set_target_returning_rep(t, v)? {
+            if let Some(watchers) = generator_indices_by_watches.get(&watch) {
+                pending_generator_indices.extend(
+                    watchers
+                        .iter()
+                        .filter(|&&watcher_idx| !generator_is_expired[watcher_idx]),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Given a `PartitionWitness` that has only inputs set, populates the rest of the witness using the
+/// given set of generators. Panics if generators set copy constrained targets to different values.
+pub fn generate_partial_witness<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inputs: PartialWitness<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+) -> PartitionWitness<'a, F> {
+    try_generate_partial_witness(inputs, prover_data, common_data)
+        .unwrap_or_else(|conflict| panic!("{conflict}"))
 }
 
 /// A generator participates in the generation of the witness.
@@ -105,6 +202,17 @@ pub trait WitnessGenerator<F: Field>: 'static + Send + Sync + Debug {
     /// flag is true, the generator will never be run again, otherwise it will be queued for another
     /// run next time a target in its watch list is populated.
     fn run(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> bool;
+
+    /// Returns this generator as `Any`, so that a `WitnessGeneratorSerializer` can identify its
+    /// concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Serializes this generator, so that it can be reconstructed with `deserialize`.
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()>;
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self>
+    where
+        Self: Sized;
 }
 
 /// Values generated by a generator invocation.
@@ -209,6 +317,12 @@ pub trait SimpleGenerator<F: Field>: 'static + Send + Sync + Debug {
 
     fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>);
 
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()>;
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self>
+    where
+        Self: Sized;
+
     fn adapter(self) -> SimpleGeneratorAdapter<F, Self>
     where
         Self: Sized,
@@ -239,11 +353,23 @@ impl<F: Field, SG: SimpleGenerator<F>> WitnessGenerator<F> for SimpleGeneratorAd
             false
         }
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        self.inner.serialize(dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        Ok(SG::deserialize(src)?.adapter())
+    }
 }
 
 /// A generator which copies one wire to another.
 #[derive(Debug)]
-pub(crate) struct CopyGenerator {
+pub struct CopyGenerator {
     pub(crate) src: Target,
     pub(crate) dst: Target,
 }
@@ -257,11 +383,25 @@ impl<F: Field> SimpleGenerator<F> for CopyGenerator {
         let value = witness.get_target(self.src);
         out_buffer.set_target(self.dst, value);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.src)?;
+        dst.write_target(self.dst)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let src_target = src.read_target()?;
+        let dst_target = src.read_target()?;
+        Ok(Self {
+            src: src_target,
+            dst: dst_target,
+        })
+    }
 }
 
 /// A generator for including a random value
 #[derive(Debug)]
-pub(crate) struct RandomValueGenerator {
+pub struct RandomValueGenerator {
     pub(crate) target: Target,
 }
 
@@ -275,11 +415,20 @@ impl<F: Field> SimpleGenerator<F> for RandomValueGenerator {
 
         out_buffer.set_target(self.target, random_value);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.target)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let target = src.read_target()?;
+        Ok(Self { target })
+    }
 }
 
 /// A generator for testing if a value equals zero
 #[derive(Debug)]
-pub(crate) struct NonzeroTestGenerator {
+pub struct NonzeroTestGenerator {
     pub(crate) to_test: Target,
     pub(crate) dummy: Target,
 }
@@ -300,11 +449,22 @@ impl<F: Field> SimpleGenerator<F> for NonzeroTestGenerator {
 
         out_buffer.set_target(self.dummy, dummy_value);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_target(self.to_test)?;
+        dst.write_target(self.dummy)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let to_test = src.read_target()?;
+        let dummy = src.read_target()?;
+        Ok(Self { to_test, dummy })
+    }
 }
 
 /// Generator used to fill an extra constant.
 #[derive(Debug, Clone)]
-pub(crate) struct ConstantGenerator<F: Field> {
+pub struct ConstantGenerator<F: Field> {
     pub row: usize,
     pub constant_index: usize,
     pub wire_index: usize,
@@ -317,7 +477,7 @@ impl<F: Field> ConstantGenerator<F> {
     }
 }
 
-impl<F: Field> SimpleGenerator<F> for ConstantGenerator<F> {
+impl<F: RichField> SimpleGenerator<F> for ConstantGenerator<F> {
     fn dependencies(&self) -> Vec<Target> {
         vec![]
     }
@@ -325,4 +485,79 @@ impl<F: Field> SimpleGenerator<F> for ConstantGenerator<F> {
     fn run_once(&self, _witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
         out_buffer.set_target(Target::wire(self.row, self.wire_index), self.constant);
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.constant_index)?;
+        dst.write_usize(self.wire_index)?;
+        dst.write_field(self.constant)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let constant_index = src.read_usize()?;
+        let wire_index = src.read_usize()?;
+        let constant = src.read_field()?;
+        Ok(Self {
+            row,
+            constant_index,
+            wire_index,
+            constant,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::types::Field;
+
+    use super::*;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_generate_partial_witness() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        // Independent chains of operations, which are generated in parallel.
+        let outputs = (1..=10)
+            .map(|i| {
+                let c = builder.constant(F::from_canonical_usize(i));
+                let mut y = x;
+                for _ in 0..i {
+                    y = builder.mul_add(y, x, c);
+                }
+                y
+            })
+            .collect::<Vec<_>>();
+        let data = builder.build::<C>();
+        assert!(!data
+            .prover_only
+            .generator_indices_without_watches
+            .is_empty());
+
+        let x_value = F::from_canonical_u64(3);
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, x_value);
+        let witness = generate_partial_witness(pw.clone(), &data.prover_only, &data.common);
+        for (i, &y) in (1..=10).zip(&outputs) {
+            let c = F::from_canonical_usize(i);
+            let expected = (0..i).fold(x_value, |y, _| y * x_value + c);
+            assert_eq!(witness.get_target(y), expected);
+        }
+
+        // Generating the witness again should give the same wire values.
+        let other_witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        assert_eq!(
+            witness.full_witness().wire_values,
+            other_witness.full_witness().wire_values
+        );
+    }
 }
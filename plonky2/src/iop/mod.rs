@@ -1,5 +1,6 @@
 //! Logic common to multiple IOPs.
 pub mod challenger;
+pub mod circuit_variable;
 pub mod ext_target;
 pub mod generator;
 pub mod target;
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
 use itertools::Itertools;
 use plonky2_field::extension::{Extendable, FieldExtension};
@@ -15,6 +16,10 @@ use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
 use crate::plonk::circuit_data::{VerifierCircuitTarget, VerifierOnlyCircuitData};
 use crate::plonk::config::{AlgebraicHasher, GenericConfig};
+use crate::plonk::multi_circuit::{
+    MultiCircuitProof, MultiCircuitProofTarget, MultiCircuitVerifierData,
+    MultiCircuitVerifierTarget,
+};
 use crate::plonk::proof::{Proof, ProofTarget, ProofWithPublicInputs, ProofWithPublicInputsTarget};
 
 /// A witness holds information on the values of targets in a circuit.
@@ -182,6 +187,39 @@ pub trait Witness<F: Field> {
         set_fri_proof_target(self, &proof_target.opening_proof, &proof.opening_proof);
     }
 
+    /// Set the targets in a `MultiCircuitProofTarget` to their corresponding values in a
+    /// `MultiCircuitProof`.
+    fn set_multi_circuit_proof_target<C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        proof_target: &MultiCircuitProofTarget<D>,
+        proof: &MultiCircuitProof<F, C, D>,
+    ) where
+        F: RichField + Extendable<D>,
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        for (entry_target, entry) in proof_target.entries.iter().zip_eq(&proof.entries) {
+            self.set_cap_target(&entry_target.wires_cap, &entry.wires_cap);
+            self.set_cap_target(
+                &entry_target.plonk_zs_partial_products_cap,
+                &entry.plonk_zs_partial_products_cap,
+            );
+            self.set_cap_target(&entry_target.quotient_polys_cap, &entry.quotient_polys_cap);
+            self.set_fri_openings(
+                &entry_target.openings.to_fri_openings(),
+                &entry.openings.to_fri_openings(),
+            );
+            for (&pi_t, &pi) in entry_target
+                .public_inputs
+                .iter()
+                .zip_eq(&entry.public_inputs)
+            {
+                self.set_target(pi_t, pi);
+            }
+        }
+
+        set_fri_proof_target(self, &proof_target.opening_proof, &proof.opening_proof);
+    }
+
     fn set_fri_openings<const D: usize>(
         &mut self,
         fri_openings_target: &FriOpeningsTarget<D>,
@@ -210,6 +248,28 @@ pub trait Witness<F: Field> {
         self.set_hash_target(vdt.circuit_digest, vd.circuit_digest);
     }
 
+    fn set_multi_circuit_verifier_data_target<C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        vdt: &MultiCircuitVerifierTarget,
+        vd: &MultiCircuitVerifierData<F, C, D>,
+    ) where
+        F: RichField + Extendable<D>,
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        for ((circuit_target, circuit), constants_sigmas_cap) in vdt
+            .circuits
+            .iter()
+            .zip_eq(&vd.circuits)
+            .zip_eq(&vd.constants_sigmas_caps)
+        {
+            self.set_cap_target(&circuit_target.constants_sigmas_cap, constants_sigmas_cap);
+            self.set_hash_target(
+                circuit_target.circuit_digest,
+                circuit.verifier_only.circuit_digest,
+            );
+        }
+    }
+
     fn set_wire(&mut self, wire: Wire, value: F) {
         self.set_target(Target::Wire(wire), value)
     }
@@ -292,8 +352,21 @@ pub struct PartitionWitness<'a, F: Field> {
 
 impl<'a, F: Field> PartitionWitness<'a, F> {
     pub fn new(num_wires: usize, degree: usize, representative_map: &'a [usize]) -> Self {
+        Self::new_with_buffer(num_wires, degree, representative_map, Vec::new())
+    }
+
+    /// Like `new`, but reuses the allocation of `values`, e.g. one returned by
+    /// `full_witness_and_buffer`.
+    pub(crate) fn new_with_buffer(
+        num_wires: usize,
+        degree: usize,
+        representative_map: &'a [usize],
+        mut values: Vec<Option<F>>,
+    ) -> Self {
+        values.clear();
+        values.resize(representative_map.len(), None);
         Self {
-            values: vec![None; representative_map.len()],
+            values,
             representative_map,
             num_wires,
             degree,
@@ -301,20 +374,26 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
     }
 
     /// Set a `Target`. On success, returns the representative index of the newly-set target. If the
-    /// target was already set, returns `None`.
-    pub(crate) fn set_target_returning_rep(&mut self, target: Target, value: F) -> Option<usize> {
+    /// target was already set to the same value, returns `None`, and if it was set to a different
+    /// value, returns the conflict.
+    pub(crate) fn set_target_returning_rep(
+        &mut self,
+        target: Target,
+        value: F,
+    ) -> Result<Option<usize>, WitnessConflict<F>> {
         let rep_index = self.representative_map[self.target_index(target)];
         let rep_value = &mut self.values[rep_index];
-        if let Some(old_value) = *rep_value {
-            assert_eq!(
-                value, old_value,
-                "Partition containing {:?} was set twice with different values",
-                target
-            );
-            None
-        } else {
-            *rep_value = Some(value);
-            Some(rep_index)
+        match *rep_value {
+            Some(old_value) if old_value != value => Err(WitnessConflict {
+                target,
+                old_value,
+                new_value: value,
+            }),
+            Some(_) => Ok(None),
+            None => {
+                *rep_value = Some(value);
+                Ok(Some(rep_index))
+            }
         }
     }
 
@@ -323,6 +402,12 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
     }
 
     pub fn full_witness(self) -> MatrixWitness<F> {
+        self.full_witness_and_buffer().0
+    }
+
+    /// Like `full_witness`, but also returns the buffer holding the values of this witness, so
+    /// that it can be reused by `new_with_buffer`.
+    pub(crate) fn full_witness_and_buffer(self) -> (MatrixWitness<F>, Vec<Option<F>>) {
         let mut wire_values = vec![vec![F::ZERO; self.degree]; self.num_wires];
         for i in 0..self.degree {
             for j in 0..self.num_wires {
@@ -333,7 +418,7 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
             }
         }
 
-        MatrixWitness { wire_values }
+        (MatrixWitness { wire_values }, self.values)
     }
 }
 
@@ -344,6 +429,28 @@ impl<'a, F: Field> Witness<F> for PartitionWitness<'a, F> {
     }
 
     fn set_target(&mut self, target: Target, value: F) {
-        self.set_target_returning_rep(target, value);
+        if let Err(conflict) = self.set_target_returning_rep(target, value) {
+            panic!("{conflict}");
+        }
+    }
+}
+
+/// A target whose partition was already set to a different value, e.g. because two generators
+/// disagree on the value of copy constrained targets.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct WitnessConflict<F: Field> {
+    pub target: Target,
+    /// The value the partition of `target` was already set to.
+    pub old_value: F,
+    pub new_value: F,
+}
+
+impl<F: Field> Display for WitnessConflict<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Partition containing {:?} was set twice with different values: {} and {}",
+            self.target, self.old_value, self.new_value
+        )
     }
 }
@@ -10,6 +10,9 @@
 #![feature(specialization)]
 #![feature(stdsimd)]
 
+// Lets the code generated by `plonky2_derive`, which refers to `::plonky2`, be used in this crate.
+extern crate self as plonky2;
+
 pub use plonky2_field as field;
 
 pub mod fri;
@@ -19,4 +22,5 @@ pub mod hash;
 pub mod iop;
 pub mod plonk;
 pub mod recursion;
+pub mod solidity;
 pub mod util;
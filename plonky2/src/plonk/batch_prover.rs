@@ -0,0 +1,205 @@
+//! Proving many witnesses of the same circuit.
+//!
+//! Proving a single witness leaves threads idle at times: witness generators only run in parallel
+//! within each round of generators, whose size depends on the circuit, and steps such as the
+//! transcript, partial products and FRI folding are sequential. When many witnesses are proven for
+//! the same circuit, it is faster overall to prove several of them at once, while sharing the
+//! values which only depend on the circuit.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use maybe_rayon::*;
+use plonky2_field::extension::Extendable;
+
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::PartialWitness;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::plonk::prover::{
+    prove_with_precomputation, ProverOptions, ProverPrecomputation, ProverScratch,
+};
+use crate::util::timing::TimingTree;
+
+/// Proves many witnesses of the same circuit concurrently, optimizing for throughput rather than
+/// for the latency of each proof.
+///
+/// The LDEs of the constants and sigmas, the FFT root table, the quotient domain and the values of
+/// the lookup constants are computed once and shared by all proofs. Witnesses are taken from a
+/// shared queue by up to `max_concurrent_proofs` workers, each of which starts its next proof as
+/// soon as its current one is done, and reuses its witness buffers from one proof to the next.
+pub struct BatchProver<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+    precomputation: ProverPrecomputation<F>,
+    options: ProverOptions,
+    max_concurrent_proofs: usize,
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    BatchProver<'a, F, C, D>
+{
+    /// Creates a batch prover for the given circuit. By default, as many proofs as there are
+    /// threads are generated at once.
+    pub fn new(
+        prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+        common_data: &'a CommonCircuitData<F, D>,
+    ) -> Self {
+        Self {
+            prover_data,
+            common_data,
+            precomputation: ProverPrecomputation::new(prover_data, common_data),
+            options: ProverOptions::default(),
+            max_concurrent_proofs: current_num_threads(),
+        }
+    }
+
+    /// Sets the options used for each proof.
+    pub fn with_options(mut self, options: ProverOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the maximum number of proofs generated at once. Each proof in progress holds its own
+    /// LDEs, so this bounds the memory used by the prover.
+    pub fn with_max_concurrent_proofs(mut self, max_concurrent_proofs: usize) -> Self {
+        assert!(max_concurrent_proofs > 0);
+        self.max_concurrent_proofs = max_concurrent_proofs;
+        self
+    }
+
+    /// Proves a single witness.
+    pub fn prove(&self, inputs: PartialWitness<F>) -> Result<ProofWithPublicInputs<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        prove_with_precomputation(
+            self.prover_data,
+            self.common_data,
+            &self.precomputation,
+            inputs,
+            &self.options,
+            None,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Proves all the given witnesses, returning their proofs in the same order. Fails if any of
+    /// them fails to be proven, in which case no new proof is started.
+    pub fn prove_all(
+        &self,
+        inputs: Vec<PartialWitness<F>>,
+    ) -> Result<Vec<ProofWithPublicInputs<F, C, D>>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let num_proofs = inputs.len();
+        let queue = Mutex::new(inputs.into_iter().enumerate());
+        let proofs = Mutex::new((0..num_proofs).map(|_| None).collect::<Vec<_>>());
+        let failed = AtomicBool::new(false);
+
+        let num_workers = self.max_concurrent_proofs.min(num_proofs);
+        (0..num_workers).into_par_iter().for_each(|_| {
+            let mut scratch = ProverScratch::default();
+            while !failed.load(Ordering::Relaxed) {
+                let next = queue.lock().unwrap().next();
+                let (index, inputs) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                let proof = prove_with_precomputation(
+                    self.prover_data,
+                    self.common_data,
+                    &self.precomputation,
+                    inputs,
+                    &self.options,
+                    Some(&mut scratch),
+                    &mut TimingTree::default(),
+                );
+                if proof.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                proofs.lock().unwrap()[index] = Some(proof);
+            }
+        });
+
+        // Proofs which were never started are skipped, which only happens after an error.
+        proofs.into_inner().unwrap().into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_batch_prover() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let mut y = x;
+        for _ in 0..100 {
+            y = builder.mul_add(y, y, x);
+        }
+        builder.register_public_input(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let xs = F::rand_vec(5);
+        let inputs = xs
+            .iter()
+            .map(|&x_value| {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, x_value);
+                pw
+            })
+            .collect::<Vec<_>>();
+        let batch_prover = data.batch_prover().with_max_concurrent_proofs(2);
+        let proofs = batch_prover.prove_all(inputs.clone())?;
+
+        assert_eq!(proofs.len(), xs.len());
+        for ((proof, pw), x_value) in proofs.into_iter().zip(inputs).zip(xs) {
+            assert_eq!(proof.public_inputs[0], x_value);
+            // Without zero knowledge, proving is deterministic.
+            assert_eq!(proof, data.prove(pw)?);
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_prover_conflict() {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let inputs = (0..4)
+            .map(|i| {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_usize(i));
+                if i == 2 {
+                    // Conflicts with the value of `y` computed by the generators.
+                    pw.set_target(y, F::from_canonical_usize(5));
+                }
+                pw
+            })
+            .collect();
+        assert!(data.batch_prover().prove_all(inputs).is_err());
+    }
+}
@@ -1,9 +1,10 @@
 use std::cmp::max;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Instant;
 
 use itertools::Itertools;
-use log::{debug, info, Level};
+use log::{debug, info, warn, Level};
 use plonky2_field::cosets::get_unique_coset_shifts;
 use plonky2_field::extension::{Extendable, FieldExtension};
 use plonky2_field::fft::fft_root_table;
@@ -12,7 +13,7 @@ use plonky2_field::types::Field;
 use plonky2_util::{log2_ceil, log2_strict};
 
 use crate::fri::oracle::PolynomialBatch;
-use crate::fri::{FriConfig, FriParams};
+use crate::fri::FriParams;
 use crate::gadgets::arithmetic::BaseArithmeticOperation;
 use crate::gadgets::arithmetic_extension::ExtensionArithmeticOperation;
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
@@ -20,6 +21,8 @@ use crate::gates::arithmetic_base::ArithmeticGate;
 use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
 use crate::gates::constant::ConstantGate;
 use crate::gates::gate::{CurrentSlot, Gate, GateInstance, GateRef};
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::{LookupTable, LookupTableGate, LookupTableGenerator};
 use crate::gates::noop::NoopGate;
 use crate::gates::public_input::PublicInputGate;
 use crate::gates::selectors::selector_polynomials;
@@ -40,6 +43,7 @@ use crate::plonk::config::{GenericConfig, Hasher};
 use crate::plonk::copy_constraint::CopyConstraint;
 use crate::plonk::permutation_argument::Forest;
 use crate::plonk::plonk_common::PlonkOracle;
+use crate::plonk::public_data::PublicDataCommitment;
 use crate::timed;
 use crate::util::context_tree::ContextTree;
 use crate::util::partial_products::num_partial_products;
@@ -56,7 +60,7 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     pub(crate) gate_instances: Vec<GateInstance<F, D>>,
 
     /// Targets to be made public.
-    public_inputs: Vec<Target>,
+    pub(crate) public_inputs: Vec<Target>,
 
     /// The next available index for a `VirtualTarget`.
     virtual_target_index: usize,
@@ -92,6 +96,22 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// Optional verifier data that is registered as public inputs.
     /// This is used in cyclic recursion to hold the circuit's own verifier key.
     pub(crate) verifier_data_public_input: Option<VerifierCircuitTarget>,
+
+    /// Lookup tables registered with `add_lookup_table`.
+    pub(crate) lookup_tables: Vec<LookupTable>,
+
+    /// For each lookup table, the input wires of all lookups into it.
+    pub(crate) lookup_inputs: Vec<Vec<Target>>,
+
+    /// For each lookup table, the output of each of its inputs, shared by the generators of all
+    /// lookups into it.
+    pub(crate) lookup_outputs: Vec<Arc<HashMap<u16, u16>>>,
+
+    /// Targets registered with `register_public_data`, exposed only through a commitment.
+    pub(crate) public_data: Vec<Target>,
+
+    /// The hash used to commit to `public_data`.
+    pub(crate) public_data_commitment: PublicDataCommitment,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
@@ -113,34 +133,52 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             constant_generators: Vec::new(),
             goal_common_data: None,
             verifier_data_public_input: None,
+            lookup_tables: Vec::new(),
+            lookup_inputs: Vec::new(),
+            lookup_outputs: Vec::new(),
+            public_data: Vec::new(),
+            public_data_commitment: PublicDataCommitment::Poseidon,
         };
         builder.check_config();
         builder
     }
 
     fn check_config(&self) {
-        let &CircuitConfig {
-            security_bits,
-            fri_config:
-                FriConfig {
-                    rate_bits,
-                    proof_of_work_bits,
-                    num_query_rounds,
-                    ..
-                },
-            ..
-        } = &self.config;
-
         // Conjectured FRI security; see the ethSTARK paper.
         let fri_field_bits = F::Extension::order().bits() as usize;
-        let fri_query_security_bits = num_query_rounds * rate_bits + proof_of_work_bits as usize;
+        let fri_query_security_bits = self.config.fri_config.query_security_bits();
         let fri_security_bits = fri_field_bits.min(fri_query_security_bits);
         assert!(
-            fri_security_bits >= security_bits,
+            fri_security_bits >= self.config.security_bits,
             "FRI params fall short of target security"
         );
     }
 
+    /// Checks the estimated soundness of the given FRI parameters. Unlike the bound checked by
+    /// `check_config`, the conjectured estimate also accounts for the size of the LDE domain, so
+    /// it can fall short of the target for large circuits even when `check_config` passes.
+    fn check_fri_soundness(&self, fri_params: &FriParams) {
+        let soundness = fri_params.soundness::<F, D>();
+        info!(
+            "FRI soundness: {:.1} conjectured bits, {:.1} provable bits",
+            soundness.conjectured_bits, soundness.provable_bits
+        );
+        if soundness.conjectured_bits >= self.config.security_bits as f64 {
+            return;
+        }
+        assert!(
+            self.config.allow_insufficient_fri_soundness,
+            "FRI soundness falls short of target security: {:.1} conjectured bits at degree 2^{}, {} targeted",
+            soundness.conjectured_bits,
+            fri_params.degree_bits,
+            self.config.security_bits
+        );
+        warn!(
+            "FRI soundness is below target: {:.1} conjectured bits at degree 2^{}, {} targeted",
+            soundness.conjectured_bits, fri_params.degree_bits, self.config.security_bits
+        );
+    }
+
     pub fn num_gates(&self) -> usize {
         self.gate_instances.len()
     }
@@ -230,8 +268,9 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         t
     }
     /// Add a virtual verifier data, register it as a public input and set it to `self.verifier_data_public_input`.
-    /// WARNING: Do not register any public input after calling this! TODO: relax this
-    pub(crate) fn add_verifier_data_public_input(&mut self) {
+    /// This determines where the verifier data of a cyclic circuit appears in its public inputs;
+    /// other public inputs may be registered before or after it.
+    pub fn add_verifier_data_public_input(&mut self) -> VerifierCircuitTarget {
         let verifier_data = VerifierCircuitTarget {
             constants_sigmas_cap: self.add_virtual_cap(self.config.fri_config.cap_height),
             circuit_digest: self.add_virtual_hash(),
@@ -242,7 +281,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.register_public_inputs(&verifier_data.constants_sigmas_cap.0[i].elements);
         }
 
-        self.verifier_data_public_input = Some(verifier_data);
+        self.verifier_data_public_input = Some(verifier_data.clone());
+        verifier_data
     }
 
     /// Adds a gate to the circuit, and returns its index.
@@ -667,6 +707,92 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         )
     }
 
+    /// Fills the unused slots of the last `LookupGate` of each table, then adds the
+    /// `LookupTableGate`s holding the tables, along with the generators of their multiplicities.
+    /// Tables without any lookups are skipped. Returns the number of slots per lookup gate, or 0 if
+    /// the circuit has no lookups.
+    fn add_lookup_table_gates(&mut self) -> usize {
+        if self.lookup_inputs.iter().all(|inputs| inputs.is_empty()) {
+            return 0;
+        }
+
+        let lookup_gate = LookupGate::new_from_config(&self.config);
+        let table_gate = LookupTableGate::new_from_config(&self.config);
+        for table_index in 0..self.lookup_tables.len() {
+            if self.lookup_inputs[table_index].is_empty() {
+                continue;
+            }
+            let table = self.lookup_tables[table_index].clone();
+            let index = F::from_canonical_usize(table_index);
+
+            // Unused slots would hold zeros, which may not be in the table, so we fill them with
+            // lookups of the first entry instead.
+            let dummy_input = self.constant(F::from_canonical_u16(table[0].0));
+            while self
+                .current_slots
+                .get(&GateRef::new(lookup_gate))
+                .is_some_and(|slots| slots.current_slot.contains_key(&vec![index]))
+            {
+                self.lookup(table_index, dummy_input);
+            }
+
+            let mut multiplicities = Vec::with_capacity(table.len());
+            for chunk in table.chunks(table_gate.num_slots) {
+                let row = self.add_gate(table_gate, vec![index]);
+                for slot in 0..table_gate.num_slots {
+                    // The lookup argument sums over every slot, so the unused slots of the last
+                    // gate must hold a real entry too. We use copies of the first entry. Their
+                    // multiplicity wires aren't routed, so they can't be pinned, but a nonzero
+                    // multiplicity on a copy only shares the count of the first entry.
+                    let (inp, out) = match chunk.get(slot) {
+                        Some(&entry) => {
+                            multiplicities
+                                .push(Target::wire(row, table_gate.wire_ith_multiplicity(slot)));
+                            entry
+                        }
+                        None => table[0],
+                    };
+                    let inp = self.constant(F::from_canonical_u16(inp));
+                    let out = self.constant(F::from_canonical_u16(out));
+                    self.connect(
+                        inp,
+                        Target::wire(row, LookupTableGate::wire_ith_looked_inp(slot)),
+                    );
+                    self.connect(
+                        out,
+                        Target::wire(row, LookupTableGate::wire_ith_looked_out(slot)),
+                    );
+                }
+            }
+
+            self.add_simple_generator(LookupTableGenerator {
+                table,
+                looking_inputs: self.lookup_inputs[table_index].clone(),
+                multiplicities,
+            });
+        }
+
+        lookup_gate.num_slots
+    }
+
+    /// The lookup selector polynomials, which are appended to the constant polynomials when the
+    /// circuit has lookups. They are respectively 1 on the rows of `LookupGate`s and
+    /// `LookupTableGate`s, and 0 elsewhere.
+    fn lookup_selector_polys(&self) -> Vec<PolynomialValues<F>> {
+        let selector = |is_selected: fn(&GateInstance<F, D>) -> bool| {
+            PolynomialValues::new(
+                self.gate_instances
+                    .iter()
+                    .map(|inst| F::from_bool(is_selected(inst)))
+                    .collect(),
+            )
+        };
+        vec![
+            selector(|inst| inst.gate_ref.0.as_any().is::<LookupGate>()),
+            selector(|inst| inst.gate_ref.0.as_any().is::<LookupTableGate>()),
+        ]
+    }
+
     pub fn print_gate_counts(&self, min_delta: usize) {
         // Print gate counts for each context.
         self.context_log
@@ -695,6 +821,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let rate_bits = self.config.fri_config.rate_bits;
         let cap_height = self.config.fri_config.cap_height;
 
+        let public_data_commitment = self.register_public_data_commitment();
+
         // Hash the public inputs, and route them to a `PublicInputGate` which will enforce that
         // those hash wires match the claimed public inputs.
         let num_public_inputs = self.public_inputs.len();
@@ -709,6 +837,10 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.connect(hash_part, Target::wire(pi_gate, wire))
         }
 
+        // Lay out the lookup tables. This may add constants, so it must happen before constant
+        // generators are assigned.
+        let num_lookup_slots = self.add_lookup_table_gates();
+
         // Make sure we have enough constant generators. If not, add a `ConstantGate`.
         while self.constants_to_targets.len() > self.constant_generators.len() {
             self.add_gate(
@@ -751,6 +883,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             fri_params.total_arities() <= degree_bits + rate_bits - cap_height,
             "FRI total reduction arity is too large.",
         );
+        self.check_fri_soundness(&fri_params);
 
         let quotient_degree_factor = self.config.max_quotient_degree_factor;
         let mut gates = self.gates.iter().cloned().collect::<Vec<_>>();
@@ -759,6 +892,9 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let (mut constant_vecs, selectors_info) =
             selector_polynomials(&gates, &self.gate_instances, quotient_degree_factor + 1);
         constant_vecs.extend(self.constant_polys());
+        if num_lookup_slots > 0 {
+            constant_vecs.extend(self.lookup_selector_polys());
+        }
         let num_constants = constant_vecs.len();
 
         let subgroup = F::two_adic_subgroup(degree_bits);
@@ -809,8 +945,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
         // Index generator indices by their watched targets.
         let mut generator_indices_by_watches = BTreeMap::new();
+        let mut generator_indices_without_watches = Vec::new();
         for (i, generator) in self.generators.iter().enumerate() {
-            for watch in generator.watch_list() {
+            let watch_list = generator.watch_list();
+            if watch_list.is_empty() {
+                generator_indices_without_watches.push(i);
+            }
+            for watch in watch_list {
                 let watch_index = forest.target_index(watch);
                 let watch_rep_index = forest.parents[watch_index];
                 generator_indices_by_watches
@@ -855,6 +996,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             num_public_inputs,
             k_is,
             num_partial_products,
+            num_lookup_slots,
+            public_data_commitment,
         };
         if let Some(goal_data) = self.goal_common_data {
             assert_eq!(goal_data, common);
@@ -863,6 +1006,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let prover_only = ProverOnlyCircuitData {
             generators: self.generators,
             generator_indices_by_watches,
+            generator_indices_without_watches,
             constants_sigmas_commitment,
             sigmas: transpose_poly_values(sigma_vecs),
             subgroup,
@@ -870,6 +1014,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             representative_map: forest.parents,
             fft_root_table: Some(fft_root_table),
             circuit_digest,
+            context_tree: self.context_log,
         };
 
         let verifier_only = VerifierOnlyCircuitData {
@@ -1,9 +1,11 @@
 use std::collections::BTreeMap;
-use std::ops::{Range, RangeFrom};
+use std::ops::Range;
 
 use anyhow::Result;
 use plonky2_field::extension::Extendable;
 use plonky2_field::fft::FftRootTable;
+use plonky2_field::polynomial::PolynomialValues;
+use plonky2_util::ceil_div_usize;
 
 use crate::field::types::Field;
 use crate::fri::oracle::PolynomialBatch;
@@ -21,12 +23,20 @@ use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::WitnessGenerator;
 use crate::iop::target::Target;
 use crate::iop::witness::PartialWitness;
+use crate::plonk::batch_prover::BatchProver;
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_stats::{circuit_stats, CircuitStats};
 use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::mock_prover::{mock_prove, MockProverFailure};
 use crate::plonk::plonk_common::PlonkOracle;
 use crate::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
-use crate::plonk::prover::prove;
+use crate::plonk::prover::{prove, prove_with_options, ProverOptions};
+use crate::plonk::public_data::PublicDataCommitment;
 use crate::plonk::verifier::verify;
+use crate::util::context_tree::ContextTree;
+use crate::util::serialization::gate_serialization::GateSerializer;
+use crate::util::serialization::generator_serialization::WitnessGeneratorSerializer;
+use crate::util::serialization::Buffer;
 use crate::util::timing::TimingTree;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -46,6 +56,9 @@ pub struct CircuitConfig {
     /// systematically, but will never exceed this value.
     pub max_quotient_degree_factor: usize,
     pub fri_config: FriConfig,
+    /// Whether to build circuits whose conjectured FRI soundness, which decreases with the size of
+    /// the LDE domain, falls short of `security_bits`. Only a warning is logged in that case.
+    pub allow_insufficient_fri_soundness: bool,
 }
 
 impl Default for CircuitConfig {
@@ -77,6 +90,7 @@ impl CircuitConfig {
                 reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
                 num_query_rounds: 28,
             },
+            allow_insufficient_fri_soundness: false,
         }
     }
 
@@ -124,6 +138,39 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         )
     }
 
+    /// A prover for many witnesses of this circuit, sharing precomputed values between proofs.
+    pub fn batch_prover(&self) -> BatchProver<F, C, D> {
+        BatchProver::new(&self.prover_only, &self.common)
+    }
+
+    /// Like `prove`, with options trading proving time for memory.
+    pub fn prove_with_options(
+        &self,
+        inputs: PartialWitness<F>,
+        options: &ProverOptions,
+    ) -> Result<ProofWithPublicInputs<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        prove_with_options(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            options,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Checks the witness generated from the given inputs against every constraint of the
+    /// circuit, without generating a proof. See `mock_prove`.
+    pub fn mock_prove(&self, inputs: PartialWitness<F>) -> Vec<MockProverFailure> {
+        mock_prove(&self.prover_only, &self.common, inputs)
+    }
+
+    pub fn stats(&self) -> CircuitStats {
+        circuit_stats(&self.prover_only, &self.common)
+    }
+
     pub fn verify(&self, proof_with_pis: ProofWithPublicInputs<F, C, D>) -> Result<()>
     where
         [(); C::Hasher::HASH_SIZE]:,
@@ -181,6 +228,28 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             common,
         }
     }
+
+    pub fn to_bytes(
+        &self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_circuit_data_header()?;
+        buffer.write_circuit_data(self, gate_serializer, generator_serializer)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(
+        bytes: Vec<u8>,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        buffer.read_circuit_data_header()?;
+        let circuit_data = buffer.read_circuit_data(gate_serializer, generator_serializer)?;
+        Ok(circuit_data)
+    }
 }
 
 /// Circuit data required by the prover. This may be thought of as a proving key, although it
@@ -213,6 +282,62 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             &mut TimingTree::default(),
         )
     }
+
+    /// A prover for many witnesses of this circuit, sharing precomputed values between proofs.
+    pub fn batch_prover(&self) -> BatchProver<F, C, D> {
+        BatchProver::new(&self.prover_only, &self.common)
+    }
+
+    /// Like `prove`, with options trading proving time for memory.
+    pub fn prove_with_options(
+        &self,
+        inputs: PartialWitness<F>,
+        options: &ProverOptions,
+    ) -> Result<ProofWithPublicInputs<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        prove_with_options(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            options,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Checks the witness generated from the given inputs against every constraint of the
+    /// circuit, without generating a proof. See `mock_prove`.
+    pub fn mock_prove(&self, inputs: PartialWitness<F>) -> Vec<MockProverFailure> {
+        mock_prove(&self.prover_only, &self.common, inputs)
+    }
+
+    pub fn stats(&self) -> CircuitStats {
+        circuit_stats(&self.prover_only, &self.common)
+    }
+
+    pub fn to_bytes(
+        &self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_circuit_data_header()?;
+        buffer.write_prover_circuit_data(self, gate_serializer, generator_serializer)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(
+        bytes: Vec<u8>,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        buffer.read_circuit_data_header()?;
+        let prover_circuit_data =
+            buffer.read_prover_circuit_data(gate_serializer, generator_serializer)?;
+        Ok(prover_circuit_data)
+    }
 }
 
 /// Circuit data required by the prover.
@@ -245,6 +370,20 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     {
         compressed_proof_with_pis.verify(&self.verifier_only, &self.common)
     }
+
+    pub fn to_bytes(&self, gate_serializer: &dyn GateSerializer<F, D>) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_circuit_data_header()?;
+        buffer.write_verifier_circuit_data(self, gate_serializer)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, gate_serializer: &dyn GateSerializer<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        buffer.read_circuit_data_header()?;
+        let verifier_circuit_data = buffer.read_verifier_circuit_data(gate_serializer)?;
+        Ok(verifier_circuit_data)
+    }
 }
 
 /// Circuit data required by the prover, but not the verifier.
@@ -257,6 +396,11 @@ pub struct ProverOnlyCircuitData<
     /// Generator indices (within the `Vec` above), indexed by the representative of each target
     /// they watch.
     pub generator_indices_by_watches: BTreeMap<usize, Vec<usize>>,
+    /// Indices of the generators which don't watch any target. Along with
+    /// `generator_indices_by_watches`, this forms the dependency graph used to schedule generators:
+    /// these generators are run first, and any other generator is run once a target it watches is
+    /// populated.
+    pub generator_indices_without_watches: Vec<usize>,
     /// Commitments to the constants polynomials and sigma polynomials.
     pub constants_sigmas_commitment: PolynomialBatch<F, C, D>,
     /// The transpose of the list of sigma polynomials.
@@ -273,6 +417,25 @@ pub struct ProverOnlyCircuitData<
     /// A digest of the "circuit" (i.e. the instance, minus public inputs), which can be used to
     /// seed Fiat-Shamir.
     pub circuit_digest: <<C as GenericConfig<D>>::Hasher as Hasher<F>>::Hash,
+    /// The contexts in which gates were added, used to report failures when mock proving.
+    pub(crate) context_tree: ContextTree,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProverOnlyCircuitData<F, C, D>
+{
+    /// The values of the constant polynomials, including selectors, on each row of the circuit.
+    pub(crate) fn constants_values(
+        &self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Vec<PolynomialValues<F>> {
+        let indices = common_data.constants_range().collect::<Vec<_>>();
+        self.constants_sigmas_commitment
+            .polynomial_chunks(&indices)
+            .flatten()
+            .map(|poly| poly.into_owned().fft())
+            .collect()
+    }
 }
 
 /// Circuit data required by the verifier, but not the prover.
@@ -285,6 +448,22 @@ pub struct VerifierOnlyCircuitData<C: GenericConfig<D>, const D: usize> {
     pub circuit_digest: <<C as GenericConfig<D>>::Hasher as Hasher<C::F>>::Hash,
 }
 
+impl<C: GenericConfig<D>, const D: usize> VerifierOnlyCircuitData<C, D> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_circuit_data_header()?;
+        buffer.write_verifier_only_circuit_data(self)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        buffer.read_circuit_data_header()?;
+        let verifier_only_data = buffer.read_verifier_only_circuit_data()?;
+        Ok(verifier_only_data)
+    }
+}
+
 /// Circuit data required by both the prover and the verifier.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct CommonCircuitData<F: RichField + Extendable<D>, const D: usize> {
@@ -314,6 +493,13 @@ pub struct CommonCircuitData<F: RichField + Extendable<D>, const D: usize> {
 
     /// The number of partial products needed to compute the `Z` polynomials.
     pub(crate) num_partial_products: usize,
+
+    /// The number of lookups held by each `LookupGate`, or 0 if the circuit has no lookups.
+    pub(crate) num_lookup_slots: usize,
+
+    /// The hash committing to the circuit's public data, if it has any. The commitment is at the
+    /// end of the public inputs.
+    pub(crate) public_data_commitment: Option<PublicDataCommitment>,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
@@ -361,8 +547,59 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
     }
 
     /// Range of the partial products polynomials in the `zs_partial_products_commitment`.
-    pub fn partial_products_range(&self) -> RangeFrom<usize> {
-        self.config.num_challenges..
+    pub fn partial_products_range(&self) -> Range<usize> {
+        self.config.num_challenges..self.config.num_challenges * (1 + self.num_partial_products)
+    }
+
+    /// Range of the lookup polynomials in the `zs_partial_products_commitment`. For each challenge,
+    /// these are the running sum `Z` of the lookup argument followed by its helper polynomials.
+    pub fn lookup_range(&self) -> Range<usize> {
+        let start = self.partial_products_range().end;
+        start..start + self.config.num_challenges * self.num_lookup_polys()
+    }
+
+    /// The number of lookup polynomials per challenge.
+    pub fn num_lookup_polys(&self) -> usize {
+        if self.num_lookup_slots == 0 {
+            0
+        } else {
+            1 + ceil_div_usize(self.num_lookup_slots, self.num_lookup_slots_per_helper())
+        }
+    }
+
+    /// The number of lookup slots handled by each helper polynomial of the lookup argument. The
+    /// constraint of a helper multiplies it by a selector and the denominators of all its slots, so
+    /// we use as many slots as the quotient degree allows.
+    pub(crate) fn num_lookup_slots_per_helper(&self) -> usize {
+        self.quotient_degree_factor - 1
+    }
+
+    /// The number of lookup selector polynomials, which come last in the constant polynomials.
+    pub fn num_lookup_selectors(&self) -> usize {
+        if self.num_lookup_slots == 0 {
+            0
+        } else {
+            2
+        }
+    }
+
+    /// Range of the lookup selectors in the constant polynomials.
+    pub fn lookup_selectors_range(&self) -> Range<usize> {
+        self.num_constants - self.num_lookup_selectors()..self.num_constants
+    }
+
+    /// The index in `gates` of the gate of a row, given the values of the selector polynomials on
+    /// that row.
+    pub(crate) fn gate_index(&self, selectors: &[F]) -> usize {
+        self.selectors_info
+            .groups
+            .iter()
+            .zip(selectors)
+            .find_map(|(group, selector)| {
+                let index = selector.to_canonical_u64() as usize;
+                group.contains(&index).then_some(index)
+            })
+            .expect("No gate is selected")
     }
 
     pub(crate) fn get_fri_instance(&self, zeta: F::Extension) -> FriInstanceInfo<F, D> {
@@ -372,12 +609,12 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
             polynomials: self.fri_all_polys(),
         };
 
-        // The Z polynomials are also opened at g * zeta.
+        // The Z polynomials and the lookup polynomials are also opened at g * zeta.
         let g = F::Extension::primitive_root_of_unity(self.degree_bits());
         let zeta_next = g * zeta;
         let zeta_next_batch = FriBatchInfo {
             point: zeta_next,
-            polynomials: self.fri_zs_polys(),
+            polynomials: [self.fri_zs_polys(), self.fri_lookup_polys()].concat(),
         };
 
         let openings = vec![zeta_batch, zeta_next_batch];
@@ -398,12 +635,12 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
             polynomials: self.fri_all_polys(),
         };
 
-        // The Z polynomials are also opened at g * zeta.
+        // The Z polynomials and the lookup polynomials are also opened at g * zeta.
         let g = F::primitive_root_of_unity(self.degree_bits());
         let zeta_next = builder.mul_const_extension(g, zeta);
         let zeta_next_batch = FriBatchInfoTarget {
             point: zeta_next,
-            polynomials: self.fri_zs_polys(),
+            polynomials: [self.fri_zs_polys(), self.fri_lookup_polys()].concat(),
         };
 
         let openings = vec![zeta_batch, zeta_next_batch];
@@ -458,13 +695,17 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
     }
 
     pub(crate) fn num_zs_partial_products_polys(&self) -> usize {
-        self.config.num_challenges * (1 + self.num_partial_products)
+        self.lookup_range().end
     }
 
     fn fri_zs_polys(&self) -> Vec<FriPolynomialInfo> {
         FriPolynomialInfo::from_range(PlonkOracle::ZS_PARTIAL_PRODUCTS.index, self.zs_range())
     }
 
+    fn fri_lookup_polys(&self) -> Vec<FriPolynomialInfo> {
+        FriPolynomialInfo::from_range(PlonkOracle::ZS_PARTIAL_PRODUCTS.index, self.lookup_range())
+    }
+
     fn fri_quotient_polys(&self) -> Vec<FriPolynomialInfo> {
         FriPolynomialInfo::from_range(PlonkOracle::QUOTIENT.index, 0..self.num_quotient_polys())
     }
@@ -482,6 +723,20 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         ]
         .concat()
     }
+
+    pub fn to_bytes(&self, gate_serializer: &dyn GateSerializer<F, D>) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_circuit_data_header()?;
+        buffer.write_common_circuit_data(self, gate_serializer)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, gate_serializer: &dyn GateSerializer<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        buffer.read_circuit_data_header()?;
+        let common_data = buffer.read_common_circuit_data(gate_serializer)?;
+        Ok(common_data)
+    }
 }
 
 /// The `Target` version of `VerifierCircuitData`, for use inside recursive circuits. Note that this
@@ -0,0 +1,236 @@
+use plonky2_field::extension::Extendable;
+use plonky2_field::types::Field;
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_types::RichField;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::plonk_common::salt_size;
+
+/// Statistics about the cost of a circuit, which can be serialized to track how it evolves.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    pub degree_bits: usize,
+    /// The degree of the quotient polynomial, as a multiple of the circuit's degree.
+    pub quotient_degree_factor: usize,
+    pub num_public_inputs: usize,
+    /// The number of wires on each row.
+    pub num_wires: usize,
+    /// The number of constants available to gates on each row, excluding selectors.
+    pub num_constants: usize,
+    /// The number of independent copy constraints, i.e. the number of wires and virtual targets
+    /// which are not the representative of their partition.
+    pub num_copy_constraints: usize,
+    /// The number of wires used by gates, summed over all rows.
+    pub num_used_wires: usize,
+    /// The number of constants used by gates, summed over all rows.
+    pub num_used_constants: usize,
+    /// The instances of each gate type, sorted by gate ID.
+    pub gates: Vec<GateStats>,
+    /// The number of gates added in each context.
+    pub contexts: ContextStats,
+    /// An estimate of the size of a serialized proof, in bytes.
+    pub estimated_proof_size: usize,
+}
+
+impl CircuitStats {
+    pub fn degree(&self) -> usize {
+        1 << self.degree_bits
+    }
+
+    /// The fraction of wires which are used by gates.
+    pub fn wire_utilisation(&self) -> f64 {
+        self.num_used_wires as f64 / (self.degree() * self.num_wires) as f64
+    }
+
+    /// The fraction of constants which are used by gates.
+    pub fn constant_utilisation(&self) -> f64 {
+        self.num_used_constants as f64 / (self.degree() * self.num_constants) as f64
+    }
+}
+
+/// The instances of a gate type in a circuit.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GateStats {
+    pub id: String,
+    pub num_instances: usize,
+    /// The number of wires used by each instance.
+    pub num_wires: usize,
+    /// The number of constants used by each instance.
+    pub num_constants: usize,
+}
+
+/// The number of gates added in a context, and in each of its children.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ContextStats {
+    pub name: String,
+    pub num_gates: usize,
+    pub children: Vec<ContextStats>,
+}
+
+pub fn circuit_stats<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> CircuitStats {
+    let config = &common_data.config;
+    let degree = common_data.degree();
+
+    let num_selectors = common_data.selectors_info.num_selectors();
+    let selectors = &prover_data.constants_values(common_data)[..num_selectors];
+    let mut num_instances = vec![0; common_data.gates.len()];
+    for row in 0..degree {
+        let row_selectors = selectors.iter().map(|s| s.values[row]).collect::<Vec<_>>();
+        num_instances[common_data.gate_index(&row_selectors)] += 1;
+    }
+
+    let mut gates = common_data
+        .gates
+        .iter()
+        .zip(num_instances)
+        .map(|(gate, num_instances)| GateStats {
+            id: gate.0.id(),
+            num_instances,
+            num_wires: gate.0.num_wires(),
+            num_constants: gate.0.num_constants(),
+        })
+        .collect::<Vec<_>>();
+    gates.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let num_copy_constraints = prover_data
+        .representative_map
+        .iter()
+        .enumerate()
+        .filter(|&(i, &rep)| i != rep)
+        .count();
+
+    CircuitStats {
+        degree_bits: common_data.degree_bits(),
+        quotient_degree_factor: common_data.quotient_degree_factor,
+        num_public_inputs: common_data.num_public_inputs,
+        num_wires: config.num_wires,
+        num_constants: config.num_constants,
+        num_copy_constraints,
+        num_used_wires: gates.iter().map(|g| g.num_instances * g.num_wires).sum(),
+        num_used_constants: gates
+            .iter()
+            .map(|g| g.num_instances * g.num_constants)
+            .sum(),
+        gates,
+        contexts: prover_data.context_tree.stats(degree),
+        estimated_proof_size: estimated_proof_size::<F, C, D>(common_data),
+    }
+}
+
+/// Estimates the size of a serialized (uncompressed) proof, ignoring the few bytes used to encode
+/// lengths.
+fn estimated_proof_size<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    common_data: &CommonCircuitData<F, D>,
+) -> usize {
+    const FIELD_SIZE: usize = std::mem::size_of::<u64>();
+    let ext_size = D * FIELD_SIZE;
+    let hash_size = C::Hasher::HASH_SIZE;
+    let fri_params = &common_data.fri_params;
+    let cap_height = fri_params.config.cap_height;
+    let cap_size = fri_params.config.num_cap_elements() * hash_size;
+    let fri_instance = common_data.get_fri_instance(F::Extension::ZERO);
+
+    // Caps of the wires, Zs and partial products, and quotient oracles.
+    let mut size = 3 * cap_size;
+    // Openings.
+    size += fri_instance
+        .batches
+        .iter()
+        .map(|batch| batch.polynomials.len() * ext_size)
+        .sum::<usize>();
+    // FRI commit phase caps.
+    size += fri_params.reduction_arity_bits.len() * cap_size;
+
+    // FRI query rounds, each opening every oracle and then every reduction step.
+    let mut layer_bits = fri_params.lde_bits();
+    let mut query_round_size = fri_instance
+        .oracles
+        .iter()
+        .map(|oracle| {
+            let num_leaf_elements =
+                oracle.num_polys + salt_size(oracle.blinding && fri_params.hiding);
+            num_leaf_elements * FIELD_SIZE + (layer_bits - cap_height) * hash_size
+        })
+        .sum::<usize>();
+    for &arity_bits in &fri_params.reduction_arity_bits {
+        layer_bits -= arity_bits;
+        query_round_size += (1 << arity_bits) * ext_size + (layer_bits - cap_height) * hash_size;
+    }
+    size += fri_params.config.num_query_rounds * query_round_size;
+
+    // Final polynomial and proof-of-work witness.
+    size += fri_params.final_poly_len() * ext_size + FIELD_SIZE;
+
+    size + common_data.num_public_inputs * FIELD_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_circuit_stats() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_zk_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        builder.push_context(log::Level::Debug, "powers");
+        let mut y = x;
+        for _ in 0..100 {
+            y = builder.mul(y, x);
+        }
+        builder.pop_context();
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        let stats = data.stats();
+
+        assert_eq!(stats.degree_bits, data.common.degree_bits());
+        assert_eq!(stats.num_public_inputs, 1);
+        assert_eq!(
+            stats.gates.iter().map(|g| g.num_instances).sum::<usize>(),
+            stats.degree()
+        );
+        assert!(stats
+            .gates
+            .iter()
+            .any(|g| g.id.starts_with("ArithmeticGate") && g.num_instances > 0));
+        assert!(stats.num_copy_constraints > 0);
+        assert!(stats.wire_utilisation() > 0.0 && stats.wire_utilisation() < 1.0);
+
+        assert_eq!(stats.contexts.name, "root");
+        assert_eq!(stats.contexts.num_gates, stats.degree());
+        let powers_context = &stats.contexts.children[0];
+        assert_eq!(powers_context.name, "powers");
+        assert_eq!(powers_context.num_gates, 5);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO);
+        let proof = data.prove(pw)?;
+        let proof_size = proof.to_bytes()?.len();
+        assert!(stats.estimated_proof_size <= proof_size);
+        assert!(stats.estimated_proof_size * 100 >= proof_size * 99);
+
+        let serialized = serde_cbor::to_vec(&stats)?;
+        let deserialized: CircuitStats = serde_cbor::from_slice(&serialized)?;
+        assert_eq!(deserialized, stats);
+
+        Ok(())
+    }
+}
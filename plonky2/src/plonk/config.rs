@@ -10,6 +10,8 @@ use crate::hash::hash_types::RichField;
 use crate::hash::hashing::{PlonkyPermutation, SPONGE_WIDTH};
 use crate::hash::keccak::KeccakHash;
 use crate::hash::poseidon::PoseidonHash;
+use crate::hash::poseidon2::CustomPoseidon2Hash;
+use crate::hash::rescue_prime::CustomRescuePrimeHash;
 use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
 
@@ -116,3 +118,25 @@ impl GenericConfig<2> for KeccakGoldilocksConfig {
     type Hasher = KeccakHash<25>;
     type InnerHasher = PoseidonHash;
 }
+
+/// Configuration using our custom Poseidon2 instance over the Goldilocks field. See
+/// `hash::poseidon2`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CustomPoseidon2GoldilocksConfig;
+impl GenericConfig<2> for CustomPoseidon2GoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = CustomPoseidon2Hash;
+    type InnerHasher = CustomPoseidon2Hash;
+}
+
+/// Configuration using our custom Rescue-Prime instance over the Goldilocks field. See
+/// `hash::rescue_prime`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CustomRescuePrimeGoldilocksConfig;
+impl GenericConfig<2> for CustomRescuePrimeGoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = CustomRescuePrimeHash;
+    type InnerHasher = CustomRescuePrimeHash;
+}
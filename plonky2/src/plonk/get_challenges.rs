@@ -1,10 +1,7 @@
-use std::collections::HashSet;
-
 use plonky2_field::extension::Extendable;
 use plonky2_field::polynomial::PolynomialCoeffs;
 
-use crate::fri::proof::{CompressedFriProof, FriChallenges, FriProof, FriProofTarget};
-use crate::fri::verifier::{compute_evaluation, fri_combine_initial, PrecomputedReducedOpenings};
+use crate::fri::proof::{CompressedFriProof, FriInferredElements, FriProof, FriProofTarget};
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
@@ -14,11 +11,10 @@ use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::CommonCircuitData;
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
 use crate::plonk::proof::{
-    CompressedProof, CompressedProofWithPublicInputs, FriInferredElements, OpeningSet,
-    OpeningSetTarget, Proof, ProofChallenges, ProofChallengesTarget, ProofTarget,
-    ProofWithPublicInputs, ProofWithPublicInputsTarget,
+    CompressedProof, CompressedProofWithPublicInputs, OpeningSet, OpeningSetTarget, Proof,
+    ProofChallenges, ProofChallengesTarget, ProofTarget, ProofWithPublicInputs,
+    ProofWithPublicInputsTarget,
 };
-use crate::util::reverse_bits;
 
 fn get_challenges<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     public_inputs_hash: <<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash,
@@ -44,6 +40,14 @@ fn get_challenges<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, cons
     challenger.observe_cap(wires_cap);
     let plonk_betas = challenger.get_n_challenges(num_challenges);
     let plonk_gammas = challenger.get_n_challenges(num_challenges);
+    let (lookup_alphas, lookup_deltas) = if common_data.num_lookup_polys() > 0 {
+        (
+            challenger.get_n_challenges(num_challenges),
+            challenger.get_n_challenges(num_challenges),
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
 
     challenger.observe_cap(plonk_zs_partial_products_cap);
     let plonk_alphas = challenger.get_n_challenges(num_challenges);
@@ -57,6 +61,8 @@ fn get_challenges<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, cons
         plonk_betas,
         plonk_gammas,
         plonk_alphas,
+        lookup_alphas,
+        lookup_deltas,
         plonk_zeta,
         fri_challenges: challenger.fri_challenges::<C, D>(
             commit_phase_merkle_caps,
@@ -162,73 +168,12 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         challenges: &ProofChallenges<F, D>,
         common_data: &CommonCircuitData<F, D>,
     ) -> FriInferredElements<F, D> {
-        let ProofChallenges {
-            plonk_zeta,
-            fri_challenges:
-                FriChallenges {
-                    fri_alpha,
-                    fri_betas,
-                    fri_query_indices,
-                    ..
-                },
-            ..
-        } = challenges;
-        let mut fri_inferred_elements = Vec::new();
-        // Holds the indices that have already been seen at each reduction depth.
-        let mut seen_indices_by_depth =
-            vec![HashSet::new(); common_data.fri_params.reduction_arity_bits.len()];
-        let precomputed_reduced_evals = PrecomputedReducedOpenings::from_os_and_alpha(
+        self.proof.opening_proof.get_inferred_elements::<C>(
+            &common_data.get_fri_instance(challenges.plonk_zeta),
             &self.proof.openings.to_fri_openings(),
-            *fri_alpha,
-        );
-        let log_n = common_data.degree_bits() + common_data.config.fri_config.rate_bits;
-        // Simulate the proof verification and collect the inferred elements.
-        // The content of the loop is basically the same as the `fri_verifier_query_round` function.
-        for &(mut x_index) in fri_query_indices {
-            let mut subgroup_x = F::MULTIPLICATIVE_GROUP_GENERATOR
-                * F::primitive_root_of_unity(log_n).exp_u64(reverse_bits(x_index, log_n) as u64);
-            let mut old_eval = fri_combine_initial::<F, C, D>(
-                &common_data.get_fri_instance(*plonk_zeta),
-                &self
-                    .proof
-                    .opening_proof
-                    .query_round_proofs
-                    .initial_trees_proofs[&x_index],
-                *fri_alpha,
-                subgroup_x,
-                &precomputed_reduced_evals,
-                &common_data.fri_params,
-            );
-            for (i, &arity_bits) in common_data
-                .fri_params
-                .reduction_arity_bits
-                .iter()
-                .enumerate()
-            {
-                let coset_index = x_index >> arity_bits;
-                if !seen_indices_by_depth[i].insert(coset_index) {
-                    // If this index has already been seen, we can skip the rest of the reductions.
-                    break;
-                }
-                fri_inferred_elements.push(old_eval);
-                let arity = 1 << arity_bits;
-                let mut evals = self.proof.opening_proof.query_round_proofs.steps[i][&coset_index]
-                    .evals
-                    .clone();
-                let x_index_within_coset = x_index & (arity - 1);
-                evals.insert(x_index_within_coset, old_eval);
-                old_eval = compute_evaluation(
-                    subgroup_x,
-                    x_index_within_coset,
-                    arity_bits,
-                    &evals,
-                    fri_betas[i],
-                );
-                subgroup_x = subgroup_x.exp_power_of_2(arity_bits);
-                x_index = coset_index;
-            }
-        }
-        FriInferredElements(fri_inferred_elements)
+            &challenges.fri_challenges,
+            &common_data.fri_params,
+        )
     }
 }
 
@@ -261,6 +206,14 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         challenger.observe_cap(wires_cap);
         let plonk_betas = challenger.get_n_challenges(self, num_challenges);
         let plonk_gammas = challenger.get_n_challenges(self, num_challenges);
+        let (lookup_alphas, lookup_deltas) = if inner_common_data.num_lookup_polys() > 0 {
+            (
+                challenger.get_n_challenges(self, num_challenges),
+                challenger.get_n_challenges(self, num_challenges),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
 
         challenger.observe_cap(plonk_zs_partial_products_cap);
         let plonk_alphas = challenger.get_n_challenges(self, num_challenges);
@@ -274,6 +227,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             plonk_betas,
             plonk_gammas,
             plonk_alphas,
+            lookup_alphas,
+            lookup_deltas,
             plonk_zeta,
             fri_challenges: challenger.fri_challenges::<C>(
                 self,
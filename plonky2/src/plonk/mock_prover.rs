@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use plonky2_field::extension::{Extendable, FieldExtension};
+use plonky2_field::polynomial::PolynomialValues;
+use plonky2_field::types::Field;
+
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::try_generate_partial_witness;
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{MatrixWitness, PartialWitness, Witness, WitnessConflict};
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::vars::EvaluationVars;
+
+/// A constraint which is not satisfied by a witness.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MockProverFailure {
+    /// A gate constraint which doesn't evaluate to zero.
+    GateConstraint {
+        row: usize,
+        gate_id: String,
+        constraint_index: usize,
+        /// The stack of contexts which were open when the gate was added.
+        context: String,
+    },
+    /// Two routed wires which are copy constrained, but have different values.
+    CopyConstraint {
+        wire: Wire,
+        other_wire: Wire,
+        /// The stack of contexts which were open when the gate of `wire` was added.
+        context: String,
+        /// The stack of contexts which were open when the gate of `other_wire` was added.
+        other_context: String,
+    },
+    /// A generator, or an input, set a target to a different value than the one already set for a
+    /// target it is copy constrained to. Witness generation stops at the first conflict, so no
+    /// other constraint is checked.
+    WitnessConflict {
+        target: Target,
+        /// A routed wire copy constrained to `target`, if there is one.
+        wire: Option<Wire>,
+        /// The id of the gate of `wire`.
+        gate_id: Option<String>,
+        /// The stack of contexts which were open when the gate of `wire` was added.
+        context: Option<String>,
+    },
+    /// A lookup whose `(input, output)` pair is not an entry of its table.
+    Lookup {
+        row: usize,
+        slot: usize,
+        table_index: usize,
+        /// The stack of contexts which were open when the gate was added.
+        context: String,
+    },
+    /// A table entry whose multiplicity is not the number of lookups of that entry.
+    LookupMultiplicity {
+        row: usize,
+        slot: usize,
+        table_index: usize,
+        /// The stack of contexts which were open when the gate was added.
+        context: String,
+    },
+}
+
+impl Display for MockProverFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MockProverFailure::GateConstraint {
+                row,
+                gate_id,
+                constraint_index,
+                context,
+            } => write!(
+                f,
+                "Constraint {} of {} at row {} is not satisfied (context: {})",
+                constraint_index, gate_id, row, context
+            ),
+            MockProverFailure::CopyConstraint {
+                wire,
+                other_wire,
+                context,
+                other_context,
+            } => write!(
+                f,
+                "Copy constraint between {:?} (context: {}) and {:?} (context: {}) is not satisfied",
+                wire, context, other_wire, other_context
+            ),
+            MockProverFailure::WitnessConflict {
+                target,
+                wire,
+                gate_id,
+                context,
+            } => match (wire, gate_id, context) {
+                (Some(wire), Some(gate_id), Some(context)) => write!(
+                    f,
+                    "{:?} was generated with two different values, at {:?} of {} (context: {})",
+                    target, wire, gate_id, context
+                ),
+                _ => write!(f, "{:?} was generated with two different values", target),
+            },
+            MockProverFailure::Lookup {
+                row,
+                slot,
+                table_index,
+                context,
+            } => write!(
+                f,
+                "Lookup {} at row {} is not in table {} (context: {})",
+                slot, row, table_index, context
+            ),
+            MockProverFailure::LookupMultiplicity {
+                row,
+                slot,
+                table_index,
+                context,
+            } => write!(
+                f,
+                "Multiplicity of entry {} at row {} of table {} is wrong (context: {})",
+                slot, row, table_index, context
+            ),
+        }
+    }
+}
+
+/// Generates the witness for the given inputs, then checks it against every gate constraint, copy
+/// constraint and lookup of the circuit, without committing to anything. Returns all unsatisfied
+/// constraints, which is more helpful than a failing proof when debugging a circuit.
+pub fn mock_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+) -> Vec<MockProverFailure> {
+    let partition_witness = match try_generate_partial_witness(inputs, prover_data, common_data) {
+        Ok(partition_witness) => partition_witness,
+        Err(conflict) => return vec![conflict_failure(prover_data, common_data, conflict)],
+    };
+    let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
+    let witness = partition_witness.full_witness();
+    check_witness(prover_data, common_data, &witness, &public_inputs)
+}
+
+/// Checks a full witness against every gate constraint, copy constraint and lookup of the circuit.
+pub(crate) fn check_witness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    witness: &MatrixWitness<F>,
+    public_inputs: &[F],
+) -> Vec<MockProverFailure> {
+    let constants = prover_data.constants_values(common_data);
+    let mut failures =
+        check_gate_constraints(prover_data, common_data, &constants, witness, public_inputs);
+    failures.extend(check_copy_constraints(prover_data, common_data, witness));
+    failures.extend(check_lookups(prover_data, common_data, &constants, witness));
+    failures
+}
+
+/// The values of the constant polynomials on a row, along with the index in `common_data.gates` of
+/// the row's gate.
+fn row_constants_and_gate<F: RichField + Extendable<D>, const D: usize>(
+    common_data: &CommonCircuitData<F, D>,
+    constants: &[PolynomialValues<F>],
+    row: usize,
+) -> (Vec<F>, usize) {
+    let num_selectors = common_data.selectors_info.num_selectors();
+    let row_constants = constants.iter().map(|c| c.values[row]).collect::<Vec<_>>();
+    let gate_index = common_data.gate_index(&row_constants[..num_selectors]);
+    (row_constants, gate_index)
+}
+
+/// Locates a witness conflict, using the first routed wire in the partition of the conflicting
+/// target.
+fn conflict_failure<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    conflict: WitnessConflict<F>,
+) -> MockProverFailure {
+    let config = &common_data.config;
+    let degree = common_data.degree();
+    let rep_of = |t: Target| prover_data.representative_map[t.index(config.num_wires, degree)];
+    let rep = rep_of(conflict.target);
+    let wire = (0..degree)
+        .flat_map(|row| (0..config.num_routed_wires).map(move |column| Wire { row, column }))
+        .find(|&wire| rep_of(Target::Wire(wire)) == rep)
+        .or(match conflict.target {
+            Target::Wire(wire) => Some(wire),
+            Target::VirtualTarget { .. } => None,
+        });
+
+    let constants = prover_data.constants_values(common_data);
+    MockProverFailure::WitnessConflict {
+        target: conflict.target,
+        wire,
+        gate_id: wire.map(|wire| {
+            let (_, gate_index) = row_constants_and_gate(common_data, &constants, wire.row);
+            common_data.gates[gate_index].0.id()
+        }),
+        context: wire.map(|wire| prover_data.context_tree.gate_context(wire.row)),
+    }
+}
+
+fn check_gate_constraints<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    constants: &[PolynomialValues<F>],
+    witness: &MatrixWitness<F>,
+    public_inputs: &[F],
+) -> Vec<MockProverFailure> {
+    let public_inputs_hash = C::InnerHasher::hash_no_pad(public_inputs);
+    let num_selectors = common_data.selectors_info.num_selectors();
+
+    let mut failures = Vec::new();
+    for row in 0..common_data.degree() {
+        let (row_constants, gate_index) = row_constants_and_gate(common_data, constants, row);
+        let gate = &common_data.gates[gate_index];
+
+        let local_constants = row_constants
+            .into_iter()
+            .map(F::Extension::from_basefield)
+            .collect::<Vec<_>>();
+        let local_wires = (0..common_data.config.num_wires)
+            .map(|column| F::Extension::from_basefield(witness.get_wire(row, column)))
+            .collect::<Vec<_>>();
+        let mut vars = EvaluationVars {
+            local_constants: &local_constants,
+            local_wires: &local_wires,
+            public_inputs_hash: &public_inputs_hash,
+        };
+        vars.remove_prefix(num_selectors);
+
+        for (constraint_index, constraint) in gate.0.eval_unfiltered(vars).into_iter().enumerate() {
+            if constraint != F::Extension::ZERO {
+                failures.push(MockProverFailure::GateConstraint {
+                    row,
+                    gate_id: gate.0.id(),
+                    constraint_index,
+                    context: prover_data.context_tree.gate_context(row),
+                });
+            }
+        }
+    }
+    failures
+}
+
+fn check_copy_constraints<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    witness: &MatrixWitness<F>,
+) -> Vec<MockProverFailure> {
+    let config = &common_data.config;
+    let degree = common_data.degree();
+
+    // The first wire of each partition, along with its value, indexed by the partition's
+    // representative.
+    let mut partition_wires = HashMap::new();
+    let mut failures = Vec::new();
+    for row in 0..degree {
+        for column in 0..config.num_routed_wires {
+            let wire = Wire { row, column };
+            let rep =
+                prover_data.representative_map[Target::Wire(wire).index(config.num_wires, degree)];
+            let value = witness.get_wire(row, column);
+            let &mut (first_wire, first_value) =
+                partition_wires.entry(rep).or_insert((wire, value));
+            if value != first_value {
+                failures.push(MockProverFailure::CopyConstraint {
+                    wire: first_wire,
+                    other_wire: wire,
+                    context: prover_data.context_tree.gate_context(first_wire.row),
+                    other_context: prover_data.context_tree.gate_context(row),
+                });
+            }
+        }
+    }
+    failures
+}
+
+/// Checks the lookup argument in its multiset form: every lookup must be an entry of its table, and
+/// the multiplicities of each entry, summed over the table's slots holding it, must be the number
+/// of lookups of the entry.
+fn check_lookups<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    constants: &[PolynomialValues<F>],
+    witness: &MatrixWitness<F>,
+) -> Vec<MockProverFailure> {
+    let num_slots = common_data.num_lookup_slots;
+    if num_slots == 0 {
+        return Vec::new();
+    }
+    let num_selectors = common_data.selectors_info.num_selectors();
+    let table_gate = LookupTableGate { num_slots };
+
+    // Entries are identified by their table index, input and output.
+    let mut lookups = Vec::new();
+    let mut lookup_counts = HashMap::<_, usize>::new();
+    let mut entries = Vec::new();
+    let mut multiplicities = HashMap::<_, F>::new();
+    for row in 0..common_data.degree() {
+        let (row_constants, gate_index) = row_constants_and_gate(common_data, constants, row);
+        let gate = common_data.gates[gate_index].0.as_any();
+        // The table index is the first constant of both lookup gates.
+        let table_index = row_constants[num_selectors].to_canonical_u64() as usize;
+        let entry = |inp: usize, out: usize| {
+            (
+                table_index,
+                witness.get_wire(row, inp).to_canonical_u64(),
+                witness.get_wire(row, out).to_canonical_u64(),
+            )
+        };
+        if gate.is::<LookupGate>() {
+            for slot in 0..num_slots {
+                let key = entry(
+                    LookupGate::wire_ith_looking_inp(slot),
+                    LookupGate::wire_ith_looking_out(slot),
+                );
+                lookups.push((row, slot, key));
+                *lookup_counts.entry(key).or_default() += 1;
+            }
+        } else if gate.is::<LookupTableGate>() {
+            for slot in 0..num_slots {
+                let key = entry(
+                    LookupTableGate::wire_ith_looked_inp(slot),
+                    LookupTableGate::wire_ith_looked_out(slot),
+                );
+                let multiplicity = witness.get_wire(row, table_gate.wire_ith_multiplicity(slot));
+                match multiplicities.get_mut(&key) {
+                    Some(m) => *m += multiplicity,
+                    None => {
+                        multiplicities.insert(key, multiplicity);
+                        entries.push((row, slot, key));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut failures = Vec::new();
+    for (row, slot, key) in lookups {
+        if !multiplicities.contains_key(&key) {
+            failures.push(MockProverFailure::Lookup {
+                row,
+                slot,
+                table_index: key.0,
+                context: prover_data.context_tree.gate_context(row),
+            });
+        }
+    }
+    for (row, slot, key) in entries {
+        let count = lookup_counts.get(&key).copied().unwrap_or(0);
+        if multiplicities[&key] != F::from_canonical_usize(count) {
+            failures.push(MockProverFailure::LookupMultiplicity {
+                row,
+                slot,
+                table_index: key.0,
+                context: prover_data.context_tree.gate_context(row),
+            });
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::gates::arithmetic_base::ArithmeticGate;
+    use crate::gates::gate::Gate;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_mock_prover() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        builder.push_context(log::Level::Debug, "square");
+        let square = builder.square(x);
+        builder.pop_context();
+        builder.register_public_input(square);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        assert_eq!(data.mock_prove(pw.clone()), vec![]);
+
+        // Tamper with the output of the multiplication.
+        let partition_witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        let public_inputs = partition_witness.get_targets(&data.prover_only.public_inputs);
+        let mut witness = partition_witness.full_witness();
+        let wire = match square {
+            Target::Wire(wire) => wire,
+            Target::VirtualTarget { .. } => panic!("Expected a wire"),
+        };
+        assert_eq!(wire.column, ArithmeticGate::wire_ith_output(0));
+        witness.wire_values[wire.column][wire.row] += F::ONE;
+
+        let failures = check_witness(&data.prover_only, &data.common, &witness, &public_inputs);
+        assert!(failures.contains(&MockProverFailure::GateConstraint {
+            row: wire.row,
+            gate_id: Gate::<F, D>::id(&ArithmeticGate::new_from_config(&data.common.config)),
+            constraint_index: 0,
+            context: "root > square".to_string(),
+        }));
+        assert!(failures.iter().any(|failure| matches!(
+            failure,
+            MockProverFailure::CopyConstraint { wire: w, other_wire: o, .. } if *w == wire || *o == wire
+        )));
+        assert!(failures.iter().all(|failure| match failure {
+            MockProverFailure::GateConstraint { row, .. } => *row == wire.row,
+            MockProverFailure::CopyConstraint { .. } => true,
+            _ => false,
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mock_prover_witness_conflict() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        builder.push_context(log::Level::Debug, "conflict");
+        let square = builder.square(x);
+        let one = builder.one();
+        let x_plus_one = builder.add(x, one);
+        builder.pop_context();
+        // The generators of `square` and `x_plus_one` only agree if `x^2 = x + 1`.
+        builder.connect(square, x_plus_one);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let failures = data.mock_prove(pw);
+        assert_eq!(failures.len(), 1);
+        let gate_id = Gate::<F, D>::id(&ArithmeticGate::new_from_config(&data.common.config));
+        match &failures[0] {
+            MockProverFailure::WitnessConflict {
+                target,
+                wire: Some(_),
+                gate_id: Some(id),
+                context: Some(context),
+            } => {
+                assert!(*target == square || *target == x_plus_one);
+                assert_eq!(*id, gate_id);
+                assert_eq!(context, "root > conflict");
+            }
+            failure => panic!("Unexpected failure: {failure}"),
+        }
+    }
+
+    #[test]
+    fn test_mock_prover_lookups() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let table = Arc::new((0..8).map(|i| (i, 7 - i)).collect::<Vec<_>>());
+        let table_index = builder.add_lookup_table(table);
+        let x = builder.add_virtual_target();
+        builder.push_context(log::Level::Debug, "lookup");
+        let y = builder.lookup(table_index, x);
+        builder.pop_context();
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        assert_eq!(data.mock_prove(pw.clone()), vec![]);
+
+        let partition_witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        let public_inputs = partition_witness.get_targets(&data.prover_only.public_inputs);
+        let witness = partition_witness.full_witness();
+        let Target::Wire(y_wire) = y else {
+            panic!("Expected a wire")
+        };
+
+        let constants = data.prover_only.constants_values(&data.common);
+        let table_row = (0..data.common.degree())
+            .find(|&row| {
+                let (_, gate_index) = row_constants_and_gate(&data.common, &constants, row);
+                data.common.gates[gate_index]
+                    .0
+                    .as_any()
+                    .is::<LookupTableGate>()
+            })
+            .unwrap();
+
+        // Tamper with the output of the lookup, which is then not in the table. The looked up
+        // entry, `(3, 4)`, is then looked up one time less than its multiplicity.
+        let mut tampered = witness.clone();
+        tampered.wire_values[y_wire.column][y_wire.row] += F::ONE;
+        let failures = check_witness(&data.prover_only, &data.common, &tampered, &public_inputs);
+        assert_eq!(
+            failures,
+            vec![
+                MockProverFailure::Lookup {
+                    row: y_wire.row,
+                    slot: 0,
+                    table_index,
+                    context: "root > lookup".to_string(),
+                },
+                MockProverFailure::LookupMultiplicity {
+                    row: table_row,
+                    slot: 3,
+                    table_index,
+                    context: "root".to_string(),
+                }
+            ]
+        );
+
+        // Tamper with the multiplicity of the looked up entry.
+        let table_gate = LookupTableGate::new_from_config(&data.common.config);
+        let mut tampered = witness;
+        tampered.wire_values[table_gate.wire_ith_multiplicity(3)][table_row] += F::ONE;
+        let failures = check_witness(&data.prover_only, &data.common, &tampered, &public_inputs);
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(
+            failures[0],
+            MockProverFailure::LookupMultiplicity { row, slot: 3, .. } if row == table_row
+        ));
+    }
+}
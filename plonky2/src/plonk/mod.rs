@@ -1,13 +1,18 @@
+pub mod batch_prover;
 pub mod circuit_builder;
 pub mod circuit_data;
+pub mod circuit_stats;
 pub mod config;
 pub(crate) mod copy_constraint;
 mod get_challenges;
+pub mod mock_prover;
+pub mod multi_circuit;
 pub(crate) mod permutation_argument;
 pub mod plonk_common;
 pub mod proof;
 pub mod prover;
-mod validate_shape;
+pub mod public_data;
+pub(crate) mod validate_shape;
 pub(crate) mod vanishing_poly;
 pub mod vars;
 pub mod verifier;
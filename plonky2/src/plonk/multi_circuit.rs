@@ -0,0 +1,780 @@
+use std::borrow::Cow;
+
+use anyhow::{anyhow, ensure, Result};
+use maybe_rayon::*;
+use plonky2_field::extension::Extendable;
+use plonky2_field::fft::{fft_root_table, FftRootTable};
+use plonky2_field::polynomial::PolynomialValues;
+use plonky2_field::types::Field;
+use serde::{Deserialize, Serialize};
+
+use crate::fri::oracle::PolynomialBatch;
+use crate::fri::proof::{FriProof, FriProofTarget};
+use crate::fri::structure::{FriInstanceInfo, FriOpenings};
+use crate::fri::verifier::verify_fri_proof;
+use crate::fri::FriParams;
+use crate::hash::hash_types::{MerkleCapTarget, RichField};
+use crate::hash::merkle_tree::MerkleCap;
+use crate::iop::challenger::Challenger;
+use crate::iop::generator::try_generate_partial_witness;
+use crate::iop::target::Target;
+use crate::iop::witness::{PartialWitness, Witness};
+use crate::plonk::circuit_data::{
+    CircuitData, CommonCircuitData, ProverCircuitData, VerifierCircuitData, VerifierCircuitTarget,
+};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::plonk_common::PlonkOracle;
+use crate::plonk::proof::{OpeningSet, OpeningSetTarget};
+use crate::plonk::prover::{
+    compute_quotient_polys, compute_zs_partial_products, split_quotient_polys, ProverPrecomputation,
+};
+use crate::plonk::validate_shape::validate_opening_set_shape;
+use crate::plonk::verifier::verify_vanishing_identity;
+use crate::timed;
+use crate::util::timing::TimingTree;
+
+/// The number of oracles committed to for each circuit, namely the constants and sigmas, wires,
+/// Zs and partial products, and quotient oracles.
+pub(crate) const NUM_ORACLES_PER_CIRCUIT: usize = 4;
+
+/// Several independent circuits, of possibly different degrees, proven together with a single FRI
+/// proof.
+///
+/// The polynomials of every circuit are committed on the LDE domain of the largest circuit, i.e.
+/// with a higher rate for smaller circuits, so that all their openings can be checked by one FRI
+/// instance. Each circuit is still opened at its own `zeta`. The degree of the polynomials of a
+/// smaller circuit is then only bounded by the degree of the largest circuit, which only loosens
+/// the Schwartz-Zippel bound of the checks at `zeta`, since the identities of a circuit only
+/// concern the values of its polynomials on its own subgroup.
+pub struct MultiCircuitData<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub prover_data: Vec<ProverCircuitData<F, C, D>>,
+    pub verifier_data: MultiCircuitVerifierData<F, C, D>,
+    /// The constants and sigmas polynomials of each circuit, committed on the common LDE domain.
+    constants_sigmas_commitments: Vec<PolynomialBatch<F, C, D>>,
+    /// Pre-computed roots for FFTs on the common LDE domain.
+    fft_root_table: FftRootTable<F>,
+}
+
+/// The data required to verify a `MultiCircuitProof`.
+pub struct MultiCircuitVerifierData<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub circuits: Vec<VerifierCircuitData<F, C, D>>,
+    /// A commitment to the constants and sigmas polynomials of each circuit, on the common LDE
+    /// domain. These differ from the `constants_sigmas_cap` of each circuit, except for the
+    /// largest ones.
+    pub constants_sigmas_caps: Vec<MerkleCap<F, C::Hasher>>,
+    /// The parameters of the FRI proof, whose degree is that of the largest circuit.
+    pub fri_params: FriParams,
+}
+
+/// The commitments, openings and public inputs of one of the circuits of a `MultiCircuitProof`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(bound = "")]
+pub struct MultiCircuitProofEntry<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    /// Merkle cap of LDEs of wire values.
+    pub wires_cap: MerkleCap<F, C::Hasher>,
+    /// Merkle cap of LDEs of Z, in the context of Plonk's permutation argument.
+    pub plonk_zs_partial_products_cap: MerkleCap<F, C::Hasher>,
+    /// Merkle cap of LDEs of the quotient polynomial components.
+    pub quotient_polys_cap: MerkleCap<F, C::Hasher>,
+    /// Purported values of each polynomial at the circuit's challenge point.
+    pub openings: OpeningSet<F, D>,
+    pub public_inputs: Vec<F>,
+}
+
+/// A proof of several circuits, sharing a single FRI proof.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(bound = "")]
+pub struct MultiCircuitProof<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    /// The part of the proof specific to each circuit, in order.
+    pub entries: Vec<MultiCircuitProofEntry<F, C, D>>,
+    /// A batch FRI argument for the openings of all circuits.
+    pub opening_proof: FriProof<F, C::Hasher, D>,
+}
+
+/// The verifier data of each circuit of a `MultiCircuitProof`, in a circuit verifying it
+/// recursively. The `constants_sigmas_cap` of each circuit is committed on the common LDE domain,
+/// as in `MultiCircuitVerifierData::constants_sigmas_caps`.
+#[derive(Clone)]
+pub struct MultiCircuitVerifierTarget {
+    pub circuits: Vec<VerifierCircuitTarget>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MultiCircuitProofEntryTarget<const D: usize> {
+    pub wires_cap: MerkleCapTarget,
+    pub plonk_zs_partial_products_cap: MerkleCapTarget,
+    pub quotient_polys_cap: MerkleCapTarget,
+    pub openings: OpeningSetTarget<D>,
+    pub public_inputs: Vec<Target>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MultiCircuitProofTarget<const D: usize> {
+    pub entries: Vec<MultiCircuitProofEntryTarget<D>>,
+    pub opening_proof: FriProofTarget<D>,
+}
+
+/// The challenges of Plonk's permutation argument and of the lookup argument of one circuit.
+struct PermutationChallenges<F: RichField> {
+    betas: Vec<F>,
+    gammas: Vec<F>,
+    lookup_alphas: Vec<F>,
+    lookup_deltas: Vec<F>,
+}
+
+impl<F: RichField> PermutationChallenges<F> {
+    fn sample<H: Hasher<F>, const D: usize>(
+        challenger: &mut Challenger<F, H>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Self
+    where
+        F: Extendable<D>,
+    {
+        let num_challenges = common_data.config.num_challenges;
+        let betas = challenger.get_n_challenges(num_challenges);
+        let gammas = challenger.get_n_challenges(num_challenges);
+        let (lookup_alphas, lookup_deltas) = if common_data.num_lookup_polys() > 0 {
+            (
+                challenger.get_n_challenges(num_challenges),
+                challenger.get_n_challenges(num_challenges),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        Self {
+            betas,
+            gammas,
+            lookup_alphas,
+            lookup_deltas,
+        }
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    MultiCircuitData<F, C, D>
+{
+    /// Prepares the given circuits to be proven together. They must all use the same FRI config,
+    /// and either all or none of them must be zero-knowledge.
+    pub fn new(circuits: Vec<CircuitData<F, C, D>>) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        assert!(!circuits.is_empty(), "Cannot batch zero circuits");
+        let config = &circuits[0].common.config;
+        for circuit in &circuits[1..] {
+            assert_eq!(
+                circuit.common.config.fri_config, config.fri_config,
+                "Batched circuits should use the same FRI config"
+            );
+            assert_eq!(
+                circuit.common.config.zero_knowledge, config.zero_knowledge,
+                "Batched circuits should all be zero-knowledge, or none of them"
+            );
+        }
+
+        let fri_params = circuits
+            .iter()
+            .map(|circuit| &circuit.common.fri_params)
+            .max_by_key(|fri_params| fri_params.degree_bits)
+            .unwrap()
+            .clone();
+        let lde_bits = fri_params.lde_bits();
+        let fft_root_table = fft_root_table(1 << lde_bits);
+
+        let constants_sigmas_commitments = circuits
+            .iter()
+            .map(|circuit| {
+                let commitment = &circuit.prover_only.constants_sigmas_commitment;
+                let indices = (0..commitment.num_polynomials()).collect::<Vec<_>>();
+                PolynomialBatch::from_coeffs(
+                    commitment
+                        .polynomial_chunks(&indices)
+                        .flatten()
+                        .map(Cow::into_owned)
+                        .collect(),
+                    lde_bits - circuit.common.degree_bits(),
+                    PlonkOracle::CONSTANTS_SIGMAS.blinding,
+                    fri_params.config.cap_height,
+                    &mut TimingTree::default(),
+                    Some(&fft_root_table),
+                )
+            })
+            .collect::<Vec<_>>();
+        let constants_sigmas_caps = constants_sigmas_commitments
+            .iter()
+            .map(|commitment| commitment.merkle_tree.cap.clone())
+            .collect();
+
+        let (prover_data, verifier_circuits) = circuits
+            .into_iter()
+            .map(|circuit| {
+                let CircuitData {
+                    prover_only,
+                    verifier_only,
+                    common,
+                } = circuit;
+                let prover_data = ProverCircuitData {
+                    prover_only,
+                    common: common.clone(),
+                };
+                let verifier_data = VerifierCircuitData {
+                    verifier_only,
+                    common,
+                };
+                (prover_data, verifier_data)
+            })
+            .unzip();
+
+        Self {
+            prover_data,
+            verifier_data: MultiCircuitVerifierData {
+                circuits: verifier_circuits,
+                constants_sigmas_caps,
+                fri_params,
+            },
+            constants_sigmas_commitments,
+            fft_root_table,
+        }
+    }
+
+    pub fn num_circuits(&self) -> usize {
+        self.prover_data.len()
+    }
+
+    /// Proves all circuits, given the inputs of each of them, in order.
+    pub fn prove(&self, inputs: Vec<PartialWitness<F>>) -> Result<MultiCircuitProof<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        self.prove_with_timing(inputs, &mut TimingTree::default())
+    }
+
+    pub fn prove_with_timing(
+        &self,
+        inputs: Vec<PartialWitness<F>>,
+        timing: &mut TimingTree,
+    ) -> Result<MultiCircuitProof<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        ensure!(
+            inputs.len() == self.num_circuits(),
+            "Expected inputs for {} circuits, got {}",
+            self.num_circuits(),
+            inputs.len()
+        );
+        let fri_params = &self.verifier_data.fri_params;
+        let lde_bits = fri_params.lde_bits();
+        let zero_knowledge = self.prover_data[0].common.config.zero_knowledge;
+        let cap_height = fri_params.config.cap_height;
+        let commit = |polynomials, degree_bits, blinding, timing: &mut TimingTree| {
+            PolynomialBatch::<F, C, D>::from_coeffs(
+                polynomials,
+                lde_bits - degree_bits,
+                zero_knowledge && blinding,
+                cap_height,
+                timing,
+                Some(&self.fft_root_table),
+            )
+        };
+
+        let mut witnesses = Vec::with_capacity(self.num_circuits());
+        let mut all_public_inputs = Vec::with_capacity(self.num_circuits());
+        let mut wires_commitments = Vec::with_capacity(self.num_circuits());
+        for (circuit, inputs) in self.prover_data.iter().zip(inputs) {
+            let partition_witness = timed!(
+                timing,
+                &format!("run {} generators", circuit.prover_only.generators.len()),
+                try_generate_partial_witness(inputs, &circuit.prover_only, &circuit.common)
+                    .map_err(|conflict| anyhow!("{conflict}"))?
+            );
+            all_public_inputs
+                .push(partition_witness.get_targets(&circuit.prover_only.public_inputs));
+            let witness = timed!(
+                timing,
+                "compute full witness",
+                partition_witness.full_witness()
+            );
+
+            let wires_coeffs = timed!(
+                timing,
+                "compute wire polynomials",
+                witness
+                    .wire_values
+                    .par_iter()
+                    .map(|column| PolynomialValues::new(column.clone()).ifft())
+                    .collect()
+            );
+            wires_commitments.push(timed!(
+                timing,
+                "compute wires commitment",
+                commit(
+                    wires_coeffs,
+                    circuit.common.degree_bits(),
+                    PlonkOracle::WIRES.blinding,
+                    timing
+                )
+            ));
+            witnesses.push(witness);
+        }
+        let public_inputs_hashes = all_public_inputs
+            .iter()
+            .map(|public_inputs| C::InnerHasher::hash_no_pad(public_inputs))
+            .collect::<Vec<_>>();
+
+        let mut challenger = Challenger::<F, C::Hasher>::new();
+        self.verifier_data
+            .observe_instances(&mut challenger, &public_inputs_hashes);
+
+        for wires_commitment in &wires_commitments {
+            challenger.observe_cap(&wires_commitment.merkle_tree.cap);
+        }
+        let permutation_challenges = self
+            .prover_data
+            .iter()
+            .map(|circuit| PermutationChallenges::sample(&mut challenger, &circuit.common))
+            .collect::<Vec<_>>();
+
+        let precomputations = self
+            .prover_data
+            .iter()
+            .map(|circuit| ProverPrecomputation::new(&circuit.prover_only, &circuit.common))
+            .collect::<Vec<_>>();
+        let zs_partial_products_commitments = self
+            .prover_data
+            .iter()
+            .zip(&witnesses)
+            .zip(&permutation_challenges)
+            .zip(&precomputations)
+            .map(|(((circuit, witness), challenges), precomputation)| {
+                let zs_partial_products = compute_zs_partial_products(
+                    witness,
+                    &challenges.betas,
+                    &challenges.gammas,
+                    &challenges.lookup_alphas,
+                    &challenges.lookup_deltas,
+                    &circuit.prover_only,
+                    &circuit.common,
+                    precomputation,
+                    timing,
+                );
+                let zs_partial_products_coeffs = zs_partial_products
+                    .into_par_iter()
+                    .map(|values| values.ifft())
+                    .collect();
+                timed!(
+                    timing,
+                    "commit to partial products and Z's",
+                    commit(
+                        zs_partial_products_coeffs,
+                        circuit.common.degree_bits(),
+                        PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
+                        timing
+                    )
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for zs_partial_products_commitment in &zs_partial_products_commitments {
+            challenger.observe_cap(&zs_partial_products_commitment.merkle_tree.cap);
+        }
+        let all_alphas = self
+            .prover_data
+            .iter()
+            .map(|circuit| challenger.get_n_challenges(circuit.common.config.num_challenges))
+            .collect::<Vec<_>>();
+
+        let mut quotient_polys_commitments = Vec::with_capacity(self.num_circuits());
+        for i in 0..self.num_circuits() {
+            let common_data = &self.prover_data[i].common;
+            let challenges = &permutation_challenges[i];
+            let quotient_polys = timed!(
+                timing,
+                "compute quotient polys",
+                compute_quotient_polys::<F, C, D>(
+                    common_data,
+                    &precomputations[i],
+                    &self.constants_sigmas_commitments[i],
+                    &public_inputs_hashes[i],
+                    &wires_commitments[i],
+                    &zs_partial_products_commitments[i],
+                    &challenges.betas,
+                    &challenges.gammas,
+                    &challenges.lookup_alphas,
+                    &challenges.lookup_deltas,
+                    &all_alphas[i],
+                )
+            );
+            let all_quotient_poly_chunks = timed!(
+                timing,
+                "split up quotient polys",
+                split_quotient_polys(quotient_polys, common_data)
+            );
+            quotient_polys_commitments.push(timed!(
+                timing,
+                "commit to quotient polys",
+                commit(
+                    all_quotient_poly_chunks,
+                    common_data.degree_bits(),
+                    PlonkOracle::QUOTIENT.blinding,
+                    timing
+                )
+            ));
+        }
+
+        for quotient_polys_commitment in &quotient_polys_commitments {
+            challenger.observe_cap(&quotient_polys_commitment.merkle_tree.cap);
+        }
+        let mut zetas = Vec::with_capacity(self.num_circuits());
+        for circuit in &self.prover_data {
+            let zeta = challenger.get_extension_challenge::<D>();
+            // As in the single circuit prover, `zeta` should not be in the subgroup `H` of the
+            // circuit, to avoid leaking witness data.
+            ensure!(
+                zeta.exp_power_of_2(circuit.common.degree_bits()) != F::Extension::ONE,
+                "Opening point is in the subgroup."
+            );
+            zetas.push(zeta);
+        }
+
+        let all_openings = timed!(
+            timing,
+            "construct the opening sets",
+            (0..self.num_circuits())
+                .map(|i| {
+                    let common_data = &self.prover_data[i].common;
+                    let g = F::Extension::primitive_root_of_unity(common_data.degree_bits());
+                    OpeningSet::new(
+                        zetas[i],
+                        g,
+                        &self.constants_sigmas_commitments[i],
+                        &wires_commitments[i],
+                        &zs_partial_products_commitments[i],
+                        &quotient_polys_commitments[i],
+                        common_data,
+                    )
+                })
+                .collect::<Vec<_>>()
+        );
+        for openings in &all_openings {
+            challenger.observe_openings(&openings.to_fri_openings());
+        }
+
+        let oracles = (0..self.num_circuits())
+            .flat_map(|i| {
+                [
+                    &self.constants_sigmas_commitments[i],
+                    &wires_commitments[i],
+                    &zs_partial_products_commitments[i],
+                    &quotient_polys_commitments[i],
+                ]
+            })
+            .collect::<Vec<_>>();
+        let opening_proof = timed!(
+            timing,
+            "compute opening proofs",
+            PolynomialBatch::prove_openings(
+                &self.verifier_data.get_fri_instance(&zetas),
+                &oracles,
+                &mut challenger,
+                fri_params,
+                timing,
+            )
+        );
+
+        let entries = wires_commitments
+            .into_iter()
+            .zip(zs_partial_products_commitments)
+            .zip(quotient_polys_commitments)
+            .zip(all_openings)
+            .zip(all_public_inputs)
+            .map(
+                |(
+                    (
+                        (
+                            (wires_commitment, zs_partial_products_commitment),
+                            quotient_polys_commitment,
+                        ),
+                        openings,
+                    ),
+                    public_inputs,
+                )| MultiCircuitProofEntry {
+                    wires_cap: wires_commitment.merkle_tree.cap,
+                    plonk_zs_partial_products_cap: zs_partial_products_commitment.merkle_tree.cap,
+                    quotient_polys_cap: quotient_polys_commitment.merkle_tree.cap,
+                    openings,
+                    public_inputs,
+                },
+            )
+            .collect();
+        Ok(MultiCircuitProof {
+            entries,
+            opening_proof,
+        })
+    }
+
+    pub fn verify(&self, proof: MultiCircuitProof<F, C, D>) -> Result<()>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        self.verifier_data.verify(proof)
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    MultiCircuitVerifierData<F, C, D>
+{
+    pub fn verify(&self, proof: MultiCircuitProof<F, C, D>) -> Result<()>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        self.validate_proof_shape(&proof)?;
+
+        let public_inputs_hashes = proof
+            .entries
+            .iter()
+            .map(|entry| C::InnerHasher::hash_no_pad(&entry.public_inputs))
+            .collect::<Vec<_>>();
+
+        // Replay the prover's transcript.
+        let mut challenger = Challenger::<F, C::Hasher>::new();
+        self.observe_instances(&mut challenger, &public_inputs_hashes);
+        for entry in &proof.entries {
+            challenger.observe_cap(&entry.wires_cap);
+        }
+        let permutation_challenges = self
+            .circuits
+            .iter()
+            .map(|circuit| PermutationChallenges::sample(&mut challenger, &circuit.common))
+            .collect::<Vec<_>>();
+        for entry in &proof.entries {
+            challenger.observe_cap(&entry.plonk_zs_partial_products_cap);
+        }
+        let all_alphas = self
+            .circuits
+            .iter()
+            .map(|circuit| challenger.get_n_challenges(circuit.common.config.num_challenges))
+            .collect::<Vec<_>>();
+        for entry in &proof.entries {
+            challenger.observe_cap(&entry.quotient_polys_cap);
+        }
+        let zetas = self
+            .circuits
+            .iter()
+            .map(|_| challenger.get_extension_challenge::<D>())
+            .collect::<Vec<_>>();
+        for entry in &proof.entries {
+            challenger.observe_openings(&entry.openings.to_fri_openings());
+        }
+        let fri_challenges = challenger.fri_challenges::<C, D>(
+            &proof.opening_proof.commit_phase_merkle_caps,
+            &proof.opening_proof.final_poly,
+            proof.opening_proof.pow_witness,
+            self.fri_params.degree_bits,
+            &self.fri_params.config,
+        );
+
+        for (i, entry) in proof.entries.iter().enumerate() {
+            let challenges = &permutation_challenges[i];
+            verify_vanishing_identity::<F, C, D>(
+                &entry.openings,
+                public_inputs_hashes[i],
+                &challenges.betas,
+                &challenges.gammas,
+                &all_alphas[i],
+                &challenges.lookup_alphas,
+                &challenges.lookup_deltas,
+                zetas[i],
+                &self.circuits[i].common,
+            )?;
+        }
+
+        let merkle_caps = proof
+            .entries
+            .iter()
+            .zip(&self.constants_sigmas_caps)
+            .flat_map(|(entry, constants_sigmas_cap)| {
+                [
+                    constants_sigmas_cap.clone(),
+                    entry.wires_cap.clone(),
+                    entry.plonk_zs_partial_products_cap.clone(),
+                    entry.quotient_polys_cap.clone(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let openings = FriOpenings {
+            batches: proof
+                .entries
+                .iter()
+                .flat_map(|entry| entry.openings.to_fri_openings().batches)
+                .collect(),
+        };
+
+        verify_fri_proof::<F, C, D>(
+            &self.get_fri_instance(&zetas),
+            &openings,
+            &fri_challenges,
+            &merkle_caps,
+            &proof.opening_proof,
+            &self.fri_params,
+        )
+    }
+
+    fn validate_proof_shape(&self, proof: &MultiCircuitProof<F, C, D>) -> Result<()>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        ensure!(
+            proof.entries.len() == self.circuits.len(),
+            "Number of proven circuits doesn't match verifier data."
+        );
+        let cap_height = self.fri_params.config.cap_height;
+        for (entry, circuit) in proof.entries.iter().zip(&self.circuits) {
+            ensure!(entry.wires_cap.height() == cap_height);
+            ensure!(entry.plonk_zs_partial_products_cap.height() == cap_height);
+            ensure!(entry.quotient_polys_cap.height() == cap_height);
+            validate_opening_set_shape(&entry.openings, &circuit.common)?;
+            ensure!(
+                entry.public_inputs.len() == circuit.common.num_public_inputs,
+                "Number of public inputs doesn't match circuit data."
+            );
+        }
+        Ok(())
+    }
+
+    /// Observes the digest and constants of each circuit, along with its public inputs.
+    fn observe_instances(
+        &self,
+        challenger: &mut Challenger<F, C::Hasher>,
+        public_inputs_hashes: &[<<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash],
+    ) {
+        for ((circuit, constants_sigmas_cap), &public_inputs_hash) in self
+            .circuits
+            .iter()
+            .zip(&self.constants_sigmas_caps)
+            .zip(public_inputs_hashes)
+        {
+            challenger.observe_hash::<C::Hasher>(circuit.verifier_only.circuit_digest);
+            challenger.observe_cap(constants_sigmas_cap);
+            challenger.observe_hash::<C::InnerHasher>(public_inputs_hash);
+        }
+    }
+
+    /// The concatenation of the FRI instances of each circuit, opened at its own `zeta`.
+    fn get_fri_instance(&self, zetas: &[F::Extension]) -> FriInstanceInfo<F, D> {
+        let mut oracles = Vec::new();
+        let mut batches = Vec::new();
+        for (i, (circuit, &zeta)) in self.circuits.iter().zip(zetas).enumerate() {
+            let instance = circuit.common.get_fri_instance(zeta);
+            debug_assert_eq!(instance.oracles.len(), NUM_ORACLES_PER_CIRCUIT);
+            oracles.extend(instance.oracles);
+            batches.extend(instance.batches.into_iter().map(|mut batch| {
+                for polynomial in &mut batch.polynomials {
+                    polynomial.oracle_index += NUM_ORACLES_PER_CIRCUIT * i;
+                }
+                batch
+            }));
+        }
+        FriInstanceInfo { oracles, batches }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_multi_circuit_proof() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        // A small circuit proving knowledge of a square root of its public input.
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let square = builder.square(x);
+        builder.register_public_input(square);
+        let square_data = builder.build::<C>();
+
+        // A larger circuit computing a power of its public input.
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let y = builder.add_virtual_target();
+        builder.register_public_input(y);
+        let mut power = y;
+        for _ in 0..2000 {
+            power = builder.mul(power, y);
+        }
+        builder.register_public_input(power);
+        let power_data = builder.build::<C>();
+
+        // A circuit with lookups.
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let table = Arc::new((0..16).map(|i| (i, 15 - i)).collect::<Vec<_>>());
+        let table_index = builder.add_lookup_table(table);
+        let z = builder.add_virtual_target();
+        let output = builder.lookup(table_index, z);
+        builder.register_public_input(output);
+        let lookup_data = builder.build::<C>();
+
+        assert!(square_data.common.degree_bits() < power_data.common.degree_bits());
+        let data = MultiCircuitData::new(vec![square_data, power_data, lookup_data]);
+        assert_eq!(
+            data.verifier_data.fri_params.degree_bits,
+            data.verifier_data.circuits[1].common.degree_bits()
+        );
+
+        let mut square_pw = PartialWitness::new();
+        square_pw.set_target(x, F::from_canonical_u64(3));
+        let mut power_pw = PartialWitness::new();
+        power_pw.set_target(y, F::TWO);
+        let mut lookup_pw = PartialWitness::new();
+        lookup_pw.set_target(z, F::from_canonical_u64(4));
+        let proof = data.prove(vec![square_pw, power_pw, lookup_pw])?;
+
+        assert_eq!(
+            proof.entries[0].public_inputs,
+            vec![F::from_canonical_u64(9)]
+        );
+        assert_eq!(
+            proof.entries[1].public_inputs,
+            vec![F::TWO, F::TWO.exp_u64(2001)]
+        );
+        assert_eq!(
+            proof.entries[2].public_inputs,
+            vec![F::from_canonical_u64(11)]
+        );
+        data.verify(proof.clone())?;
+
+        // Tampering with the public inputs of any circuit should be detected.
+        let mut bad_proof = proof;
+        bad_proof.entries[0].public_inputs[0] = F::from_canonical_u64(10);
+        assert!(data.verify(bad_proof).is_err());
+
+        Ok(())
+    }
+}
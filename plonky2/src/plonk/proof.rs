@@ -5,7 +5,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::fri::oracle::PolynomialBatch;
 use crate::fri::proof::{
-    CompressedFriProof, FriChallenges, FriChallengesTarget, FriProof, FriProofTarget,
+    CompressedFriProof, FriChallenges, FriChallengesTarget, FriInferredElements, FriProof,
+    FriProofTarget,
 };
 use crate::fri::structure::{
     FriOpeningBatch, FriOpeningBatchTarget, FriOpenings, FriOpeningsTarget,
@@ -35,7 +36,7 @@ pub struct Proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const
     pub opening_proof: FriProof<F, C::Hasher, D>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ProofTarget<const D: usize> {
     pub wires_cap: MerkleCapTarget,
     pub plonk_zs_partial_products_cap: MerkleCapTarget,
@@ -156,7 +157,11 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             plonk_zs_partial_products_cap,
             quotient_polys_cap,
             openings,
-            opening_proof: opening_proof.decompress::<C>(challenges, fri_inferred_elements, params),
+            opening_proof: opening_proof.decompress::<C>(
+                &challenges.fri_challenges,
+                fri_inferred_elements,
+                params,
+            ),
         }
     }
 }
@@ -258,6 +263,14 @@ pub(crate) struct ProofChallenges<F: RichField + Extendable<D>, const D: usize>
     /// Random values used to combine PLONK constraints.
     pub plonk_alphas: Vec<F>,
 
+    /// Random values used in the lookup argument to combine the inputs, outputs and table index
+    /// of each lookup. Empty if the circuit has no lookups.
+    pub lookup_alphas: Vec<F>,
+
+    /// Random values used in the lookup argument as the shift of each denominator. Empty if the
+    /// circuit has no lookups.
+    pub lookup_deltas: Vec<F>,
+
     /// Point at which the PLONK polynomials are opened.
     pub plonk_zeta: F::Extension,
 
@@ -268,16 +281,13 @@ pub(crate) struct ProofChallengesTarget<const D: usize> {
     pub plonk_betas: Vec<Target>,
     pub plonk_gammas: Vec<Target>,
     pub plonk_alphas: Vec<Target>,
+    pub lookup_alphas: Vec<Target>,
+    pub lookup_deltas: Vec<Target>,
     pub plonk_zeta: ExtensionTarget<D>,
     pub fri_challenges: FriChallengesTarget<D>,
 }
 
-/// Coset elements that can be inferred in the FRI reduction steps.
-pub(crate) struct FriInferredElements<F: RichField + Extendable<D>, const D: usize>(
-    pub Vec<F::Extension>,
-);
-
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ProofWithPublicInputsTarget<const D: usize> {
     pub proof: ProofTarget<D>,
     pub public_inputs: Vec<Target>,
@@ -292,6 +302,8 @@ pub struct OpeningSet<F: RichField + Extendable<D>, const D: usize> {
     pub plonk_zs: Vec<F::Extension>,
     pub plonk_zs_next: Vec<F::Extension>,
     pub partial_products: Vec<F::Extension>,
+    pub lookup_zs: Vec<F::Extension>,
+    pub lookup_zs_next: Vec<F::Extension>,
     pub quotient_polys: Vec<F::Extension>,
 }
 
@@ -305,25 +317,42 @@ impl<F: RichField + Extendable<D>, const D: usize> OpeningSet<F, D> {
         quotient_polys_commitment: &PolynomialBatch<F, C, D>,
         common_data: &CommonCircuitData<F, D>,
     ) -> Self {
-        let eval_commitment = |z: F::Extension, c: &PolynomialBatch<F, C, D>| {
-            c.polynomials
-                .par_iter()
-                .map(|p| p.to_extension().eval(z))
-                .collect::<Vec<_>>()
+        // Evaluates all the polynomials of `c` at each of `zs`, going through the polynomials once.
+        let eval_commitment = |zs: &[F::Extension], c: &PolynomialBatch<F, C, D>| {
+            let indices = (0..c.num_polynomials()).collect::<Vec<_>>();
+            let mut evals = vec![Vec::with_capacity(indices.len()); zs.len()];
+            for chunk in c.polynomial_chunks(&indices) {
+                let chunk_evals = chunk
+                    .par_iter()
+                    .map(|p| {
+                        let p = p.to_extension();
+                        zs.iter().map(|&z| p.eval(z)).collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                for poly_evals in chunk_evals {
+                    for (evals, eval) in evals.iter_mut().zip(poly_evals) {
+                        evals.push(eval);
+                    }
+                }
+            }
+            evals
         };
-        let constants_sigmas_eval = eval_commitment(zeta, constants_sigmas_commitment);
-        let zs_partial_products_eval = eval_commitment(zeta, zs_partial_products_commitment);
+        let constants_sigmas_eval = eval_commitment(&[zeta], constants_sigmas_commitment).remove(0);
+        let [zs_partial_products_eval, zs_partial_products_next_eval]: [Vec<_>; 2] =
+            eval_commitment(&[zeta, g * zeta], zs_partial_products_commitment)
+                .try_into()
+                .unwrap();
         Self {
             constants: constants_sigmas_eval[common_data.constants_range()].to_vec(),
             plonk_sigmas: constants_sigmas_eval[common_data.sigmas_range()].to_vec(),
-            wires: eval_commitment(zeta, wires_commitment),
+            wires: eval_commitment(&[zeta], wires_commitment).remove(0),
             plonk_zs: zs_partial_products_eval[common_data.zs_range()].to_vec(),
-            plonk_zs_next: eval_commitment(g * zeta, zs_partial_products_commitment)
-                [common_data.zs_range()]
-            .to_vec(),
+            plonk_zs_next: zs_partial_products_next_eval[common_data.zs_range()].to_vec(),
             partial_products: zs_partial_products_eval[common_data.partial_products_range()]
                 .to_vec(),
-            quotient_polys: eval_commitment(zeta, quotient_polys_commitment),
+            lookup_zs: zs_partial_products_eval[common_data.lookup_range()].to_vec(),
+            lookup_zs_next: zs_partial_products_next_eval[common_data.lookup_range()].to_vec(),
+            quotient_polys: eval_commitment(&[zeta], quotient_polys_commitment).remove(0),
         }
     }
 
@@ -335,12 +364,17 @@ impl<F: RichField + Extendable<D>, const D: usize> OpeningSet<F, D> {
                 self.wires.as_slice(),
                 self.plonk_zs.as_slice(),
                 self.partial_products.as_slice(),
+                self.lookup_zs.as_slice(),
                 self.quotient_polys.as_slice(),
             ]
             .concat(),
         };
         let zeta_next_batch = FriOpeningBatch {
-            values: self.plonk_zs_next.clone(),
+            values: [
+                self.plonk_zs_next.as_slice(),
+                self.lookup_zs_next.as_slice(),
+            ]
+            .concat(),
         };
         FriOpenings {
             batches: vec![zeta_batch, zeta_next_batch],
@@ -357,6 +391,8 @@ pub struct OpeningSetTarget<const D: usize> {
     pub plonk_zs: Vec<ExtensionTarget<D>>,
     pub plonk_zs_next: Vec<ExtensionTarget<D>>,
     pub partial_products: Vec<ExtensionTarget<D>>,
+    pub lookup_zs: Vec<ExtensionTarget<D>>,
+    pub lookup_zs_next: Vec<ExtensionTarget<D>>,
     pub quotient_polys: Vec<ExtensionTarget<D>>,
 }
 
@@ -369,12 +405,17 @@ impl<const D: usize> OpeningSetTarget<D> {
                 self.wires.as_slice(),
                 self.plonk_zs.as_slice(),
                 self.partial_products.as_slice(),
+                self.lookup_zs.as_slice(),
                 self.quotient_polys.as_slice(),
             ]
             .concat(),
         };
         let zeta_next_batch = FriOpeningBatchTarget {
-            values: self.plonk_zs_next.clone(),
+            values: [
+                self.plonk_zs_next.as_slice(),
+                self.lookup_zs_next.as_slice(),
+            ]
+            .concat(),
         };
         FriOpeningsTarget {
             batches: vec![zeta_batch, zeta_next_batch],
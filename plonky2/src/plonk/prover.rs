@@ -1,7 +1,6 @@
-use std::mem::swap;
+use std::mem::{swap, take};
 
-use anyhow::ensure;
-use anyhow::Result;
+use anyhow::{anyhow, ensure, Result};
 use maybe_rayon::*;
 use plonky2_field::extension::Extendable;
 use plonky2_field::polynomial::{PolynomialCoeffs, PolynomialValues};
@@ -10,9 +9,11 @@ use plonky2_util::{ceil_div_usize, log2_ceil};
 
 use crate::field::types::Field;
 use crate::fri::oracle::PolynomialBatch;
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
 use crate::hash::hash_types::RichField;
 use crate::iop::challenger::Challenger;
-use crate::iop::generator::generate_partial_witness;
+use crate::iop::generator::try_generate_partial_witness_with_buffer;
 use crate::iop::witness::{MatrixWitness, PartialWitness, Witness};
 use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, Hasher};
@@ -22,10 +23,102 @@ use crate::plonk::proof::{Proof, ProofWithPublicInputs};
 use crate::plonk::vanishing_poly::eval_vanishing_poly_base_batch;
 use crate::plonk::vars::EvaluationVarsBaseBatch;
 use crate::timed;
+use crate::util::lookup::lookup_helpers;
 use crate::util::partial_products::{partial_products_and_z_gx, quotient_chunk_products};
 use crate::util::timing::TimingTree;
 use crate::util::transpose;
 
+/// Options of the prover, which trade proving time for memory.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProverOptions {
+    /// If set, the prover runs in low-memory mode. LDEs are then computed in batches of columns
+    /// using about this many bytes, rather than all at once, and the coefficients of committed
+    /// polynomials are dropped and recomputed from the Merkle leaves when needed, in chunks using
+    /// about this many bytes.
+    ///
+    /// The budget only bounds these temporary LDEs and coefficients. The Merkle trees of the
+    /// commitments, which hold the full LDEs, the witness and the quotient polynomials are still
+    /// kept in memory in full; there is no option to keep Merkle leaves on disk.
+    pub memory_budget: Option<usize>,
+}
+
+impl ProverOptions {
+    /// Low-memory mode, with the given budget in bytes for the LDEs computed at once.
+    pub fn low_memory(memory_budget: usize) -> Self {
+        Self {
+            memory_budget: Some(memory_budget),
+        }
+    }
+
+    fn commit_values<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        values: Vec<PolynomialValues<F>>,
+        blinding: bool,
+        common_data: &CommonCircuitData<F, D>,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        timing: &mut TimingTree,
+    ) -> PolynomialBatch<F, C, D>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let fri_config = &common_data.config.fri_config;
+        let fft_root_table = prover_data.fft_root_table.as_ref();
+        match self.memory_budget {
+            Some(memory_budget) => PolynomialBatch::from_values_low_memory(
+                values,
+                fri_config.rate_bits,
+                blinding,
+                fri_config.cap_height,
+                memory_budget,
+                timing,
+                fft_root_table,
+            ),
+            None => PolynomialBatch::from_values(
+                values,
+                fri_config.rate_bits,
+                blinding,
+                fri_config.cap_height,
+                timing,
+                fft_root_table,
+            ),
+        }
+    }
+
+    fn commit_coeffs<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        coeffs: Vec<PolynomialCoeffs<F>>,
+        blinding: bool,
+        common_data: &CommonCircuitData<F, D>,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        timing: &mut TimingTree,
+    ) -> PolynomialBatch<F, C, D>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let fri_config = &common_data.config.fri_config;
+        let fft_root_table = prover_data.fft_root_table.as_ref();
+        match self.memory_budget {
+            Some(memory_budget) => PolynomialBatch::from_coeffs_low_memory(
+                coeffs,
+                fri_config.rate_bits,
+                blinding,
+                fri_config.cap_height,
+                memory_budget,
+                timing,
+                fft_root_table,
+            ),
+            None => PolynomialBatch::from_coeffs(
+                coeffs,
+                fri_config.rate_bits,
+                blinding,
+                fri_config.cap_height,
+                timing,
+                fft_root_table,
+            ),
+        }
+    }
+}
+
 pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     prover_data: &ProverOnlyCircuitData<F, C, D>,
     common_data: &CommonCircuitData<F, D>,
@@ -35,25 +128,193 @@ pub fn prove<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D:
 where
     [(); C::Hasher::HASH_SIZE]:,
 {
-    let config = &common_data.config;
-    let num_challenges = config.num_challenges;
-    let quotient_degree = common_data.quotient_degree();
-    let degree = common_data.degree();
+    prove_with_options(
+        prover_data,
+        common_data,
+        inputs,
+        &ProverOptions::default(),
+        timing,
+    )
+}
+
+/// Values used by the prover which only depend on the circuit, so that they can be computed once
+/// and shared by several proofs of the same circuit.
+pub(crate) struct ProverPrecomputation<F: Field> {
+    /// The subgroup whose coset is the domain on which the quotient polynomials are evaluated.
+    quotient_points: Vec<F>,
+    /// The evaluations of `Z_H` on that coset.
+    z_h_on_coset: ZeroPolyOnCoset<F>,
+    /// The values of the lookup selectors and of the table index constant, if the circuit has
+    /// lookups.
+    lookup_constants: Option<LookupConstants<F>>,
+}
+
+struct LookupConstants<F: Field> {
+    s_lookup: Vec<F>,
+    s_lookup_table: Vec<F>,
+    table_index: Vec<F>,
+}
+
+impl<F: RichField> ProverPrecomputation<F> {
+    pub(crate) fn new<C: GenericConfig<D, F = F>, const D: usize>(
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Self
+    where
+        F: Extendable<D>,
+    {
+        let quotient_degree_bits = log2_ceil(common_data.quotient_degree_factor);
+        let quotient_points =
+            F::two_adic_subgroup(common_data.degree_bits() + quotient_degree_bits);
+        let z_h_on_coset = ZeroPolyOnCoset::new(common_data.degree_bits(), quotient_degree_bits);
+
+        let lookup_constants = (common_data.num_lookup_polys() > 0).then(|| {
+            // Recover the values of the lookup selectors and of the table index constant.
+            let constant_values = |index: usize| {
+                prover_data
+                    .constants_sigmas_commitment
+                    .polynomial(index)
+                    .into_owned()
+                    .fft()
+                    .values
+            };
+            let lookup_selectors_start = common_data.lookup_selectors_range().start;
+            LookupConstants {
+                s_lookup: constant_values(lookup_selectors_start),
+                s_lookup_table: constant_values(lookup_selectors_start + 1),
+                // The table index is the first constant of both lookup gates.
+                table_index: constant_values(common_data.selectors_info.num_selectors()),
+            }
+        });
 
+        Self {
+            quotient_points,
+            z_h_on_coset,
+            lookup_constants,
+        }
+    }
+}
+
+pub fn prove_with_options<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    options: &ProverOptions,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let precomputation = timed!(
+        timing,
+        "precompute circuit-dependent values",
+        ProverPrecomputation::new(prover_data, common_data)
+    );
+    prove_with_precomputation(
+        prover_data,
+        common_data,
+        &precomputation,
+        inputs,
+        options,
+        None,
+        timing,
+    )
+}
+
+/// Buffers which a prover proving several witnesses in a row reuses from one proof to the next,
+/// rather than reallocating them. They are kept in memory for the whole duration of each proof.
+///
+/// Only the buffers of witness generation are reused. The wire matrix, the LDEs and Merkle trees of
+/// the commitments and the quotient polynomials are still allocated for each proof, and make up
+/// most of its memory, which is why `BatchProver` bounds the number of proofs in progress instead.
+#[derive(Default)]
+pub(crate) struct ProverScratch<F: Field> {
+    /// The values of the partition witness filled by the generators.
+    partition_values: Vec<Option<F>>,
+}
+
+/// Proves a witness, given the values precomputed for the circuit. If `scratch` is given, its
+/// buffers are reused for this proof, and are left in it for the next one.
+pub(crate) fn prove_with_precomputation<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    precomputation: &ProverPrecomputation<F>,
+    inputs: PartialWitness<F>,
+    options: &ProverOptions,
+    mut scratch: Option<&mut ProverScratch<F>>,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let partition_values = scratch
+        .as_mut()
+        .map(|scratch| take(&mut scratch.partition_values))
+        .unwrap_or_default();
     let partition_witness = timed!(
         timing,
         &format!("run {} generators", prover_data.generators.len()),
-        generate_partial_witness(inputs, prover_data, common_data)
+        try_generate_partial_witness_with_buffer(
+            inputs,
+            prover_data,
+            common_data,
+            partition_values
+        )
+        .map_err(|conflict| anyhow!("{conflict}"))?
     );
 
     let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
-    let public_inputs_hash = C::InnerHasher::hash_no_pad(&public_inputs);
 
-    let witness = timed!(
+    let (witness, partition_values) = timed!(
         timing,
         "compute full witness",
-        partition_witness.full_witness()
+        partition_witness.full_witness_and_buffer()
     );
+    match scratch {
+        Some(scratch) => scratch.partition_values = partition_values,
+        None => drop(partition_values),
+    }
+
+    prove_with_witness(
+        prover_data,
+        common_data,
+        precomputation,
+        public_inputs,
+        witness,
+        options,
+        timing,
+    )
+}
+
+/// Proves a full witness, without running any generator. The witness is not checked, so a witness
+/// which doesn't satisfy the circuit yields an invalid proof, or a panic.
+pub(crate) fn prove_with_witness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    precomputation: &ProverPrecomputation<F>,
+    public_inputs: Vec<F>,
+    witness: MatrixWitness<F>,
+    options: &ProverOptions,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let config = &common_data.config;
+    let num_challenges = config.num_challenges;
+    let public_inputs_hash = C::InnerHasher::hash_no_pad(&public_inputs);
 
     let wires_values: Vec<PolynomialValues<F>> = timed!(
         timing,
@@ -68,13 +329,12 @@ where
     let wires_commitment = timed!(
         timing,
         "compute wires commitment",
-        PolynomialBatch::from_values(
+        options.commit_values(
             wires_values,
-            config.fri_config.rate_bits,
             config.zero_knowledge && PlonkOracle::WIRES.blinding,
-            config.fri_config.cap_height,
+            common_data,
+            prover_data,
             timing,
-            prover_data.fft_root_table.as_ref(),
         )
     );
 
@@ -87,34 +347,39 @@ where
     challenger.observe_cap(&wires_commitment.merkle_tree.cap);
     let betas = challenger.get_n_challenges(num_challenges);
     let gammas = challenger.get_n_challenges(num_challenges);
+    let (lookup_alphas, lookup_deltas) = if common_data.num_lookup_polys() > 0 {
+        (
+            challenger.get_n_challenges(num_challenges),
+            challenger.get_n_challenges(num_challenges),
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
 
-    assert!(
-        common_data.quotient_degree_factor < common_data.config.num_routed_wires,
-        "When the number of routed wires is smaller that the degree, we should change the logic to avoid computing partial products."
-    );
-    let mut partial_products_and_zs = timed!(
+    let zs_partial_products = compute_zs_partial_products(
+        &witness,
+        &betas,
+        &gammas,
+        &lookup_alphas,
+        &lookup_deltas,
+        prover_data,
+        common_data,
+        precomputation,
         timing,
-        "compute partial products",
-        all_wires_permutation_partial_products(&witness, &betas, &gammas, prover_data, common_data)
     );
 
-    // Z is expected at the front of our batch; see `zs_range` and `partial_products_range`.
-    let plonk_z_vecs = partial_products_and_zs
-        .iter_mut()
-        .map(|partial_products_and_z| partial_products_and_z.pop().unwrap())
-        .collect();
-    let zs_partial_products = [plonk_z_vecs, partial_products_and_zs.concat()].concat();
+    // The witness is no longer needed, as the wires have been committed to.
+    drop(witness);
 
     let partial_products_and_zs_commitment = timed!(
         timing,
         "commit to partial products and Z's",
-        PolynomialBatch::from_values(
+        options.commit_values(
             zs_partial_products,
-            config.fri_config.rate_bits,
             config.zero_knowledge && PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
-            config.fri_config.cap_height,
+            common_data,
+            prover_data,
             timing,
-            prover_data.fft_root_table.as_ref(),
         )
     );
 
@@ -127,12 +392,15 @@ where
         "compute quotient polys",
         compute_quotient_polys(
             common_data,
-            prover_data,
+            precomputation,
+            &prover_data.constants_sigmas_commitment,
             &public_inputs_hash,
             &wires_commitment,
             &partial_products_and_zs_commitment,
             &betas,
             &gammas,
+            &lookup_alphas,
+            &lookup_deltas,
             &alphas,
         )
     );
@@ -141,28 +409,18 @@ where
     let all_quotient_poly_chunks = timed!(
         timing,
         "split up quotient polys",
-        quotient_polys
-            .into_par_iter()
-            .flat_map(|mut quotient_poly| {
-                quotient_poly.trim_to_len(quotient_degree).expect(
-                    "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
-                );
-                // Split quotient into degree-n chunks.
-                quotient_poly.chunks(degree)
-            })
-            .collect()
+        split_quotient_polys(quotient_polys, common_data)
     );
 
     let quotient_polys_commitment = timed!(
         timing,
         "commit to quotient polys",
-        PolynomialBatch::from_coeffs(
+        options.commit_coeffs(
             all_quotient_poly_chunks,
-            config.fri_config.rate_bits,
             config.zero_knowledge && PlonkOracle::QUOTIENT.blinding,
-            config.fri_config.cap_height,
+            common_data,
+            prover_data,
             timing,
-            prover_data.fft_root_table.as_ref(),
         )
     );
 
@@ -223,6 +481,72 @@ where
     })
 }
 
+/// Compute the polynomials committed to after the permutation and lookup challenges are sampled:
+/// the permutation `Z`s, followed by the permutation partial products and the lookup polynomials.
+pub(crate) fn compute_zs_partial_products<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    witness: &MatrixWitness<F>,
+    betas: &[F],
+    gammas: &[F],
+    lookup_alphas: &[F],
+    lookup_deltas: &[F],
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    precomputation: &ProverPrecomputation<F>,
+    timing: &mut TimingTree,
+) -> Vec<PolynomialValues<F>> {
+    assert!(
+        common_data.quotient_degree_factor < common_data.config.num_routed_wires,
+        "When the number of routed wires is smaller that the degree, we should change the logic to avoid computing partial products."
+    );
+    let mut partial_products_and_zs = timed!(
+        timing,
+        "compute partial products",
+        all_wires_permutation_partial_products(witness, betas, gammas, prover_data, common_data)
+    );
+
+    // Z is expected at the front of our batch; see `zs_range` and `partial_products_range`.
+    let plonk_z_vecs = partial_products_and_zs
+        .iter_mut()
+        .map(|partial_products_and_z| partial_products_and_z.pop().unwrap())
+        .collect();
+    let lookup_polys = timed!(
+        timing,
+        "compute lookup polys",
+        all_lookup_polys(
+            witness,
+            lookup_alphas,
+            lookup_deltas,
+            precomputation,
+            common_data
+        )
+    );
+    // Lookup polynomials come last; see `lookup_range`.
+    [plonk_z_vecs, partial_products_and_zs.concat(), lookup_polys].concat()
+}
+
+/// Splits each quotient polynomial into chunks of degree `n`, which are committed to separately.
+pub(crate) fn split_quotient_polys<F: RichField + Extendable<D>, const D: usize>(
+    quotient_polys: Vec<PolynomialCoeffs<F>>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<PolynomialCoeffs<F>> {
+    let quotient_degree = common_data.quotient_degree();
+    let degree = common_data.degree();
+    quotient_polys
+        .into_par_iter()
+        .flat_map(|mut quotient_poly| {
+            quotient_poly
+                .trim_to_len(quotient_degree)
+                .expect("Quotient has failed, the vanishing polynomial is not divisible by Z_H");
+            // Split quotient into degree-n chunks.
+            quotient_poly.chunks(degree)
+        })
+        .collect()
+}
+
 /// Compute the partial products used in the `Z` polynomials.
 fn all_wires_permutation_partial_products<
     F: RichField + Extendable<D>,
@@ -310,42 +634,139 @@ fn wires_permutation_partial_products_and_zs<
         .collect()
 }
 
+/// Compute the lookup polynomials of each challenge, i.e. the running sum `Z` followed by the
+/// helper polynomials. Returns an empty vector if the circuit has no lookups.
+fn all_lookup_polys<F: RichField + Extendable<D>, const D: usize>(
+    witness: &MatrixWitness<F>,
+    alphas: &[F],
+    deltas: &[F],
+    precomputation: &ProverPrecomputation<F>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<PolynomialValues<F>> {
+    let LookupConstants {
+        s_lookup,
+        s_lookup_table,
+        table_index,
+    } = match &precomputation.lookup_constants {
+        Some(lookup_constants) => lookup_constants,
+        None => return Vec::new(),
+    };
+
+    (0..common_data.config.num_challenges)
+        .flat_map(|i| {
+            lookup_polys(
+                witness,
+                alphas[i],
+                deltas[i],
+                s_lookup,
+                s_lookup_table,
+                table_index,
+                common_data,
+            )
+        })
+        .collect()
+}
+
+/// Compute the running sum `Z` and the helper polynomials of the lookup argument.
+fn lookup_polys<F: RichField + Extendable<D>, const D: usize>(
+    witness: &MatrixWitness<F>,
+    alpha: F,
+    delta: F,
+    s_lookup: &[F],
+    s_lookup_table: &[F],
+    table_index: &[F],
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<PolynomialValues<F>> {
+    let num_slots = common_data.num_lookup_slots;
+    let num_helpers = common_data.num_lookup_polys() - 1;
+    let table_gate = LookupTableGate { num_slots };
+    let all_helpers = (0..common_data.degree())
+        .into_par_iter()
+        .map(|i| {
+            if s_lookup[i].is_zero() && s_lookup_table[i].is_zero() {
+                return vec![F::ZERO; num_helpers];
+            }
+            let numerators = (0..num_slots)
+                .map(|k| {
+                    let multiplicity = witness.get_wire(i, table_gate.wire_ith_multiplicity(k));
+                    s_lookup[i] - s_lookup_table[i] * multiplicity
+                })
+                .collect::<Vec<_>>();
+            let denominators = (0..num_slots)
+                .map(|k| {
+                    let input = witness.get_wire(i, LookupGate::wire_ith_looking_inp(k));
+                    let output = witness.get_wire(i, LookupGate::wire_ith_looking_out(k));
+                    delta - (input + alpha * (output + alpha * table_index[i]))
+                })
+                .collect::<Vec<_>>();
+            lookup_helpers(
+                &numerators,
+                &denominators,
+                common_data.num_lookup_slots_per_helper(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut z_x = F::ZERO;
+    let mut all_zs_and_helpers = Vec::with_capacity(common_data.degree());
+    for helpers in all_helpers {
+        let z_gx = z_x + helpers.iter().copied().sum();
+        all_zs_and_helpers.push([vec![z_x], helpers].concat());
+        z_x = z_gx;
+    }
+    debug_assert!(
+        z_x.is_zero(),
+        "Lookup argument's running sum doesn't wrap around to 0."
+    );
+
+    transpose(&all_zs_and_helpers)
+        .into_par_iter()
+        .map(PolynomialValues::new)
+        .collect()
+}
+
 const BATCH_SIZE: usize = 32;
 
-fn compute_quotient_polys<
+/// Computes the quotient polynomials from the LDEs of the committed polynomials, which may use a
+/// rate larger than `config.fri_config.rate_bits` when circuits share an LDE domain.
+pub(crate) fn compute_quotient_polys<
     'a,
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 >(
     common_data: &CommonCircuitData<F, D>,
-    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    precomputation: &ProverPrecomputation<F>,
+    constants_sigmas_commitment: &'a PolynomialBatch<F, C, D>,
     public_inputs_hash: &<<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash,
     wires_commitment: &'a PolynomialBatch<F, C, D>,
     zs_partial_products_commitment: &'a PolynomialBatch<F, C, D>,
     betas: &[F],
     gammas: &[F],
+    lookup_alphas: &[F],
+    lookup_deltas: &[F],
     alphas: &[F],
 ) -> Vec<PolynomialCoeffs<F>> {
     let num_challenges = common_data.config.num_challenges;
     let quotient_degree_bits = log2_ceil(common_data.quotient_degree_factor);
+    let rate_bits = wires_commitment.rate_bits;
     assert!(
-        quotient_degree_bits <= common_data.config.fri_config.rate_bits,
+        quotient_degree_bits <= rate_bits,
         "Having constraints of degree higher than the rate is not supported yet. \
         If we need this in the future, we can precompute the larger LDE before computing the `PolynomialBatch`s."
     );
 
     // We reuse the LDE computed in `PolynomialBatch` and extract every `step` points to get
     // an LDE matching `max_filtered_constraint_degree`.
-    let step = 1 << (common_data.config.fri_config.rate_bits - quotient_degree_bits);
+    let step = 1 << (rate_bits - quotient_degree_bits);
     // When opening the `Z`s polys at the "next" point in Plonk, need to look at the point `next_step`
     // steps away since we work on an LDE of degree `max_filtered_constraint_degree`.
     let next_step = 1 << quotient_degree_bits;
 
-    let points = F::two_adic_subgroup(common_data.degree_bits() + quotient_degree_bits);
+    let points = &precomputation.quotient_points;
     let lde_size = points.len();
 
-    let z_h_on_coset = ZeroPolyOnCoset::new(common_data.degree_bits(), quotient_degree_bits);
+    let z_h_on_coset = &precomputation.z_h_on_coset;
 
     let points_batches = points.par_chunks(BATCH_SIZE);
     let num_batches = ceil_div_usize(points.len(), BATCH_SIZE);
@@ -366,6 +787,8 @@ fn compute_quotient_polys<
             let mut next_zs_batch = Vec::with_capacity(xs_batch.len());
             let mut partial_products_batch = Vec::with_capacity(xs_batch.len());
             let mut s_sigmas_batch = Vec::with_capacity(xs_batch.len());
+            let mut lookup_zs_batch = Vec::with_capacity(xs_batch.len());
+            let mut next_lookup_zs_batch = Vec::with_capacity(xs_batch.len());
 
             let mut local_constants_batch_refs = Vec::with_capacity(xs_batch.len());
             let mut local_wires_batch_refs = Vec::with_capacity(xs_batch.len());
@@ -373,19 +796,20 @@ fn compute_quotient_polys<
             for (&i, &x) in indices_batch.iter().zip(xs_batch) {
                 let shifted_x = F::coset_shift() * x;
                 let i_next = (i + next_step) % lde_size;
-                let local_constants_sigmas = prover_data
-                    .constants_sigmas_commitment
-                    .get_lde_values(i, step);
+                let local_constants_sigmas = constants_sigmas_commitment.get_lde_values(i, step);
                 let local_constants = &local_constants_sigmas[common_data.constants_range()];
                 let s_sigmas = &local_constants_sigmas[common_data.sigmas_range()];
                 let local_wires = wires_commitment.get_lde_values(i, step);
                 let local_zs_partial_products =
                     zs_partial_products_commitment.get_lde_values(i, step);
+                let next_zs_partial_products =
+                    zs_partial_products_commitment.get_lde_values(i_next, step);
                 let local_zs = &local_zs_partial_products[common_data.zs_range()];
-                let next_zs = &zs_partial_products_commitment.get_lde_values(i_next, step)
-                    [common_data.zs_range()];
+                let next_zs = &next_zs_partial_products[common_data.zs_range()];
                 let partial_products =
                     &local_zs_partial_products[common_data.partial_products_range()];
+                let lookup_zs = &local_zs_partial_products[common_data.lookup_range()];
+                let next_lookup_zs = &next_zs_partial_products[common_data.lookup_range()];
 
                 debug_assert_eq!(local_wires.len(), common_data.config.num_wires);
                 debug_assert_eq!(local_zs.len(), num_challenges);
@@ -398,6 +822,8 @@ fn compute_quotient_polys<
                 next_zs_batch.push(next_zs);
                 partial_products_batch.push(partial_products);
                 s_sigmas_batch.push(s_sigmas);
+                lookup_zs_batch.push(lookup_zs);
+                next_lookup_zs_batch.push(next_lookup_zs);
             }
 
             // NB (JN): I'm not sure how (in)efficient the below is. It needs measuring.
@@ -433,10 +859,14 @@ fn compute_quotient_polys<
                 &next_zs_batch,
                 &partial_products_batch,
                 &s_sigmas_batch,
+                &lookup_zs_batch,
+                &next_lookup_zs_batch,
                 betas,
                 gammas,
+                lookup_alphas,
+                lookup_deltas,
                 alphas,
-                &z_h_on_coset,
+                z_h_on_coset,
             );
 
             for (&i, quotient_values) in indices_batch.iter().zip(quotient_values_batch.iter_mut())
@@ -457,3 +887,53 @@ fn compute_quotient_polys<
         .map(|values| values.coset_ifft(F::coset_shift()))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn test_low_memory_prover_with_config(config: CircuitConfig) -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let mut y = x;
+        for _ in 0..1000 {
+            y = builder.mul_add(y, y, x);
+        }
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        // A budget of a single byte commits to one polynomial at a time.
+        let proof = data.prove_with_options(pw.clone(), &ProverOptions::low_memory(1))?;
+        // A budget of 1 MiB recovers several polynomials at once.
+        let chunked_proof =
+            data.prove_with_options(pw.clone(), &ProverOptions::low_memory(1 << 20))?;
+        if !data.common.config.zero_knowledge {
+            // Without zero knowledge, proving is deterministic.
+            assert_eq!(proof, data.prove(pw)?);
+            assert_eq!(chunked_proof, proof);
+        }
+        data.verify(chunked_proof)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_low_memory_prover() -> Result<()> {
+        test_low_memory_prover_with_config(CircuitConfig::standard_recursion_config())
+    }
+
+    #[test]
+    fn test_low_memory_prover_zk() -> Result<()> {
+        test_low_memory_prover_with_config(CircuitConfig::standard_recursion_zk_config())
+    }
+}
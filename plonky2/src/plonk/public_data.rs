@@ -0,0 +1,217 @@
+//! Public data, exposed only through a commitment in the public inputs.
+//!
+//! Circuits with many public values can register them as public data rather than as public
+//! inputs. Only a hash of the public data is then appended to the public inputs, so that the size
+//! of proofs and the cost of verifying them recursively don't depend on the amount of public data.
+//! A verifier holding the raw data checks it against the commitment with
+//! `ProofWithPublicInputs::check_public_data`.
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2_field::extension::Extendable;
+
+use crate::hash::hash_types::RichField;
+use crate::hash::hashing::hash_n_to_hash_no_pad;
+use crate::hash::keccak::KeccakHash;
+use crate::hash::poseidon::{PoseidonHash, PoseidonPermutation};
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::proof::ProofWithPublicInputs;
+
+/// The hash used to commit to the public data of a circuit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PublicDataCommitment {
+    /// A Poseidon hash of the data, as 4 field elements.
+    Poseidon,
+    /// A Keccak-256 hash of the canonical little-endian encoding of the data, as 8 little-endian
+    /// 32-bit words. It is much more expensive to compute in the circuit than a Poseidon hash, but
+    /// cheap to compute in the EVM.
+    Keccak,
+}
+
+impl PublicDataCommitment {
+    /// The number of public inputs holding the commitment.
+    pub fn num_public_inputs(&self) -> usize {
+        match self {
+            Self::Poseidon => 4,
+            Self::Keccak => 8,
+        }
+    }
+
+    /// Computes the commitment to `data`, as it appears in the public inputs.
+    pub fn commit<F: RichField>(&self, data: &[F]) -> Vec<F> {
+        match self {
+            Self::Poseidon => hash_n_to_hash_no_pad::<F, PoseidonPermutation>(data)
+                .elements
+                .to_vec(),
+            Self::Keccak => KeccakHash::<32>::hash_no_pad(data)
+                .0
+                .chunks(4)
+                .map(|word| F::from_canonical_u32(u32::from_le_bytes(word.try_into().unwrap())))
+                .collect(),
+        }
+    }
+
+    fn commit_circuit<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        data: Vec<Target>,
+    ) -> Vec<Target> {
+        match self {
+            Self::Poseidon => builder
+                .hash_n_to_hash_no_pad::<PoseidonHash>(data)
+                .elements
+                .to_vec(),
+            Self::Keccak => builder.keccak256_field_elements(&data).to_vec(),
+        }
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Registers the given targets as public data. Only a commitment to all public data is exposed,
+    /// after the other public inputs.
+    pub fn register_public_data(&mut self, targets: &[Target]) {
+        self.public_data.extend_from_slice(targets);
+    }
+
+    /// Sets the hash used to commit to the public data. Defaults to Poseidon.
+    pub fn set_public_data_commitment(&mut self, commitment: PublicDataCommitment) {
+        self.public_data_commitment = commitment;
+    }
+
+    /// Registers the commitment to the public data, if any, as public inputs.
+    pub(crate) fn register_public_data_commitment(&mut self) -> Option<PublicDataCommitment> {
+        if self.public_data.is_empty() {
+            return None;
+        }
+        let commitment = self.public_data_commitment;
+        let data = std::mem::take(&mut self.public_data);
+        let hash = commitment.commit_circuit(self, data);
+        self.register_public_inputs(&hash);
+        Some(commitment)
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProofWithPublicInputs<F, C, D>
+{
+    /// The public inputs other than the commitment to the public data. Fails if there are fewer
+    /// public inputs than the commitment takes.
+    pub fn public_inputs_without_data<'a>(
+        &'a self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<&'a [F]> {
+        let commitment_len = common_data
+            .public_data_commitment
+            .map_or(0, |c| c.num_public_inputs());
+        let len = self
+            .public_inputs
+            .len()
+            .checked_sub(commitment_len)
+            .ok_or_else(|| anyhow!("Too few public inputs for the public data commitment."))?;
+        Ok(&self.public_inputs[..len])
+    }
+
+    /// Checks that `data` is the public data committed to in the public inputs. This doesn't verify
+    /// the proof itself.
+    pub fn check_public_data(
+        &self,
+        common_data: &CommonCircuitData<F, D>,
+        data: &[F],
+    ) -> Result<()> {
+        let commitment = match common_data.public_data_commitment {
+            Some(commitment) => commitment,
+            None => {
+                ensure!(data.is_empty(), "The circuit has no public data.");
+                return Ok(());
+            }
+        };
+        let start = self.public_inputs_without_data(common_data)?.len();
+        ensure!(
+            self.public_inputs[start..] == commitment.commit(data),
+            "Public data doesn't match its commitment."
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use super::*;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_data::{CircuitConfig, VerifierCircuitTarget};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn test_public_data(commitment: PublicDataCommitment) -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        builder.set_public_data_commitment(commitment);
+        let x = builder.add_virtual_target();
+        builder.register_public_input(x);
+        let data_targets = builder.add_virtual_targets(10);
+        builder.register_public_data(&data_targets);
+        let data = builder.build::<C>();
+
+        let x_value = F::rand();
+        let public_data = F::rand_vec(10);
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, x_value);
+        for (&t, &v) in data_targets.iter().zip(&public_data) {
+            pw.set_target(t, v);
+        }
+        let proof = data.prove(pw)?;
+        assert_eq!(
+            proof.public_inputs.len(),
+            1 + commitment.num_public_inputs()
+        );
+        assert_eq!(proof.public_inputs_without_data(&data.common)?, [x_value]);
+        proof.check_public_data(&data.common, &public_data)?;
+        let mut bad_data = public_data.clone();
+        bad_data[7] += F::ONE;
+        assert!(proof.check_public_data(&data.common, &bad_data).is_err());
+
+        // A proof with fewer public inputs than the commitment is rejected rather than sliced.
+        let mut truncated_proof = proof.clone();
+        truncated_proof.public_inputs.truncate(1);
+        assert!(truncated_proof
+            .public_inputs_without_data(&data.common)
+            .is_err());
+        assert!(truncated_proof
+            .check_public_data(&data.common, &public_data)
+            .is_err());
+
+        // The recursive verifier only sees the commitment.
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let proof_target = builder.add_virtual_proof_with_pis::<C>(&data.common);
+        let verifier_target = VerifierCircuitTarget {
+            constants_sigmas_cap: builder
+                .constant_merkle_cap(&data.verifier_only.constants_sigmas_cap),
+            circuit_digest: builder.constant_hash(data.verifier_only.circuit_digest),
+        };
+        builder.verify_proof::<C>(proof_target.clone(), &verifier_target, &data.common);
+        let recursive_data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&proof_target, &proof);
+        let recursive_proof = recursive_data.prove(pw)?;
+        data.verify(proof)?;
+        recursive_data.verify(recursive_proof)
+    }
+
+    #[test]
+    fn test_public_data_poseidon() -> Result<()> {
+        test_public_data(PublicDataCommitment::Poseidon)
+    }
+
+    #[test]
+    fn test_public_data_keccak() -> Result<()> {
+        test_public_data(PublicDataCommitment::Keccak)
+    }
+}
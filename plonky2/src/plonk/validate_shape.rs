@@ -39,7 +39,6 @@ where
     C: GenericConfig<D, F = F>,
     [(); C::Hasher::HASH_SIZE]:,
 {
-    let config = &common_data.config;
     let Proof {
         wires_cap,
         plonk_zs_partial_products_cap,
@@ -50,6 +49,19 @@ where
         opening_proof: _,
     } = proof;
 
+    let cap_height = common_data.fri_params.config.cap_height;
+    ensure!(wires_cap.height() == cap_height);
+    ensure!(plonk_zs_partial_products_cap.height() == cap_height);
+    ensure!(quotient_polys_cap.height() == cap_height);
+
+    validate_opening_set_shape(openings, common_data)
+}
+
+pub(crate) fn validate_opening_set_shape<F: RichField + Extendable<D>, const D: usize>(
+    openings: &OpeningSet<F, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> anyhow::Result<()> {
+    let config = &common_data.config;
     let OpeningSet {
         constants,
         plonk_sigmas,
@@ -57,20 +69,19 @@ where
         plonk_zs,
         plonk_zs_next,
         partial_products,
+        lookup_zs,
+        lookup_zs_next,
         quotient_polys,
     } = openings;
 
-    let cap_height = common_data.fri_params.config.cap_height;
-    ensure!(wires_cap.height() == cap_height);
-    ensure!(plonk_zs_partial_products_cap.height() == cap_height);
-    ensure!(quotient_polys_cap.height() == cap_height);
-
     ensure!(constants.len() == common_data.num_constants);
     ensure!(plonk_sigmas.len() == config.num_routed_wires);
     ensure!(wires.len() == config.num_wires);
     ensure!(plonk_zs.len() == config.num_challenges);
     ensure!(plonk_zs_next.len() == config.num_challenges);
     ensure!(partial_products.len() == config.num_challenges * common_data.num_partial_products);
+    ensure!(lookup_zs.len() == config.num_challenges * common_data.num_lookup_polys());
+    ensure!(lookup_zs_next.len() == config.num_challenges * common_data.num_lookup_polys());
     ensure!(quotient_polys.len() == common_data.num_quotient_polys());
 
     Ok(())
@@ -3,6 +3,8 @@ use plonky2_field::extension::{Extendable, FieldExtension};
 use plonky2_field::types::Field;
 use plonky2_field::zero_poly_coset::ZeroPolyOnCoset;
 
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
@@ -12,6 +14,7 @@ use crate::plonk::config::GenericConfig;
 use crate::plonk::plonk_common;
 use crate::plonk::plonk_common::eval_l_0_circuit;
 use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
+use crate::util::lookup::{check_lookup_constraints, check_lookup_constraints_circuit};
 use crate::util::partial_products::{check_partial_products, check_partial_products_circuit};
 use crate::util::reducing::ReducingFactorTarget;
 use crate::util::strided_view::PackedStridedView;
@@ -32,12 +35,20 @@ pub(crate) fn eval_vanishing_poly<
     next_zs: &[F::Extension],
     partial_products: &[F::Extension],
     s_sigmas: &[F::Extension],
+    lookup_zs: &[F::Extension],
+    next_lookup_zs: &[F::Extension],
     betas: &[F],
     gammas: &[F],
+    lookup_alphas: &[F],
+    lookup_deltas: &[F],
     alphas: &[F],
 ) -> Vec<F::Extension> {
     let max_degree = common_data.quotient_degree_factor;
     let num_prods = common_data.num_partial_products;
+    let num_lookup_polys = common_data.num_lookup_polys();
+    let table_gate = LookupTableGate {
+        num_slots: common_data.num_lookup_slots,
+    };
 
     let constraint_terms = evaluate_gate_constraints::<F, C, D>(common_data, vars);
 
@@ -45,6 +56,8 @@ pub(crate) fn eval_vanishing_poly<
     let mut vanishing_z_1_terms = Vec::new();
     // The terms checking the partial products.
     let mut vanishing_partial_products_terms = Vec::new();
+    // The terms of the lookup argument.
+    let mut vanishing_lookup_terms = Vec::new();
 
     let l_0_x = plonk_common::eval_l_0(common_data.degree(), x);
 
@@ -81,11 +94,51 @@ pub(crate) fn eval_vanishing_poly<
             max_degree,
         );
         vanishing_partial_products_terms.extend(partial_product_checks);
+
+        if num_lookup_polys > 0 {
+            let current_lookup_zs = &lookup_zs[i * num_lookup_polys..(i + 1) * num_lookup_polys];
+            let lookup_z_x = current_lookup_zs[0];
+            let lookup_z_gx = next_lookup_zs[i * num_lookup_polys];
+            // L_0(x) Z(x) = 0.
+            vanishing_lookup_terms.push(l_0_x * lookup_z_x);
+
+            let lookup_selectors = &vars.local_constants[common_data.lookup_selectors_range()];
+            let (s_lookup, s_lookup_table) = (lookup_selectors[0], lookup_selectors[1]);
+            // The table index is the first constant of both lookup gates.
+            let table_index = vars.local_constants[common_data.selectors_info.num_selectors()];
+            let alpha = F::Extension::from_basefield(lookup_alphas[i]);
+            let delta = F::Extension::from_basefield(lookup_deltas[i]);
+            let numerator_values = (0..common_data.num_lookup_slots)
+                .map(|k| {
+                    let multiplicity = vars.local_wires[table_gate.wire_ith_multiplicity(k)];
+                    s_lookup - s_lookup_table * multiplicity
+                })
+                .collect::<Vec<_>>();
+            let denominator_values = (0..common_data.num_lookup_slots)
+                .map(|k| {
+                    let input = vars.local_wires[LookupGate::wire_ith_looking_inp(k)];
+                    let output = vars.local_wires[LookupGate::wire_ith_looking_out(k)];
+                    delta - (input + alpha * (output + alpha * table_index))
+                })
+                .collect::<Vec<_>>();
+
+            let lookup_checks = check_lookup_constraints(
+                &numerator_values,
+                &denominator_values,
+                &current_lookup_zs[1..],
+                s_lookup + s_lookup_table,
+                lookup_z_x,
+                lookup_z_gx,
+                common_data.num_lookup_slots_per_helper(),
+            );
+            vanishing_lookup_terms.extend(lookup_checks);
+        }
     }
 
     let vanishing_terms = [
         vanishing_z_1_terms,
         vanishing_partial_products_terms,
+        vanishing_lookup_terms,
         constraint_terms,
     ]
     .concat();
@@ -108,8 +161,12 @@ pub(crate) fn eval_vanishing_poly_base_batch<
     next_zs_batch: &[&[F]],
     partial_products_batch: &[&[F]],
     s_sigmas_batch: &[&[F]],
+    lookup_zs_batch: &[&[F]],
+    next_lookup_zs_batch: &[&[F]],
     betas: &[F],
     gammas: &[F],
+    lookup_alphas: &[F],
+    lookup_deltas: &[F],
     alphas: &[F],
     z_h_on_coset: &ZeroPolyOnCoset<F>,
 ) -> Vec<Vec<F>> {
@@ -120,9 +177,16 @@ pub(crate) fn eval_vanishing_poly_base_batch<
     assert_eq!(next_zs_batch.len(), n);
     assert_eq!(partial_products_batch.len(), n);
     assert_eq!(s_sigmas_batch.len(), n);
+    assert_eq!(lookup_zs_batch.len(), n);
+    assert_eq!(next_lookup_zs_batch.len(), n);
 
     let max_degree = common_data.quotient_degree_factor;
     let num_prods = common_data.num_partial_products;
+    let num_lookup_polys = common_data.num_lookup_polys();
+    let num_lookup_slots = common_data.num_lookup_slots;
+    let table_gate = LookupTableGate {
+        num_slots: num_lookup_slots,
+    };
 
     let num_gate_constraints = common_data.num_gate_constraints;
 
@@ -140,6 +204,11 @@ pub(crate) fn eval_vanishing_poly_base_batch<
     let mut vanishing_z_1_terms = Vec::with_capacity(num_challenges);
     // The terms checking the partial products.
     let mut vanishing_partial_products_terms = Vec::new();
+    // The terms of the lookup argument.
+    let mut vanishing_lookup_terms = Vec::new();
+
+    let mut lookup_numerator_values = Vec::with_capacity(num_lookup_slots);
+    let mut lookup_denominator_values = Vec::with_capacity(num_lookup_slots);
 
     let mut res_batch: Vec<Vec<F>> = Vec::with_capacity(n);
     for k in 0..n {
@@ -150,6 +219,8 @@ pub(crate) fn eval_vanishing_poly_base_batch<
         let next_zs = next_zs_batch[k];
         let partial_products = partial_products_batch[k];
         let s_sigmas = s_sigmas_batch[k];
+        let lookup_zs = lookup_zs_batch[k];
+        let next_lookup_zs = next_lookup_zs_batch[k];
 
         let constraint_terms = PackedStridedView::new(&constraint_terms_batch, n, k);
 
@@ -186,17 +257,59 @@ pub(crate) fn eval_vanishing_poly_base_batch<
 
             numerator_values.clear();
             denominator_values.clear();
+
+            if num_lookup_polys > 0 {
+                let current_lookup_zs =
+                    &lookup_zs[i * num_lookup_polys..(i + 1) * num_lookup_polys];
+                let lookup_z_x = current_lookup_zs[0];
+                let lookup_z_gx = next_lookup_zs[i * num_lookup_polys];
+                // L_0(x) Z(x) = 0.
+                vanishing_lookup_terms.push(l_0_x * lookup_z_x);
+
+                let lookup_selectors_start = common_data.lookup_selectors_range().start;
+                let s_lookup = vars.local_constants[lookup_selectors_start];
+                let s_lookup_table = vars.local_constants[lookup_selectors_start + 1];
+                // The table index is the first constant of both lookup gates.
+                let table_index = vars.local_constants[common_data.selectors_info.num_selectors()];
+                let alpha = lookup_alphas[i];
+                let delta = lookup_deltas[i];
+                lookup_numerator_values.extend((0..num_lookup_slots).map(|k| {
+                    let multiplicity = vars.local_wires[table_gate.wire_ith_multiplicity(k)];
+                    s_lookup - s_lookup_table * multiplicity
+                }));
+                lookup_denominator_values.extend((0..num_lookup_slots).map(|k| {
+                    let input = vars.local_wires[LookupGate::wire_ith_looking_inp(k)];
+                    let output = vars.local_wires[LookupGate::wire_ith_looking_out(k)];
+                    delta - (input + alpha * (output + alpha * table_index))
+                }));
+
+                let lookup_checks = check_lookup_constraints(
+                    &lookup_numerator_values,
+                    &lookup_denominator_values,
+                    &current_lookup_zs[1..],
+                    s_lookup + s_lookup_table,
+                    lookup_z_x,
+                    lookup_z_gx,
+                    common_data.num_lookup_slots_per_helper(),
+                );
+                vanishing_lookup_terms.extend(lookup_checks);
+
+                lookup_numerator_values.clear();
+                lookup_denominator_values.clear();
+            }
         }
 
         let vanishing_terms = vanishing_z_1_terms
             .iter()
             .chain(vanishing_partial_products_terms.iter())
+            .chain(vanishing_lookup_terms.iter())
             .chain(constraint_terms);
         let res = plonk_common::reduce_with_powers_multi(vanishing_terms, alphas);
         res_batch.push(res);
 
         vanishing_z_1_terms.clear();
         vanishing_partial_products_terms.clear();
+        vanishing_lookup_terms.clear();
     }
     res_batch
 }
@@ -320,12 +433,20 @@ pub(crate) fn eval_vanishing_poly_circuit<
     next_zs: &[ExtensionTarget<D>],
     partial_products: &[ExtensionTarget<D>],
     s_sigmas: &[ExtensionTarget<D>],
+    lookup_zs: &[ExtensionTarget<D>],
+    next_lookup_zs: &[ExtensionTarget<D>],
     betas: &[Target],
     gammas: &[Target],
+    lookup_alphas: &[Target],
+    lookup_deltas: &[Target],
     alphas: &[Target],
 ) -> Vec<ExtensionTarget<D>> {
     let max_degree = common_data.quotient_degree_factor;
     let num_prods = common_data.num_partial_products;
+    let num_lookup_polys = common_data.num_lookup_polys();
+    let table_gate = LookupTableGate {
+        num_slots: common_data.num_lookup_slots,
+    };
 
     let constraint_terms = with_context!(
         builder,
@@ -337,6 +458,8 @@ pub(crate) fn eval_vanishing_poly_circuit<
     let mut vanishing_z_1_terms = Vec::new();
     // The terms checking the partial products.
     let mut vanishing_partial_products_terms = Vec::new();
+    // The terms of the lookup argument.
+    let mut vanishing_lookup_terms = Vec::new();
 
     let l_0_x = eval_l_0_circuit(builder, common_data.degree(), x, x_pow_deg);
 
@@ -385,11 +508,62 @@ pub(crate) fn eval_vanishing_poly_circuit<
             max_degree,
         );
         vanishing_partial_products_terms.extend(partial_product_checks);
+
+        if num_lookup_polys > 0 {
+            let current_lookup_zs = &lookup_zs[i * num_lookup_polys..(i + 1) * num_lookup_polys];
+            let lookup_z_x = current_lookup_zs[0];
+            let lookup_z_gx = next_lookup_zs[i * num_lookup_polys];
+            // L_0(x) Z(x) = 0.
+            vanishing_lookup_terms.push(builder.mul_extension(l_0_x, lookup_z_x));
+
+            let lookup_selectors = &vars.local_constants[common_data.lookup_selectors_range()];
+            let (s_lookup, s_lookup_table) = (lookup_selectors[0], lookup_selectors[1]);
+            // The table index is the first constant of both lookup gates.
+            let table_index = vars.local_constants[common_data.selectors_info.num_selectors()];
+            let alpha = builder.convert_to_ext(lookup_alphas[i]);
+            let delta = builder.convert_to_ext(lookup_deltas[i]);
+
+            let mut numerator_values = Vec::new();
+            let mut denominator_values = Vec::new();
+            for k in 0..common_data.num_lookup_slots {
+                // The numerator is `s_lookup - s_lookup_table * multiplicity`, and the
+                // denominator is `delta - (input + alpha * (output + alpha * table_index))`.
+                let multiplicity = vars.local_wires[table_gate.wire_ith_multiplicity(k)];
+                let numerator = builder.arithmetic_extension(
+                    F::NEG_ONE,
+                    F::ONE,
+                    s_lookup_table,
+                    multiplicity,
+                    s_lookup,
+                );
+                let input = vars.local_wires[LookupGate::wire_ith_looking_inp(k)];
+                let output = vars.local_wires[LookupGate::wire_ith_looking_out(k)];
+                let combined = builder.mul_add_extension(alpha, table_index, output);
+                let combined = builder.mul_add_extension(alpha, combined, input);
+                let denominator = builder.sub_extension(delta, combined);
+                numerator_values.push(numerator);
+                denominator_values.push(denominator);
+            }
+
+            let filter = builder.add_extension(s_lookup, s_lookup_table);
+            let lookup_checks = check_lookup_constraints_circuit(
+                builder,
+                &numerator_values,
+                &denominator_values,
+                &current_lookup_zs[1..],
+                filter,
+                lookup_z_x,
+                lookup_z_gx,
+                common_data.num_lookup_slots_per_helper(),
+            );
+            vanishing_lookup_terms.extend(lookup_checks);
+        }
     }
 
     let vanishing_terms = [
         vanishing_z_1_terms,
         vanishing_partial_products_terms,
+        vanishing_lookup_terms,
         constraint_terms,
     ]
     .concat();
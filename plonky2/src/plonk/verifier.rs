@@ -7,7 +7,7 @@ use crate::hash::hash_types::RichField;
 use crate::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, Hasher};
 use crate::plonk::plonk_common::reduce_with_powers;
-use crate::plonk::proof::{Proof, ProofChallenges, ProofWithPublicInputs};
+use crate::plonk::proof::{OpeningSet, Proof, ProofChallenges, ProofWithPublicInputs};
 use crate::plonk::validate_shape::validate_proof_with_pis_shape;
 use crate::plonk::vanishing_poly::eval_vanishing_poly;
 use crate::plonk::vars::EvaluationVars;
@@ -52,37 +52,88 @@ pub(crate) fn verify_with_challenges<
 where
     [(); C::Hasher::HASH_SIZE]:,
 {
-    let local_constants = &proof.openings.constants;
-    let local_wires = &proof.openings.wires;
+    verify_vanishing_identity::<F, C, D>(
+        &proof.openings,
+        public_inputs_hash,
+        &challenges.plonk_betas,
+        &challenges.plonk_gammas,
+        &challenges.plonk_alphas,
+        &challenges.lookup_alphas,
+        &challenges.lookup_deltas,
+        challenges.plonk_zeta,
+        common_data,
+    )?;
+
+    let merkle_caps = &[
+        verifier_data.constants_sigmas_cap.clone(),
+        proof.wires_cap,
+        proof.plonk_zs_partial_products_cap,
+        proof.quotient_polys_cap,
+    ];
+
+    verify_fri_proof::<F, C, D>(
+        &common_data.get_fri_instance(challenges.plonk_zeta),
+        &proof.openings.to_fri_openings(),
+        &challenges.fri_challenges,
+        merkle_caps,
+        &proof.opening_proof,
+        &common_data.fri_params,
+    )?;
+
+    Ok(())
+}
+
+/// Checks that the opened values satisfy the vanishing identities of the circuit at `zeta`.
+pub(crate) fn verify_vanishing_identity<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    openings: &OpeningSet<F, D>,
+    public_inputs_hash: <<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash,
+    betas: &[F],
+    gammas: &[F],
+    alphas: &[F],
+    lookup_alphas: &[F],
+    lookup_deltas: &[F],
+    zeta: F::Extension,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<()> {
+    let local_constants = &openings.constants;
+    let local_wires = &openings.wires;
     let vars = EvaluationVars {
         local_constants,
         local_wires,
         public_inputs_hash: &public_inputs_hash,
     };
-    let local_zs = &proof.openings.plonk_zs;
-    let next_zs = &proof.openings.plonk_zs_next;
-    let s_sigmas = &proof.openings.plonk_sigmas;
-    let partial_products = &proof.openings.partial_products;
+    let local_zs = &openings.plonk_zs;
+    let next_zs = &openings.plonk_zs_next;
+    let s_sigmas = &openings.plonk_sigmas;
+    let partial_products = &openings.partial_products;
+    let lookup_zs = &openings.lookup_zs;
+    let next_lookup_zs = &openings.lookup_zs_next;
 
     // Evaluate the vanishing polynomial at our challenge point, zeta.
     let vanishing_polys_zeta = eval_vanishing_poly::<F, C, D>(
         common_data,
-        challenges.plonk_zeta,
+        zeta,
         vars,
         local_zs,
         next_zs,
         partial_products,
         s_sigmas,
-        &challenges.plonk_betas,
-        &challenges.plonk_gammas,
-        &challenges.plonk_alphas,
+        lookup_zs,
+        next_lookup_zs,
+        betas,
+        gammas,
+        lookup_alphas,
+        lookup_deltas,
+        alphas,
     );
 
     // Check each polynomial identity, of the form `vanishing(x) = Z_H(x) quotient(x)`, at zeta.
-    let quotient_polys_zeta = &proof.openings.quotient_polys;
-    let zeta_pow_deg = challenges
-        .plonk_zeta
-        .exp_power_of_2(common_data.degree_bits());
+    let quotient_polys_zeta = &openings.quotient_polys;
+    let zeta_pow_deg = zeta.exp_power_of_2(common_data.degree_bits());
     let z_h_zeta = zeta_pow_deg - F::Extension::ONE;
     // `quotient_polys_zeta` holds `num_challenges * quotient_degree_factor` evaluations.
     // Each chunk of `quotient_degree_factor` holds the evaluations of `t_0(zeta),...,t_{quotient_degree_factor-1}(zeta)`
@@ -96,21 +147,5 @@ where
         ensure!(vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg));
     }
 
-    let merkle_caps = &[
-        verifier_data.constants_sigmas_cap.clone(),
-        proof.wires_cap,
-        proof.plonk_zs_partial_products_cap,
-        proof.quotient_polys_cap,
-    ];
-
-    verify_fri_proof::<F, C, D>(
-        &common_data.get_fri_instance(challenges.plonk_zeta),
-        &proof.openings.to_fri_openings(),
-        &challenges.fri_challenges,
-        merkle_caps,
-        &proof.opening_proof,
-        &common_data.fri_params,
-    )?;
-
     Ok(())
 }
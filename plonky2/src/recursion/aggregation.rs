@@ -0,0 +1,321 @@
+use anyhow::{ensure, Result};
+use maybe_rayon::*;
+use plonky2_field::extension::Extendable;
+use plonky2_util::ceil_div_usize;
+
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::iop::witness::{PartialWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+use crate::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+
+/// A circuit verifying `arity` proofs of the same inner circuit. Its public inputs are a
+/// commitment to the inner proofs, namely the hash of the concatenation of their commitments.
+///
+/// The commitment of an inner proof is either the hash of its public inputs, for leaf proofs, or
+/// its public inputs themselves, for proofs of another aggregation circuit.
+pub struct AggregationCircuit<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub data: CircuitData<F, C, D>,
+    inner_proofs: Vec<ProofWithPublicInputsTarget<D>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    AggregationCircuit<F, C, D>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    /// Builds a circuit aggregating `arity` proofs of the given inner circuit. If
+    /// `hash_inner_public_inputs` is false, the inner public inputs must already be a commitment,
+    /// i.e. a single hash.
+    pub fn new(
+        inner_verifier_data: &VerifierOnlyCircuitData<C, D>,
+        inner_common_data: &CommonCircuitData<F, D>,
+        hash_inner_public_inputs: bool,
+        config: &CircuitConfig,
+        arity: usize,
+    ) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        assert!(arity > 0, "Cannot aggregate zero proofs");
+        assert!(
+            hash_inner_public_inputs || inner_common_data.num_public_inputs == 4,
+            "Inner public inputs should be a hash"
+        );
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let inner_verifier_target = builder.constant_verifier_data(inner_verifier_data);
+
+        let inner_proofs = (0..arity)
+            .map(|_| builder.add_virtual_proof_with_pis::<C>(inner_common_data))
+            .collect::<Vec<_>>();
+        let mut inner_commitments = Vec::with_capacity(4 * arity);
+        for inner_proof in &inner_proofs {
+            if hash_inner_public_inputs {
+                let hash = builder
+                    .hash_n_to_hash_no_pad::<C::InnerHasher>(inner_proof.public_inputs.clone());
+                inner_commitments.extend(hash.elements);
+            } else {
+                inner_commitments.extend(&inner_proof.public_inputs);
+            }
+            builder.verify_proof::<C>(
+                inner_proof.clone(),
+                &inner_verifier_target,
+                inner_common_data,
+            );
+        }
+        let commitment = builder.hash_n_to_hash_no_pad::<C::InnerHasher>(inner_commitments);
+        builder.register_public_inputs(&commitment.elements);
+
+        let data = builder.build::<C>();
+        Self { data, inner_proofs }
+    }
+
+    pub fn arity(&self) -> usize {
+        self.inner_proofs.len()
+    }
+
+    /// Proves the aggregation of the given inner proofs.
+    pub fn prove(
+        &self,
+        inner_proofs: &[ProofWithPublicInputs<F, C, D>],
+    ) -> Result<ProofWithPublicInputs<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        ensure!(
+            inner_proofs.len() == self.arity(),
+            "Expected {} inner proofs, got {}",
+            self.arity(),
+            inner_proofs.len()
+        );
+        let mut pw = PartialWitness::new();
+        for (target, proof) in self.inner_proofs.iter().zip(inner_proofs) {
+            pw.set_proof_with_pis_target(target, proof);
+        }
+        self.data.prove(pw)
+    }
+}
+
+/// The result of aggregating leaf proofs with an `AggregationTree`.
+#[derive(Clone, Debug)]
+pub struct AggregationProof<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    /// The proof of the root of the tree, whose public inputs are the root commitment.
+    pub root_proof: ProofWithPublicInputs<F, C, D>,
+    /// The hashes of the public inputs of the leaf proofs, in order.
+    pub leaf_hashes: Vec<HashOut<F>>,
+}
+
+/// A tree of aggregation circuits, aggregating a fixed number of proofs of a leaf circuit into a
+/// single root proof. Each level of the tree uses a single `AggregationCircuit`, which verifies
+/// `arity` proofs of the level below. When the number of proofs of a level is not a multiple of
+/// `arity`, the last one is repeated to fill the last node.
+pub struct AggregationTree<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    num_leaves: usize,
+    /// `levels[0]` aggregates leaf proofs, and `levels[i]` aggregates proofs of `levels[i - 1]`.
+    levels: Vec<AggregationCircuit<F, C, D>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    AggregationTree<F, C, D>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    /// Builds the aggregation circuits of a tree of the given arity, for `num_leaves` proofs of
+    /// the leaf circuit.
+    pub fn new(
+        leaf_data: &VerifierCircuitData<F, C, D>,
+        config: &CircuitConfig,
+        arity: usize,
+        num_leaves: usize,
+    ) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        assert!(
+            arity > 1,
+            "Aggregation trees should have an arity of at least 2"
+        );
+        assert!(num_leaves > 0, "Cannot aggregate zero proofs");
+
+        let mut levels = vec![AggregationCircuit::new(
+            &leaf_data.verifier_only,
+            &leaf_data.common,
+            true,
+            config,
+            arity,
+        )];
+        let mut num_nodes = ceil_div_usize(num_leaves, arity);
+        while num_nodes > 1 {
+            let inner_data = &levels.last().unwrap().data;
+            let level = AggregationCircuit::new(
+                &inner_data.verifier_only,
+                &inner_data.common,
+                false,
+                config,
+                arity,
+            );
+            levels.push(level);
+            num_nodes = ceil_div_usize(num_nodes, arity);
+        }
+
+        Self { num_leaves, levels }
+    }
+
+    pub fn arity(&self) -> usize {
+        self.levels[0].arity()
+    }
+
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    pub fn levels(&self) -> &[AggregationCircuit<F, C, D>] {
+        &self.levels
+    }
+
+    /// The circuit proving the root of the tree.
+    pub fn root_circuit(&self) -> &CircuitData<F, C, D> {
+        &self.levels.last().unwrap().data
+    }
+
+    /// Aggregates the given leaf proofs, proving the nodes of each level in parallel.
+    pub fn prove(
+        &self,
+        leaf_proofs: &[ProofWithPublicInputs<F, C, D>],
+    ) -> Result<AggregationProof<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        ensure!(
+            leaf_proofs.len() == self.num_leaves,
+            "Expected {} leaf proofs, got {}",
+            self.num_leaves,
+            leaf_proofs.len()
+        );
+        let leaf_hashes = leaf_proofs
+            .iter()
+            .map(|proof| C::InnerHasher::hash_no_pad(&proof.public_inputs))
+            .collect();
+
+        let mut proofs = leaf_proofs.to_vec();
+        for level in &self.levels {
+            pad_to_multiple(&mut proofs, level.arity());
+            proofs = proofs
+                .par_chunks(level.arity())
+                .map(|inner_proofs| level.prove(inner_proofs))
+                .collect::<Result<Vec<_>>>()?;
+        }
+        debug_assert_eq!(proofs.len(), 1);
+
+        Ok(AggregationProof {
+            root_proof: proofs.pop().unwrap(),
+            leaf_hashes,
+        })
+    }
+
+    /// Computes the root commitment of the tree from the hashes of the leaf public inputs.
+    pub fn root_commitment(&self, leaf_hashes: &[HashOut<F>]) -> HashOut<F> {
+        let mut hashes = leaf_hashes.to_vec();
+        for _ in &self.levels {
+            pad_to_multiple(&mut hashes, self.arity());
+            hashes = hashes
+                .chunks(self.arity())
+                .map(|children| {
+                    let elements = children.iter().flat_map(|h| h.elements).collect::<Vec<_>>();
+                    C::InnerHasher::hash_no_pad(&elements)
+                })
+                .collect();
+        }
+        hashes[0]
+    }
+
+    /// Verifies the root proof, and checks that it commits to the given leaf hashes.
+    pub fn verify(&self, proof: AggregationProof<F, C, D>) -> Result<()>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        ensure!(
+            proof.leaf_hashes.len() == self.num_leaves,
+            "Expected {} leaf hashes, got {}",
+            self.num_leaves,
+            proof.leaf_hashes.len()
+        );
+        let root_commitment = self.root_commitment(&proof.leaf_hashes);
+        ensure!(
+            proof.root_proof.public_inputs == root_commitment.elements,
+            "Root proof does not commit to the leaf hashes"
+        );
+        self.root_circuit().verify(proof.root_proof)
+    }
+}
+
+/// Repeats the last element of `v` until its length is a multiple of `n`.
+fn pad_to_multiple<T: Clone>(v: &mut Vec<T>, n: usize) {
+    while v.len() % n != 0 {
+        v.push(v.last().unwrap().clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use super::*;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_aggregation_tree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        const NUM_LEAVES: usize = 5;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        // Leaf proofs show knowledge of a square root of their public input.
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let square = builder.square(x);
+        builder.register_public_input(square);
+        let leaf_data = builder.build::<C>();
+        let leaf_proofs = (0..NUM_LEAVES)
+            .map(|i| {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_usize(i));
+                leaf_data.prove(pw)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let tree = AggregationTree::new(&leaf_data.verifier_data(), &config, 2, NUM_LEAVES);
+        assert_eq!(tree.levels().len(), 3);
+        let proof = tree.prove(&leaf_proofs)?;
+        for (i, leaf_hash) in proof.leaf_hashes.iter().enumerate() {
+            let expected =
+                <C as GenericConfig<D>>::InnerHasher::hash_no_pad(&[F::from_canonical_usize(
+                    i * i,
+                )]);
+            assert_eq!(*leaf_hash, expected);
+        }
+        tree.verify(proof.clone())?;
+
+        // Tampering with the leaf hashes should be detected.
+        let mut bad_proof = proof;
+        bad_proof.leaf_hashes.swap(0, 1);
+        assert!(tree.verify(bad_proof).is_err());
+
+        Ok(())
+    }
+}
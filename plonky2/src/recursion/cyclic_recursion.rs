@@ -10,7 +10,7 @@ use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartialWitness, Witness};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::{
-    CommonCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
+    CircuitConfig, CommonCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
 };
 use crate::plonk::config::Hasher;
 use crate::plonk::config::{AlgebraicHasher, GenericConfig};
@@ -23,37 +23,45 @@ pub struct CyclicRecursionData<
     C: GenericConfig<D, F = F>,
     const D: usize,
 > {
-    proof: &'a Option<ProofWithPublicInputs<F, C, D>>,
-    verifier_data: &'a VerifierOnlyCircuitData<C, D>,
-    common_data: &'a CommonCircuitData<F, D>,
+    pub proof: &'a Option<ProofWithPublicInputs<F, C, D>>,
+    pub verifier_data: &'a VerifierOnlyCircuitData<C, D>,
+    pub common_data: &'a CommonCircuitData<F, D>,
 }
 
 pub struct CyclicRecursionTarget<const D: usize> {
     pub proof: ProofWithPublicInputsTarget<D>,
     pub verifier_data: VerifierCircuitTarget,
+    /// Index of the verifier data in the public inputs of the circuit.
+    pub verifier_data_start: usize,
     pub dummy_proof: ProofWithPublicInputsTarget<D>,
     pub dummy_verifier_data: VerifierCircuitTarget,
     pub base_case: BoolTarget,
 }
 
 impl<C: GenericConfig<D>, const D: usize> VerifierOnlyCircuitData<C, D> {
-    fn from_slice(slice: &[C::F], common_data: &CommonCircuitData<C::F, D>) -> Result<Self>
+    /// Reads verifier data from public inputs, where it is laid out as
+    /// `[circuit_digest, constants_sigmas_cap]` starting at index `start`.
+    fn from_slice(
+        slice: &[C::F],
+        start: usize,
+        common_data: &CommonCircuitData<C::F, D>,
+    ) -> Result<Self>
     where
         C::Hasher: AlgebraicHasher<C::F>,
     {
-        // The structure of the public inputs is `[..., circuit_digest, constants_sigmas_cap]`.
         let cap_len = common_data.config.fri_config.num_cap_elements();
-        let len = slice.len();
-        ensure!(len >= 4 + 4 * cap_len, "Not enough public inputs");
+        ensure!(
+            slice.len() >= start + 4 + 4 * cap_len,
+            "Not enough public inputs"
+        );
+        let circuit_digest = HashOut::from_partial(&slice[start..start + 4]);
         let constants_sigmas_cap = MerkleCap(
             (0..cap_len)
                 .map(|i| HashOut {
-                    elements: std::array::from_fn(|j| slice[len - 4 * (cap_len - i) + j]),
+                    elements: std::array::from_fn(|j| slice[start + 4 * (1 + i) + j]),
                 })
                 .collect(),
         );
-        let circuit_digest =
-            HashOut::from_partial(&slice[len - 4 - 4 * cap_len..len - 4 * cap_len]);
 
         Ok(Self {
             circuit_digest,
@@ -63,23 +71,28 @@ impl<C: GenericConfig<D>, const D: usize> VerifierOnlyCircuitData<C, D> {
 }
 
 impl VerifierCircuitTarget {
+    /// Reads verifier data from public input targets, laid out as in
+    /// `VerifierOnlyCircuitData::from_slice`.
     fn from_slice<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
         slice: &[Target],
+        start: usize,
         common_data: &CommonCircuitData<F, D>,
     ) -> Result<Self> {
         let cap_len = common_data.config.fri_config.num_cap_elements();
-        let len = slice.len();
-        ensure!(len >= 4 + 4 * cap_len, "Not enough public inputs");
+        ensure!(
+            slice.len() >= start + 4 + 4 * cap_len,
+            "Not enough public inputs"
+        );
+        let circuit_digest = HashOutTarget {
+            elements: std::array::from_fn(|i| slice[start + i]),
+        };
         let constants_sigmas_cap = MerkleCapTarget(
             (0..cap_len)
                 .map(|i| HashOutTarget {
-                    elements: std::array::from_fn(|j| slice[len - 4 * (cap_len - i) + j]),
+                    elements: std::array::from_fn(|j| slice[start + 4 * (1 + i) + j]),
                 })
                 .collect(),
         );
-        let circuit_digest = HashOutTarget {
-            elements: std::array::from_fn(|i| slice[len - 4 - 4 * cap_len + i]),
-        };
 
         Ok(Self {
             circuit_digest,
@@ -90,6 +103,11 @@ impl VerifierCircuitTarget {
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Cyclic recursion gadget.
+    ///
+    /// The verifier data of the circuit is part of its public inputs. It is registered where
+    /// `add_verifier_data_public_input` was called, or appended to the public inputs if it wasn't.
+    /// `previous_virtual_public_inputs` are connected, in order, to the public inputs of the
+    /// previous proof other than its verifier data.
     /// WARNING: Do not register any public input after calling this! TODO: relax this
     pub fn cyclic_recursion<C: GenericConfig<D, F = F>>(
         &mut self,
@@ -106,6 +124,11 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.add_verifier_data_public_input();
         }
         let verifier_data = self.verifier_data_public_input.clone().unwrap();
+        let verifier_data_start = self
+            .public_inputs
+            .iter()
+            .position(|&t| t == verifier_data.circuit_digest.elements[0])
+            .expect("Verifier data should be registered as public inputs");
         common_data.num_public_inputs = self.num_public_inputs();
         self.goal_common_data = Some(common_data.clone());
 
@@ -117,7 +140,11 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let proof = self.add_virtual_proof_with_pis::<C>(common_data);
         let dummy_proof = self.add_virtual_proof_with_pis::<C>(common_data);
 
-        let pis = VerifierCircuitTarget::from_slice::<F, C, D>(&proof.public_inputs, common_data)?;
+        let pis = VerifierCircuitTarget::from_slice::<F, C, D>(
+            &proof.public_inputs,
+            verifier_data_start,
+            common_data,
+        )?;
         // Connect previous verifier data to current one. This guarantees that every proof in the cycle uses the same verifier data.
         self.connect_hashes(pis.circuit_digest, verifier_data.circuit_digest);
         for (h0, h1) in pis
@@ -129,9 +156,17 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.connect_hashes(*h0, *h1);
         }
 
+        let verifier_data_range = verifier_data_start
+            ..verifier_data_start + 4 + 4 * verifier_data.constants_sigmas_cap.0.len();
+        let previous_public_inputs = proof
+            .public_inputs
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !verifier_data_range.contains(i))
+            .map(|(_, t)| t);
         for (x, y) in previous_virtual_public_inputs
             .iter()
-            .zip(&proof.public_inputs)
+            .zip(previous_public_inputs)
         {
             self.connect(*x, *y);
         }
@@ -158,6 +193,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         Ok(CyclicRecursionTarget {
             proof,
             verifier_data: verifier_data.clone(),
+            verifier_data_start,
             dummy_proof,
             dummy_verifier_data,
             base_case,
@@ -165,6 +201,43 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 }
 
+/// Generates `CommonCircuitData` usable for recursion: that of a circuit of degree `2^12`
+/// verifying a proof of a circuit with the same common data.
+pub fn common_data_for_recursion<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    config: &CircuitConfig,
+) -> CommonCircuitData<F, D>
+where
+    C::Hasher: AlgebraicHasher<F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let builder = CircuitBuilder::<F, D>::new(config.clone());
+    let data = builder.build::<C>();
+    let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+    let proof = builder.add_virtual_proof_with_pis::<C>(&data.common);
+    let verifier_data = VerifierCircuitTarget {
+        constants_sigmas_cap: builder.add_virtual_cap(data.common.config.fri_config.cap_height),
+        circuit_digest: builder.add_virtual_hash(),
+    };
+    builder.verify_proof::<C>(proof, &verifier_data, &data.common);
+    let data = builder.build::<C>();
+
+    let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+    let proof = builder.add_virtual_proof_with_pis::<C>(&data.common);
+    let verifier_data = VerifierCircuitTarget {
+        constants_sigmas_cap: builder.add_virtual_cap(data.common.config.fri_config.cap_height),
+        circuit_digest: builder.add_virtual_hash(),
+    };
+    builder.verify_proof::<C>(proof, &verifier_data, &data.common);
+    while builder.num_gates() < 1 << 12 {
+        builder.add_gate(NoopGate, vec![]);
+    }
+    builder.build::<C>().common
+}
+
 /// Set the targets in a `CyclicRecursionTarget` to their corresponding values in a `CyclicRecursionData`.
 pub fn set_cyclic_recursion_data_target<
     F: RichField + Extendable<D>,
@@ -174,7 +247,8 @@ pub fn set_cyclic_recursion_data_target<
     pw: &mut PartialWitness<F>,
     cyclic_recursion_data_target: &CyclicRecursionTarget<D>,
     cyclic_recursion_data: &CyclicRecursionData<F, C, D>,
-    // Public inputs to set in the base case to seed some initial data.
+    // Public inputs to set in the base case to seed some initial data. They are set in order,
+    // skipping the verifier data.
     public_inputs: &[F],
 ) -> Result<()>
 where
@@ -197,8 +271,6 @@ where
         let (dummy_proof, dummy_data) = dummy_proof::<F, C, D>(cyclic_recursion_data.common_data)?;
         pw.set_bool_target(cyclic_recursion_data_target.base_case, true);
         let mut proof = dummy_proof.clone();
-        proof.public_inputs[0..public_inputs.len()].copy_from_slice(public_inputs);
-        let pis_len = proof.public_inputs.len();
         // The circuit checks that the verifier data is the same throughout the cycle, so
         // we set the verifier data to the "real" verifier data even though it's unused in the base case.
         let num_cap = cyclic_recursion_data
@@ -206,7 +278,17 @@ where
             .config
             .fri_config
             .num_cap_elements();
-        let s = pis_len - 4 - 4 * num_cap;
+        let s = cyclic_recursion_data_target.verifier_data_start;
+        let verifier_data_range = s..s + 4 + 4 * num_cap;
+        ensure!(
+            public_inputs.len() <= proof.public_inputs.len() - verifier_data_range.len(),
+            "Too many public inputs"
+        );
+        let seeded_indices =
+            (0..proof.public_inputs.len()).filter(|i| !verifier_data_range.contains(i));
+        for (i, &x) in seeded_indices.zip(public_inputs) {
+            proof.public_inputs[i] = x;
+        }
         proof.public_inputs[s..s + 4]
             .copy_from_slice(&cyclic_recursion_data.verifier_data.circuit_digest.elements);
         for i in 0..num_cap {
@@ -231,8 +313,8 @@ where
 }
 
 /// Additional checks to be performed on a cyclic recursive proof in addition to verifying the proof.
-/// Checks that the `base_case` flag is boolean and that the purported verifier data in the public inputs
-/// match the real verifier data.
+/// Checks that the `base_case` flag is boolean and that the purported verifier data in the public inputs,
+/// starting at index `verifier_data_start`, match the real verifier data.
 pub fn check_cyclic_proof_verifier_data<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -241,11 +323,16 @@ pub fn check_cyclic_proof_verifier_data<
     proof: &ProofWithPublicInputs<F, C, D>,
     verifier_data: &VerifierOnlyCircuitData<C, D>,
     common_data: &CommonCircuitData<F, D>,
+    verifier_data_start: usize,
 ) -> Result<()>
 where
     C::Hasher: AlgebraicHasher<F>,
 {
-    let pis = VerifierOnlyCircuitData::<C, D>::from_slice(&proof.public_inputs, common_data)?;
+    let pis = VerifierOnlyCircuitData::<C, D>::from_slice(
+        &proof.public_inputs,
+        verifier_data_start,
+        common_data,
+    )?;
     ensure!(verifier_data.constants_sigmas_cap == pis.constants_sigmas_cap);
     ensure!(verifier_data.circuit_digest == pis.circuit_digest);
 
@@ -256,59 +343,20 @@ where
 mod tests {
 
     use anyhow::Result;
-    use plonky2_field::extension::Extendable;
     use plonky2_field::types::PrimeField64;
 
     use crate::field::types::Field;
-    use crate::gates::noop::NoopGate;
-    use crate::hash::hash_types::RichField;
     use crate::hash::hashing::hash_n_to_hash_no_pad;
     use crate::hash::poseidon::{PoseidonHash, PoseidonPermutation};
     use crate::iop::witness::PartialWitness;
     use crate::plonk::circuit_builder::CircuitBuilder;
-    use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData, VerifierCircuitTarget};
-    use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher, PoseidonGoldilocksConfig};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use crate::recursion::cyclic_recursion::{
-        check_cyclic_proof_verifier_data, set_cyclic_recursion_data_target, CyclicRecursionData,
+        check_cyclic_proof_verifier_data, common_data_for_recursion,
+        set_cyclic_recursion_data_target, CyclicRecursionData,
     };
 
-    // Generates `CommonCircuitData` usable for recursion.
-    fn common_data_for_recursion<
-        F: RichField + Extendable<D>,
-        C: GenericConfig<D, F = F>,
-        const D: usize,
-    >() -> CommonCircuitData<F, D>
-    where
-        C::Hasher: AlgebraicHasher<F>,
-        [(); C::Hasher::HASH_SIZE]:,
-    {
-        let config = CircuitConfig::standard_recursion_config();
-        let builder = CircuitBuilder::<F, D>::new(config);
-        let data = builder.build::<C>();
-        let config = CircuitConfig::standard_recursion_config();
-        let mut builder = CircuitBuilder::<F, D>::new(config);
-        let proof = builder.add_virtual_proof_with_pis::<C>(&data.common);
-        let verifier_data = VerifierCircuitTarget {
-            constants_sigmas_cap: builder.add_virtual_cap(data.common.config.fri_config.cap_height),
-            circuit_digest: builder.add_virtual_hash(),
-        };
-        builder.verify_proof::<C>(proof, &verifier_data, &data.common);
-        let data = builder.build::<C>();
-
-        let config = CircuitConfig::standard_recursion_config();
-        let mut builder = CircuitBuilder::<F, D>::new(config);
-        let proof = builder.add_virtual_proof_with_pis::<C>(&data.common);
-        let verifier_data = VerifierCircuitTarget {
-            constants_sigmas_cap: builder.add_virtual_cap(data.common.config.fri_config.cap_height),
-            circuit_digest: builder.add_virtual_hash(),
-        };
-        builder.verify_proof::<C>(proof, &verifier_data, &data.common);
-        while builder.num_gates() < 1 << 12 {
-            builder.add_gate(NoopGate, vec![]);
-        }
-        builder.build::<C>().common
-    }
-
     #[test]
     fn test_cyclic_recursion() -> Result<()> {
         const D: usize = 2;
@@ -317,7 +365,7 @@ mod tests {
 
         let config = CircuitConfig::standard_recursion_config();
         let mut pw = PartialWitness::new();
-        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
 
         // Circuit that computes a repeated hash.
         let initial_hash = builder.add_virtual_hash();
@@ -340,7 +388,7 @@ mod tests {
         ]
         .concat();
 
-        let mut common_data = common_data_for_recursion::<F, C, D>();
+        let mut common_data = common_data_for_recursion::<F, C, D>(&config);
 
         let base_case = builder.add_virtual_bool_target_safe();
         // Add cyclic recursion gadget.
@@ -373,6 +421,7 @@ mod tests {
             &proof,
             cyclic_recursion_data.verifier_data,
             cyclic_recursion_data.common_data,
+            cyclic_data_target.verifier_data_start,
         )?;
         cyclic_circuit_data.verify(proof.clone())?;
 
@@ -394,6 +443,7 @@ mod tests {
             &proof,
             cyclic_recursion_data.verifier_data,
             cyclic_recursion_data.common_data,
+            cyclic_data_target.verifier_data_start,
         )?;
         cyclic_circuit_data.verify(proof.clone())?;
 
@@ -415,6 +465,7 @@ mod tests {
             &proof,
             cyclic_recursion_data.verifier_data,
             cyclic_recursion_data.common_data,
+            cyclic_data_target.verifier_data_start,
         )?;
 
         // Verify that the proof correctly computes a repeated hash.
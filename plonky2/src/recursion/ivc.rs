@@ -0,0 +1,336 @@
+//! Incrementally verifiable computation (IVC) on top of cyclic recursion.
+//!
+//! An IVC repeatedly applies a step function to a state. Each proof attests that its state was
+//! obtained by applying the step function a number of times to an initial state, by verifying
+//! the previous proof in the same circuit. The public inputs of an IVC proof are laid out as
+//! `[verifier_data, initial_state, state, num_steps]`.
+
+use std::marker::PhantomData;
+
+use anyhow::{bail, ensure, Result};
+use plonky2_field::extension::Extendable;
+use plonky2_field::types::Field;
+
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::iop::target::Target;
+use crate::iop::witness::PartialWitness;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CircuitConfig, CircuitData, CommonCircuitData};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::recursion::cyclic_recursion::{
+    check_cyclic_proof_verifier_data, common_data_for_recursion, set_cyclic_recursion_data_target,
+    CyclicRecursionData, CyclicRecursionTarget,
+};
+
+/// Maximum number of circuit builds when searching for the common data of an IVC circuit.
+const MAX_COMMON_DATA_ITERATIONS: usize = 8;
+
+/// A state of an IVC, encoded as a fixed number of field elements.
+pub trait IvcState<F: Field>: Sized {
+    /// The number of field elements encoding a state.
+    const LEN: usize;
+
+    fn to_fields(&self) -> Vec<F>;
+
+    fn from_fields(fields: &[F]) -> Self;
+}
+
+impl<F: Field, const N: usize> IvcState<F> for [F; N] {
+    const LEN: usize = N;
+
+    fn to_fields(&self) -> Vec<F> {
+        self.to_vec()
+    }
+
+    fn from_fields(fields: &[F]) -> Self {
+        fields.try_into().unwrap()
+    }
+}
+
+impl<F: Field> IvcState<F> for HashOut<F> {
+    const LEN: usize = 4;
+
+    fn to_fields(&self) -> Vec<F> {
+        self.elements.to_vec()
+    }
+
+    fn from_fields(fields: &[F]) -> Self {
+        HashOut::from_vec(fields.to_vec())
+    }
+}
+
+/// The step function of an IVC.
+pub trait IvcStep<F: RichField + Extendable<D>, const D: usize> {
+    type State: IvcState<F>;
+    /// Private inputs of a step.
+    type Witness;
+    /// Targets for the private inputs of a step.
+    type WitnessTarget;
+
+    /// Adds the constraints of a step to `builder`. Returns the targets of the next state, computed
+    /// from the targets of the previous `state`, along with the targets of the step's private
+    /// inputs.
+    fn build_step(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        state: &[Target],
+    ) -> (Vec<Target>, Self::WitnessTarget);
+
+    /// Sets the private inputs of a step.
+    fn set_step_witness(
+        &self,
+        pw: &mut PartialWitness<F>,
+        target: &Self::WitnessTarget,
+        witness: &Self::Witness,
+    );
+}
+
+/// A proof that some state was reached by an IVC.
+#[derive(Clone, Debug)]
+pub struct IvcProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, T, const D: usize> {
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    _phantom: PhantomData<T>,
+}
+
+impl<F, C, T, const D: usize> IvcProof<F, C, T, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    T: IvcState<F>,
+{
+    /// The state the IVC started from.
+    pub fn initial_state(&self) -> T {
+        let start = self.state_start() - T::LEN;
+        T::from_fields(&self.proof.public_inputs[start..start + T::LEN])
+    }
+
+    /// The state reached after `num_steps` steps.
+    pub fn state(&self) -> T {
+        let start = self.state_start();
+        T::from_fields(&self.proof.public_inputs[start..start + T::LEN])
+    }
+
+    pub fn num_steps(&self) -> u64 {
+        self.proof.public_inputs.last().unwrap().to_canonical_u64()
+    }
+
+    fn state_start(&self) -> usize {
+        self.proof.public_inputs.len() - T::LEN - 1
+    }
+}
+
+/// A cyclic circuit proving steps of an IVC.
+pub struct IvcCircuit<F, C, S, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: IvcStep<F, D>,
+{
+    pub data: CircuitData<F, C, D>,
+    step: S,
+    step_target: S::WitnessTarget,
+    cyclic_target: CyclicRecursionTarget<D>,
+}
+
+impl<F, C, S, const D: usize> IvcCircuit<F, C, S, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+    S: IvcStep<F, D>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    /// Builds the cyclic circuit of an IVC with the given step function.
+    pub fn new(step: S, config: &CircuitConfig) -> Result<Self> {
+        // The circuit verifies proofs of itself, so its common data is a fixed point: we build it
+        // assuming some common data, and retry with the resulting common data until they match.
+        let mut common_data = common_data_for_recursion::<F, C, D>(config);
+        for _ in 0..MAX_COMMON_DATA_ITERATIONS {
+            let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+            let (step_target, cyclic_target) =
+                Self::add_ivc_circuit(&step, &mut builder, &mut common_data)?;
+            // `cyclic_recursion` sets the assumed common data as the goal of the build; we check
+            // it ourselves instead.
+            builder.goal_common_data = None;
+            let data = builder.build::<C>();
+            if data.common == common_data {
+                return Ok(Self {
+                    data,
+                    step,
+                    step_target,
+                    cyclic_target,
+                });
+            }
+            common_data = data.common;
+        }
+        bail!("Could not find common data for the IVC circuit.")
+    }
+
+    fn add_ivc_circuit(
+        step: &S,
+        builder: &mut CircuitBuilder<F, D>,
+        common_data: &mut CommonCircuitData<F, D>,
+    ) -> Result<(S::WitnessTarget, CyclicRecursionTarget<D>)> {
+        let len = S::State::LEN;
+        builder.add_verifier_data_public_input();
+        let initial_state = builder.add_virtual_targets(len);
+        builder.register_public_inputs(&initial_state);
+
+        // Public inputs of the previous proof, other than its verifier data.
+        let prev_initial_state = builder.add_virtual_targets(len);
+        let prev_state = builder.add_virtual_targets(len);
+        let prev_num_steps = builder.add_virtual_target();
+        let base_case = builder.add_virtual_bool_target_safe();
+
+        // The initial state is carried through the cycle. In the base case, the step is applied to
+        // the initial state.
+        for (&x, &y) in initial_state.iter().zip(&prev_initial_state) {
+            builder.connect(x, y);
+        }
+        let input_state = initial_state
+            .iter()
+            .zip(&prev_state)
+            .map(|(&x, &y)| builder.select(base_case, x, y))
+            .collect::<Vec<_>>();
+        let (state, step_target) = step.build_step(builder, &input_state);
+        ensure!(state.len() == len, "Invalid length of the next state.");
+        builder.register_public_inputs(&state);
+
+        let one = builder.one();
+        let incremented_num_steps = builder.add(prev_num_steps, one);
+        let num_steps = builder.select(base_case, one, incremented_num_steps);
+        builder.register_public_input(num_steps);
+
+        let prev_public_inputs = [prev_initial_state, prev_state, vec![prev_num_steps]].concat();
+        let cyclic_target =
+            builder.cyclic_recursion::<C>(base_case, &prev_public_inputs, common_data)?;
+        Ok((step_target, cyclic_target))
+    }
+
+    /// Proves the first step of the IVC, starting from `initial_state`.
+    pub fn prove_first_step(
+        &self,
+        initial_state: &S::State,
+        witness: &S::Witness,
+    ) -> Result<IvcProof<F, C, S::State, D>> {
+        self.prove(&None, &initial_state.to_fields(), witness)
+    }
+
+    /// Proves a step of the IVC, continuing from the state of `prev_proof`.
+    pub fn prove_step(
+        &self,
+        prev_proof: &IvcProof<F, C, S::State, D>,
+        witness: &S::Witness,
+    ) -> Result<IvcProof<F, C, S::State, D>> {
+        self.prove(&Some(prev_proof.proof.clone()), &[], witness)
+    }
+
+    fn prove(
+        &self,
+        prev_proof: &Option<ProofWithPublicInputs<F, C, D>>,
+        initial_state: &[F],
+        witness: &S::Witness,
+    ) -> Result<IvcProof<F, C, S::State, D>> {
+        let mut pw = PartialWitness::new();
+        let cyclic_recursion_data = CyclicRecursionData {
+            proof: prev_proof,
+            verifier_data: &self.data.verifier_only,
+            common_data: &self.data.common,
+        };
+        set_cyclic_recursion_data_target(
+            &mut pw,
+            &self.cyclic_target,
+            &cyclic_recursion_data,
+            initial_state,
+        )?;
+        self.step
+            .set_step_witness(&mut pw, &self.step_target, witness);
+        let proof = self.data.prove(pw)?;
+        Ok(IvcProof {
+            proof,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Verifies an IVC proof, including that it was produced by this circuit. The states and
+    /// number of steps it attests to can then be read from the proof.
+    pub fn verify(&self, proof: &IvcProof<F, C, S::State, D>) -> Result<()> {
+        check_cyclic_proof_verifier_data(
+            &proof.proof,
+            &self.data.verifier_only,
+            &self.data.common,
+            self.cyclic_target.verifier_data_start,
+        )?;
+        self.data.verify(proof.proof.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::hash::hashing::hash_n_to_hash_no_pad;
+    use crate::hash::poseidon::{PoseidonHash, PoseidonPermutation};
+    use crate::iop::witness::Witness;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    /// Hashes the state together with a private input.
+    struct HashChainStep;
+
+    impl IvcStep<F, D> for HashChainStep {
+        type State = HashOut<F>;
+        type Witness = F;
+        type WitnessTarget = Target;
+
+        fn build_step(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            state: &[Target],
+        ) -> (Vec<Target>, Target) {
+            let input = builder.add_virtual_target();
+            let inputs = [state, &[input]].concat();
+            let next_state = builder.hash_n_to_hash_no_pad::<PoseidonHash>(inputs);
+            (next_state.elements.to_vec(), input)
+        }
+
+        fn set_step_witness(&self, pw: &mut PartialWitness<F>, target: &Target, witness: &F) {
+            pw.set_target(*target, *witness);
+        }
+    }
+
+    #[test]
+    fn test_ivc() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let circuit = IvcCircuit::<F, C, _, D>::new(HashChainStep, &config)?;
+
+        let initial_state = HashOut::from_vec(vec![F::ZERO, F::ONE, F::TWO, F::NEG_ONE]);
+        let inputs = [F::from_canonical_u64(7), F::from_canonical_u64(8), F::ONE];
+        let mut proof = circuit.prove_first_step(&initial_state, &inputs[0])?;
+        circuit.verify(&proof)?;
+        for input in &inputs[1..] {
+            proof = circuit.prove_step(&proof, input)?;
+            circuit.verify(&proof)?;
+        }
+
+        let expected_state = inputs.iter().fold(initial_state, |h, &input| {
+            hash_n_to_hash_no_pad::<F, PoseidonPermutation>(&[h.to_fields(), vec![input]].concat())
+        });
+        assert_eq!(proof.initial_state(), initial_state);
+        assert_eq!(proof.state(), expected_state);
+        assert_eq!(proof.num_steps(), inputs.len() as u64);
+
+        // A proof claiming another state is rejected.
+        let mut bad_proof = proof;
+        let state_start = bad_proof.state_start();
+        bad_proof.proof.public_inputs[state_start] += F::ONE;
+        assert!(circuit.verify(&bad_proof).is_err());
+
+        Ok(())
+    }
+}
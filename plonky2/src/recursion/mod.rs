@@ -1,3 +1,7 @@
+pub mod aggregation;
 pub mod conditional_recursive_verifier;
 pub mod cyclic_recursion;
+pub mod ivc;
+pub mod multi_circuit_verifier;
 pub mod recursive_verifier;
+pub mod shrink;
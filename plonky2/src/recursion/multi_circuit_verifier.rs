@@ -0,0 +1,331 @@
+use plonky2_field::extension::Extendable;
+
+use crate::fri::structure::{FriInstanceInfoTarget, FriOpeningsTarget};
+use crate::hash::hash_types::{HashOutTarget, RichField};
+use crate::iop::challenger::RecursiveChallenger;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::VerifierCircuitTarget;
+use crate::plonk::config::{AlgebraicHasher, GenericConfig};
+use crate::plonk::multi_circuit::{
+    MultiCircuitProofEntryTarget, MultiCircuitProofTarget, MultiCircuitVerifierData,
+    MultiCircuitVerifierTarget, NUM_ORACLES_PER_CIRCUIT,
+};
+use crate::plonk::plonk_common::salt_size;
+use crate::with_context;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Recursively verifies a proof of several circuits sharing a single FRI proof. The
+    /// `constants_sigmas_cap` of each circuit in `inner_verifier_data` is the one committed on the
+    /// common LDE domain, as in `MultiCircuitVerifierData::constants_sigmas_caps`.
+    pub fn verify_multi_circuit_proof<C: GenericConfig<D, F = F>>(
+        &mut self,
+        proof: &MultiCircuitProofTarget<D>,
+        inner_verifier_data: &MultiCircuitVerifierTarget,
+        inner_data: &MultiCircuitVerifierData<F, C, D>,
+    ) where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        let num_circuits = inner_data.circuits.len();
+        assert_eq!(proof.entries.len(), num_circuits);
+        assert_eq!(inner_verifier_data.circuits.len(), num_circuits);
+        for (entry, circuit) in proof.entries.iter().zip(&inner_data.circuits) {
+            assert_eq!(entry.public_inputs.len(), circuit.common.num_public_inputs);
+        }
+
+        let public_inputs_hashes = proof
+            .entries
+            .iter()
+            .map(|entry| self.hash_n_to_hash_no_pad::<C::InnerHasher>(entry.public_inputs.clone()))
+            .collect::<Vec<_>>();
+
+        // Replay the prover's transcript, as in `MultiCircuitVerifierData::verify`.
+        let mut challenger = RecursiveChallenger::<F, C::Hasher, D>::new(self);
+        for (verifier_data, public_inputs_hash) in inner_verifier_data
+            .circuits
+            .iter()
+            .zip(&public_inputs_hashes)
+        {
+            challenger.observe_hash(&verifier_data.circuit_digest);
+            challenger.observe_cap(&verifier_data.constants_sigmas_cap);
+            challenger.observe_hash(public_inputs_hash);
+        }
+        for entry in &proof.entries {
+            challenger.observe_cap(&entry.wires_cap);
+        }
+        let permutation_challenges = inner_data
+            .circuits
+            .iter()
+            .map(|circuit| {
+                let num_challenges = circuit.common.config.num_challenges;
+                let betas = challenger.get_n_challenges(self, num_challenges);
+                let gammas = challenger.get_n_challenges(self, num_challenges);
+                let (lookup_alphas, lookup_deltas) = if circuit.common.num_lookup_polys() > 0 {
+                    (
+                        challenger.get_n_challenges(self, num_challenges),
+                        challenger.get_n_challenges(self, num_challenges),
+                    )
+                } else {
+                    (Vec::new(), Vec::new())
+                };
+                (betas, gammas, lookup_alphas, lookup_deltas)
+            })
+            .collect::<Vec<_>>();
+        for entry in &proof.entries {
+            challenger.observe_cap(&entry.plonk_zs_partial_products_cap);
+        }
+        let all_alphas = inner_data
+            .circuits
+            .iter()
+            .map(|circuit| challenger.get_n_challenges(self, circuit.common.config.num_challenges))
+            .collect::<Vec<_>>();
+        for entry in &proof.entries {
+            challenger.observe_cap(&entry.quotient_polys_cap);
+        }
+        let zetas = (0..num_circuits)
+            .map(|_| challenger.get_extension_challenge(self))
+            .collect::<Vec<_>>();
+        for entry in &proof.entries {
+            challenger.observe_openings(&entry.openings.to_fri_openings());
+        }
+        let fri_challenges = challenger.fri_challenges::<C>(
+            self,
+            &proof.opening_proof.commit_phase_merkle_caps,
+            &proof.opening_proof.final_poly,
+            proof.opening_proof.pow_witness,
+            &inner_data.fri_params.config,
+        );
+
+        for (i, entry) in proof.entries.iter().enumerate() {
+            let (betas, gammas, lookup_alphas, lookup_deltas) = &permutation_challenges[i];
+            with_context!(
+                self,
+                &format!("check the vanishing identity of circuit {i}"),
+                self.verify_vanishing_identity::<C>(
+                    &entry.openings,
+                    public_inputs_hashes[i],
+                    betas,
+                    gammas,
+                    &all_alphas[i],
+                    lookup_alphas,
+                    lookup_deltas,
+                    zetas[i],
+                    &inner_data.circuits[i].common,
+                )
+            );
+        }
+
+        let merkle_caps = proof
+            .entries
+            .iter()
+            .zip(&inner_verifier_data.circuits)
+            .flat_map(|(entry, verifier_data)| {
+                [
+                    verifier_data.constants_sigmas_cap.clone(),
+                    entry.wires_cap.clone(),
+                    entry.plonk_zs_partial_products_cap.clone(),
+                    entry.quotient_polys_cap.clone(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let openings = FriOpeningsTarget {
+            batches: proof
+                .entries
+                .iter()
+                .flat_map(|entry| entry.openings.to_fri_openings().batches)
+                .collect(),
+        };
+        let fri_instance = self.multi_circuit_fri_instance_target(inner_data, &zetas);
+        with_context!(
+            self,
+            "verify FRI proof",
+            self.verify_fri_proof::<C>(
+                &fri_instance,
+                &openings,
+                &fri_challenges,
+                &merkle_caps,
+                &proof.opening_proof,
+                &inner_data.fri_params,
+            )
+        );
+    }
+
+    /// Recursive version of `MultiCircuitVerifierData::get_fri_instance`.
+    fn multi_circuit_fri_instance_target<C: GenericConfig<D, F = F>>(
+        &mut self,
+        inner_data: &MultiCircuitVerifierData<F, C, D>,
+        zetas: &[ExtensionTarget<D>],
+    ) -> FriInstanceInfoTarget<D> {
+        let mut oracles = Vec::new();
+        let mut batches = Vec::new();
+        for (i, (circuit, &zeta)) in inner_data.circuits.iter().zip(zetas).enumerate() {
+            let instance = circuit.common.get_fri_instance_target(self, zeta);
+            debug_assert_eq!(instance.oracles.len(), NUM_ORACLES_PER_CIRCUIT);
+            oracles.extend(instance.oracles);
+            batches.extend(instance.batches.into_iter().map(|mut batch| {
+                for polynomial in &mut batch.polynomials {
+                    polynomial.oracle_index += NUM_ORACLES_PER_CIRCUIT * i;
+                }
+                batch
+            }));
+        }
+        FriInstanceInfoTarget { oracles, batches }
+    }
+
+    pub fn add_virtual_multi_circuit_proof<InnerC: GenericConfig<D, F = F>>(
+        &mut self,
+        inner_data: &MultiCircuitVerifierData<F, InnerC, D>,
+    ) -> MultiCircuitProofTarget<D> {
+        let fri_params = &inner_data.fri_params;
+        let cap_height = fri_params.config.cap_height;
+        let salt = salt_size(fri_params.hiding);
+
+        let mut num_leaves_per_oracle = Vec::new();
+        let entries = inner_data
+            .circuits
+            .iter()
+            .map(|circuit| {
+                let common_data = &circuit.common;
+                num_leaves_per_oracle.extend([
+                    common_data.num_preprocessed_polys(),
+                    common_data.config.num_wires + salt,
+                    common_data.num_zs_partial_products_polys() + salt,
+                    common_data.num_quotient_polys() + salt,
+                ]);
+                MultiCircuitProofEntryTarget {
+                    wires_cap: self.add_virtual_cap(cap_height),
+                    plonk_zs_partial_products_cap: self.add_virtual_cap(cap_height),
+                    quotient_polys_cap: self.add_virtual_cap(cap_height),
+                    openings: self.add_opening_set::<InnerC>(common_data),
+                    public_inputs: self.add_virtual_targets(common_data.num_public_inputs),
+                }
+            })
+            .collect();
+
+        MultiCircuitProofTarget {
+            entries,
+            opening_proof: self.add_virtual_fri_proof(&num_leaves_per_oracle, fri_params),
+        }
+    }
+
+    /// Returns a `MultiCircuitVerifierTarget` holding the given verifier data as constants, for
+    /// verifying proofs of circuits known at build time.
+    pub fn constant_multi_circuit_verifier_data<InnerC: GenericConfig<D, F = F>>(
+        &mut self,
+        inner_data: &MultiCircuitVerifierData<F, InnerC, D>,
+    ) -> MultiCircuitVerifierTarget
+    where
+        InnerC::Hasher: AlgebraicHasher<F>,
+    {
+        let circuits = inner_data
+            .circuits
+            .iter()
+            .zip(&inner_data.constants_sigmas_caps)
+            .map(|(circuit, constants_sigmas_cap)| VerifierCircuitTarget {
+                constants_sigmas_cap: self.constant_merkle_cap(constants_sigmas_cap),
+                circuit_digest: self.constant_hash(circuit.verifier_only.circuit_digest),
+            })
+            .collect();
+        MultiCircuitVerifierTarget { circuits }
+    }
+
+    /// Adds virtual verifier data for the given circuits, to be set with
+    /// `Witness::set_multi_circuit_verifier_data_target`.
+    pub fn add_virtual_multi_circuit_verifier_data<InnerC: GenericConfig<D, F = F>>(
+        &mut self,
+        inner_data: &MultiCircuitVerifierData<F, InnerC, D>,
+    ) -> MultiCircuitVerifierTarget {
+        let cap_height = inner_data.fri_params.config.cap_height;
+        let circuits = (0..inner_data.circuits.len())
+            .map(|_| VerifierCircuitTarget {
+                constants_sigmas_cap: self.add_virtual_cap(cap_height),
+                circuit_digest: HashOutTarget::from_vec(self.add_virtual_targets(4)),
+            })
+            .collect();
+        MultiCircuitVerifierTarget { circuits }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::multi_circuit::MultiCircuitData;
+
+    #[test]
+    fn test_recursive_multi_circuit_verifier() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        // Two inner circuits of different degrees.
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let square = builder.square(x);
+        builder.register_public_input(square);
+        let square_data = builder.build::<C>();
+
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let y = builder.add_virtual_target();
+        builder.register_public_input(y);
+        let mut power = y;
+        for _ in 0..2000 {
+            power = builder.mul(power, y);
+        }
+        builder.register_public_input(power);
+        let power_data = builder.build::<C>();
+
+        let inner = MultiCircuitData::new(vec![square_data, power_data]);
+        let mut square_pw = PartialWitness::new();
+        square_pw.set_target(x, F::from_canonical_u64(3));
+        let mut power_pw = PartialWitness::new();
+        power_pw.set_target(y, F::TWO);
+        let inner_proof = inner.prove(vec![square_pw, power_pw])?;
+        inner.verify(inner_proof.clone())?;
+
+        // The outer circuit verifies the multi-circuit proof, with the inner verifier data given
+        // both as constants and in the witness.
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let proof_target = builder.add_virtual_multi_circuit_proof(&inner.verifier_data);
+        let constant_verifier_data =
+            builder.constant_multi_circuit_verifier_data(&inner.verifier_data);
+        builder.verify_multi_circuit_proof(
+            &proof_target,
+            &constant_verifier_data,
+            &inner.verifier_data,
+        );
+        let verifier_data_target =
+            builder.add_virtual_multi_circuit_verifier_data(&inner.verifier_data);
+        builder.verify_multi_circuit_proof(
+            &proof_target,
+            &verifier_data_target,
+            &inner.verifier_data,
+        );
+        builder.register_public_inputs(&proof_target.entries[1].public_inputs);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_multi_circuit_proof_target(&proof_target, &inner_proof);
+        pw.set_multi_circuit_verifier_data_target(&verifier_data_target, &inner.verifier_data);
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, inner_proof.entries[1].public_inputs);
+        data.verify(proof)?;
+
+        // A tampered inner proof can't be proven in the outer circuit.
+        let mut bad_proof = inner_proof;
+        bad_proof.entries[0].public_inputs[0] = F::from_canonical_u64(10);
+        let mut pw = PartialWitness::new();
+        pw.set_multi_circuit_proof_target(&proof_target, &bad_proof);
+        pw.set_multi_circuit_verifier_data_target(&verifier_data_target, &inner.verifier_data);
+        assert!(data.prove(pw).is_err());
+
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
 use plonky2_field::extension::Extendable;
 
 use crate::hash::hash_types::{HashOutTarget, RichField};
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
-use crate::plonk::circuit_data::{CommonCircuitData, VerifierCircuitTarget};
+use crate::plonk::circuit_data::{
+    CommonCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
+};
 use crate::plonk::config::{AlgebraicHasher, GenericConfig};
 use crate::plonk::plonk_common::salt_size;
 use crate::plonk::proof::{
@@ -56,43 +60,96 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     ) where
         C::Hasher: AlgebraicHasher<F>,
     {
+        self.verify_vanishing_identity::<C>(
+            &proof.openings,
+            public_inputs_hash,
+            &challenges.plonk_betas,
+            &challenges.plonk_gammas,
+            &challenges.plonk_alphas,
+            &challenges.lookup_alphas,
+            &challenges.lookup_deltas,
+            challenges.plonk_zeta,
+            inner_common_data,
+        );
+
+        let merkle_caps = &[
+            inner_verifier_data.constants_sigmas_cap.clone(),
+            proof.wires_cap,
+            proof.plonk_zs_partial_products_cap,
+            proof.quotient_polys_cap,
+        ];
+
+        let fri_instance = inner_common_data.get_fri_instance_target(self, challenges.plonk_zeta);
+        with_context!(
+            self,
+            "verify FRI proof",
+            self.verify_fri_proof::<C>(
+                &fri_instance,
+                &proof.openings.to_fri_openings(),
+                &challenges.fri_challenges,
+                merkle_caps,
+                &proof.opening_proof,
+                &inner_common_data.fri_params,
+            )
+        );
+    }
+
+    /// Checks that the opened values satisfy the vanishing identities of the inner circuit at
+    /// `zeta`.
+    pub(crate) fn verify_vanishing_identity<C: GenericConfig<D, F = F>>(
+        &mut self,
+        openings: &OpeningSetTarget<D>,
+        public_inputs_hash: HashOutTarget,
+        betas: &[Target],
+        gammas: &[Target],
+        alphas: &[Target],
+        lookup_alphas: &[Target],
+        lookup_deltas: &[Target],
+        zeta: ExtensionTarget<D>,
+        inner_common_data: &CommonCircuitData<F, D>,
+    ) {
         let one = self.one_extension();
 
-        let local_constants = &proof.openings.constants;
-        let local_wires = &proof.openings.wires;
+        let local_constants = &openings.constants;
+        let local_wires = &openings.wires;
         let vars = EvaluationTargets {
             local_constants,
             local_wires,
             public_inputs_hash: &public_inputs_hash,
         };
-        let local_zs = &proof.openings.plonk_zs;
-        let next_zs = &proof.openings.plonk_zs_next;
-        let s_sigmas = &proof.openings.plonk_sigmas;
-        let partial_products = &proof.openings.partial_products;
-
-        let zeta_pow_deg =
-            self.exp_power_of_2_extension(challenges.plonk_zeta, inner_common_data.degree_bits());
+        let local_zs = &openings.plonk_zs;
+        let next_zs = &openings.plonk_zs_next;
+        let s_sigmas = &openings.plonk_sigmas;
+        let partial_products = &openings.partial_products;
+        let lookup_zs = &openings.lookup_zs;
+        let next_lookup_zs = &openings.lookup_zs_next;
+
+        let zeta_pow_deg = self.exp_power_of_2_extension(zeta, inner_common_data.degree_bits());
         let vanishing_polys_zeta = with_context!(
             self,
             "evaluate the vanishing polynomial at our challenge point, zeta.",
             eval_vanishing_poly_circuit::<F, C, D>(
                 self,
                 inner_common_data,
-                challenges.plonk_zeta,
+                zeta,
                 zeta_pow_deg,
                 vars,
                 local_zs,
                 next_zs,
                 partial_products,
                 s_sigmas,
-                &challenges.plonk_betas,
-                &challenges.plonk_gammas,
-                &challenges.plonk_alphas,
+                lookup_zs,
+                next_lookup_zs,
+                betas,
+                gammas,
+                lookup_alphas,
+                lookup_deltas,
+                alphas,
             )
         );
 
         with_context!(self, "check vanishing and quotient polynomials.", {
-            let quotient_polys_zeta = &proof.openings.quotient_polys;
+            let quotient_polys_zeta = &openings.quotient_polys;
             let mut scale = ReducingFactorTarget::new(zeta_pow_deg);
             let z_h_zeta = self.sub_extension(zeta_pow_deg, one);
             for (i, chunk) in quotient_polys_zeta
@@ -104,27 +161,6 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
                 self.connect_extension(vanishing_polys_zeta[i], computed_vanishing_poly);
             }
         });
-
-        let merkle_caps = &[
-            inner_verifier_data.constants_sigmas_cap.clone(),
-            proof.wires_cap,
-            proof.plonk_zs_partial_products_cap,
-            proof.quotient_polys_cap,
-        ];
-
-        let fri_instance = inner_common_data.get_fri_instance_target(self, challenges.plonk_zeta);
-        with_context!(
-            self,
-            "verify FRI proof",
-            self.verify_fri_proof::<C>(
-                &fri_instance,
-                &proof.openings.to_fri_openings(),
-                &challenges.fri_challenges,
-                merkle_caps,
-                &proof.opening_proof,
-                &inner_common_data.fri_params,
-            )
-        );
     }
 
     pub fn add_virtual_proof_with_pis<InnerC: GenericConfig<D, F = F>>(
@@ -139,6 +175,21 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
+    /// Returns a `VerifierCircuitTarget` holding the given verifier data as constants, for verifying
+    /// proofs of a circuit known at build time.
+    pub fn constant_verifier_data<InnerC: GenericConfig<D, F = F>>(
+        &mut self,
+        verifier_data: &VerifierOnlyCircuitData<InnerC, D>,
+    ) -> VerifierCircuitTarget
+    where
+        InnerC::Hasher: AlgebraicHasher<F>,
+    {
+        VerifierCircuitTarget {
+            constants_sigmas_cap: self.constant_merkle_cap(&verifier_data.constants_sigmas_cap),
+            circuit_digest: self.constant_hash(verifier_data.circuit_digest),
+        }
+    }
+
     fn add_virtual_proof<InnerC: GenericConfig<D, F = F>>(
         &mut self,
         common_data: &CommonCircuitData<F, D>,
@@ -164,13 +215,14 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
-    fn add_opening_set<InnerC: GenericConfig<D, F = F>>(
+    pub(crate) fn add_opening_set<InnerC: GenericConfig<D, F = F>>(
         &mut self,
         common_data: &CommonCircuitData<F, D>,
     ) -> OpeningSetTarget<D> {
         let config = &common_data.config;
         let num_challenges = config.num_challenges;
         let total_partial_products = num_challenges * common_data.num_partial_products;
+        let total_lookup_polys = num_challenges * common_data.num_lookup_polys();
         OpeningSetTarget {
             constants: self.add_virtual_extension_targets(common_data.num_constants),
             plonk_sigmas: self.add_virtual_extension_targets(config.num_routed_wires),
@@ -178,6 +230,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             plonk_zs: self.add_virtual_extension_targets(num_challenges),
             plonk_zs_next: self.add_virtual_extension_targets(num_challenges),
             partial_products: self.add_virtual_extension_targets(total_partial_products),
+            lookup_zs: self.add_virtual_extension_targets(total_lookup_polys),
+            lookup_zs_next: self.add_virtual_extension_targets(total_lookup_polys),
             quotient_polys: self.add_virtual_extension_targets(common_data.num_quotient_polys()),
         }
     }
@@ -185,6 +239,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use anyhow::Result;
     use log::{info, Level};
 
@@ -192,13 +248,17 @@ mod tests {
     use crate::fri::reduction_strategies::FriReductionStrategy;
     use crate::fri::FriConfig;
     use crate::gates::noop::NoopGate;
+    use crate::hash::poseidon2::CustomPoseidon2;
+    use crate::hash::rescue_prime::CustomRescuePrime;
     use crate::iop::witness::{PartialWitness, Witness};
     use crate::plonk::circuit_data::{CircuitConfig, VerifierOnlyCircuitData};
     use crate::plonk::config::{
-        GenericConfig, Hasher, KeccakGoldilocksConfig, PoseidonGoldilocksConfig,
+        CustomPoseidon2GoldilocksConfig, CustomRescuePrimeGoldilocksConfig, GenericConfig, Hasher,
+        KeccakGoldilocksConfig, PoseidonGoldilocksConfig,
     };
     use crate::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
     use crate::plonk::prover::prove;
+    use crate::util::serialization::gate_serialization::HashGateSerializer;
     use crate::util::timing::TimingTree;
 
     #[test]
@@ -323,6 +383,55 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_recursive_verifier_poseidon2() -> Result<()> {
+        init_logger();
+        const D: usize = 2;
+        type C = CustomPoseidon2GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let config = CircuitConfig::standard_recursion_config();
+
+        let (proof, vd, cd) = dummy_proof::<F, C, D>(&config, 4_000)?;
+        let (proof, vd, cd) =
+            recursive_proof::<F, C, C, D>(proof, vd, cd, &config, None, true, true)?;
+        test_serialization(&proof, &vd, &cd)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recursive_verifier_rescue_prime() -> Result<()> {
+        init_logger();
+        const D: usize = 2;
+        type C = CustomRescuePrimeGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let config = CircuitConfig::standard_recursion_config();
+
+        let (proof, vd, cd) = dummy_proof::<F, C, D>(&config, 4_000)?;
+        let (proof, vd, cd) =
+            recursive_proof::<F, C, C, D>(proof, vd, cd, &config, None, true, true)?;
+        test_serialization(&proof, &vd, &cd)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recursive_verifier_lookup() -> Result<()> {
+        init_logger();
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let config = CircuitConfig::standard_recursion_config();
+
+        let (proof, vd, cd) = lookup_proof::<F, C, D>(&config, 500)?;
+        assert!(cd.num_lookup_polys() > 0);
+        let (proof, vd, cd) =
+            recursive_proof::<F, C, C, D>(proof, vd, cd, &config, None, true, true)?;
+        test_serialization(&proof, &vd, &cd)?;
+
+        Ok(())
+    }
+
     /// Creates a dummy proof which should have roughly `num_dummy_gates` gates.
     fn dummy_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
         config: &CircuitConfig,
@@ -348,6 +457,36 @@ mod tests {
         Ok((proof, data.verifier_only, data.common))
     }
 
+    /// Creates a proof performing `num_lookups` lookups in a byte-squaring table.
+    fn lookup_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        config: &CircuitConfig,
+        num_lookups: usize,
+    ) -> Result<(
+        ProofWithPublicInputs<F, C, D>,
+        VerifierOnlyCircuitData<C, D>,
+        CommonCircuitData<F, D>,
+    )>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let table = Arc::new((0..256).map(|i| (i, i * i)).collect::<Vec<_>>());
+        let table_index = builder.add_lookup_table(table);
+        let mut inputs = PartialWitness::new();
+        for i in 0..num_lookups {
+            let x = builder.add_virtual_target();
+            inputs.set_target(x, F::from_canonical_usize(i % 256));
+            let square = builder.lookup(table_index, x);
+            builder.register_public_input(square);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(inputs)?;
+        data.verify(proof.clone())?;
+
+        Ok((proof, data.verifier_only, data.common))
+    }
+
     fn recursive_proof<
         F: RichField + Extendable<D>,
         C: GenericConfig<D, F = F>,
@@ -416,7 +555,7 @@ mod tests {
 
     /// Test serialization and print some size info.
     fn test_serialization<
-        F: RichField + Extendable<D>,
+        F: RichField + Extendable<D> + CustomPoseidon2 + CustomRescuePrime,
         C: GenericConfig<D, F = F>,
         const D: usize,
     >(
@@ -449,6 +588,20 @@ mod tests {
             CompressedProofWithPublicInputs::from_bytes(compressed_proof_bytes, cd)?;
         assert_eq!(compressed_proof, compressed_proof_from_bytes);
 
+        let gate_serializer = HashGateSerializer;
+        let common_data_bytes = cd.to_bytes(&gate_serializer)?;
+        info!(
+            "Common circuit data length: {} bytes",
+            common_data_bytes.len()
+        );
+        let common_data_from_bytes =
+            CommonCircuitData::from_bytes(common_data_bytes, &gate_serializer)?;
+        assert_eq!(cd, &common_data_from_bytes);
+
+        let verifier_data_bytes = vd.to_bytes()?;
+        let verifier_data_from_bytes = VerifierOnlyCircuitData::from_bytes(verifier_data_bytes)?;
+        assert_eq!(vd, &verifier_data_from_bytes);
+
         Ok(())
     }
 
@@ -0,0 +1,234 @@
+use anyhow::{ensure, Result};
+use log::info;
+use plonky2_field::extension::Extendable;
+
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::{PartialWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+use crate::plonk::proof::ProofWithPublicInputs;
+
+/// The maximum number of recursion layers used by `shrink_proof` to reach a fixed point.
+const MAX_SHRINK_LAYERS: usize = 8;
+
+/// The shape of the proof produced by one layer of a shrinking pipeline.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ShrinkStep {
+    pub degree_bits: usize,
+    /// The size of the serialized proof, in bytes.
+    pub proof_size: usize,
+}
+
+/// A proof produced by a shrinking pipeline, along with the circuit of its last layer. The public
+/// inputs of the proof are those of the original proof.
+pub struct ShrunkProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    pub circuit_data: CircuitData<F, C, D>,
+    /// The shape of the proof after each layer, starting with the original proof.
+    pub steps: Vec<ShrinkStep>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ShrunkProof<F, C, D>
+{
+    pub fn verify(&self) -> Result<()>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        self.circuit_data.verify(self.proof.clone())
+    }
+}
+
+/// Wraps a proof in a recursive verifier circuit built with the given config, which re-exposes the
+/// inner public inputs. The outer circuit may use a different `GenericConfig`, e.g.
+/// `KeccakGoldilocksConfig` for a final layer meant to be verified outside of a circuit.
+pub fn wrap_proof<
+    F: RichField + Extendable<D>,
+    InnerC: GenericConfig<D, F = F>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inner_proof: &ProofWithPublicInputs<F, InnerC, D>,
+    inner_verifier_data: &VerifierOnlyCircuitData<InnerC, D>,
+    inner_common_data: &CommonCircuitData<F, D>,
+    config: &CircuitConfig,
+) -> Result<(ProofWithPublicInputs<F, C, D>, CircuitData<F, C, D>)>
+where
+    InnerC::Hasher: AlgebraicHasher<F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+    let proof_target = builder.add_virtual_proof_with_pis::<InnerC>(inner_common_data);
+    let inner_verifier_target = builder.constant_verifier_data(inner_verifier_data);
+    builder.register_public_inputs(&proof_target.public_inputs);
+    builder.verify_proof::<InnerC>(
+        proof_target.clone(),
+        &inner_verifier_target,
+        inner_common_data,
+    );
+    let data = builder.build::<C>();
+
+    let mut pw = PartialWitness::new();
+    pw.set_proof_with_pis_target(&proof_target, inner_proof);
+    let proof = data.prove(pw)?;
+    Ok((proof, data))
+}
+
+/// Repeatedly wraps a proof in recursive verifier circuits built with `target_config`, until the
+/// circuit reaches a fixed point, i.e. until wrapping a proof yields a circuit with the same common
+/// data as the one it verifies.
+pub fn shrink_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    inner_proof: &ProofWithPublicInputs<F, C, D>,
+    inner_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    inner_common_data: &CommonCircuitData<F, D>,
+    target_config: &CircuitConfig,
+) -> Result<ShrunkProof<F, C, D>>
+where
+    C::Hasher: AlgebraicHasher<F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let mut steps = vec![shrink_step(inner_proof, inner_common_data)?];
+    let (mut proof, mut circuit_data) = wrap_proof::<F, C, C, D>(
+        inner_proof,
+        inner_verifier_data,
+        inner_common_data,
+        target_config,
+    )?;
+    steps.push(shrink_step(&proof, &circuit_data.common)?);
+
+    loop {
+        ensure!(
+            steps.len() <= MAX_SHRINK_LAYERS,
+            "No fixed point reached after {} layers",
+            MAX_SHRINK_LAYERS
+        );
+        let (next_proof, next_circuit_data) = wrap_proof::<F, C, C, D>(
+            &proof,
+            &circuit_data.verifier_only,
+            &circuit_data.common,
+            target_config,
+        )?;
+        let fixed_point = next_circuit_data.common == circuit_data.common;
+        proof = next_proof;
+        circuit_data = next_circuit_data;
+        steps.push(shrink_step(&proof, &circuit_data.common)?);
+        if fixed_point {
+            break;
+        }
+    }
+
+    Ok(ShrunkProof {
+        proof,
+        circuit_data,
+        steps,
+    })
+}
+
+/// Shrinks a proof with `shrink_proof`, then wraps the result in a final layer using `FinalC` and
+/// `final_config`, e.g. to switch to `KeccakGoldilocksConfig` for cheap on-chain verification.
+pub fn shrink_proof_to<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    FinalC: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inner_proof: &ProofWithPublicInputs<F, C, D>,
+    inner_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    inner_common_data: &CommonCircuitData<F, D>,
+    target_config: &CircuitConfig,
+    final_config: &CircuitConfig,
+) -> Result<ShrunkProof<F, FinalC, D>>
+where
+    C::Hasher: AlgebraicHasher<F>,
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); FinalC::Hasher::HASH_SIZE]:,
+{
+    let shrunk = shrink_proof(
+        inner_proof,
+        inner_verifier_data,
+        inner_common_data,
+        target_config,
+    )?;
+    let (proof, circuit_data) = wrap_proof::<F, C, FinalC, D>(
+        &shrunk.proof,
+        &shrunk.circuit_data.verifier_only,
+        &shrunk.circuit_data.common,
+        final_config,
+    )?;
+    let mut steps = shrunk.steps;
+    steps.push(shrink_step(&proof, &circuit_data.common)?);
+
+    Ok(ShrunkProof {
+        proof,
+        circuit_data,
+        steps,
+    })
+}
+
+fn shrink_step<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    proof: &ProofWithPublicInputs<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<ShrinkStep> {
+    let step = ShrinkStep {
+        degree_bits: common_data.degree_bits(),
+        proof_size: proof.to_bytes()?.len(),
+    };
+    info!(
+        "Shrinking step: degree 2^{}, proof size {} bytes",
+        step.degree_bits, step.proof_size
+    );
+    Ok(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use super::*;
+    use crate::gates::noop::NoopGate;
+    use crate::plonk::config::{KeccakGoldilocksConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_shrink_proof() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type KC = KeccakGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        for _ in 0..10_000 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let proof = data.prove(pw)?;
+
+        let shrunk = shrink_proof_to::<F, C, KC, D>(
+            &proof,
+            &data.verifier_only,
+            &data.common,
+            &config,
+            &config,
+        )?;
+        shrunk.verify()?;
+        assert_eq!(shrunk.proof.public_inputs, proof.public_inputs);
+
+        let steps = &shrunk.steps;
+        assert_eq!(steps[0].degree_bits, 14);
+        // The last two Poseidon layers reached a fixed point.
+        let num_steps = steps.len();
+        assert_eq!(steps[num_steps - 2], steps[num_steps - 3]);
+        assert!(steps[num_steps - 2].degree_bits < steps[0].degree_bits);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{ensure, Result};
+use keccak_hash::keccak;
+use plonky2_field::extension::FieldExtension;
+use plonky2_field::goldilocks_field::GoldilocksField;
+use plonky2_field::types::PrimeField64;
+
+use crate::hash::hash_types::BytesHash;
+use crate::hash::merkle_proofs::MerkleProof;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::config::{GenericConfig, KeccakGoldilocksConfig};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::plonk::validate_shape::validate_proof_with_pis_shape;
+use crate::solidity::layout::ProofLayout;
+
+type F = GoldilocksField;
+type C = KeccakGoldilocksConfig;
+type FE = <C as GenericConfig<2>>::FE;
+type H = <C as GenericConfig<2>>::Hasher;
+
+/// A big-endian EVM word.
+pub type Word = [u8; 32];
+
+/// Signature of the entry point of the generated verifier.
+pub const VERIFY_SIGNATURE: &str = "verify(uint256[],uint256[])";
+
+fn base_word(x: F) -> Word {
+    let mut word = [0; 32];
+    word[24..].copy_from_slice(&x.to_canonical_u64().to_be_bytes());
+    word
+}
+
+fn ext_word(x: FE) -> Word {
+    let [c0, c1] = x.to_basefield_array();
+    let mut word = base_word(c0);
+    word[8..16].copy_from_slice(&c1.to_canonical_u64().to_be_bytes());
+    word
+}
+
+fn hash_word(h: BytesHash<25>) -> Word {
+    let mut word = [0; 32];
+    word[..25].copy_from_slice(&h.0);
+    word
+}
+
+fn usize_word(x: usize) -> Word {
+    let mut word = [0; 32];
+    word[24..].copy_from_slice(&(x as u64).to_be_bytes());
+    word
+}
+
+fn push_cap(words: &mut Vec<Word>, cap: &MerkleCap<F, H>, layout: &ProofLayout) -> Result<()> {
+    ensure!(cap.0.len() == layout.cap_len, "Invalid Merkle cap length.");
+    words.extend(cap.0.iter().map(|&h| hash_word(h)));
+    Ok(())
+}
+
+fn push_merkle_proof(
+    words: &mut Vec<Word>,
+    proof: &MerkleProof<F, H>,
+    num_siblings: usize,
+) -> Result<()> {
+    ensure!(proof.len() == num_siblings, "Invalid Merkle proof length.");
+    words.extend(proof.siblings.iter().map(|&h| hash_word(h)));
+    Ok(())
+}
+
+/// Flattens a proof into the words expected by the `proof` argument of the generated verifier.
+/// See `ProofLayout` for the order of the components.
+pub fn proof_words(
+    proof_with_pis: &ProofWithPublicInputs<F, C, 2>,
+    common_data: &CommonCircuitData<F, 2>,
+) -> Result<Vec<Word>> {
+    validate_proof_with_pis_shape(proof_with_pis, common_data)?;
+    let layout = ProofLayout::new(common_data);
+    let proof = &proof_with_pis.proof;
+    let fri_proof = &proof.opening_proof;
+
+    let mut words = Vec::with_capacity(layout.len);
+    push_cap(&mut words, &proof.wires_cap, &layout)?;
+    push_cap(&mut words, &proof.plonk_zs_partial_products_cap, &layout)?;
+    push_cap(&mut words, &proof.quotient_polys_cap, &layout)?;
+
+    for batch in &proof.openings.to_fri_openings().batches {
+        words.extend(batch.values.iter().map(|&v| ext_word(v)));
+    }
+
+    for cap in &fri_proof.commit_phase_merkle_caps {
+        push_cap(&mut words, cap, &layout)?;
+    }
+
+    for round in &fri_proof.query_round_proofs {
+        for (evals, merkle_proof) in &round.initial_trees_proof.evals_proofs {
+            words.extend(evals.iter().map(|&e| base_word(e)));
+            push_merkle_proof(&mut words, merkle_proof, layout.initial_siblings)?;
+        }
+        for (step, &num_siblings) in round.steps.iter().zip(&layout.step_siblings) {
+            words.extend(step.evals.iter().map(|&e| ext_word(e)));
+            push_merkle_proof(&mut words, &step.merkle_proof, num_siblings)?;
+        }
+    }
+
+    words.extend(fri_proof.final_poly.coeffs.iter().map(|&c| ext_word(c)));
+    words.push(base_word(fri_proof.pow_witness));
+
+    ensure!(
+        words.len() == layout.len,
+        "Proof does not match the layout of the circuit."
+    );
+    Ok(words)
+}
+
+/// Flattens the public inputs of a proof into the words expected by the `publicInputs` argument
+/// of the generated verifier.
+pub fn public_inputs_words(proof_with_pis: &ProofWithPublicInputs<F, C, 2>) -> Vec<Word> {
+    proof_with_pis
+        .public_inputs
+        .iter()
+        .map(|&x| base_word(x))
+        .collect()
+}
+
+/// ABI-encodes a call to `verify(uint256[] publicInputs, uint256[] proof)` on the generated
+/// verifier, including the function selector.
+pub fn verify_calldata(
+    proof_with_pis: &ProofWithPublicInputs<F, C, 2>,
+    common_data: &CommonCircuitData<F, 2>,
+) -> Result<Vec<u8>> {
+    let public_inputs = public_inputs_words(proof_with_pis);
+    let proof = proof_words(proof_with_pis, common_data)?;
+
+    let mut calldata = keccak(VERIFY_SIGNATURE.as_bytes()).0[..4].to_vec();
+    // Both arguments are dynamic arrays, so the head holds their offsets.
+    let public_inputs_offset = 2 * 32;
+    let proof_offset = public_inputs_offset + (1 + public_inputs.len()) * 32;
+    calldata.extend(usize_word(public_inputs_offset));
+    calldata.extend(usize_word(proof_offset));
+    for array in [public_inputs, proof] {
+        calldata.extend(usize_word(array.len()));
+        calldata.extend(array.into_iter().flatten());
+    }
+    Ok(calldata)
+}
@@ -0,0 +1,93 @@
+use anyhow::{bail, Result};
+use plonky2_field::goldilocks_field::GoldilocksField;
+
+use crate::gates::arithmetic_base::ArithmeticGate;
+use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
+use crate::gates::base_sum::BaseSumGate;
+use crate::gates::constant::ConstantGate;
+use crate::gates::exponentiation::ExponentiationGate;
+use crate::gates::gate::GateRef;
+use crate::gates::high_degree_interpolation::HighDegreeInterpolationGate;
+use crate::gates::low_degree_interpolation::LowDegreeInterpolationGate;
+use crate::gates::multiplication_extension::MulExtensionGate;
+use crate::gates::noop::NoopGate;
+use crate::gates::poseidon::PoseidonGate;
+use crate::gates::poseidon_mds::PoseidonMdsGate;
+use crate::gates::public_input::PublicInputGate;
+use crate::gates::random_access::RandomAccessGate;
+use crate::gates::reducing::ReducingGate;
+use crate::gates::reducing_extension::ReducingExtensionGate;
+
+/// Returns the Solidity statement which adds the constraints of `gate`, multiplied by the Solidity
+/// expression `filter`, to the constraint accumulator `cs` of the generated verifier. Returns
+/// `None` for gates without constraints.
+pub(crate) fn gate_constraints_call(
+    gate: &GateRef<GoldilocksField, 2>,
+    filter: &str,
+) -> Result<Option<String>> {
+    let gate_any = gate.0.as_any();
+    let call = if gate_any.is::<NoopGate>() {
+        return Ok(None);
+    } else if let Some(g) = gate_any.downcast_ref::<ArithmeticGate>() {
+        format!("arithmeticConstraints(os, cs, {filter}, {});", g.num_ops)
+    } else if let Some(g) = gate_any.downcast_ref::<BaseSumGate<2>>() {
+        format!("baseSumConstraints(os, cs, {filter}, 2, {});", g.num_limbs)
+    } else if let Some(g) = gate_any.downcast_ref::<BaseSumGate<4>>() {
+        format!("baseSumConstraints(os, cs, {filter}, 4, {});", g.num_limbs)
+    } else if let Some(g) = gate_any.downcast_ref::<ConstantGate>() {
+        format!("constantConstraints(os, cs, {filter}, {});", g.num_consts)
+    } else if gate_any.is::<PublicInputGate>() {
+        format!("publicInputConstraints(os, cs, {filter}, piHash);")
+    } else if gate_any.is::<PoseidonGate<GoldilocksField, 2>>() {
+        format!("poseidonConstraints(os, cs, {filter}, pc);")
+    } else if gate_any.is::<PoseidonMdsGate<GoldilocksField, 2>>() {
+        format!("poseidonMdsConstraints(os, cs, {filter}, pc);")
+    } else if let Some(g) = gate_any.downcast_ref::<ArithmeticExtensionGate<2>>() {
+        format!(
+            "arithmeticExtensionConstraints(os, cs, {filter}, {});",
+            g.num_ops
+        )
+    } else if let Some(g) = gate_any.downcast_ref::<MulExtensionGate<2>>() {
+        format!("mulExtensionConstraints(os, cs, {filter}, {});", g.num_ops)
+    } else if let Some(g) = gate_any.downcast_ref::<ReducingGate<2>>() {
+        format!(
+            "reducingConstraints(os, cs, {filter}, {}, 1);",
+            g.num_coeffs
+        )
+    } else if let Some(g) = gate_any.downcast_ref::<ReducingExtensionGate<2>>() {
+        format!(
+            "reducingConstraints(os, cs, {filter}, {}, 2);",
+            g.num_coeffs
+        )
+    } else if let Some(g) =
+        gate_any.downcast_ref::<HighDegreeInterpolationGate<GoldilocksField, 2>>()
+    {
+        format!(
+            "highDegreeInterpolationConstraints(os, cs, {filter}, {});",
+            g.subgroup_bits
+        )
+    } else if let Some(g) =
+        gate_any.downcast_ref::<LowDegreeInterpolationGate<GoldilocksField, 2>>()
+    {
+        format!(
+            "lowDegreeInterpolationConstraints(os, cs, {filter}, {});",
+            g.subgroup_bits
+        )
+    } else if let Some(g) = gate_any.downcast_ref::<RandomAccessGate<GoldilocksField, 2>>() {
+        format!(
+            "randomAccessConstraints(os, cs, {filter}, {}, {}, {});",
+            g.bits, g.num_copies, g.num_extra_constants
+        )
+    } else if let Some(g) = gate_any.downcast_ref::<ExponentiationGate<GoldilocksField, 2>>() {
+        format!(
+            "exponentiationConstraints(os, cs, {filter}, {});",
+            g.num_power_bits
+        )
+    } else {
+        bail!(
+            "Gate {} is not supported by the Solidity verifier.",
+            gate.0.id()
+        );
+    };
+    Ok(Some(call))
+}
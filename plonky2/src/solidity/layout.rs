@@ -0,0 +1,114 @@
+use plonky2_field::goldilocks_field::GoldilocksField;
+
+use crate::fri::oracle::SALT_SIZE;
+use crate::plonk::circuit_data::CommonCircuitData;
+
+/// Position of every proof component in the flattened proof passed to the generated verifier.
+///
+/// The proof is a sequence of 256-bit words: one word per base field element, one word per
+/// extension element (packed as `c0 + c1 * 2^128`) and one word per Merkle digest (the 25 digest
+/// bytes, left-aligned). Components appear in the following order:
+/// - the wires, Zs/partial products and quotient caps;
+/// - the openings, in the order of `OpeningSet::to_fri_openings`;
+/// - the FRI commit phase caps;
+/// - the query rounds, each holding, for every initial oracle, the leaf followed by its Merkle
+///   siblings, then, for every reduction step, the coset evaluations followed by their siblings;
+/// - the final polynomial coefficients;
+/// - the proof-of-work witness.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ProofLayout {
+    pub cap_len: usize,
+    /// Number of polynomials in each of the four initial oracles, excluding salts.
+    pub oracle_polys: [usize; 4],
+    /// Number of salt elements appended to the leaves of blinded oracles.
+    pub salt_size: usize,
+    pub num_openings_zeta: usize,
+    pub num_openings: usize,
+    /// Number of Merkle siblings in each initial oracle proof.
+    pub initial_siblings: usize,
+    /// Number of Merkle siblings in each reduction step proof.
+    pub step_siblings: Vec<usize>,
+    pub wires_cap: usize,
+    pub zs_partial_products_cap: usize,
+    pub quotient_cap: usize,
+    pub openings: usize,
+    pub commit_phase_caps: usize,
+    pub query_rounds: usize,
+    pub query_round_len: usize,
+    pub final_poly: usize,
+    pub pow_witness: usize,
+    pub len: usize,
+}
+
+impl ProofLayout {
+    pub(crate) fn new(common: &CommonCircuitData<GoldilocksField, 2>) -> Self {
+        let config = &common.config;
+        let fri_params = &common.fri_params;
+        let num_challenges = config.num_challenges;
+
+        let cap_len = 1 << config.fri_config.cap_height;
+        let oracle_polys = [
+            common.num_preprocessed_polys(),
+            config.num_wires,
+            common.num_zs_partial_products_polys(),
+            common.num_quotient_polys(),
+        ];
+        let salt_size = if fri_params.hiding { SALT_SIZE } else { 0 };
+        let num_openings_zeta = oracle_polys.iter().sum::<usize>();
+        let num_openings = num_openings_zeta + num_challenges;
+
+        let lde_bits = fri_params.lde_bits();
+        let cap_height = config.fri_config.cap_height;
+        let initial_siblings = lde_bits - cap_height;
+        let step_siblings = fri_params
+            .reduction_arity_bits
+            .iter()
+            .scan(lde_bits, |bits, &arity_bits| {
+                *bits -= arity_bits;
+                Some(*bits - cap_height)
+            })
+            .collect::<Vec<_>>();
+
+        // All oracles but the constants and sigmas one are salted.
+        let initial_len = oracle_polys.iter().sum::<usize>()
+            + 3 * salt_size
+            + oracle_polys.len() * initial_siblings;
+        let steps_len = fri_params
+            .reduction_arity_bits
+            .iter()
+            .zip(&step_siblings)
+            .map(|(&arity_bits, &siblings)| (1 << arity_bits) + siblings)
+            .sum::<usize>();
+        let query_round_len = initial_len + steps_len;
+
+        let wires_cap = 0;
+        let zs_partial_products_cap = wires_cap + cap_len;
+        let quotient_cap = zs_partial_products_cap + cap_len;
+        let openings = quotient_cap + cap_len;
+        let commit_phase_caps = openings + num_openings;
+        let query_rounds = commit_phase_caps + fri_params.reduction_arity_bits.len() * cap_len;
+        let final_poly = query_rounds + config.fri_config.num_query_rounds * query_round_len;
+        let pow_witness = final_poly + fri_params.final_poly_len();
+        let len = pow_witness + 1;
+
+        Self {
+            cap_len,
+            oracle_polys,
+            salt_size,
+            num_openings_zeta,
+            num_openings,
+            initial_siblings,
+            step_siblings,
+            wires_cap,
+            zs_partial_products_cap,
+            quotient_cap,
+            openings,
+            commit_phase_caps,
+            query_rounds,
+            query_round_len,
+            final_poly,
+            pow_witness,
+            len,
+        }
+    }
+}
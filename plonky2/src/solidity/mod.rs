@@ -0,0 +1,15 @@
+//! Generation of Solidity verifiers for plonky2 proofs.
+//!
+//! Proofs made with `KeccakGoldilocksConfig` use Keccak for their Merkle trees and transcript, which
+//! the EVM computes natively; the public input hash and the FRI proof of work still use Poseidon,
+//! which the generated contract implements together with Goldilocks and quadratic extension
+//! arithmetic. `verifier::generate_solidity_verifier` emits the contract for a given circuit, and
+//! `calldata::verify_calldata` encodes proofs for it.
+//!
+//! The tests which run generated verifiers in the EVM need `solc` and geth's `evm` tool, so they
+//! are ignored by default; run them with `cargo test -- --ignored solidity`.
+
+pub mod calldata;
+mod gates;
+pub(crate) mod layout;
+pub mod verifier;
@@ -0,0 +1,450 @@
+use std::fmt::Write;
+
+use anyhow::{ensure, Result};
+use itertools::Itertools;
+use plonky2_field::goldilocks_field::GoldilocksField;
+use plonky2_field::types::{Field, PrimeField64};
+
+use crate::hash::hash_types::BytesHash;
+use crate::hash::hashing::SPONGE_WIDTH;
+use crate::hash::poseidon::{Poseidon, ALL_ROUND_CONSTANTS, N_ROUNDS};
+use crate::plonk::circuit_data::VerifierCircuitData;
+use crate::plonk::config::KeccakGoldilocksConfig;
+use crate::solidity::gates::gate_constraints_call;
+use crate::solidity::layout::ProofLayout;
+
+type F = GoldilocksField;
+
+/// The circuit-independent part of the verifier.
+const VERIFIER_BODY: &str = include_str!("verifier.sol");
+
+/// Name of the generated contract.
+pub const CONTRACT_NAME: &str = "Plonky2Verifier";
+
+fn hex_u64s(values: impl IntoIterator<Item = u64>) -> String {
+    values.into_iter().map(|v| format!("{v:016x}")).join("")
+}
+
+fn hex_digest(h: &BytesHash<25>) -> String {
+    h.0.iter().map(|b| format!("{b:02x}")).join("")
+}
+
+/// Generates a self-contained Solidity contract which verifies proofs of the circuit described by
+/// `data`. The contract exposes `verify(uint256[] publicInputs, uint256[] proof)`, whose calldata
+/// can be built with `solidity::calldata::verify_calldata`.
+///
+/// The gates used by `CircuitConfig::standard_recursion_config` circuits, including the ones which
+/// verify Poseidon proofs recursively, are supported (see `gate_constraints_call`); an error is
+/// returned for other gates, and for circuits using lookups. The contract should be compiled with
+/// `solc --via-ir`.
+pub fn generate_solidity_verifier(
+    data: &VerifierCircuitData<F, KeccakGoldilocksConfig, 2>,
+) -> Result<String> {
+    let common = &data.common;
+    let config = &common.config;
+    let fri_params = &common.fri_params;
+    ensure!(
+        common.num_lookup_polys() == 0,
+        "Lookups are not supported by the Solidity verifier."
+    );
+    let layout = ProofLayout::new(common);
+
+    let mut gate_calls = String::new();
+    for (i, gate) in common.gates.iter().enumerate() {
+        let selector_index = common.selectors_info.selector_indices[i];
+        let group = &common.selectors_info.groups[selector_index];
+        let filter = format!(
+            "selectorFilter(os, {i}, {selector_index}, {}, {})",
+            group.start, group.end
+        );
+        if let Some(call) = gate_constraints_call(gate, &filter)? {
+            writeln!(gate_calls, "        // {}", gate.0.id())?;
+            writeln!(gate_calls, "        {call}")?;
+        }
+    }
+
+    let degree_bits = common.degree_bits();
+    let lde_bits = fri_params.lde_bits();
+    let num_challenges = config.num_challenges;
+    let os_sigmas = common.num_constants;
+    let os_wires = os_sigmas + config.num_routed_wires;
+    let os_zs = os_wires + config.num_wires;
+    let os_partial_products = os_zs + num_challenges;
+    let os_quotient = os_partial_products + num_challenges * common.num_partial_products;
+
+    let integers = [
+        // Circuit parameters.
+        ("DEGREE_BITS", degree_bits),
+        ("NUM_CHALLENGES", num_challenges),
+        ("NUM_ROUTED_WIRES", config.num_routed_wires),
+        ("NUM_SELECTORS", common.selectors_info.num_selectors()),
+        ("QUOTIENT_DEGREE_FACTOR", common.quotient_degree_factor),
+        ("NUM_PARTIAL_PRODUCTS", common.num_partial_products),
+        ("NUM_GATE_CONSTRAINTS", common.num_gate_constraints),
+        ("NUM_PUBLIC_INPUTS", common.num_public_inputs),
+        ("TWO_ADICITY", F::TWO_ADICITY),
+        // FRI parameters.
+        ("LDE_BITS", lde_bits),
+        ("CAP_HEIGHT", config.fri_config.cap_height),
+        ("CAP_LEN", layout.cap_len),
+        ("NUM_QUERY_ROUNDS", config.fri_config.num_query_rounds),
+        ("POW_BITS", config.fri_config.proof_of_work_bits as usize),
+        ("NUM_REDUCTIONS", fri_params.reduction_arity_bits.len()),
+        ("FINAL_POLY_LEN", fri_params.final_poly_len()),
+        ("SALT_SIZE", layout.salt_size),
+        ("ORACLE_POLYS_0", layout.oracle_polys[0]),
+        ("ORACLE_POLYS_1", layout.oracle_polys[1]),
+        ("ORACLE_POLYS_2", layout.oracle_polys[2]),
+        ("ORACLE_POLYS_3", layout.oracle_polys[3]),
+        // Indices of the openings.
+        ("OS_CONSTANTS", 0),
+        ("OS_SIGMAS", os_sigmas),
+        ("OS_WIRES", os_wires),
+        ("OS_ZS", os_zs),
+        ("OS_PARTIAL_PRODUCTS", os_partial_products),
+        ("OS_QUOTIENT", os_quotient),
+        ("OS_ZS_NEXT", layout.num_openings_zeta),
+        ("NUM_OPENINGS_ZETA", layout.num_openings_zeta),
+        ("NUM_OPENINGS", layout.num_openings),
+        // Proof layout.
+        ("OFF_WIRES_CAP", layout.wires_cap),
+        (
+            "OFF_ZS_PARTIAL_PRODUCTS_CAP",
+            layout.zs_partial_products_cap,
+        ),
+        ("OFF_QUOTIENT_CAP", layout.quotient_cap),
+        ("OFF_OPENINGS", layout.openings),
+        ("OFF_COMMIT_PHASE_CAPS", layout.commit_phase_caps),
+        ("OFF_QUERY_ROUNDS", layout.query_rounds),
+        ("QUERY_ROUND_LEN", layout.query_round_len),
+        ("OFF_FINAL_POLY", layout.final_poly),
+        ("OFF_POW_WITNESS", layout.pow_witness),
+        ("PROOF_LEN", layout.len),
+    ];
+    let field_elements = [
+        ("TWO_ADIC_GENERATOR", F::POWER_OF_TWO_GENERATOR),
+        ("COSET_SHIFT", F::coset_shift()),
+        ("DEGREE_GENERATOR", F::primitive_root_of_unity(degree_bits)),
+        ("LDE_GENERATOR", F::primitive_root_of_unity(lde_bits)),
+    ];
+    let mds = <F as Poseidon>::MDS_MATRIX_CIRC
+        .into_iter()
+        .chain(<F as Poseidon>::MDS_MATRIX_DIAG);
+    let byte_strings = [
+        (
+            "K_IS",
+            hex_u64s(common.k_is.iter().map(|k| k.to_canonical_u64())),
+        ),
+        (
+            "REDUCTION_ARITY_BITS",
+            fri_params
+                .reduction_arity_bits
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .join(""),
+        ),
+        (
+            "POSEIDON_ROUND_CONSTANTS",
+            hex_u64s(
+                ALL_ROUND_CONSTANTS[..SPONGE_WIDTH * N_ROUNDS]
+                    .iter()
+                    .copied(),
+            ),
+        ),
+        ("POSEIDON_MDS", hex_u64s(mds)),
+        (
+            "CONSTANTS_SIGMAS_CAP",
+            data.verifier_only
+                .constants_sigmas_cap
+                .0
+                .iter()
+                .map(hex_digest)
+                .join(""),
+        ),
+    ];
+
+    let mut out = String::new();
+    writeln!(out, "// SPDX-License-Identifier: MIT OR Apache-2.0")?;
+    writeln!(out, "pragma solidity ^0.8.17;")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// Verifier for plonky2 proofs of a single circuit, generated by `plonky2::solidity`."
+    )?;
+    writeln!(out, "contract {CONTRACT_NAME} {{")?;
+    for (name, value) in integers {
+        writeln!(out, "    uint256 constant {name} = {value};")?;
+    }
+    for (name, value) in field_elements {
+        writeln!(
+            out,
+            "    uint256 constant {name} = {};",
+            value.to_canonical_u64()
+        )?;
+    }
+    for (name, value) in byte_strings {
+        writeln!(out, "    bytes constant {name} = hex\"{value}\";")?;
+    }
+    writeln!(
+        out,
+        "    bytes25 constant CIRCUIT_DIGEST = 0x{};",
+        hex_digest(&data.verifier_only.circuit_digest)
+    )?;
+    writeln!(out)?;
+
+    writeln!(
+        out,
+        "    function evalGateConstraints(uint256[] memory os, uint256[4] memory piHash, uint256[] memory pc)"
+    )?;
+    writeln!(out, "        internal")?;
+    writeln!(out, "        pure")?;
+    writeln!(out, "        returns (uint256[] memory cs)")?;
+    writeln!(out, "    {{")?;
+    writeln!(out, "        cs = new uint256[](NUM_GATE_CONSTRAINTS);")?;
+    out.push_str(&gate_calls);
+    writeln!(out, "    }}")?;
+    writeln!(out)?;
+
+    out.push_str(VERIFIER_BODY);
+    writeln!(out, "}}")?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::process::Command;
+    use std::sync::Arc;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::gates::high_degree_interpolation::HighDegreeInterpolationGate;
+    use crate::gates::low_degree_interpolation::LowDegreeInterpolationGate;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::proof::ProofWithPublicInputs;
+    use crate::solidity::calldata::{proof_words, verify_calldata};
+
+    const D: usize = 2;
+    type C = KeccakGoldilocksConfig;
+    type FE = <C as GenericConfig<D>>::FE;
+
+    fn test_circuit() -> Result<(VerifierCircuitData<F, C, D>, ProofWithPublicInputs<F, C, D>)> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.mul(x, x);
+        let z = builder.add_const(y, F::from_canonical_u64(3));
+        builder.range_check(z, 32);
+        builder.register_public_input(x);
+        builder.register_public_input(z);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(5));
+        let proof = data.prove(pw)?;
+        let data = data.verifier_data();
+        data.verify(proof.clone())?;
+        Ok((data, proof))
+    }
+
+    #[test]
+    fn test_generate_solidity_verifier() -> Result<()> {
+        let (data, proof) = test_circuit()?;
+        let layout = ProofLayout::new(&data.common);
+        assert_eq!(proof_words(&proof, &data.common)?.len(), layout.len);
+
+        let calldata = verify_calldata(&proof, &data.common)?;
+        let num_words = 2 + (1 + proof.public_inputs.len()) + (1 + layout.len);
+        assert_eq!(calldata.len(), 4 + 32 * num_words);
+
+        let source = generate_solidity_verifier(&data)?;
+        assert!(source.contains(&format!("contract {CONTRACT_NAME} {{")));
+        assert!(source.contains(&format!("uint256 constant PROOF_LEN = {};", layout.len)));
+        assert!(source.contains(&format!(
+            "bytes25 constant CIRCUIT_DIGEST = 0x{};",
+            hex_digest(&data.verifier_only.circuit_digest)
+        )));
+        for call in [
+            "arithmeticConstraints",
+            "publicInputConstraints",
+            "poseidonConstraints",
+        ] {
+            assert!(
+                source.contains(&format!("        {call}(")),
+                "missing {call}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Proves a circuit which verifies a `PoseidonGoldilocksConfig` proof, so that it uses the
+    /// gates of the recursive verifier. It also uses the exponentiation and interpolation gates,
+    /// which recursive verifiers need with other configs.
+    fn recursive_test_circuit(
+    ) -> Result<(VerifierCircuitData<F, C, D>, ProofWithPublicInputs<F, C, D>)> {
+        type InnerC = PoseidonGoldilocksConfig;
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let y = builder.mul_const_add(F::from_canonical_u64(7), x, x);
+        builder.register_public_input(y);
+        let inner_data = builder.build::<InnerC>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(5));
+        let inner_proof = inner_data.prove(pw)?;
+
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let pt = builder.add_virtual_proof_with_pis::<InnerC>(&inner_data.common);
+        let inner_vd = builder.constant_verifier_data(&inner_data.verifier_only);
+        builder.verify_proof::<InnerC>(pt.clone(), &inner_vd, &inner_data.common);
+        builder.register_public_inputs(&pt.public_inputs);
+
+        let y = pt.public_inputs[0];
+        let y_pow = builder.exp(y, y, 8);
+        builder.register_public_input(y_pow);
+        let shift = builder.constant(F::MULTIPLICATIVE_GROUP_GENERATOR);
+        let values = (0..4)
+            .map(|i| builder.constant_extension(FE::from_canonical_u64(i)))
+            .collect::<Vec<_>>();
+        let point = builder.convert_to_ext(y);
+        let high = builder
+            .interpolate_coset::<HighDegreeInterpolationGate<F, D>>(2, shift, &values, point);
+        let low =
+            builder.interpolate_coset::<LowDegreeInterpolationGate<F, D>>(2, shift, &values, point);
+        builder.connect_extension(high, low);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&pt, &inner_proof);
+        let proof = data.prove(pw)?;
+        let data = data.verifier_data();
+        data.verify(proof.clone())?;
+        Ok((data, proof))
+    }
+
+    #[test]
+    fn test_unsupported_lookups() {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let table = builder.add_lookup_table(Arc::new(vec![(0, 1), (1, 2)]));
+        let x = builder.add_virtual_target();
+        let y = builder.lookup(table, x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let err = generate_solidity_verifier(&data.verifier_data()).unwrap_err();
+        assert!(err.to_string().contains("not supported"));
+    }
+
+    /// Whether `solc` and geth's `evm`, which the EVM tests need, are in the `PATH`.
+    fn evm_tools_available() -> bool {
+        ["solc", "evm"]
+            .iter()
+            .all(|tool| Command::new(tool).arg("--version").output().is_ok())
+    }
+
+    /// Compiles `source` with `solc` and calls it with `calldata` using geth's `evm` tool. Returns
+    /// whether the call returned `true`.
+    fn run_in_evm(source: &str, calldata: &[u8], dir: &Path) -> Result<bool> {
+        let sol_path = dir.join(format!("{CONTRACT_NAME}.sol"));
+        std::fs::write(&sol_path, source)?;
+        let status = Command::new("solc")
+            .args([
+                "--via-ir",
+                "--optimize",
+                "--bin-runtime",
+                "--overwrite",
+                "-o",
+            ])
+            .arg(dir)
+            .arg(&sol_path)
+            .status()?;
+        ensure!(status.success(), "solc failed");
+
+        let input = calldata.iter().map(|b| format!("{b:02x}")).join("");
+        let output = Command::new("evm")
+            .arg("--codefile")
+            .arg(dir.join(format!("{CONTRACT_NAME}.bin-runtime")))
+            .args(["--gas", "10000000000", "--input", &input, "run"])
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        let stderr = String::from_utf8(output.stderr)?;
+        let returned_true = stdout.contains(&format!("{:064x}", 1));
+        Ok(returned_true && !stderr.contains("error"))
+    }
+
+    /// Generates the verifier of `data`, and checks in the EVM that it accepts `proof` and rejects
+    /// it once a public input or an opening is tampered with. Fails if `solc` or `evm` is missing.
+    fn check_solidity_verifier(
+        name: &str,
+        data: &VerifierCircuitData<F, C, D>,
+        proof: ProofWithPublicInputs<F, C, D>,
+    ) -> Result<()> {
+        ensure!(
+            evm_tools_available(),
+            "The EVM tests need solc and geth's evm tool in the PATH"
+        );
+        let source = generate_solidity_verifier(data)?;
+        let dir =
+            std::env::temp_dir().join(format!("plonky2_solidity_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let calldata = verify_calldata(&proof, &data.common)?;
+        assert!(run_in_evm(&source, &calldata, &dir)?);
+
+        let mut bad_pis = proof.clone();
+        bad_pis.public_inputs[0] += F::ONE;
+        assert!(data.verify(bad_pis.clone()).is_err());
+        let calldata = verify_calldata(&bad_pis, &data.common)?;
+        assert!(!run_in_evm(&source, &calldata, &dir)?);
+
+        let mut bad_opening = proof;
+        bad_opening.proof.openings.wires[0] += FE::ONE;
+        assert!(data.verify(bad_opening.clone()).is_err());
+        let calldata = verify_calldata(&bad_opening, &data.common)?;
+        assert!(!run_in_evm(&source, &calldata, &dir)?);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs solc and geth's evm tool"]
+    fn test_solidity_verifier_in_evm() -> Result<()> {
+        let (data, proof) = test_circuit()?;
+        check_solidity_verifier("simple", &data, proof)
+    }
+
+    #[test]
+    fn test_generate_recursive_solidity_verifier() -> Result<()> {
+        let (data, _proof) = recursive_test_circuit()?;
+        let source = generate_solidity_verifier(&data)?;
+        for call in [
+            "arithmeticExtensionConstraints",
+            "mulExtensionConstraints",
+            "reducingConstraints",
+            "highDegreeInterpolationConstraints",
+            "lowDegreeInterpolationConstraints",
+            "randomAccessConstraints",
+            "exponentiationConstraints",
+            "poseidonMdsConstraints",
+        ] {
+            assert!(
+                source.contains(&format!("        {call}(")),
+                "missing {call}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs solc and geth's evm tool"]
+    fn test_solidity_verifier_recursive_in_evm() -> Result<()> {
+        let (data, proof) = recursive_test_circuit()?;
+        check_solidity_verifier("recursive", &data, proof)
+    }
+}
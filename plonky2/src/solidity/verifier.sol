@@ -0,0 +1,1291 @@
+    // This file is spliced into the body of the generated contract, after the circuit-specific
+    // constants and `evalGateConstraints`. See `plonky2::solidity::generate_solidity_verifier`.
+
+    // ---------------------------------------------------------------------------------------------
+    // Entry point.
+    // ---------------------------------------------------------------------------------------------
+
+    /// Verifies a proof, flattened as described in `plonky2::solidity::ProofLayout`. Reverts with
+    /// a reason if the proof is invalid, and returns `true` otherwise.
+    function verify(uint256[] calldata publicInputs, uint256[] calldata proof)
+        external
+        pure
+        returns (bool)
+    {
+        require(publicInputs.length == NUM_PUBLIC_INPUTS, "wrong number of public inputs");
+        require(proof.length == PROOF_LEN, "wrong proof length");
+
+        uint256[] memory pc = loadPoseidonConstants();
+        uint256[] memory pis = new uint256[](NUM_PUBLIC_INPUTS);
+        for (uint256 i = 0; i < NUM_PUBLIC_INPUTS; i++) {
+            pis[i] = checkBase(publicInputs[i]);
+        }
+        uint256[4] memory piHash = poseidonHashNoPad(pis, pc);
+
+        uint256[] memory os = new uint256[](NUM_OPENINGS);
+        for (uint256 i = 0; i < NUM_OPENINGS; i++) {
+            os[i] = checkExt(proof[OFF_OPENINGS + i]);
+        }
+
+        Challenges memory ch = computeChallenges(proof, piHash, os, pc);
+        verifyVanishingIdentity(os, piHash, ch, pc);
+        verifyFri(proof, os, ch);
+        return true;
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // Goldilocks field and its quadratic extension.
+    //
+    // Base field elements are words in `[0, P)`. An extension element `c0 + c1 * X`, where
+    // `X^2 = W`, is packed into a single word as `c0 | (c1 << 128)`, so that base field elements
+    // are also valid extension elements.
+    // ---------------------------------------------------------------------------------------------
+
+    uint256 constant P = 0xFFFFFFFF00000001;
+    uint256 constant W = 7;
+    uint256 constant LO = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF;
+    uint256 constant MASK64 = 0xFFFFFFFFFFFFFFFF;
+
+    function checkBase(uint256 x) internal pure returns (uint256) {
+        require(x < P, "non-canonical field element");
+        return x;
+    }
+
+    function checkExt(uint256 x) internal pure returns (uint256) {
+        require((x & LO) < P && (x >> 128) < P, "non-canonical extension element");
+        return x;
+    }
+
+    function fPow(uint256 a, uint256 e) internal pure returns (uint256 r) {
+        r = 1;
+        while (e != 0) {
+            if (e & 1 == 1) {
+                r = mulmod(r, a, P);
+            }
+            a = mulmod(a, a, P);
+            e >>= 1;
+        }
+    }
+
+    /// Computes `a^(2^k)`.
+    function fExpPowerOf2(uint256 a, uint256 k) internal pure returns (uint256) {
+        for (uint256 i = 0; i < k; i++) {
+            a = mulmod(a, a, P);
+        }
+        return a;
+    }
+
+    function fInv(uint256 a) internal pure returns (uint256) {
+        require(a != 0, "inverse of zero");
+        return fPow(a, P - 2);
+    }
+
+    function ext(uint256 c0, uint256 c1) internal pure returns (uint256) {
+        return c0 | (c1 << 128);
+    }
+
+    function eAdd(uint256 a, uint256 b) internal pure returns (uint256) {
+        return ext(addmod(a & LO, b & LO, P), addmod(a >> 128, b >> 128, P));
+    }
+
+    function eSub(uint256 a, uint256 b) internal pure returns (uint256) {
+        return ext(addmod(a & LO, P - (b & LO), P), addmod(a >> 128, P - (b >> 128), P));
+    }
+
+    function eMul(uint256 a, uint256 b) internal pure returns (uint256) {
+        uint256 a0 = a & LO;
+        uint256 a1 = a >> 128;
+        uint256 b0 = b & LO;
+        uint256 b1 = b >> 128;
+        return ext(
+            addmod(mulmod(a0, b0, P), mulmod(W, mulmod(a1, b1, P), P), P),
+            addmod(mulmod(a0, b1, P), mulmod(a1, b0, P), P)
+        );
+    }
+
+    /// Multiplies an extension element by a base field element.
+    function eScale(uint256 a, uint256 s) internal pure returns (uint256) {
+        return ext(mulmod(a & LO, s, P), mulmod(a >> 128, s, P));
+    }
+
+    /// Computes `a^(2^k)`.
+    function eExpPowerOf2(uint256 a, uint256 k) internal pure returns (uint256) {
+        for (uint256 i = 0; i < k; i++) {
+            a = eMul(a, a);
+        }
+        return a;
+    }
+
+    function eInv(uint256 a) internal pure returns (uint256) {
+        uint256 a0 = a & LO;
+        uint256 a1 = a >> 128;
+        // (a0 + a1 X)^-1 = (a0 - a1 X) / (a0^2 - W a1^2).
+        uint256 normInv =
+            fInv(addmod(mulmod(a0, a0, P), P - mulmod(W, mulmod(a1, a1, P), P), P));
+        return ext(mulmod(a0, normInv, P), mulmod(P - a1, normInv, P));
+    }
+
+    function eDiv(uint256 a, uint256 b) internal pure returns (uint256) {
+        return eMul(a, eInv(b));
+    }
+
+    /// Computes `sum_i v[start + i] alpha^i` over `start <= start + i < end`.
+    function reduceWithPowers(uint256[] memory v, uint256 start, uint256 end, uint256 alpha)
+        internal
+        pure
+        returns (uint256 acc)
+    {
+        for (uint256 i = end; i > start; i--) {
+            acc = eAdd(eMul(acc, alpha), v[i - 1]);
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // Byte manipulation.
+    // ---------------------------------------------------------------------------------------------
+
+    /// Reverses the byte order of a 64-bit word.
+    function reverseBytes64(uint256 x) internal pure returns (uint256) {
+        x = ((x & 0xFF00FF00FF00FF00) >> 8) | ((x & 0x00FF00FF00FF00FF) << 8);
+        x = ((x & 0xFFFF0000FFFF0000) >> 16) | ((x & 0x0000FFFF0000FFFF) << 16);
+        return (x >> 32) | ((x & 0xFFFFFFFF) << 32);
+    }
+
+    function reverseBits(uint256 x, uint256 bits) internal pure returns (uint256 r) {
+        for (uint256 i = 0; i < bits; i++) {
+            r = (r << 1) | ((x >> i) & 1);
+        }
+    }
+
+    /// Reads the `i`th big-endian 64-bit word of `b`.
+    function readU64(bytes memory b, uint256 i) internal pure returns (uint256 v) {
+        assembly ("memory-safe") {
+            v := shr(192, mload(add(add(b, 32), mul(i, 8))))
+        }
+    }
+
+    /// Reads the `i`th 25-byte digest of `b`.
+    function readDigest(bytes memory b, uint256 i) internal pure returns (bytes25 h) {
+        assembly ("memory-safe") {
+            h := and(mload(add(add(b, 32), mul(i, 25))), not(0xFFFFFFFFFFFFFF))
+        }
+    }
+
+    /// Reads a Merkle digest from the proof.
+    function proofDigest(uint256[] calldata proof, uint256 i) internal pure returns (bytes25) {
+        uint256 w = proof[i];
+        require(w & 0xFFFFFFFFFFFFFF == 0, "non-canonical digest");
+        return bytes25(bytes32(w));
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // Keccak hashing, mirroring `KeccakHash<25>` and `KeccakPermutation`.
+    // ---------------------------------------------------------------------------------------------
+
+    /// Hashes field elements into a leaf digest. Like `Hasher::hash_or_noop`, short inputs are used
+    /// as the digest directly.
+    function hashOrNoop(uint256[] memory v) internal pure returns (bytes25) {
+        uint256 n = v.length;
+        if (n <= 4) {
+            uint256 acc = 0;
+            for (uint256 i = 0; i < n; i++) {
+                acc |= reverseBytes64(v[i]) << (192 - 64 * i);
+            }
+            return bytes25(bytes32(acc));
+        }
+        // Leave room for the last 32-byte store.
+        bytes memory buf = new bytes(8 * n + 24);
+        for (uint256 i = 0; i < n; i++) {
+            uint256 w = reverseBytes64(v[i]) << 192;
+            assembly ("memory-safe") {
+                mstore(add(add(buf, 32), mul(i, 8)), w)
+            }
+        }
+        bytes32 h;
+        assembly ("memory-safe") {
+            h := keccak256(add(buf, 32), mul(n, 8))
+        }
+        return bytes25(h);
+    }
+
+    function twoToOne(bytes25 left, bytes25 right) internal pure returns (bytes25) {
+        return bytes25(keccak256(abi.encodePacked(left, right)));
+    }
+
+    /// Applies `KeccakPermutation`: the state is hashed into a stream of 64-bit words, from which
+    /// the new state is rejection sampled.
+    function keccakPermute(uint256[12] memory s) internal pure {
+        bytes32 h = keccak256(
+            abi.encodePacked(
+                packLe(s[0], s[1], s[2], s[3]),
+                packLe(s[4], s[5], s[6], s[7]),
+                packLe(s[8], s[9], s[10], s[11])
+            )
+        );
+        uint256 n = 0;
+        while (true) {
+            for (uint256 j = 0; j < 4; j++) {
+                uint256 v = reverseBytes64((uint256(h) >> (192 - 64 * j)) & MASK64);
+                if (v < P) {
+                    s[n] = v;
+                    n++;
+                    if (n == 12) {
+                        return;
+                    }
+                }
+            }
+            h = keccak256(abi.encodePacked(h));
+        }
+    }
+
+    /// Packs four field elements into a word holding their little-endian encodings.
+    function packLe(uint256 a, uint256 b, uint256 c, uint256 d) internal pure returns (uint256) {
+        return (reverseBytes64(a) << 192) | (reverseBytes64(b) << 128) | (reverseBytes64(c) << 64)
+            | reverseBytes64(d);
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // Fiat-Shamir challenger, mirroring `iop::challenger::Challenger`.
+    //
+    // Inputs overwrite the first `SPONGE_RATE` state elements. Since buffered outputs are discarded
+    // by every observation, inputs are written to the state directly.
+    // ---------------------------------------------------------------------------------------------
+
+    uint256 constant SPONGE_RATE = 8;
+
+    struct Challenger {
+        uint256[12] state;
+        uint256 numInputs;
+        uint256 numOutputs;
+    }
+
+    function duplexing(Challenger memory c) internal pure {
+        keccakPermute(c.state);
+        c.numInputs = 0;
+        c.numOutputs = SPONGE_RATE;
+    }
+
+    function observeElement(Challenger memory c, uint256 x) internal pure {
+        c.numOutputs = 0;
+        c.state[c.numInputs] = x;
+        c.numInputs++;
+        if (c.numInputs == SPONGE_RATE) {
+            duplexing(c);
+        }
+    }
+
+    function observeExtensionElement(Challenger memory c, uint256 x) internal pure {
+        observeElement(c, x & LO);
+        observeElement(c, x >> 128);
+    }
+
+    /// Observes a digest as field elements, using little-endian chunks of 7 bytes like
+    /// `BytesHash::to_vec`.
+    function observeDigest(Challenger memory c, bytes25 h) internal pure {
+        uint256 w = uint256(bytes32(h));
+        for (uint256 i = 0; i < 3; i++) {
+            observeElement(c, reverseBytes64(((w >> (200 - 56 * i)) & 0xFFFFFFFFFFFFFF) << 8));
+        }
+        observeElement(c, reverseBytes64(((w >> 56) & 0xFFFFFFFF) << 32));
+    }
+
+    function observeCap(Challenger memory c, uint256[] calldata proof, uint256 offset)
+        internal
+        pure
+    {
+        for (uint256 i = 0; i < CAP_LEN; i++) {
+            observeDigest(c, proofDigest(proof, offset + i));
+        }
+    }
+
+    function getChallenge(Challenger memory c) internal pure returns (uint256) {
+        if (c.numInputs != 0 || c.numOutputs == 0) {
+            duplexing(c);
+        }
+        c.numOutputs--;
+        return c.state[c.numOutputs];
+    }
+
+    function getNChallenges(Challenger memory c, uint256 n)
+        internal
+        pure
+        returns (uint256[] memory v)
+    {
+        v = new uint256[](n);
+        for (uint256 i = 0; i < n; i++) {
+            v[i] = getChallenge(c);
+        }
+    }
+
+    function getExtensionChallenge(Challenger memory c) internal pure returns (uint256) {
+        uint256 c0 = getChallenge(c);
+        uint256 c1 = getChallenge(c);
+        return ext(c0, c1);
+    }
+
+    struct Challenges {
+        uint256[] betas;
+        uint256[] gammas;
+        uint256[] alphas;
+        uint256 zeta;
+        uint256 friAlpha;
+        uint256[] friBetas;
+        uint256[] queryIndices;
+    }
+
+    /// Replays the transcript of `get_challenges`, and checks the proof of work on the way.
+    function computeChallenges(
+        uint256[] calldata proof,
+        uint256[4] memory piHash,
+        uint256[] memory os,
+        uint256[] memory pc
+    ) internal pure returns (Challenges memory ch) {
+        uint256[12] memory state;
+        Challenger memory c = Challenger(state, 0, 0);
+
+        observeDigest(c, CIRCUIT_DIGEST);
+        for (uint256 i = 0; i < 4; i++) {
+            observeElement(c, piHash[i]);
+        }
+
+        observeCap(c, proof, OFF_WIRES_CAP);
+        ch.betas = getNChallenges(c, NUM_CHALLENGES);
+        ch.gammas = getNChallenges(c, NUM_CHALLENGES);
+
+        observeCap(c, proof, OFF_ZS_PARTIAL_PRODUCTS_CAP);
+        ch.alphas = getNChallenges(c, NUM_CHALLENGES);
+
+        observeCap(c, proof, OFF_QUOTIENT_CAP);
+        ch.zeta = getExtensionChallenge(c);
+
+        for (uint256 i = 0; i < NUM_OPENINGS; i++) {
+            observeExtensionElement(c, os[i]);
+        }
+
+        ch.friAlpha = getExtensionChallenge(c);
+        ch.friBetas = new uint256[](NUM_REDUCTIONS);
+        for (uint256 i = 0; i < NUM_REDUCTIONS; i++) {
+            observeCap(c, proof, OFF_COMMIT_PHASE_CAPS + i * CAP_LEN);
+            ch.friBetas[i] = getExtensionChallenge(c);
+        }
+
+        for (uint256 i = 0; i < FINAL_POLY_LEN; i++) {
+            observeExtensionElement(c, checkExt(proof[OFF_FINAL_POLY + i]));
+        }
+
+        uint256[] memory powInputs = new uint256[](5);
+        for (uint256 i = 0; i < 4; i++) {
+            powInputs[i] = getChallenge(c);
+        }
+        powInputs[4] = checkBase(proof[OFF_POW_WITNESS]);
+        uint256 powResponse = poseidonHashNoPad(powInputs, pc)[0];
+        require(powResponse >> (64 - POW_BITS) == 0, "invalid proof of work witness");
+
+        ch.queryIndices = new uint256[](NUM_QUERY_ROUNDS);
+        for (uint256 i = 0; i < NUM_QUERY_ROUNDS; i++) {
+            ch.queryIndices[i] = getChallenge(c) % (1 << LDE_BITS);
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // Poseidon over Goldilocks, evaluated with plain (non-optimized) partial rounds.
+    //
+    // The state holds extension elements so the same rounds serve both hashing and the evaluation
+    // of `PoseidonGate` constraints at `zeta`. `pc` holds the round constants followed by the
+    // circulant and diagonal parts of the MDS matrix.
+    // ---------------------------------------------------------------------------------------------
+
+    uint256 constant POSEIDON_WIDTH = 12;
+    uint256 constant POSEIDON_HALF_N_FULL_ROUNDS = 4;
+    uint256 constant POSEIDON_N_PARTIAL_ROUNDS = 22;
+    uint256 constant POSEIDON_N_ROUNDS = 30;
+    uint256 constant PC_MDS_CIRC = POSEIDON_WIDTH * POSEIDON_N_ROUNDS;
+    uint256 constant PC_MDS_DIAG = PC_MDS_CIRC + POSEIDON_WIDTH;
+
+    function loadPoseidonConstants() internal pure returns (uint256[] memory pc) {
+        bytes memory roundConstants = POSEIDON_ROUND_CONSTANTS;
+        bytes memory mds = POSEIDON_MDS;
+        pc = new uint256[](PC_MDS_DIAG + POSEIDON_WIDTH);
+        for (uint256 i = 0; i < PC_MDS_CIRC; i++) {
+            pc[i] = readU64(roundConstants, i);
+        }
+        for (uint256 i = 0; i < 2 * POSEIDON_WIDTH; i++) {
+            pc[PC_MDS_CIRC + i] = readU64(mds, i);
+        }
+    }
+
+    function sboxMonomial(uint256 x) internal pure returns (uint256) {
+        uint256 x2 = eMul(x, x);
+        uint256 x4 = eMul(x2, x2);
+        uint256 x3 = eMul(x, x2);
+        return eMul(x3, x4);
+    }
+
+    function poseidonConstantLayer(uint256[12] memory s, uint256[] memory pc, uint256 round)
+        internal
+        pure
+    {
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+            uint256 v = s[i];
+            s[i] = ext(addmod(v & LO, pc[POSEIDON_WIDTH * round + i], P), v >> 128);
+        }
+    }
+
+    function poseidonSboxLayer(uint256[12] memory s) internal pure {
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+            s[i] = sboxMonomial(s[i]);
+        }
+    }
+
+    function poseidonMdsLayer(uint256[12] memory s, uint256[] memory pc) internal pure {
+        uint256[12] memory t;
+        for (uint256 r = 0; r < POSEIDON_WIDTH; r++) {
+            // The MDS coefficients are small, so both packed components can be accumulated
+            // without overflowing into each other, and reduced once at the end.
+            uint256 acc = s[r] * pc[PC_MDS_DIAG + r];
+            for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+                acc += s[(i + r) % POSEIDON_WIDTH] * pc[PC_MDS_CIRC + i];
+            }
+            t[r] = ext((acc & LO) % P, (acc >> 128) % P);
+        }
+        for (uint256 r = 0; r < POSEIDON_WIDTH; r++) {
+            s[r] = t[r];
+        }
+    }
+
+    function poseidonPermute(uint256[12] memory s, uint256[] memory pc) internal pure {
+        uint256 round = 0;
+        for (uint256 r = 0; r < POSEIDON_HALF_N_FULL_ROUNDS; r++) {
+            poseidonConstantLayer(s, pc, round++);
+            poseidonSboxLayer(s);
+            poseidonMdsLayer(s, pc);
+        }
+        for (uint256 r = 0; r < POSEIDON_N_PARTIAL_ROUNDS; r++) {
+            poseidonConstantLayer(s, pc, round++);
+            s[0] = sboxMonomial(s[0]);
+            poseidonMdsLayer(s, pc);
+        }
+        for (uint256 r = 0; r < POSEIDON_HALF_N_FULL_ROUNDS; r++) {
+            poseidonConstantLayer(s, pc, round++);
+            poseidonSboxLayer(s);
+            poseidonMdsLayer(s, pc);
+        }
+    }
+
+    /// Mirrors `PoseidonHash::hash_no_pad`.
+    function poseidonHashNoPad(uint256[] memory inputs, uint256[] memory pc)
+        internal
+        pure
+        returns (uint256[4] memory out)
+    {
+        uint256[12] memory s;
+        for (uint256 start = 0; start < inputs.length; start += SPONGE_RATE) {
+            for (uint256 i = start; i < inputs.length && i < start + SPONGE_RATE; i++) {
+                s[i - start] = inputs[i];
+            }
+            poseidonPermute(s, pc);
+        }
+        for (uint256 i = 0; i < 4; i++) {
+            out[i] = s[i];
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // Gate constraints. Each gate adds its constraints, multiplied by its selector filter, to the
+    // accumulator `cs`, starting from index 0.
+    // ---------------------------------------------------------------------------------------------
+
+    uint256 constant UNUSED_SELECTOR = 0xFFFFFFFF;
+
+    /// Mirrors `compute_filter` for the gate at `row` of the selector group `[start, end)`.
+    function selectorFilter(
+        uint256[] memory os,
+        uint256 row,
+        uint256 selectorIndex,
+        uint256 start,
+        uint256 end
+    ) internal pure returns (uint256 filter) {
+        uint256 s = os[OS_CONSTANTS + selectorIndex];
+        filter = 1;
+        for (uint256 i = start; i < end; i++) {
+            if (i != row) {
+                filter = eMul(filter, eSub(i, s));
+            }
+        }
+        if (NUM_SELECTORS > 1) {
+            filter = eMul(filter, eSub(UNUSED_SELECTOR, s));
+        }
+    }
+
+    function wire(uint256[] memory os, uint256 i) internal pure returns (uint256) {
+        return os[OS_WIRES + i];
+    }
+
+    function gateConstant(uint256[] memory os, uint256 i) internal pure returns (uint256) {
+        return os[OS_CONSTANTS + NUM_SELECTORS + i];
+    }
+
+    function addConstraint(uint256[] memory cs, uint256 i, uint256 filter, uint256 c)
+        internal
+        pure
+        returns (uint256)
+    {
+        cs[i] = eAdd(cs[i], eMul(filter, c));
+        return i + 1;
+    }
+
+    /// Mirrors `ArithmeticGate::eval_unfiltered`.
+    function arithmeticConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 numOps
+    ) internal pure {
+        uint256 c0 = gateConstant(os, 0);
+        uint256 c1 = gateConstant(os, 1);
+        for (uint256 i = 0; i < numOps; i++) {
+            uint256 computed = eAdd(
+                eMul(eMul(wire(os, 4 * i), wire(os, 4 * i + 1)), c0), eMul(wire(os, 4 * i + 2), c1)
+            );
+            addConstraint(cs, i, filter, eSub(wire(os, 4 * i + 3), computed));
+        }
+    }
+
+    /// Mirrors `BaseSumGate::eval_unfiltered`: wire 0 holds the sum, and the limbs follow.
+    function baseSumConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 base,
+        uint256 numLimbs
+    ) internal pure {
+        uint256 computed = 0;
+        for (uint256 i = numLimbs; i > 0; i--) {
+            computed = eAdd(eScale(computed, base), wire(os, i));
+        }
+        addConstraint(cs, 0, filter, eSub(computed, wire(os, 0)));
+        for (uint256 i = 0; i < numLimbs; i++) {
+            uint256 limb = wire(os, 1 + i);
+            uint256 product = 1;
+            for (uint256 j = 0; j < base; j++) {
+                product = eMul(product, eSub(limb, j));
+            }
+            addConstraint(cs, 1 + i, filter, product);
+        }
+    }
+
+    /// Mirrors `ConstantGate::eval_unfiltered`.
+    function constantConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 numConsts
+    ) internal pure {
+        for (uint256 i = 0; i < numConsts; i++) {
+            addConstraint(cs, i, filter, eSub(gateConstant(os, i), wire(os, i)));
+        }
+    }
+
+    /// Mirrors `PublicInputGate::eval_unfiltered`.
+    function publicInputConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256[4] memory piHash
+    ) internal pure {
+        for (uint256 i = 0; i < 4; i++) {
+            addConstraint(cs, i, filter, eSub(wire(os, i), piHash[i]));
+        }
+    }
+
+    // Wire layout of `PoseidonGate`.
+    uint256 constant POSEIDON_WIRE_OUTPUT = 12;
+    uint256 constant POSEIDON_WIRE_SWAP = 24;
+    uint256 constant POSEIDON_START_DELTA = 25;
+    uint256 constant POSEIDON_START_FULL_0 = 29;
+    uint256 constant POSEIDON_START_PARTIAL = 65;
+    uint256 constant POSEIDON_START_FULL_1 = 87;
+
+    /// Mirrors `PoseidonGate::eval_unfiltered`. The gate uses the optimized partial rounds, which
+    /// compute the same S-box inputs as the plain rounds used here.
+    function poseidonConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256[] memory pc
+    ) internal pure {
+        uint256 swap = wire(os, POSEIDON_WIRE_SWAP);
+        uint256 j = addConstraint(cs, 0, filter, eMul(swap, eSub(swap, 1)));
+
+        uint256[12] memory s;
+        for (uint256 i = 0; i < 4; i++) {
+            uint256 lhs = wire(os, i);
+            uint256 rhs = wire(os, i + 4);
+            uint256 delta = wire(os, POSEIDON_START_DELTA + i);
+            j = addConstraint(cs, j, filter, eSub(eMul(swap, eSub(rhs, lhs)), delta));
+            s[i] = eAdd(lhs, delta);
+            s[i + 4] = eSub(rhs, delta);
+        }
+        for (uint256 i = 8; i < POSEIDON_WIDTH; i++) {
+            s[i] = wire(os, i);
+        }
+
+        uint256 round = 0;
+        for (uint256 r = 0; r < POSEIDON_HALF_N_FULL_ROUNDS; r++) {
+            poseidonConstantLayer(s, pc, round++);
+            if (r != 0) {
+                j = substituteSboxInputs(os, cs, j, filter, s, POSEIDON_START_FULL_0 + 12 * (r - 1));
+            }
+            poseidonSboxLayer(s);
+            poseidonMdsLayer(s, pc);
+        }
+        for (uint256 r = 0; r < POSEIDON_N_PARTIAL_ROUNDS; r++) {
+            poseidonConstantLayer(s, pc, round++);
+            uint256 sboxIn = wire(os, POSEIDON_START_PARTIAL + r);
+            j = addConstraint(cs, j, filter, eSub(s[0], sboxIn));
+            s[0] = sboxMonomial(sboxIn);
+            poseidonMdsLayer(s, pc);
+        }
+        for (uint256 r = 0; r < POSEIDON_HALF_N_FULL_ROUNDS; r++) {
+            poseidonConstantLayer(s, pc, round++);
+            j = substituteSboxInputs(os, cs, j, filter, s, POSEIDON_START_FULL_1 + 12 * r);
+            poseidonSboxLayer(s);
+            poseidonMdsLayer(s, pc);
+        }
+
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+            j = addConstraint(cs, j, filter, eSub(s[i], wire(os, POSEIDON_WIRE_OUTPUT + i)));
+        }
+    }
+
+    /// Constrains a full round's S-box inputs to the wires starting at `start`, and replaces the
+    /// state with them.
+    function substituteSboxInputs(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 j,
+        uint256 filter,
+        uint256[12] memory s,
+        uint256 start
+    ) internal pure returns (uint256) {
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+            uint256 sboxIn = wire(os, start + i);
+            j = addConstraint(cs, j, filter, eSub(s[i], sboxIn));
+            s[i] = sboxIn;
+        }
+        return j;
+    }
+
+    // Gates on extension targets evaluate their constraints in an extension algebra, whose elements
+    // `a0 + a1 * Y`, with `Y^2 = W`, are pairs of extension elements. Such an element starting at
+    // wire `i` is made of wires `i` and `i + 1`, and adds one constraint per component.
+
+    function algebraMul(uint256 a0, uint256 a1, uint256 b0, uint256 b1)
+        internal
+        pure
+        returns (uint256, uint256)
+    {
+        return (eAdd(eMul(a0, b0), eScale(eMul(a1, b1), W)), eAdd(eMul(a0, b1), eMul(a1, b0)));
+    }
+
+    function addAlgebraConstraint(
+        uint256[] memory cs,
+        uint256 i,
+        uint256 filter,
+        uint256 c0,
+        uint256 c1
+    ) internal pure returns (uint256) {
+        i = addConstraint(cs, i, filter, c0);
+        return addConstraint(cs, i, filter, c1);
+    }
+
+    /// Mirrors `ArithmeticExtensionGate::eval_unfiltered`.
+    function arithmeticExtensionConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 numOps
+    ) internal pure {
+        uint256 c0 = gateConstant(os, 0);
+        uint256 c1 = gateConstant(os, 1);
+        uint256 j = 0;
+        for (uint256 i = 0; i < numOps; i++) {
+            uint256 k = 8 * i;
+            (uint256 p0, uint256 p1) =
+                algebraMul(wire(os, k), wire(os, k + 1), wire(os, k + 2), wire(os, k + 3));
+            j = addAlgebraConstraint(
+                cs,
+                j,
+                filter,
+                eSub(wire(os, k + 6), eAdd(eMul(p0, c0), eMul(wire(os, k + 4), c1))),
+                eSub(wire(os, k + 7), eAdd(eMul(p1, c0), eMul(wire(os, k + 5), c1)))
+            );
+        }
+    }
+
+    /// Mirrors `MulExtensionGate::eval_unfiltered`.
+    function mulExtensionConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 numOps
+    ) internal pure {
+        uint256 c0 = gateConstant(os, 0);
+        uint256 j = 0;
+        for (uint256 i = 0; i < numOps; i++) {
+            uint256 k = 6 * i;
+            (uint256 p0, uint256 p1) =
+                algebraMul(wire(os, k), wire(os, k + 1), wire(os, k + 2), wire(os, k + 3));
+            j = addAlgebraConstraint(
+                cs, j, filter, eSub(wire(os, k + 4), eMul(p0, c0)), eSub(wire(os, k + 5), eMul(p1, c0))
+            );
+        }
+    }
+
+    /// Mirrors `ReducingGate::eval_unfiltered` if `coeffWidth` is 1, and
+    /// `ReducingExtensionGate::eval_unfiltered` if it is 2. Wires 0, 2 and 4 start the output,
+    /// `alpha` and the old accumulator, followed by the coefficients and the other accumulators.
+    function reducingConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 numCoeffs,
+        uint256 coeffWidth
+    ) internal pure {
+        uint256 startAccs = 6 + coeffWidth * numCoeffs;
+        uint256 acc0 = wire(os, 4);
+        uint256 acc1 = wire(os, 5);
+        uint256 j = 0;
+        for (uint256 i = 0; i < numCoeffs; i++) {
+            (uint256 r0, uint256 r1) = algebraMul(acc0, acc1, wire(os, 2), wire(os, 3));
+            uint256 coeff = 6 + coeffWidth * i;
+            r0 = eAdd(r0, wire(os, coeff));
+            if (coeffWidth == 2) {
+                r1 = eAdd(r1, wire(os, coeff + 1));
+            }
+            // The last accumulator is the output.
+            uint256 accStart = i == numCoeffs - 1 ? 0 : startAccs + 2 * i;
+            acc0 = wire(os, accStart);
+            acc1 = wire(os, accStart + 1);
+            j = addAlgebraConstraint(cs, j, filter, eSub(r0, acc0), eSub(r1, acc1));
+        }
+    }
+
+    /// Evaluates the interpolant whose algebra coefficients start at wire `coeffs` at the
+    /// extension element `x`.
+    function evalInterpolantAt(uint256[] memory os, uint256 coeffs, uint256 numPoints, uint256 x)
+        internal
+        pure
+        returns (uint256 acc0, uint256 acc1)
+    {
+        for (uint256 i = numPoints; i > 0; i--) {
+            acc0 = eAdd(eMul(acc0, x), wire(os, coeffs + 2 * (i - 1)));
+            acc1 = eAdd(eMul(acc1, x), wire(os, coeffs + 2 * (i - 1) + 1));
+        }
+    }
+
+    /// Mirrors `HighDegreeInterpolationGate::eval_unfiltered`. Wire 0 holds the coset shift,
+    /// followed by the values, the evaluation point, the evaluation value and the coefficients.
+    function highDegreeInterpolationConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 subgroupBits
+    ) internal pure {
+        uint256 numPoints = 1 << subgroupBits;
+        uint256 coeffs = 5 + 2 * numPoints;
+        uint256 g = fExpPowerOf2(TWO_ADIC_GENERATOR, TWO_ADICITY - subgroupBits);
+        uint256 point = wire(os, 0);
+        uint256 j = 0;
+        for (uint256 i = 0; i < numPoints; i++) {
+            (uint256 v0, uint256 v1) = evalInterpolantAt(os, coeffs, numPoints, point);
+            j = addAlgebraConstraint(
+                cs, j, filter, eSub(wire(os, 1 + 2 * i), v0), eSub(wire(os, 2 + 2 * i), v1)
+            );
+            point = eScale(point, g);
+        }
+
+        uint256 x0 = wire(os, 1 + 2 * numPoints);
+        uint256 x1 = wire(os, 2 + 2 * numPoints);
+        uint256 acc0 = 0;
+        uint256 acc1 = 0;
+        for (uint256 i = numPoints; i > 0; i--) {
+            (acc0, acc1) = algebraMul(acc0, acc1, x0, x1);
+            acc0 = eAdd(acc0, wire(os, coeffs + 2 * (i - 1)));
+            acc1 = eAdd(acc1, wire(os, coeffs + 2 * (i - 1) + 1));
+        }
+        addAlgebraConstraint(
+            cs,
+            j,
+            filter,
+            eSub(wire(os, 3 + 2 * numPoints), acc0),
+            eSub(wire(os, 4 + 2 * numPoints), acc1)
+        );
+    }
+
+    /// Mirrors `LowDegreeInterpolationGate::eval_unfiltered`. The wires are laid out as for
+    /// `HighDegreeInterpolationGate`, followed by the powers of the shift and of the evaluation
+    /// point from the second one on.
+    function lowDegreeInterpolationConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 subgroupBits
+    ) internal pure {
+        uint256 numPoints = 1 << subgroupBits;
+        uint256 coeffs = 5 + 2 * numPoints;
+        uint256 endCoeffs = coeffs + 2 * numPoints;
+        uint256 j = 0;
+
+        // `shiftPowers[i] = shift^i`.
+        uint256[] memory shiftPowers = new uint256[](numPoints);
+        shiftPowers[0] = 1;
+        shiftPowers[1] = wire(os, 0);
+        for (uint256 i = 2; i < numPoints; i++) {
+            shiftPowers[i] = wire(os, endCoeffs + i - 2);
+            j = addConstraint(
+                cs, j, filter, eSub(eMul(shiftPowers[i - 1], shiftPowers[1]), shiftPowers[i])
+            );
+        }
+
+        // The values are checked against the interpolant with coefficients `c_i shift^i`, on the
+        // subgroup itself.
+        uint256 g = fExpPowerOf2(TWO_ADIC_GENERATOR, TWO_ADICITY - subgroupBits);
+        uint256 point = 1;
+        for (uint256 i = 0; i < numPoints; i++) {
+            uint256 v0 = 0;
+            uint256 v1 = 0;
+            for (uint256 k = numPoints; k > 0; k--) {
+                uint256 c = coeffs + 2 * (k - 1);
+                v0 = eAdd(eScale(v0, point), eMul(wire(os, c), shiftPowers[k - 1]));
+                v1 = eAdd(eScale(v1, point), eMul(wire(os, c + 1), shiftPowers[k - 1]));
+            }
+            j = addAlgebraConstraint(
+                cs, j, filter, eSub(wire(os, 1 + 2 * i), v0), eSub(wire(os, 2 + 2 * i), v1)
+            );
+            point = mulmod(point, g, P);
+        }
+
+        uint256 x0 = wire(os, 1 + 2 * numPoints);
+        uint256 x1 = wire(os, 2 + 2 * numPoints);
+        uint256 startPowers = endCoeffs + numPoints - 2;
+        for (uint256 i = 2; i < numPoints; i++) {
+            uint256 prev = i == 2 ? 1 + 2 * numPoints : startPowers + 2 * (i - 3);
+            (uint256 p0, uint256 p1) = algebraMul(wire(os, prev), wire(os, prev + 1), x0, x1);
+            uint256 power = startPowers + 2 * (i - 2);
+            j = addAlgebraConstraint(
+                cs, j, filter, eSub(p0, wire(os, power)), eSub(p1, wire(os, power + 1))
+            );
+        }
+
+        uint256 acc0 = wire(os, coeffs);
+        uint256 acc1 = wire(os, coeffs + 1);
+        for (uint256 i = 1; i < numPoints; i++) {
+            uint256 power = i == 1 ? 1 + 2 * numPoints : startPowers + 2 * (i - 2);
+            (uint256 t0, uint256 t1) = algebraMul(
+                wire(os, coeffs + 2 * i), wire(os, coeffs + 2 * i + 1), wire(os, power), wire(os, power + 1)
+            );
+            acc0 = eAdd(acc0, t0);
+            acc1 = eAdd(acc1, t1);
+        }
+        addAlgebraConstraint(
+            cs,
+            j,
+            filter,
+            eSub(wire(os, 3 + 2 * numPoints), acc0),
+            eSub(wire(os, 4 + 2 * numPoints), acc1)
+        );
+    }
+
+    /// Mirrors `RandomAccessGate::eval_unfiltered`.
+    function randomAccessConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 bits,
+        uint256 numCopies,
+        uint256 numExtraConstants
+    ) internal pure {
+        uint256 vecSize = 1 << bits;
+        uint256 numRoutedWires = (2 + vecSize) * numCopies + numExtraConstants;
+        uint256[] memory items = new uint256[](vecSize);
+        uint256 j = 0;
+        for (uint256 copy = 0; copy < numCopies; copy++) {
+            uint256 start = (2 + vecSize) * copy;
+            uint256 startBits = numRoutedWires + copy * bits;
+            uint256 reconstructed = 0;
+            for (uint256 i = 0; i < bits; i++) {
+                uint256 b = wire(os, startBits + i);
+                j = addConstraint(cs, j, filter, eMul(b, eSub(b, 1)));
+            }
+            for (uint256 i = bits; i > 0; i--) {
+                uint256 b = wire(os, startBits + i - 1);
+                reconstructed = eAdd(eAdd(reconstructed, reconstructed), b);
+            }
+            j = addConstraint(cs, j, filter, eSub(reconstructed, wire(os, start)));
+
+            for (uint256 i = 0; i < vecSize; i++) {
+                items[i] = wire(os, start + 2 + i);
+            }
+            uint256 len = vecSize;
+            for (uint256 i = 0; i < bits; i++) {
+                uint256 b = wire(os, startBits + i);
+                len >>= 1;
+                for (uint256 k = 0; k < len; k++) {
+                    uint256 x = items[2 * k];
+                    items[k] = eAdd(x, eMul(b, eSub(items[2 * k + 1], x)));
+                }
+            }
+            j = addConstraint(cs, j, filter, eSub(items[0], wire(os, start + 1)));
+        }
+        for (uint256 i = 0; i < numExtraConstants; i++) {
+            uint256 extraConstant = wire(os, (2 + vecSize) * numCopies + i);
+            j = addConstraint(cs, j, filter, eSub(gateConstant(os, i), extraConstant));
+        }
+    }
+
+    /// Mirrors `ExponentiationGate::eval_unfiltered`. Wire 0 holds the base, followed by the bits
+    /// of the exponent, the output and the intermediate values.
+    function exponentiationConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256 numPowerBits
+    ) internal pure {
+        uint256 base = wire(os, 0);
+        uint256 prev = 1;
+        for (uint256 i = 0; i < numPowerBits; i++) {
+            // The bits are in little-endian order, but are accumulated in big-endian order.
+            uint256 bit = wire(os, numPowerBits - i);
+            uint256 computed = eMul(prev, eAdd(eMul(bit, base), eSub(1, bit)));
+            uint256 intermediate = wire(os, 2 + numPowerBits + i);
+            addConstraint(cs, i, filter, eSub(computed, intermediate));
+            prev = eMul(intermediate, intermediate);
+        }
+        addConstraint(
+            cs,
+            numPowerBits,
+            filter,
+            eSub(wire(os, 1 + numPowerBits), wire(os, 1 + 2 * numPowerBits))
+        );
+    }
+
+    /// Mirrors `PoseidonMdsGate::eval_unfiltered`. The MDS layer is linear, so it is applied to
+    /// each component of the algebra elements separately.
+    function poseidonMdsConstraints(
+        uint256[] memory os,
+        uint256[] memory cs,
+        uint256 filter,
+        uint256[] memory pc
+    ) internal pure {
+        uint256[12] memory s0;
+        uint256[12] memory s1;
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+            s0[i] = wire(os, 2 * i);
+            s1[i] = wire(os, 2 * i + 1);
+        }
+        poseidonMdsLayer(s0, pc);
+        poseidonMdsLayer(s1, pc);
+        uint256 j = 0;
+        for (uint256 i = 0; i < POSEIDON_WIDTH; i++) {
+            uint256 out = 2 * (POSEIDON_WIDTH + i);
+            j = addAlgebraConstraint(
+                cs, j, filter, eSub(wire(os, out), s0[i]), eSub(wire(os, out + 1), s1[i])
+            );
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // Vanishing polynomial, mirroring `verify_vanishing_identity`.
+    // ---------------------------------------------------------------------------------------------
+
+    uint256 constant NUM_PARTIAL_PRODUCT_CHECKS = NUM_PARTIAL_PRODUCTS + 1;
+    uint256 constant NUM_VANISHING_TERMS =
+        NUM_CHALLENGES * (1 + NUM_PARTIAL_PRODUCT_CHECKS) + NUM_GATE_CONSTRAINTS;
+
+    function verifyVanishingIdentity(
+        uint256[] memory os,
+        uint256[4] memory piHash,
+        Challenges memory ch,
+        uint256[] memory pc
+    ) internal pure {
+        uint256 zeta = ch.zeta;
+        uint256 zetaPowN = eExpPowerOf2(zeta, DEGREE_BITS);
+        uint256 zH = eSub(zetaPowN, 1);
+
+        // The terms are ordered as in `eval_vanishing_poly`: the `L_0(x) (Z(x) - 1)` terms, the
+        // partial product checks, and the gate constraints.
+        uint256[] memory terms = new uint256[](NUM_VANISHING_TERMS);
+        uint256 l0 = zeta == 1 ? 1 : eDiv(zH, eScale(eSub(zeta, 1), (1 << DEGREE_BITS) % P));
+        for (uint256 i = 0; i < NUM_CHALLENGES; i++) {
+            terms[i] = eMul(l0, eSub(os[OS_ZS + i], 1));
+        }
+        for (uint256 i = 0; i < NUM_CHALLENGES; i++) {
+            partialProductChecks(os, zeta, ch.betas[i], ch.gammas[i], i, terms);
+        }
+        uint256[] memory cs = evalGateConstraints(os, piHash, pc);
+        uint256 offset = NUM_CHALLENGES * (1 + NUM_PARTIAL_PRODUCT_CHECKS);
+        for (uint256 i = 0; i < NUM_GATE_CONSTRAINTS; i++) {
+            terms[offset + i] = cs[i];
+        }
+
+        for (uint256 i = 0; i < NUM_CHALLENGES; i++) {
+            uint256 vanishing = reduceWithPowers(terms, 0, NUM_VANISHING_TERMS, ch.alphas[i]);
+            // The quotient is split into `QUOTIENT_DEGREE_FACTOR` chunks of degree `n`.
+            uint256 quotient = reduceWithPowers(
+                os,
+                OS_QUOTIENT + i * QUOTIENT_DEGREE_FACTOR,
+                OS_QUOTIENT + (i + 1) * QUOTIENT_DEGREE_FACTOR,
+                zetaPowN
+            );
+            require(vanishing == eMul(zH, quotient), "vanishing identity does not hold");
+        }
+    }
+
+    /// Mirrors `check_partial_products` for the `i`th challenge.
+    function partialProductChecks(
+        uint256[] memory os,
+        uint256 zeta,
+        uint256 beta,
+        uint256 gamma,
+        uint256 i,
+        uint256[] memory terms
+    ) internal pure {
+        bytes memory kIs = K_IS;
+        uint256 prev = os[OS_ZS + i];
+        for (uint256 chunk = 0; chunk < NUM_PARTIAL_PRODUCT_CHECKS; chunk++) {
+            (uint256 numerator, uint256 denominator) =
+                partialProductChunk(os, kIs, zeta, beta, gamma, chunk);
+            uint256 next = chunk == NUM_PARTIAL_PRODUCTS
+                ? os[OS_ZS_NEXT + i]
+                : os[OS_PARTIAL_PRODUCTS + i * NUM_PARTIAL_PRODUCTS + chunk];
+            terms[NUM_CHALLENGES + i * NUM_PARTIAL_PRODUCT_CHECKS + chunk] =
+                eSub(eMul(prev, numerator), eMul(next, denominator));
+            prev = next;
+        }
+    }
+
+    /// Computes the products of the permutation argument's numerators and denominators over the
+    /// `chunk`th group of `QUOTIENT_DEGREE_FACTOR` routed wires.
+    function partialProductChunk(
+        uint256[] memory os,
+        bytes memory kIs,
+        uint256 zeta,
+        uint256 beta,
+        uint256 gamma,
+        uint256 chunk
+    ) internal pure returns (uint256 numerator, uint256 denominator) {
+        numerator = 1;
+        denominator = 1;
+        uint256 end = (chunk + 1) * QUOTIENT_DEGREE_FACTOR;
+        if (end > NUM_ROUTED_WIRES) {
+            end = NUM_ROUTED_WIRES;
+        }
+        for (uint256 j = chunk * QUOTIENT_DEGREE_FACTOR; j < end; j++) {
+            uint256 w = wire(os, j);
+            uint256 sId = eScale(zeta, mulmod(readU64(kIs, j), beta, P));
+            uint256 sSigma = eScale(os[OS_SIGMAS + j], beta);
+            numerator = eMul(numerator, eAdd(eAdd(w, sId), gamma));
+            denominator = eMul(denominator, eAdd(eAdd(w, sSigma), gamma));
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // FRI, mirroring `verify_fri_proof`.
+    // ---------------------------------------------------------------------------------------------
+
+    uint256 constant NUM_ORACLES = 4;
+
+    struct QueryState {
+        /// Position of the next unread word of the query round.
+        uint256 cursor;
+        uint256 xIndex;
+        /// The point of the current domain being queried.
+        uint256 x;
+        /// The last derived evaluation, to be checked against the next layer.
+        uint256 oldEval;
+    }
+
+    function verifyFri(uint256[] calldata proof, uint256[] memory os, Challenges memory ch)
+        internal
+        pure
+    {
+        uint256[3] memory precomputed;
+        precomputed[0] = reduceWithPowers(os, 0, NUM_OPENINGS_ZETA, ch.friAlpha);
+        precomputed[1] = reduceWithPowers(os, NUM_OPENINGS_ZETA, NUM_OPENINGS, ch.friAlpha);
+        precomputed[2] = eScale(ch.zeta, DEGREE_GENERATOR);
+
+        for (uint256 i = 0; i < NUM_QUERY_ROUNDS; i++) {
+            uint256 xIndex = ch.queryIndices[i];
+            QueryState memory q = QueryState(
+                OFF_QUERY_ROUNDS + i * QUERY_ROUND_LEN,
+                xIndex,
+                mulmod(COSET_SHIFT, fPow(LDE_GENERATOR, reverseBits(xIndex, LDE_BITS)), P),
+                0
+            );
+            verifyInitialTrees(proof, q, ch, precomputed);
+            verifyReductionSteps(proof, q, ch);
+            require(evalFinalPoly(proof, q.x) == q.oldEval, "final polynomial evaluation is invalid");
+        }
+    }
+
+    function oraclePolys(uint256 oracle) internal pure returns (uint256) {
+        if (oracle == 0) {
+            return ORACLE_POLYS_0;
+        } else if (oracle == 1) {
+            return ORACLE_POLYS_1;
+        } else if (oracle == 2) {
+            return ORACLE_POLYS_2;
+        }
+        return ORACLE_POLYS_3;
+    }
+
+    /// Checks the Merkle proofs of the initial oracles, and mirrors `fri_combine_initial`.
+    function verifyInitialTrees(
+        uint256[] calldata proof,
+        QueryState memory q,
+        Challenges memory ch,
+        uint256[3] memory precomputed
+    ) internal pure {
+        uint256 alpha = ch.friAlpha;
+        // Evaluations of all polynomials, reduced by powers of alpha, for the opening at zeta.
+        uint256 sumZeta = 0;
+        uint256 alphaPower = 1;
+        // Evaluations of the Zs, reduced by powers of alpha, for the opening at g * zeta.
+        uint256 sumZetaNext = 0;
+
+        for (uint256 oracle = 0; oracle < NUM_ORACLES; oracle++) {
+            uint256 numPolys = oraclePolys(oracle);
+            uint256[] memory leaf = new uint256[](numPolys + (oracle == 0 ? 0 : SALT_SIZE));
+            for (uint256 i = 0; i < leaf.length; i++) {
+                leaf[i] = checkBase(proof[q.cursor + i]);
+            }
+            for (uint256 i = 0; i < numPolys; i++) {
+                sumZeta = eAdd(sumZeta, eScale(alphaPower, leaf[i]));
+                alphaPower = eMul(alphaPower, alpha);
+            }
+            if (oracle == 2) {
+                sumZetaNext = reduceWithPowers(leaf, 0, NUM_CHALLENGES, alpha);
+            }
+            q.cursor += leaf.length;
+
+            (bytes25 root, uint256 capIndex) =
+                merkleRootToCap(proof, hashOrNoop(leaf), q.xIndex, q.cursor, LDE_BITS - CAP_HEIGHT);
+            require(root == initialCapDigest(proof, oracle, capIndex), "invalid Merkle proof");
+            q.cursor += LDE_BITS - CAP_HEIGHT;
+        }
+
+        uint256 zetaTerm = eDiv(eSub(sumZeta, precomputed[0]), eSub(q.x, ch.zeta));
+        uint256 zetaNextTerm = eDiv(eSub(sumZetaNext, precomputed[1]), eSub(q.x, precomputed[2]));
+        uint256 shift = 1;
+        for (uint256 i = 0; i < NUM_CHALLENGES; i++) {
+            shift = eMul(shift, alpha);
+        }
+        // Multiply by `x`, as in `fri_combine_initial`.
+        q.oldEval = eScale(eAdd(eMul(shift, zetaTerm), zetaNextTerm), q.x);
+    }
+
+    function initialCapDigest(uint256[] calldata proof, uint256 oracle, uint256 capIndex)
+        internal
+        pure
+        returns (bytes25)
+    {
+        if (oracle == 0) {
+            return readDigest(CONSTANTS_SIGMAS_CAP, capIndex);
+        }
+        return proofDigest(proof, OFF_WIRES_CAP + (oracle - 1) * CAP_LEN + capIndex);
+    }
+
+    /// Hashes a leaf up to the cap, returning the computed digest and the index in the cap it
+    /// should match.
+    function merkleRootToCap(
+        uint256[] calldata proof,
+        bytes25 digest,
+        uint256 index,
+        uint256 siblingsOffset,
+        uint256 numSiblings
+    ) internal pure returns (bytes25, uint256) {
+        for (uint256 i = 0; i < numSiblings; i++) {
+            bytes25 sibling = proofDigest(proof, siblingsOffset + i);
+            digest = index & 1 == 1 ? twoToOne(sibling, digest) : twoToOne(digest, sibling);
+            index >>= 1;
+        }
+        return (digest, index);
+    }
+
+    function verifyReductionSteps(
+        uint256[] calldata proof,
+        QueryState memory q,
+        Challenges memory ch
+    ) internal pure {
+        bytes memory arityBitsList = REDUCTION_ARITY_BITS;
+        uint256 remainingBits = LDE_BITS;
+        for (uint256 r = 0; r < NUM_REDUCTIONS; r++) {
+            uint256 arityBits = uint8(arityBitsList[r]);
+            uint256 arity = 1 << arityBits;
+            uint256 cosetIndex = q.xIndex >> arityBits;
+            uint256 xIndexWithinCoset = q.xIndex & (arity - 1);
+
+            uint256[] memory evals = new uint256[](arity);
+            uint256[] memory flattened = new uint256[](2 * arity);
+            for (uint256 i = 0; i < arity; i++) {
+                evals[i] = checkExt(proof[q.cursor + i]);
+                flattened[2 * i] = evals[i] & LO;
+                flattened[2 * i + 1] = evals[i] >> 128;
+            }
+            q.cursor += arity;
+            require(evals[xIndexWithinCoset] == q.oldEval, "inconsistent FRI evaluations");
+
+            q.oldEval = computeEvaluation(q.x, xIndexWithinCoset, arityBits, evals, ch.friBetas[r]);
+
+            remainingBits -= arityBits;
+            (bytes25 root, uint256 capIndex) = merkleRootToCap(
+                proof, hashOrNoop(flattened), cosetIndex, q.cursor, remainingBits - CAP_HEIGHT
+            );
+            require(
+                root == proofDigest(proof, OFF_COMMIT_PHASE_CAPS + r * CAP_LEN + capIndex),
+                "invalid Merkle proof"
+            );
+            q.cursor += remainingBits - CAP_HEIGHT;
+
+            q.x = fExpPowerOf2(q.x, arityBits);
+            q.xIndex = cosetIndex;
+        }
+    }
+
+    /// Mirrors `compute_evaluation`: interpolates the coset evaluations, given in bit-reversed
+    /// order, and evaluates the interpolant at `beta`. For points `x_k = c g^k` of a coset of
+    /// order `n`, the interpolant is `(beta^n - c^n) / (n c^n) sum_k v_k x_k / (beta - x_k)`.
+    function computeEvaluation(
+        uint256 x,
+        uint256 xIndexWithinCoset,
+        uint256 arityBits,
+        uint256[] memory evals,
+        uint256 beta
+    ) internal pure returns (uint256) {
+        uint256 arity = 1 << arityBits;
+        uint256 g = fExpPowerOf2(TWO_ADIC_GENERATOR, TWO_ADICITY - arityBits);
+        uint256 cosetStart =
+            mulmod(x, fPow(g, arity - reverseBits(xIndexWithinCoset, arityBits)), P);
+
+        uint256 sum = 0;
+        uint256 point = cosetStart;
+        for (uint256 k = 0; k < arity; k++) {
+            uint256 v = evals[reverseBits(k, arityBits)];
+            sum = eAdd(sum, eDiv(eScale(v, point), eSub(beta, point)));
+            point = mulmod(point, g, P);
+        }
+
+        uint256 cosetStartPowN = fExpPowerOf2(cosetStart, arityBits);
+        uint256 factor = eScale(
+            eSub(eExpPowerOf2(beta, arityBits), cosetStartPowN),
+            fInv(mulmod(arity, cosetStartPowN, P))
+        );
+        return eMul(factor, sum);
+    }
+
+    function evalFinalPoly(uint256[] calldata proof, uint256 x) internal pure returns (uint256 acc) {
+        for (uint256 i = FINAL_POLY_LEN; i > 0; i--) {
+            acc = eAdd(eScale(acc, x), proof[OFF_FINAL_POLY + i - 1]);
+        }
+    }
@@ -1,17 +1,19 @@
 use log::{log, Level};
 
+use crate::plonk::circuit_stats::ContextStats;
+
 /// The hierarchy of contexts, and the gate count contributed by each one. Useful for debugging.
 pub(crate) struct ContextTree {
     /// The name of this scope.
-    name: String,
+    pub(crate) name: String,
     /// The level at which to log this scope and its children.
-    level: log::Level,
+    pub(crate) level: log::Level,
     /// The gate count when this scope was created.
-    enter_gate_count: usize,
+    pub(crate) enter_gate_count: usize,
     /// The gate count when this scope was destroyed, or None if it has not yet been destroyed.
-    exit_gate_count: Option<usize>,
+    pub(crate) exit_gate_count: Option<usize>,
     /// Any child contexts.
-    children: Vec<ContextTree>,
+    pub(crate) children: Vec<ContextTree>,
 }
 
 impl ContextTree {
@@ -46,6 +48,23 @@ impl ContextTree {
         }
     }
 
+    /// A description of the stack of scopes which were open when the given gate was added.
+    pub fn gate_context(&self, gate: usize) -> String {
+        let mut stack = Vec::new();
+        self.gate_context_helper(gate, &mut stack);
+        stack.join(" > ")
+    }
+
+    fn gate_context_helper(&self, gate: usize, stack: &mut Vec<String>) {
+        let exit_gate_count = self.exit_gate_count.unwrap_or(usize::MAX);
+        if (self.enter_gate_count..exit_gate_count).contains(&gate) {
+            stack.push(self.name.clone());
+            for child in &self.children {
+                child.gate_context_helper(gate, stack);
+            }
+        }
+    }
+
     pub fn push(&mut self, ctx: &str, mut level: log::Level, current_gate_count: usize) {
         assert!(self.is_open());
 
@@ -102,6 +121,19 @@ impl ContextTree {
         }
     }
 
+    /// The gate count of this scope and of each of its children.
+    pub fn stats(&self, current_gate_count: usize) -> ContextStats {
+        ContextStats {
+            name: self.name.clone(),
+            num_gates: self.gate_count_delta(current_gate_count),
+            children: self
+                .children
+                .iter()
+                .map(|c| c.stats(current_gate_count))
+                .collect(),
+        }
+    }
+
     pub fn print(&self, current_gate_count: usize) {
         self.print_helper(current_gate_count, 0);
     }
@@ -0,0 +1,157 @@
+use itertools::Itertools;
+use plonky2_field::extension::Extendable;
+use plonky2_field::types::Field;
+
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+// The lookup argument is a logarithmic derivative argument. On each row, slot `k` holds a value
+// `v_k`, combining an input, an output and a table index, and a numerator `c_k`, which is 1 for a
+// lookup, minus the entry's multiplicity for a table entry, and 0 elsewhere. A lookup is valid if
+// and only if, for a random `delta`, the sum of `c_k / (delta - v_k)` over all rows and slots is
+// zero. Each helper polynomial `h_j` holds the sum over a chunk of slots on each row, and `Z` is
+// the running sum of the helpers, starting at 0 and wrapping around to 0.
+
+/// Computes the helper values of the lookup argument on a single row, given the numerators and
+/// denominators of its slots.
+pub(crate) fn lookup_helpers<F: Field>(
+    numerators: &[F],
+    denominators: &[F],
+    slots_per_helper: usize,
+) -> Vec<F> {
+    debug_assert!(slots_per_helper > 0);
+    let denominator_invs = F::batch_multiplicative_inverse(denominators);
+    numerators
+        .chunks(slots_per_helper)
+        .zip_eq(denominator_invs.chunks(slots_per_helper))
+        .map(|(nume_chunk, deno_inv_chunk)| {
+            nume_chunk
+                .iter()
+                .zip(deno_inv_chunk)
+                .map(|(&n, &d_inv)| n * d_inv)
+                .sum()
+        })
+        .collect()
+}
+
+/// Checks the lookup helpers and the running sum `Z` on a single row. `filter` is 1 on rows of
+/// lookup and table gates, and 0 elsewhere, where helpers are constrained to be 0.
+pub(crate) fn check_lookup_constraints<F: Field>(
+    numerators: &[F],
+    denominators: &[F],
+    helpers: &[F],
+    filter: F,
+    z_x: F,
+    z_gx: F,
+    slots_per_helper: usize,
+) -> Vec<F> {
+    debug_assert!(slots_per_helper > 0);
+    let mut constraints = Vec::with_capacity(1 + helpers.len());
+    // Check that Z(gx) = Z(x) + sum_j h_j(x).
+    constraints.push(z_gx - z_x - helpers.iter().copied().sum());
+    for ((nume_chunk, deno_chunk), &helper) in numerators
+        .chunks(slots_per_helper)
+        .zip_eq(denominators.chunks(slots_per_helper))
+        .zip_eq(helpers)
+    {
+        // Compute the fraction `sum_k n_k / d_k` as `(sum_k n_k prod_{l != k} d_l) / prod_k d_k`.
+        let (nume, deno) = nume_chunk
+            .iter()
+            .zip(deno_chunk)
+            .fold((F::ZERO, F::ONE), |(nume, deno), (&n, &d)| {
+                (nume * d + n * deno, deno * d)
+            });
+        // Assert that h * deno = nume if filter = 1, and h = 0 if filter = 0.
+        constraints.push(helper * (filter * (deno - F::ONE) + F::ONE) - nume);
+    }
+    constraints
+}
+
+/// Circuit version of `check_lookup_constraints`.
+pub(crate) fn check_lookup_constraints_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    numerators: &[ExtensionTarget<D>],
+    denominators: &[ExtensionTarget<D>],
+    helpers: &[ExtensionTarget<D>],
+    filter: ExtensionTarget<D>,
+    z_x: ExtensionTarget<D>,
+    z_gx: ExtensionTarget<D>,
+    slots_per_helper: usize,
+) -> Vec<ExtensionTarget<D>> {
+    debug_assert!(slots_per_helper > 0);
+    let one = builder.one_extension();
+    let mut constraints = Vec::with_capacity(1 + helpers.len());
+    // Check that Z(gx) = Z(x) + sum_j h_j(x).
+    let helpers_sum = builder.add_many_extension(helpers);
+    let z_diff = builder.sub_extension(z_gx, z_x);
+    constraints.push(builder.sub_extension(z_diff, helpers_sum));
+    for ((nume_chunk, deno_chunk), &helper) in numerators
+        .chunks(slots_per_helper)
+        .zip_eq(denominators.chunks(slots_per_helper))
+        .zip_eq(helpers)
+    {
+        let mut nume = nume_chunk[0];
+        let mut deno = deno_chunk[0];
+        for (&n, &d) in nume_chunk.iter().zip(deno_chunk).skip(1) {
+            let n_deno = builder.mul_extension(n, deno);
+            nume = builder.mul_add_extension(nume, d, n_deno);
+            deno = builder.mul_extension(deno, d);
+        }
+        // Assert that h * deno = nume if filter = 1, and h = 0 if filter = 0.
+        let filtered_deno = builder.mul_sub_extension(filter, deno, filter);
+        let filtered_deno = builder.add_extension(filtered_deno, one);
+        constraints.push(builder.mul_sub_extension(helper, filtered_deno, nume));
+    }
+    constraints
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+
+    #[test]
+    fn test_lookup_helpers() {
+        type F = GoldilocksField;
+        let numerators = F::rand_vec(7);
+        let denominators = F::rand_vec(7);
+        let z_x = F::rand();
+
+        for slots_per_helper in 1..=7 {
+            let helpers = lookup_helpers(&numerators, &denominators, slots_per_helper);
+            let z_gx = z_x + helpers.iter().copied().sum::<F>();
+            for filter in [F::ZERO, F::ONE] {
+                let constraints = check_lookup_constraints(
+                    &numerators,
+                    &denominators,
+                    &helpers,
+                    filter,
+                    z_x,
+                    z_gx,
+                    slots_per_helper,
+                );
+                assert!(constraints[0].is_zero());
+                assert_eq!(
+                    constraints[1..].iter().all(|c| c.is_zero()),
+                    filter.is_one()
+                );
+            }
+
+            // Outside of lookup rows, numerators and helpers are all 0.
+            let zero_numerators = vec![F::ZERO; numerators.len()];
+            let zero_helpers = vec![F::ZERO; helpers.len()];
+            let constraints = check_lookup_constraints(
+                &zero_numerators,
+                &denominators,
+                &zero_helpers,
+                F::ZERO,
+                z_x,
+                z_x,
+                slots_per_helper,
+            );
+            assert!(constraints.iter().all(|c| c.is_zero()));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use plonky2_field::extension::Extendable;
+
+use crate::gates::arithmetic_base::ArithmeticGate;
+use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
+use crate::gates::base_sum::BaseSumGate;
+use crate::gates::constant::ConstantGate;
+use crate::gates::exponentiation::ExponentiationGate;
+use crate::gates::gate::GateRef;
+use crate::gates::high_degree_interpolation::HighDegreeInterpolationGate;
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
+use crate::gates::low_degree_interpolation::LowDegreeInterpolationGate;
+use crate::gates::multiplication_extension::MulExtensionGate;
+use crate::gates::noop::NoopGate;
+use crate::gates::poseidon::PoseidonGate;
+use crate::gates::poseidon2::CustomPoseidon2Gate;
+use crate::gates::poseidon_mds::PoseidonMdsGate;
+use crate::gates::public_input::PublicInputGate;
+use crate::gates::random_access::RandomAccessGate;
+use crate::gates::reducing::ReducingGate;
+use crate::gates::reducing_extension::ReducingExtensionGate;
+use crate::gates::rescue_prime::CustomRescuePrimeGate;
+use crate::hash::hash_types::RichField;
+use crate::hash::poseidon2::CustomPoseidon2;
+use crate::hash::rescue_prime::CustomRescuePrime;
+use crate::util::serialization::{Buffer, IoResult};
+
+/// Reads and writes `GateRef`s. Since gates are stored as trait objects, a serializer needs to know
+/// the set of concrete gate types it may encounter; each one is identified by a tag.
+pub trait GateSerializer<F: RichField + Extendable<D>, const D: usize> {
+    fn read_gate(&self, buf: &mut Buffer) -> IoResult<GateRef<F, D>>;
+    fn write_gate(&self, buf: &mut Buffer, gate: &GateRef<F, D>) -> IoResult<()>;
+}
+
+/// Implements the methods of `GateSerializer` for the given list of `tag => gate type` pairs, to
+/// be used inside an `impl GateSerializer<F, D> for ...` block. Tags are written before each gate,
+/// so a tag must never be changed or reused for another gate type once data has been serialized
+/// with it. Duplicate tags are rejected at compile time.
+#[macro_export]
+macro_rules! impl_gate_serializer {
+    ($($tag:literal => $gate_types:ty),+ $(,)?) => {
+        fn read_gate(
+            &self,
+            buf: &mut $crate::util::serialization::Buffer,
+        ) -> $crate::util::serialization::IoResult<$crate::gates::gate::GateRef<F, D>> {
+            const _: () = $crate::util::serialization::assert_distinct_tags(&[$($tag),+]);
+            let tag = buf.read_u32()?;
+            $(if tag == $tag {
+                return Ok($crate::gates::gate::GateRef::new(
+                    <$gate_types as $crate::gates::gate::Gate<F, D>>::deserialize(buf)?,
+                ));
+            })+
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown gate tag {}", tag),
+            ))
+        }
+
+        fn write_gate(
+            &self,
+            buf: &mut $crate::util::serialization::Buffer,
+            gate: &$crate::gates::gate::GateRef<F, D>,
+        ) -> $crate::util::serialization::IoResult<()> {
+            let type_id = std::any::Any::type_id(gate.0.as_any());
+            $(if type_id == std::any::TypeId::of::<$gate_types>() {
+                buf.write_u32($tag)?;
+                return gate.0.serialize(buf);
+            })+
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Gate {} is not supported by this serializer", gate.0.id()),
+            ))
+        }
+    };
+}
+
+/// Expands `impl_gate_serializer!` for the gates of `DefaultGateSerializer`, followed by any extra
+/// `tag => gate type` pairs.
+macro_rules! impl_default_gate_serializer {
+    ($($extra:tt)*) => {
+        impl_gate_serializer! {
+            0 => ArithmeticGate,
+            1 => ArithmeticExtensionGate<D>,
+            2 => BaseSumGate<2>,
+            3 => BaseSumGate<4>,
+            4 => ConstantGate,
+            5 => ExponentiationGate<F, D>,
+            6 => HighDegreeInterpolationGate<F, D>,
+            7 => LowDegreeInterpolationGate<F, D>,
+            8 => MulExtensionGate<D>,
+            9 => NoopGate,
+            10 => PoseidonMdsGate<F, D>,
+            11 => PoseidonGate<F, D>,
+            12 => PublicInputGate,
+            13 => RandomAccessGate<F, D>,
+            14 => ReducingExtensionGate<D>,
+            15 => ReducingGate<D>,
+            16 => LookupGate,
+            17 => LookupTableGate,
+            $($extra)*
+        }
+    };
+}
+
+/// A `GateSerializer` supporting all the gates defined in this crate, except for the gates of the
+/// Poseidon2 and Rescue-Prime hashes, which require the field to implement those hashes. See
+/// `HashGateSerializer`.
+pub struct DefaultGateSerializer;
+
+impl<F: RichField + Extendable<D>, const D: usize> GateSerializer<F, D> for DefaultGateSerializer {
+    impl_default_gate_serializer! {}
+}
+
+/// A `GateSerializer` supporting all the gates defined in this crate, including the Poseidon2 and
+/// Rescue-Prime gates. Gates are tagged as in `DefaultGateSerializer`.
+pub struct HashGateSerializer;
+
+impl<F: RichField + Extendable<D> + CustomPoseidon2 + CustomRescuePrime, const D: usize>
+    GateSerializer<F, D> for HashGateSerializer
+{
+    impl_default_gate_serializer! {
+        18 => CustomPoseidon2Gate<F, D>,
+        19 => CustomRescuePrimeGate<F, D>,
+    }
+}
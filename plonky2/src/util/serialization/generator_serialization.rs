@@ -0,0 +1,150 @@
+use plonky2_field::extension::Extendable;
+
+use crate::gadgets::arithmetic::EqualityGenerator;
+use crate::gadgets::arithmetic_extension::QuotientGeneratorExtension;
+use crate::gadgets::range_check::LowHighGenerator;
+use crate::gadgets::split_base::BaseSumGenerator;
+use crate::gadgets::split_join::{SplitGenerator, WireSplitGenerator};
+use crate::gates::arithmetic_base::ArithmeticBaseGenerator;
+use crate::gates::arithmetic_extension::ArithmeticExtensionGenerator;
+use crate::gates::base_sum::BaseSplitGenerator;
+use crate::gates::exponentiation::ExponentiationGenerator;
+use crate::gates::high_degree_interpolation::InterpolationGenerator as HighDegreeInterpolationGenerator;
+use crate::gates::lookup::LookupGenerator;
+use crate::gates::lookup_table::LookupTableGenerator;
+use crate::gates::low_degree_interpolation::InterpolationGenerator as LowDegreeInterpolationGenerator;
+use crate::gates::multiplication_extension::MulExtensionGenerator;
+use crate::gates::poseidon::PoseidonGenerator;
+use crate::gates::poseidon2::CustomPoseidon2Generator;
+use crate::gates::poseidon_mds::PoseidonMdsGenerator;
+use crate::gates::random_access::RandomAccessGenerator;
+use crate::gates::reducing::ReducingGenerator;
+use crate::gates::reducing_extension::ReducingGenerator as ReducingExtensionGenerator;
+use crate::gates::rescue_prime::CustomRescuePrimeGenerator;
+use crate::hash::hash_types::RichField;
+use crate::hash::poseidon2::CustomPoseidon2;
+use crate::hash::rescue_prime::CustomRescuePrime;
+use crate::iop::generator::{
+    ConstantGenerator, CopyGenerator, NonzeroTestGenerator, RandomValueGenerator,
+    SimpleGeneratorAdapter, WitnessGenerator,
+};
+use crate::util::serialization::{Buffer, IoResult};
+
+/// Reads and writes witness generators. Since generators are stored as trait objects, a serializer
+/// needs to know the set of concrete generator types it may encounter; each one is identified by a
+/// tag.
+pub trait WitnessGeneratorSerializer<F: RichField + Extendable<D>, const D: usize> {
+    fn read_generator(&self, buf: &mut Buffer) -> IoResult<Box<dyn WitnessGenerator<F>>>;
+    fn write_generator(
+        &self,
+        buf: &mut Buffer,
+        generator: &dyn WitnessGenerator<F>,
+    ) -> IoResult<()>;
+}
+
+/// Implements the methods of `WitnessGeneratorSerializer` for the given list of
+/// `tag => generator type` pairs, to be used inside an `impl WitnessGeneratorSerializer<F, D> for
+/// ...` block. Tags are written before each generator, so a tag must never be changed or reused
+/// for another generator type once data has been serialized with it. Duplicate tags are rejected
+/// at compile time. Simple generators should be listed wrapped in `SimpleGeneratorAdapter`.
+#[macro_export]
+macro_rules! impl_generator_serializer {
+    ($($tag:literal => $generator_types:ty),+ $(,)?) => {
+        fn read_generator(
+            &self,
+            buf: &mut $crate::util::serialization::Buffer,
+        ) -> $crate::util::serialization::IoResult<
+            Box<dyn $crate::iop::generator::WitnessGenerator<F>>,
+        > {
+            const _: () = $crate::util::serialization::assert_distinct_tags(&[$($tag),+]);
+            let tag = buf.read_u32()?;
+            $(if tag == $tag {
+                return Ok(Box::new(
+                    <$generator_types as $crate::iop::generator::WitnessGenerator<F>>::deserialize(
+                        buf,
+                    )?,
+                ));
+            })+
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown generator tag {}", tag),
+            ))
+        }
+
+        fn write_generator(
+            &self,
+            buf: &mut $crate::util::serialization::Buffer,
+            generator: &dyn $crate::iop::generator::WitnessGenerator<F>,
+        ) -> $crate::util::serialization::IoResult<()> {
+            let type_id = std::any::Any::type_id(generator.as_any());
+            $(if type_id == std::any::TypeId::of::<$generator_types>() {
+                buf.write_u32($tag)?;
+                return generator.serialize(buf);
+            })+
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Generator {:?} is not supported by this serializer", generator),
+            ))
+        }
+    };
+}
+
+/// Expands `impl_generator_serializer!` for the generators of `DefaultGeneratorSerializer`,
+/// followed by any extra `tag => generator type` pairs.
+macro_rules! impl_default_generator_serializer {
+    ($($extra:tt)*) => {
+        impl_generator_serializer! {
+            0 => SimpleGeneratorAdapter<F, ArithmeticBaseGenerator<F, D>>,
+            1 => SimpleGeneratorAdapter<F, ArithmeticExtensionGenerator<F, D>>,
+            2 => SimpleGeneratorAdapter<F, BaseSplitGenerator<2>>,
+            3 => SimpleGeneratorAdapter<F, BaseSplitGenerator<4>>,
+            4 => SimpleGeneratorAdapter<F, BaseSumGenerator<2>>,
+            5 => SimpleGeneratorAdapter<F, BaseSumGenerator<4>>,
+            6 => SimpleGeneratorAdapter<F, ConstantGenerator<F>>,
+            7 => SimpleGeneratorAdapter<F, CopyGenerator>,
+            8 => SimpleGeneratorAdapter<F, EqualityGenerator>,
+            9 => SimpleGeneratorAdapter<F, ExponentiationGenerator<F, D>>,
+            10 => SimpleGeneratorAdapter<F, HighDegreeInterpolationGenerator<F, D>>,
+            11 => SimpleGeneratorAdapter<F, LowDegreeInterpolationGenerator<F, D>>,
+            12 => SimpleGeneratorAdapter<F, LowHighGenerator>,
+            13 => SimpleGeneratorAdapter<F, MulExtensionGenerator<F, D>>,
+            14 => SimpleGeneratorAdapter<F, NonzeroTestGenerator>,
+            15 => SimpleGeneratorAdapter<F, PoseidonGenerator<F, D>>,
+            16 => SimpleGeneratorAdapter<F, PoseidonMdsGenerator<D>>,
+            17 => SimpleGeneratorAdapter<F, QuotientGeneratorExtension<D>>,
+            18 => SimpleGeneratorAdapter<F, RandomAccessGenerator<F, D>>,
+            19 => SimpleGeneratorAdapter<F, RandomValueGenerator>,
+            20 => SimpleGeneratorAdapter<F, ReducingGenerator<D>>,
+            21 => SimpleGeneratorAdapter<F, ReducingExtensionGenerator<D>>,
+            22 => SimpleGeneratorAdapter<F, SplitGenerator>,
+            23 => SimpleGeneratorAdapter<F, WireSplitGenerator>,
+            24 => SimpleGeneratorAdapter<F, LookupGenerator>,
+            25 => SimpleGeneratorAdapter<F, LookupTableGenerator>,
+            $($extra)*
+        }
+    };
+}
+
+/// A `WitnessGeneratorSerializer` supporting all the generators defined in this crate, except for
+/// the generators of the Poseidon2 and Rescue-Prime gates. See `HashGeneratorSerializer`.
+pub struct DefaultGeneratorSerializer;
+
+impl<F: RichField + Extendable<D>, const D: usize> WitnessGeneratorSerializer<F, D>
+    for DefaultGeneratorSerializer
+{
+    impl_default_generator_serializer! {}
+}
+
+/// A `WitnessGeneratorSerializer` supporting all the generators defined in this crate, including
+/// those of the Poseidon2 and Rescue-Prime gates. Generators are tagged as in
+/// `DefaultGeneratorSerializer`.
+pub struct HashGeneratorSerializer;
+
+impl<F: RichField + Extendable<D> + CustomPoseidon2 + CustomRescuePrime, const D: usize>
+    WitnessGeneratorSerializer<F, D> for HashGeneratorSerializer
+{
+    impl_default_generator_serializer! {
+        26 => SimpleGeneratorAdapter<F, CustomPoseidon2Generator<F, D>>,
+        27 => SimpleGeneratorAdapter<F, CustomRescuePrimeGenerator<F, D>>,
+    }
+}
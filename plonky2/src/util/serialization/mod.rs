@@ -0,0 +1,1789 @@
+pub mod gate_serialization;
+pub mod generator_serialization;
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use log::Level;
+use plonky2_field::extension::{Extendable, FieldExtension};
+use plonky2_field::polynomial::PolynomialCoeffs;
+use plonky2_field::types::{Field64, PrimeField64};
+use plonky2_util::log2_strict;
+
+use crate::fri::oracle::PolynomialBatch;
+use crate::fri::proof::{
+    CompressedFriProof, CompressedFriQueryRounds, FriInitialTreeProof, FriProof, FriQueryRound,
+    FriQueryStep,
+};
+use crate::fri::reduction_strategies::FriReductionStrategy;
+use crate::fri::{FriConfig, FriParams};
+use crate::gates::selectors::SelectorsInfo;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_proofs::MerkleProof;
+use crate::hash::merkle_tree::{MerkleCap, MerkleTree};
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, ProverCircuitData, ProverOnlyCircuitData,
+    VerifierCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use crate::plonk::plonk_common::salt_size;
+use crate::plonk::proof::{
+    CompressedProof, CompressedProofWithPublicInputs, OpeningSet, Proof, ProofWithPublicInputs,
+};
+use crate::plonk::public_data::PublicDataCommitment;
+use crate::util::context_tree::ContextTree;
+use crate::util::serialization::gate_serialization::GateSerializer;
+use crate::util::serialization::generator_serialization::WitnessGeneratorSerializer;
+
+pub type IoResult<T> = Result<T>;
+
+/// Magic bytes at the start of serialized circuit data, followed by
+/// `CIRCUIT_DATA_SERIALIZATION_VERSION`.
+pub const CIRCUIT_DATA_MAGIC: [u8; 4] = *b"PLK2";
+
+/// The version of the circuit data encoding, to be bumped whenever it changes.
+pub const CIRCUIT_DATA_SERIALIZATION_VERSION: u8 = 2;
+
+/// Panics if two tags of a gate or generator serializer are equal. Meant to be evaluated at
+/// compile time, see `impl_gate_serializer`.
+pub const fn assert_distinct_tags(tags: &[u32]) {
+    let mut i = 0;
+    while i < tags.len() {
+        let mut j = i + 1;
+        while j < tags.len() {
+            assert!(tags[i] != tags[j], "Duplicate serialization tag");
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+#[derive(Debug)]
+pub struct Buffer(Cursor<Vec<u8>>);
+
+impl Buffer {
+    pub fn new(buffer: Vec<u8>) -> Self {
+        Self(Cursor::new(buffer))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.get_ref().len()
+    }
+
+    pub fn bytes(self) -> Vec<u8> {
+        self.0.into_inner()
+    }
+
+    /// The number of bytes which haven't been read yet.
+    pub fn remaining(&self) -> usize {
+        self.len().saturating_sub(self.0.position() as usize)
+    }
+
+    pub fn write_u8(&mut self, x: u8) -> Result<()> {
+        self.0.write_all(&[x])
+    }
+    pub fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0; std::mem::size_of::<u8>()];
+        self.0.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// Writes the magic bytes and version which start serialized circuit data.
+    pub fn write_circuit_data_header(&mut self) -> Result<()> {
+        self.0.write_all(&CIRCUIT_DATA_MAGIC)?;
+        self.write_u8(CIRCUIT_DATA_SERIALIZATION_VERSION)
+    }
+
+    /// Reads the header written by `write_circuit_data_header`, and checks its version.
+    pub fn read_circuit_data_header(&mut self) -> Result<()> {
+        let mut magic = [0; CIRCUIT_DATA_MAGIC.len()];
+        self.0.read_exact(&mut magic)?;
+        if magic != CIRCUIT_DATA_MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Invalid magic bytes, this is not serialized circuit data",
+            ));
+        }
+        let version = self.read_u8()?;
+        if version != CIRCUIT_DATA_SERIALIZATION_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Unsupported serialization version {}, expected {}",
+                    version, CIRCUIT_DATA_SERIALIZATION_VERSION
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn write_bool(&mut self, x: bool) -> Result<()> {
+        self.write_u8(u8::from(x))
+    }
+    pub fn read_bool(&mut self) -> Result<bool> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            x => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid boolean byte {}", x),
+            )),
+        }
+    }
+
+    pub fn write_u32(&mut self, x: u32) -> Result<()> {
+        self.0.write_all(&x.to_le_bytes())
+    }
+    pub fn read_u32(&mut self) -> Result<u32> {
+        let mut buf = [0; std::mem::size_of::<u32>()];
+        self.0.read_exact(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Writes a `usize` as a little-endian `u64`, so that the encoding doesn't depend on the
+    /// platform.
+    pub fn write_usize(&mut self, x: usize) -> Result<()> {
+        self.0.write_all(&(x as u64).to_le_bytes())
+    }
+    pub fn read_usize(&mut self) -> Result<usize> {
+        let mut buf = [0; std::mem::size_of::<u64>()];
+        self.0.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf) as usize)
+    }
+
+    /// Writes the length of `v`, followed by its elements.
+    pub fn write_usize_vec(&mut self, v: &[usize]) -> Result<()> {
+        self.write_usize(v.len())?;
+        for &a in v {
+            self.write_usize(a)?;
+        }
+        Ok(())
+    }
+    pub fn read_usize_vec(&mut self) -> Result<Vec<usize>> {
+        let length = self.read_usize()?;
+        (0..length)
+            .map(|_| self.read_usize())
+            .collect::<Result<Vec<_>>>()
+    }
+
+    pub fn write_field<F: PrimeField64>(&mut self, x: F) -> Result<()> {
+        self.0.write_all(&x.to_canonical_u64().to_le_bytes())
+    }
+    pub fn read_field<F: Field64>(&mut self) -> Result<F> {
+        let mut buf = [0; std::mem::size_of::<u64>()];
+        self.0.read_exact(&mut buf)?;
+        Ok(F::from_canonical_u64(u64::from_le_bytes(
+            buf.try_into().unwrap(),
+        )))
+    }
+
+    pub fn write_field_ext<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        x: F::Extension,
+    ) -> Result<()> {
+        for &a in &x.to_basefield_array() {
+            self.write_field(a)?;
+        }
+        Ok(())
+    }
+    pub fn read_field_ext<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+    ) -> Result<F::Extension> {
+        let mut arr = [F::ZERO; D];
+        for a in arr.iter_mut() {
+            *a = self.read_field()?;
+        }
+        Ok(<F::Extension as FieldExtension<D>>::from_basefield_array(
+            arr,
+        ))
+    }
+
+    pub fn write_hash<F: RichField, H: Hasher<F>>(&mut self, h: H::Hash) -> Result<()> {
+        self.0.write_all(&h.to_bytes())
+    }
+
+    pub fn read_hash<F: RichField, H: Hasher<F>>(&mut self) -> Result<H::Hash> {
+        let mut buf = vec![0; H::HASH_SIZE];
+        self.0.read_exact(&mut buf)?;
+        Ok(H::Hash::from_bytes(&buf))
+    }
+
+    pub fn write_merkle_cap<F: RichField, H: Hasher<F>>(
+        &mut self,
+        cap: &MerkleCap<F, H>,
+    ) -> Result<()> {
+        for &a in &cap.0 {
+            self.write_hash::<F, H>(a)?;
+        }
+        Ok(())
+    }
+    pub fn read_merkle_cap<F: RichField, H: Hasher<F>>(
+        &mut self,
+        cap_height: usize,
+    ) -> Result<MerkleCap<F, H>> {
+        let cap_length = 1 << cap_height;
+        Ok(MerkleCap(
+            (0..cap_length)
+                .map(|_| self.read_hash::<F, H>())
+                .collect::<Result<Vec<_>>>()?,
+        ))
+    }
+
+    pub fn write_field_vec<F: PrimeField64>(&mut self, v: &[F]) -> Result<()> {
+        for &a in v {
+            self.write_field(a)?;
+        }
+        Ok(())
+    }
+    pub fn read_field_vec<F: Field64>(&mut self, length: usize) -> Result<Vec<F>> {
+        (0..length)
+            .map(|_| self.read_field())
+            .collect::<Result<Vec<_>>>()
+    }
+
+    pub fn write_field_ext_vec<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        v: &[F::Extension],
+    ) -> Result<()> {
+        for &a in v {
+            self.write_field_ext::<F, D>(a)?;
+        }
+        Ok(())
+    }
+    pub fn read_field_ext_vec<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        length: usize,
+    ) -> Result<Vec<F::Extension>> {
+        (0..length)
+            .map(|_| self.read_field_ext::<F, D>())
+            .collect::<Result<Vec<_>>>()
+    }
+
+    pub fn write_target(&mut self, x: Target) -> Result<()> {
+        match x {
+            Target::Wire(wire) => {
+                self.write_u8(0)?;
+                self.write_usize(wire.row)?;
+                self.write_usize(wire.column)
+            }
+            Target::VirtualTarget { index } => {
+                self.write_u8(1)?;
+                self.write_usize(index)
+            }
+        }
+    }
+    pub fn read_target(&mut self) -> Result<Target> {
+        match self.read_u8()? {
+            0 => {
+                let row = self.read_usize()?;
+                let column = self.read_usize()?;
+                Ok(Target::wire(row, column))
+            }
+            1 => {
+                let index = self.read_usize()?;
+                Ok(Target::VirtualTarget { index })
+            }
+            x => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid target tag {}", x),
+            )),
+        }
+    }
+
+    /// Writes the length of `v`, followed by its elements.
+    pub fn write_target_vec(&mut self, v: &[Target]) -> Result<()> {
+        self.write_usize(v.len())?;
+        for &t in v {
+            self.write_target(t)?;
+        }
+        Ok(())
+    }
+    pub fn read_target_vec(&mut self) -> Result<Vec<Target>> {
+        let length = self.read_usize()?;
+        (0..length)
+            .map(|_| self.read_target())
+            .collect::<Result<Vec<_>>>()
+    }
+
+    /// Writes the length of `table`, followed by its entries.
+    pub fn write_lookup_table(&mut self, table: &[(u16, u16)]) -> Result<()> {
+        self.write_usize(table.len())?;
+        for &(inp, out) in table {
+            self.0.write_all(&inp.to_le_bytes())?;
+            self.0.write_all(&out.to_le_bytes())?;
+        }
+        Ok(())
+    }
+    pub fn read_lookup_table(&mut self) -> Result<Vec<(u16, u16)>> {
+        let length = self.read_usize()?;
+        (0..length)
+            .map(|_| {
+                let mut buf = [0; 4];
+                self.0.read_exact(&mut buf)?;
+                Ok((
+                    u16::from_le_bytes([buf[0], buf[1]]),
+                    u16::from_le_bytes([buf[2], buf[3]]),
+                ))
+            })
+            .collect()
+    }
+
+    pub fn write_bool_target(&mut self, x: BoolTarget) -> Result<()> {
+        self.write_target(x.target)
+    }
+    pub fn read_bool_target(&mut self) -> Result<BoolTarget> {
+        Ok(BoolTarget::new_unsafe(self.read_target()?))
+    }
+
+    /// Writes the length of `v`, followed by its elements.
+    pub fn write_bool_target_vec(&mut self, v: &[BoolTarget]) -> Result<()> {
+        self.write_usize(v.len())?;
+        for &t in v {
+            self.write_bool_target(t)?;
+        }
+        Ok(())
+    }
+    pub fn read_bool_target_vec(&mut self) -> Result<Vec<BoolTarget>> {
+        let length = self.read_usize()?;
+        (0..length)
+            .map(|_| self.read_bool_target())
+            .collect::<Result<Vec<_>>>()
+    }
+
+    pub fn write_target_ext<const D: usize>(&mut self, x: ExtensionTarget<D>) -> Result<()> {
+        for &t in &x.0 {
+            self.write_target(t)?;
+        }
+        Ok(())
+    }
+    pub fn read_target_ext<const D: usize>(&mut self) -> Result<ExtensionTarget<D>> {
+        let mut arr = [Target::VirtualTarget { index: 0 }; D];
+        for t in arr.iter_mut() {
+            *t = self.read_target()?;
+        }
+        Ok(ExtensionTarget(arr))
+    }
+
+    fn write_fri_reduction_strategy(&mut self, strategy: &FriReductionStrategy) -> Result<()> {
+        match strategy {
+            FriReductionStrategy::Fixed(arities) => {
+                self.write_u8(0)?;
+                self.write_usize_vec(arities)
+            }
+            FriReductionStrategy::ConstantArityBits(arity_bits, final_poly_bits) => {
+                self.write_u8(1)?;
+                self.write_usize(*arity_bits)?;
+                self.write_usize(*final_poly_bits)
+            }
+            FriReductionStrategy::MinSize(opt_max_arity_bits) => {
+                self.write_u8(2)?;
+                self.write_bool(opt_max_arity_bits.is_some())?;
+                if let Some(max_arity_bits) = opt_max_arity_bits {
+                    self.write_usize(*max_arity_bits)?;
+                }
+                Ok(())
+            }
+        }
+    }
+    fn read_fri_reduction_strategy(&mut self) -> Result<FriReductionStrategy> {
+        match self.read_u8()? {
+            0 => Ok(FriReductionStrategy::Fixed(self.read_usize_vec()?)),
+            1 => {
+                let arity_bits = self.read_usize()?;
+                let final_poly_bits = self.read_usize()?;
+                Ok(FriReductionStrategy::ConstantArityBits(
+                    arity_bits,
+                    final_poly_bits,
+                ))
+            }
+            2 => {
+                let opt_max_arity_bits = if self.read_bool()? {
+                    Some(self.read_usize()?)
+                } else {
+                    None
+                };
+                Ok(FriReductionStrategy::MinSize(opt_max_arity_bits))
+            }
+            x => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid FRI reduction strategy tag {}", x),
+            )),
+        }
+    }
+
+    pub fn write_fri_config(&mut self, config: &FriConfig) -> Result<()> {
+        let FriConfig {
+            rate_bits,
+            cap_height,
+            proof_of_work_bits,
+            reduction_strategy,
+            num_query_rounds,
+        } = config;
+        self.write_usize(*rate_bits)?;
+        self.write_usize(*cap_height)?;
+        self.write_u32(*proof_of_work_bits)?;
+        self.write_fri_reduction_strategy(reduction_strategy)?;
+        self.write_usize(*num_query_rounds)
+    }
+    pub fn read_fri_config(&mut self) -> Result<FriConfig> {
+        let rate_bits = self.read_usize()?;
+        let cap_height = self.read_usize()?;
+        let proof_of_work_bits = self.read_u32()?;
+        let reduction_strategy = self.read_fri_reduction_strategy()?;
+        let num_query_rounds = self.read_usize()?;
+        Ok(FriConfig {
+            rate_bits,
+            cap_height,
+            proof_of_work_bits,
+            reduction_strategy,
+            num_query_rounds,
+        })
+    }
+
+    pub fn write_fri_params(&mut self, params: &FriParams) -> Result<()> {
+        let FriParams {
+            config,
+            hiding,
+            degree_bits,
+            reduction_arity_bits,
+        } = params;
+        self.write_fri_config(config)?;
+        self.write_bool(*hiding)?;
+        self.write_usize(*degree_bits)?;
+        self.write_usize_vec(reduction_arity_bits)
+    }
+    pub fn read_fri_params(&mut self) -> Result<FriParams> {
+        let config = self.read_fri_config()?;
+        let hiding = self.read_bool()?;
+        let degree_bits = self.read_usize()?;
+        let reduction_arity_bits = self.read_usize_vec()?;
+        Ok(FriParams {
+            config,
+            hiding,
+            degree_bits,
+            reduction_arity_bits,
+        })
+    }
+
+    pub fn write_circuit_config(&mut self, config: &CircuitConfig) -> Result<()> {
+        let CircuitConfig {
+            num_wires,
+            num_routed_wires,
+            num_constants,
+            use_base_arithmetic_gate,
+            security_bits,
+            num_challenges,
+            zero_knowledge,
+            max_quotient_degree_factor,
+            fri_config,
+            allow_insufficient_fri_soundness,
+        } = config;
+        self.write_usize(*num_wires)?;
+        self.write_usize(*num_routed_wires)?;
+        self.write_usize(*num_constants)?;
+        self.write_bool(*use_base_arithmetic_gate)?;
+        self.write_usize(*security_bits)?;
+        self.write_usize(*num_challenges)?;
+        self.write_bool(*zero_knowledge)?;
+        self.write_usize(*max_quotient_degree_factor)?;
+        self.write_fri_config(fri_config)?;
+        self.write_bool(*allow_insufficient_fri_soundness)
+    }
+    pub fn read_circuit_config(&mut self) -> Result<CircuitConfig> {
+        let num_wires = self.read_usize()?;
+        let num_routed_wires = self.read_usize()?;
+        let num_constants = self.read_usize()?;
+        let use_base_arithmetic_gate = self.read_bool()?;
+        let security_bits = self.read_usize()?;
+        let num_challenges = self.read_usize()?;
+        let zero_knowledge = self.read_bool()?;
+        let max_quotient_degree_factor = self.read_usize()?;
+        let fri_config = self.read_fri_config()?;
+        let allow_insufficient_fri_soundness = self.read_bool()?;
+        Ok(CircuitConfig {
+            num_wires,
+            num_routed_wires,
+            num_constants,
+            use_base_arithmetic_gate,
+            security_bits,
+            num_challenges,
+            zero_knowledge,
+            max_quotient_degree_factor,
+            fri_config,
+            allow_insufficient_fri_soundness,
+        })
+    }
+
+    fn write_selectors_info(&mut self, selectors_info: &SelectorsInfo) -> Result<()> {
+        let SelectorsInfo {
+            selector_indices,
+            groups,
+        } = selectors_info;
+        self.write_usize_vec(selector_indices)?;
+        self.write_usize(groups.len())?;
+        for group in groups {
+            self.write_usize(group.start)?;
+            self.write_usize(group.end)?;
+        }
+        Ok(())
+    }
+    fn read_selectors_info(&mut self) -> Result<SelectorsInfo> {
+        let selector_indices = self.read_usize_vec()?;
+        let num_groups = self.read_usize()?;
+        let groups = (0..num_groups)
+            .map(|_| Ok(self.read_usize()?..self.read_usize()?))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(SelectorsInfo {
+            selector_indices,
+            groups,
+        })
+    }
+
+    /// Writes the length of `s` in bytes, followed by its UTF-8 encoding.
+    pub fn write_string(&mut self, s: &str) -> Result<()> {
+        self.write_usize(s.len())?;
+        self.0.write_all(s.as_bytes())
+    }
+    pub fn read_string(&mut self) -> Result<String> {
+        let length = self.read_usize()?;
+        // The length is untrusted, so we check it before allocating.
+        if length > self.remaining() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("String of length {} exceeds the remaining bytes", length),
+            ));
+        }
+        let mut buf = vec![0; length];
+        self.0.read_exact(&mut buf)?;
+        String::from_utf8(buf).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    fn write_context_tree(&mut self, tree: &ContextTree) -> Result<()> {
+        let ContextTree {
+            name,
+            level,
+            enter_gate_count,
+            exit_gate_count,
+            children,
+        } = tree;
+        self.write_string(name)?;
+        self.write_usize(*level as usize)?;
+        self.write_usize(*enter_gate_count)?;
+        self.write_bool(exit_gate_count.is_some())?;
+        if let Some(exit_gate_count) = exit_gate_count {
+            self.write_usize(*exit_gate_count)?;
+        }
+        self.write_usize(children.len())?;
+        for child in children {
+            self.write_context_tree(child)?;
+        }
+        Ok(())
+    }
+    fn read_context_tree(&mut self) -> Result<ContextTree> {
+        let name = self.read_string()?;
+        let level_index = self.read_usize()?;
+        let level = Level::iter()
+            .find(|&l| l as usize == level_index)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid log level {}", level_index),
+                )
+            })?;
+        let enter_gate_count = self.read_usize()?;
+        let exit_gate_count = if self.read_bool()? {
+            Some(self.read_usize()?)
+        } else {
+            None
+        };
+        let num_children = self.read_usize()?;
+        let children = (0..num_children)
+            .map(|_| self.read_context_tree())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ContextTree {
+            name,
+            level,
+            enter_gate_count,
+            exit_gate_count,
+            children,
+        })
+    }
+
+    pub fn write_common_circuit_data<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        common_data: &CommonCircuitData<F, D>,
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> Result<()> {
+        let CommonCircuitData {
+            config,
+            fri_params,
+            gates,
+            selectors_info,
+            quotient_degree_factor,
+            num_gate_constraints,
+            num_constants,
+            num_public_inputs,
+            k_is,
+            num_partial_products,
+            num_lookup_slots,
+            public_data_commitment,
+        } = common_data;
+        self.write_circuit_config(config)?;
+        self.write_fri_params(fri_params)?;
+        self.write_usize(gates.len())?;
+        for gate in gates {
+            gate_serializer.write_gate(self, gate)?;
+        }
+        self.write_selectors_info(selectors_info)?;
+        self.write_usize(*quotient_degree_factor)?;
+        self.write_usize(*num_gate_constraints)?;
+        self.write_usize(*num_constants)?;
+        self.write_usize(*num_public_inputs)?;
+        self.write_usize(k_is.len())?;
+        self.write_field_vec(k_is)?;
+        self.write_usize(*num_partial_products)?;
+        self.write_usize(*num_lookup_slots)?;
+        self.write_public_data_commitment(*public_data_commitment)
+    }
+    pub fn read_common_circuit_data<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> Result<CommonCircuitData<F, D>> {
+        let config = self.read_circuit_config()?;
+        let fri_params = self.read_fri_params()?;
+        let num_gates = self.read_usize()?;
+        let gates = (0..num_gates)
+            .map(|_| gate_serializer.read_gate(self))
+            .collect::<Result<Vec<_>>>()?;
+        let selectors_info = self.read_selectors_info()?;
+        let quotient_degree_factor = self.read_usize()?;
+        let num_gate_constraints = self.read_usize()?;
+        let num_constants = self.read_usize()?;
+        let num_public_inputs = self.read_usize()?;
+        let num_k_is = self.read_usize()?;
+        let k_is = self.read_field_vec(num_k_is)?;
+        let num_partial_products = self.read_usize()?;
+        let num_lookup_slots = self.read_usize()?;
+        let public_data_commitment = self.read_public_data_commitment()?;
+        Ok(CommonCircuitData {
+            config,
+            fri_params,
+            gates,
+            selectors_info,
+            quotient_degree_factor,
+            num_gate_constraints,
+            num_constants,
+            num_public_inputs,
+            k_is,
+            num_partial_products,
+            num_lookup_slots,
+            public_data_commitment,
+        })
+    }
+
+    /// Writes an optional public data commitment as a single byte.
+    fn write_public_data_commitment(
+        &mut self,
+        commitment: Option<PublicDataCommitment>,
+    ) -> Result<()> {
+        self.write_u8(match commitment {
+            None => 0,
+            Some(PublicDataCommitment::Poseidon) => 1,
+            Some(PublicDataCommitment::Keccak) => 2,
+        })
+    }
+    fn read_public_data_commitment(&mut self) -> Result<Option<PublicDataCommitment>> {
+        match self.read_u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(PublicDataCommitment::Poseidon)),
+            2 => Ok(Some(PublicDataCommitment::Keccak)),
+            x => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid public data commitment byte {}", x),
+            )),
+        }
+    }
+
+    pub fn write_merkle_tree<F: RichField, H: Hasher<F>>(
+        &mut self,
+        tree: &MerkleTree<F, H>,
+    ) -> Result<()> {
+        self.write_usize(tree.leaves.len())?;
+        for leaf in &tree.leaves {
+            self.write_usize(leaf.len())?;
+            self.write_field_vec(leaf)?;
+        }
+        self.write_usize(tree.digests.len())?;
+        for &digest in &tree.digests {
+            self.write_hash::<F, H>(digest)?;
+        }
+        self.write_usize(log2_strict(tree.cap.len()))?;
+        self.write_merkle_cap(&tree.cap)
+    }
+    pub fn read_merkle_tree<F: RichField, H: Hasher<F>>(&mut self) -> Result<MerkleTree<F, H>> {
+        let num_leaves = self.read_usize()?;
+        let leaves = (0..num_leaves)
+            .map(|_| {
+                let leaf_len = self.read_usize()?;
+                self.read_field_vec(leaf_len)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let num_digests = self.read_usize()?;
+        let digests = (0..num_digests)
+            .map(|_| self.read_hash::<F, H>())
+            .collect::<Result<Vec<_>>>()?;
+        let cap_height = self.read_usize()?;
+        let cap = self.read_merkle_cap(cap_height)?;
+        Ok(MerkleTree {
+            leaves,
+            digests,
+            cap,
+        })
+    }
+
+    pub fn write_polynomial_batch<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        batch: &PolynomialBatch<F, C, D>,
+    ) -> Result<()> {
+        // Batches committed in low-memory mode don't store their polynomials, which are then
+        // recovered from the Merkle leaves of the batch read back.
+        let polynomials = batch.stored_polynomials().unwrap_or_default();
+        self.write_usize(polynomials.len())?;
+        for poly in polynomials {
+            self.write_usize(poly.len())?;
+            self.write_field_vec(&poly.coeffs)?;
+        }
+        self.write_merkle_tree(&batch.merkle_tree)?;
+        self.write_usize(batch.degree_log)?;
+        self.write_usize(batch.rate_bits)?;
+        self.write_bool(batch.blinding)
+    }
+    pub fn read_polynomial_batch<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+    ) -> Result<PolynomialBatch<F, C, D>> {
+        let num_polys = self.read_usize()?;
+        let polynomials = (0..num_polys)
+            .map(|_| {
+                let len = self.read_usize()?;
+                Ok(PolynomialCoeffs::new(self.read_field_vec(len)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let merkle_tree = self.read_merkle_tree()?;
+        let degree_log = self.read_usize()?;
+        let rate_bits = self.read_usize()?;
+        let blinding = self.read_bool()?;
+        Ok(PolynomialBatch::from_parts(
+            polynomials,
+            merkle_tree,
+            degree_log,
+            rate_bits,
+            blinding,
+        ))
+    }
+
+    pub fn write_prover_only_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        prover_only_data: &ProverOnlyCircuitData<F, C, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<()> {
+        let ProverOnlyCircuitData {
+            generators,
+            generator_indices_by_watches,
+            generator_indices_without_watches,
+            constants_sigmas_commitment,
+            sigmas,
+            subgroup,
+            public_inputs,
+            representative_map,
+            fft_root_table,
+            circuit_digest,
+            context_tree,
+        } = prover_only_data;
+
+        self.write_usize(generators.len())?;
+        for generator in generators {
+            generator_serializer.write_generator(self, generator.as_ref())?;
+        }
+
+        self.write_usize(generator_indices_by_watches.len())?;
+        for (&watch, indices) in generator_indices_by_watches {
+            self.write_usize(watch)?;
+            self.write_usize_vec(indices)?;
+        }
+        self.write_usize_vec(generator_indices_without_watches)?;
+
+        self.write_polynomial_batch(constants_sigmas_commitment)?;
+
+        self.write_usize(sigmas.len())?;
+        for sigma in sigmas {
+            self.write_usize(sigma.len())?;
+            self.write_field_vec(sigma)?;
+        }
+
+        self.write_usize(subgroup.len())?;
+        self.write_field_vec(subgroup)?;
+        self.write_target_vec(public_inputs)?;
+        self.write_usize_vec(representative_map)?;
+
+        self.write_bool(fft_root_table.is_some())?;
+        if let Some(table) = fft_root_table {
+            self.write_usize(table.len())?;
+            for row in table {
+                self.write_usize(row.len())?;
+                self.write_field_vec(row)?;
+            }
+        }
+
+        self.write_hash::<F, C::Hasher>(*circuit_digest)?;
+        self.write_context_tree(context_tree)
+    }
+    pub fn read_prover_only_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<ProverOnlyCircuitData<F, C, D>> {
+        let num_generators = self.read_usize()?;
+        let generators = (0..num_generators)
+            .map(|_| generator_serializer.read_generator(self))
+            .collect::<Result<Vec<_>>>()?;
+
+        let num_watches = self.read_usize()?;
+        let mut generator_indices_by_watches = BTreeMap::new();
+        for _ in 0..num_watches {
+            let watch = self.read_usize()?;
+            let indices = self.read_usize_vec()?;
+            generator_indices_by_watches.insert(watch, indices);
+        }
+        let generator_indices_without_watches = self.read_usize_vec()?;
+
+        let constants_sigmas_commitment = self.read_polynomial_batch()?;
+
+        let num_sigmas = self.read_usize()?;
+        let sigmas = (0..num_sigmas)
+            .map(|_| {
+                let len = self.read_usize()?;
+                self.read_field_vec(len)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let subgroup_len = self.read_usize()?;
+        let subgroup = self.read_field_vec(subgroup_len)?;
+        let public_inputs = self.read_target_vec()?;
+        let representative_map = self.read_usize_vec()?;
+
+        let fft_root_table = if self.read_bool()? {
+            let num_rows = self.read_usize()?;
+            Some(
+                (0..num_rows)
+                    .map(|_| {
+                        let len = self.read_usize()?;
+                        self.read_field_vec(len)
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )
+        } else {
+            None
+        };
+
+        let circuit_digest = self.read_hash::<F, C::Hasher>()?;
+        let context_tree = self.read_context_tree()?;
+
+        Ok(ProverOnlyCircuitData {
+            generators,
+            generator_indices_by_watches,
+            generator_indices_without_watches,
+            constants_sigmas_commitment,
+            sigmas,
+            subgroup,
+            public_inputs,
+            representative_map,
+            fft_root_table,
+            circuit_digest,
+            context_tree,
+        })
+    }
+
+    pub fn write_verifier_only_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        verifier_only_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<()> {
+        let VerifierOnlyCircuitData {
+            constants_sigmas_cap,
+            circuit_digest,
+        } = verifier_only_data;
+        self.write_usize(log2_strict(constants_sigmas_cap.len()))?;
+        self.write_merkle_cap(constants_sigmas_cap)?;
+        self.write_hash::<F, C::Hasher>(*circuit_digest)
+    }
+    pub fn read_verifier_only_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+    ) -> Result<VerifierOnlyCircuitData<C, D>> {
+        let cap_height = self.read_usize()?;
+        let constants_sigmas_cap = self.read_merkle_cap(cap_height)?;
+        let circuit_digest = self.read_hash::<F, C::Hasher>()?;
+        Ok(VerifierOnlyCircuitData {
+            constants_sigmas_cap,
+            circuit_digest,
+        })
+    }
+
+    pub fn write_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        circuit_data: &CircuitData<F, C, D>,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<()> {
+        self.write_common_circuit_data(&circuit_data.common, gate_serializer)?;
+        self.write_prover_only_circuit_data(&circuit_data.prover_only, generator_serializer)?;
+        self.write_verifier_only_circuit_data(&circuit_data.verifier_only)
+    }
+    pub fn read_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<CircuitData<F, C, D>> {
+        let common = self.read_common_circuit_data(gate_serializer)?;
+        let prover_only = self.read_prover_only_circuit_data(generator_serializer)?;
+        let verifier_only = self.read_verifier_only_circuit_data()?;
+        Ok(CircuitData {
+            prover_only,
+            verifier_only,
+            common,
+        })
+    }
+
+    pub fn write_prover_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        prover_circuit_data: &ProverCircuitData<F, C, D>,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<()> {
+        self.write_common_circuit_data(&prover_circuit_data.common, gate_serializer)?;
+        self.write_prover_only_circuit_data(&prover_circuit_data.prover_only, generator_serializer)
+    }
+    pub fn read_prover_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<ProverCircuitData<F, C, D>> {
+        let common = self.read_common_circuit_data(gate_serializer)?;
+        let prover_only = self.read_prover_only_circuit_data(generator_serializer)?;
+        Ok(ProverCircuitData {
+            prover_only,
+            common,
+        })
+    }
+
+    pub fn write_verifier_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        verifier_circuit_data: &VerifierCircuitData<F, C, D>,
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> Result<()> {
+        self.write_common_circuit_data(&verifier_circuit_data.common, gate_serializer)?;
+        self.write_verifier_only_circuit_data(&verifier_circuit_data.verifier_only)
+    }
+    pub fn read_verifier_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> Result<VerifierCircuitData<F, C, D>> {
+        let common = self.read_common_circuit_data(gate_serializer)?;
+        let verifier_only = self.read_verifier_only_circuit_data()?;
+        Ok(VerifierCircuitData {
+            verifier_only,
+            common,
+        })
+    }
+
+    fn write_opening_set<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        os: &OpeningSet<F, D>,
+    ) -> Result<()> {
+        self.write_field_ext_vec::<F, D>(&os.constants)?;
+        self.write_field_ext_vec::<F, D>(&os.plonk_sigmas)?;
+        self.write_field_ext_vec::<F, D>(&os.wires)?;
+        self.write_field_ext_vec::<F, D>(&os.plonk_zs)?;
+        self.write_field_ext_vec::<F, D>(&os.plonk_zs_next)?;
+        self.write_field_ext_vec::<F, D>(&os.partial_products)?;
+        self.write_field_ext_vec::<F, D>(&os.lookup_zs)?;
+        self.write_field_ext_vec::<F, D>(&os.lookup_zs_next)?;
+        self.write_field_ext_vec::<F, D>(&os.quotient_polys)
+    }
+    fn read_opening_set<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<OpeningSet<F, D>> {
+        let config = &common_data.config;
+        let constants = self.read_field_ext_vec::<F, D>(common_data.num_constants)?;
+        let plonk_sigmas = self.read_field_ext_vec::<F, D>(config.num_routed_wires)?;
+        let wires = self.read_field_ext_vec::<F, D>(config.num_wires)?;
+        let plonk_zs = self.read_field_ext_vec::<F, D>(config.num_challenges)?;
+        let plonk_zs_next = self.read_field_ext_vec::<F, D>(config.num_challenges)?;
+        let partial_products = self
+            .read_field_ext_vec::<F, D>(common_data.num_partial_products * config.num_challenges)?;
+        let lookup_zs = self
+            .read_field_ext_vec::<F, D>(common_data.num_lookup_polys() * config.num_challenges)?;
+        let lookup_zs_next = self
+            .read_field_ext_vec::<F, D>(common_data.num_lookup_polys() * config.num_challenges)?;
+        let quotient_polys = self.read_field_ext_vec::<F, D>(
+            common_data.quotient_degree_factor * config.num_challenges,
+        )?;
+        Ok(OpeningSet {
+            constants,
+            plonk_sigmas,
+            wires,
+            plonk_zs,
+            plonk_zs_next,
+            partial_products,
+            lookup_zs,
+            lookup_zs_next,
+            quotient_polys,
+        })
+    }
+
+    fn write_merkle_proof<F: RichField, H: Hasher<F>>(
+        &mut self,
+        p: &MerkleProof<F, H>,
+    ) -> Result<()> {
+        let length = p.siblings.len();
+        self.write_u8(
+            length
+                .try_into()
+                .expect("Merkle proof length must fit in u8."),
+        )?;
+        for &h in &p.siblings {
+            self.write_hash::<F, H>(h)?;
+        }
+        Ok(())
+    }
+    fn read_merkle_proof<F: RichField, H: Hasher<F>>(&mut self) -> Result<MerkleProof<F, H>> {
+        let length = self.read_u8()?;
+        Ok(MerkleProof {
+            siblings: (0..length)
+                .map(|_| self.read_hash::<F, H>())
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+
+    fn write_fri_initial_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fitp: &FriInitialTreeProof<F, C::Hasher>,
+    ) -> Result<()> {
+        for (v, p) in &fitp.evals_proofs {
+            self.write_field_vec(v)?;
+            self.write_merkle_proof(p)?;
+        }
+        Ok(())
+    }
+    fn read_fri_initial_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        leaf_sizes: &[usize],
+    ) -> Result<FriInitialTreeProof<F, C::Hasher>> {
+        let evals_proofs = leaf_sizes
+            .iter()
+            .map(|&leaf_size| Ok((self.read_field_vec(leaf_size)?, self.read_merkle_proof()?)))
+            .collect::<Result<_>>()?;
+        Ok(FriInitialTreeProof { evals_proofs })
+    }
+
+    fn write_fri_query_step<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fqs: &FriQueryStep<F, C::Hasher, D>,
+    ) -> Result<()> {
+        self.write_field_ext_vec::<F, D>(&fqs.evals)?;
+        self.write_merkle_proof(&fqs.merkle_proof)
+    }
+    fn read_fri_query_step<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        arity: usize,
+        compressed: bool,
+    ) -> Result<FriQueryStep<F, C::Hasher, D>> {
+        let evals = self.read_field_ext_vec::<F, D>(arity - usize::from(compressed))?;
+        let merkle_proof = self.read_merkle_proof()?;
+        Ok(FriQueryStep {
+            evals,
+            merkle_proof,
+        })
+    }
+
+    fn write_fri_query_rounds<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fqrs: &[FriQueryRound<F, C::Hasher, D>],
+    ) -> Result<()> {
+        for fqr in fqrs {
+            self.write_fri_initial_proof::<F, C, D>(&fqr.initial_trees_proof)?;
+            for fqs in &fqr.steps {
+                self.write_fri_query_step::<F, C, D>(fqs)?;
+            }
+        }
+        Ok(())
+    }
+    fn read_fri_query_rounds<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fri_params: &FriParams,
+        initial_leaf_sizes: &[usize],
+    ) -> Result<Vec<FriQueryRound<F, C::Hasher, D>>> {
+        let mut fqrs = Vec::with_capacity(fri_params.config.num_query_rounds);
+        for _ in 0..fri_params.config.num_query_rounds {
+            let initial_trees_proof = self.read_fri_initial_proof::<F, C, D>(initial_leaf_sizes)?;
+            let steps = fri_params
+                .reduction_arity_bits
+                .iter()
+                .map(|&ar| self.read_fri_query_step::<F, C, D>(1 << ar, false))
+                .collect::<Result<_>>()?;
+            fqrs.push(FriQueryRound {
+                initial_trees_proof,
+                steps,
+            })
+        }
+        Ok(fqrs)
+    }
+
+    pub fn write_fri_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fp: &FriProof<F, C::Hasher, D>,
+    ) -> Result<()> {
+        for cap in &fp.commit_phase_merkle_caps {
+            self.write_merkle_cap(cap)?;
+        }
+        self.write_fri_query_rounds::<F, C, D>(&fp.query_round_proofs)?;
+        self.write_field_ext_vec::<F, D>(&fp.final_poly.coeffs)?;
+        self.write_field(fp.pow_witness)
+    }
+    /// Reads a FRI proof with the given parameters, whose initial Merkle trees have leaves of
+    /// the given sizes.
+    pub fn read_fri_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fri_params: &FriParams,
+        initial_leaf_sizes: &[usize],
+    ) -> Result<FriProof<F, C::Hasher, D>> {
+        let commit_phase_merkle_caps = (0..fri_params.reduction_arity_bits.len())
+            .map(|_| self.read_merkle_cap(fri_params.config.cap_height))
+            .collect::<Result<Vec<_>>>()?;
+        let query_round_proofs =
+            self.read_fri_query_rounds::<F, C, D>(fri_params, initial_leaf_sizes)?;
+        let final_poly =
+            PolynomialCoeffs::new(self.read_field_ext_vec::<F, D>(fri_params.final_poly_len())?);
+        let pow_witness = self.read_field()?;
+        Ok(FriProof {
+            commit_phase_merkle_caps,
+            query_round_proofs,
+            final_poly,
+            pow_witness,
+        })
+    }
+
+    pub fn write_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        proof: &Proof<F, C, D>,
+    ) -> Result<()> {
+        self.write_merkle_cap(&proof.wires_cap)?;
+        self.write_merkle_cap(&proof.plonk_zs_partial_products_cap)?;
+        self.write_merkle_cap(&proof.quotient_polys_cap)?;
+        self.write_opening_set(&proof.openings)?;
+        self.write_fri_proof::<F, C, D>(&proof.opening_proof)
+    }
+    pub fn read_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Proof<F, C, D>> {
+        let config = &common_data.config;
+        let wires_cap = self.read_merkle_cap(config.fri_config.cap_height)?;
+        let plonk_zs_partial_products_cap = self.read_merkle_cap(config.fri_config.cap_height)?;
+        let quotient_polys_cap = self.read_merkle_cap(config.fri_config.cap_height)?;
+        let openings = self.read_opening_set::<F, C, D>(common_data)?;
+        let opening_proof = self.read_fri_proof::<F, C, D>(
+            &common_data.fri_params,
+            &fri_initial_leaf_sizes(common_data),
+        )?;
+
+        Ok(Proof {
+            wires_cap,
+            plonk_zs_partial_products_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof,
+        })
+    }
+
+    pub fn write_proof_with_public_inputs<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+    ) -> Result<()> {
+        let ProofWithPublicInputs {
+            proof,
+            public_inputs,
+        } = proof_with_pis;
+        self.write_proof(proof)?;
+        self.write_field_vec(public_inputs)
+    }
+    pub fn read_proof_with_public_inputs<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        let proof = self.read_proof(common_data)?;
+        let public_inputs = self.read_field_vec(
+            (self.len() - self.0.position() as usize) / std::mem::size_of::<u64>(),
+        )?;
+
+        Ok(ProofWithPublicInputs {
+            proof,
+            public_inputs,
+        })
+    }
+
+    fn write_compressed_fri_query_rounds<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        cfqrs: &CompressedFriQueryRounds<F, C::Hasher, D>,
+    ) -> Result<()> {
+        for &i in &cfqrs.indices {
+            self.write_u32(i as u32)?;
+        }
+
+        let mut initial_trees_proofs = cfqrs.initial_trees_proofs.iter().collect::<Vec<_>>();
+        initial_trees_proofs.sort_by_key(|&x| x.0);
+        for (_, itp) in initial_trees_proofs {
+            self.write_fri_initial_proof::<F, C, D>(itp)?;
+        }
+        for h in &cfqrs.steps {
+            let mut fri_query_steps = h.iter().collect::<Vec<_>>();
+            fri_query_steps.sort_by_key(|&x| x.0);
+            for (_, fqs) in fri_query_steps {
+                self.write_fri_query_step::<F, C, D>(fqs)?;
+            }
+        }
+        Ok(())
+    }
+    fn read_compressed_fri_query_rounds<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fri_params: &FriParams,
+        initial_leaf_sizes: &[usize],
+    ) -> Result<CompressedFriQueryRounds<F, C::Hasher, D>> {
+        let original_indices = (0..fri_params.config.num_query_rounds)
+            .map(|_| self.read_u32().map(|i| i as usize))
+            .collect::<Result<Vec<_>>>()?;
+        let mut indices = original_indices.clone();
+        indices.sort_unstable();
+        indices.dedup();
+        let mut pairs = Vec::new();
+        for &i in &indices {
+            pairs.push((
+                i,
+                self.read_fri_initial_proof::<F, C, D>(initial_leaf_sizes)?,
+            ));
+        }
+        let initial_trees_proofs = HashMap::from_iter(pairs);
+
+        let mut steps = Vec::with_capacity(fri_params.reduction_arity_bits.len());
+        for &a in &fri_params.reduction_arity_bits {
+            indices.iter_mut().for_each(|x| {
+                *x >>= a;
+            });
+            indices.dedup();
+            let query_steps = (0..indices.len())
+                .map(|_| self.read_fri_query_step::<F, C, D>(1 << a, true))
+                .collect::<Result<Vec<_>>>()?;
+            steps.push(
+                indices
+                    .iter()
+                    .copied()
+                    .zip(query_steps)
+                    .collect::<HashMap<_, _>>(),
+            );
+        }
+
+        Ok(CompressedFriQueryRounds {
+            indices: original_indices,
+            initial_trees_proofs,
+            steps,
+        })
+    }
+
+    pub fn write_compressed_fri_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fp: &CompressedFriProof<F, C::Hasher, D>,
+    ) -> Result<()> {
+        for cap in &fp.commit_phase_merkle_caps {
+            self.write_merkle_cap(cap)?;
+        }
+        self.write_compressed_fri_query_rounds::<F, C, D>(&fp.query_round_proofs)?;
+        self.write_field_ext_vec::<F, D>(&fp.final_poly.coeffs)?;
+        self.write_field(fp.pow_witness)
+    }
+    /// Reads a compressed FRI proof with the given parameters, whose initial Merkle trees have
+    /// leaves of the given sizes.
+    pub fn read_compressed_fri_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        fri_params: &FriParams,
+        initial_leaf_sizes: &[usize],
+    ) -> Result<CompressedFriProof<F, C::Hasher, D>> {
+        let commit_phase_merkle_caps = (0..fri_params.reduction_arity_bits.len())
+            .map(|_| self.read_merkle_cap(fri_params.config.cap_height))
+            .collect::<Result<Vec<_>>>()?;
+        let query_round_proofs =
+            self.read_compressed_fri_query_rounds::<F, C, D>(fri_params, initial_leaf_sizes)?;
+        let final_poly =
+            PolynomialCoeffs::new(self.read_field_ext_vec::<F, D>(fri_params.final_poly_len())?);
+        let pow_witness = self.read_field()?;
+        Ok(CompressedFriProof {
+            commit_phase_merkle_caps,
+            query_round_proofs,
+            final_poly,
+            pow_witness,
+        })
+    }
+
+    pub fn write_compressed_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        proof: &CompressedProof<F, C, D>,
+    ) -> Result<()> {
+        self.write_merkle_cap(&proof.wires_cap)?;
+        self.write_merkle_cap(&proof.plonk_zs_partial_products_cap)?;
+        self.write_merkle_cap(&proof.quotient_polys_cap)?;
+        self.write_opening_set(&proof.openings)?;
+        self.write_compressed_fri_proof::<F, C, D>(&proof.opening_proof)
+    }
+    pub fn read_compressed_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<CompressedProof<F, C, D>> {
+        let config = &common_data.config;
+        let wires_cap = self.read_merkle_cap(config.fri_config.cap_height)?;
+        let plonk_zs_partial_products_cap = self.read_merkle_cap(config.fri_config.cap_height)?;
+        let quotient_polys_cap = self.read_merkle_cap(config.fri_config.cap_height)?;
+        let openings = self.read_opening_set::<F, C, D>(common_data)?;
+        let opening_proof = self.read_compressed_fri_proof::<F, C, D>(
+            &common_data.fri_params,
+            &fri_initial_leaf_sizes(common_data),
+        )?;
+
+        Ok(CompressedProof {
+            wires_cap,
+            plonk_zs_partial_products_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof,
+        })
+    }
+
+    pub fn write_compressed_proof_with_public_inputs<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        proof_with_pis: &CompressedProofWithPublicInputs<F, C, D>,
+    ) -> Result<()> {
+        let CompressedProofWithPublicInputs {
+            proof,
+            public_inputs,
+        } = proof_with_pis;
+        self.write_compressed_proof(proof)?;
+        self.write_field_vec(public_inputs)
+    }
+    pub fn read_compressed_proof_with_public_inputs<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<CompressedProofWithPublicInputs<F, C, D>> {
+        let proof = self.read_compressed_proof(common_data)?;
+        let public_inputs = self.read_field_vec(
+            (self.len() - self.0.position() as usize) / std::mem::size_of::<u64>(),
+        )?;
+
+        Ok(CompressedProofWithPublicInputs {
+            proof,
+            public_inputs,
+        })
+    }
+}
+
+/// The sizes of the leaves of the Merkle trees opened in the initial FRI round of a Plonk proof.
+fn fri_initial_leaf_sizes<F: RichField + Extendable<D>, const D: usize>(
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<usize> {
+    let config = &common_data.config;
+    let salt = salt_size(common_data.fri_params.hiding);
+    vec![
+        common_data.num_constants + config.num_routed_wires,
+        config.num_wires + salt,
+        common_data.num_zs_partial_products_polys() + salt,
+        config.num_challenges * common_data.quotient_degree_factor + salt,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::types::Field;
+
+    use super::*;
+    use crate::gates::arithmetic_base::ArithmeticGate;
+    use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
+    use crate::gates::base_sum::BaseSumGate;
+    use crate::gates::constant::ConstantGate;
+    use crate::gates::exponentiation::ExponentiationGate;
+    use crate::gates::gate::GateRef;
+    use crate::gates::high_degree_interpolation::HighDegreeInterpolationGate;
+    use crate::gates::interpolation::InterpolationGate;
+    use crate::gates::lookup::LookupGate;
+    use crate::gates::lookup_table::LookupTableGate;
+    use crate::gates::low_degree_interpolation::LowDegreeInterpolationGate;
+    use crate::gates::multiplication_extension::MulExtensionGate;
+    use crate::gates::noop::NoopGate;
+    use crate::gates::poseidon::PoseidonGate;
+    use crate::gates::poseidon2::CustomPoseidon2Gate;
+    use crate::gates::poseidon_mds::PoseidonMdsGate;
+    use crate::gates::public_input::PublicInputGate;
+    use crate::gates::random_access::RandomAccessGate;
+    use crate::gates::reducing::ReducingGate;
+    use crate::gates::reducing_extension::ReducingExtensionGate;
+    use crate::gates::rescue_prime::CustomRescuePrimeGate;
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::reducing::ReducingFactorTarget;
+    use crate::util::serialization::gate_serialization::{
+        DefaultGateSerializer, HashGateSerializer,
+    };
+    use crate::util::serialization::generator_serialization::DefaultGeneratorSerializer;
+    use crate::util::timing::TimingTree;
+
+    #[test]
+    fn test_circuit_data_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let config = CircuitConfig::standard_recursion_zk_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        builder.register_public_input(x);
+
+        let xy = builder.mul_add(x, y, x);
+        let is_equal = builder.is_equal(x, y);
+        builder.assert_zero(is_equal.target);
+        builder.range_check(y, 32);
+        let bits = builder.split_le(y, 32);
+        let y_from_bits = builder.le_sum(bits.into_iter());
+        builder.connect(y, y_from_bits);
+        builder.split_le_base::<4>(y, 16);
+        let x_inv = builder.inverse(x);
+        let x_pow = builder.exp(x, y, 32);
+        let xe = builder.convert_to_ext(x);
+        let ye = builder.convert_to_ext(y);
+        let quotient = builder.div_extension(ye, xe);
+        let product = builder.mul_extension(quotient, xe);
+        builder.connect_extension(product, ye);
+
+        let index = builder.constant(F::TWO);
+        let list = vec![x, y, xy, x_inv];
+        let accessed = builder.random_access(index, list);
+        builder.connect(accessed, xy);
+
+        let hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x, y, x_pow]);
+        let base = builder.constant_extension(FF::from_canonical_u64(3));
+        let terms = builder.add_virtual_targets(50);
+        let ext_terms = builder.add_virtual_extension_targets(50);
+        builder.register_public_inputs(&hash.elements);
+        for &t in &terms {
+            builder.generate_copy(x, t);
+        }
+        for &t in &ext_terms {
+            builder.generate_copy(y, t.0[0]);
+            builder.generate_copy(x, t.0[1]);
+        }
+        ReducingFactorTarget::new(base).reduce_base(&terms, &mut builder);
+        ReducingFactorTarget::new(base).reduce(&ext_terms, &mut builder);
+
+        let data = builder.build::<C>();
+        let bytes = data.to_bytes(&DefaultGateSerializer, &DefaultGeneratorSerializer)?;
+        let data_from_bytes = CircuitData::<F, C, D>::from_bytes(
+            bytes,
+            &DefaultGateSerializer,
+            &DefaultGeneratorSerializer,
+        )?;
+        assert_eq!(data.common, data_from_bytes.common);
+        assert_eq!(data.verifier_only, data_from_bytes.verifier_only);
+        assert_eq!(
+            data.prover_only.generators.len(),
+            data_from_bytes.prover_only.generators.len()
+        );
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(5));
+        pw.set_target(y, F::from_canonical_u64(12345));
+        let proof = data_from_bytes.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_default_gates_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let gates: Vec<GateRef<F, D>> = vec![
+            GateRef::new(ArithmeticGate::new_from_config(&config)),
+            GateRef::new(ArithmeticExtensionGate::new_from_config(&config)),
+            GateRef::new(BaseSumGate::<2>::new_from_config::<F>(&config)),
+            GateRef::new(BaseSumGate::<4>::new(16)),
+            GateRef::new(ConstantGate { num_consts: 2 }),
+            GateRef::new(ExponentiationGate::new_from_config(&config)),
+            GateRef::new(HighDegreeInterpolationGate::new(4)),
+            GateRef::new(LowDegreeInterpolationGate::new(4)),
+            GateRef::new(LookupGate::new_from_config(&config)),
+            GateRef::new(LookupTableGate::new_from_config(&config)),
+            GateRef::new(MulExtensionGate::new_from_config(&config)),
+            GateRef::new(NoopGate),
+            GateRef::new(PoseidonMdsGate::new()),
+            GateRef::new(PoseidonGate::new()),
+            GateRef::new(PublicInputGate),
+            GateRef::new(RandomAccessGate::new_from_config(&config, 4)),
+            GateRef::new(ReducingExtensionGate::new(10)),
+            GateRef::new(ReducingGate::new(20)),
+        ];
+
+        let mut buffer = Buffer::new(Vec::new());
+        for gate in &gates {
+            DefaultGateSerializer.write_gate(&mut buffer, gate)?;
+        }
+        let mut buffer = Buffer::new(buffer.bytes());
+        for gate in &gates {
+            let gate_from_bytes: GateRef<F, D> = DefaultGateSerializer.read_gate(&mut buffer)?;
+            assert_eq!(gate.0.id(), gate_from_bytes.0.id());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_gates_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let gates: Vec<GateRef<F, D>> = vec![
+            GateRef::new(NoopGate),
+            GateRef::new(CustomPoseidon2Gate::new()),
+            GateRef::new(CustomRescuePrimeGate::new()),
+        ];
+
+        let mut buffer = Buffer::new(Vec::new());
+        for gate in &gates {
+            HashGateSerializer.write_gate(&mut buffer, gate)?;
+        }
+        let mut buffer = Buffer::new(buffer.bytes());
+        for gate in &gates {
+            let gate_from_bytes: GateRef<F, D> = HashGateSerializer.read_gate(&mut buffer)?;
+            assert_eq!(gate.0.id(), gate_from_bytes.0.id());
+        }
+
+        // The default serializer doesn't know about the hash gates.
+        let mut buffer = Buffer::new(Vec::new());
+        assert!(DefaultGateSerializer
+            .write_gate(&mut buffer, &gates[1])
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialization_tags_and_version() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // Tags are part of the format, so they must not change when gates are added.
+        let mut buffer = Buffer::new(Vec::new());
+        GateSerializer::<F, D>::write_gate(
+            &DefaultGateSerializer,
+            &mut buffer,
+            &GateRef::new(NoopGate),
+        )?;
+        assert_eq!(buffer.bytes(), 9u32.to_le_bytes());
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        builder.register_public_input(x);
+        let data = builder.build::<C>();
+        let mut bytes = data.common.to_bytes(&DefaultGateSerializer)?;
+        assert_eq!(bytes[..CIRCUIT_DATA_MAGIC.len()], CIRCUIT_DATA_MAGIC);
+        assert_eq!(
+            bytes[CIRCUIT_DATA_MAGIC.len()],
+            CIRCUIT_DATA_SERIALIZATION_VERSION
+        );
+
+        bytes[CIRCUIT_DATA_MAGIC.len()] += 1;
+        assert!(CommonCircuitData::<F, D>::from_bytes(bytes, &DefaultGateSerializer).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_string_with_invalid_length() -> Result<()> {
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_usize(usize::MAX)?;
+        buffer.write_string("abc")?;
+        let mut buffer = Buffer::new(buffer.bytes());
+        assert!(buffer.read_string().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_low_memory_polynomial_batch_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let polynomials = (0..5)
+            .map(|_| PolynomialCoeffs::new(F::rand_vec(16)))
+            .collect::<Vec<_>>();
+        let batch = PolynomialBatch::<F, C, D>::from_coeffs_low_memory(
+            polynomials.clone(),
+            2,
+            true,
+            1,
+            1,
+            &mut TimingTree::default(),
+            None,
+        );
+
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_polynomial_batch(&batch)?;
+        let mut buffer = Buffer::new(buffer.bytes());
+        let batch = buffer.read_polynomial_batch::<F, C, D>()?;
+        for (i, polynomial) in polynomials.iter().enumerate() {
+            assert_eq!(batch.polynomial(i).as_ref(), polynomial);
+        }
+
+        Ok(())
+    }
+}
@@ -9,6 +9,11 @@ pub struct StarkConfig {
     pub num_challenges: usize,
 
     pub fri_config: FriConfig,
+
+    /// Whether proofs should hide the trace. If so, trace and permutation polynomials are blinded
+    /// with random multiples of the vanishing polynomial of the trace domain, which doubles their
+    /// degree, and Merkle leaves are salted.
+    pub zero_knowledge: bool,
 }
 
 impl StarkConfig {
@@ -25,10 +30,27 @@ impl StarkConfig {
                 reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
                 num_query_rounds: 84,
             },
+            zero_knowledge: false,
         }
     }
 
+    /// Like `standard_fast_config`, but with zero-knowledge enabled.
+    pub fn standard_fast_zk_config() -> Self {
+        Self {
+            zero_knowledge: true,
+            ..Self::standard_fast_config()
+        }
+    }
+
+    /// The log of the degree bound of committed polynomials, for a trace of length
+    /// `2^degree_bits`.
+    pub(crate) fn committed_degree_bits(&self, degree_bits: usize) -> usize {
+        degree_bits + self.zero_knowledge as usize
+    }
+
+    /// The FRI parameters for a trace of length `2^degree_bits`.
     pub(crate) fn fri_params(&self, degree_bits: usize) -> FriParams {
-        self.fri_config.fri_params(degree_bits, false)
+        self.fri_config
+            .fri_params(self.committed_degree_bits(degree_bits), self.zero_knowledge)
     }
 }
@@ -0,0 +1,850 @@
+//! Cross-table lookups, which check that the rows of some columns of several STARKs, or tables,
+//! are a permutation of the rows of some columns of another table.
+//!
+//! Tables are identified by their index in a `MultiStark`. For each cross-table lookup and
+//! challenge, every table involved commits to a running product `Z` of the combined rows it
+//! contributes. The last values of these `Z`s are opened, and the verifier checks that the
+//! products of the looking tables match the product of the looked table.
+
+use std::borrow::Borrow;
+use std::iter::repeat;
+
+use anyhow::{ensure, Result};
+use itertools::Itertools;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::target::Target;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::config::GenericConfig;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::permutation::{PermutationChallenge, PermutationChallengeSet};
+use crate::proof::{StarkProof, StarkProofTarget};
+use crate::stark::Stark;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// Represent a linear combination of columns.
+#[derive(Clone, Debug)]
+pub struct Column<F: Field> {
+    linear_combination: Vec<(usize, F)>,
+    constant: F,
+}
+
+impl<F: Field> Column<F> {
+    pub fn single(c: usize) -> Self {
+        Self {
+            linear_combination: vec![(c, F::ONE)],
+            constant: F::ZERO,
+        }
+    }
+
+    pub fn singles<I: IntoIterator<Item = impl Borrow<usize>>>(
+        cs: I,
+    ) -> impl Iterator<Item = Self> {
+        cs.into_iter().map(|c| Self::single(*c.borrow()))
+    }
+
+    pub fn constant(constant: F) -> Self {
+        Self {
+            linear_combination: vec![],
+            constant,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::constant(F::ZERO)
+    }
+
+    pub fn one() -> Self {
+        Self::constant(F::ONE)
+    }
+
+    pub fn linear_combination_with_constant<I: IntoIterator<Item = (usize, F)>>(
+        iter: I,
+        constant: F,
+    ) -> Self {
+        let v = iter.into_iter().collect::<Vec<_>>();
+        assert!(!v.is_empty());
+        debug_assert_eq!(
+            v.iter().map(|(c, _)| c).unique().count(),
+            v.len(),
+            "Duplicate columns."
+        );
+        Self {
+            linear_combination: v,
+            constant,
+        }
+    }
+
+    pub fn linear_combination<I: IntoIterator<Item = (usize, F)>>(iter: I) -> Self {
+        Self::linear_combination_with_constant(iter, F::ZERO)
+    }
+
+    pub fn le_bits<I: IntoIterator<Item = impl Borrow<usize>>>(cs: I) -> Self {
+        Self::linear_combination(cs.into_iter().map(|c| *c.borrow()).zip(F::TWO.powers()))
+    }
+
+    pub fn le_bytes<I: IntoIterator<Item = impl Borrow<usize>>>(cs: I) -> Self {
+        Self::linear_combination(
+            cs.into_iter()
+                .map(|c| *c.borrow())
+                .zip(F::from_canonical_u16(256).powers()),
+        )
+    }
+
+    pub fn sum<I: IntoIterator<Item = impl Borrow<usize>>>(cs: I) -> Self {
+        Self::linear_combination(cs.into_iter().map(|c| *c.borrow()).zip(repeat(F::ONE)))
+    }
+
+    pub fn eval<FE, P, const D: usize>(&self, v: &[P]) -> P
+    where
+        FE: FieldExtension<D, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        self.linear_combination
+            .iter()
+            .map(|&(c, f)| v[c] * FE::from_basefield(f))
+            .sum::<P>()
+            + FE::from_basefield(self.constant)
+    }
+
+    /// Evaluate on an row of a table given in column-major form.
+    pub fn eval_table(&self, table: &[PolynomialValues<F>], row: usize) -> F {
+        self.linear_combination
+            .iter()
+            .map(|&(c, f)| table[c].values[row] * f)
+            .sum::<F>()
+            + self.constant
+    }
+
+    pub fn eval_circuit<const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        v: &[ExtensionTarget<D>],
+    ) -> ExtensionTarget<D>
+    where
+        F: RichField + Extendable<D>,
+    {
+        let pairs = self
+            .linear_combination
+            .iter()
+            .map(|&(c, f)| {
+                (
+                    v[c],
+                    builder.constant_extension(F::Extension::from_basefield(f)),
+                )
+            })
+            .collect::<Vec<_>>();
+        let constant = builder.constant_extension(F::Extension::from_basefield(self.constant));
+        builder.inner_product_extension(F::ONE, constant, pairs)
+    }
+}
+
+/// Some columns of a table, along with an optional filter selecting the rows which take part in a
+/// cross-table lookup. The filter should evaluate to 0 or 1 on every row.
+#[derive(Clone, Debug)]
+pub struct TableWithColumns<F: Field> {
+    /// The index of the table in its `MultiStark`.
+    table: usize,
+    columns: Vec<Column<F>>,
+    filter_column: Option<Column<F>>,
+}
+
+impl<F: Field> TableWithColumns<F> {
+    pub fn new(table: usize, columns: Vec<Column<F>>, filter_column: Option<Column<F>>) -> Self {
+        Self {
+            table,
+            columns,
+            filter_column,
+        }
+    }
+
+    /// The index of the table in its `MultiStark`.
+    pub fn table(&self) -> usize {
+        self.table
+    }
+
+    pub fn columns(&self) -> &[Column<F>] {
+        &self.columns
+    }
+
+    pub fn filter_column(&self) -> &Option<Column<F>> {
+        &self.filter_column
+    }
+}
+
+/// A lookup of the rows of some columns of the looking tables in some columns of the looked table.
+/// The rows of all looking tables, taken together, must be a permutation of the rows of the looked
+/// table.
+#[derive(Clone, Debug)]
+pub struct CrossTableLookup<F: Field> {
+    looking_tables: Vec<TableWithColumns<F>>,
+    looked_table: TableWithColumns<F>,
+    /// Default value if filters are not used.
+    default: Option<Vec<F>>,
+}
+
+impl<F: Field> CrossTableLookup<F> {
+    pub fn new(
+        looking_tables: Vec<TableWithColumns<F>>,
+        looked_table: TableWithColumns<F>,
+        default: Option<Vec<F>>,
+    ) -> Self {
+        assert!(looking_tables
+            .iter()
+            .all(|twc| twc.columns.len() == looked_table.columns.len()));
+        assert!(
+            looking_tables
+                .iter()
+                .all(|twc| twc.filter_column.is_none() == default.is_some())
+                && default.is_some() == looked_table.filter_column.is_none(),
+            "Default values should be provided iff there are no filter columns."
+        );
+        if let Some(default) = &default {
+            assert_eq!(default.len(), looked_table.columns.len());
+        }
+        Self {
+            looking_tables,
+            looked_table,
+            default,
+        }
+    }
+
+    pub fn looking_tables(&self) -> &[TableWithColumns<F>] {
+        &self.looking_tables
+    }
+
+    pub fn looked_table(&self) -> &TableWithColumns<F> {
+        &self.looked_table
+    }
+
+    /// The number of `Z` polynomials the given table commits to for the given lookups.
+    pub fn num_ctl_zs(ctls: &[Self], table: usize, num_challenges: usize) -> usize {
+        let mut num_ctls = 0;
+        for ctl in ctls {
+            let all_tables = std::iter::once(&ctl.looked_table).chain(&ctl.looking_tables);
+            num_ctls += all_tables.filter(|twc| twc.table == table).count();
+        }
+        num_ctls * num_challenges
+    }
+
+    /// Checks that every table referenced by the given lookups is smaller than `num_tables`.
+    pub(crate) fn check_tables(ctls: &[Self], num_tables: usize) -> Result<()> {
+        for ctl in ctls {
+            let all_tables = std::iter::once(&ctl.looked_table).chain(&ctl.looking_tables);
+            for twc in all_tables {
+                ensure!(
+                    twc.table < num_tables,
+                    "Cross-table lookup references table {} out of {}.",
+                    twc.table,
+                    num_tables
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cross-table lookup data for one table.
+#[derive(Clone, Default)]
+pub struct CtlData<F: Field> {
+    pub zs_columns: Vec<CtlZData<F>>,
+}
+
+/// Cross-table lookup data associated with one Z(x) polynomial.
+#[derive(Clone)]
+pub struct CtlZData<F: Field> {
+    pub z: PolynomialValues<F>,
+    pub challenge: PermutationChallenge<F>,
+    pub columns: Vec<Column<F>>,
+    pub filter_column: Option<Column<F>>,
+}
+
+impl<F: Field> CtlData<F> {
+    pub fn len(&self) -> usize {
+        self.zs_columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zs_columns.is_empty()
+    }
+
+    pub fn z_polys(&self) -> Vec<PolynomialValues<F>> {
+        self.zs_columns
+            .iter()
+            .map(|zs_columns| zs_columns.z.clone())
+            .collect()
+    }
+}
+
+/// Computes the cross-table lookup `Z` polynomials of every table, given their traces.
+pub fn cross_table_lookup_data<F: Field>(
+    trace_poly_values: &[Vec<PolynomialValues<F>>],
+    cross_table_lookups: &[CrossTableLookup<F>],
+    ctl_challenges: &PermutationChallengeSet<F>,
+) -> Vec<CtlData<F>> {
+    let mut ctl_data_per_table = vec![CtlData::default(); trace_poly_values.len()];
+    for CrossTableLookup {
+        looking_tables,
+        looked_table,
+        ..
+    } in cross_table_lookups
+    {
+        for &challenge in &ctl_challenges.challenges {
+            let zs_looking = looking_tables.iter().map(|table| {
+                partial_products(
+                    &trace_poly_values[table.table],
+                    &table.columns,
+                    &table.filter_column,
+                    challenge,
+                )
+            });
+            let z_looked = partial_products(
+                &trace_poly_values[looked_table.table],
+                &looked_table.columns,
+                &looked_table.filter_column,
+                challenge,
+            );
+
+            for (table, z) in looking_tables.iter().zip(zs_looking) {
+                ctl_data_per_table[table.table].zs_columns.push(CtlZData {
+                    z,
+                    challenge,
+                    columns: table.columns.clone(),
+                    filter_column: table.filter_column.clone(),
+                });
+            }
+            ctl_data_per_table[looked_table.table]
+                .zs_columns
+                .push(CtlZData {
+                    z: z_looked,
+                    challenge,
+                    columns: looked_table.columns.clone(),
+                    filter_column: looked_table.filter_column.clone(),
+                });
+        }
+    }
+    ctl_data_per_table
+}
+
+fn partial_products<F: Field>(
+    trace: &[PolynomialValues<F>],
+    columns: &[Column<F>],
+    filter_column: &Option<Column<F>>,
+    challenge: PermutationChallenge<F>,
+) -> PolynomialValues<F> {
+    let mut partial_prod = F::ONE;
+    let degree = trace[0].len();
+    let mut res = Vec::with_capacity(degree);
+    for i in 0..degree {
+        let filter = if let Some(column) = filter_column {
+            column.eval_table(trace, i)
+        } else {
+            F::ONE
+        };
+        if filter.is_one() {
+            let evals = columns
+                .iter()
+                .map(|c| c.eval_table(trace, i))
+                .collect::<Vec<_>>();
+            partial_prod *= challenge.combine(evals.iter());
+        } else {
+            assert_eq!(filter, F::ZERO, "Non-binary filter?")
+        };
+        res.push(partial_prod);
+    }
+    res.into()
+}
+
+#[derive(Clone)]
+pub struct CtlCheckVars<'a, F, FE, P, const D2: usize>
+where
+    F: Field,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+{
+    pub(crate) local_z: P,
+    pub(crate) next_z: P,
+    pub(crate) challenges: PermutationChallenge<F>,
+    pub(crate) columns: &'a [Column<F>],
+    pub(crate) filter_column: &'a Option<Column<F>>,
+}
+
+impl<'a, F: RichField + Extendable<D>, const D: usize>
+    CtlCheckVars<'a, F, F::Extension, F::Extension, D>
+{
+    /// Extracts the cross-table lookup data of the given table from its proof. The `Z`s are the
+    /// last auxiliary polynomials, after the first `num_auxiliary_polys` used by the table itself.
+    pub(crate) fn from_proof<C: GenericConfig<D, F = F>>(
+        table: usize,
+        proof: &StarkProof<F, C, D>,
+        cross_table_lookups: &'a [CrossTableLookup<F>],
+        ctl_challenges: &'a PermutationChallengeSet<F>,
+        num_auxiliary_polys: usize,
+    ) -> Vec<Self> {
+        let mut ctl_zs = {
+            let openings = &proof.openings;
+            let ctl_zs = openings
+                .auxiliary_polys
+                .iter()
+                .flatten()
+                .skip(num_auxiliary_polys);
+            let ctl_zs_next = openings
+                .auxiliary_polys_next
+                .iter()
+                .flatten()
+                .skip(num_auxiliary_polys);
+            ctl_zs.zip(ctl_zs_next)
+        };
+
+        let mut ctl_vars = vec![];
+        for CrossTableLookup {
+            looking_tables,
+            looked_table,
+            ..
+        } in cross_table_lookups
+        {
+            for &challenges in &ctl_challenges.challenges {
+                for looking_table in looking_tables {
+                    if looking_table.table == table {
+                        let (looking_z, looking_z_next) = ctl_zs.next().unwrap();
+                        ctl_vars.push(Self {
+                            local_z: *looking_z,
+                            next_z: *looking_z_next,
+                            challenges,
+                            columns: &looking_table.columns,
+                            filter_column: &looking_table.filter_column,
+                        });
+                    }
+                }
+
+                if looked_table.table == table {
+                    let (looked_z, looked_z_next) = ctl_zs.next().unwrap();
+                    ctl_vars.push(Self {
+                        local_z: *looked_z,
+                        next_z: *looked_z_next,
+                        challenges,
+                        columns: &looked_table.columns,
+                        filter_column: &looked_table.filter_column,
+                    });
+                }
+            }
+        }
+        assert!(ctl_zs.next().is_none());
+        ctl_vars
+    }
+}
+
+pub(crate) fn eval_cross_table_lookup_checks<F, FE, P, S, const D: usize, const D2: usize>(
+    vars: StarkEvaluationVars<FE, P, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    ctl_vars: &[CtlCheckVars<F, FE, P, D2>],
+    consumer: &mut ConstraintConsumer<P>,
+) where
+    F: RichField + Extendable<D>,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    for lookup_vars in ctl_vars {
+        let CtlCheckVars {
+            local_z,
+            next_z,
+            challenges,
+            columns,
+            filter_column,
+        } = lookup_vars;
+        let combine = |v: &[P]| -> P {
+            let evals = columns.iter().map(|c| c.eval(v)).collect::<Vec<_>>();
+            challenges.combine(evals.iter())
+        };
+        let filter = |v: &[P]| -> P {
+            if let Some(column) = filter_column {
+                column.eval(v)
+            } else {
+                P::ONES
+            }
+        };
+        let local_filter = filter(vars.local_values);
+        let next_filter = filter(vars.next_values);
+        let select = |filter, x| filter * x + P::ONES - filter;
+
+        // Check value of `Z(1)`
+        consumer.constraint_first_row(*local_z - select(local_filter, combine(vars.local_values)));
+        // Check `Z(gw) = combination * Z(w)`
+        consumer.constraint_transition(
+            *next_z - *local_z * select(next_filter, combine(vars.next_values)),
+        );
+    }
+}
+
+#[derive(Clone)]
+pub struct CtlCheckVarsTarget<'a, F: Field, const D: usize> {
+    pub(crate) local_z: ExtensionTarget<D>,
+    pub(crate) next_z: ExtensionTarget<D>,
+    pub(crate) challenges: PermutationChallenge<Target>,
+    pub(crate) columns: &'a [Column<F>],
+    pub(crate) filter_column: &'a Option<Column<F>>,
+}
+
+impl<'a, F: Field, const D: usize> CtlCheckVarsTarget<'a, F, D> {
+    /// Extracts the cross-table lookup data of the given table from its proof. The `Z`s are the
+    /// last auxiliary polynomials, after the first `num_auxiliary_polys` used by the table itself.
+    pub(crate) fn from_proof(
+        table: usize,
+        proof: &StarkProofTarget<D>,
+        cross_table_lookups: &'a [CrossTableLookup<F>],
+        ctl_challenges: &'a PermutationChallengeSet<Target>,
+        num_auxiliary_polys: usize,
+    ) -> Vec<Self> {
+        let mut ctl_zs = {
+            let openings = &proof.openings;
+            let ctl_zs = openings
+                .auxiliary_polys
+                .iter()
+                .flatten()
+                .skip(num_auxiliary_polys);
+            let ctl_zs_next = openings
+                .auxiliary_polys_next
+                .iter()
+                .flatten()
+                .skip(num_auxiliary_polys);
+            ctl_zs.zip(ctl_zs_next)
+        };
+
+        let mut ctl_vars = vec![];
+        for CrossTableLookup {
+            looking_tables,
+            looked_table,
+            ..
+        } in cross_table_lookups
+        {
+            for &challenges in &ctl_challenges.challenges {
+                for looking_table in looking_tables {
+                    if looking_table.table == table {
+                        let (looking_z, looking_z_next) = ctl_zs.next().unwrap();
+                        ctl_vars.push(Self {
+                            local_z: *looking_z,
+                            next_z: *looking_z_next,
+                            challenges,
+                            columns: &looking_table.columns,
+                            filter_column: &looking_table.filter_column,
+                        });
+                    }
+                }
+
+                if looked_table.table == table {
+                    let (looked_z, looked_z_next) = ctl_zs.next().unwrap();
+                    ctl_vars.push(Self {
+                        local_z: *looked_z,
+                        next_z: *looked_z_next,
+                        challenges,
+                        columns: &looked_table.columns,
+                        filter_column: &looked_table.filter_column,
+                    });
+                }
+            }
+        }
+        assert!(ctl_zs.next().is_none());
+        ctl_vars
+    }
+}
+
+pub(crate) fn eval_cross_table_lookup_checks_circuit<
+    S: Stark<F, D>,
+    F: RichField + Extendable<D>,
+    const D: usize,
+>(
+    builder: &mut CircuitBuilder<F, D>,
+    vars: StarkEvaluationTargets<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    ctl_vars: &[CtlCheckVarsTarget<F, D>],
+    consumer: &mut RecursiveConstraintConsumer<F, D>,
+) where
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    for lookup_vars in ctl_vars {
+        let CtlCheckVarsTarget {
+            local_z,
+            next_z,
+            challenges,
+            columns,
+            filter_column,
+        } = lookup_vars;
+
+        let one = builder.one_extension();
+        let local_filter = if let Some(column) = filter_column {
+            column.eval_circuit(builder, vars.local_values)
+        } else {
+            one
+        };
+        let next_filter = if let Some(column) = filter_column {
+            column.eval_circuit(builder, vars.next_values)
+        } else {
+            one
+        };
+        fn select<F: RichField + Extendable<D>, const D: usize>(
+            builder: &mut CircuitBuilder<F, D>,
+            filter: ExtensionTarget<D>,
+            x: ExtensionTarget<D>,
+        ) -> ExtensionTarget<D> {
+            let one = builder.one_extension();
+            let tmp = builder.sub_extension(one, filter);
+            builder.mul_add_extension(filter, x, tmp) // filter * x + 1 - filter
+        }
+
+        // Check value of `Z(1)`
+        let local_columns_eval = columns
+            .iter()
+            .map(|c| c.eval_circuit(builder, vars.local_values))
+            .collect::<Vec<_>>();
+        let combined_local = challenges.combine_circuit(builder, &local_columns_eval);
+        let selected_local = select(builder, local_filter, combined_local);
+        let first_row = builder.sub_extension(*local_z, selected_local);
+        consumer.constraint_first_row(builder, first_row);
+        // Check `Z(gw) = combination * Z(w)`
+        let next_columns_eval = columns
+            .iter()
+            .map(|c| c.eval_circuit(builder, vars.next_values))
+            .collect::<Vec<_>>();
+        let combined_next = challenges.combine_circuit(builder, &next_columns_eval);
+        let selected_next = select(builder, next_filter, combined_next);
+        let mut transition = builder.mul_extension(*local_z, selected_next);
+        transition = builder.sub_extension(*next_z, transition);
+        consumer.constraint_transition(builder, transition);
+    }
+}
+
+/// Checks that the products of the last values of the looking tables' `Z`s match those of the
+/// looked table, for every cross-table lookup and challenge.
+pub fn verify_cross_table_lookups<F: RichField + Extendable<D>, const D: usize>(
+    cross_table_lookups: &[CrossTableLookup<F>],
+    ctl_zs_lasts: &[Vec<F>],
+    degrees_bits: &[usize],
+    ctl_challenges: &PermutationChallengeSet<F>,
+) -> Result<()> {
+    let mut ctl_zs_openings = ctl_zs_lasts.iter().map(|v| v.iter()).collect::<Vec<_>>();
+    for CrossTableLookup {
+        looking_tables,
+        looked_table,
+        default,
+    } in cross_table_lookups
+    {
+        for challenge in &ctl_challenges.challenges {
+            let looking_zs_prod = looking_tables
+                .iter()
+                .map(|table| *ctl_zs_openings[table.table].next().unwrap())
+                .product::<F>();
+            let looked_z = *ctl_zs_openings[looked_table.table].next().unwrap();
+            let padded_looked_z = match default {
+                Some(default) => {
+                    let looking_degrees_sum = looking_tables
+                        .iter()
+                        .map(|table| 1 << degrees_bits[table.table])
+                        .sum::<u64>();
+                    let looked_degree = 1 << degrees_bits[looked_table.table];
+                    ensure!(
+                        looking_degrees_sum >= looked_degree,
+                        "Looked table is longer than the looking tables."
+                    );
+                    looked_z
+                        * challenge
+                            .combine(default.iter())
+                            .exp_u64(looking_degrees_sum - looked_degree)
+                }
+                None => looked_z,
+            };
+
+            ensure!(
+                looking_zs_prod == padded_looked_z,
+                "Cross-table lookup verification failed."
+            );
+        }
+    }
+    debug_assert!(ctl_zs_openings.iter_mut().all(|iter| iter.next().is_none()));
+
+    Ok(())
+}
+
+/// Circuit version of `verify_cross_table_lookups`.
+pub fn verify_cross_table_lookups_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    cross_table_lookups: &[CrossTableLookup<F>],
+    ctl_zs_lasts: &[Vec<Target>],
+    degrees_bits: &[usize],
+    ctl_challenges: &PermutationChallengeSet<Target>,
+) {
+    let mut ctl_zs_openings = ctl_zs_lasts.iter().map(|v| v.iter()).collect::<Vec<_>>();
+    for CrossTableLookup {
+        looking_tables,
+        looked_table,
+        default,
+    } in cross_table_lookups
+    {
+        for challenge in &ctl_challenges.challenges {
+            let looking_zs_prod = builder.mul_many(
+                looking_tables
+                    .iter()
+                    .map(|table| *ctl_zs_openings[table.table].next().unwrap()),
+            );
+            let looked_z = *ctl_zs_openings[looked_table.table].next().unwrap();
+            if let Some(default) = default {
+                let looking_degrees_sum = looking_tables
+                    .iter()
+                    .map(|table| 1 << degrees_bits[table.table])
+                    .sum::<u64>();
+                let looked_degree = 1 << degrees_bits[looked_table.table];
+                let default = default
+                    .iter()
+                    .map(|&x| builder.constant(x))
+                    .collect::<Vec<_>>();
+                let combined_default = challenge.combine_base_circuit(builder, &default);
+
+                let pad = builder.exp_u64(combined_default, looking_degrees_sum - looked_degree);
+                let padded_looked_z = builder.mul(looked_z, pad);
+                builder.connect(looking_zs_prod, padded_looked_z);
+            } else {
+                builder.connect(looking_zs_prod, looked_z);
+            }
+        }
+    }
+    debug_assert!(ctl_zs_openings.iter_mut().all(|iter| iter.next().is_none()));
+}
+
+/// Helpers to debug cross-table lookups on concrete traces, before proving them.
+pub mod testutils {
+    use std::collections::HashMap;
+
+    use plonky2::field::polynomial::PolynomialValues;
+    use plonky2::field::types::Field;
+
+    use crate::cross_table_lookup::{CrossTableLookup, TableWithColumns};
+
+    type MultiSet<F> = HashMap<Vec<F>, Vec<(usize, usize)>>;
+
+    /// Check that the provided traces and cross-table lookups are consistent.
+    pub fn check_ctls<F: Field>(
+        trace_poly_values: &[Vec<PolynomialValues<F>>],
+        cross_table_lookups: &[CrossTableLookup<F>],
+    ) {
+        for (i, ctl) in cross_table_lookups.iter().enumerate() {
+            check_ctl(trace_poly_values, ctl, i);
+        }
+    }
+
+    fn check_ctl<F: Field>(
+        trace_poly_values: &[Vec<PolynomialValues<F>>],
+        ctl: &CrossTableLookup<F>,
+        ctl_index: usize,
+    ) {
+        let CrossTableLookup {
+            looking_tables,
+            looked_table,
+            default,
+        } = ctl;
+
+        // Maps `m` with `(table, i) in m[row]` iff the `i`-th row of `table` is equal to `row` and
+        // the filter is 1. Without default values, the CTL check holds iff `looking_multiset == looked_multiset`.
+        let mut looking_multiset = MultiSet::<F>::new();
+        let mut looked_multiset = MultiSet::<F>::new();
+
+        for table in looking_tables {
+            process_table(trace_poly_values, table, &mut looking_multiset);
+        }
+        process_table(trace_poly_values, looked_table, &mut looked_multiset);
+
+        let empty = &vec![];
+        // Check that every row in the looking tables appears in the looked table the same number of times
+        // with some special logic for the default row.
+        for (row, looking_locations) in &looking_multiset {
+            let looked_locations = looked_multiset.get(row).unwrap_or(empty);
+            if let Some(default) = default {
+                if row == default {
+                    continue;
+                }
+            }
+            check_locations(looking_locations, looked_locations, ctl_index, row);
+        }
+        let extra_default_count = default.as_ref().map(|d| {
+            let looking_default_locations = looking_multiset.get(d).unwrap_or(empty);
+            let looked_default_locations = looked_multiset.get(d).unwrap_or(empty);
+            looking_default_locations
+                .len()
+                .checked_sub(looked_default_locations.len())
+                .unwrap_or_else(|| {
+                    // If underflow, panic. There should be more default rows in the looking side.
+                    check_locations(
+                        looking_default_locations,
+                        looked_default_locations,
+                        ctl_index,
+                        d,
+                    );
+                    unreachable!()
+                })
+        });
+        // Check that the number of extra default rows is correct.
+        if let Some(count) = extra_default_count {
+            assert_eq!(
+                count,
+                looking_tables
+                    .iter()
+                    .map(|table| trace_poly_values[table.table][0].len())
+                    .sum::<usize>()
+                    - trace_poly_values[looked_table.table][0].len()
+            );
+        }
+        // Check that every row in the looked tables appears in the looked table the same number of times.
+        for (row, looked_locations) in &looked_multiset {
+            let looking_locations = looking_multiset.get(row).unwrap_or(empty);
+            check_locations(looking_locations, looked_locations, ctl_index, row);
+        }
+    }
+
+    fn process_table<F: Field>(
+        trace_poly_values: &[Vec<PolynomialValues<F>>],
+        table: &TableWithColumns<F>,
+        multiset: &mut MultiSet<F>,
+    ) {
+        let trace = &trace_poly_values[table.table];
+        for i in 0..trace[0].len() {
+            let filter = if let Some(column) = &table.filter_column {
+                column.eval_table(trace, i)
+            } else {
+                F::ONE
+            };
+            if filter.is_one() {
+                let row = table
+                    .columns
+                    .iter()
+                    .map(|c| c.eval_table(trace, i))
+                    .collect::<Vec<_>>();
+                multiset.entry(row).or_default().push((table.table, i));
+            } else {
+                assert_eq!(filter, F::ZERO, "Non-binary filter?")
+            }
+        }
+    }
+
+    fn check_locations<F: Field>(
+        looking_locations: &[(usize, usize)],
+        looked_locations: &[(usize, usize)],
+        ctl_index: usize,
+        row: &[F],
+    ) {
+        if looking_locations.len() != looked_locations.len() {
+            panic!(
+                "CTL #{ctl_index}:\n\
+                 Row {row:?} is present {l0} times in the looking tables, but {l1} times in the looked table.\n\
+                 Looking locations (Table, Row index): {looking_locations:?}.\n\
+                 Looked locations (Table, Row index): {looked_locations:?}.",
+                l0 = looking_locations.len(),
+                l1 = looked_locations.len(),
+            );
+        }
+    }
+}
@@ -124,6 +124,8 @@ mod tests {
     use anyhow::Result;
     use plonky2::field::extension::Extendable;
     use plonky2::field::types::Field;
+    use plonky2::fri::reduction_strategies::FriReductionStrategy;
+    use plonky2::fri::FriConfig;
     use plonky2::hash::hash_types::RichField;
     use plonky2::iop::witness::PartialWitness;
     use plonky2::plonk::circuit_builder::CircuitBuilder;
@@ -135,7 +137,8 @@ mod tests {
 
     use crate::config::StarkConfig;
     use crate::fibonacci_stark::FibonacciStark;
-    use crate::proof::StarkProofWithPublicInputs;
+    use crate::preprocessed::StarkVerifyingKey;
+    use crate::proof::{CompressedStarkProofWithPublicInputs, StarkProofWithPublicInputs};
     use crate::prover::prove;
     use crate::recursive_verifier::{
         add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
@@ -172,6 +175,126 @@ mod tests {
         verify_stark_proof(stark, proof, &config)
     }
 
+    #[test]
+    fn test_fibonacci_stark_zk() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_zk_config();
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let prove_trace = || {
+            prove::<F, C, S, D>(
+                stark,
+                &config,
+                trace.clone(),
+                public_inputs,
+                &mut TimingTree::default(),
+            )
+        };
+        let proof = prove_trace()?;
+        assert_eq!(proof.proof.recover_degree_bits(&config), 5);
+
+        // Blinding is randomized, so proving the same trace twice gives different openings.
+        let other_proof = prove_trace()?;
+        assert_ne!(
+            proof.proof.openings.local_values,
+            other_proof.proof.openings.local_values
+        );
+
+        verify_stark_proof(stark, proof.clone(), &config)?;
+        recursive_proof::<F, C, S, C, D>(stark, proof, &config, false)
+    }
+
+    #[test]
+    fn test_fibonacci_stark_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let fixed_arities_config = StarkConfig {
+            fri_config: FriConfig {
+                reduction_strategy: FriReductionStrategy::Fixed(vec![1, 1]),
+                ..StarkConfig::standard_fast_config().fri_config
+            },
+            ..StarkConfig::standard_fast_config()
+        };
+        for config in [
+            StarkConfig::standard_fast_config(),
+            StarkConfig::standard_fast_zk_config(),
+            fixed_arities_config,
+        ] {
+            let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+            let proof = prove::<F, C, S, D>(
+                stark,
+                &config,
+                trace,
+                public_inputs,
+                &mut TimingTree::default(),
+            )?;
+            let proof_bytes = proof.to_bytes(&config)?;
+            let num_proof_bytes = proof_bytes.len();
+            assert_eq!(
+                StarkProofWithPublicInputs::from_bytes(proof_bytes.clone(), &config)?,
+                proof
+            );
+
+            // Verify that `decompress ∘ compress = identity`, including through serialization.
+            let verifying_key = StarkVerifyingKey::new(&stark, &config);
+            let compressed_proof = proof.clone().compress(&stark, &verifying_key, &config);
+            let compressed_proof_bytes = compressed_proof.to_bytes()?;
+            assert!(compressed_proof_bytes.len() < num_proof_bytes);
+            let compressed_proof_from_bytes =
+                CompressedStarkProofWithPublicInputs::from_bytes(compressed_proof_bytes, &config)?;
+            assert_eq!(compressed_proof_from_bytes, compressed_proof);
+            let decompressed_proof =
+                compressed_proof_from_bytes.decompress(&stark, &verifying_key, &config)?;
+            assert_eq!(decompressed_proof, proof);
+            verify_stark_proof(stark, decompressed_proof, &config)?;
+
+            // Encodings with an unknown version are rejected.
+            let mut bad_proof_bytes = proof_bytes.clone();
+            bad_proof_bytes[0] += 1;
+            assert!(
+                StarkProofWithPublicInputs::<F, C, D>::from_bytes(bad_proof_bytes, &config)
+                    .is_err()
+            );
+
+            // Encodings with a corrupted trace length are rejected rather than panicking: lengths
+            // too large for their LDE to be indexed, and with fixed FRI arities, lengths shorter
+            // than the arities.
+            let mut bad_degree_bits = vec![u8::MAX];
+            if let FriReductionStrategy::Fixed(_) = config.fri_config.reduction_strategy {
+                bad_degree_bits.extend([0, 1]);
+            }
+            for degree_bits in bad_degree_bits {
+                let mut bad_proof_bytes = proof_bytes.clone();
+                bad_proof_bytes[1] = degree_bits;
+                assert!(StarkProofWithPublicInputs::<F, C, D>::from_bytes(
+                    bad_proof_bytes,
+                    &config
+                )
+                .is_err());
+                let mut bad_proof_bytes = compressed_proof.to_bytes()?;
+                bad_proof_bytes[1] = degree_bits;
+                assert!(CompressedStarkProofWithPublicInputs::<F, C, D>::from_bytes(
+                    bad_proof_bytes,
+                    &config
+                )
+                .is_err());
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_fibonacci_stark_degree() -> Result<()> {
         const D: usize = 2;
@@ -238,6 +361,7 @@ mod tests {
         [(); S::COLUMNS]:,
         [(); S::PUBLIC_INPUTS]:,
         [(); C::Hasher::HASH_SIZE]:,
+        [(); InnerC::Hasher::HASH_SIZE]:,
     {
         let circuit_config = CircuitConfig::standard_recursion_config();
         let mut builder = CircuitBuilder::<F, D>::new(circuit_config);
@@ -5,11 +5,16 @@
 
 pub mod config;
 pub mod constraint_consumer;
+pub mod cross_table_lookup;
 mod get_challenges;
+pub mod lookup;
+pub mod multi_stark;
 pub mod permutation;
+pub mod preprocessed;
 pub mod proof;
 pub mod prover;
 pub mod recursive_verifier;
+pub mod serialization;
 pub mod stark;
 pub mod stark_testing;
 pub mod util;
@@ -19,3 +24,11 @@ pub mod verifier;
 
 #[cfg(test)]
 pub mod fibonacci_stark;
+#[cfg(test)]
+pub mod range_check_stark;
+#[cfg(test)]
+pub mod round_constants_stark;
+#[cfg(test)]
+pub mod squares_multi_stark;
+#[cfg(test)]
+pub mod window_stark;
@@ -0,0 +1,337 @@
+//! Lookup arguments, based on logarithmic derivatives ("logUp").
+//!
+//! The values of some columns `x_j` are all contained in a table column `t` iff there is a column
+//! of frequencies `m` such that, for a random challenge `alpha`,
+//! `sum_i sum_j 1 / (alpha - x_j(i)) = sum_i m(i) / (alpha - t(i))`.
+//!
+//! The terms on the left are summed into helper columns, each holding the sum for a batch of
+//! looked up columns, and a last column `Z` holds the running sum of the differences, with
+//! `Z(g x) = Z(x) + sum_k h_k(g x) - m(g x) / (alpha - t(g x))` on all rows. Since the trace domain
+//! is cyclic, this can only hold if the differences sum to zero.
+
+use std::collections::HashMap;
+
+use maybe_rayon::*;
+use plonky2::field::batch_util::batch_multiply_inplace;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::target::Target;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2_util::ceil_div_usize;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::stark::Stark;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// A lookup of the values of some columns in a table column. If a filter column is given, only
+/// the rows where it is nonzero are looked up, with the filter value as multiplicity.
+pub struct Lookup {
+    /// Columns whose values should be contained in the table.
+    pub columns: Vec<usize>,
+    pub table_column: usize,
+    /// Column holding, in each row, the number of times the table value of that row is looked up.
+    /// It can be computed with `Lookup::frequencies`.
+    pub frequencies_column: usize,
+    pub filter_column: Option<usize>,
+}
+
+impl Lookup {
+    pub fn new(columns: Vec<usize>, table_column: usize, frequencies_column: usize) -> Self {
+        Self {
+            columns,
+            table_column,
+            frequencies_column,
+            filter_column: None,
+        }
+    }
+
+    pub fn with_filter(
+        columns: Vec<usize>,
+        table_column: usize,
+        frequencies_column: usize,
+        filter_column: usize,
+    ) -> Self {
+        Self {
+            filter_column: Some(filter_column),
+            ..Self::new(columns, table_column, frequencies_column)
+        }
+    }
+
+    /// The number of helper columns, including `Z`, used by each instance of this lookup.
+    pub(crate) fn num_helper_columns(&self, batch_size: usize) -> usize {
+        ceil_div_usize(self.columns.len(), batch_size) + 1
+    }
+
+    /// Computes the frequencies column of this lookup for the given trace. Each looked up value is
+    /// counted in the first row of the table containing it.
+    pub fn frequencies<F: PrimeField64>(
+        &self,
+        trace_poly_values: &[PolynomialValues<F>],
+    ) -> PolynomialValues<F> {
+        let degree = trace_poly_values[0].len();
+        let mut table_rows = HashMap::new();
+        for (i, t) in trace_poly_values[self.table_column]
+            .values
+            .iter()
+            .enumerate()
+        {
+            table_rows.entry(t.to_canonical_u64()).or_insert(i);
+        }
+
+        let mut frequencies = vec![F::ZERO; degree];
+        for &column in &self.columns {
+            for i in 0..degree {
+                let multiplicity = self
+                    .filter_column
+                    .map_or(F::ONE, |filter| trace_poly_values[filter].values[i]);
+                if multiplicity.is_zero() {
+                    continue;
+                }
+                let x = trace_poly_values[column].values[i];
+                let row = table_rows.get(&x.to_canonical_u64()).unwrap_or_else(|| {
+                    panic!("Value {} of column {} is not in the table", x, column)
+                });
+                frequencies[*row] += multiplicity;
+            }
+        }
+        PolynomialValues::new(frequencies)
+    }
+}
+
+/// Computes the helper columns of all lookups, for each challenge. For each challenge and each
+/// lookup in turn, these are the helper columns of each batch of looked up columns, then `Z`.
+pub(crate) fn compute_lookup_helper_polys<F, S, const D: usize>(
+    stark: &S,
+    trace_poly_values: &[PolynomialValues<F>],
+    lookup_challenges: &[F],
+) -> Vec<PolynomialValues<F>>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+{
+    let lookups = stark.lookups();
+    let batch_size = stark.lookup_batch_size();
+    lookup_challenges
+        .iter()
+        .flat_map(|&alpha| {
+            lookups
+                .iter()
+                .flat_map(|lookup| {
+                    compute_lookup_helper_polys_single(lookup, trace_poly_values, alpha, batch_size)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Computes the helper columns and `Z` for a single lookup and challenge.
+fn compute_lookup_helper_polys_single<F: Field>(
+    lookup: &Lookup,
+    trace_poly_values: &[PolynomialValues<F>],
+    alpha: F,
+    batch_size: usize,
+) -> Vec<PolynomialValues<F>> {
+    let degree = trace_poly_values[0].len();
+    // Computes `multiplicity / (alpha - column)` for each row.
+    let log_derivative = |column: usize, multiplicities: Option<&[F]>| {
+        let denominators = trace_poly_values[column]
+            .values
+            .iter()
+            .map(|&x| alpha - x)
+            .collect::<Vec<_>>();
+        let mut inverses = F::batch_multiplicative_inverse(&denominators);
+        if let Some(multiplicities) = multiplicities {
+            batch_multiply_inplace(&mut inverses, multiplicities);
+        }
+        inverses
+    };
+    let filter = lookup
+        .filter_column
+        .map(|filter| trace_poly_values[filter].values.as_slice());
+
+    let mut helper_polys = lookup
+        .columns
+        .par_chunks(batch_size)
+        .map(|batch| {
+            let mut sum = vec![F::ZERO; degree];
+            for &column in batch {
+                for (s, x) in sum.iter_mut().zip(log_derivative(column, filter)) {
+                    *s += x;
+                }
+            }
+            PolynomialValues::new(sum)
+        })
+        .collect::<Vec<_>>();
+
+    let table_terms = log_derivative(
+        lookup.table_column,
+        Some(&trace_poly_values[lookup.frequencies_column].values),
+    );
+    let mut z = Vec::with_capacity(degree);
+    let mut acc = F::ZERO;
+    for (i, table_term) in table_terms.into_iter().enumerate() {
+        acc += helper_polys.iter().map(|h| h.values[i]).sum::<F>() - table_term;
+        z.push(acc);
+    }
+    helper_polys.push(PolynomialValues::new(z));
+    helper_polys
+}
+
+pub struct LookupCheckVars<F, FE, P, const D2: usize>
+where
+    F: Field,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+{
+    pub(crate) local_values: Vec<P>,
+    pub(crate) next_values: Vec<P>,
+    pub(crate) challenges: Vec<F>,
+}
+
+pub(crate) fn eval_lookup_checks<F, FE, P, S, const D: usize, const D2: usize>(
+    stark: &S,
+    vars: StarkEvaluationVars<FE, P, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    lookup_vars: LookupCheckVars<F, FE, P, D2>,
+    consumer: &mut ConstraintConsumer<P>,
+) where
+    F: RichField + Extendable<D>,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let LookupCheckVars {
+        local_values,
+        next_values,
+        challenges,
+    } = lookup_vars;
+    let lookups = stark.lookups();
+    let batch_size = stark.lookup_batch_size();
+
+    let mut start = 0;
+    for alpha in challenges {
+        let alpha = P::from(FE::from_basefield(alpha));
+        for lookup in &lookups {
+            let num_helpers = lookup.num_helper_columns(batch_size) - 1;
+            let helpers = &local_values[start..start + num_helpers];
+            let next_helpers = &next_values[start..start + num_helpers];
+            let z = local_values[start + num_helpers];
+            let next_z = next_values[start + num_helpers];
+            start += num_helpers + 1;
+
+            // Each helper `h` holds `sum_j f / (alpha - x_j)` for a batch of columns `x_j`.
+            let filter = lookup
+                .filter_column
+                .map_or(P::ONES, |filter| vars.local_values[filter]);
+            for (&h, batch) in helpers.iter().zip(lookup.columns.chunks(batch_size)) {
+                let denominators = batch
+                    .iter()
+                    .map(|&column| alpha - vars.local_values[column])
+                    .collect::<Vec<_>>();
+                let numerator = (0..denominators.len())
+                    .map(|j| {
+                        denominators
+                            .iter()
+                            .enumerate()
+                            .filter(|&(k, _)| k != j)
+                            .map(|(_, &d)| d)
+                            .product::<P>()
+                    })
+                    .sum::<P>()
+                    * filter;
+                consumer.constraint(h * denominators.into_iter().product::<P>() - numerator);
+            }
+
+            // Z(g x) = Z(x) + sum_k h_k(g x) - m(g x) / (alpha - t(g x))
+            let table_denominator = alpha - vars.next_values[lookup.table_column];
+            let frequency = vars.next_values[lookup.frequencies_column];
+            consumer.constraint(
+                (next_z - z - next_helpers.iter().copied().sum::<P>()) * table_denominator
+                    + frequency,
+            );
+        }
+    }
+}
+
+pub struct LookupCheckDataTarget<const D: usize> {
+    pub(crate) local_values: Vec<ExtensionTarget<D>>,
+    pub(crate) next_values: Vec<ExtensionTarget<D>>,
+    pub(crate) challenges: Vec<Target>,
+}
+
+pub(crate) fn eval_lookup_checks_circuit<F, S, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: &S,
+    vars: StarkEvaluationTargets<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    lookup_data: LookupCheckDataTarget<D>,
+    consumer: &mut RecursiveConstraintConsumer<F, D>,
+) where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let LookupCheckDataTarget {
+        local_values,
+        next_values,
+        challenges,
+    } = lookup_data;
+    let lookups = stark.lookups();
+    let batch_size = stark.lookup_batch_size();
+    let one = builder.one_extension();
+
+    let mut start = 0;
+    for alpha in challenges {
+        let alpha = builder.convert_to_ext(alpha);
+        for lookup in &lookups {
+            let num_helpers = lookup.num_helper_columns(batch_size) - 1;
+            let helpers = &local_values[start..start + num_helpers];
+            let next_helpers = &next_values[start..start + num_helpers];
+            let z = local_values[start + num_helpers];
+            let next_z = next_values[start + num_helpers];
+            start += num_helpers + 1;
+
+            // Each helper `h` holds `sum_j f / (alpha - x_j)` for a batch of columns `x_j`.
+            let filter = lookup
+                .filter_column
+                .map_or(one, |filter| vars.local_values[filter]);
+            for (&h, batch) in helpers.iter().zip(lookup.columns.chunks(batch_size)) {
+                let denominators = batch
+                    .iter()
+                    .map(|&column| builder.sub_extension(alpha, vars.local_values[column]))
+                    .collect::<Vec<_>>();
+                let terms = (0..denominators.len())
+                    .map(|j| {
+                        let others = denominators
+                            .iter()
+                            .enumerate()
+                            .filter(|&(k, _)| k != j)
+                            .map(|(_, &d)| d)
+                            .collect::<Vec<_>>();
+                        builder.mul_many_extension(others)
+                    })
+                    .collect::<Vec<_>>();
+                let numerator = builder.add_many_extension(terms);
+                let numerator = builder.mul_extension(numerator, filter);
+                let denominator = builder.mul_many_extension(denominators);
+                let constraint = builder.mul_sub_extension(h, denominator, numerator);
+                consumer.constraint(builder, constraint);
+            }
+
+            // Z(g x) = Z(x) + sum_k h_k(g x) - m(g x) / (alpha - t(g x))
+            let table_denominator =
+                builder.sub_extension(alpha, vars.next_values[lookup.table_column]);
+            let frequency = vars.next_values[lookup.frequencies_column];
+            let next_helpers_sum = builder.add_many_extension(next_helpers);
+            let diff = builder.sub_extension(next_z, z);
+            let diff = builder.sub_extension(diff, next_helpers_sum);
+            let constraint = builder.mul_add_extension(diff, table_denominator, frequency);
+            consumer.constraint(builder, constraint);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! STARKs made of several tables, each proven by its own `Stark`, which are tied together by
+//! cross-table lookups.
+//!
+//! All tables are proven with a shared challenger. Their trace commitments are observed first, so
+//! that the cross-table lookup challenges depend on every table, and each table is then proven in
+//! turn.
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+
+use crate::cross_table_lookup::CrossTableLookup;
+use crate::stark::Stark;
+
+/// A set of tables, each with its own `Stark`, and the cross-table lookups between them. Tables are
+/// identified by their index, from `0` to `num_tables() - 1`.
+pub trait MultiStark<F: RichField + Extendable<D>, const D: usize> {
+    /// The number of tables.
+    fn num_tables(&self) -> usize;
+
+    /// The cross-table lookups between the tables.
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>>;
+
+    /// Calls the visitor on the `Stark` of the given table. As the tables' `Stark`s have different
+    /// types, this is how generic code gets access to them, e.g.
+    /// ```ignore
+    /// match table {
+    ///     0 => visitor.visit(&self.cpu_stark),
+    ///     1 => visitor.visit(&self.memory_stark),
+    ///     _ => panic!("Unknown table {table}"),
+    /// }
+    /// ```
+    /// If the field is generic, the implementation needs `[(); S::COLUMNS]:` and
+    /// `[(); S::PUBLIC_INPUTS]:` bounds for the `Stark` of each table.
+    fn visit_table<V: TableVisitor<F, D>>(&self, table: usize, visitor: V) -> V::Output;
+}
+
+/// An operation on the `Stark` of one table of a `MultiStark`.
+pub trait TableVisitor<F: RichField + Extendable<D>, const D: usize> {
+    type Output;
+
+    fn visit<S: Stark<F, D>>(self, stark: &S) -> Self::Output
+    where
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:;
+}
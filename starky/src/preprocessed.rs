@@ -0,0 +1,147 @@
+//! Commitments to the preprocessed columns of a STARK, which only depend on the STARK itself and
+//! can thus be computed once and shared by all of its proofs.
+
+use anyhow::{ensure, Result};
+use maybe_rayon::*;
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleCap;
+use plonky2::plonk::config::{GenericConfig, Hasher};
+use plonky2::timed;
+use plonky2::util::serialization::Buffer;
+use plonky2::util::timing::TimingTree;
+use plonky2_util::log2_strict;
+
+use crate::config::StarkConfig;
+use crate::serialization::{read_stark_verifying_key, write_stark_verifying_key};
+use crate::stark::Stark;
+
+/// Data needed by the prover of a STARK which doesn't depend on the witness.
+pub struct StarkProverData<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    /// Commitment to the preprocessed columns, if the STARK has any.
+    pub preprocessed_commitment: Option<PolynomialBatch<F, C, D>>,
+    pub verifying_key: StarkVerifyingKey<F, C, D>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    StarkProverData<F, C, D>
+{
+    pub fn new<S: Stark<F, D>>(stark: &S, config: &StarkConfig, timing: &mut TimingTree) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let preprocessed_columns = stark
+            .uses_preprocessed_columns()
+            .then(|| stark.preprocessed_columns());
+        let degree_bits = preprocessed_columns.as_ref().map(|preprocessed_columns| {
+            assert_eq!(
+                preprocessed_columns.len(),
+                S::PREPROCESSED_COLUMNS,
+                "Wrong number of preprocessed columns"
+            );
+            log2_strict(preprocessed_columns[0].len())
+        });
+        let preprocessed_commitment = preprocessed_columns.map(|preprocessed_columns| {
+            timed!(
+                timing,
+                "compute preprocessed commitment",
+                commit_preprocessed_polys(preprocessed_columns, config, timing)
+            )
+        });
+        let verifying_key = StarkVerifyingKey {
+            preprocessed_cap: preprocessed_commitment
+                .as_ref()
+                .map(|commitment| commitment.merkle_tree.cap.clone()),
+            degree_bits,
+        };
+        Self {
+            preprocessed_commitment,
+            verifying_key,
+        }
+    }
+}
+
+/// Data needed by the verifier of a STARK which doesn't depend on the proof.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StarkVerifyingKey<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    /// Merkle cap of LDEs of preprocessed values, if the STARK has any.
+    pub preprocessed_cap: Option<MerkleCap<F, C::Hasher>>,
+    /// The log of the length of the trace, if it is fixed by the preprocessed columns.
+    pub degree_bits: Option<usize>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    StarkVerifyingKey<F, C, D>
+{
+    pub fn new<S: Stark<F, D>>(stark: &S, config: &StarkConfig) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        StarkProverData::new(stark, config, &mut TimingTree::default()).verifying_key
+    }
+
+    /// Checks that a proof of a trace of length `2^degree_bits` can be verified against this key.
+    pub fn check_degree_bits(&self, degree_bits: usize) -> Result<()> {
+        if let Some(expected_degree_bits) = self.degree_bits {
+            ensure!(
+                degree_bits == expected_degree_bits,
+                "Proof is for a trace of length 2^{}, but the preprocessed columns of the verifying key have length 2^{}.",
+                degree_bits,
+                expected_degree_bits
+            );
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        write_stark_verifying_key(&mut buffer, self)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, config: &StarkConfig) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let verifying_key = read_stark_verifying_key(&mut buffer, config)?;
+        Ok(verifying_key)
+    }
+}
+
+/// Commits to the preprocessed columns. They are public, so they are never blinded, but they are
+/// still extended to the degree bound of committed polynomials so that all oracles share the same
+/// LDE domain.
+fn commit_preprocessed_polys<F, C, const D: usize>(
+    values: Vec<PolynomialValues<F>>,
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> PolynomialBatch<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let degree = values[0].len();
+    assert!(
+        values.iter().all(|column| column.len() == degree),
+        "Preprocessed columns have different lengths"
+    );
+    let committed_degree = 1 << config.committed_degree_bits(log2_strict(degree));
+    let coeffs = values
+        .into_par_iter()
+        .map(|values| values.ifft().padded(committed_degree))
+        .collect();
+    PolynomialBatch::from_coeffs(
+        coeffs,
+        config.fri_config.rate_bits,
+        false,
+        config.fri_config.cap_height,
+        timing,
+        None,
+    )
+}
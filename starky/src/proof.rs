@@ -1,3 +1,4 @@
+use anyhow::{ensure, Result};
 use itertools::Itertools;
 use maybe_rayon::*;
 use plonky2::field::extension::{Extendable, FieldExtension};
@@ -12,17 +13,28 @@ use plonky2::hash::hash_types::{MerkleCapTarget, RichField};
 use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
-use plonky2::plonk::config::GenericConfig;
+use plonky2::plonk::config::{GenericConfig, Hasher};
+use plonky2::plonk::plonk_common::salt_size;
+use plonky2::util::serialization::Buffer;
 
 use crate::config::StarkConfig;
 use crate::permutation::PermutationChallengeSet;
+use crate::preprocessed::StarkVerifyingKey;
+use crate::serialization::{
+    read_compressed_stark_proof_with_public_inputs, read_multi_proof,
+    read_stark_proof_with_public_inputs, write_compressed_stark_proof_with_public_inputs,
+    write_multi_proof, write_stark_proof_with_public_inputs,
+};
+use crate::stark::Stark;
+use crate::util::row_offset_power;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct StarkProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
     /// Merkle cap of LDEs of trace values.
     pub trace_cap: MerkleCap<F, C::Hasher>,
-    /// Merkle cap of LDEs of permutation Z values.
-    pub permutation_zs_cap: Option<MerkleCap<F, C::Hasher>>,
+    /// Merkle cap of LDEs of permutation Z values, lookup helper columns and cross-table lookup Z
+    /// values.
+    pub auxiliary_polys_cap: Option<MerkleCap<F, C::Hasher>>,
     /// Merkle cap of LDEs of trace values.
     pub quotient_polys_cap: MerkleCap<F, C::Hasher>,
     /// Purported values of each polynomial at the challenge point.
@@ -39,13 +51,38 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> S
             .evals_proofs[0]
             .1;
         let lde_bits = config.fri_config.cap_height + initial_merkle_proof.siblings.len();
-        lde_bits - config.fri_config.rate_bits
+        lde_bits - config.fri_config.rate_bits - config.zero_knowledge as usize
+    }
+
+    /// Compresses the opening proof, given the FRI query indices.
+    pub fn compress(
+        self,
+        indices: &[usize],
+        config: &StarkConfig,
+    ) -> CompressedStarkProof<F, C, D> {
+        let degree_bits = self.recover_degree_bits(config);
+        let StarkProof {
+            trace_cap,
+            auxiliary_polys_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof,
+        } = self;
+
+        CompressedStarkProof {
+            degree_bits,
+            trace_cap,
+            auxiliary_polys_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof: opening_proof.compress::<C>(indices, &config.fri_params(degree_bits)),
+        }
     }
 }
 
 pub struct StarkProofTarget<const D: usize> {
     pub trace_cap: MerkleCapTarget,
-    pub permutation_zs_cap: Option<MerkleCapTarget>,
+    pub auxiliary_polys_cap: Option<MerkleCapTarget>,
     pub quotient_polys_cap: MerkleCapTarget,
     pub openings: StarkOpeningSetTarget<D>,
     pub opening_proof: FriProofTarget<D>,
@@ -59,11 +96,11 @@ impl<const D: usize> StarkProofTarget<D> {
             .evals_proofs[0]
             .1;
         let lde_bits = config.fri_config.cap_height + initial_merkle_proof.siblings.len();
-        lde_bits - config.fri_config.rate_bits
+        lde_bits - config.fri_config.rate_bits - config.zero_knowledge as usize
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct StarkProofWithPublicInputs<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -74,24 +111,179 @@ pub struct StarkProofWithPublicInputs<
     pub public_inputs: Vec<F>,
 }
 
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    StarkProofWithPublicInputs<F, C, D>
+{
+    /// Compresses the proof, which must be a proof of the given STARK.
+    pub fn compress<S: Stark<F, D>>(
+        self,
+        stark: &S,
+        verifying_key: &StarkVerifyingKey<F, C, D>,
+        config: &StarkConfig,
+    ) -> CompressedStarkProofWithPublicInputs<F, C, D> {
+        let degree_bits = self.proof.recover_degree_bits(config);
+        let indices = self.fri_query_indices(stark, verifying_key, config, degree_bits);
+        CompressedStarkProofWithPublicInputs {
+            proof: self.proof.compress(&indices, config),
+            public_inputs: self.public_inputs,
+        }
+    }
+
+    pub fn to_bytes(&self, config: &StarkConfig) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        write_stark_proof_with_public_inputs(&mut buffer, self, config)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, config: &StarkConfig) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let proof = read_stark_proof_with_public_inputs(&mut buffer, config)?;
+        Ok(proof)
+    }
+}
+
 pub struct StarkProofWithPublicInputsTarget<const D: usize> {
     pub proof: StarkProofTarget<D>,
     pub public_inputs: Vec<Target>,
 }
 
+/// A proof of a `MultiStark`, made of one STARK proof per table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MultiProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub stark_proofs: Vec<StarkProofWithPublicInputs<F, C, D>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> MultiProof<F, C, D> {
+    /// Recover the length of the trace of each table from a multi-table proof and a STARK config.
+    pub fn degree_bits(&self, config: &StarkConfig) -> Vec<usize> {
+        self.stark_proofs
+            .iter()
+            .map(|proof_with_pis| proof_with_pis.proof.recover_degree_bits(config))
+            .collect()
+    }
+
+    pub fn to_bytes(&self, config: &StarkConfig) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        write_multi_proof(&mut buffer, self, config)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, config: &StarkConfig) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let proof = read_multi_proof(&mut buffer, config)?;
+        Ok(proof)
+    }
+}
+
+pub struct MultiProofTarget<const D: usize> {
+    pub stark_proofs: Vec<StarkProofWithPublicInputsTarget<D>>,
+}
+
+impl<const D: usize> MultiProofTarget<D> {
+    /// Recover the length of the trace of each table from a multi-table proof and a STARK config.
+    pub fn degree_bits(&self, config: &StarkConfig) -> Vec<usize> {
+        self.stark_proofs
+            .iter()
+            .map(|proof_with_pis| proof_with_pis.proof.recover_degree_bits(config))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct CompressedStarkProof<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 > {
+    /// The log of the trace length, which can't be recovered from compressed Merkle proofs.
+    pub degree_bits: usize,
     /// Merkle cap of LDEs of trace values.
     pub trace_cap: MerkleCap<F, C::Hasher>,
+    /// Merkle cap of LDEs of permutation Z values, lookup helper columns and cross-table lookup Z
+    /// values.
+    pub auxiliary_polys_cap: Option<MerkleCap<F, C::Hasher>>,
+    /// Merkle cap of LDEs of the quotient polynomial components.
+    pub quotient_polys_cap: MerkleCap<F, C::Hasher>,
     /// Purported values of each polynomial at the challenge point.
     pub openings: StarkOpeningSet<F, D>,
-    /// A batch FRI argument for all openings.
+    /// A compressed batch FRI argument for all openings.
     pub opening_proof: CompressedFriProof<F, C::Hasher, D>,
 }
 
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    CompressedStarkProof<F, C, D>
+{
+    /// Decompresses the proof, given the challenges of the proof of the given STARK.
+    pub(crate) fn decompress<S: Stark<F, D>>(
+        self,
+        stark: &S,
+        challenges: &StarkProofChallenges<F, D>,
+        config: &StarkConfig,
+    ) -> Result<StarkProof<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let CompressedStarkProof {
+            degree_bits,
+            trace_cap,
+            auxiliary_polys_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof,
+        } = self;
+        let fri_params = config.fri_params(degree_bits);
+        let num_ctl_zs = openings.ctl_zs_last.as_ref().map_or(0, Vec::len);
+        let instance = stark.fri_instance(
+            challenges.stark_zeta,
+            F::primitive_root_of_unity(degree_bits),
+            num_ctl_zs,
+            config,
+        );
+
+        // Inferring the FRI elements walks the query rounds, so their shape is checked first.
+        let query_round_proofs = &opening_proof.query_round_proofs;
+        ensure!(
+            query_round_proofs.indices == challenges.fri_challenges.fri_query_indices,
+            "FRI query indices don't match the proof's challenges."
+        );
+        let leaf_sizes = instance
+            .oracles
+            .iter()
+            .map(|oracle| oracle.num_polys + salt_size(fri_params.hiding && oracle.blinding))
+            .collect_vec();
+        ensure!(
+            query_round_proofs
+                .initial_trees_proofs
+                .values()
+                .all(|proof| proof
+                    .evals_proofs
+                    .iter()
+                    .map(|(leaf, _)| leaf.len())
+                    .eq(leaf_sizes.iter().copied())),
+            "Initial FRI Merkle leaves don't match the STARK's oracles."
+        );
+
+        let fri_inferred_elements = opening_proof.get_inferred_elements::<C>(
+            &instance,
+            &openings.to_fri_openings(),
+            &challenges.fri_challenges,
+            &fri_params,
+        );
+        Ok(StarkProof {
+            trace_cap,
+            auxiliary_polys_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof: opening_proof.decompress::<C>(
+                &challenges.fri_challenges,
+                fri_inferred_elements,
+                &fri_params,
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct CompressedStarkProofWithPublicInputs<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -101,10 +293,46 @@ pub struct CompressedStarkProofWithPublicInputs<
     pub public_inputs: Vec<F>,
 }
 
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    CompressedStarkProofWithPublicInputs<F, C, D>
+{
+    /// Decompresses the proof, which must be a proof of the given STARK.
+    pub fn decompress<S: Stark<F, D>>(
+        self,
+        stark: &S,
+        verifying_key: &StarkVerifyingKey<F, C, D>,
+        config: &StarkConfig,
+    ) -> Result<StarkProofWithPublicInputs<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let challenges = self.get_challenges(stark, verifying_key, config);
+        Ok(StarkProofWithPublicInputs {
+            proof: self.proof.decompress(stark, &challenges, config)?,
+            public_inputs: self.public_inputs,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buffer = Buffer::new(Vec::new());
+        write_compressed_stark_proof_with_public_inputs(&mut buffer, self)?;
+        Ok(buffer.bytes())
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, config: &StarkConfig) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let proof = read_compressed_stark_proof_with_public_inputs(&mut buffer, config)?;
+        Ok(proof)
+    }
+}
+
 pub(crate) struct StarkProofChallenges<F: RichField + Extendable<D>, const D: usize> {
     /// Randomness used in any permutation arguments.
     pub permutation_challenge_sets: Option<Vec<PermutationChallengeSet<F>>>,
 
+    /// Randomness used in any lookup arguments.
+    pub lookup_challenges: Option<Vec<F>>,
+
     /// Random values used to combine STARK constraints.
     pub stark_alphas: Vec<F>,
 
@@ -116,18 +344,26 @@ pub(crate) struct StarkProofChallenges<F: RichField + Extendable<D>, const D: us
 
 pub(crate) struct StarkProofChallengesTarget<const D: usize> {
     pub permutation_challenge_sets: Option<Vec<PermutationChallengeSet<Target>>>,
+    pub lookup_challenges: Option<Vec<Target>>,
     pub stark_alphas: Vec<Target>,
     pub stark_zeta: ExtensionTarget<D>,
     pub fri_challenges: FriChallengesTarget<D>,
 }
 
 /// Purported values of each polynomial at the challenge point.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct StarkOpeningSet<F: RichField + Extendable<D>, const D: usize> {
+    pub preprocessed_values: Option<Vec<F::Extension>>,
+    pub preprocessed_next_values: Option<Vec<F::Extension>>,
     pub local_values: Vec<F::Extension>,
     pub next_values: Vec<F::Extension>,
-    pub permutation_zs: Option<Vec<F::Extension>>,
-    pub permutation_zs_next: Option<Vec<F::Extension>>,
+    /// Openings of the trace at `zeta * g^k` for each `k` in `Stark::extra_row_offsets`.
+    pub extra_rows_values: Vec<Vec<F::Extension>>,
+    pub auxiliary_polys: Option<Vec<F::Extension>>,
+    pub auxiliary_polys_next: Option<Vec<F::Extension>>,
+    /// Openings of cross-table lookup `Z` polynomials at `g^-1`, if the STARK is part of a
+    /// `MultiStark` and takes part in cross-table lookups.
+    pub ctl_zs_last: Option<Vec<F>>,
     pub quotient_polys: Vec<F::Extension>,
 }
 
@@ -135,22 +371,55 @@ impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
     pub fn new<C: GenericConfig<D, F = F>>(
         zeta: F::Extension,
         g: F,
+        preprocessed_commitment: Option<&PolynomialBatch<F, C, D>>,
         trace_commitment: &PolynomialBatch<F, C, D>,
-        permutation_zs_commitment: Option<&PolynomialBatch<F, C, D>>,
+        auxiliary_polys_commitment: Option<&PolynomialBatch<F, C, D>>,
         quotient_commitment: &PolynomialBatch<F, C, D>,
+        extra_row_offsets: &[isize],
+        num_ctl_zs: usize,
     ) -> Self {
         let eval_commitment = |z: F::Extension, c: &PolynomialBatch<F, C, D>| {
-            c.polynomials
-                .par_iter()
-                .map(|p| p.to_extension().eval(z))
+            let indices = (0..c.num_polynomials()).collect::<Vec<_>>();
+            c.polynomial_chunks(&indices)
+                .flat_map(|chunk| {
+                    chunk
+                        .par_iter()
+                        .map(|p| p.to_extension().eval(z))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let eval_commitment_base = |z: F, c: &PolynomialBatch<F, C, D>| {
+            let indices = (0..c.num_polynomials()).collect::<Vec<_>>();
+            c.polynomial_chunks(&indices)
+                .flat_map(|chunk| chunk.par_iter().map(|p| p.eval(z)).collect::<Vec<_>>())
                 .collect::<Vec<_>>()
         };
         let zeta_next = zeta.scalar_mul(g);
+        // The cross-table lookup `Z`s are the last auxiliary polynomials.
+        let ctl_zs_last = (num_ctl_zs > 0).then(|| {
+            let auxiliary_polys_last =
+                eval_commitment_base(g.inverse(), auxiliary_polys_commitment.unwrap());
+            auxiliary_polys_last[auxiliary_polys_last.len() - num_ctl_zs..].to_vec()
+        });
         Self {
+            preprocessed_values: preprocessed_commitment.map(|c| eval_commitment(zeta, c)),
+            preprocessed_next_values: preprocessed_commitment
+                .map(|c| eval_commitment(zeta_next, c)),
             local_values: eval_commitment(zeta, trace_commitment),
             next_values: eval_commitment(zeta_next, trace_commitment),
-            permutation_zs: permutation_zs_commitment.map(|c| eval_commitment(zeta, c)),
-            permutation_zs_next: permutation_zs_commitment.map(|c| eval_commitment(zeta_next, c)),
+            extra_rows_values: extra_row_offsets
+                .iter()
+                .map(|&offset| {
+                    eval_commitment(
+                        zeta.scalar_mul(row_offset_power(g, offset)),
+                        trace_commitment,
+                    )
+                })
+                .collect(),
+            auxiliary_polys: auxiliary_polys_commitment.map(|c| eval_commitment(zeta, c)),
+            auxiliary_polys_next: auxiliary_polys_commitment.map(|c| eval_commitment(zeta_next, c)),
+            ctl_zs_last,
             quotient_polys: eval_commitment(zeta, quotient_commitment),
         }
     }
@@ -158,32 +427,53 @@ impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
     pub(crate) fn to_fri_openings(&self) -> FriOpenings<F, D> {
         let zeta_batch = FriOpeningBatch {
             values: self
-                .local_values
+                .preprocessed_values
                 .iter()
-                .chain(self.permutation_zs.iter().flatten())
+                .flatten()
+                .chain(&self.local_values)
+                .chain(self.auxiliary_polys.iter().flatten())
                 .chain(&self.quotient_polys)
                 .copied()
                 .collect_vec(),
         };
         let zeta_next_batch = FriOpeningBatch {
             values: self
-                .next_values
+                .preprocessed_next_values
                 .iter()
-                .chain(self.permutation_zs_next.iter().flatten())
+                .flatten()
+                .chain(&self.next_values)
+                .chain(self.auxiliary_polys_next.iter().flatten())
                 .copied()
                 .collect_vec(),
         };
-        FriOpenings {
-            batches: vec![zeta_batch, zeta_next_batch],
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        batches.extend(self.extra_rows_values.iter().map(|values| FriOpeningBatch {
+            values: values.clone(),
+        }));
+        if let Some(ctl_zs_last) = &self.ctl_zs_last {
+            batches.push(FriOpeningBatch {
+                values: ctl_zs_last
+                    .iter()
+                    .copied()
+                    .map(F::Extension::from_basefield)
+                    .collect(),
+            });
         }
+        FriOpenings { batches }
     }
 }
 
 pub struct StarkOpeningSetTarget<const D: usize> {
+    pub preprocessed_values: Option<Vec<ExtensionTarget<D>>>,
+    pub preprocessed_next_values: Option<Vec<ExtensionTarget<D>>>,
     pub local_values: Vec<ExtensionTarget<D>>,
     pub next_values: Vec<ExtensionTarget<D>>,
-    pub permutation_zs: Option<Vec<ExtensionTarget<D>>>,
-    pub permutation_zs_next: Option<Vec<ExtensionTarget<D>>>,
+    pub extra_rows_values: Vec<Vec<ExtensionTarget<D>>>,
+    pub auxiliary_polys: Option<Vec<ExtensionTarget<D>>>,
+    pub auxiliary_polys_next: Option<Vec<ExtensionTarget<D>>>,
+    /// Openings of cross-table lookup `Z` polynomials at `g^-1`. They are base field elements, so
+    /// the higher limbs of these extension targets are zero.
+    pub ctl_zs_last: Option<Vec<ExtensionTarget<D>>>,
     pub quotient_polys: Vec<ExtensionTarget<D>>,
 }
 
@@ -191,23 +481,38 @@ impl<const D: usize> StarkOpeningSetTarget<D> {
     pub(crate) fn to_fri_openings(&self) -> FriOpeningsTarget<D> {
         let zeta_batch = FriOpeningBatchTarget {
             values: self
-                .local_values
+                .preprocessed_values
                 .iter()
-                .chain(self.permutation_zs.iter().flatten())
+                .flatten()
+                .chain(&self.local_values)
+                .chain(self.auxiliary_polys.iter().flatten())
                 .chain(&self.quotient_polys)
                 .copied()
                 .collect_vec(),
         };
         let zeta_next_batch = FriOpeningBatchTarget {
             values: self
-                .next_values
+                .preprocessed_next_values
                 .iter()
-                .chain(self.permutation_zs_next.iter().flatten())
+                .flatten()
+                .chain(&self.next_values)
+                .chain(self.auxiliary_polys_next.iter().flatten())
                 .copied()
                 .collect_vec(),
         };
-        FriOpeningsTarget {
-            batches: vec![zeta_batch, zeta_next_batch],
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        batches.extend(
+            self.extra_rows_values
+                .iter()
+                .map(|values| FriOpeningBatchTarget {
+                    values: values.clone(),
+                }),
+        );
+        if let Some(ctl_zs_last) = &self.ctl_zs_last {
+            batches.push(FriOpeningBatchTarget {
+                values: ctl_zs_last.clone(),
+            });
         }
+        FriOpeningsTarget { batches }
     }
 }
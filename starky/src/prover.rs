@@ -1,6 +1,7 @@
 use std::iter::once;
+use std::marker::PhantomData;
 
-use anyhow::{ensure, Result};
+use anyhow::{anyhow, ensure, Result};
 use itertools::Itertools;
 use maybe_rayon::*;
 use plonky2::field::extension::Extendable;
@@ -20,15 +21,22 @@ use plonky2_util::{log2_ceil, log2_strict};
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::ConstraintConsumer;
+use crate::cross_table_lookup::{cross_table_lookup_data, CrossTableLookup, CtlCheckVars, CtlData};
+use crate::lookup::{compute_lookup_helper_polys, LookupCheckVars};
+use crate::multi_stark::{MultiStark, TableVisitor};
 use crate::permutation::PermutationCheckVars;
 use crate::permutation::{
-    compute_permutation_z_polys, get_n_permutation_challenge_sets, PermutationChallengeSet,
+    compute_permutation_z_polys, get_n_permutation_challenge_sets, get_permutation_challenge_set,
+    PermutationChallengeSet,
 };
-use crate::proof::{StarkOpeningSet, StarkProof, StarkProofWithPublicInputs};
+use crate::preprocessed::StarkProverData;
+use crate::proof::{MultiProof, StarkOpeningSet, StarkProof, StarkProofWithPublicInputs};
 use crate::stark::Stark;
 use crate::vanishing_poly::eval_vanishing_poly;
 use crate::vars::StarkEvaluationVars;
 
+/// Proves the given trace. The preprocessed columns of the STARK, if any, are committed to from
+/// scratch; `prove_with_prover_data` should be preferred to prove many traces of such a STARK.
 pub fn prove<F, C, S, const D: usize>(
     stark: S,
     config: &StarkConfig,
@@ -44,78 +52,362 @@ where
     [(); S::PUBLIC_INPUTS]:,
     [(); C::Hasher::HASH_SIZE]:,
 {
-    let degree = trace_poly_values[0].len();
-    let degree_bits = log2_strict(degree);
-    let fri_params = config.fri_params(degree_bits);
-    let rate_bits = config.fri_config.rate_bits;
-    let cap_height = config.fri_config.cap_height;
-    assert!(
-        fri_params.total_arities() <= degree_bits + rate_bits - cap_height,
-        "FRI total reduction arity is too large.",
-    );
+    let prover_data = StarkProverData::new(&stark, config, timing);
+    prove_with_prover_data(
+        stark,
+        &prover_data,
+        config,
+        trace_poly_values,
+        public_inputs,
+        timing,
+    )
+}
 
+pub fn prove_with_prover_data<F, C, S, const D: usize>(
+    stark: S,
+    prover_data: &StarkProverData<F, C, D>,
+    config: &StarkConfig,
+    trace_poly_values: Vec<PolynomialValues<F>>,
+    public_inputs: [F; S::PUBLIC_INPUTS],
+    timing: &mut TimingTree,
+) -> Result<StarkProofWithPublicInputs<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+    [(); C::Hasher::HASH_SIZE]:,
+{
     let trace_commitment = timed!(
         timing,
         "compute trace commitment",
-        PolynomialBatch::<F, C, D>::from_values(
+        commit_trace_polys::<F, C, D>(
             // TODO: Cloning this isn't great; consider having `from_values` accept a reference,
             // or having `compute_permutation_z_polys` read trace values from the `PolynomialBatch`.
             trace_poly_values.clone(),
-            rate_bits,
-            false,
-            cap_height,
+            config,
             timing,
-            None,
         )
     );
 
-    let trace_cap = trace_commitment.merkle_tree.cap.clone();
     let mut challenger = Challenger::new();
-    challenger.observe_cap(&trace_cap);
+    if let Some(preprocessed_cap) = &prover_data.verifying_key.preprocessed_cap {
+        challenger.observe_cap(preprocessed_cap);
+    }
+    challenger.observe_cap(&trace_commitment.merkle_tree.cap);
 
-    // Permutation arguments.
-    let permutation_zs_commitment_challenges = stark.uses_permutation_args().then(|| {
-        let permutation_challenge_sets = get_n_permutation_challenge_sets(
-            &mut challenger,
-            config.num_challenges,
-            stark.permutation_batch_size(),
+    let proof = prove_with_commitment(
+        &stark,
+        prover_data,
+        config,
+        &trace_poly_values,
+        &trace_commitment,
+        public_inputs,
+        None,
+        &mut challenger,
+        timing,
+    )?;
+
+    Ok(StarkProofWithPublicInputs {
+        proof,
+        public_inputs: public_inputs.to_vec(),
+    })
+}
+
+/// Proves the given traces of the tables of a `MultiStark`, along with the cross-table lookups
+/// between them. The preprocessed columns of the tables, if any, are committed to from scratch.
+pub fn prove_multi_stark<F, C, M, const D: usize>(
+    multi_stark: &M,
+    config: &StarkConfig,
+    trace_poly_values: Vec<Vec<PolynomialValues<F>>>,
+    public_inputs: Vec<Vec<F>>,
+    timing: &mut TimingTree,
+) -> Result<MultiProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    M: MultiStark<F, D>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let num_tables = multi_stark.num_tables();
+    ensure!(
+        trace_poly_values.len() == num_tables,
+        "Expected {} traces, got {}.",
+        num_tables,
+        trace_poly_values.len()
+    );
+    ensure!(
+        public_inputs.len() == num_tables,
+        "Expected {} sets of public inputs, got {}.",
+        num_tables,
+        public_inputs.len()
+    );
+    let cross_table_lookups = multi_stark.cross_table_lookups();
+    CrossTableLookup::check_tables(&cross_table_lookups, num_tables)?;
+
+    let (prover_data, trace_commitments): (Vec<_>, Vec<_>) = trace_poly_values
+        .iter()
+        .enumerate()
+        .map(|(table, trace_poly_values)| {
+            multi_stark.visit_table(
+                table,
+                CommitTableVisitor::<F, C, D> {
+                    config,
+                    trace_poly_values,
+                    timing,
+                    _phantom: PhantomData,
+                },
+            )
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+
+    let mut challenger = Challenger::<F, C::Hasher>::new();
+    for (prover_data, trace_commitment) in prover_data.iter().zip(&trace_commitments) {
+        if let Some(preprocessed_cap) = &prover_data.verifying_key.preprocessed_cap {
+            challenger.observe_cap(preprocessed_cap);
+        }
+        challenger.observe_cap(&trace_commitment.merkle_tree.cap);
+    }
+
+    let ctl_challenges = get_permutation_challenge_set(&mut challenger, config.num_challenges);
+    let ctl_data_per_table = timed!(
+        timing,
+        "compute cross-table lookup data",
+        cross_table_lookup_data(&trace_poly_values, &cross_table_lookups, &ctl_challenges)
+    );
+
+    let stark_proofs = (0..num_tables)
+        .map(|table| {
+            multi_stark.visit_table(
+                table,
+                ProveTableVisitor {
+                    prover_data: &prover_data[table],
+                    config,
+                    trace_poly_values: &trace_poly_values[table],
+                    trace_commitment: &trace_commitments[table],
+                    public_inputs: &public_inputs[table],
+                    ctl_data: &ctl_data_per_table[table],
+                    challenger: &mut challenger,
+                    timing,
+                },
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(MultiProof { stark_proofs })
+}
+
+/// Commits to the preprocessed columns and the trace of a table of a `MultiStark`.
+struct CommitTableVisitor<'a, F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    config: &'a StarkConfig,
+    trace_poly_values: &'a [PolynomialValues<F>],
+    timing: &'a mut TimingTree,
+    _phantom: PhantomData<C>,
+}
+
+impl<'a, F, C, const D: usize> TableVisitor<F, D> for CommitTableVisitor<'a, F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    type Output = Result<(StarkProverData<F, C, D>, PolynomialBatch<F, C, D>)>;
+
+    fn visit<S: Stark<F, D>>(self, stark: &S) -> Self::Output
+    where
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+    {
+        ensure!(
+            self.trace_poly_values.len() == S::COLUMNS,
+            "Expected {} trace columns, got {}.",
+            S::COLUMNS,
+            self.trace_poly_values.len()
         );
-        let permutation_z_polys = compute_permutation_z_polys::<F, C, S, D>(
-            &stark,
-            config,
-            &trace_poly_values,
-            &permutation_challenge_sets,
+        let prover_data = StarkProverData::new(stark, self.config, self.timing);
+        let trace_commitment = timed!(
+            self.timing,
+            "compute trace commitment",
+            commit_trace_polys::<F, C, D>(
+                self.trace_poly_values.to_vec(),
+                self.config,
+                self.timing
+            )
         );
+        Ok((prover_data, trace_commitment))
+    }
+}
 
-        let permutation_zs_commitment = timed!(
-            timing,
-            "compute permutation Z commitments",
-            PolynomialBatch::from_values(
-                permutation_z_polys,
-                rate_bits,
-                false,
-                config.fri_config.cap_height,
-                timing,
-                None,
+/// Proves a table of a `MultiStark`, whose trace commitment has already been observed.
+struct ProveTableVisitor<'a, F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    prover_data: &'a StarkProverData<F, C, D>,
+    config: &'a StarkConfig,
+    trace_poly_values: &'a [PolynomialValues<F>],
+    trace_commitment: &'a PolynomialBatch<F, C, D>,
+    public_inputs: &'a [F],
+    ctl_data: &'a CtlData<F>,
+    challenger: &'a mut Challenger<F, C::Hasher>,
+    timing: &'a mut TimingTree,
+}
+
+impl<'a, F, C, const D: usize> TableVisitor<F, D> for ProveTableVisitor<'a, F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    type Output = Result<StarkProofWithPublicInputs<F, C, D>>;
+
+    fn visit<S: Stark<F, D>>(self, stark: &S) -> Self::Output
+    where
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+    {
+        let public_inputs: [F; S::PUBLIC_INPUTS] = self.public_inputs.try_into().map_err(|_| {
+            anyhow!(
+                "Expected {} public inputs, got {}.",
+                S::PUBLIC_INPUTS,
+                self.public_inputs.len()
             )
+        })?;
+        let proof = prove_with_commitment(
+            stark,
+            self.prover_data,
+            self.config,
+            self.trace_poly_values,
+            self.trace_commitment,
+            public_inputs,
+            Some(self.ctl_data),
+            self.challenger,
+            self.timing,
+        )?;
+        Ok(StarkProofWithPublicInputs {
+            proof,
+            public_inputs: public_inputs.to_vec(),
+        })
+    }
+}
+
+/// Proves the given trace, whose commitment has already been observed by `challenger`, along with
+/// the preprocessed commitment if any. If the STARK is part of a `MultiStark`, `ctl_data` holds
+/// the cross-table lookup `Z`s of its trace, which are committed to with its auxiliary
+/// polynomials.
+pub(crate) fn prove_with_commitment<F, C, S, const D: usize>(
+    stark: &S,
+    prover_data: &StarkProverData<F, C, D>,
+    config: &StarkConfig,
+    trace_poly_values: &[PolynomialValues<F>],
+    trace_commitment: &PolynomialBatch<F, C, D>,
+    public_inputs: [F; S::PUBLIC_INPUTS],
+    ctl_data: Option<&CtlData<F>>,
+    challenger: &mut Challenger<F, C::Hasher>,
+    timing: &mut TimingTree,
+) -> Result<StarkProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let degree = trace_poly_values[0].len();
+    let degree_bits = log2_strict(degree);
+    let fri_params = config.fri_params(degree_bits);
+    let committed_degree = 1 << config.committed_degree_bits(degree_bits);
+    let rate_bits = config.fri_config.rate_bits;
+    let cap_height = config.fri_config.cap_height;
+    assert!(
+        fri_params.total_arities() <= fri_params.lde_bits() - cap_height,
+        "FRI total reduction arity is too large.",
+    );
+
+    let preprocessed_commitment = prover_data.preprocessed_commitment.as_ref();
+    ensure!(
+        preprocessed_commitment.is_some() == stark.uses_preprocessed_columns(),
+        "Prover data doesn't match with Stark configuration."
+    );
+    if let Some(commitment) = preprocessed_commitment {
+        ensure!(
+            commitment.degree_log == config.committed_degree_bits(degree_bits),
+            "Preprocessed columns and trace have different lengths."
         );
-        (permutation_zs_commitment, permutation_challenge_sets)
+    }
+    let extra_row_offsets = stark.extra_row_offsets();
+    ensure!(
+        extra_row_offsets.iter().all_unique()
+            && extra_row_offsets
+                .iter()
+                .all(|&offset| offset != 0 && offset != 1 && offset.unsigned_abs() < degree),
+        "Extra row offsets should be distinct, neither 0 nor 1, and smaller than the trace length."
+    );
+
+    // Permutation and lookup arguments.
+    let permutation_challenge_sets = stark.uses_permutation_args().then(|| {
+        get_n_permutation_challenge_sets(
+            challenger,
+            config.num_challenges,
+            stark.permutation_batch_size(),
+        )
     });
-    let permutation_zs_commitment = permutation_zs_commitment_challenges
-        .as_ref()
-        .map(|(comm, _)| comm);
-    let permutation_zs_cap = permutation_zs_commitment
+    let lookup_challenges = stark
+        .uses_lookups()
+        .then(|| challenger.get_n_challenges(config.num_challenges));
+    let num_ctl_zs = ctl_data.map_or(0, |ctl_data| ctl_data.len());
+    let auxiliary_polys_commitment = (stark.uses_auxiliary_polys() || num_ctl_zs > 0).then(|| {
+        let mut auxiliary_polys = vec![];
+        if let Some(permutation_challenge_sets) = &permutation_challenge_sets {
+            auxiliary_polys.extend(compute_permutation_z_polys::<F, C, S, D>(
+                stark,
+                config,
+                trace_poly_values,
+                permutation_challenge_sets,
+            ));
+        }
+        if let Some(lookup_challenges) = &lookup_challenges {
+            auxiliary_polys.extend(compute_lookup_helper_polys::<F, S, D>(
+                stark,
+                trace_poly_values,
+                lookup_challenges,
+            ));
+        }
+        if let Some(ctl_data) = ctl_data {
+            auxiliary_polys.extend(ctl_data.z_polys());
+        }
+
+        timed!(
+            timing,
+            "compute auxiliary polynomials commitment",
+            commit_trace_polys(auxiliary_polys, config, timing)
+        )
+    });
+    let auxiliary_polys_cap = auxiliary_polys_commitment
         .as_ref()
         .map(|commit| commit.merkle_tree.cap.clone());
-    if let Some(cap) = &permutation_zs_cap {
+    if let Some(cap) = &auxiliary_polys_cap {
         challenger.observe_cap(cap);
     }
 
     let alphas = challenger.get_n_challenges(config.num_challenges);
     let quotient_polys = compute_quotient_polys::<F, <F as Packable>::Packing, C, S, D>(
-        &stark,
-        &trace_commitment,
-        &permutation_zs_commitment_challenges,
+        stark,
+        preprocessed_commitment,
+        trace_commitment,
+        auxiliary_polys_commitment.as_ref(),
+        permutation_challenge_sets.as_deref(),
+        lookup_challenges.as_deref(),
+        ctl_data,
         public_inputs,
         alphas,
         degree_bits,
@@ -125,10 +417,10 @@ where
         .into_par_iter()
         .flat_map(|mut quotient_poly| {
             quotient_poly
-                .trim_to_len(degree * stark.quotient_degree_factor())
+                .trim_to_len(committed_degree * stark.num_quotient_chunks(config))
                 .expect("Quotient has failed, the vanishing polynomial is not divisible by Z_H");
-            // Split quotient into degree-n chunks.
-            quotient_poly.chunks(degree)
+            // Split quotient into chunks with the degree bound of committed polynomials.
+            quotient_poly.chunks(committed_degree)
         })
         .collect();
     let quotient_commitment = timed!(
@@ -137,7 +429,7 @@ where
         PolynomialBatch::from_coeffs(
             all_quotient_chunks,
             rate_bits,
-            false,
+            config.zero_knowledge,
             config.fri_config.cap_height,
             timing,
             None,
@@ -158,14 +450,19 @@ where
     let openings = StarkOpeningSet::new(
         zeta,
         g,
-        &trace_commitment,
-        permutation_zs_commitment,
+        preprocessed_commitment,
+        trace_commitment,
+        auxiliary_polys_commitment.as_ref(),
         &quotient_commitment,
+        &extra_row_offsets,
+        num_ctl_zs,
     );
     challenger.observe_openings(&openings.to_fri_openings());
 
-    let initial_merkle_trees = once(&trace_commitment)
-        .chain(permutation_zs_commitment)
+    let initial_merkle_trees = preprocessed_commitment
+        .into_iter()
+        .chain(once(trace_commitment))
+        .chain(&auxiliary_polys_commitment)
         .chain(once(&quotient_commitment))
         .collect_vec();
 
@@ -173,24 +470,20 @@ where
         timing,
         "compute openings proof",
         PolynomialBatch::prove_openings(
-            &stark.fri_instance(zeta, g, config),
+            &stark.fri_instance(zeta, g, num_ctl_zs, config),
             &initial_merkle_trees,
-            &mut challenger,
+            challenger,
             &fri_params,
             timing,
         )
     );
-    let proof = StarkProof {
-        trace_cap,
-        permutation_zs_cap,
+
+    Ok(StarkProof {
+        trace_cap: trace_commitment.merkle_tree.cap.clone(),
+        auxiliary_polys_cap,
         quotient_polys_cap,
         openings,
         opening_proof,
-    };
-
-    Ok(StarkProofWithPublicInputs {
-        proof,
-        public_inputs: public_inputs.to_vec(),
     })
 }
 
@@ -198,11 +491,12 @@ where
 /// where the `C_i`s are the Stark constraints.
 fn compute_quotient_polys<'a, F, P, C, S, const D: usize>(
     stark: &S,
+    preprocessed_commitment: Option<&'a PolynomialBatch<F, C, D>>,
     trace_commitment: &'a PolynomialBatch<F, C, D>,
-    permutation_zs_commitment_challenges: &'a Option<(
-        PolynomialBatch<F, C, D>,
-        Vec<PermutationChallengeSet<F>>,
-    )>,
+    auxiliary_polys_commitment: Option<&'a PolynomialBatch<F, C, D>>,
+    permutation_challenge_sets: Option<&[PermutationChallengeSet<F>]>,
+    lookup_challenges: Option<&[F]>,
+    ctl_data: Option<&CtlData<F>>,
     public_inputs: [F; S::PUBLIC_INPUTS],
     alphas: Vec<F>,
     degree_bits: usize,
@@ -218,23 +512,26 @@ where
 {
     let degree = 1 << degree_bits;
     let rate_bits = config.fri_config.rate_bits;
+    // Committed polynomials may have a larger degree than the trace when blinded.
+    let blinding_bits = config.committed_degree_bits(degree_bits) - degree_bits;
 
-    let quotient_degree_bits = log2_ceil(stark.quotient_degree_factor());
+    let quotient_degree_bits = log2_ceil(stark.num_quotient_chunks(config));
     assert!(
         quotient_degree_bits <= rate_bits,
         "Having constraints of degree higher than the rate is not supported yet."
     );
     let step = 1 << (rate_bits - quotient_degree_bits);
+    // The quotient is evaluated on a coset `2^eval_bits` times larger than the trace domain.
+    let eval_bits = quotient_degree_bits + blinding_bits;
     // When opening the `Z`s polys at the "next" point, need to look at the point `next_step` steps away.
-    let next_step = 1 << quotient_degree_bits;
+    let next_step = 1 << eval_bits;
 
     // Evaluation of the first Lagrange polynomial on the LDE domain.
-    let lagrange_first = PolynomialValues::selector(degree, 0).lde_onto_coset(quotient_degree_bits);
+    let lagrange_first = PolynomialValues::selector(degree, 0).lde_onto_coset(eval_bits);
     // Evaluation of the last Lagrange polynomial on the LDE domain.
-    let lagrange_last =
-        PolynomialValues::selector(degree, degree - 1).lde_onto_coset(quotient_degree_bits);
+    let lagrange_last = PolynomialValues::selector(degree, degree - 1).lde_onto_coset(eval_bits);
 
-    let z_h_on_coset = ZeroPolyOnCoset::<F>::new(degree_bits, quotient_degree_bits);
+    let z_h_on_coset = ZeroPolyOnCoset::<F>::new(degree_bits, eval_bits);
 
     // Retrieve the LDE values at index `i`.
     let get_trace_values_packed = |i_start| -> [P; S::COLUMNS] {
@@ -243,12 +540,25 @@ where
             .try_into()
             .unwrap()
     };
+    let get_preprocessed_values_packed = |i_start| -> Vec<P> {
+        preprocessed_commitment
+            .map(|commitment| commitment.get_lde_values_packed(i_start, step))
+            .unwrap_or_default()
+    };
+    let get_auxiliary_values_packed = |i_start| -> Vec<P> {
+        auxiliary_polys_commitment
+            .map(|commitment| commitment.get_lde_values_packed(i_start, step))
+            .unwrap_or_default()
+    };
+    let num_permutation_zs = stark.num_permutation_batches(config);
+    let num_auxiliary_polys = stark.num_auxiliary_polys(config);
+    let extra_row_offsets = stark.extra_row_offsets();
 
     // Last element of the subgroup.
     let last = F::primitive_root_of_unity(degree_bits).inverse();
-    let size = degree << quotient_degree_bits;
+    let size = degree << eval_bits;
     let coset = F::cyclic_subgroup_coset_known_order(
-        F::primitive_root_of_unity(degree_bits + quotient_degree_bits),
+        F::primitive_root_of_unity(degree_bits + eval_bits),
         F::coset_shift(),
         size,
     );
@@ -260,6 +570,14 @@ where
         .step_by(P::WIDTH)
         .flat_map_iter(|i_start| {
             let i_next_start = (i_start + next_step) % size;
+            let extra_rows = extra_row_offsets
+                .iter()
+                .map(|&offset| {
+                    let i_offset_start =
+                        (i_start as isize + offset * next_step as isize).rem_euclid(size as isize);
+                    get_trace_values_packed(i_offset_start as usize)
+                })
+                .collect::<Vec<_>>();
             let i_range = i_start..i_start + P::WIDTH;
 
             let x = *P::from_slice(&coset[i_range.clone()]);
@@ -276,20 +594,45 @@ where
             let vars = StarkEvaluationVars {
                 local_values: &get_trace_values_packed(i_start),
                 next_values: &get_trace_values_packed(i_next_start),
+                extra_rows: &extra_rows,
+                local_preprocessed: &get_preprocessed_values_packed(i_start),
+                next_preprocessed: &get_preprocessed_values_packed(i_next_start),
                 public_inputs: &public_inputs,
             };
-            let permutation_check_data = permutation_zs_commitment_challenges.as_ref().map(
-                |(permutation_zs_commitment, permutation_challenge_sets)| PermutationCheckVars {
-                    local_zs: permutation_zs_commitment.get_lde_values_packed(i_start, step),
-                    next_zs: permutation_zs_commitment.get_lde_values_packed(i_next_start, step),
+            let local_auxiliary_values = get_auxiliary_values_packed(i_start);
+            let next_auxiliary_values = get_auxiliary_values_packed(i_next_start);
+            let permutation_check_data =
+                permutation_challenge_sets.map(|permutation_challenge_sets| PermutationCheckVars {
+                    local_zs: local_auxiliary_values[..num_permutation_zs].to_vec(),
+                    next_zs: next_auxiliary_values[..num_permutation_zs].to_vec(),
                     permutation_challenge_sets: permutation_challenge_sets.to_vec(),
-                },
-            );
+                });
+            let lookup_check_data = lookup_challenges.map(|lookup_challenges| LookupCheckVars {
+                local_values: local_auxiliary_values[num_permutation_zs..num_auxiliary_polys]
+                    .to_vec(),
+                next_values: next_auxiliary_values[num_permutation_zs..num_auxiliary_polys]
+                    .to_vec(),
+                challenges: lookup_challenges.to_vec(),
+            });
+            let ctl_vars = ctl_data
+                .iter()
+                .flat_map(|ctl_data| &ctl_data.zs_columns)
+                .enumerate()
+                .map(|(i, zs_columns)| CtlCheckVars::<F, F, P, 1> {
+                    local_z: local_auxiliary_values[num_auxiliary_polys + i],
+                    next_z: next_auxiliary_values[num_auxiliary_polys + i],
+                    challenges: zs_columns.challenge,
+                    columns: &zs_columns.columns,
+                    filter_column: &zs_columns.filter_column,
+                })
+                .collect::<Vec<_>>();
             eval_vanishing_poly::<F, F, P, C, S, D, 1>(
                 stark,
                 config,
                 vars,
                 permutation_check_data,
+                lookup_check_data,
+                &ctl_vars,
                 &mut consumer,
             );
 
@@ -317,3 +660,36 @@ where
         .map(|values| values.coset_ifft(F::coset_shift()))
         .collect()
 }
+
+/// Commits to polynomials given by their values on the trace domain `H`. With zero-knowledge, each
+/// polynomial `p` is replaced by `p + Z_H r` for a random `r` of degree less than `|H|`, which
+/// agrees with `p` on `H` while making its evaluations outside of `H` uniformly random.
+pub(crate) fn commit_trace_polys<F, C, const D: usize>(
+    values: Vec<PolynomialValues<F>>,
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> PolynomialBatch<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let rate_bits = config.fri_config.rate_bits;
+    let cap_height = config.fri_config.cap_height;
+    if !config.zero_knowledge {
+        return PolynomialBatch::from_values(values, rate_bits, false, cap_height, timing, None);
+    }
+
+    let blinded_polys = values
+        .into_par_iter()
+        .map(|values| {
+            let r = F::rand_vec(values.len());
+            // `Z_H(x) r(x) = x^n r(x) - r(x)`.
+            let mut coeffs = values.ifft().coeffs;
+            coeffs.iter_mut().zip(&r).for_each(|(c, &r_i)| *c -= r_i);
+            coeffs.extend(r);
+            PolynomialCoeffs::new(coeffs)
+        })
+        .collect();
+    PolynomialBatch::from_coeffs(blinded_polys, rate_bits, true, cap_height, timing, None)
+}
@@ -0,0 +1,196 @@
+use std::marker::PhantomData;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::lookup::Lookup;
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+const TABLE: usize = 0;
+const FREQUENCIES: usize = 1;
+const VALUES: [usize; 3] = [2, 3, 4];
+const FILTER: usize = 5;
+const NUM_COLUMNS: usize = 6;
+
+/// Toy STARK system with a lookup, used for testing.
+/// Range checks the values of three columns to `[0, num_rows)`, using a table column holding
+/// `0, 1, ..., num_rows - 1`. Only the rows where the filter column is 1 are range checked.
+#[derive(Copy, Clone)]
+struct RangeCheckStark<F: RichField + Extendable<D>, const D: usize> {
+    num_rows: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> RangeCheckStark<F, D> {
+    fn new(num_rows: usize) -> Self {
+        Self {
+            num_rows,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Generate a trace in which every other row is range checked. The other rows hold values out
+    /// of range.
+    fn generate_trace(&self) -> Vec<PolynomialValues<F>> {
+        let n = self.num_rows;
+        let trace_rows = (0..n)
+            .map(|i| {
+                let mut row = [F::ZERO; NUM_COLUMNS];
+                row[TABLE] = F::from_canonical_usize(i);
+                if i % 2 == 0 {
+                    row[VALUES[0]] = F::from_canonical_usize(i * i % n);
+                    row[VALUES[1]] = F::from_canonical_usize((7 * i + 3) % n);
+                    row[VALUES[2]] = F::from_canonical_usize(n - 1 - i);
+                    row[FILTER] = F::ONE;
+                } else {
+                    row[VALUES[0]] = F::NEG_ONE;
+                    row[VALUES[1]] = F::from_canonical_usize(n + i);
+                }
+                row
+            })
+            .collect::<Vec<_>>();
+        let mut trace = trace_rows_to_poly_values(trace_rows);
+        trace[FREQUENCIES] = self.lookups()[0].frequencies(&trace);
+        trace
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckStark<F, D> {
+    const COLUMNS: usize = NUM_COLUMNS;
+    const PUBLIC_INPUTS: usize = 0;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        // The table starts at 0 and increments by 1.
+        yield_constr.constraint_first_row(vars.local_values[TABLE]);
+        yield_constr
+            .constraint_transition(vars.next_values[TABLE] - vars.local_values[TABLE] - FE::ONE);
+
+        // The filter is boolean.
+        let filter = vars.local_values[FILTER];
+        yield_constr.constraint(filter * (filter - FE::ONE));
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let one = builder.one_extension();
+
+        // The table starts at 0 and increments by 1.
+        yield_constr.constraint_first_row(builder, vars.local_values[TABLE]);
+        let table_constraint = {
+            let tmp = builder.sub_extension(vars.next_values[TABLE], vars.local_values[TABLE]);
+            builder.sub_extension(tmp, one)
+        };
+        yield_constr.constraint_transition(builder, table_constraint);
+
+        // The filter is boolean.
+        let filter = vars.local_values[FILTER];
+        let filter_constraint = builder.mul_sub_extension(filter, filter, filter);
+        yield_constr.constraint(builder, filter_constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        vec![Lookup::with_filter(
+            VALUES.to_vec(),
+            TABLE,
+            FREQUENCIES,
+            FILTER,
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Field;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+
+    use crate::config::StarkConfig;
+    use crate::prover::prove;
+    use crate::range_check_stark::{RangeCheckStark, FREQUENCIES, VALUES};
+    use crate::recursive_verifier::{
+        add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
+        verify_stark_proof_circuit,
+    };
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::verifier::verify_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type S = RangeCheckStark<F, D>;
+
+    #[test]
+    fn test_range_check_stark() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        let trace = stark.generate_trace();
+        // Each of the 16 checked rows looks up 3 values.
+        assert_eq!(
+            trace[FREQUENCIES].values.iter().copied().sum::<F>(),
+            F::from_canonical_usize(48)
+        );
+
+        let proof = prove::<F, C, S, D>(stark, &config, trace, [], &mut TimingTree::default())?;
+        verify_stark_proof(stark, proof.clone(), &config)?;
+
+        // Recursively verify the proof.
+        let circuit_config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(circuit_config);
+        let mut pw = PartialWitness::new();
+        let degree_bits = proof.proof.recover_degree_bits(&config);
+        let pt = add_virtual_stark_proof_with_pis(&mut builder, stark, &config, degree_bits);
+        set_stark_proof_with_pis_target(&mut pw, &pt, &proof);
+        verify_stark_proof_circuit::<F, C, S, D>(&mut builder, stark, pt, &config);
+        let data = builder.build::<C>();
+        let recursive_proof = data.prove(pw)?;
+        data.verify(recursive_proof)
+    }
+
+    #[test]
+    fn test_range_check_stark_out_of_range() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        let mut trace = stark.generate_trace();
+        // Row 0 is checked, so its values must be in the table.
+        trace[VALUES[1]].values[0] = F::from_canonical_usize(1 << 5);
+
+        let proof = prove::<F, C, S, D>(stark, &config, trace, [], &mut TimingTree::default())?;
+        assert!(verify_stark_proof(stark, proof, &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_check_stark_degree() -> Result<()> {
+        test_stark_low_degree(S::new(1 << 5))
+    }
+
+    #[test]
+    fn test_range_check_stark_circuit() -> Result<()> {
+        test_stark_circuit_constraints::<F, C, S, D>(S::new(1 << 5))
+    }
+}
@@ -5,24 +5,36 @@ use itertools::Itertools;
 use plonky2::field::extension::Extendable;
 use plonky2::field::types::Field;
 use plonky2::fri::witness_util::set_fri_proof_target;
-use plonky2::hash::hash_types::RichField;
+use plonky2::hash::hash_types::{MerkleCapTarget, RichField};
+use plonky2::iop::challenger::RecursiveChallenger;
 use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::target::Target;
 use plonky2::iop::witness::Witness;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
-use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+use plonky2::plonk::plonk_common::salt_size;
 use plonky2::util::reducing::ReducingFactorTarget;
 use plonky2::with_context;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::RecursiveConstraintConsumer;
-use crate::permutation::PermutationCheckDataTarget;
+use crate::cross_table_lookup::{
+    verify_cross_table_lookups_circuit, CrossTableLookup, CtlCheckVarsTarget,
+};
+use crate::lookup::LookupCheckDataTarget;
+use crate::multi_stark::{MultiStark, TableVisitor};
+use crate::permutation::{
+    get_permutation_challenge_set_target, PermutationChallengeSet, PermutationCheckDataTarget,
+};
+use crate::preprocessed::StarkVerifyingKey;
 use crate::proof::{
-    StarkOpeningSetTarget, StarkProof, StarkProofChallengesTarget, StarkProofTarget,
-    StarkProofWithPublicInputs, StarkProofWithPublicInputsTarget,
+    MultiProof, MultiProofTarget, StarkOpeningSetTarget, StarkProof, StarkProofChallengesTarget,
+    StarkProofTarget, StarkProofWithPublicInputs, StarkProofWithPublicInputsTarget,
 };
 use crate::stark::Stark;
 use crate::vanishing_poly::eval_vanishing_poly_circuit;
 use crate::vars::StarkEvaluationTargets;
+use crate::verifier::VerifyingKeyVisitor;
 
 pub fn verify_stark_proof_circuit<
     F: RichField + Extendable<D>,
@@ -34,40 +46,265 @@ pub fn verify_stark_proof_circuit<
     stark: S,
     proof_with_pis: StarkProofWithPublicInputsTarget<D>,
     inner_config: &StarkConfig,
+) where
+    C::Hasher: AlgebraicHasher<F>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let verifying_key = StarkVerifyingKey::<F, C, D>::new(&stark, inner_config);
+    verify_stark_proof_with_verifying_key_circuit::<F, C, S, D>(
+        builder,
+        stark,
+        &verifying_key,
+        proof_with_pis,
+        inner_config,
+    );
+}
+
+/// Recursively verifies an inner proof against the given verifying key, which is hardcoded in the
+/// circuit.
+pub fn verify_stark_proof_with_verifying_key_circuit<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    const D: usize,
+>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: S,
+    verifying_key: &StarkVerifyingKey<F, C, D>,
+    proof_with_pis: StarkProofWithPublicInputsTarget<D>,
+    inner_config: &StarkConfig,
 ) where
     C::Hasher: AlgebraicHasher<F>,
     [(); S::COLUMNS]:,
     [(); S::PUBLIC_INPUTS]:,
 {
     assert_eq!(proof_with_pis.public_inputs.len(), S::PUBLIC_INPUTS);
+    assert_eq!(
+        verifying_key.preprocessed_cap.is_some(),
+        stark.uses_preprocessed_columns(),
+        "Verifying key doesn't match with Stark configuration."
+    );
     let degree_bits = proof_with_pis.proof.recover_degree_bits(inner_config);
+    verifying_key.check_degree_bits(degree_bits).unwrap();
+    let preprocessed_cap = verifying_key
+        .preprocessed_cap
+        .as_ref()
+        .map(|cap| builder.constant_merkle_cap(cap));
     let challenges = with_context!(
         builder,
         "compute challenges",
-        proof_with_pis.get_challenges::<F, C, S>(builder, &stark, inner_config)
+        proof_with_pis.get_challenges::<F, C, S>(
+            builder,
+            &stark,
+            preprocessed_cap.as_ref(),
+            inner_config
+        )
     );
 
     verify_stark_proof_with_challenges_circuit::<F, C, S, D>(
         builder,
-        stark,
-        proof_with_pis,
+        &stark,
+        preprocessed_cap,
+        &proof_with_pis,
         challenges,
+        &[],
         inner_config,
         degree_bits,
     );
 }
 
-/// Recursively verifies an inner proof.
-fn verify_stark_proof_with_challenges_circuit<
+/// Recursively verifies a proof of a `MultiStark`, including the cross-table lookups between its
+/// tables.
+pub fn verify_multi_stark_proof_circuit<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    M: MultiStark<F, D>,
+    const D: usize,
+>(
+    builder: &mut CircuitBuilder<F, D>,
+    multi_stark: &M,
+    multi_proof: MultiProofTarget<D>,
+    inner_config: &StarkConfig,
+) where
+    C::Hasher: AlgebraicHasher<F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let num_tables = multi_stark.num_tables();
+    assert_eq!(multi_proof.stark_proofs.len(), num_tables);
+    let cross_table_lookups = multi_stark.cross_table_lookups();
+    CrossTableLookup::check_tables(&cross_table_lookups, num_tables).unwrap();
+
+    let verifying_keys = (0..num_tables)
+        .map(|table| {
+            multi_stark.visit_table(table, VerifyingKeyVisitor::<F, C, D>::new(inner_config))
+        })
+        .collect::<Vec<_>>();
+    let preprocessed_caps = verifying_keys
+        .iter()
+        .map(|verifying_key| {
+            verifying_key
+                .preprocessed_cap
+                .as_ref()
+                .map(|cap| builder.constant_merkle_cap(cap))
+        })
+        .collect::<Vec<_>>();
+
+    let mut challenger = RecursiveChallenger::<F, C::Hasher, D>::new(builder);
+    for (preprocessed_cap, proof_with_pis) in
+        preprocessed_caps.iter().zip(&multi_proof.stark_proofs)
+    {
+        if let Some(preprocessed_cap) = preprocessed_cap {
+            challenger.observe_cap(preprocessed_cap);
+        }
+        challenger.observe_cap(&proof_with_pis.proof.trace_cap);
+    }
+
+    let ctl_challenges =
+        get_permutation_challenge_set_target(builder, &mut challenger, inner_config.num_challenges);
+
+    for (table, ((verifying_key, preprocessed_cap), proof_with_pis)) in verifying_keys
+        .iter()
+        .zip(preprocessed_caps)
+        .zip(&multi_proof.stark_proofs)
+        .enumerate()
+    {
+        multi_stark.visit_table(
+            table,
+            VerifyTableCircuitVisitor::<F, C, D> {
+                builder,
+                table,
+                verifying_key,
+                preprocessed_cap,
+                proof_with_pis,
+                cross_table_lookups: &cross_table_lookups,
+                ctl_challenges: &ctl_challenges,
+                challenger: &mut challenger,
+                inner_config,
+            },
+        );
+    }
+
+    // The openings of the cross-table lookup `Z`s are base field elements.
+    let ctl_zs_lasts = multi_proof
+        .stark_proofs
+        .iter()
+        .map(|proof_with_pis| {
+            proof_with_pis
+                .proof
+                .openings
+                .ctl_zs_last
+                .iter()
+                .flatten()
+                .map(|z_last| z_last.0[0])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    with_context!(
+        builder,
+        "verify cross-table lookups",
+        verify_cross_table_lookups_circuit::<F, D>(
+            builder,
+            &cross_table_lookups,
+            &ctl_zs_lasts,
+            &multi_proof.degree_bits(inner_config),
+            &ctl_challenges,
+        )
+    );
+}
+
+/// Recursively verifies the proof of a table of a `MultiStark`, whose trace commitment has already
+/// been observed.
+struct VerifyTableCircuitVisitor<'a, F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    builder: &'a mut CircuitBuilder<F, D>,
+    table: usize,
+    verifying_key: &'a StarkVerifyingKey<F, C, D>,
+    preprocessed_cap: Option<MerkleCapTarget>,
+    proof_with_pis: &'a StarkProofWithPublicInputsTarget<D>,
+    cross_table_lookups: &'a [CrossTableLookup<F>],
+    ctl_challenges: &'a PermutationChallengeSet<Target>,
+    challenger: &'a mut RecursiveChallenger<F, C::Hasher, D>,
+    inner_config: &'a StarkConfig,
+}
+
+impl<'a, F, C, const D: usize> TableVisitor<F, D> for VerifyTableCircuitVisitor<'a, F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    type Output = ();
+
+    fn visit<S: Stark<F, D>>(self, stark: &S)
+    where
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+    {
+        let Self {
+            builder,
+            table,
+            verifying_key,
+            preprocessed_cap,
+            proof_with_pis,
+            cross_table_lookups,
+            ctl_challenges,
+            challenger,
+            inner_config,
+        } = self;
+        assert_eq!(proof_with_pis.public_inputs.len(), S::PUBLIC_INPUTS);
+        let degree_bits = proof_with_pis.proof.recover_degree_bits(inner_config);
+        verifying_key.check_degree_bits(degree_bits).unwrap();
+        let challenges = with_context!(
+            builder,
+            "compute challenges",
+            proof_with_pis.proof.get_challenges::<F, C, S>(
+                builder,
+                stark,
+                challenger,
+                inner_config
+            )
+        );
+        let ctl_vars = CtlCheckVarsTarget::from_proof(
+            table,
+            &proof_with_pis.proof,
+            cross_table_lookups,
+            ctl_challenges,
+            stark.num_auxiliary_polys(inner_config),
+        );
+
+        verify_stark_proof_with_challenges_circuit::<F, C, S, D>(
+            builder,
+            stark,
+            preprocessed_cap,
+            proof_with_pis,
+            challenges,
+            &ctl_vars,
+            inner_config,
+            degree_bits,
+        );
+    }
+}
+
+/// Recursively verifies an inner proof. If the STARK is part of a `MultiStark`, `ctl_vars` holds
+/// the data needed to check its cross-table lookup `Z`s.
+pub(crate) fn verify_stark_proof_with_challenges_circuit<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     S: Stark<F, D>,
     const D: usize,
 >(
     builder: &mut CircuitBuilder<F, D>,
-    stark: S,
-    proof_with_pis: StarkProofWithPublicInputsTarget<D>,
+    stark: &S,
+    preprocessed_cap: Option<MerkleCapTarget>,
+    proof_with_pis: &StarkProofWithPublicInputsTarget<D>,
     challenges: StarkProofChallengesTarget<D>,
+    ctl_vars: &[CtlCheckVarsTarget<F, D>],
     inner_config: &StarkConfig,
     degree_bits: usize,
 ) where
@@ -75,7 +312,8 @@ fn verify_stark_proof_with_challenges_circuit<
     [(); S::COLUMNS]:,
     [(); S::PUBLIC_INPUTS]:,
 {
-    check_permutation_options(&stark, &proof_with_pis, &challenges).unwrap();
+    let num_ctl_zs = ctl_vars.len();
+    check_auxiliary_options(stark, proof_with_pis, &challenges, num_ctl_zs).unwrap();
     let one = builder.one_extension();
 
     let StarkProofWithPublicInputsTarget {
@@ -83,18 +321,28 @@ fn verify_stark_proof_with_challenges_circuit<
         public_inputs,
     } = proof_with_pis;
     let StarkOpeningSetTarget {
+        preprocessed_values,
+        preprocessed_next_values,
         local_values,
         next_values,
-        permutation_zs,
-        permutation_zs_next,
+        extra_rows_values,
+        auxiliary_polys,
+        auxiliary_polys_next,
+        ctl_zs_last: _,
         quotient_polys,
     } = &proof.openings;
     let vars = StarkEvaluationTargets {
         local_values: &local_values.to_vec().try_into().unwrap(),
         next_values: &next_values.to_vec().try_into().unwrap(),
+        extra_rows: &extra_rows_values
+            .iter()
+            .map(|values| values.to_vec().try_into().unwrap())
+            .collect::<Vec<_>>(),
+        local_preprocessed: preprocessed_values.as_deref().unwrap_or_default(),
+        next_preprocessed: preprocessed_next_values.as_deref().unwrap_or_default(),
         public_inputs: &public_inputs
-            .into_iter()
-            .map(|t| builder.convert_to_ext(t))
+            .iter()
+            .map(|&t| builder.convert_to_ext(t))
             .collect::<Vec<_>>()
             .try_into()
             .unwrap(),
@@ -116,32 +364,48 @@ fn verify_stark_proof_with_challenges_circuit<
         l_last,
     );
 
+    let num_permutation_zs = stark.num_permutation_batches(inner_config);
+    let num_auxiliary_polys = stark.num_auxiliary_polys(inner_config);
     let permutation_data = stark
         .uses_permutation_args()
         .then(|| PermutationCheckDataTarget {
-            local_zs: permutation_zs.as_ref().unwrap().clone(),
-            next_zs: permutation_zs_next.as_ref().unwrap().clone(),
+            local_zs: auxiliary_polys.as_ref().unwrap()[..num_permutation_zs].to_vec(),
+            next_zs: auxiliary_polys_next.as_ref().unwrap()[..num_permutation_zs].to_vec(),
             permutation_challenge_sets: challenges.permutation_challenge_sets.unwrap(),
         });
+    let lookup_data = stark.uses_lookups().then(|| LookupCheckDataTarget {
+        local_values: auxiliary_polys.as_ref().unwrap()[num_permutation_zs..num_auxiliary_polys]
+            .to_vec(),
+        next_values: auxiliary_polys_next.as_ref().unwrap()
+            [num_permutation_zs..num_auxiliary_polys]
+            .to_vec(),
+        challenges: challenges.lookup_challenges.unwrap(),
+    });
 
     with_context!(
         builder,
         "evaluate vanishing polynomial",
         eval_vanishing_poly_circuit::<F, C, S, D>(
             builder,
-            &stark,
+            stark,
             inner_config,
             vars,
             permutation_data,
+            lookup_data,
+            ctl_vars,
             &mut consumer,
         )
     );
     let vanishing_polys_zeta = consumer.accumulators();
 
     // Check each polynomial identity, of the form `vanishing(x) = Z_H(x) quotient(x)`, at zeta.
-    let mut scale = ReducingFactorTarget::new(zeta_pow_deg);
+    let zeta_pow_committed_deg = builder.exp_power_of_2_extension(
+        zeta_pow_deg,
+        inner_config.committed_degree_bits(degree_bits) - degree_bits,
+    );
+    let mut scale = ReducingFactorTarget::new(zeta_pow_committed_deg);
     for (i, chunk) in quotient_polys
-        .chunks(stark.quotient_degree_factor())
+        .chunks(stark.num_quotient_chunks(inner_config))
         .enumerate()
     {
         let recombined_quotient = scale.reduce(chunk, builder);
@@ -149,15 +413,18 @@ fn verify_stark_proof_with_challenges_circuit<
         builder.connect_extension(vanishing_polys_zeta[i], computed_vanishing_poly);
     }
 
-    let merkle_caps = once(proof.trace_cap)
-        .chain(proof.permutation_zs_cap)
-        .chain(once(proof.quotient_polys_cap))
+    let merkle_caps = preprocessed_cap
+        .into_iter()
+        .chain(once(proof.trace_cap.clone()))
+        .chain(proof.auxiliary_polys_cap.clone())
+        .chain(once(proof.quotient_polys_cap.clone()))
         .collect_vec();
 
     let fri_instance = stark.fri_instance_target(
         builder,
         challenges.stark_zeta,
         F::primitive_root_of_unity(degree_bits),
+        num_ctl_zs,
         inner_config,
     );
     builder.verify_fri_proof::<C>(
@@ -212,49 +479,158 @@ pub fn add_virtual_stark_proof<F: RichField + Extendable<D>, S: Stark<F, D>, con
     stark: S,
     config: &StarkConfig,
     degree_bits: usize,
+) -> StarkProofTarget<D> {
+    add_virtual_stark_proof_with_ctl_zs::<F, S, D>(builder, &stark, config, degree_bits, 0)
+}
+
+/// Adds a virtual proof of a STARK with `num_ctl_zs` cross-table lookup `Z`s, which are committed
+/// to along with its auxiliary polynomials.
+pub(crate) fn add_virtual_stark_proof_with_ctl_zs<
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    const D: usize,
+>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: &S,
+    config: &StarkConfig,
+    degree_bits: usize,
+    num_ctl_zs: usize,
 ) -> StarkProofTarget<D> {
     let fri_params = config.fri_params(degree_bits);
     let cap_height = fri_params.config.cap_height;
 
-    let num_leaves_per_oracle = once(S::COLUMNS)
+    let salt = salt_size(fri_params.hiding);
+    let uses_auxiliary_polys = stark.uses_auxiliary_polys() || num_ctl_zs > 0;
+    // Preprocessed values are public, so their leaves are never salted.
+    let num_leaves_per_oracle = stark
+        .uses_preprocessed_columns()
+        .then_some(S::PREPROCESSED_COLUMNS)
+        .into_iter()
         .chain(
-            stark
-                .uses_permutation_args()
-                .then(|| stark.num_permutation_batches(config)),
+            once(S::COLUMNS)
+                .chain(uses_auxiliary_polys.then(|| stark.num_auxiliary_polys(config) + num_ctl_zs))
+                .chain(once(stark.num_quotient_polys(config)))
+                .map(|num_polys| num_polys + salt),
         )
-        .chain(once(stark.quotient_degree_factor() * config.num_challenges))
         .collect_vec();
 
-    let permutation_zs_cap = stark
-        .uses_permutation_args()
-        .then(|| builder.add_virtual_cap(cap_height));
+    let auxiliary_polys_cap = uses_auxiliary_polys.then(|| builder.add_virtual_cap(cap_height));
 
     StarkProofTarget {
         trace_cap: builder.add_virtual_cap(cap_height),
-        permutation_zs_cap,
+        auxiliary_polys_cap,
         quotient_polys_cap: builder.add_virtual_cap(cap_height),
-        openings: add_stark_opening_set_target::<F, S, D>(builder, stark, config),
+        openings: add_stark_opening_set_target::<F, S, D>(builder, stark, config, num_ctl_zs),
         opening_proof: builder.add_virtual_fri_proof(&num_leaves_per_oracle, &fri_params),
     }
 }
 
+/// Adds a virtual proof of a `MultiStark`, whose tables have the given lengths.
+pub fn add_virtual_multi_stark_proof<
+    F: RichField + Extendable<D>,
+    M: MultiStark<F, D>,
+    const D: usize,
+>(
+    builder: &mut CircuitBuilder<F, D>,
+    multi_stark: &M,
+    config: &StarkConfig,
+    degrees_bits: &[usize],
+) -> MultiProofTarget<D> {
+    let num_tables = multi_stark.num_tables();
+    assert_eq!(degrees_bits.len(), num_tables);
+    let cross_table_lookups = multi_stark.cross_table_lookups();
+
+    let stark_proofs = degrees_bits
+        .iter()
+        .enumerate()
+        .map(|(table, &degree_bits)| {
+            multi_stark.visit_table(
+                table,
+                AddVirtualProofVisitor {
+                    builder,
+                    config,
+                    degree_bits,
+                    num_ctl_zs: CrossTableLookup::num_ctl_zs(
+                        &cross_table_lookups,
+                        table,
+                        config.num_challenges,
+                    ),
+                },
+            )
+        })
+        .collect();
+
+    MultiProofTarget { stark_proofs }
+}
+
+/// Adds a virtual proof of a table of a `MultiStark`.
+struct AddVirtualProofVisitor<'a, F: RichField + Extendable<D>, const D: usize> {
+    builder: &'a mut CircuitBuilder<F, D>,
+    config: &'a StarkConfig,
+    degree_bits: usize,
+    num_ctl_zs: usize,
+}
+
+impl<'a, F: RichField + Extendable<D>, const D: usize> TableVisitor<F, D>
+    for AddVirtualProofVisitor<'a, F, D>
+{
+    type Output = StarkProofWithPublicInputsTarget<D>;
+
+    fn visit<S: Stark<F, D>>(self, stark: &S) -> Self::Output
+    where
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+    {
+        let proof = add_virtual_stark_proof_with_ctl_zs::<F, S, D>(
+            self.builder,
+            stark,
+            self.config,
+            self.degree_bits,
+            self.num_ctl_zs,
+        );
+        let public_inputs = self.builder.add_virtual_targets(S::PUBLIC_INPUTS);
+        StarkProofWithPublicInputsTarget {
+            proof,
+            public_inputs,
+        }
+    }
+}
+
 fn add_stark_opening_set_target<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
     builder: &mut CircuitBuilder<F, D>,
-    stark: S,
+    stark: &S,
     config: &StarkConfig,
+    num_ctl_zs: usize,
 ) -> StarkOpeningSetTarget<D> {
-    let num_challenges = config.num_challenges;
+    let uses_auxiliary_polys = stark.uses_auxiliary_polys() || num_ctl_zs > 0;
+    let num_auxiliary_polys = stark.num_auxiliary_polys(config) + num_ctl_zs;
+    let zero = builder.zero();
     StarkOpeningSetTarget {
+        preprocessed_values: stark
+            .uses_preprocessed_columns()
+            .then(|| builder.add_virtual_extension_targets(S::PREPROCESSED_COLUMNS)),
+        preprocessed_next_values: stark
+            .uses_preprocessed_columns()
+            .then(|| builder.add_virtual_extension_targets(S::PREPROCESSED_COLUMNS)),
         local_values: builder.add_virtual_extension_targets(S::COLUMNS),
         next_values: builder.add_virtual_extension_targets(S::COLUMNS),
-        permutation_zs: stark
-            .uses_permutation_args()
-            .then(|| builder.add_virtual_extension_targets(stark.num_permutation_batches(config))),
-        permutation_zs_next: stark
-            .uses_permutation_args()
-            .then(|| builder.add_virtual_extension_targets(stark.num_permutation_batches(config))),
-        quotient_polys: builder
-            .add_virtual_extension_targets(stark.quotient_degree_factor() * num_challenges),
+        extra_rows_values: stark
+            .extra_row_offsets()
+            .iter()
+            .map(|_| builder.add_virtual_extension_targets(S::COLUMNS))
+            .collect(),
+        auxiliary_polys: uses_auxiliary_polys
+            .then(|| builder.add_virtual_extension_targets(num_auxiliary_polys)),
+        auxiliary_polys_next: uses_auxiliary_polys
+            .then(|| builder.add_virtual_extension_targets(num_auxiliary_polys)),
+        ctl_zs_last: (num_ctl_zs > 0).then(|| {
+            builder
+                .add_virtual_targets(num_ctl_zs)
+                .into_iter()
+                .map(|t| t.to_ext_target(zero))
+                .collect()
+        }),
+        quotient_polys: builder.add_virtual_extension_targets(stark.num_quotient_polys(config)),
     }
 }
 
@@ -284,6 +660,24 @@ pub fn set_stark_proof_with_pis_target<F, C: GenericConfig<D, F = F>, W, const D
     set_stark_proof_target(witness, pt, proof);
 }
 
+pub fn set_multi_stark_proof_target<F, C: GenericConfig<D, F = F>, W, const D: usize>(
+    witness: &mut W,
+    multi_proof_target: &MultiProofTarget<D>,
+    multi_proof: &MultiProof<F, C, D>,
+) where
+    F: RichField + Extendable<D>,
+    C::Hasher: AlgebraicHasher<F>,
+    W: Witness<F>,
+{
+    for (proof_with_pis_target, proof_with_pis) in multi_proof_target
+        .stark_proofs
+        .iter()
+        .zip_eq(&multi_proof.stark_proofs)
+    {
+        set_stark_proof_with_pis_target(witness, proof_with_pis_target, proof_with_pis);
+    }
+}
+
 pub fn set_stark_proof_target<F, C: GenericConfig<D, F = F>, W, const D: usize>(
     witness: &mut W,
     proof_target: &StarkProofTarget<D>,
@@ -301,33 +695,43 @@ pub fn set_stark_proof_target<F, C: GenericConfig<D, F = F>, W, const D: usize>(
         &proof.openings.to_fri_openings(),
     );
 
-    if let (Some(permutation_zs_cap_target), Some(permutation_zs_cap)) =
-        (&proof_target.permutation_zs_cap, &proof.permutation_zs_cap)
-    {
-        witness.set_cap_target(permutation_zs_cap_target, permutation_zs_cap);
+    if let (Some(auxiliary_polys_cap_target), Some(auxiliary_polys_cap)) = (
+        &proof_target.auxiliary_polys_cap,
+        &proof.auxiliary_polys_cap,
+    ) {
+        witness.set_cap_target(auxiliary_polys_cap_target, auxiliary_polys_cap);
     }
 
     set_fri_proof_target(witness, &proof_target.opening_proof, &proof.opening_proof);
 }
 
-/// Utility function to check that all permutation data wrapped in `Option`s are `Some` iff
-/// the Stark uses a permutation argument.
-fn check_permutation_options<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
+/// Utility function to check that all permutation and lookup data wrapped in `Option`s are `Some`
+/// iff the Stark uses a permutation or lookup argument respectively. Auxiliary polynomials are also
+/// committed to if the Stark takes part in cross-table lookups.
+fn check_auxiliary_options<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
     stark: &S,
     proof_with_pis: &StarkProofWithPublicInputsTarget<D>,
     challenges: &StarkProofChallengesTarget<D>,
+    num_ctl_zs: usize,
 ) -> Result<()> {
-    let options_is_some = [
-        proof_with_pis.proof.permutation_zs_cap.is_some(),
-        proof_with_pis.proof.openings.permutation_zs.is_some(),
-        proof_with_pis.proof.openings.permutation_zs_next.is_some(),
-        challenges.permutation_challenge_sets.is_some(),
+    let auxiliary_options_is_some = [
+        proof_with_pis.proof.auxiliary_polys_cap.is_some(),
+        proof_with_pis.proof.openings.auxiliary_polys.is_some(),
+        proof_with_pis.proof.openings.auxiliary_polys_next.is_some(),
     ];
     ensure!(
-        options_is_some
+        auxiliary_options_is_some
             .into_iter()
-            .all(|b| b == stark.uses_permutation_args()),
+            .all(|b| b == (stark.uses_auxiliary_polys() || num_ctl_zs > 0)),
+        "Auxiliary polynomials data doesn't match with Stark configuration."
+    );
+    ensure!(
+        challenges.permutation_challenge_sets.is_some() == stark.uses_permutation_args(),
         "Permutation data doesn't match with Stark configuration."
     );
+    ensure!(
+        challenges.lookup_challenges.is_some() == stark.uses_lookups(),
+        "Lookup data doesn't match with Stark configuration."
+    );
     Ok(())
 }
@@ -0,0 +1,345 @@
+use std::marker::PhantomData;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// Toy STARK system with a preprocessed column, used for testing.
+/// Computes `x_i = x_{i-1} + c_i`, starting from `x_0 = x + c_0`, where the round constants
+/// `c_i = i^3` are a preprocessed column.
+#[derive(Copy, Clone)]
+struct RoundConstantsStark<F: RichField + Extendable<D>, const D: usize> {
+    num_rows: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> RoundConstantsStark<F, D> {
+    // The first public input is `x`.
+    const PI_INDEX_X: usize = 0;
+    // The second public input is the value of the last row.
+    const PI_INDEX_RES: usize = 1;
+
+    fn new(num_rows: usize) -> Self {
+        Self {
+            num_rows,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn round_constants(&self) -> Vec<F> {
+        (0..self.num_rows)
+            .map(|i| F::from_canonical_usize(i).cube())
+            .collect()
+    }
+
+    /// Generate the trace starting from `x`.
+    fn generate_trace(&self, x: F) -> Vec<PolynomialValues<F>> {
+        let trace_rows = self
+            .round_constants()
+            .into_iter()
+            .scan(x, |acc, c| {
+                *acc += c;
+                Some([*acc])
+            })
+            .collect::<Vec<_>>();
+        trace_rows_to_poly_values(trace_rows)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RoundConstantsStark<F, D> {
+    const COLUMNS: usize = 1;
+    const PUBLIC_INPUTS: usize = 2;
+    const PREPROCESSED_COLUMNS: usize = 1;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        // Check public inputs.
+        yield_constr.constraint_first_row(
+            vars.local_values[0]
+                - vars.public_inputs[Self::PI_INDEX_X]
+                - vars.local_preprocessed[0],
+        );
+        yield_constr
+            .constraint_last_row(vars.local_values[0] - vars.public_inputs[Self::PI_INDEX_RES]);
+
+        // x' <- x + c'
+        yield_constr.constraint_transition(
+            vars.next_values[0] - vars.local_values[0] - vars.next_preprocessed[0],
+        );
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        // Check public inputs.
+        let first_row_constraint = {
+            let tmp =
+                builder.sub_extension(vars.local_values[0], vars.public_inputs[Self::PI_INDEX_X]);
+            builder.sub_extension(tmp, vars.local_preprocessed[0])
+        };
+        yield_constr.constraint_first_row(builder, first_row_constraint);
+        let last_row_constraint =
+            builder.sub_extension(vars.local_values[0], vars.public_inputs[Self::PI_INDEX_RES]);
+        yield_constr.constraint_last_row(builder, last_row_constraint);
+
+        // x' <- x + c'
+        let transition_constraint = {
+            let tmp = builder.sub_extension(vars.next_values[0], vars.local_values[0]);
+            builder.sub_extension(tmp, vars.next_preprocessed[0])
+        };
+        yield_constr.constraint_transition(builder, transition_constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_columns(&self) -> Vec<PolynomialValues<F>> {
+        vec![PolynomialValues::new(self.round_constants())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::extension::Extendable;
+    use plonky2::field::types::Field;
+    use plonky2::hash::hash_types::RichField;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{
+        AlgebraicHasher, GenericConfig, Hasher, PoseidonGoldilocksConfig,
+    };
+    use plonky2::util::timing::TimingTree;
+
+    use crate::config::StarkConfig;
+    use crate::preprocessed::{StarkProverData, StarkVerifyingKey};
+    use crate::proof::{CompressedStarkProofWithPublicInputs, StarkProofWithPublicInputs};
+    use crate::prover::{prove, prove_with_prover_data};
+    use crate::recursive_verifier::{
+        add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
+        verify_stark_proof_with_verifying_key_circuit,
+    };
+    use crate::round_constants_stark::RoundConstantsStark;
+    use crate::stark::Stark;
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::verifier::{verify_stark_proof, verify_stark_proof_with_verifying_key};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type S = RoundConstantsStark<F, D>;
+
+    fn public_inputs(stark: &S, x: F) -> [F; 2] {
+        [x, x + stark.round_constants().into_iter().sum::<F>()]
+    }
+
+    #[test]
+    fn test_round_constants_stark() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        let prover_data =
+            StarkProverData::<F, C, D>::new(&stark, &config, &mut TimingTree::default());
+        let verifying_key = &prover_data.verifying_key;
+
+        // The preprocessed commitment is shared by proofs of different traces.
+        for x in [F::ZERO, F::rand()] {
+            let proof = prove_with_prover_data::<F, C, S, D>(
+                stark,
+                &prover_data,
+                &config,
+                stark.generate_trace(x),
+                public_inputs(&stark, x),
+                &mut TimingTree::default(),
+            )?;
+            verify_stark_proof_with_verifying_key(stark, verifying_key, proof.clone(), &config)?;
+            verify_stark_proof(stark, proof, &config)?;
+        }
+
+        let x = F::rand();
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            stark.generate_trace(x),
+            public_inputs(&stark, x),
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof_with_verifying_key(stark, verifying_key, proof.clone(), &config)?;
+
+        // The proof is bound to the preprocessed values of the verifying key.
+        let mut bad_proof = proof.clone();
+        bad_proof
+            .proof
+            .openings
+            .preprocessed_values
+            .as_mut()
+            .unwrap()[0] += <F as Extendable<D>>::Extension::ONE;
+        assert!(
+            verify_stark_proof_with_verifying_key(stark, verifying_key, bad_proof, &config)
+                .is_err()
+        );
+        let other_verifying_key = StarkVerifyingKey::new(&S::new(1 << 6), &config);
+        assert_eq!(other_verifying_key.degree_bits, Some(6));
+        let err =
+            verify_stark_proof_with_verifying_key(stark, &other_verifying_key, proof, &config)
+                .unwrap_err();
+        assert!(err.to_string().contains("verifying key have length 2^6"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_constants_stark_zk() -> Result<()> {
+        let config = StarkConfig::standard_fast_zk_config();
+        let stark = S::new(1 << 5);
+        let prover_data =
+            StarkProverData::<F, C, D>::new(&stark, &config, &mut TimingTree::default());
+
+        let x = F::rand();
+        let proof = prove_with_prover_data::<F, C, S, D>(
+            stark,
+            &prover_data,
+            &config,
+            stark.generate_trace(x),
+            public_inputs(&stark, x),
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof_with_verifying_key(
+            stark,
+            &prover_data.verifying_key,
+            proof.clone(),
+            &config,
+        )?;
+        recursive_proof::<F, C, S, C, D>(stark, &prover_data.verifying_key, proof, &config)
+    }
+
+    #[test]
+    fn test_round_constants_stark_serialization() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        let prover_data =
+            StarkProverData::<F, C, D>::new(&stark, &config, &mut TimingTree::default());
+        let verifying_key =
+            StarkVerifyingKey::from_bytes(prover_data.verifying_key.to_bytes()?, &config)?;
+        assert_eq!(verifying_key, prover_data.verifying_key);
+
+        let x = F::rand();
+        let proof = prove_with_prover_data::<F, C, S, D>(
+            stark,
+            &prover_data,
+            &config,
+            stark.generate_trace(x),
+            public_inputs(&stark, x),
+            &mut TimingTree::default(),
+        )?;
+        let compressed_proof = proof.clone().compress(&stark, &verifying_key, &config);
+        let compressed_proof = CompressedStarkProofWithPublicInputs::from_bytes(
+            compressed_proof.to_bytes()?,
+            &config,
+        )?;
+        let decompressed_proof = compressed_proof.decompress(&stark, &verifying_key, &config)?;
+        assert_eq!(decompressed_proof, proof);
+        verify_stark_proof_with_verifying_key(stark, &verifying_key, decompressed_proof, &config)
+    }
+
+    #[test]
+    fn test_round_constants_stark_degree() -> Result<()> {
+        test_stark_low_degree(S::new(1 << 5))
+    }
+
+    #[test]
+    fn test_round_constants_stark_circuit() -> Result<()> {
+        test_stark_circuit_constraints::<F, C, S, D>(S::new(1 << 5))
+    }
+
+    #[test]
+    fn test_recursive_round_constants_stark_verifier() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        let prover_data =
+            StarkProverData::<F, C, D>::new(&stark, &config, &mut TimingTree::default());
+
+        let x = F::rand();
+        let proof = prove_with_prover_data::<F, C, S, D>(
+            stark,
+            &prover_data,
+            &config,
+            stark.generate_trace(x),
+            public_inputs(&stark, x),
+            &mut TimingTree::default(),
+        )?;
+        recursive_proof::<F, C, S, C, D>(stark, &prover_data.verifying_key, proof, &config)
+    }
+
+    #[test]
+    #[should_panic(expected = "verifying key have length 2^6")]
+    fn test_recursive_round_constants_stark_verifier_wrong_degree() {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        let x = F::rand();
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            stark.generate_trace(x),
+            public_inputs(&stark, x),
+            &mut TimingTree::default(),
+        )
+        .unwrap();
+        let other_verifying_key = StarkVerifyingKey::new(&S::new(1 << 6), &config);
+        recursive_proof::<F, C, S, C, D>(stark, &other_verifying_key, proof, &config).unwrap();
+    }
+
+    fn recursive_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        S: Stark<F, D> + Copy,
+        InnerC: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        stark: S,
+        verifying_key: &StarkVerifyingKey<F, InnerC, D>,
+        inner_proof: StarkProofWithPublicInputs<F, InnerC, D>,
+        inner_config: &StarkConfig,
+    ) -> Result<()>
+    where
+        InnerC::Hasher: AlgebraicHasher<F>,
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let circuit_config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(circuit_config);
+        let mut pw = PartialWitness::new();
+        let degree_bits = inner_proof.proof.recover_degree_bits(inner_config);
+        let pt = add_virtual_stark_proof_with_pis(&mut builder, stark, inner_config, degree_bits);
+        set_stark_proof_with_pis_target(&mut pw, &pt, &inner_proof);
+
+        verify_stark_proof_with_verifying_key_circuit::<F, InnerC, S, D>(
+            &mut builder,
+            stark,
+            verifying_key,
+            pt,
+            inner_config,
+        );
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
@@ -0,0 +1,483 @@
+//! Binary encoding of STARK proofs and verifying keys, built on plonky2's `Buffer`.
+//!
+//! Every encoded object starts with a version byte, so that the format can evolve while older
+//! encodings are rejected explicitly. The lengths of opening vectors and public inputs are
+//! prefixed, which makes the encoding independent of the `Stark` that produced it, but the
+//! `StarkConfig` is still needed to read Merkle caps and FRI proofs. The FRI proof itself is
+//! written without any length prefix, as its shape follows from the config, the trace length and
+//! the openings.
+//!
+//! The helpers encoding versions, lengths, options and field vectors, and checking degrees read
+//! from an encoding, are public, so that crates with their own proof types, such as the EVM, can
+//! encode them the same way.
+
+use std::io::{Error, ErrorKind};
+
+use plonky2::field::extension::Extendable;
+use plonky2::fri::{FriConfig, FriParams};
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleCap;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::plonk::plonk_common::salt_size;
+use plonky2::util::serialization::{Buffer, IoResult};
+
+use crate::config::StarkConfig;
+use crate::preprocessed::StarkVerifyingKey;
+use crate::proof::{
+    CompressedStarkProof, CompressedStarkProofWithPublicInputs, MultiProof, StarkOpeningSet,
+    StarkProof, StarkProofWithPublicInputs,
+};
+
+/// The version of the encoding, written at the start of every encoded object.
+pub const SERIALIZATION_VERSION: u8 = 1;
+
+pub fn write_stark_proof_with_public_inputs<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    proof_with_pis: &StarkProofWithPublicInputs<F, C, D>,
+    config: &StarkConfig,
+) -> IoResult<()> {
+    buffer.write_u8(SERIALIZATION_VERSION)?;
+    write_proof_with_pis_body(buffer, proof_with_pis, config)
+}
+
+pub fn read_stark_proof_with_public_inputs<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    config: &StarkConfig,
+) -> IoResult<StarkProofWithPublicInputs<F, C, D>> {
+    read_version(buffer)?;
+    read_proof_with_pis_body(buffer, config)
+}
+
+pub fn write_compressed_stark_proof_with_public_inputs<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    proof_with_pis: &CompressedStarkProofWithPublicInputs<F, C, D>,
+) -> IoResult<()> {
+    let CompressedStarkProofWithPublicInputs {
+        proof,
+        public_inputs,
+    } = proof_with_pis;
+    let CompressedStarkProof {
+        degree_bits,
+        trace_cap,
+        auxiliary_polys_cap,
+        quotient_polys_cap,
+        openings,
+        opening_proof,
+    } = proof;
+
+    buffer.write_u8(SERIALIZATION_VERSION)?;
+    write_degree_bits(buffer, *degree_bits)?;
+    write_caps::<F, C, D>(buffer, trace_cap, auxiliary_polys_cap, quotient_polys_cap)?;
+    write_opening_set(buffer, openings)?;
+    buffer.write_compressed_fri_proof::<F, C, D>(opening_proof)?;
+    write_field_vec(buffer, public_inputs)
+}
+
+pub fn read_compressed_stark_proof_with_public_inputs<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    config: &StarkConfig,
+) -> IoResult<CompressedStarkProofWithPublicInputs<F, C, D>> {
+    read_version(buffer)?;
+    let degree_bits = buffer.read_u8()? as usize;
+    let cap_height = config.fri_config.cap_height;
+    let trace_cap = buffer.read_merkle_cap(cap_height)?;
+    let auxiliary_polys_cap = read_option(buffer, |buffer| buffer.read_merkle_cap(cap_height))?;
+    let quotient_polys_cap = buffer.read_merkle_cap(cap_height)?;
+    let openings = read_opening_set(buffer)?;
+    let opening_proof = buffer.read_compressed_fri_proof::<F, C, D>(
+        &read_fri_params(config, degree_bits)?,
+        &initial_leaf_sizes(&openings, config),
+    )?;
+    let public_inputs = read_field_vec(buffer)?;
+
+    Ok(CompressedStarkProofWithPublicInputs {
+        proof: CompressedStarkProof {
+            degree_bits,
+            trace_cap,
+            auxiliary_polys_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof,
+        },
+        public_inputs,
+    })
+}
+
+pub fn write_multi_proof<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    multi_proof: &MultiProof<F, C, D>,
+    config: &StarkConfig,
+) -> IoResult<()> {
+    buffer.write_u8(SERIALIZATION_VERSION)?;
+    write_len(buffer, multi_proof.stark_proofs.len())?;
+    for proof_with_pis in &multi_proof.stark_proofs {
+        write_proof_with_pis_body(buffer, proof_with_pis, config)?;
+    }
+    Ok(())
+}
+
+pub fn read_multi_proof<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    config: &StarkConfig,
+) -> IoResult<MultiProof<F, C, D>> {
+    read_version(buffer)?;
+    let num_tables = read_len(buffer)?;
+    let stark_proofs = (0..num_tables)
+        .map(|_| read_proof_with_pis_body(buffer, config))
+        .collect::<IoResult<_>>()?;
+    Ok(MultiProof { stark_proofs })
+}
+
+pub fn write_stark_verifying_key<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    verifying_key: &StarkVerifyingKey<F, C, D>,
+) -> IoResult<()> {
+    buffer.write_u8(SERIALIZATION_VERSION)?;
+    write_option(
+        buffer,
+        verifying_key.preprocessed_cap.as_ref(),
+        |buffer, cap| buffer.write_merkle_cap(cap),
+    )?;
+    write_option(
+        buffer,
+        verifying_key.degree_bits.as_ref(),
+        |buffer, &bits| write_degree_bits(buffer, bits),
+    )
+}
+
+pub fn read_stark_verifying_key<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    config: &StarkConfig,
+) -> IoResult<StarkVerifyingKey<F, C, D>> {
+    read_version(buffer)?;
+    let preprocessed_cap = read_option(buffer, |buffer| {
+        buffer.read_merkle_cap(config.fri_config.cap_height)
+    })?;
+    let degree_bits = read_option(buffer, |buffer| Ok(buffer.read_u8()? as usize))?;
+    Ok(StarkVerifyingKey {
+        preprocessed_cap,
+        degree_bits,
+    })
+}
+
+pub fn read_version(buffer: &mut Buffer) -> IoResult<()> {
+    let version = buffer.read_u8()?;
+    if version != SERIALIZATION_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Unsupported serialization version {version}, expected {SERIALIZATION_VERSION}."
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Writes a STARK proof and its public inputs, without the version byte, so that several proofs
+/// can share it.
+fn write_proof_with_pis_body<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    proof_with_pis: &StarkProofWithPublicInputs<F, C, D>,
+    config: &StarkConfig,
+) -> IoResult<()> {
+    let StarkProofWithPublicInputs {
+        proof,
+        public_inputs,
+    } = proof_with_pis;
+    let StarkProof {
+        trace_cap,
+        auxiliary_polys_cap,
+        quotient_polys_cap,
+        openings,
+        opening_proof,
+    } = proof;
+
+    // The trace length can't be recovered before reading the FRI proof, which depends on it.
+    write_degree_bits(buffer, proof.recover_degree_bits(config))?;
+    write_caps::<F, C, D>(buffer, trace_cap, auxiliary_polys_cap, quotient_polys_cap)?;
+    write_opening_set(buffer, openings)?;
+    buffer.write_fri_proof::<F, C, D>(opening_proof)?;
+    write_field_vec(buffer, public_inputs)
+}
+
+fn read_proof_with_pis_body<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    buffer: &mut Buffer,
+    config: &StarkConfig,
+) -> IoResult<StarkProofWithPublicInputs<F, C, D>> {
+    let degree_bits = buffer.read_u8()? as usize;
+    let cap_height = config.fri_config.cap_height;
+    let trace_cap = buffer.read_merkle_cap(cap_height)?;
+    let auxiliary_polys_cap = read_option(buffer, |buffer| buffer.read_merkle_cap(cap_height))?;
+    let quotient_polys_cap = buffer.read_merkle_cap(cap_height)?;
+    let openings = read_opening_set(buffer)?;
+    let opening_proof = buffer.read_fri_proof::<F, C, D>(
+        &read_fri_params(config, degree_bits)?,
+        &initial_leaf_sizes(&openings, config),
+    )?;
+    let public_inputs = read_field_vec(buffer)?;
+
+    Ok(StarkProofWithPublicInputs {
+        proof: StarkProof {
+            trace_cap,
+            auxiliary_polys_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof,
+        },
+        public_inputs,
+    })
+}
+
+/// The FRI parameters of a proof whose trace length was read from an untrusted encoding.
+fn read_fri_params(config: &StarkConfig, degree_bits: usize) -> IoResult<FriParams> {
+    checked_fri_params(
+        &config.fri_config,
+        config.committed_degree_bits(degree_bits),
+        config.zero_knowledge,
+    )
+}
+
+/// Like `FriConfig::fri_params`, but for a `degree_bits` read from an untrusted encoding: fails
+/// rather than panicking if the LDE size doesn't fit in a `usize`, or if the degree is smaller than
+/// the total reduction arity of the config.
+pub fn checked_fri_params(
+    fri_config: &FriConfig,
+    degree_bits: usize,
+    hiding: bool,
+) -> IoResult<FriParams> {
+    if degree_bits + fri_config.rate_bits >= usize::BITS as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Degree bits {degree_bits} are too large."),
+        ));
+    }
+    let fri_params = fri_config.fri_params(degree_bits, hiding);
+    if fri_params.total_arities() > degree_bits {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Degree bits {degree_bits} are smaller than the FRI reduction arities."),
+        ));
+    }
+    Ok(fri_params)
+}
+
+pub fn write_degree_bits(buffer: &mut Buffer, degree_bits: usize) -> IoResult<()> {
+    buffer.write_u8(degree_bits.try_into().expect("Degree bits must fit in u8."))
+}
+
+fn write_caps<F: RichField, C: GenericConfig<D, F = F>, const D: usize>(
+    buffer: &mut Buffer,
+    trace_cap: &MerkleCap<F, C::Hasher>,
+    auxiliary_polys_cap: &Option<MerkleCap<F, C::Hasher>>,
+    quotient_polys_cap: &MerkleCap<F, C::Hasher>,
+) -> IoResult<()> {
+    buffer.write_merkle_cap(trace_cap)?;
+    write_option(buffer, auxiliary_polys_cap.as_ref(), |buffer, cap| {
+        buffer.write_merkle_cap(cap)
+    })?;
+    buffer.write_merkle_cap(quotient_polys_cap)
+}
+
+fn write_opening_set<F: RichField + Extendable<D>, const D: usize>(
+    buffer: &mut Buffer,
+    openings: &StarkOpeningSet<F, D>,
+) -> IoResult<()> {
+    let StarkOpeningSet {
+        preprocessed_values,
+        preprocessed_next_values,
+        local_values,
+        next_values,
+        extra_rows_values,
+        auxiliary_polys,
+        auxiliary_polys_next,
+        ctl_zs_last,
+        quotient_polys,
+    } = openings;
+
+    write_option(
+        buffer,
+        preprocessed_values.as_deref(),
+        write_field_ext_vec::<F, D>,
+    )?;
+    write_option(
+        buffer,
+        preprocessed_next_values.as_deref(),
+        write_field_ext_vec::<F, D>,
+    )?;
+    write_field_ext_vec::<F, D>(buffer, local_values)?;
+    write_field_ext_vec::<F, D>(buffer, next_values)?;
+    write_len(buffer, extra_rows_values.len())?;
+    for values in extra_rows_values {
+        write_field_ext_vec::<F, D>(buffer, values)?;
+    }
+    write_option(
+        buffer,
+        auxiliary_polys.as_deref(),
+        write_field_ext_vec::<F, D>,
+    )?;
+    write_option(
+        buffer,
+        auxiliary_polys_next.as_deref(),
+        write_field_ext_vec::<F, D>,
+    )?;
+    write_option(buffer, ctl_zs_last.as_deref(), write_field_vec)?;
+    write_field_ext_vec::<F, D>(buffer, quotient_polys)
+}
+
+fn read_opening_set<F: RichField + Extendable<D>, const D: usize>(
+    buffer: &mut Buffer,
+) -> IoResult<StarkOpeningSet<F, D>> {
+    let preprocessed_values = read_option(buffer, read_field_ext_vec::<F, D>)?;
+    let preprocessed_next_values = read_option(buffer, read_field_ext_vec::<F, D>)?;
+    let local_values = read_field_ext_vec::<F, D>(buffer)?;
+    let next_values = read_field_ext_vec::<F, D>(buffer)?;
+    let num_extra_rows = read_len(buffer)?;
+    let extra_rows_values = (0..num_extra_rows)
+        .map(|_| read_field_ext_vec::<F, D>(buffer))
+        .collect::<IoResult<_>>()?;
+    let auxiliary_polys = read_option(buffer, read_field_ext_vec::<F, D>)?;
+    let auxiliary_polys_next = read_option(buffer, read_field_ext_vec::<F, D>)?;
+    let ctl_zs_last = read_option(buffer, read_field_vec)?;
+    let quotient_polys = read_field_ext_vec::<F, D>(buffer)?;
+
+    Ok(StarkOpeningSet {
+        preprocessed_values,
+        preprocessed_next_values,
+        local_values,
+        next_values,
+        extra_rows_values,
+        auxiliary_polys,
+        auxiliary_polys_next,
+        ctl_zs_last,
+        quotient_polys,
+    })
+}
+
+/// The sizes of the leaves of the Merkle trees opened in the initial FRI round, which follow the
+/// order of the oracles in `Stark::fri_instance`.
+fn initial_leaf_sizes<F: RichField + Extendable<D>, const D: usize>(
+    openings: &StarkOpeningSet<F, D>,
+    config: &StarkConfig,
+) -> Vec<usize> {
+    // Preprocessed values are public, so their leaves are never salted.
+    let salt = salt_size(config.zero_knowledge);
+    openings
+        .preprocessed_values
+        .as_ref()
+        .map(Vec::len)
+        .into_iter()
+        .chain([openings.local_values.len() + salt])
+        .chain(
+            openings
+                .auxiliary_polys
+                .as_ref()
+                .map(|values| values.len() + salt),
+        )
+        .chain([openings.quotient_polys.len() + salt])
+        .collect()
+}
+
+pub fn write_len(buffer: &mut Buffer, len: usize) -> IoResult<()> {
+    buffer.write_u32(len.try_into().expect("Length must fit in u32."))
+}
+
+/// Reads a length prefix, rejecting lengths larger than the number of unread bytes to avoid huge
+/// allocations when reading malformed data. Every encoded item takes at least one byte.
+pub fn read_len(buffer: &mut Buffer) -> IoResult<usize> {
+    let len = buffer.read_u32()? as usize;
+    if len > buffer.remaining() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Length {len} exceeds the remaining size of the buffer."),
+        ));
+    }
+    Ok(len)
+}
+
+pub fn write_option<T: ?Sized>(
+    buffer: &mut Buffer,
+    value: Option<&T>,
+    write_value: impl FnOnce(&mut Buffer, &T) -> IoResult<()>,
+) -> IoResult<()> {
+    buffer.write_bool(value.is_some())?;
+    match value {
+        Some(value) => write_value(buffer, value),
+        None => Ok(()),
+    }
+}
+
+pub fn read_option<T>(
+    buffer: &mut Buffer,
+    read_value: impl FnOnce(&mut Buffer) -> IoResult<T>,
+) -> IoResult<Option<T>> {
+    if buffer.read_bool()? {
+        read_value(buffer).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn write_field_vec<F: RichField>(buffer: &mut Buffer, values: &[F]) -> IoResult<()> {
+    write_len(buffer, values.len())?;
+    buffer.write_field_vec(values)
+}
+
+pub fn read_field_vec<F: RichField>(buffer: &mut Buffer) -> IoResult<Vec<F>> {
+    let len = read_len(buffer)?;
+    buffer.read_field_vec(len)
+}
+
+pub fn write_field_ext_vec<F: RichField + Extendable<D>, const D: usize>(
+    buffer: &mut Buffer,
+    values: &[F::Extension],
+) -> IoResult<()> {
+    write_len(buffer, values.len())?;
+    buffer.write_field_ext_vec::<F, D>(values)
+}
+
+pub fn read_field_ext_vec<F: RichField + Extendable<D>, const D: usize>(
+    buffer: &mut Buffer,
+) -> IoResult<Vec<F::Extension>> {
+    let len = read_len(buffer)?;
+    buffer.read_field_ext_vec::<F, D>(len)
+}
@@ -0,0 +1,356 @@
+use std::marker::PhantomData;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, CrossTableLookup, TableWithColumns};
+use crate::multi_stark::{MultiStark, TableVisitor};
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+const SQUARES_TABLE: usize = 0;
+const LOOKING_TABLE: usize = 1;
+
+const X: usize = 0;
+const X_SQUARED: usize = 1;
+
+const A: usize = 0;
+const B: usize = 1;
+const FILTER: usize = 2;
+
+/// Toy table used for testing, holding `x, x^2` for `x` in `1, ..., num_rows`.
+#[derive(Copy, Clone)]
+struct SquaresStark<F: RichField + Extendable<D>, const D: usize> {
+    num_rows: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SquaresStark<F, D> {
+    fn generate_trace(&self) -> Vec<PolynomialValues<F>> {
+        let trace_rows = (1..=self.num_rows)
+            .map(|x| [F::from_canonical_usize(x), F::from_canonical_usize(x * x)])
+            .collect::<Vec<_>>();
+        trace_rows_to_poly_values(trace_rows)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for SquaresStark<F, D> {
+    const COLUMNS: usize = 2;
+    const PUBLIC_INPUTS: usize = 0;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        // x starts at 1 and increments by 1.
+        yield_constr.constraint_first_row(vars.local_values[X] - FE::ONE);
+        yield_constr.constraint_transition(vars.next_values[X] - vars.local_values[X] - FE::ONE);
+
+        let x = vars.local_values[X];
+        yield_constr.constraint(x * x - vars.local_values[X_SQUARED]);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let one = builder.one_extension();
+
+        // x starts at 1 and increments by 1.
+        let first_row_constraint = builder.sub_extension(vars.local_values[X], one);
+        yield_constr.constraint_first_row(builder, first_row_constraint);
+        let transition_constraint = {
+            let tmp = builder.sub_extension(vars.next_values[X], vars.local_values[X]);
+            builder.sub_extension(tmp, one)
+        };
+        yield_constr.constraint_transition(builder, transition_constraint);
+
+        let x = vars.local_values[X];
+        let square_constraint = builder.mul_sub_extension(x, x, vars.local_values[X_SQUARED]);
+        yield_constr.constraint(builder, square_constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+}
+
+/// Toy table used for testing, holding pairs `a, b` with `b = a^2` which are only checked through
+/// cross-table lookups into the `SquaresStark` table. The rows where the filter is 0 have `a = 0`.
+#[derive(Copy, Clone)]
+struct LookingStark<F: RichField + Extendable<D>, const D: usize> {
+    num_rows: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> LookingStark<F, D> {
+    /// Generate a trace in which every other row looks up a square, from the largest to the
+    /// smallest. The trace is twice as long as the looked table.
+    fn generate_trace(&self) -> Vec<PolynomialValues<F>> {
+        let n = self.num_rows;
+        let trace_rows = (0..n)
+            .map(|i| {
+                let mut row = [F::ZERO; 3];
+                if i % 2 == 0 {
+                    let a = n / 2 - i / 2;
+                    row[A] = F::from_canonical_usize(a);
+                    row[B] = F::from_canonical_usize(a * a);
+                    row[FILTER] = F::ONE;
+                }
+                row
+            })
+            .collect::<Vec<_>>();
+        trace_rows_to_poly_values(trace_rows)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for LookingStark<F, D> {
+    const COLUMNS: usize = 3;
+    const PUBLIC_INPUTS: usize = 0;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        // The filter is boolean, and `a` is 0 where the filter is 0.
+        let filter = vars.local_values[FILTER];
+        yield_constr.constraint(filter * (filter - FE::ONE));
+        yield_constr.constraint((filter - FE::ONE) * vars.local_values[A]);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        // The filter is boolean, and `a` is 0 where the filter is 0.
+        let filter = vars.local_values[FILTER];
+        let filter_constraint = builder.mul_sub_extension(filter, filter, filter);
+        yield_constr.constraint(builder, filter_constraint);
+        let a = vars.local_values[A];
+        let a_constraint = builder.mul_sub_extension(filter, a, a);
+        yield_constr.constraint(builder, a_constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+}
+
+/// Toy `MultiStark` used for testing, made of a `SquaresStark` table with `num_rows` rows and a
+/// `LookingStark` table with `2 * num_rows` rows. The filtered rows of the latter must be the rows
+/// of the former, and its `a` column must hold the `x` column padded with zeros.
+#[derive(Copy, Clone)]
+struct SquaresMultiStark<F: RichField + Extendable<D>, const D: usize> {
+    squares_stark: SquaresStark<F, D>,
+    looking_stark: LookingStark<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SquaresMultiStark<F, D> {
+    fn new(num_rows: usize) -> Self {
+        Self {
+            squares_stark: SquaresStark {
+                num_rows,
+                _phantom: PhantomData,
+            },
+            looking_stark: LookingStark {
+                num_rows: 2 * num_rows,
+                _phantom: PhantomData,
+            },
+        }
+    }
+
+    fn generate_traces(&self) -> Vec<Vec<PolynomialValues<F>>> {
+        vec![
+            self.squares_stark.generate_trace(),
+            self.looking_stark.generate_trace(),
+        ]
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> MultiStark<F, D> for SquaresMultiStark<F, D>
+where
+    [(); SquaresStark::<F, D>::COLUMNS]:,
+    [(); SquaresStark::<F, D>::PUBLIC_INPUTS]:,
+    [(); LookingStark::<F, D>::COLUMNS]:,
+    [(); LookingStark::<F, D>::PUBLIC_INPUTS]:,
+{
+    fn num_tables(&self) -> usize {
+        2
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![
+            CrossTableLookup::new(
+                vec![TableWithColumns::new(
+                    LOOKING_TABLE,
+                    Column::singles([A, B]).collect(),
+                    Some(Column::single(FILTER)),
+                )],
+                TableWithColumns::new(
+                    SQUARES_TABLE,
+                    Column::singles([X, X_SQUARED]).collect(),
+                    Some(Column::one()),
+                ),
+                None,
+            ),
+            CrossTableLookup::new(
+                vec![TableWithColumns::new(
+                    LOOKING_TABLE,
+                    vec![Column::single(A)],
+                    None,
+                )],
+                TableWithColumns::new(SQUARES_TABLE, vec![Column::single(X)], None),
+                Some(vec![F::ZERO]),
+            ),
+        ]
+    }
+
+    fn visit_table<V: TableVisitor<F, D>>(&self, table: usize, visitor: V) -> V::Output {
+        match table {
+            SQUARES_TABLE => visitor.visit(&self.squares_stark),
+            LOOKING_TABLE => visitor.visit(&self.looking_stark),
+            _ => panic!("Unknown table {table}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Field;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+
+    use crate::config::StarkConfig;
+    use crate::cross_table_lookup::testutils::check_ctls;
+    use crate::multi_stark::MultiStark;
+    use crate::proof::MultiProof;
+    use crate::prover::prove_multi_stark;
+    use crate::recursive_verifier::{
+        add_virtual_multi_stark_proof, set_multi_stark_proof_target,
+        verify_multi_stark_proof_circuit,
+    };
+    use crate::squares_multi_stark::{SquaresMultiStark, B, LOOKING_TABLE};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::verifier::verify_multi_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type M = SquaresMultiStark<F, D>;
+
+    #[test]
+    fn test_squares_multi_stark() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let multi_stark = M::new(1 << 4);
+        let traces = multi_stark.generate_traces();
+        check_ctls(&traces, &multi_stark.cross_table_lookups());
+
+        let proof = prove_multi_stark::<F, C, M, D>(
+            &multi_stark,
+            &config,
+            traces,
+            vec![vec![]; 2],
+            &mut TimingTree::default(),
+        )?;
+        verify_multi_stark_proof(&multi_stark, proof.clone(), &config)?;
+
+        // Recursively verify the proof.
+        let circuit_config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(circuit_config);
+        let mut pw = PartialWitness::new();
+        let degree_bits = proof.degree_bits(&config);
+        let pt = add_virtual_multi_stark_proof(&mut builder, &multi_stark, &config, &degree_bits);
+        set_multi_stark_proof_target(&mut pw, &pt, &proof);
+        verify_multi_stark_proof_circuit::<F, C, M, D>(&mut builder, &multi_stark, pt, &config);
+        let data = builder.build::<C>();
+        let recursive_proof = data.prove(pw)?;
+        data.verify(recursive_proof)
+    }
+
+    #[test]
+    fn test_squares_multi_stark_serialization() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let multi_stark = M::new(1 << 4);
+        let proof = prove_multi_stark::<F, C, M, D>(
+            &multi_stark,
+            &config,
+            multi_stark.generate_traces(),
+            vec![vec![]; 2],
+            &mut TimingTree::default(),
+        )?;
+
+        let proof_from_bytes = MultiProof::from_bytes(proof.to_bytes(&config)?, &config)?;
+        assert_eq!(proof_from_bytes, proof);
+        verify_multi_stark_proof(&multi_stark, proof_from_bytes, &config)
+    }
+
+    #[test]
+    fn test_squares_multi_stark_bad_lookup() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let multi_stark = M::new(1 << 4);
+        let mut traces = multi_stark.generate_traces();
+        // Row 0 is looked up, so it must hold a square. `b` isn't constrained within its table.
+        traces[LOOKING_TABLE][B].values[0] += F::ONE;
+
+        let proof = prove_multi_stark::<F, C, M, D>(
+            &multi_stark,
+            &config,
+            traces,
+            vec![vec![]; 2],
+            &mut TimingTree::default(),
+        )?;
+        assert!(verify_multi_stark_proof(&multi_stark, proof, &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_squares_multi_stark_wrong_public_inputs() {
+        let config = StarkConfig::standard_fast_config();
+        let multi_stark = M::new(1 << 4);
+        let traces = multi_stark.generate_traces();
+
+        assert!(prove_multi_stark::<F, C, M, D>(
+            &multi_stark,
+            &config,
+            traces,
+            vec![vec![], vec![F::ONE]],
+            &mut TimingTree::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_squares_multi_stark_degree() -> Result<()> {
+        let multi_stark = M::new(1 << 4);
+        test_stark_low_degree(multi_stark.squares_stark)?;
+        test_stark_low_degree(multi_stark.looking_stark)
+    }
+
+    #[test]
+    fn test_squares_multi_stark_circuit() -> Result<()> {
+        let multi_stark = M::new(1 << 4);
+        test_stark_circuit_constraints::<F, C, _, D>(multi_stark.squares_stark)?;
+        test_stark_circuit_constraints::<F, C, _, D>(multi_stark.looking_stark)
+    }
+}
@@ -1,5 +1,6 @@
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
 use plonky2::fri::structure::{
     FriBatchInfo, FriBatchInfoTarget, FriInstanceInfo, FriInstanceInfoTarget, FriOracleInfo,
     FriPolynomialInfo,
@@ -11,7 +12,9 @@ use plonky2_util::ceil_div_usize;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::lookup::Lookup;
 use crate::permutation::PermutationPair;
+use crate::util::row_offset_power;
 use crate::vars::StarkEvaluationTargets;
 use crate::vars::StarkEvaluationVars;
 
@@ -21,6 +24,10 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
     const COLUMNS: usize;
     /// The number of public inputs.
     const PUBLIC_INPUTS: usize;
+    /// The number of preprocessed columns, i.e. columns which are fixed by the STARK rather than
+    /// by the witness, such as round constants or lookup tables. They are committed to once, in
+    /// the verifying key, and are available to constraints alongside the trace.
+    const PREPROCESSED_COLUMNS: usize = 0;
 
     /// Evaluate constraints at a vector of points.
     ///
@@ -78,58 +85,101 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         1.max(self.constraint_degree() - 1)
     }
 
+    /// The number of chunks the quotient polynomial of each challenge is split into. Each chunk
+    /// has the degree bound of committed polynomials, which is doubled with zero-knowledge.
+    fn num_quotient_chunks(&self, config: &StarkConfig) -> usize {
+        if config.zero_knowledge {
+            1.max(self.constraint_degree())
+        } else {
+            self.quotient_degree_factor()
+        }
+    }
+
     fn num_quotient_polys(&self, config: &StarkConfig) -> usize {
-        self.quotient_degree_factor() * config.num_challenges
+        self.num_quotient_chunks(config) * config.num_challenges
     }
 
-    /// Computes the FRI instance used to prove this Stark.
+    /// Computes the FRI instance used to prove this Stark, which commits to `num_ctl_zs`
+    /// cross-table lookup `Z`s if it is part of a `MultiStark`.
     fn fri_instance(
         &self,
         zeta: F::Extension,
         g: F,
+        num_ctl_zs: usize,
         config: &StarkConfig,
     ) -> FriInstanceInfo<F, D> {
         let mut oracles = vec![];
 
+        let preprocessed_info = if self.uses_preprocessed_columns() {
+            let polys = FriPolynomialInfo::from_range(oracles.len(), 0..Self::PREPROCESSED_COLUMNS);
+            oracles.push(FriOracleInfo {
+                num_polys: Self::PREPROCESSED_COLUMNS,
+                blinding: false,
+            });
+            polys
+        } else {
+            vec![]
+        };
+
         let trace_info = FriPolynomialInfo::from_range(oracles.len(), 0..Self::COLUMNS);
         oracles.push(FriOracleInfo {
             num_polys: Self::COLUMNS,
-            blinding: false,
+            blinding: config.zero_knowledge,
         });
 
-        let permutation_zs_info = if self.uses_permutation_args() {
-            let num_z_polys = self.num_permutation_batches(config);
-            let polys = FriPolynomialInfo::from_range(oracles.len(), 0..num_z_polys);
+        // Cross-table lookup `Z`s come after the STARK's own auxiliary polynomials.
+        let num_auxiliary_polys = self.num_auxiliary_polys(config) + num_ctl_zs;
+        let (auxiliary_polys_info, ctl_zs_info) = if num_auxiliary_polys > 0 {
+            let auxiliary_polys_oracle = oracles.len();
             oracles.push(FriOracleInfo {
-                num_polys: num_z_polys,
-                blinding: false,
+                num_polys: num_auxiliary_polys,
+                blinding: config.zero_knowledge,
             });
-            polys
+            (
+                FriPolynomialInfo::from_range(auxiliary_polys_oracle, 0..num_auxiliary_polys),
+                FriPolynomialInfo::from_range(
+                    auxiliary_polys_oracle,
+                    num_auxiliary_polys - num_ctl_zs..num_auxiliary_polys,
+                ),
+            )
         } else {
-            vec![]
+            (vec![], vec![])
         };
 
-        let num_quotient_polys = self.quotient_degree_factor() * config.num_challenges;
+        let num_quotient_polys = self.num_quotient_polys(config);
         let quotient_info = FriPolynomialInfo::from_range(oracles.len(), 0..num_quotient_polys);
         oracles.push(FriOracleInfo {
             num_polys: num_quotient_polys,
-            blinding: false,
+            blinding: config.zero_knowledge,
         });
 
         let zeta_batch = FriBatchInfo {
             point: zeta,
             polynomials: [
+                preprocessed_info.clone(),
                 trace_info.clone(),
-                permutation_zs_info.clone(),
+                auxiliary_polys_info.clone(),
                 quotient_info,
             ]
             .concat(),
         };
         let zeta_next_batch = FriBatchInfo {
             point: zeta.scalar_mul(g),
-            polynomials: [trace_info, permutation_zs_info].concat(),
+            polynomials: [preprocessed_info, trace_info.clone(), auxiliary_polys_info].concat(),
         };
-        let batches = vec![zeta_batch, zeta_next_batch];
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        for offset in self.extra_row_offsets() {
+            batches.push(FriBatchInfo {
+                point: zeta.scalar_mul(row_offset_power(g, offset)),
+                polynomials: trace_info.clone(),
+            });
+        }
+        if num_ctl_zs > 0 {
+            batches.push(FriBatchInfo {
+                point: F::Extension::from_basefield(g.inverse()),
+                polynomials: ctl_zs_info,
+            });
+        }
 
         FriInstanceInfo { oracles, batches }
     }
@@ -140,40 +190,60 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         builder: &mut CircuitBuilder<F, D>,
         zeta: ExtensionTarget<D>,
         g: F,
+        num_ctl_zs: usize,
         config: &StarkConfig,
     ) -> FriInstanceInfoTarget<D> {
         let mut oracles = vec![];
 
+        let preprocessed_info = if self.uses_preprocessed_columns() {
+            let polys = FriPolynomialInfo::from_range(oracles.len(), 0..Self::PREPROCESSED_COLUMNS);
+            oracles.push(FriOracleInfo {
+                num_polys: Self::PREPROCESSED_COLUMNS,
+                blinding: false,
+            });
+            polys
+        } else {
+            vec![]
+        };
+
         let trace_info = FriPolynomialInfo::from_range(oracles.len(), 0..Self::COLUMNS);
         oracles.push(FriOracleInfo {
             num_polys: Self::COLUMNS,
-            blinding: false,
+            blinding: config.zero_knowledge,
         });
 
-        let permutation_zs_info = if self.uses_permutation_args() {
-            let num_z_polys = self.num_permutation_batches(config);
-            let polys = FriPolynomialInfo::from_range(oracles.len(), 0..num_z_polys);
+        // Cross-table lookup `Z`s come after the STARK's own auxiliary polynomials.
+        let num_auxiliary_polys = self.num_auxiliary_polys(config) + num_ctl_zs;
+        let (auxiliary_polys_info, ctl_zs_info) = if num_auxiliary_polys > 0 {
+            let auxiliary_polys_oracle = oracles.len();
             oracles.push(FriOracleInfo {
-                num_polys: num_z_polys,
-                blinding: false,
+                num_polys: num_auxiliary_polys,
+                blinding: config.zero_knowledge,
             });
-            polys
+            (
+                FriPolynomialInfo::from_range(auxiliary_polys_oracle, 0..num_auxiliary_polys),
+                FriPolynomialInfo::from_range(
+                    auxiliary_polys_oracle,
+                    num_auxiliary_polys - num_ctl_zs..num_auxiliary_polys,
+                ),
+            )
         } else {
-            vec![]
+            (vec![], vec![])
         };
 
-        let num_quotient_polys = self.quotient_degree_factor() * config.num_challenges;
+        let num_quotient_polys = self.num_quotient_polys(config);
         let quotient_info = FriPolynomialInfo::from_range(oracles.len(), 0..num_quotient_polys);
         oracles.push(FriOracleInfo {
             num_polys: num_quotient_polys,
-            blinding: false,
+            blinding: config.zero_knowledge,
         });
 
         let zeta_batch = FriBatchInfoTarget {
             point: zeta,
             polynomials: [
+                preprocessed_info.clone(),
                 trace_info.clone(),
-                permutation_zs_info.clone(),
+                auxiliary_polys_info.clone(),
                 quotient_info,
             ]
             .concat(),
@@ -181,13 +251,47 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let zeta_next = builder.mul_const_extension(g, zeta);
         let zeta_next_batch = FriBatchInfoTarget {
             point: zeta_next,
-            polynomials: [trace_info, permutation_zs_info].concat(),
+            polynomials: [preprocessed_info, trace_info.clone(), auxiliary_polys_info].concat(),
         };
-        let batches = vec![zeta_batch, zeta_next_batch];
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        for offset in self.extra_row_offsets() {
+            batches.push(FriBatchInfoTarget {
+                point: builder.mul_const_extension(row_offset_power(g, offset), zeta),
+                polynomials: trace_info.clone(),
+            });
+        }
+        if num_ctl_zs > 0 {
+            batches.push(FriBatchInfoTarget {
+                point: builder.constant_extension(F::Extension::from_basefield(g.inverse())),
+                polynomials: ctl_zs_info,
+            });
+        }
 
         FriInstanceInfoTarget { oracles, batches }
     }
 
+    /// Offsets of the rows, besides the current and next ones, whose trace values the constraints
+    /// read through `StarkEvaluationVars::extra_rows`, e.g. `2` for the row after the next one or
+    /// `-1` for the previous one. Offsets should be distinct and neither `0` nor `1`. Empty by
+    /// default.
+    ///
+    /// Rows wrap around: with `n` rows, the row at offset `k` from row `i` is row `(i + k) mod n`.
+    /// As transition constraints only exclude the last row, constraints reading other rows may
+    /// need to be filtered, e.g. with a preprocessed selector.
+    fn extra_row_offsets(&self) -> Vec<isize> {
+        vec![]
+    }
+
+    /// The values of the preprocessed columns, which should have the same length as the trace.
+    /// Empty by default.
+    fn preprocessed_columns(&self) -> Vec<PolynomialValues<F>> {
+        vec![]
+    }
+
+    fn uses_preprocessed_columns(&self) -> bool {
+        Self::PREPROCESSED_COLUMNS > 0
+    }
+
     /// Pairs of lists of columns that should be permutations of one another. A permutation argument
     /// will be used for each such pair. Empty by default.
     fn permutation_pairs(&self) -> Vec<PermutationPair> {
@@ -217,4 +321,42 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
             self.permutation_batch_size(),
         )
     }
+
+    /// Lookups of the values of some columns in a table column. A logUp argument will be used for
+    /// each of them. Empty by default.
+    fn lookups(&self) -> Vec<Lookup> {
+        vec![]
+    }
+
+    fn uses_lookups(&self) -> bool {
+        !self.lookups().is_empty()
+    }
+
+    /// The number of looked up columns whose logarithmic derivatives are summed in a single helper
+    /// column.
+    fn lookup_batch_size(&self) -> usize {
+        // The helper constraints look like
+        //     h(x) \prod(alpha - x_j) = \sum_j f \prod_{k != j} (alpha - x_k)
+        // so, as for permutation arguments, the batch size is one less than our constraint degree.
+        self.quotient_degree_factor()
+    }
+
+    fn num_lookup_helper_columns(&self, config: &StarkConfig) -> usize {
+        self.lookups()
+            .iter()
+            .map(|lookup| lookup.num_helper_columns(self.lookup_batch_size()))
+            .sum::<usize>()
+            * config.num_challenges
+    }
+
+    /// Whether the STARK commits to auxiliary polynomials after the trace, i.e. permutation `Z`s
+    /// and lookup helper columns.
+    fn uses_auxiliary_polys(&self) -> bool {
+        self.uses_permutation_args() || self.uses_lookups()
+    }
+
+    /// The number of auxiliary polynomials: the permutation `Z`s, then the lookup helper columns.
+    fn num_auxiliary_polys(&self, config: &StarkConfig) -> usize {
+        self.num_permutation_batches(config) + self.num_lookup_helper_columns(config)
+    }
 }
@@ -12,6 +12,9 @@ use plonky2::util::transpose;
 use plonky2_util::{log2_ceil, log2_strict};
 
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::lookup::{
+    eval_lookup_checks, eval_lookup_checks_circuit, LookupCheckDataTarget, LookupCheckVars,
+};
 use crate::stark::Stark;
 use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
@@ -30,6 +33,19 @@ where
 
     let trace_ldes = random_low_degree_matrix::<F>(S::COLUMNS, rate_bits);
     let size = trace_ldes.len();
+    let preprocessed_ldes = if stark.uses_preprocessed_columns() {
+        random_low_degree_matrix::<F>(S::PREPROCESSED_COLUMNS, rate_bits)
+    } else {
+        vec![vec![]; size]
+    };
+    // Lookup helper columns for a single challenge.
+    let num_lookup_helper_columns = num_lookup_helper_columns(&stark);
+    let lookup_helper_ldes = if stark.uses_lookups() {
+        random_low_degree_matrix::<F>(num_lookup_helper_columns, rate_bits)
+    } else {
+        vec![vec![]; size]
+    };
+    let lookup_challenge = F::rand();
     let public_inputs = F::rand_arr::<{ S::PUBLIC_INPUTS }>();
 
     let lagrange_first = PolynomialValues::selector(WITNESS_SIZE, 0).lde(rate_bits);
@@ -39,14 +55,25 @@ where
     let subgroup =
         F::cyclic_subgroup_known_order(F::primitive_root_of_unity(log2_strict(size)), size);
     let alpha = F::rand();
+    let extra_row_offsets = stark.extra_row_offsets();
     let constraint_evals = (0..size)
         .map(|i| {
+            let extra_rows = extra_row_offsets
+                .iter()
+                .map(|&offset| {
+                    let i_offset = (i as isize + (offset << rate_bits)).rem_euclid(size as isize);
+                    trace_ldes[i_offset as usize].clone().try_into().unwrap()
+                })
+                .collect::<Vec<_>>();
             let vars = StarkEvaluationVars {
                 local_values: &trace_ldes[i].clone().try_into().unwrap(),
                 next_values: &trace_ldes[(i + (1 << rate_bits)) % size]
                     .clone()
                     .try_into()
                     .unwrap(),
+                extra_rows: &extra_rows,
+                local_preprocessed: &preprocessed_ldes[i],
+                next_preprocessed: &preprocessed_ldes[(i + (1 << rate_bits)) % size],
                 public_inputs: &public_inputs,
             };
 
@@ -57,6 +84,14 @@ where
                 lagrange_last.values[i],
             );
             stark.eval_packed_base(vars, &mut consumer);
+            if stark.uses_lookups() {
+                let lookup_vars = LookupCheckVars {
+                    local_values: lookup_helper_ldes[i].clone(),
+                    next_values: lookup_helper_ldes[(i + (1 << rate_bits)) % size].clone(),
+                    challenges: vec![lookup_challenge],
+                };
+                eval_lookup_checks::<F, F, F, S, D, 1>(&stark, vars, lookup_vars, &mut consumer);
+            }
             consumer.accumulators()[0]
         })
         .collect::<Vec<_>>();
@@ -91,9 +126,17 @@ where
     [(); C::Hasher::HASH_SIZE]:,
 {
     // Compute native constraint evaluation on random values.
+    let extra_rows = stark
+        .extra_row_offsets()
+        .iter()
+        .map(|_| F::Extension::rand_arr::<{ S::COLUMNS }>())
+        .collect::<Vec<_>>();
     let vars = StarkEvaluationVars {
         local_values: &F::Extension::rand_arr::<{ S::COLUMNS }>(),
         next_values: &F::Extension::rand_arr::<{ S::COLUMNS }>(),
+        extra_rows: &extra_rows,
+        local_preprocessed: &F::Extension::rand_vec(S::PREPROCESSED_COLUMNS),
+        next_preprocessed: &F::Extension::rand_vec(S::PREPROCESSED_COLUMNS),
         public_inputs: &F::Extension::rand_arr::<{ S::PUBLIC_INPUTS }>(),
     };
     let alphas = F::rand_vec(1);
@@ -111,6 +154,21 @@ where
         lagrange_last,
     );
     stark.eval_ext(vars, &mut consumer);
+    let num_lookup_helper_columns = num_lookup_helper_columns(&stark);
+    let lookup_locals = F::Extension::rand_vec(num_lookup_helper_columns);
+    let lookup_nexts = F::Extension::rand_vec(num_lookup_helper_columns);
+    let lookup_challenges = F::rand_vec(stark.uses_lookups() as usize);
+    let lookup_vars = LookupCheckVars {
+        local_values: lookup_locals.clone(),
+        next_values: lookup_nexts.clone(),
+        challenges: lookup_challenges.clone(),
+    };
+    eval_lookup_checks::<F, F::Extension, F::Extension, S, D, D>(
+        &stark,
+        vars,
+        lookup_vars,
+        &mut consumer,
+    );
     let native_eval = consumer.accumulators()[0];
 
     // Compute circuit constraint evaluation on same random values.
@@ -122,6 +180,19 @@ where
     pw.set_extension_targets(&locals_t, vars.local_values);
     let nexts_t = builder.add_virtual_extension_targets(S::COLUMNS);
     pw.set_extension_targets(&nexts_t, vars.next_values);
+    let extra_rows_t = vars
+        .extra_rows
+        .iter()
+        .map(|values| {
+            let values_t = builder.add_virtual_extension_targets(S::COLUMNS);
+            pw.set_extension_targets(&values_t, values);
+            values_t.try_into().unwrap()
+        })
+        .collect::<Vec<_>>();
+    let local_preprocessed_t = builder.add_virtual_extension_targets(S::PREPROCESSED_COLUMNS);
+    pw.set_extension_targets(&local_preprocessed_t, vars.local_preprocessed);
+    let next_preprocessed_t = builder.add_virtual_extension_targets(S::PREPROCESSED_COLUMNS);
+    pw.set_extension_targets(&next_preprocessed_t, vars.next_preprocessed);
     let pis_t = builder.add_virtual_extension_targets(S::PUBLIC_INPUTS);
     pw.set_extension_targets(&pis_t, vars.public_inputs);
     let alphas_t = builder.add_virtual_targets(1);
@@ -136,6 +207,9 @@ where
     let vars = StarkEvaluationTargets::<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }> {
         local_values: &locals_t.try_into().unwrap(),
         next_values: &nexts_t.try_into().unwrap(),
+        extra_rows: &extra_rows_t,
+        local_preprocessed: &local_preprocessed_t,
+        next_preprocessed: &next_preprocessed_t,
         public_inputs: &pis_t.try_into().unwrap(),
     };
     let mut consumer = RecursiveConstraintConsumer::<F, D>::new(
@@ -146,6 +220,20 @@ where
         lagrange_last_t,
     );
     stark.eval_ext_circuit(&mut builder, vars, &mut consumer);
+    let lookup_locals_t = builder.add_virtual_extension_targets(num_lookup_helper_columns);
+    pw.set_extension_targets(&lookup_locals_t, &lookup_locals);
+    let lookup_nexts_t = builder.add_virtual_extension_targets(num_lookup_helper_columns);
+    pw.set_extension_targets(&lookup_nexts_t, &lookup_nexts);
+    let lookup_challenges_t = builder.add_virtual_targets(lookup_challenges.len());
+    for (&t, &challenge) in lookup_challenges_t.iter().zip(&lookup_challenges) {
+        pw.set_target(t, challenge);
+    }
+    let lookup_data = LookupCheckDataTarget {
+        local_values: lookup_locals_t,
+        next_values: lookup_nexts_t,
+        challenges: lookup_challenges_t,
+    };
+    eval_lookup_checks_circuit::<F, S, D>(&mut builder, &stark, vars, lookup_data, &mut consumer);
     let circuit_eval = consumer.accumulators()[0];
     let native_eval_t = builder.constant_extension(native_eval);
     builder.connect_extension(circuit_eval, native_eval_t);
@@ -155,6 +243,17 @@ where
     data.verify(proof)
 }
 
+/// The number of lookup helper columns for a single challenge.
+fn num_lookup_helper_columns<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
+    stark: &S,
+) -> usize {
+    stark
+        .lookups()
+        .iter()
+        .map(|lookup| lookup.num_helper_columns(stark.lookup_batch_size()))
+        .sum()
+}
+
 fn random_low_degree_matrix<F: Field>(num_polys: usize, rate_bits: usize) -> Vec<Vec<F>> {
     let polys = (0..num_polys)
         .map(|_| random_low_degree_values(rate_bits))
@@ -14,3 +14,13 @@ pub fn trace_rows_to_poly_values<F: Field, const COLUMNS: usize>(
         .map(|column| PolynomialValues::new(column))
         .collect()
 }
+
+/// Computes `g^offset` for a row offset which may be negative.
+pub(crate) fn row_offset_power<F: Field>(g: F, offset: isize) -> F {
+    let power = g.exp_u64(offset.unsigned_abs() as u64);
+    if offset < 0 {
+        power.inverse()
+    } else {
+        power
+    }
+}
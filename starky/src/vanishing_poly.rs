@@ -6,6 +6,13 @@ use plonky2::plonk::config::GenericConfig;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{
+    eval_cross_table_lookup_checks, eval_cross_table_lookup_checks_circuit, CtlCheckVars,
+    CtlCheckVarsTarget,
+};
+use crate::lookup::{
+    eval_lookup_checks, eval_lookup_checks_circuit, LookupCheckDataTarget, LookupCheckVars,
+};
 use crate::permutation::{
     eval_permutation_checks, eval_permutation_checks_circuit, PermutationCheckDataTarget,
     PermutationCheckVars,
@@ -18,6 +25,8 @@ pub(crate) fn eval_vanishing_poly<F, FE, P, C, S, const D: usize, const D2: usiz
     config: &StarkConfig,
     vars: StarkEvaluationVars<FE, P, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
     permutation_data: Option<PermutationCheckVars<F, FE, P, D2>>,
+    lookup_data: Option<LookupCheckVars<F, FE, P, D2>>,
+    ctl_vars: &[CtlCheckVars<F, FE, P, D2>],
     consumer: &mut ConstraintConsumer<P>,
 ) where
     F: RichField + Extendable<D>,
@@ -38,6 +47,10 @@ pub(crate) fn eval_vanishing_poly<F, FE, P, C, S, const D: usize, const D2: usiz
             consumer,
         );
     }
+    if let Some(lookup_data) = lookup_data {
+        eval_lookup_checks::<F, FE, P, S, D, D2>(stark, vars, lookup_data, consumer);
+    }
+    eval_cross_table_lookup_checks::<F, FE, P, S, D, D2>(vars, ctl_vars, consumer);
 }
 
 pub(crate) fn eval_vanishing_poly_circuit<F, C, S, const D: usize>(
@@ -46,6 +59,8 @@ pub(crate) fn eval_vanishing_poly_circuit<F, C, S, const D: usize>(
     config: &StarkConfig,
     vars: StarkEvaluationTargets<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
     permutation_data: Option<PermutationCheckDataTarget<D>>,
+    lookup_data: Option<LookupCheckDataTarget<D>>,
+    ctl_vars: &[CtlCheckVarsTarget<F, D>],
     consumer: &mut RecursiveConstraintConsumer<F, D>,
 ) where
     F: RichField + Extendable<D>,
@@ -65,4 +80,8 @@ pub(crate) fn eval_vanishing_poly_circuit<F, C, S, const D: usize>(
             consumer,
         );
     }
+    if let Some(lookup_data) = lookup_data {
+        eval_lookup_checks_circuit::<F, S, D>(builder, stark, vars, lookup_data, consumer);
+    }
+    eval_cross_table_lookup_checks_circuit::<S, F, D>(builder, vars, ctl_vars, consumer);
 }
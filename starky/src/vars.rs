@@ -10,6 +10,13 @@ where
 {
     pub local_values: &'a [P; COLUMNS],
     pub next_values: &'a [P; COLUMNS],
+    /// Values of the trace in the rows at `Stark::extra_row_offsets` from the current row, in the
+    /// same order.
+    pub extra_rows: &'a [[P; COLUMNS]],
+    /// Values of the preprocessed columns in the current row, see `Stark::PREPROCESSED_COLUMNS`.
+    pub local_preprocessed: &'a [P],
+    /// Values of the preprocessed columns in the next row.
+    pub next_preprocessed: &'a [P],
     pub public_inputs: &'a [P::Scalar; PUBLIC_INPUTS],
 }
 
@@ -22,5 +29,8 @@ pub struct StarkEvaluationTargets<
 > {
     pub local_values: &'a [ExtensionTarget<D>; COLUMNS],
     pub next_values: &'a [ExtensionTarget<D>; COLUMNS],
+    pub extra_rows: &'a [[ExtensionTarget<D>; COLUMNS]],
+    pub local_preprocessed: &'a [ExtensionTarget<D>],
+    pub next_preprocessed: &'a [ExtensionTarget<D>],
     pub public_inputs: &'a [ExtensionTarget<D>; PUBLIC_INPUTS],
 }
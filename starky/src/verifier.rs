@@ -1,4 +1,5 @@
 use std::iter::once;
+use std::marker::PhantomData;
 
 use anyhow::{anyhow, ensure, Result};
 use itertools::Itertools;
@@ -6,13 +7,22 @@ use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::types::Field;
 use plonky2::fri::verifier::verify_fri_proof;
 use plonky2::hash::hash_types::RichField;
+use plonky2::iop::challenger::Challenger;
 use plonky2::plonk::config::{GenericConfig, Hasher};
 use plonky2::plonk::plonk_common::reduce_with_powers;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::ConstraintConsumer;
-use crate::permutation::PermutationCheckVars;
-use crate::proof::{StarkOpeningSet, StarkProof, StarkProofChallenges, StarkProofWithPublicInputs};
+use crate::cross_table_lookup::{verify_cross_table_lookups, CrossTableLookup, CtlCheckVars};
+use crate::lookup::LookupCheckVars;
+use crate::multi_stark::{MultiStark, TableVisitor};
+use crate::permutation::{
+    get_permutation_challenge_set, PermutationChallengeSet, PermutationCheckVars,
+};
+use crate::preprocessed::StarkVerifyingKey;
+use crate::proof::{
+    MultiProof, StarkOpeningSet, StarkProof, StarkProofChallenges, StarkProofWithPublicInputs,
+};
 use crate::stark::Stark;
 use crate::vanishing_poly::eval_vanishing_poly;
 use crate::vars::StarkEvaluationVars;
@@ -27,6 +37,26 @@ pub fn verify_stark_proof<
     proof_with_pis: StarkProofWithPublicInputs<F, C, D>,
     config: &StarkConfig,
 ) -> Result<()>
+where
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let verifying_key = StarkVerifyingKey::new(&stark, config);
+    verify_stark_proof_with_verifying_key(stark, &verifying_key, proof_with_pis, config)
+}
+
+pub fn verify_stark_proof_with_verifying_key<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    const D: usize,
+>(
+    stark: S,
+    verifying_key: &StarkVerifyingKey<F, C, D>,
+    proof_with_pis: StarkProofWithPublicInputs<F, C, D>,
+    config: &StarkConfig,
+) -> Result<()>
 where
     [(); S::COLUMNS]:,
     [(); S::PUBLIC_INPUTS]:,
@@ -34,19 +64,216 @@ where
 {
     ensure!(proof_with_pis.public_inputs.len() == S::PUBLIC_INPUTS);
     let degree_bits = proof_with_pis.proof.recover_degree_bits(config);
-    let challenges = proof_with_pis.get_challenges(&stark, config, degree_bits);
-    verify_stark_proof_with_challenges(stark, proof_with_pis, challenges, degree_bits, config)
+    let challenges = proof_with_pis.get_challenges(&stark, verifying_key, config, degree_bits);
+    verify_stark_proof_with_challenges(
+        &stark,
+        verifying_key,
+        &proof_with_pis,
+        challenges,
+        &[],
+        degree_bits,
+        config,
+    )
+}
+
+/// Verifies a proof of a `MultiStark`, including the cross-table lookups between its tables.
+pub fn verify_multi_stark_proof<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    M: MultiStark<F, D>,
+    const D: usize,
+>(
+    multi_stark: &M,
+    multi_proof: MultiProof<F, C, D>,
+    config: &StarkConfig,
+) -> Result<()>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let num_tables = multi_stark.num_tables();
+    ensure!(
+        multi_proof.stark_proofs.len() == num_tables,
+        "Expected {} STARK proofs, got {}.",
+        num_tables,
+        multi_proof.stark_proofs.len()
+    );
+    let cross_table_lookups = multi_stark.cross_table_lookups();
+    CrossTableLookup::check_tables(&cross_table_lookups, num_tables)?;
+
+    let verifying_keys = (0..num_tables)
+        .map(|table| multi_stark.visit_table(table, VerifyingKeyVisitor::<F, C, D>::new(config)))
+        .collect::<Vec<_>>();
+
+    let mut challenger = Challenger::<F, C::Hasher>::new();
+    for (verifying_key, proof_with_pis) in verifying_keys.iter().zip(&multi_proof.stark_proofs) {
+        if let Some(preprocessed_cap) = &verifying_key.preprocessed_cap {
+            challenger.observe_cap(preprocessed_cap);
+        }
+        challenger.observe_cap(&proof_with_pis.proof.trace_cap);
+    }
+
+    let ctl_challenges = get_permutation_challenge_set(&mut challenger, config.num_challenges);
+
+    for (table, (verifying_key, proof_with_pis)) in verifying_keys
+        .iter()
+        .zip(&multi_proof.stark_proofs)
+        .enumerate()
+    {
+        multi_stark.visit_table(
+            table,
+            VerifyTableVisitor {
+                table,
+                verifying_key,
+                proof_with_pis,
+                cross_table_lookups: &cross_table_lookups,
+                ctl_challenges: &ctl_challenges,
+                challenger: &mut challenger,
+                config,
+            },
+        )?;
+    }
+
+    let ctl_zs_lasts = multi_proof
+        .stark_proofs
+        .iter()
+        .map(|proof_with_pis| {
+            proof_with_pis
+                .proof
+                .openings
+                .ctl_zs_last
+                .clone()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    verify_cross_table_lookups::<F, D>(
+        &cross_table_lookups,
+        &ctl_zs_lasts,
+        &multi_proof.degree_bits(config),
+        &ctl_challenges,
+    )
+}
+
+/// Computes the verifying key of a table of a `MultiStark`.
+pub(crate) struct VerifyingKeyVisitor<'a, F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    config: &'a StarkConfig,
+    _phantom: PhantomData<C>,
+}
+
+impl<'a, F, C, const D: usize> VerifyingKeyVisitor<'a, F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    pub(crate) fn new(config: &'a StarkConfig) -> Self {
+        Self {
+            config,
+            _phantom: PhantomData,
+        }
+    }
 }
 
+impl<'a, F, C, const D: usize> TableVisitor<F, D> for VerifyingKeyVisitor<'a, F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    type Output = StarkVerifyingKey<F, C, D>;
+
+    fn visit<S: Stark<F, D>>(self, stark: &S) -> Self::Output
+    where
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+    {
+        StarkVerifyingKey::new(stark, self.config)
+    }
+}
+
+/// Verifies the proof of a table of a `MultiStark`, whose trace commitment has already been
+/// observed.
+struct VerifyTableVisitor<'a, F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    table: usize,
+    verifying_key: &'a StarkVerifyingKey<F, C, D>,
+    proof_with_pis: &'a StarkProofWithPublicInputs<F, C, D>,
+    cross_table_lookups: &'a [CrossTableLookup<F>],
+    ctl_challenges: &'a PermutationChallengeSet<F>,
+    challenger: &'a mut Challenger<F, C::Hasher>,
+    config: &'a StarkConfig,
+}
+
+impl<'a, F, C, const D: usize> TableVisitor<F, D> for VerifyTableVisitor<'a, F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    type Output = Result<()>;
+
+    fn visit<S: Stark<F, D>>(self, stark: &S) -> Self::Output
+    where
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+    {
+        let proof = &self.proof_with_pis.proof;
+        let num_auxiliary_polys = stark.num_auxiliary_polys(self.config);
+        let num_ctl_zs = CrossTableLookup::num_ctl_zs(
+            self.cross_table_lookups,
+            self.table,
+            self.config.num_challenges,
+        );
+        // Checked before extracting the cross-table lookup `Z`s from the openings.
+        for openings in [
+            &proof.openings.auxiliary_polys,
+            &proof.openings.auxiliary_polys_next,
+        ] {
+            ensure!(
+                openings.as_ref().map_or(0, Vec::len) == num_auxiliary_polys + num_ctl_zs,
+                "Wrong number of auxiliary polynomials openings."
+            );
+        }
+
+        let degree_bits = proof.recover_degree_bits(self.config);
+        let challenges = proof.get_challenges(stark, self.challenger, self.config, degree_bits);
+        let ctl_vars = CtlCheckVars::from_proof(
+            self.table,
+            proof,
+            self.cross_table_lookups,
+            self.ctl_challenges,
+            num_auxiliary_polys,
+        );
+        verify_stark_proof_with_challenges(
+            stark,
+            self.verifying_key,
+            self.proof_with_pis,
+            challenges,
+            &ctl_vars,
+            degree_bits,
+            self.config,
+        )
+    }
+}
+
+/// Verifies a STARK proof given its challenges. If the STARK is part of a `MultiStark`, `ctl_vars`
+/// holds the data needed to check its cross-table lookup `Z`s.
 pub(crate) fn verify_stark_proof_with_challenges<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     S: Stark<F, D>,
     const D: usize,
 >(
-    stark: S,
-    proof_with_pis: StarkProofWithPublicInputs<F, C, D>,
+    stark: &S,
+    verifying_key: &StarkVerifyingKey<F, C, D>,
+    proof_with_pis: &StarkProofWithPublicInputs<F, C, D>,
     challenges: StarkProofChallenges<F, D>,
+    ctl_vars: &[CtlCheckVars<F, F::Extension, F::Extension, D>],
     degree_bits: usize,
     config: &StarkConfig,
 ) -> Result<()>
@@ -55,24 +282,36 @@ where
     [(); S::PUBLIC_INPUTS]:,
     [(); C::Hasher::HASH_SIZE]:,
 {
-    validate_proof_shape(&stark, &proof_with_pis, config)?;
-    check_permutation_options(&stark, &proof_with_pis, &challenges)?;
+    let num_ctl_zs = ctl_vars.len();
+    validate_proof_shape(stark, verifying_key, proof_with_pis, num_ctl_zs, config)?;
+    check_auxiliary_options(stark, proof_with_pis, &challenges, num_ctl_zs)?;
     let StarkProofWithPublicInputs {
         proof,
         public_inputs,
     } = proof_with_pis;
     let StarkOpeningSet {
+        preprocessed_values,
+        preprocessed_next_values,
         local_values,
         next_values,
-        permutation_zs,
-        permutation_zs_next,
+        extra_rows_values,
+        auxiliary_polys,
+        auxiliary_polys_next,
+        ctl_zs_last: _,
         quotient_polys,
     } = &proof.openings;
     let vars = StarkEvaluationVars {
         local_values: &local_values.to_vec().try_into().unwrap(),
         next_values: &next_values.to_vec().try_into().unwrap(),
+        extra_rows: &extra_rows_values
+            .iter()
+            .map(|values| values.to_vec().try_into().unwrap())
+            .collect::<Vec<_>>(),
+        local_preprocessed: preprocessed_values.as_deref().unwrap_or_default(),
+        next_preprocessed: preprocessed_next_values.as_deref().unwrap_or_default(),
         public_inputs: &public_inputs
-            .into_iter()
+            .iter()
+            .copied()
             .map(F::Extension::from_basefield)
             .collect::<Vec<_>>()
             .try_into()
@@ -92,16 +331,28 @@ where
         l_0,
         l_last,
     );
+    let num_permutation_zs = stark.num_permutation_batches(config);
+    let num_auxiliary_polys = stark.num_auxiliary_polys(config);
     let permutation_data = stark.uses_permutation_args().then(|| PermutationCheckVars {
-        local_zs: permutation_zs.as_ref().unwrap().clone(),
-        next_zs: permutation_zs_next.as_ref().unwrap().clone(),
+        local_zs: auxiliary_polys.as_ref().unwrap()[..num_permutation_zs].to_vec(),
+        next_zs: auxiliary_polys_next.as_ref().unwrap()[..num_permutation_zs].to_vec(),
         permutation_challenge_sets: challenges.permutation_challenge_sets.unwrap(),
     });
+    let lookup_data = stark.uses_lookups().then(|| LookupCheckVars {
+        local_values: auxiliary_polys.as_ref().unwrap()[num_permutation_zs..num_auxiliary_polys]
+            .to_vec(),
+        next_values: auxiliary_polys_next.as_ref().unwrap()
+            [num_permutation_zs..num_auxiliary_polys]
+            .to_vec(),
+        challenges: challenges.lookup_challenges.unwrap(),
+    });
     eval_vanishing_poly::<F, F::Extension, F::Extension, C, S, D, D>(
-        &stark,
+        stark,
         config,
         vars,
         permutation_data,
+        lookup_data,
+        ctl_vars,
         &mut consumer,
     );
     let vanishing_polys_zeta = consumer.accumulators();
@@ -109,30 +360,39 @@ where
     // Check each polynomial identity, of the form `vanishing(x) = Z_H(x) quotient(x)`, at zeta.
     let zeta_pow_deg = challenges.stark_zeta.exp_power_of_2(degree_bits);
     let z_h_zeta = zeta_pow_deg - F::Extension::ONE;
-    // `quotient_polys_zeta` holds `num_challenges * quotient_degree_factor` evaluations.
-    // Each chunk of `quotient_degree_factor` holds the evaluations of `t_0(zeta),...,t_{quotient_degree_factor-1}(zeta)`
-    // where the "real" quotient polynomial is `t(X) = t_0(X) + t_1(X)*X^n + t_2(X)*X^{2n} + ...`.
-    // So to reconstruct `t(zeta)` we can compute `reduce_with_powers(chunk, zeta^n)` for each
-    // `quotient_degree_factor`-sized chunk of the original evaluations.
+    // `quotient_polys_zeta` holds `num_challenges * num_quotient_chunks` evaluations.
+    // Each chunk of `num_quotient_chunks` holds the evaluations of `t_0(zeta),...,t_{num_quotient_chunks-1}(zeta)`
+    // where the "real" quotient polynomial is `t(X) = t_0(X) + t_1(X)*X^m + t_2(X)*X^{2m} + ...`,
+    // with `m` the degree bound of committed polynomials.
+    // So to reconstruct `t(zeta)` we can compute `reduce_with_powers(chunk, zeta^m)` for each
+    // `num_quotient_chunks`-sized chunk of the original evaluations.
+    let zeta_pow_committed_deg = challenges
+        .stark_zeta
+        .exp_power_of_2(config.committed_degree_bits(degree_bits));
     for (i, chunk) in quotient_polys
-        .chunks(stark.quotient_degree_factor())
+        .chunks(stark.num_quotient_chunks(config))
         .enumerate()
     {
         ensure!(
-            vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg),
+            vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_committed_deg),
             "Mismatch between evaluation and opening of quotient polynomial"
         );
     }
 
-    let merkle_caps = once(proof.trace_cap)
-        .chain(proof.permutation_zs_cap)
-        .chain(once(proof.quotient_polys_cap))
+    let merkle_caps = verifying_key
+        .preprocessed_cap
+        .clone()
+        .into_iter()
+        .chain(once(proof.trace_cap.clone()))
+        .chain(proof.auxiliary_polys_cap.clone())
+        .chain(once(proof.quotient_polys_cap.clone()))
         .collect_vec();
 
     verify_fri_proof::<F, C, D>(
         &stark.fri_instance(
             challenges.stark_zeta,
             F::primitive_root_of_unity(degree_bits),
+            num_ctl_zs,
             config,
         ),
         &proof.openings.to_fri_openings(),
@@ -147,7 +407,9 @@ where
 
 fn validate_proof_shape<F, C, S, const D: usize>(
     stark: &S,
+    verifying_key: &StarkVerifyingKey<F, C, D>,
     proof_with_pis: &StarkProofWithPublicInputs<F, C, D>,
+    num_ctl_zs: usize,
     config: &StarkConfig,
 ) -> anyhow::Result<()>
 where
@@ -162,10 +424,11 @@ where
         public_inputs,
     } = proof_with_pis;
     let degree_bits = proof.recover_degree_bits(config);
+    verifying_key.check_degree_bits(degree_bits)?;
 
     let StarkProof {
         trace_cap,
-        permutation_zs_cap,
+        auxiliary_polys_cap,
         quotient_polys_cap,
         openings,
         // The shape of the opening proof will be checked in the FRI verifier (see
@@ -174,10 +437,14 @@ where
     } = proof;
 
     let StarkOpeningSet {
+        preprocessed_values,
+        preprocessed_next_values,
         local_values,
         next_values,
-        permutation_zs,
-        permutation_zs_next,
+        extra_rows_values,
+        auxiliary_polys,
+        auxiliary_polys_next,
+        ctl_zs_last,
         quotient_polys,
     } = openings;
 
@@ -185,33 +452,69 @@ where
 
     let fri_params = config.fri_params(degree_bits);
     let cap_height = fri_params.config.cap_height;
-    let num_zs = stark.num_permutation_batches(config);
+    let num_auxiliary_polys = stark.num_auxiliary_polys(config);
 
     ensure!(trace_cap.height() == cap_height);
     ensure!(quotient_polys_cap.height() == cap_height);
 
     ensure!(local_values.len() == S::COLUMNS);
     ensure!(next_values.len() == S::COLUMNS);
+    ensure!(extra_rows_values.len() == stark.extra_row_offsets().len());
+    ensure!(extra_rows_values
+        .iter()
+        .all(|values| values.len() == S::COLUMNS));
     ensure!(quotient_polys.len() == stark.num_quotient_polys(config));
 
-    if stark.uses_permutation_args() {
-        let permutation_zs_cap = permutation_zs_cap
+    if stark.uses_preprocessed_columns() {
+        let preprocessed_cap = verifying_key
+            .preprocessed_cap
             .as_ref()
-            .ok_or_else(|| anyhow!("Missing Zs cap"))?;
-        let permutation_zs = permutation_zs
+            .ok_or_else(|| anyhow!("Missing preprocessed cap"))?;
+        let preprocessed_values = preprocessed_values
             .as_ref()
-            .ok_or_else(|| anyhow!("Missing permutation_zs"))?;
-        let permutation_zs_next = permutation_zs_next
+            .ok_or_else(|| anyhow!("Missing preprocessed_values"))?;
+        let preprocessed_next_values = preprocessed_next_values
             .as_ref()
-            .ok_or_else(|| anyhow!("Missing permutation_zs_next"))?;
+            .ok_or_else(|| anyhow!("Missing preprocessed_next_values"))?;
 
-        ensure!(permutation_zs_cap.height() == cap_height);
-        ensure!(permutation_zs.len() == num_zs);
-        ensure!(permutation_zs_next.len() == num_zs);
+        ensure!(verifying_key.degree_bits.is_some());
+        ensure!(preprocessed_cap.height() == cap_height);
+        ensure!(preprocessed_values.len() == S::PREPROCESSED_COLUMNS);
+        ensure!(preprocessed_next_values.len() == S::PREPROCESSED_COLUMNS);
     } else {
-        ensure!(permutation_zs_cap.is_none());
-        ensure!(permutation_zs.is_none());
-        ensure!(permutation_zs_next.is_none());
+        ensure!(verifying_key.preprocessed_cap.is_none());
+        ensure!(verifying_key.degree_bits.is_none());
+        ensure!(preprocessed_values.is_none());
+        ensure!(preprocessed_next_values.is_none());
+    }
+
+    if stark.uses_auxiliary_polys() || num_ctl_zs > 0 {
+        let auxiliary_polys_cap = auxiliary_polys_cap
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing auxiliary polynomials cap"))?;
+        let auxiliary_polys = auxiliary_polys
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing auxiliary_polys"))?;
+        let auxiliary_polys_next = auxiliary_polys_next
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing auxiliary_polys_next"))?;
+
+        ensure!(auxiliary_polys_cap.height() == cap_height);
+        ensure!(auxiliary_polys.len() == num_auxiliary_polys + num_ctl_zs);
+        ensure!(auxiliary_polys_next.len() == num_auxiliary_polys + num_ctl_zs);
+    } else {
+        ensure!(auxiliary_polys_cap.is_none());
+        ensure!(auxiliary_polys.is_none());
+        ensure!(auxiliary_polys_next.is_none());
+    }
+
+    if num_ctl_zs > 0 {
+        let ctl_zs_last = ctl_zs_last
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing ctl_zs_last"))?;
+        ensure!(ctl_zs_last.len() == num_ctl_zs);
+    } else {
+        ensure!(ctl_zs_last.is_none());
     }
 
     Ok(())
@@ -229,9 +532,10 @@ fn eval_l_0_and_l_last<F: Field>(log_n: usize, x: F) -> (F, F) {
     (z_x * invs[0], z_x * invs[1])
 }
 
-/// Utility function to check that all permutation data wrapped in `Option`s are `Some` iff
-/// the Stark uses a permutation argument.
-fn check_permutation_options<
+/// Utility function to check that all permutation and lookup data wrapped in `Option`s are `Some`
+/// iff the Stark uses a permutation or lookup argument respectively. Auxiliary polynomials are also
+/// committed to if the Stark takes part in cross-table lookups.
+fn check_auxiliary_options<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     S: Stark<F, D>,
@@ -240,19 +544,27 @@ fn check_permutation_options<
     stark: &S,
     proof_with_pis: &StarkProofWithPublicInputs<F, C, D>,
     challenges: &StarkProofChallenges<F, D>,
+    num_ctl_zs: usize,
 ) -> Result<()> {
-    let options_is_some = [
-        proof_with_pis.proof.permutation_zs_cap.is_some(),
-        proof_with_pis.proof.openings.permutation_zs.is_some(),
-        proof_with_pis.proof.openings.permutation_zs_next.is_some(),
-        challenges.permutation_challenge_sets.is_some(),
+    let auxiliary_options_is_some = [
+        proof_with_pis.proof.auxiliary_polys_cap.is_some(),
+        proof_with_pis.proof.openings.auxiliary_polys.is_some(),
+        proof_with_pis.proof.openings.auxiliary_polys_next.is_some(),
     ];
     ensure!(
-        options_is_some
+        auxiliary_options_is_some
             .into_iter()
-            .all(|b| b == stark.uses_permutation_args()),
+            .all(|b| b == (stark.uses_auxiliary_polys() || num_ctl_zs > 0)),
+        "Auxiliary polynomials data doesn't match with Stark configuration."
+    );
+    ensure!(
+        challenges.permutation_challenge_sets.is_some() == stark.uses_permutation_args(),
         "Permutation data doesn't match with Stark configuration."
     );
+    ensure!(
+        challenges.lookup_challenges.is_some() == stark.uses_lookups(),
+        "Lookup data doesn't match with Stark configuration."
+    );
     Ok(())
 }
 
@@ -0,0 +1,302 @@
+use std::marker::PhantomData;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// Toy STARK system with an evaluation window wider than two rows, used for testing.
+/// Computes a Fibonacci sequence in a single column, checking each row against both the previous
+/// row and the row two steps ahead. A preprocessed selector disables these checks where the window
+/// would wrap around the trace.
+#[derive(Copy, Clone)]
+struct WindowStark<F: RichField + Extendable<D>, const D: usize> {
+    num_rows: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> WindowStark<F, D> {
+    // The first public input is `x0`.
+    const PI_INDEX_X0: usize = 0;
+    // The second public input is `x1`.
+    const PI_INDEX_X1: usize = 1;
+    // The third public input is the value of the last row.
+    const PI_INDEX_RES: usize = 2;
+
+    fn new(num_rows: usize) -> Self {
+        Self {
+            num_rows,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Generate the trace using `x0, x1` as initial values.
+    fn generate_trace(&self, x0: F, x1: F) -> Vec<PolynomialValues<F>> {
+        let trace_rows = (0..self.num_rows)
+            .scan([x0, x1], |acc, _| {
+                let row = [acc[0]];
+                *acc = [acc[1], acc[0] + acc[1]];
+                Some(row)
+            })
+            .collect::<Vec<_>>();
+        trace_rows_to_poly_values(trace_rows)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for WindowStark<F, D> {
+    const COLUMNS: usize = 1;
+    const PUBLIC_INPUTS: usize = 3;
+    const PREPROCESSED_COLUMNS: usize = 1;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let selector = vars.local_preprocessed[0];
+        let prev = vars.extra_rows[0][0];
+        let local = vars.local_values[0];
+        let next = vars.next_values[0];
+        let next_next = vars.extra_rows[1][0];
+
+        // Check public inputs.
+        yield_constr.constraint_first_row(local - vars.public_inputs[Self::PI_INDEX_X0]);
+        yield_constr.constraint_first_row(next - vars.public_inputs[Self::PI_INDEX_X1]);
+        yield_constr.constraint_last_row(local - vars.public_inputs[Self::PI_INDEX_RES]);
+
+        // x' <- x + x_prev
+        yield_constr.constraint(selector * (next - local - prev));
+        // x'' <- x' + x
+        yield_constr.constraint(selector * (next_next - next - local));
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let selector = vars.local_preprocessed[0];
+        let prev = vars.extra_rows[0][0];
+        let local = vars.local_values[0];
+        let next = vars.next_values[0];
+        let next_next = vars.extra_rows[1][0];
+
+        // Check public inputs.
+        let pis_constraints = [
+            builder.sub_extension(local, vars.public_inputs[Self::PI_INDEX_X0]),
+            builder.sub_extension(next, vars.public_inputs[Self::PI_INDEX_X1]),
+            builder.sub_extension(local, vars.public_inputs[Self::PI_INDEX_RES]),
+        ];
+        yield_constr.constraint_first_row(builder, pis_constraints[0]);
+        yield_constr.constraint_first_row(builder, pis_constraints[1]);
+        yield_constr.constraint_last_row(builder, pis_constraints[2]);
+
+        // x' <- x + x_prev
+        let backward_constraint = {
+            let tmp = builder.sub_extension(next, local);
+            let diff = builder.sub_extension(tmp, prev);
+            builder.mul_extension(selector, diff)
+        };
+        yield_constr.constraint(builder, backward_constraint);
+        // x'' <- x' + x
+        let forward_constraint = {
+            let tmp = builder.sub_extension(next_next, next);
+            let diff = builder.sub_extension(tmp, local);
+            builder.mul_extension(selector, diff)
+        };
+        yield_constr.constraint(builder, forward_constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        2
+    }
+
+    fn extra_row_offsets(&self) -> Vec<isize> {
+        vec![-1, 2]
+    }
+
+    fn preprocessed_columns(&self) -> Vec<PolynomialValues<F>> {
+        // The window of rows `i - 1..=i + 2` doesn't wrap around for `1 <= i <= n - 3`.
+        let selector = (0..self.num_rows)
+            .map(|i| F::from_bool((1..self.num_rows - 2).contains(&i)))
+            .collect();
+        vec![PolynomialValues::new(selector)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::extension::Extendable;
+    use plonky2::field::polynomial::PolynomialValues;
+    use plonky2::field::types::Field;
+    use plonky2::hash::hash_types::RichField;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{
+        AlgebraicHasher, GenericConfig, Hasher, PoseidonGoldilocksConfig,
+    };
+    use plonky2::util::timing::TimingTree;
+
+    use crate::config::StarkConfig;
+    use crate::proof::StarkProofWithPublicInputs;
+    use crate::prover::prove;
+    use crate::recursive_verifier::{
+        add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
+        verify_stark_proof_circuit,
+    };
+    use crate::stark::Stark;
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::verifier::verify_stark_proof;
+    use crate::window_stark::WindowStark;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type S = WindowStark<F, D>;
+
+    fn fibonacci<F: Field>(n: usize, x0: F, x1: F) -> F {
+        (0..n).fold((x0, x1), |x, _| (x.1, x.0 + x.1)).0
+    }
+
+    fn public_inputs(num_rows: usize) -> [F; 3] {
+        [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)]
+    }
+
+    #[test]
+    fn test_window_stark() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(F::ZERO, F::ONE);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs(num_rows),
+            &mut TimingTree::default(),
+        )?;
+        assert_eq!(proof.proof.openings.extra_rows_values.len(), 2);
+        verify_stark_proof(stark, proof.clone(), &config)?;
+        assert_eq!(
+            StarkProofWithPublicInputs::from_bytes(proof.to_bytes(&config)?, &config)?,
+            proof
+        );
+
+        // The proof is bound to the openings of the extra rows.
+        let mut bad_proof = proof;
+        bad_proof.proof.openings.extra_rows_values[1][0] += <F as Extendable<D>>::Extension::ONE;
+        assert!(verify_stark_proof(stark, bad_proof, &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_stark_zk() -> Result<()> {
+        let config = StarkConfig::standard_fast_zk_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(F::ZERO, F::ONE);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs(num_rows),
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark, proof, &config)
+    }
+
+    #[test]
+    fn test_window_stark_wrong_trace() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+
+        // Only the checks against the extra rows catch a wrong value in the middle of the trace.
+        let mut trace = stark.generate_trace(F::ZERO, F::ONE);
+        let mut values = trace[0].values.clone();
+        values[num_rows / 2] += F::ONE;
+        trace[0] = PolynomialValues::new(values);
+        let result = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs(num_rows),
+            &mut TimingTree::default(),
+        )
+        .and_then(|proof| verify_stark_proof(stark, proof, &config));
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_stark_degree() -> Result<()> {
+        test_stark_low_degree(S::new(1 << 5))
+    }
+
+    #[test]
+    fn test_window_stark_circuit() -> Result<()> {
+        test_stark_circuit_constraints::<F, C, S, D>(S::new(1 << 5))
+    }
+
+    #[test]
+    fn test_recursive_window_stark_verifier() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(F::ZERO, F::ONE);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs(num_rows),
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark, proof.clone(), &config)?;
+
+        recursive_proof::<F, C, S, C, D>(stark, proof, &config)
+    }
+
+    fn recursive_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        S: Stark<F, D> + Copy,
+        InnerC: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        stark: S,
+        inner_proof: StarkProofWithPublicInputs<F, InnerC, D>,
+        inner_config: &StarkConfig,
+    ) -> Result<()>
+    where
+        InnerC::Hasher: AlgebraicHasher<F>,
+        [(); S::COLUMNS]:,
+        [(); S::PUBLIC_INPUTS]:,
+        [(); C::Hasher::HASH_SIZE]:,
+        [(); InnerC::Hasher::HASH_SIZE]:,
+    {
+        let circuit_config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(circuit_config);
+        let mut pw = PartialWitness::new();
+        let degree_bits = inner_proof.proof.recover_degree_bits(inner_config);
+        let pt = add_virtual_stark_proof_with_pis(&mut builder, stark, inner_config, degree_bits);
+        set_stark_proof_with_pis_target(&mut pw, &pt, &inner_proof);
+
+        verify_stark_proof_circuit::<F, InnerC, S, D>(&mut builder, stark, pt, inner_config);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
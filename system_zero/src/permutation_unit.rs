@@ -277,6 +277,9 @@ mod tests {
         let vars = StarkEvaluationVars {
             local_values: &values,
             next_values: &[F::default(); NUM_COLUMNS],
+            extra_rows: &[],
+            local_preprocessed: &[],
+            next_preprocessed: &[],
             public_inputs: &[F::default(); NUM_PUBLIC_INPUTS],
         };
 
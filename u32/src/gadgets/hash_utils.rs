@@ -0,0 +1,133 @@
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2_field::extension::Extendable;
+
+/// Computes `a ^ b` as `a + b - 2ab`.
+pub(crate) fn xor_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: BoolTarget,
+    b: BoolTarget,
+) -> BoolTarget {
+    let sum = builder.add(a.target, b.target);
+    BoolTarget::new_unsafe(builder.arithmetic(-F::TWO, F::ONE, a.target, b.target, sum))
+}
+
+/// Computes the xor of all the given bits.
+pub(crate) fn xor_many_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: &[BoolTarget],
+) -> BoolTarget {
+    let (&first, rest) = bits.split_first().expect("Expected at least one bit");
+    rest.iter()
+        .fold(first, |acc, &b| xor_circuit(builder, acc, b))
+}
+
+/// Splits each byte into its bits, in little-endian order. This also range checks the bytes.
+pub(crate) fn bytes_to_bits_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bytes: &[Target],
+) -> Vec<BoolTarget> {
+    bytes
+        .iter()
+        .flat_map(|&byte| builder.split_le(byte, 8))
+        .collect()
+}
+
+/// Recombines little-endian bits into bytes.
+pub(crate) fn bits_to_bytes_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: &[BoolTarget],
+) -> Vec<Target> {
+    debug_assert_eq!(bits.len() % 8, 0);
+    bits.chunks(8)
+        .map(|byte_bits| builder.le_sum(byte_bits.iter()))
+        .collect()
+}
+
+/// Returns the bits of the constant byte `byte`, in little-endian order.
+pub(crate) fn constant_byte_bits<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    byte: u8,
+) -> Vec<BoolTarget> {
+    (0..8)
+        .map(|i| builder.constant_bool((byte >> i) & 1 == 1))
+        .collect()
+}
+
+/// Returns flags `f_0, ..., f_n`, where `f_i` is 1 if `length = i` and 0 otherwise. Also asserts
+/// that `length <= n`, i.e. that exactly one of the flags is set.
+pub(crate) fn length_flags_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    length: Target,
+    n: usize,
+) -> Vec<BoolTarget> {
+    let flags = (0..=n)
+        .map(|i| {
+            let i = builder.constant(F::from_canonical_usize(i));
+            builder.is_equal(length, i)
+        })
+        .collect::<Vec<_>>();
+    let num_set = builder.add_many(flags.iter().map(|f| f.target));
+    let one = builder.one();
+    builder.connect(num_set, one);
+    flags
+}
+
+/// Returns the message bytes which come before `length`, with the bytes afterwards replaced by
+/// zeros. `is_length` must be the flags returned by `length_flags_circuit` for `bytes.len()`.
+pub(crate) fn masked_message_bytes_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bytes: &[Target],
+    is_length: &[BoolTarget],
+) -> Vec<Target> {
+    debug_assert_eq!(is_length.len(), bytes.len() + 1);
+    // `in_message` is 1 iff `i < length`, i.e. iff none of the first `i + 1` flags is set.
+    let mut in_message = builder.one();
+    bytes
+        .iter()
+        .zip(is_length)
+        .map(|(&byte, &is_length_i)| {
+            in_message = builder.sub(in_message, is_length_i.target);
+            builder.mul(in_message, byte)
+        })
+        .collect()
+}
+
+/// Selects, among `candidates`, the one whose flag is set. Exactly one flag must be set.
+pub(crate) fn select_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    flags: &[BoolTarget],
+    candidates: &[Vec<Target>],
+) -> Vec<Target> {
+    debug_assert_eq!(flags.len(), candidates.len());
+    let len = candidates[0].len();
+    (0..len)
+        .map(|i| {
+            let zero = builder.zero();
+            flags
+                .iter()
+                .zip(candidates)
+                .fold(zero, |acc, (flag, candidate)| {
+                    builder.mul_add(flag.target, candidate[i], acc)
+                })
+        })
+        .collect()
+}
+
+/// Selects, among `candidates`, the bits of the one whose flag is set. Exactly one flag must be
+/// set.
+pub(crate) fn select_bits_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    flags: &[BoolTarget],
+    candidates: &[Vec<BoolTarget>],
+) -> Vec<BoolTarget> {
+    let candidates = candidates
+        .iter()
+        .map(|bits| bits.iter().map(|b| b.target).collect())
+        .collect::<Vec<_>>();
+    select_circuit(builder, flags, &candidates)
+        .into_iter()
+        .map(BoolTarget::new_unsafe)
+        .collect()
+}
@@ -0,0 +1,212 @@
+use plonky2::gadgets::keccak::SignedLane;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2_field::extension::Extendable;
+
+use crate::gadgets::hash_utils::{
+    bytes_to_bits_circuit, constant_byte_bits, length_flags_circuit, masked_message_bytes_circuit,
+    select_circuit,
+};
+
+/// The number of bytes absorbed by each Keccak-f permutation, for a 256-bit output.
+pub const KECCAK256_RATE_BYTES: usize = 136;
+
+const KECCAK_LANE_BITS: usize = 64;
+
+/// The number of bytes of a Keccak-256 hash.
+const KECCAK256_OUTPUT_BYTES: usize = 32;
+
+pub trait CircuitBuilderKeccak256<F: RichField + Extendable<D>, const D: usize> {
+    /// Computes the Keccak-256 hash of `input`, as used by Ethereum. Each target of `input` must
+    /// be a byte, which is range checked. Returns the 32 bytes of the hash.
+    fn keccak256(&mut self, input: &[Target]) -> Vec<Target>;
+
+    /// Computes the Keccak-256 hash of the first `length` bytes of `input`, where `length` must be
+    /// at most `input.len()`. The bytes of `input` after `length` are ignored. Returns the 32 bytes
+    /// of the hash.
+    fn keccak256_variable(&mut self, input: &[Target], length: Target) -> Vec<Target>;
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderKeccak256<F, D>
+    for CircuitBuilder<F, D>
+{
+    fn keccak256(&mut self, input: &[Target]) -> Vec<Target> {
+        let num_blocks = input.len() / KECCAK256_RATE_BYTES + 1;
+        let padded_len = num_blocks * KECCAK256_RATE_BYTES;
+
+        let mut bits = bytes_to_bits_circuit(self, input);
+        for i in input.len()..padded_len {
+            let mut pad_byte = 0;
+            if i == input.len() {
+                pad_byte |= 0x01;
+            }
+            if i == padded_len - 1 {
+                pad_byte |= 0x80;
+            }
+            bits.extend(constant_byte_bits(self, pad_byte));
+        }
+
+        let mut state = [[self.one(); KECCAK_LANE_BITS]; 25];
+        for block in bits.chunks(KECCAK256_RATE_BYTES * 8) {
+            keccak_absorb_circuit(self, &mut state, block);
+        }
+        keccak_output_bytes_circuit(self, &state)
+    }
+
+    fn keccak256_variable(&mut self, input: &[Target], length: Target) -> Vec<Target> {
+        let num_blocks = input.len() / KECCAK256_RATE_BYTES + 1;
+        let padded_len = num_blocks * KECCAK256_RATE_BYTES;
+
+        let is_length = length_flags_circuit(self, length, input.len());
+        // The message ends in the block containing the first padding byte, at index `length`.
+        let is_last_block = (0..num_blocks)
+            .map(|b| {
+                let start = b * KECCAK256_RATE_BYTES;
+                let end = (start + KECCAK256_RATE_BYTES).min(is_length.len());
+                BoolTarget::new_unsafe(
+                    self.add_many(is_length[start..end].iter().map(|f| f.target)),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut bytes = masked_message_bytes_circuit(self, input, &is_length);
+        bytes.resize(padded_len, self.zero());
+        // Add the first padding bit right after the message.
+        for (byte, is_length_i) in bytes.iter_mut().zip(&is_length) {
+            *byte = self.add(*byte, is_length_i.target);
+        }
+        // Add the last padding bit at the end of the last block.
+        for (b, is_last) in is_last_block.iter().enumerate() {
+            let i = (b + 1) * KECCAK256_RATE_BYTES - 1;
+            bytes[i] = self.mul_const_add(F::from_canonical_u8(0x80), is_last.target, bytes[i]);
+        }
+        let bits = bytes_to_bits_circuit(self, &bytes);
+
+        let mut state = [[self.one(); KECCAK_LANE_BITS]; 25];
+        let mut outputs = Vec::with_capacity(num_blocks);
+        for block in bits.chunks(KECCAK256_RATE_BYTES * 8) {
+            keccak_absorb_circuit(self, &mut state, block);
+            outputs.push(keccak_output_bytes_circuit(self, &state));
+        }
+        select_circuit(self, &is_last_block, &outputs)
+    }
+}
+
+/// Xors a block of little-endian bits into the signed state, then applies the Keccak-f
+/// permutation.
+fn keccak_absorb_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    state: &mut [SignedLane; 25],
+    block: &[BoolTarget],
+) {
+    for (i, b) in block.iter().enumerate() {
+        let s = &mut state[i / KECCAK_LANE_BITS][i % KECCAK_LANE_BITS];
+        // Xoring a bit `b` into a signed bit `s` gives `s (1 - 2b)`.
+        *s = builder.arithmetic(-F::TWO, F::ONE, b.target, *s, *s);
+    }
+    builder.keccak_f(state);
+}
+
+/// Returns the bytes of the hash, which are the first bytes of the state.
+fn keccak_output_bytes_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    state: &[SignedLane; 25],
+) -> Vec<Target> {
+    state
+        .iter()
+        .flatten()
+        .take(KECCAK256_OUTPUT_BYTES * 8)
+        .copied()
+        .collect::<Vec<_>>()
+        .chunks(8)
+        .map(|byte_bits| builder.signed_bits_to_integer(byte_bits))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use keccak_hash::keccak;
+    use plonky2::iop::witness::{PartialWitness, Witness};
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2_field::types::Field;
+    use rand::Rng;
+
+    use crate::gadgets::keccak256::{CircuitBuilderKeccak256, KECCAK256_RATE_BYTES};
+
+    #[test]
+    fn test_keccak256() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        let mut rng = rand::thread_rng();
+
+        // Cover an empty input, and an input whose padding needs an extra block.
+        for len in [0, KECCAK256_RATE_BYTES] {
+            let message = (0..len).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let input = builder.add_virtual_targets(len);
+            for (&t, &byte) in input.iter().zip(&message) {
+                pw.set_target(t, F::from_canonical_u8(byte));
+            }
+            let hash = builder.keccak256(&input);
+            for (&t, &byte) in hash.iter().zip(keccak(&message).as_bytes()) {
+                let expected = builder.constant(F::from_canonical_u8(byte));
+                builder.connect(t, expected);
+            }
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_keccak256_variable() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        const MAX_LEN: usize = 150;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let input = builder.add_virtual_targets(MAX_LEN);
+        let length = builder.add_virtual_target();
+        let hash = builder.keccak256_variable(&input, length);
+        builder.register_public_inputs(&hash);
+        let data = builder.build::<C>();
+
+        let mut rng = rand::thread_rng();
+        for len in [
+            0,
+            1,
+            KECCAK256_RATE_BYTES - 1,
+            KECCAK256_RATE_BYTES,
+            MAX_LEN,
+        ] {
+            let message = (0..MAX_LEN).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let mut pw = PartialWitness::new();
+            for (&t, &byte) in input.iter().zip(&message) {
+                pw.set_target(t, F::from_canonical_u8(byte));
+            }
+            pw.set_target(length, F::from_canonical_usize(len));
+
+            let proof = data.prove(pw)?;
+            let expected = keccak(&message[..len])
+                .as_bytes()
+                .iter()
+                .map(|&byte| F::from_canonical_u8(byte))
+                .collect::<Vec<_>>();
+            assert_eq!(proof.public_inputs, expected);
+            data.verify(proof)?;
+        }
+
+        Ok(())
+    }
+}
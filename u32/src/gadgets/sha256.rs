@@ -0,0 +1,376 @@
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2_field::extension::Extendable;
+
+use crate::gadgets::arithmetic_u32::{CircuitBuilderU32, U32Target};
+use crate::gadgets::hash_utils::{
+    bits_to_bytes_circuit, bytes_to_bits_circuit, constant_byte_bits, length_flags_circuit,
+    masked_message_bytes_circuit, select_bits_circuit, xor_circuit, xor_many_circuit,
+};
+
+/// The number of bytes in each block of the SHA-256 compression function.
+pub const SHA256_BLOCK_BYTES: usize = 64;
+
+/// The number of bytes used to encode the message length at the end of the padding.
+const SHA256_LENGTH_BYTES: usize = 8;
+
+const SHA256_ROUNDS: usize = 64;
+
+const ROUND_CONSTANTS: [u32; SHA256_ROUNDS] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// A 32-bit word, along with its little-endian bits.
+#[derive(Clone, Debug)]
+struct WordTarget {
+    word: U32Target,
+    bits: Vec<BoolTarget>,
+}
+
+pub trait CircuitBuilderSha256<F: RichField + Extendable<D>, const D: usize> {
+    /// Computes the SHA-256 hash of `input`. Each target of `input` must be a byte, which is range
+    /// checked. Returns the 32 bytes of the hash.
+    fn sha256(&mut self, input: &[Target]) -> Vec<Target>;
+
+    /// Computes the SHA-256 hash of the first `length` bytes of `input`, where `length` must be at
+    /// most `input.len()`. The bytes of `input` after `length` are ignored. Returns the 32 bytes of
+    /// the hash.
+    fn sha256_variable(&mut self, input: &[Target], length: Target) -> Vec<Target>;
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilderSha256<F, D>
+    for CircuitBuilder<F, D>
+{
+    fn sha256(&mut self, input: &[Target]) -> Vec<Target> {
+        let num_blocks = (input.len() + SHA256_LENGTH_BYTES) / SHA256_BLOCK_BYTES + 1;
+        let padded_len = num_blocks * SHA256_BLOCK_BYTES;
+
+        let mut bits = bytes_to_bits_circuit(self, input);
+        let bit_length = (input.len() as u64 * 8).to_be_bytes();
+        for i in input.len()..padded_len {
+            let pad_byte = if i == input.len() {
+                0x80
+            } else if i >= padded_len - SHA256_LENGTH_BYTES {
+                bit_length[i + SHA256_LENGTH_BYTES - padded_len]
+            } else {
+                0
+            };
+            bits.extend(constant_byte_bits(self, pad_byte));
+        }
+
+        let mut state = sha256_initial_state(self);
+        for block in bits.chunks(SHA256_BLOCK_BYTES * 8) {
+            state = sha256_compress_circuit(self, &state, block);
+        }
+        sha256_output_bytes(self, &state_bits(&state))
+    }
+
+    fn sha256_variable(&mut self, input: &[Target], length: Target) -> Vec<Target> {
+        let num_blocks = (input.len() + SHA256_LENGTH_BYTES) / SHA256_BLOCK_BYTES + 1;
+        let padded_len = num_blocks * SHA256_BLOCK_BYTES;
+
+        let is_length = length_flags_circuit(self, length, input.len());
+        // The last block is the first one with room for the padding byte at index `length`,
+        // followed by the encoded length.
+        let is_last_block = (0..num_blocks)
+            .map(|b| {
+                let start = (b * SHA256_BLOCK_BYTES).saturating_sub(SHA256_LENGTH_BYTES);
+                let end = ((b + 1) * SHA256_BLOCK_BYTES - SHA256_LENGTH_BYTES).min(is_length.len());
+                let terms = is_length[start.min(end)..end].iter().map(|f| f.target);
+                BoolTarget::new_unsafe(self.add_many(terms))
+            })
+            .collect::<Vec<_>>();
+
+        let mut bytes = masked_message_bytes_circuit(self, input, &is_length);
+        bytes.resize(padded_len, self.zero());
+        // Add the padding byte right after the message.
+        for (byte, is_length_i) in bytes.iter_mut().zip(&is_length) {
+            *byte = self.mul_const_add(F::from_canonical_u8(0x80), is_length_i.target, *byte);
+        }
+        let mut bits = bytes_to_bits_circuit(self, &bytes);
+
+        // Add the big-endian length in bits at the end of the last block. These bits are zero in
+        // the message bytes of the last block, so we can simply add them.
+        let length_bits = self.split_le(length, 32);
+        for (b, is_last) in is_last_block.iter().enumerate() {
+            let end = (b + 1) * SHA256_BLOCK_BYTES;
+            for (i, &length_bit) in length_bits.iter().enumerate() {
+                // Bit `i` of `length` is bit `i + 3` of the length in bits.
+                let bit_index = i + 3;
+                let byte = end - 1 - bit_index / 8;
+                let j = 8 * byte + bit_index % 8;
+                bits[j] = BoolTarget::new_unsafe(self.mul_add(
+                    is_last.target,
+                    length_bit.target,
+                    bits[j].target,
+                ));
+            }
+        }
+
+        let mut state = sha256_initial_state(self);
+        let mut outputs = Vec::with_capacity(num_blocks);
+        for block in bits.chunks(SHA256_BLOCK_BYTES * 8) {
+            state = sha256_compress_circuit(self, &state, block);
+            outputs.push(state_bits(&state));
+        }
+        let output = select_bits_circuit(self, &is_last_block, &outputs);
+        sha256_output_bytes(self, &output)
+    }
+}
+
+fn constant_word<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    c: u32,
+) -> WordTarget {
+    WordTarget {
+        word: builder.constant_u32(c),
+        bits: (0..32)
+            .map(|i| builder.constant_bool((c >> i) & 1 == 1))
+            .collect(),
+    }
+}
+
+fn word_from_bits<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: Vec<BoolTarget>,
+) -> WordTarget {
+    WordTarget {
+        word: U32Target(builder.le_sum(bits.iter())),
+        bits,
+    }
+}
+
+/// Adds the given words modulo 2^32.
+fn add_words_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    words: &[U32Target],
+) -> WordTarget {
+    let (sum, _carry) = builder.add_many_u32(words);
+    WordTarget {
+        word: sum,
+        bits: builder.split_le(sum.0, 32),
+    }
+}
+
+/// Returns the xor of rotations (or shifts, for the `Some` entries of `shift`) of `x` to the right.
+fn xor_rotations_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    x: &WordTarget,
+    rotations: &[usize],
+    shift: Option<usize>,
+) -> WordTarget {
+    let bits = (0..32)
+        .map(|i| {
+            let mut terms = rotations
+                .iter()
+                .map(|r| x.bits[(i + r) % 32])
+                .collect::<Vec<_>>();
+            if let Some(s) = shift {
+                if i + s < 32 {
+                    terms.push(x.bits[i + s]);
+                }
+            }
+            xor_many_circuit(builder, &terms)
+        })
+        .collect();
+    word_from_bits(builder, bits)
+}
+
+fn sha256_initial_state<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+) -> Vec<WordTarget> {
+    INITIAL_STATE
+        .iter()
+        .map(|&c| constant_word(builder, c))
+        .collect()
+}
+
+fn state_bits(state: &[WordTarget]) -> Vec<BoolTarget> {
+    state.iter().flat_map(|w| w.bits.clone()).collect()
+}
+
+/// Converts the little-endian bits of the state words to the big-endian bytes of the hash.
+fn sha256_output_bytes<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: &[BoolTarget],
+) -> Vec<Target> {
+    bits.chunks(32)
+        .flat_map(|word_bits| {
+            let mut bytes = bits_to_bytes_circuit(builder, word_bits);
+            bytes.reverse();
+            bytes
+        })
+        .collect()
+}
+
+/// Applies the SHA-256 compression function to a block, given as the little-endian bits of its
+/// bytes.
+fn sha256_compress_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    state: &[WordTarget],
+    block: &[BoolTarget],
+) -> Vec<WordTarget> {
+    debug_assert_eq!(block.len(), SHA256_BLOCK_BYTES * 8);
+
+    // Words are read in big-endian order.
+    let mut schedule = block
+        .chunks(32)
+        .map(|word_bits| {
+            let bits = word_bits
+                .chunks(8)
+                .rev()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+            word_from_bits(builder, bits)
+        })
+        .collect::<Vec<_>>();
+    for t in 16..SHA256_ROUNDS {
+        let s0 = xor_rotations_circuit(builder, &schedule[t - 15], &[7, 18], Some(3));
+        let s1 = xor_rotations_circuit(builder, &schedule[t - 2], &[17, 19], Some(10));
+        let w = add_words_circuit(
+            builder,
+            &[
+                s1.word,
+                schedule[t - 7].word,
+                s0.word,
+                schedule[t - 16].word,
+            ],
+        );
+        schedule.push(w);
+    }
+
+    let mut vars = state.to_vec();
+    for (t, w) in schedule.iter().enumerate() {
+        let [a, b, c, d, e, f, g, h] = <[WordTarget; 8]>::try_from(vars).unwrap();
+
+        let sigma1 = xor_rotations_circuit(builder, &e, &[6, 11, 25], None);
+        // ch(e, f, g) = e ? f : g
+        let ch_bits = (0..32)
+            .map(|i| {
+                let diff = builder.sub(f.bits[i].target, g.bits[i].target);
+                BoolTarget::new_unsafe(builder.mul_add(e.bits[i].target, diff, g.bits[i].target))
+            })
+            .collect();
+        let ch = word_from_bits(builder, ch_bits);
+        let k = builder.constant_u32(ROUND_CONSTANTS[t]);
+
+        let sigma0 = xor_rotations_circuit(builder, &a, &[2, 13, 22], None);
+        // maj(a, b, c) = ab + c(a ^ b), as ab and c(a ^ b) cannot both be 1.
+        let maj_bits = (0..32)
+            .map(|i| {
+                let a_xor_b = xor_circuit(builder, a.bits[i], b.bits[i]);
+                let a_and_b = builder.and(a.bits[i], b.bits[i]);
+                BoolTarget::new_unsafe(builder.mul_add(
+                    c.bits[i].target,
+                    a_xor_b.target,
+                    a_and_b.target,
+                ))
+            })
+            .collect();
+        let maj = word_from_bits(builder, maj_bits);
+
+        let temp1 = [h.word, sigma1.word, ch.word, k, w.word];
+        let new_e = add_words_circuit(builder, &[&temp1[..], &[d.word]].concat());
+        let new_a = add_words_circuit(builder, &[&temp1[..], &[sigma0.word, maj.word]].concat());
+        vars = vec![new_a, a, b, c, new_e, e, f, g];
+    }
+
+    state
+        .iter()
+        .zip(vars)
+        .map(|(s, v)| add_words_circuit(builder, &[s.word, v.word]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::iop::witness::{PartialWitness, Witness};
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2_field::types::Field;
+    use rand::Rng;
+    use sha2::{Digest, Sha256};
+
+    use crate::gadgets::sha256::{CircuitBuilderSha256, SHA256_BLOCK_BYTES};
+
+    #[test]
+    fn test_sha256() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        let mut rng = rand::thread_rng();
+
+        // Cover an empty input, a full block, and an input whose padding needs an extra block.
+        for len in [0, SHA256_BLOCK_BYTES, SHA256_BLOCK_BYTES - 5] {
+            let message = (0..len).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let input = builder.add_virtual_targets(len);
+            for (&t, &byte) in input.iter().zip(&message) {
+                pw.set_target(t, F::from_canonical_u8(byte));
+            }
+            let hash = builder.sha256(&input);
+            for (&t, &byte) in hash.iter().zip(Sha256::digest(&message).iter()) {
+                let expected = builder.constant(F::from_canonical_u8(byte));
+                builder.connect(t, expected);
+            }
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_sha256_variable() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        const MAX_LEN: usize = 150;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let input = builder.add_virtual_targets(MAX_LEN);
+        let length = builder.add_virtual_target();
+        let hash = builder.sha256_variable(&input, length);
+        builder.register_public_inputs(&hash);
+        let data = builder.build::<C>();
+
+        let mut rng = rand::thread_rng();
+        for len in [0, 55, 56, SHA256_BLOCK_BYTES, 119, 120, MAX_LEN] {
+            let message = (0..MAX_LEN).map(|_| rng.gen()).collect::<Vec<u8>>();
+            let mut pw = PartialWitness::new();
+            for (&t, &byte) in input.iter().zip(&message) {
+                pw.set_target(t, F::from_canonical_u8(byte));
+            }
+            pw.set_target(length, F::from_canonical_usize(len));
+
+            let proof = data.prove(pw)?;
+            let expected = Sha256::digest(&message[..len])
+                .iter()
+                .map(|&byte| F::from_canonical_u8(byte))
+                .collect::<Vec<_>>();
+            assert_eq!(proof.public_inputs, expected);
+            data.verify(proof)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::marker::PhantomData;
 
 use array_tool::vec::Union;
@@ -16,6 +17,7 @@ use plonky2::plonk::vars::{
     EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
     EvaluationVarsBasePacked,
 };
+use plonky2::util::serialization::{Buffer, IoResult};
 use plonky2_field::extension::Extendable;
 use plonky2_field::packed::PackedField;
 use plonky2_field::types::Field;
@@ -77,6 +79,21 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SwitchGate<F,
         format!("{self:?}<D={D}>")
     }
 
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.chunk_size)?;
+        dst.write_usize(self.num_copies)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let chunk_size = src.read_usize()?;
+        let num_copies = src.read_usize()?;
+        Ok(Self {
+            chunk_size,
+            num_copies,
+            _phantom: PhantomData,
+        })
+    }
+
     fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
         let mut constraints = Vec::with_capacity(self.num_constraints());
 
@@ -210,7 +227,7 @@ impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for
 }
 
 #[derive(Debug)]
-struct SwitchGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub struct SwitchGenerator<F: RichField + Extendable<D>, const D: usize> {
     row: usize,
     gate: SwitchGate<F, D>,
     copy: usize,
@@ -320,6 +337,23 @@ impl<F: RichField + Extendable<D>, const D: usize> WitnessGenerator<F> for Switc
             false
         }
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        self.gate.serialize(dst)?;
+        dst.write_usize(self.copy)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let gate = SwitchGate::deserialize(src)?;
+        let copy = src.read_usize()?;
+        Ok(Self { row, gate, copy })
+    }
 }
 
 #[cfg(test)]
@@ -8,6 +8,7 @@ use plonky2::iop::generator::{GeneratedValues, SimpleGenerator};
 use plonky2::iop::target::{BoolTarget, Target};
 use plonky2::iop::witness::{PartitionWitness, Witness};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::serialization::{Buffer, IoResult};
 use plonky2_util::ceil_div_usize;
 
 use crate::gates::assert_le::AssertLessThanGate;
@@ -127,7 +128,7 @@ pub fn sort_memory_ops_circuit<F: RichField + Extendable<D>, const D: usize>(
 }
 
 #[derive(Debug)]
-struct MemoryOpSortGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub struct MemoryOpSortGenerator<F: RichField + Extendable<D>, const D: usize> {
     input_ops: Vec<MemoryOpTarget>,
     output_ops: Vec<MemoryOpTarget>,
     _phantom: PhantomData<F>,
@@ -178,6 +179,44 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
             out_buffer.set_target(out_op.value, op.value);
         }
     }
+
+    fn serialize(&self, dst: &mut Buffer) -> IoResult<()> {
+        let write_ops = |dst: &mut Buffer, ops: &[MemoryOpTarget]| -> IoResult<()> {
+            dst.write_usize(ops.len())?;
+            for op in ops {
+                dst.write_bool_target(op.is_write)?;
+                dst.write_target(op.address)?;
+                dst.write_target(op.timestamp)?;
+                dst.write_target(op.value)?;
+            }
+            Ok(())
+        };
+        write_ops(dst, &self.input_ops)?;
+        write_ops(dst, &self.output_ops)
+    }
+
+    fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let read_ops = |src: &mut Buffer| -> IoResult<Vec<MemoryOpTarget>> {
+            let len = src.read_usize()?;
+            (0..len)
+                .map(|_| {
+                    Ok(MemoryOpTarget {
+                        is_write: src.read_bool_target()?,
+                        address: src.read_target()?,
+                        timestamp: src.read_target()?,
+                        value: src.read_target()?,
+                    })
+                })
+                .collect()
+        };
+        let input_ops = read_ops(src)?;
+        let output_ops = read_ops(src)?;
+        Ok(Self {
+            input_ops,
+            output_ops,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]